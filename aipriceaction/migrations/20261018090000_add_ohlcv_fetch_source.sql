@@ -0,0 +1,12 @@
+-- Which upstream served the most recent sync for each ticker + interval.
+-- VN tickers normally come from VCI; when VCI is rate limited or returns no
+-- data the worker falls back to a UDF broker (vietstock, vndirect, dnse, vps).
+
+CREATE TABLE IF NOT EXISTS ohlcv_fetch_source (
+    ticker_id  INT         NOT NULL REFERENCES tickers(id),
+    interval   TEXT        NOT NULL,
+    provider   TEXT        NOT NULL,
+    fallback   BOOLEAN     NOT NULL DEFAULT FALSE,
+    fetched_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (ticker_id, interval)
+);
@@ -0,0 +1,20 @@
+-- Bars where two providers disagree beyond tolerance, found by the
+-- reconciliation worker. One row per (ticker, interval, bar, field, source pair);
+-- re-detections refresh the values and detected_at.
+
+CREATE TABLE IF NOT EXISTS ohlcv_discrepancies (
+    id               BIGSERIAL PRIMARY KEY,
+    ticker_id        INT              NOT NULL REFERENCES tickers(id),
+    interval         TEXT             NOT NULL,
+    time             TIMESTAMPTZ      NOT NULL,
+    field            TEXT             NOT NULL,
+    reference_source TEXT             NOT NULL,
+    other_source     TEXT             NOT NULL,
+    reference_value  DOUBLE PRECISION NOT NULL,
+    other_value      DOUBLE PRECISION NOT NULL,
+    diff_pct         DOUBLE PRECISION NOT NULL,
+    detected_at      TIMESTAMPTZ      NOT NULL DEFAULT NOW(),
+    UNIQUE (ticker_id, interval, time, field, reference_source, other_source)
+);
+
+CREATE INDEX IF NOT EXISTS ix_ohlcv_discrepancies_detected_at ON ohlcv_discrepancies (detected_at DESC);
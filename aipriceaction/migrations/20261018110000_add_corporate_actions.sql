@@ -0,0 +1,16 @@
+-- Splits / stock dividends / cash dividends detected from vendor back-adjustment.
+-- ratio = raw close before ex_date / vendor-adjusted close (> 1).
+-- Stored OHLCV stays raw for bars synced after an action is recorded;
+-- /tickers?adjust=split|total divides prices before ex_date by the ratio.
+
+CREATE TABLE IF NOT EXISTS corporate_actions (
+    id          BIGSERIAL PRIMARY KEY,
+    ticker_id   INT              NOT NULL REFERENCES tickers(id),
+    ex_date     DATE             NOT NULL,
+    action_type TEXT             NOT NULL CHECK (action_type IN ('split', 'dividend')),
+    ratio       DOUBLE PRECISION NOT NULL CHECK (ratio > 0),
+    cash_amount DOUBLE PRECISION,
+    source      TEXT             NOT NULL,
+    detected_at TIMESTAMPTZ      NOT NULL DEFAULT NOW(),
+    UNIQUE (ticker_id, ex_date, action_type)
+);
@@ -0,0 +1,23 @@
+-- Materialised aggregate intervals (MATERIALIZED_INTERVALS env var).
+-- Rows are derived from base bars (1m → 5m/15m, 1D → 1W) by the
+-- materializer and read directly by /tickers; the on-the-fly aggregation
+-- remains the fallback when a partition is disabled or not yet backfilled.
+
+CREATE TABLE ohlcv_5m     PARTITION OF ohlcv FOR VALUES IN ('5m')  PARTITION BY RANGE (time);
+CREATE TABLE ohlcv_15m    PARTITION OF ohlcv FOR VALUES IN ('15m') PARTITION BY RANGE (time);
+CREATE TABLE ohlcv_weekly PARTITION OF ohlcv FOR VALUES IN ('1W');
+
+-- Yearly sub-partitions, same range as ohlcv_minute (2010–2050)
+DO $$
+BEGIN
+    FOR y IN 2010..2050 LOOP
+        EXECUTE format(
+            'CREATE TABLE ohlcv_5m_%s PARTITION OF ohlcv_5m FOR VALUES FROM (%L) TO (%L)',
+            y, make_date(y, 1, 1), make_date(y + 1, 1, 1)
+        );
+        EXECUTE format(
+            'CREATE TABLE ohlcv_15m_%s PARTITION OF ohlcv_15m FOR VALUES FROM (%L) TO (%L)',
+            y, make_date(y, 1, 1), make_date(y + 1, 1, 1)
+        );
+    END LOOP;
+END $$;
@@ -0,0 +1,21 @@
+-- Missing bar ranges found by the gap scanner (expected bars come from the
+-- exchange calendar). One row per (ticker, interval, first missing bar);
+-- 'pending' rows are queued for a targeted backfill, then marked 'filled'
+-- or, once attempts run out, 'unfillable' (e.g. no trades upstream either).
+
+CREATE TABLE IF NOT EXISTS ohlcv_gaps (
+    id           BIGSERIAL PRIMARY KEY,
+    ticker_id    INT          NOT NULL REFERENCES tickers(id),
+    interval     TEXT         NOT NULL,
+    gap_start    TIMESTAMPTZ  NOT NULL,
+    gap_end      TIMESTAMPTZ  NOT NULL,
+    missing_bars INT          NOT NULL,
+    status       TEXT         NOT NULL DEFAULT 'pending',
+    attempts     INT          NOT NULL DEFAULT 0,
+    detected_at  TIMESTAMPTZ  NOT NULL DEFAULT NOW(),
+    updated_at   TIMESTAMPTZ  NOT NULL DEFAULT NOW(),
+    UNIQUE (ticker_id, interval, gap_start)
+);
+
+CREATE INDEX IF NOT EXISTS ix_ohlcv_gaps_pending ON ohlcv_gaps (detected_at) WHERE status = 'pending';
+CREATE INDEX IF NOT EXISTS ix_ohlcv_gaps_detected_at ON ohlcv_gaps (detected_at DESC);
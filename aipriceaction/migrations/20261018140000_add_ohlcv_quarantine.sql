@@ -0,0 +1,24 @@
+-- Provider bars rejected by the ingest validation rules (see
+-- services/validation.rs). Quarantined bars are never written to ohlcv;
+-- one row per (ticker, interval, bar, rule), refreshed when re-fetched.
+
+CREATE TABLE IF NOT EXISTS ohlcv_quarantine (
+    id            BIGSERIAL PRIMARY KEY,
+    ticker_id     INT              NOT NULL REFERENCES tickers(id),
+    interval      TEXT             NOT NULL,
+    time          TIMESTAMPTZ      NOT NULL,
+    open          DOUBLE PRECISION NOT NULL,
+    high          DOUBLE PRECISION NOT NULL,
+    low           DOUBLE PRECISION NOT NULL,
+    close         DOUBLE PRECISION NOT NULL,
+    volume        BIGINT           NOT NULL,
+    source        TEXT             NOT NULL,
+    rule          TEXT             NOT NULL,
+    reason        TEXT             NOT NULL,
+    seen_count    INT              NOT NULL DEFAULT 1,
+    first_seen_at TIMESTAMPTZ      NOT NULL DEFAULT NOW(),
+    last_seen_at  TIMESTAMPTZ      NOT NULL DEFAULT NOW(),
+    UNIQUE (ticker_id, interval, time, rule)
+);
+
+CREATE INDEX IF NOT EXISTS ix_ohlcv_quarantine_last_seen ON ohlcv_quarantine (last_seen_at DESC);
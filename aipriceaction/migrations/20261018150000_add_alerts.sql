@@ -0,0 +1,40 @@
+-- Price and indicator alert rules, registered through /alerts. `owner` is the
+-- SHA-256 of the API token that created the rule; `condition` is the tagged
+-- JSON of `services::alerts::Condition`.
+
+CREATE TABLE IF NOT EXISTS alert_rules (
+    id          BIGSERIAL PRIMARY KEY,
+    owner       TEXT         NOT NULL,
+    source      TEXT         NOT NULL,
+    ticker      TEXT         NOT NULL,
+    interval    TEXT         NOT NULL,
+    condition   JSONB        NOT NULL,
+    webhook_url TEXT         NOT NULL,
+    enabled     BOOLEAN      NOT NULL DEFAULT TRUE,
+    created_at  TIMESTAMPTZ  NOT NULL DEFAULT NOW(),
+    updated_at  TIMESTAMPTZ  NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS ix_alert_rules_owner ON alert_rules (owner, id);
+CREATE INDEX IF NOT EXISTS ix_alert_rules_key ON alert_rules (source, ticker, interval) WHERE enabled;
+
+-- One webhook notification per (rule, bar): a rule fires at most once per bar
+-- however often the forming bar is rewritten. 'pending' rows are retried with
+-- exponential backoff until 'delivered' or, once attempts run out, 'failed'.
+
+CREATE TABLE IF NOT EXISTS alert_deliveries (
+    id              BIGSERIAL PRIMARY KEY,
+    rule_id         BIGINT       NOT NULL REFERENCES alert_rules(id) ON DELETE CASCADE,
+    bar_time        TIMESTAMPTZ  NOT NULL,
+    payload         JSONB        NOT NULL,
+    status          TEXT         NOT NULL DEFAULT 'pending',
+    attempts        INT          NOT NULL DEFAULT 0,
+    response_status INT,
+    last_error      TEXT,
+    next_attempt_at TIMESTAMPTZ  NOT NULL DEFAULT NOW(),
+    created_at      TIMESTAMPTZ  NOT NULL DEFAULT NOW(),
+    delivered_at    TIMESTAMPTZ,
+    UNIQUE (rule_id, bar_time)
+);
+
+CREATE INDEX IF NOT EXISTS ix_alert_deliveries_due ON alert_deliveries (next_attempt_at) WHERE status = 'pending';
@@ -0,0 +1,37 @@
+-- Portfolios registered through /portfolios. `owner` is the SHA-256 of the
+-- API token that created the portfolio; NAV and P&L are reported in
+-- `base_currency` (VND or USD).
+
+CREATE TABLE IF NOT EXISTS portfolios (
+    id            BIGSERIAL PRIMARY KEY,
+    owner         TEXT         NOT NULL,
+    name          TEXT         NOT NULL,
+    base_currency TEXT         NOT NULL DEFAULT 'VND',
+    created_at    TIMESTAMPTZ  NOT NULL DEFAULT NOW(),
+    updated_at    TIMESTAMPTZ  NOT NULL DEFAULT NOW(),
+    UNIQUE (owner, name)
+);
+
+-- Ledger of a portfolio. Holdings and cash balances are replayed from it in
+-- (trade_date, id) order: buy/sell move `quantity` of (source, ticker) at
+-- `price`; dividend/fee/deposit/withdrawal move `amount` of cash. `currency`
+-- is the cash currency the transaction settles in (VND, USD or USDT).
+
+CREATE TABLE IF NOT EXISTS portfolio_transactions (
+    id           BIGSERIAL PRIMARY KEY,
+    portfolio_id BIGINT            NOT NULL REFERENCES portfolios(id) ON DELETE CASCADE,
+    kind         TEXT              NOT NULL
+                 CHECK (kind IN ('buy', 'sell', 'dividend', 'fee', 'deposit', 'withdrawal')),
+    trade_date   DATE              NOT NULL,
+    source       TEXT,
+    ticker       TEXT,
+    quantity     DOUBLE PRECISION  NOT NULL DEFAULT 0,
+    price        DOUBLE PRECISION  NOT NULL DEFAULT 0,
+    amount       DOUBLE PRECISION  NOT NULL DEFAULT 0,
+    fee          DOUBLE PRECISION  NOT NULL DEFAULT 0,
+    currency     TEXT              NOT NULL,
+    note         TEXT,
+    created_at   TIMESTAMPTZ       NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS ix_portfolio_transactions_portfolio ON portfolio_transactions (portfolio_id, trade_date, id);
@@ -0,0 +1,26 @@
+-- Daily market breadth per source and sector, computed by /analysis/breadth
+-- from split-adjusted daily bars and kept as a time series. `sector` = 'ALL'
+-- holds the whole market. A day is rewritten until it has closed in the
+-- exchange's local time.
+
+CREATE TABLE IF NOT EXISTS market_breadth (
+    source       TEXT         NOT NULL,
+    sector       TEXT         NOT NULL,
+    date         DATE         NOT NULL,
+    total        INT          NOT NULL,
+    advances     INT          NOT NULL,
+    declines     INT          NOT NULL,
+    unchanged    INT          NOT NULL,
+    up_volume    BIGINT       NOT NULL,
+    down_volume  BIGINT       NOT NULL,
+    above_ma20   INT          NOT NULL,
+    above_ma50   INT          NOT NULL,
+    above_ma200  INT          NOT NULL,
+    with_ma20    INT          NOT NULL,
+    with_ma50    INT          NOT NULL,
+    with_ma200   INT          NOT NULL,
+    new_highs    INT          NOT NULL,
+    new_lows     INT          NOT NULL,
+    computed_at  TIMESTAMPTZ  NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (source, sector, date)
+);
@@ -0,0 +1,28 @@
+-- Reference data per (source, ticker): names, exchange, quote currency,
+-- sector/industry taxonomy, listing dates and trading units. Seeded from the
+-- side files (vn.csv, ticker_group.json, {binance,global,sjc}_tickers.json)
+-- without touching existing rows, and edited through /admin/tickers.
+-- `sector` is the group a ticker is listed under (ticker_group.json key,
+-- Yahoo category, CRYPTO_TOP_100); rows without one are not part of any
+-- group. A NULL `currency` means the series is not a price (indices, FX).
+
+CREATE TABLE IF NOT EXISTS ticker_metadata (
+    source       TEXT              NOT NULL,
+    ticker       TEXT              NOT NULL,
+    name         TEXT,
+    en_name      TEXT,
+    exchange     TEXT,
+    currency     TEXT,
+    asset_type   TEXT,
+    sector       TEXT,
+    industry     TEXT,
+    listed_on    DATE,
+    delisted_on  DATE,
+    lot_size     DOUBLE PRECISION,
+    tick_size    DOUBLE PRECISION,
+    aliases      TEXT[]            NOT NULL DEFAULT '{}',
+    updated_at   TIMESTAMPTZ       NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (source, ticker)
+);
+
+CREATE INDEX IF NOT EXISTS idx_ticker_metadata_sector ON ticker_metadata (source, sector);
@@ -0,0 +1,19 @@
+-- Listing state changes per ticker, effective from a date on:
+-- suspended / active (suspension lifted) / delisted / renamed / merged.
+-- renamed and merged point at the successor ticker of the same source.
+-- The latest event on or before today is the current state; tickers without
+-- events are active. Sync workers only schedule active tickers, and /tickers
+-- stitches a rename chain into one history under the newest symbol.
+
+CREATE TABLE IF NOT EXISTS ticker_lifecycle (
+    id           BIGSERIAL   PRIMARY KEY,
+    ticker_id    INT         NOT NULL REFERENCES tickers(id),
+    state        TEXT        NOT NULL CHECK (state IN ('active', 'suspended', 'delisted', 'renamed', 'merged')),
+    effective_on DATE        NOT NULL,
+    successor_id INT         REFERENCES tickers(id),
+    note         TEXT,
+    created_at   TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (ticker_id, effective_on),
+    CHECK ((state IN ('renamed', 'merged')) = (successor_id IS NOT NULL)),
+    CHECK (successor_id <> ticker_id)
+);
@@ -0,0 +1,23 @@
+-- Versioned sector and index membership: ticker belongs to group_name from
+-- effective_from (inclusive, NULL = since listing) to effective_to
+-- (exclusive, NULL = still a member).
+-- Sector rows override `ticker_metadata.sector` for the tickers that have
+-- any: those follow their history, every other ticker keeps its current
+-- sector on every date. Index rows (VN30, HNX30, ...) are the only source of
+-- index constituents. Analyses with a `date` resolve groups as of that day.
+
+CREATE TABLE IF NOT EXISTS group_membership (
+    id             BIGSERIAL   PRIMARY KEY,
+    source         TEXT        NOT NULL,
+    kind           TEXT        NOT NULL CHECK (kind IN ('sector', 'index')),
+    group_name     TEXT        NOT NULL,
+    ticker         TEXT        NOT NULL,
+    effective_from DATE,
+    effective_to   DATE,
+    note           TEXT,
+    created_at     TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (effective_to IS NULL OR effective_from IS NULL OR effective_to > effective_from)
+);
+
+CREATE INDEX IF NOT EXISTS idx_group_membership_group ON group_membership (source, kind, group_name);
+CREATE INDEX IF NOT EXISTS idx_group_membership_ticker ON group_membership (source, ticker);
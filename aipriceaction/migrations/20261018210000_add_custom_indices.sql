@@ -0,0 +1,28 @@
+-- Synthetic indices over any symbol set, stored as OHLCV of
+-- tickers(source = 'custom', ticker = name) by the custom index worker.
+-- weighting: equal, cap (weight = outstanding shares) or manual (weight as
+-- given). Levels are chain-linked from base_value on the first trading day
+-- on or after base_date (NULL = the first day any member traded).
+-- computed_at < updated_at makes the worker rebuild the whole series.
+
+CREATE TABLE IF NOT EXISTS custom_indices (
+    id          SERIAL           PRIMARY KEY,
+    name        TEXT             NOT NULL UNIQUE,
+    title       TEXT,
+    weighting   TEXT             NOT NULL CHECK (weighting IN ('equal', 'cap', 'manual')),
+    base_value  DOUBLE PRECISION NOT NULL DEFAULT 1000 CHECK (base_value > 0),
+    base_date   DATE,
+    note        TEXT,
+    created_at  TIMESTAMPTZ      NOT NULL DEFAULT NOW(),
+    updated_at  TIMESTAMPTZ      NOT NULL DEFAULT NOW(),
+    computed_at TIMESTAMPTZ
+);
+
+CREATE TABLE IF NOT EXISTS custom_index_members (
+    index_id INT              NOT NULL REFERENCES custom_indices(id) ON DELETE CASCADE,
+    source   TEXT             NOT NULL,
+    ticker   TEXT             NOT NULL,
+    -- cap: outstanding shares, manual: relative weight, equal: NULL
+    weight   DOUBLE PRECISION CHECK (weight IS NULL OR weight > 0),
+    PRIMARY KEY (index_id, source, ticker)
+);
@@ -3,6 +3,7 @@ use clap::{Parser, Subcommand};
 use crate::db;
 use crate::models::interval::Interval;
 use crate::providers::binance::BinanceProvider;
+use crate::providers::market_data::{HistoryRequest, MarketDataProvider, ProviderError};
 use crate::providers::udf::UdfProvider;
 use crate::providers::vci::VciProvider;
 use crate::providers::yahoo::YahooProvider;
@@ -20,6 +21,74 @@ where
     tokio::spawn(async move { f(pool, redis).await });
 }
 
+/// Fetch each interval through the `MarketDataProvider` trait and log a short summary
+/// (record count, time span, first 3 rows). Shared by the `test-*` provider commands.
+async fn log_history_test<P: MarketDataProvider>(
+    provider: &P,
+    ticker: &str,
+    intervals: &[Interval],
+    request: HistoryRequest,
+    pause: std::time::Duration,
+) {
+    let caps = provider.capabilities();
+    tracing::info!(
+        "  {} capabilities: intervals={:?} search={} server_time={} max_count_back={}",
+        provider.name(),
+        caps.intervals,
+        caps.supports_search,
+        caps.supports_server_time,
+        caps.max_count_back,
+    );
+    for (i, interval) in intervals.iter().enumerate() {
+        if !caps.supports(*interval) {
+            tracing::info!("  Skipping {} (not supported by {})", interval, provider.name());
+            continue;
+        }
+        tracing::info!("  Fetching {} ...", interval);
+        match provider.history(ticker, *interval, request).await {
+            Ok(data) => {
+                let count = data.len();
+                if count > 0 {
+                    let first = &data[0];
+                    let last = &data[count - 1];
+                    tracing::info!(
+                        "    ✅ {} | {} records | {} → {}",
+                        interval,
+                        count,
+                        first.time.format("%Y-%m-%d %H:%M"),
+                        last.time.format("%Y-%m-%d %H:%M"),
+                    );
+                    // Print first 3 rows
+                    for row in data.iter().take(3) {
+                        tracing::info!(
+                            "       {} | O:{} H:{} L:{} C:{} V:{}",
+                            row.time.format("%Y-%m-%d %H:%M"),
+                            row.open,
+                            row.high,
+                            row.low,
+                            row.close,
+                            row.volume,
+                        );
+                    }
+                    if count > 3 {
+                        tracing::info!("       ... ({} more)", count - 3);
+                    }
+                } else {
+                    tracing::info!("    ⚠️  {} | 0 records returned", interval);
+                }
+            }
+            Err(e) => {
+                tracing::error!("    ❌ {} | {} error: {}", interval, provider.name(), e);
+            }
+        }
+
+        // Sleep between intervals to respect rate limits
+        if i + 1 < intervals.len() && !pause.is_zero() {
+            tokio::time::sleep(pause).await;
+        }
+    }
+}
+
 #[derive(Parser)]
 #[command(name = "aipriceaction")]
 #[command(about = "AI Price Action CLI", long_about = None)]
@@ -134,6 +203,33 @@ pub enum Commands {
         #[arg(long, default_value = "data/checkpoint.json.gz")]
         output: String,
     },
+    /// Scan 1h/1m bars for holes against the exchange calendar
+    Gaps {
+        /// Source to scan: vn, crypto, or all (default: all)
+        #[arg(long, default_value = "all")]
+        source: String,
+        /// Only scan one ticker (e.g. "VCB")
+        #[arg(long)]
+        ticker: Option<String>,
+        /// Interval to scan: 1h, 1m, or all (default: all)
+        #[arg(long, default_value = "all")]
+        interval: String,
+        /// Queue found gaps in ohlcv_gaps for the gap worker
+        #[arg(long)]
+        queue: bool,
+        /// Also backfill up to N pending gaps now (implies --queue)
+        #[arg(long)]
+        fill: Option<i64>,
+    },
+    /// Run a backtest from a JSON request file (same body as POST /analysis/backtest)
+    Backtest {
+        /// Path to the request JSON
+        #[arg(long)]
+        config: String,
+        /// Print the full report as JSON instead of a summary
+        #[arg(long)]
+        json: bool,
+    },
     /// Fetch company info and financial ratios for VN tickers from VCI
     GenerateCompanyInfo {
         /// Optional: query a single ticker (e.g. VCB)
@@ -231,6 +327,11 @@ pub fn run() {
             rt.block_on(async {
                 let tracer_provider = crate::tracing_otel::init();
 
+                if let Err(e) = crate::models::calendar::check_vn_holidays(chrono::Utc::now().date_naive()) {
+                    tracing::error!("{e}");
+                    std::process::exit(1);
+                }
+
                 let database_url = std::env::var("DATABASE_URL").unwrap_or_else(|_| {
                     tracing::warn!("DATABASE_URL not set, server will run without database");
                     String::new()
@@ -271,6 +372,24 @@ pub fn run() {
                     }
                 }
 
+                // Seed ticker metadata from the side files and load the catalog
+                // before any worker or handler reads it
+                match crate::services::metadata::seed(&pool).await {
+                    Ok(n) if n > 0 => tracing::info!("Seeded {n} ticker metadata rows"),
+                    Ok(_) => {}
+                    Err(e) => tracing::error!("Failed to seed ticker metadata: {e}"),
+                }
+                match crate::services::metadata::reload(&pool).await {
+                    Ok(n) => tracing::info!("Loaded {n} ticker metadata rows"),
+                    Err(e) => tracing::error!("Failed to load ticker metadata: {e} (falling back to the side files)"),
+                }
+                {
+                    let pool = pool.clone();
+                    tokio::spawn(async move {
+                        crate::workers::metadata::run(pool).await;
+                    });
+                }
+
                 tracing::info!("Starting server on {host}:{port}");
 
                 // Connect to Redis (optional — degrades gracefully if REDIS_URL is not set)
@@ -287,9 +406,11 @@ pub fn run() {
                 if vci_workers_enabled {
                     tracing::info!("VCI workers enabled");
 
-                    spawn_worker(&pool, &redis_client, crate::workers::vci_daily::run);
-                    spawn_worker(&pool, &redis_client, crate::workers::vci_hourly::run);
-                    spawn_worker(&pool, &redis_client, crate::workers::vci_minute::run);
+                    for interval in [Interval::Daily, Interval::Hourly, Interval::Minute] {
+                        spawn_worker(&pool, &redis_client, move |pool, redis| {
+                            crate::workers::interval_sync::run_vci(pool, redis, interval)
+                        });
+                    }
 
                     // Dividend worker has its own toggle
                     let dividend_worker_enabled = std::env::var("VCI_DIVIDEND_WORKER")
@@ -305,6 +426,18 @@ pub fn run() {
                     tracing::info!("VCI workers disabled (set VCI_WORKERS=true to enable)");
                 }
 
+                // Spawn cross-provider reconciliation worker if enabled
+                let reconcile_worker_enabled = std::env::var("RECONCILE_WORKER")
+                    .map(|v| v == "true" || v == "1")
+                    .unwrap_or(false);
+
+                if reconcile_worker_enabled {
+                    tracing::info!("RECONCILE_WORKER=true — spawning VCI/UDF reconciliation worker");
+                    spawn_worker(&pool, &redis_client, crate::workers::reconciler::run);
+                } else {
+                    tracing::info!("RECONCILE_WORKER=false — reconciliation worker not started");
+                }
+
                 // Spawn Binance data workers if enabled
                 let binance_workers_enabled = std::env::var("BINANCE_WORKERS")
                     .map(|v| v == "true" || v == "1")
@@ -313,10 +446,22 @@ pub fn run() {
                 if binance_workers_enabled {
                     tracing::info!("BINANCE_WORKERS=true — spawning daily/hourly/minute crypto workers");
 
+                    // Minute bars from the kline WebSocket instead of REST polling
+                    let binance_minute_stream = std::env::var("BINANCE_MINUTE_STREAM")
+                        .map(|v| v == "true" || v == "1")
+                        .unwrap_or(false);
+
                     spawn_worker(&pool, &redis_client, crate::workers::binance_bootstrap::run);
-                    spawn_worker(&pool, &redis_client, crate::workers::binance_daily::run);
-                    spawn_worker(&pool, &redis_client, crate::workers::binance_hourly::run);
-                    spawn_worker(&pool, &redis_client, crate::workers::binance_minute::run);
+                    for interval in [Interval::Daily, Interval::Hourly, Interval::Minute] {
+                        if interval == Interval::Minute && binance_minute_stream {
+                            tracing::info!("BINANCE_MINUTE_STREAM=true — streaming crypto minute bars over WebSocket");
+                            spawn_worker(&pool, &redis_client, crate::workers::binance_stream::run);
+                            continue;
+                        }
+                        spawn_worker(&pool, &redis_client, move |pool, redis| {
+                            crate::workers::interval_sync::run_binance(pool, redis, interval)
+                        });
+                    }
                 } else {
                     tracing::info!("BINANCE_WORKERS=false — Binance crypto workers not started");
                 }
@@ -330,9 +475,11 @@ pub fn run() {
                     tracing::info!("YAHOO_WORKERS=true — spawning bootstrap/daily/hourly/minute yahoo workers");
 
                     spawn_worker(&pool, &redis_client, crate::workers::yahoo_bootstrap::run);
-                    spawn_worker(&pool, &redis_client, crate::workers::yahoo_daily::run);
-                    spawn_worker(&pool, &redis_client, crate::workers::yahoo_hourly::run);
-                    spawn_worker(&pool, &redis_client, crate::workers::yahoo_minute::run);
+                    for interval in [Interval::Daily, Interval::Hourly, Interval::Minute] {
+                        spawn_worker(&pool, &redis_client, move |pool, redis| {
+                            crate::workers::interval_sync::run_yahoo(pool, redis, interval)
+                        });
+                    }
                 } else {
                     tracing::info!("YAHOO_WORKERS=false — Yahoo Finance workers not started");
                 }
@@ -386,7 +533,66 @@ pub fn run() {
                     tracing::info!("S3_ARCHIVE_WORKER=false — S3 archive worker not started");
                 }
 
-                let (app, health_snapshot) = crate::server::create_app(pool.clone(), redis_client.clone(), redis_handle);
+                // Spawn gap scanner / targeted backfill worker if enabled
+                let gap_worker_enabled = std::env::var("GAP_WORKER")
+                    .map(|v| v == "true" || v == "1")
+                    .unwrap_or(false);
+
+                if gap_worker_enabled {
+                    tracing::info!("GAP_WORKER=true — spawning gap scanner worker");
+                    spawn_worker(&pool, &redis_client, crate::workers::gap_scanner::run);
+                } else {
+                    tracing::info!("GAP_WORKER=false — gap scanner worker not started");
+                }
+
+                // Spawn alert evaluation / webhook delivery worker if enabled
+                let alert_worker_enabled = std::env::var("ALERT_WORKER")
+                    .map(|v| v == "true" || v == "1")
+                    .unwrap_or(false);
+
+                if alert_worker_enabled {
+                    if redis_client.is_none() {
+                        tracing::warn!("ALERT_WORKER=true but Redis not configured — alert worker not started");
+                    } else {
+                        tracing::info!("ALERT_WORKER=true — spawning alert worker");
+                        spawn_worker(&pool, &redis_client, crate::workers::alert_worker::run);
+                    }
+                } else {
+                    tracing::info!("ALERT_WORKER=false — alert worker not started");
+                }
+
+                // Spawn materialised-interval backfill worker if enabled
+                let materialize_worker_enabled = std::env::var("MATERIALIZE_WORKER")
+                    .map(|v| v == "true" || v == "1")
+                    .unwrap_or(false);
+
+                if materialize_worker_enabled {
+                    if crate::constants::materialize::intervals().is_empty() {
+                        tracing::warn!("MATERIALIZE_WORKER=true but MATERIALIZED_INTERVALS not set");
+                    } else {
+                        tracing::info!("MATERIALIZE_WORKER=true — spawning materialised interval worker");
+                        spawn_worker(&pool, &redis_client, crate::workers::materializer::run);
+                    }
+                } else {
+                    tracing::info!("MATERIALIZE_WORKER=false — materialised interval worker not started");
+                }
+
+                // Spawn custom index worker if enabled
+                let custom_index_worker_enabled = std::env::var("CUSTOM_INDEX_WORKER")
+                    .map(|v| v == "true" || v == "1")
+                    .unwrap_or(false);
+
+                if custom_index_worker_enabled {
+                    tracing::info!("CUSTOM_INDEX_WORKER=true — spawning custom index worker");
+                    spawn_worker(&pool, &redis_client, crate::workers::custom_index::run);
+                } else {
+                    tracing::info!("CUSTOM_INDEX_WORKER=false — custom index worker not started");
+                }
+
+                // Live candle hub for /ws (Redis pub/sub fan-out across replicas)
+                let live = crate::server::ws::LiveHub::start(redis_client.clone()).await;
+
+                let (app, health_snapshot) = crate::server::create_app(pool.clone(), redis_client.clone(), redis_handle, live);
 
                 // Spawn health-stats worker (always enabled — lightweight)
                 {
@@ -909,50 +1115,14 @@ pub fn run() {
                 tracing::info!("{}", "─".repeat(60));
                 tracing::info!("OHLCV Test — ticker={}, count_back={}", ticker, count_back);
 
-                for interval in &["1D", "1H", "1m"] {
-                    tracing::info!("  Fetching {} ...", interval);
-                    match provider.get_history(&ticker, interval, count_back, None).await {
-                        Ok(data) => {
-                            let count = data.len();
-                            if count > 0 {
-                                let first = &data[0];
-                                let last = &data[count - 1];
-                                tracing::info!(
-                                    "    ✅ {} | {} records | {} → {}",
-                                    interval,
-                                    count,
-                                    first.time.format("%Y-%m-%d %H:%M"),
-                                    last.time.format("%Y-%m-%d %H:%M"),
-                                );
-                                // Print first 3 rows
-                                for row in data.iter().take(3) {
-                                    tracing::info!(
-                                        "       {} | O:{} H:{} L:{} C:{} V:{}",
-                                        row.time.format("%Y-%m-%d %H:%M"),
-                                        row.open,
-                                        row.high,
-                                        row.low,
-                                        row.close,
-                                        row.volume,
-                                    );
-                                }
-                                if count > 3 {
-                                    tracing::info!("       ... ({} more)", count - 3);
-                                }
-                            } else {
-                                tracing::info!("    ⚠️  {} | 0 records returned", interval);
-                            }
-                        }
-                        Err(e) => {
-                            tracing::error!("    ❌ {} | error: {}", interval, e);
-                        }
-                    }
-
-                    // Sleep between intervals to respect rate limits
-                    if *interval != "1m" {
-                        tokio::time::sleep(std::time::Duration::from_secs(2)).await;
-                    }
-                }
+                log_history_test(
+                    &provider,
+                    &ticker,
+                    &[Interval::Daily, Interval::Hourly, Interval::Minute],
+                    HistoryRequest::count_back(count_back),
+                    std::time::Duration::from_secs(2),
+                )
+                .await;
 
                 // 3. Company info test (skip for index tickers)
                 tracing::info!("{}", "─".repeat(60));
@@ -1122,7 +1292,7 @@ pub fn run() {
                     {
                         let label = "vn batch 1D";
                         let start = Instant::now();
-                        match q::get_ohlcv_joined_batch(&pool, src, &["VCB".into()], "1D", Some(100), None, None, true, false).await {
+                        match q::get_ohlcv_joined_batch(&pool, src, &["VCB".into()], "1D", &q::FetchOptions { limit: Some(100), ..Default::default() }).await {
                             Ok(map) => {
                                 let total: usize = map.values().map(|v| v.len()).sum();
                                 section_results.push(BenchResult { label: label.into(), rows: total, ms: start.elapsed().as_millis() });
@@ -1134,7 +1304,7 @@ pub fn run() {
                     {
                         let label = "vn batch 1H";
                         let start = Instant::now();
-                        match q::get_ohlcv_joined_batch(&pool, src, &["VCB".into()], "1H", Some(100), None, None, true, false).await {
+                        match q::get_ohlcv_joined_batch(&pool, src, &["VCB".into()], "1H", &q::FetchOptions { limit: Some(100), ..Default::default() }).await {
                             Ok(map) => {
                                 let total: usize = map.values().map(|v| v.len()).sum();
                                 section_results.push(BenchResult { label: label.into(), rows: total, ms: start.elapsed().as_millis() });
@@ -1146,7 +1316,7 @@ pub fn run() {
                     {
                         let label = "vn batch 1m";
                         let start = Instant::now();
-                        match q::get_ohlcv_joined_batch(&pool, src, &["VCB".into()], "1m", Some(100), None, None, true, false).await {
+                        match q::get_ohlcv_joined_batch(&pool, src, &["VCB".into()], "1m", &q::FetchOptions { limit: Some(100), ..Default::default() }).await {
                             Ok(map) => {
                                 let total: usize = map.values().map(|v| v.len()).sum();
                                 section_results.push(BenchResult { label: label.into(), rows: total, ms: start.elapsed().as_millis() });
@@ -1158,7 +1328,7 @@ pub fn run() {
                     {
                         let label = "vn batch 1m multi";
                         let start = Instant::now();
-                        match q::get_ohlcv_joined_batch(&pool, src, &["VCB".into(), "FPT".into()], "1m", Some(100), None, None, true, false).await {
+                        match q::get_ohlcv_joined_batch(&pool, src, &["VCB".into(), "FPT".into()], "1m", &q::FetchOptions { limit: Some(100), ..Default::default() }).await {
                             Ok(map) => {
                                 let total: usize = map.values().map(|v| v.len()).sum();
                                 section_results.push(BenchResult { label: label.into(), rows: total, ms: start.elapsed().as_millis() });
@@ -1171,7 +1341,7 @@ pub fn run() {
                         let label = "vn batch 1m start_date";
                         let start_time = Some(Utc::now() - chrono::Duration::days(30));
                         let start = Instant::now();
-                        match q::get_ohlcv_joined_batch(&pool, src, &["VCB".into()], "1m", Some(100), start_time, None, true, false).await {
+                        match q::get_ohlcv_joined_batch(&pool, src, &["VCB".into()], "1m", &q::FetchOptions { limit: Some(100), start_time, ..Default::default() }).await {
                             Ok(map) => {
                                 let total: usize = map.values().map(|v| v.len()).sum();
                                 section_results.push(BenchResult { label: label.into(), rows: total, ms: start.elapsed().as_millis() });
@@ -1313,7 +1483,7 @@ pub fn run() {
                     {
                         let label = "crypto batch 1D";
                         let start = Instant::now();
-                        match q::get_ohlcv_joined_batch(&pool, src, &["BTCUSDT".into()], "1D", Some(100), None, None, true, false).await {
+                        match q::get_ohlcv_joined_batch(&pool, src, &["BTCUSDT".into()], "1D", &q::FetchOptions { limit: Some(100), ..Default::default() }).await {
                             Ok(map) => {
                                 let total: usize = map.values().map(|v| v.len()).sum();
                                 section_results.push(BenchResult { label: label.into(), rows: total, ms: start.elapsed().as_millis() });
@@ -1325,7 +1495,7 @@ pub fn run() {
                     {
                         let label = "crypto batch 1H";
                         let start = Instant::now();
-                        match q::get_ohlcv_joined_batch(&pool, src, &["BTCUSDT".into()], "1H", Some(100), None, None, true, false).await {
+                        match q::get_ohlcv_joined_batch(&pool, src, &["BTCUSDT".into()], "1H", &q::FetchOptions { limit: Some(100), ..Default::default() }).await {
                             Ok(map) => {
                                 let total: usize = map.values().map(|v| v.len()).sum();
                                 section_results.push(BenchResult { label: label.into(), rows: total, ms: start.elapsed().as_millis() });
@@ -1337,7 +1507,7 @@ pub fn run() {
                     {
                         let label = "crypto batch 1m";
                         let start = Instant::now();
-                        match q::get_ohlcv_joined_batch(&pool, src, &["BTCUSDT".into()], "1m", Some(100), None, None, true, false).await {
+                        match q::get_ohlcv_joined_batch(&pool, src, &["BTCUSDT".into()], "1m", &q::FetchOptions { limit: Some(100), ..Default::default() }).await {
                             Ok(map) => {
                                 let total: usize = map.values().map(|v| v.len()).sum();
                                 section_results.push(BenchResult { label: label.into(), rows: total, ms: start.elapsed().as_millis() });
@@ -1349,7 +1519,7 @@ pub fn run() {
                     {
                         let label = "crypto batch 1m multi";
                         let start = Instant::now();
-                        match q::get_ohlcv_joined_batch(&pool, src, &["BTCUSDT".into(), "ETHUSDT".into()], "1m", Some(100), None, None, true, false).await {
+                        match q::get_ohlcv_joined_batch(&pool, src, &["BTCUSDT".into(), "ETHUSDT".into()], "1m", &q::FetchOptions { limit: Some(100), ..Default::default() }).await {
                             Ok(map) => {
                                 let total: usize = map.values().map(|v| v.len()).sum();
                                 section_results.push(BenchResult { label: label.into(), rows: total, ms: start.elapsed().as_millis() });
@@ -1362,7 +1532,7 @@ pub fn run() {
                         let label = "crypto batch 1m start_date";
                         let start_time = Some(Utc::now() - chrono::Duration::days(30));
                         let start = Instant::now();
-                        match q::get_ohlcv_joined_batch(&pool, src, &["BTCUSDT".into()], "1m", Some(100), start_time, None, true, false).await {
+                        match q::get_ohlcv_joined_batch(&pool, src, &["BTCUSDT".into()], "1m", &q::FetchOptions { limit: Some(100), start_time, ..Default::default() }).await {
                             Ok(map) => {
                                 let total: usize = map.values().map(|v| v.len()).sum();
                                 section_results.push(BenchResult { label: label.into(), rows: total, ms: start.elapsed().as_millis() });
@@ -1487,55 +1657,30 @@ pub fn run() {
                 tracing::info!("Connected with {} client(s) (1 vision + {} API)", 1, provider.client_count().saturating_sub(1));
 
                 // 2. Determine intervals to test
-                let intervals: Vec<&str> = if interval == "all" {
-                    vec!["1d", "1h", "1m"]
+                let intervals: Vec<Interval> = if interval == "all" {
+                    vec![Interval::Daily, Interval::Hourly, Interval::Minute]
                 } else {
-                    vec![interval.as_str()]
+                    match Interval::from_arg(&interval) {
+                        Ok(iv) => vec![iv],
+                        Err(e) => {
+                            tracing::error!("{e}");
+                            return;
+                        }
+                    }
                 };
 
                 // 3. Fetch data for each interval
                 tracing::info!("{}", "─".repeat(60));
                 tracing::info!("OHLCV Test — ticker={}, limit={}", ticker, limit);
 
-                for iv in &intervals {
-                    tracing::info!("  Fetching {} ...", iv);
-                    match provider.get_history(&ticker, iv, limit).await {
-                        Ok(data) => {
-                            let count = data.len();
-                            if count > 0 {
-                                let first = &data[0];
-                                let last = &data[count - 1];
-                                tracing::info!(
-                                    "    ✅ {} | {} records | {} → {}",
-                                    iv,
-                                    count,
-                                    first.time.format("%Y-%m-%d %H:%M"),
-                                    last.time.format("%Y-%m-%d %H:%M"),
-                                );
-                                // Print first 3 rows
-                                for row in data.iter().take(3) {
-                                    tracing::info!(
-                                        "       {} | O:{} H:{} L:{} C:{} V:{}",
-                                        row.time.format("%Y-%m-%d %H:%M"),
-                                        row.open,
-                                        row.high,
-                                        row.low,
-                                        row.close,
-                                        row.volume,
-                                    );
-                                }
-                                if count > 3 {
-                                    tracing::info!("       ... ({} more)", count - 3);
-                                }
-                            } else {
-                                tracing::info!("    ⚠️  {} | 0 records returned", iv);
-                            }
-                        }
-                        Err(e) => {
-                            tracing::error!("    ❌ {} | error: {}", iv, e);
-                        }
-                    }
-                }
+                log_history_test(
+                    &provider,
+                    &ticker,
+                    &intervals,
+                    HistoryRequest::count_back(limit),
+                    std::time::Duration::ZERO,
+                )
+                .await;
 
                 // 4. Summary
                 tracing::info!("{}", "─".repeat(60));
@@ -1600,64 +1745,51 @@ pub fn run() {
                     }
                 }
 
-                // 3. get_history_interval test
+                // 3. Range test
                 tracing::info!("{}", "─".repeat(60));
-                tracing::info!("get_history_interval Test — ticker={}", ticker);
+                tracing::info!("Range Test — ticker={}", ticker);
                 {
                     let now = chrono::Utc::now();
-                    let intervals_to_test: &[(&str, &str, i64)] = &[
-                        ("1d", "30d", 30),
-                        ("1h", "5d", 5),
-                        ("1m", "1d", 1),
+                    let intervals_to_test: &[(Interval, i64)] = &[
+                        (Interval::Daily, 30),
+                        (Interval::Hourly, 5),
+                        (Interval::Minute, 1),
                     ];
-                    for (interval, label, days_back) in intervals_to_test {
+                    for (interval, days_back) in intervals_to_test {
                         let start = now - chrono::Duration::days(*days_back);
-                        tracing::info!("  Fetching {} (last {}) ...", interval, label);
-                        match provider.get_history_interval(&ticker, interval, start, now).await {
-                            Ok(data) => {
-                                let count = data.len();
-                                if count > 0 {
-                                    let first = &data[0];
-                                    let last = &data[count - 1];
-                                    tracing::info!(
-                                        "    ✅ {} | {} records | {} → {}",
-                                        interval,
-                                        count,
-                                        first.time.format("%Y-%m-%d %H:%M"),
-                                        last.time.format("%Y-%m-%d %H:%M"),
-                                    );
-                                } else {
-                                    tracing::info!("    ⚠️  {} | 0 records returned", interval);
-                                }
-                            }
-                            Err(e) => {
-                                tracing::error!("    ❌ {} | error: {}", interval, e);
-                            }
-                        }
+                        tracing::info!("  Range: last {}d", days_back);
+                        log_history_test(
+                            &provider,
+                            &ticker,
+                            &[*interval],
+                            HistoryRequest::Range { start, end: now },
+                            std::time::Duration::ZERO,
+                        )
+                        .await;
                     }
                 }
 
                 // 4. Search ticker test
                 tracing::info!("{}", "─".repeat(60));
                 tracing::info!("Search Test — query=\"{}\"", ticker);
-                match provider.search_ticker(&ticker).await {
-                    Ok(result) => {
-                        tracing::info!("    ✅ {} result(s)", result.count);
-                        for item in result.quotes.iter().take(5) {
+                match MarketDataProvider::search(&provider, &ticker, 10).await {
+                    Ok(results) => {
+                        tracing::info!("    ✅ {} result(s)", results.len());
+                        for item in results.iter().take(5) {
                             tracing::info!(
                                 "       {} | {} | {} | {}",
                                 item.symbol,
-                                item.exchange,
-                                item.short_name,
-                                item.quote_type,
+                                item.exchange.as_deref().unwrap_or("?"),
+                                item.description.as_deref().unwrap_or("?"),
+                                item.kind.as_deref().unwrap_or("?"),
                             );
                         }
-                        if result.quotes.len() > 5 {
-                            tracing::info!("       ... ({} more)", result.quotes.len() - 5);
+                        if results.len() > 5 {
+                            tracing::info!("       ... ({} more)", results.len() - 5);
                         }
                     }
                     Err(e) => {
-                        tracing::error!("    ❌ search_ticker error: {}", e);
+                        tracing::error!("    ❌ search error: {}", e);
                     }
                 }
 
@@ -1749,6 +1881,174 @@ pub fn run() {
                 }
             });
         }
+        Commands::Gaps { source, ticker, interval, queue, fill } => {
+            init_fmt_subscriber();
+            let rt = tokio::runtime::Runtime::new().expect("Failed to create Tokio runtime");
+            rt.block_on(async {
+                use crate::constants::gap_worker;
+                use crate::workers::gap_scanner;
+
+                let database_url =
+                    std::env::var("DATABASE_URL").unwrap_or_else(|_| String::new());
+                if database_url.is_empty() {
+                    tracing::error!("DATABASE_URL not set");
+                    return;
+                }
+                let pool = match db::connect(&database_url).await {
+                    Ok(pool) => pool,
+                    Err(e) => {
+                        tracing::error!("Failed to connect to database: {e}");
+                        return;
+                    }
+                };
+
+                let sources: Vec<&str> = if source == "all" {
+                    gap_worker::SOURCES.to_vec()
+                } else if gap_worker::SOURCES.contains(&source.as_str()) {
+                    vec![source.as_str()]
+                } else {
+                    tracing::error!("Unsupported source '{source}'. Use one of {:?} or all", gap_worker::SOURCES);
+                    return;
+                };
+                let intervals: Vec<Interval> = if interval == "all" {
+                    gap_scanner::INTERVALS.to_vec()
+                } else {
+                    match Interval::from_arg(&interval) {
+                        Ok(iv) if gap_scanner::INTERVALS.contains(&iv) => vec![iv],
+                        _ => {
+                            tracing::error!("Unsupported interval '{interval}'. Use 1h, 1m or all");
+                            return;
+                        }
+                    }
+                };
+                let save = queue || fill.is_some();
+                let ticker = ticker.map(|t| t.to_uppercase());
+
+                let mut total_bars = 0i64;
+                let mut total_gaps = 0usize;
+                for src in &sources {
+                    let found = match gap_scanner::scan_source(&pool, src, ticker.as_deref(), &intervals, save).await {
+                        Ok(f) => f,
+                        Err(e) => {
+                            tracing::error!("Gap scan failed for {src}: {e}");
+                            continue;
+                        }
+                    };
+                    for (tk, iv, gap) in &found {
+                        tracing::info!(
+                            "{src} {tk} {iv}: {} .. {} ({} missing)",
+                            gap.start.format("%Y-%m-%d %H:%M"),
+                            gap.end.format("%Y-%m-%d %H:%M"),
+                            gap.missing_bars
+                        );
+                        total_bars += gap.missing_bars as i64;
+                    }
+                    total_gaps += found.len();
+                }
+                tracing::info!("Found {total_gaps} gaps, {total_bars} missing bars{}", if save { " (queued)" } else { "" });
+
+                if let Some(limit) = fill {
+                    let vn = match VciProvider::new(gap_worker::RPM) {
+                        Ok(p) => crate::providers::failover::FailoverProvider::vci_with_udf(p),
+                        Err(e) => {
+                            tracing::error!("Failed to create VCI provider: {e}");
+                            return;
+                        }
+                    };
+                    let crypto = match BinanceProvider::new(gap_worker::RPM) {
+                        Ok(p) => p,
+                        Err(e) => {
+                            tracing::error!("Failed to create Binance provider: {e}");
+                            return;
+                        }
+                    };
+                    match gap_scanner::fill_pending(&pool, &None, &vn, &crypto, limit).await {
+                        Ok((filled, attempted)) => tracing::info!("Backfilled {filled}/{attempted} pending gaps"),
+                        Err(e) => tracing::error!("Failed to load pending gaps: {e}"),
+                    }
+                }
+            });
+        }
+        Commands::Backtest { config, json } => {
+            init_fmt_subscriber();
+            let rt = tokio::runtime::Runtime::new().expect("Failed to create Tokio runtime");
+            rt.block_on(async {
+                use crate::server::analysis::universe_history;
+                use crate::services::backtest::{BacktestRequest, Plan};
+
+                let req: BacktestRequest = match std::fs::read_to_string(&config)
+                    .map_err(|e| e.to_string())
+                    .and_then(|s| serde_json::from_str(&s).map_err(|e| e.to_string()))
+                {
+                    Ok(r) => r,
+                    Err(e) => {
+                        tracing::error!("Failed to read {config}: {e}");
+                        return;
+                    }
+                };
+                let plan = match Plan::new(&req, universe_history) {
+                    Ok(p) => p,
+                    Err(e) => {
+                        tracing::error!("Invalid backtest: {e}");
+                        return;
+                    }
+                };
+
+                let database_url =
+                    std::env::var("DATABASE_URL").unwrap_or_else(|_| String::new());
+                if database_url.is_empty() {
+                    tracing::error!("DATABASE_URL not set");
+                    return;
+                }
+                let pool = match db::connect(&database_url).await {
+                    Ok(pool) => pool,
+                    Err(e) => {
+                        tracing::error!("Failed to connect to database: {e}");
+                        return;
+                    }
+                };
+
+                tracing::info!("Loading daily bars for {} {} tickers", plan.symbols().len(), plan.source());
+                let data = match plan.load(&pool).await {
+                    Ok(d) => d,
+                    Err(e) => {
+                        tracing::error!("Failed to load bars: {e}");
+                        return;
+                    }
+                };
+                let report = plan.run(&data);
+
+                if json {
+                    match serde_json::to_string_pretty(&report) {
+                        Ok(s) => println!("{s}"),
+                        Err(e) => tracing::error!("Failed to serialize report: {e}"),
+                    }
+                    return;
+                }
+                let s = &report.summary;
+                let pct = |v: Option<f64>| v.map_or("-".to_string(), |v| format!("{v:.2}%"));
+                tracing::info!("Entry: {}", report.entry);
+                tracing::info!("Exit:  {}", report.exit);
+                tracing::info!(
+                    "{} .. {}: {:.0} -> {:.0} ({:.2}%, annualized {})",
+                    report.start_date, report.end_date, s.initial_capital, s.final_equity,
+                    s.total_return_pct, pct(s.annualized_return_pct)
+                );
+                tracing::info!(
+                    "Max drawdown {:.2}%, Sharpe {}",
+                    s.max_drawdown_pct,
+                    s.sharpe.map_or("-".to_string(), |v| format!("{v:.2}"))
+                );
+                tracing::info!(
+                    "{} trades ({} open), win rate {}, avg trade {}",
+                    s.trades, s.open_positions, pct(s.win_rate_pct), pct(s.avg_trade_return_pct)
+                );
+                tracing::info!(
+                    "Blocked: price band {}, settlement {}, lot size {}, cash {}",
+                    s.blocked.price_band, s.blocked.settlement, s.blocked.lot_size, s.blocked.cash
+                );
+            });
+        }
         Commands::TestS3 { ticker, interval, days, create_bucket } => {
             init_fmt_subscriber();
             let rt = tokio::runtime::Runtime::new().expect("Failed to create Tokio runtime");
@@ -1794,50 +2094,14 @@ pub fn run() {
                         ticker, count_back
                     );
 
-                    for interval in &["1D", "1H", "1m"] {
-                        tracing::info!("  Fetching {} ...", interval);
-                        match provider
-                            .get_history(&ticker, interval, count_back, None)
-                            .await
-                        {
-                            Ok(data) => {
-                                let count = data.len();
-                                if count > 0 {
-                                    let first = &data[0];
-                                    let last = &data[count - 1];
-                                    tracing::info!(
-                                        "    ✅ {} | {} records | {} → {}",
-                                        interval,
-                                        count,
-                                        first.time.format("%Y-%m-%d %H:%M"),
-                                        last.time.format("%Y-%m-%d %H:%M"),
-                                    );
-                                    for row in data.iter().take(3) {
-                                        tracing::info!(
-                                            "       {} | O:{} H:{} L:{} C:{} V:{}",
-                                            row.time.format("%Y-%m-%d %H:%M"),
-                                            row.open,
-                                            row.high,
-                                            row.low,
-                                            row.close,
-                                            row.volume,
-                                        );
-                                    }
-                                    if count > 3 {
-                                        tracing::info!("       ... ({} more)", count - 3);
-                                    }
-                                } else {
-                                    tracing::info!("    ⚠️  {} | 0 records returned", interval);
-                                }
-                            }
-                            Err(e) => {
-                                tracing::error!("    ❌ {} | error: {}", interval, e);
-                            }
-                        }
-
-                        // Sleep between intervals to respect rate limits
-                        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
-                    }
+                    log_history_test(
+                        &provider,
+                        &ticker,
+                        &[Interval::Daily, Interval::Hourly, Interval::Minute],
+                        HistoryRequest::count_back(count_back),
+                        std::time::Duration::from_secs(1),
+                    )
+                    .await;
 
                     // 2. /config test
                     tracing::info!("{}", "─".repeat(60));
@@ -1900,20 +2164,20 @@ pub fn run() {
                     // 4. /search test
                     let query = &ticker[..ticker.len().min(2)];
                     tracing::info!("UDF Protocol: /search?query={}", query);
-                    match provider.search(query, 5).await {
-                        Ok(Some(results)) => {
+                    match MarketDataProvider::search(&provider, query, 5).await {
+                        Ok(results) => {
                             tracing::info!("    ✅ {} result(s)", results.len());
                             for item in results.iter().take(5) {
                                 tracing::info!(
                                     "       {} | {} | {} | {}",
-                                    item.symbol.as_deref().unwrap_or("?"),
+                                    item.symbol,
                                     item.exchange.as_deref().unwrap_or("?"),
                                     item.description.as_deref().unwrap_or("?"),
-                                    item.r#type.as_deref().unwrap_or("?"),
+                                    item.kind.as_deref().unwrap_or("?"),
                                 );
                             }
                         }
-                        Ok(None) => {
+                        Err(ProviderError::Unsupported(_)) => {
                             tracing::info!("    ⚠️  /search not supported (404)");
                         }
                         Err(e) => {
@@ -1923,13 +2187,13 @@ pub fn run() {
 
                     // 5. /time test
                     tracing::info!("UDF Protocol: /time");
-                    match provider.get_server_time().await {
+                    match provider.server_time().await {
                         Ok(Some(time)) => {
-                            let dt = chrono::DateTime::<chrono::Utc>::from_timestamp(time, 0);
-                            let time_str = dt
-                                .map(|d| d.format("%Y-%m-%d %H:%M:%S UTC").to_string())
-                                .unwrap_or_else(|| time.to_string());
-                            tracing::info!("    ✅ server time: {} ({})", time, time_str);
+                            tracing::info!(
+                                "    ✅ server time: {} ({})",
+                                time.timestamp(),
+                                time.format("%Y-%m-%d %H:%M:%S UTC"),
+                            );
                         }
                         Ok(None) => {
                             tracing::info!("    ⚠️  /time not supported (404)");
@@ -36,6 +36,9 @@ pub mod vci_worker {
     pub const DIVIDEND_CHECK_BARS: i64 = 20;
     /// Minimum number of diverging candles to confirm a dividend (reduces false positives from data corrections)
     pub const DIVIDEND_MIN_DIVERGING_BARS: usize = 5;
+    /// Divergence ratios at or above this are recorded as splits / stock dividends,
+    /// smaller ones as cash dividends (VN cash payouts rarely exceed ~7% of price)
+    pub const SPLIT_RATIO_THRESHOLD: f64 = 1.08;
 
     /// Daily countBack: always fetch 100 bars
     pub const DAILY_COUNTBACK: u32 = 100;
@@ -78,6 +81,20 @@ pub mod vci_worker {
     /// Minute worker: initial delay before first sync
     pub const MINUTE_INITIAL_DELAY_SECS: u64 = 60;
 
+    /// Requests per minute for each UDF fallback broker.
+    pub const FALLBACK_RPM: u32 = 30;
+    /// Ordered UDF brokers tried when VCI is rate limited or has no data.
+    /// Override via `VCI_FALLBACK_SOURCES` (comma-separated, empty disables).
+    /// Default: vietstock,vndirect,dnse,vps.
+    pub fn fallback_sources() -> Vec<String> {
+        std::env::var("VCI_FALLBACK_SOURCES")
+            .unwrap_or_else(|_| crate::providers::udf::ALL_SOURCES.join(","))
+            .split(',')
+            .map(|s| s.trim().to_lowercase())
+            .filter(|s| !s.is_empty())
+            .collect()
+    }
+
     /// Index tickers (no dividend detection, no legacy price scaling)
     pub const INDEX_TICKERS: &[&str] = &[
         // Exchange boards
@@ -184,6 +201,26 @@ pub mod binance_worker {
             default_secs
         }
     }
+
+    /// Minute kline stream (`BINANCE_MINUTE_STREAM=true`): combined-stream base URL.
+    /// Override via `BINANCE_STREAM_URL` env var.
+    pub fn stream_url() -> String {
+        std::env::var("BINANCE_STREAM_URL")
+            .ok()
+            .filter(|v| !v.is_empty())
+            .unwrap_or_else(|| "wss://stream.binance.com:9443".to_string())
+    }
+    /// Streams per WebSocket connection (Binance allows 1024).
+    pub const STREAMS_PER_CONNECTION: usize = 200;
+    /// How often buffered kline updates are upserted (seconds).
+    pub const STREAM_FLUSH_SECS: u64 = 2;
+    /// Reconnect backoff bounds (seconds).
+    pub const STREAM_RECONNECT_MIN_SECS: u64 = 1;
+    pub const STREAM_RECONNECT_MAX_SECS: u64 = 60;
+    /// Binance closes connections after 24h; reconnect (and reload tickers) before that.
+    pub const STREAM_MAX_SESSION_SECS: u64 = 23 * 3600;
+    /// Requests per minute for the REST backfill that runs after each (re)connect.
+    pub const STREAM_BACKFILL_RPM: u32 = 120;
 }
 
 /// Yahoo Finance worker timing and configuration constants.
@@ -208,26 +245,16 @@ pub mod yahoo_worker {
         (client_count * 2).min(4).max(1)
     }
 
-    /// Ranges for incremental fetches
-    pub const DAILY_RANGE: &str = "5d";
-    pub const HOURLY_RANGE: &str = "5d";
-    pub const MINUTE_RANGE: &str = "1d";
+    /// Lookback windows (days) for incremental fetches
+    pub const DAILY_WINDOW_DAYS: i64 = 5;
+    pub const HOURLY_WINDOW_DAYS: i64 = 5;
+    pub const MINUTE_WINDOW_DAYS: i64 = 1;
 
     /// Fixed scheduling intervals (seconds)
     pub const SCHEDULE_DAILY_SECS: i64 = 60;
     pub const SCHEDULE_HOURLY_SECS: i64 = 300;
     pub const SCHEDULE_MINUTE_SECS: i64 = 600;
 
-    /// Schedule interval for a ticker: major global tickers get MAJOR_SCHEDULE_SECS,
-    /// others get the standard interval for their timeframe.
-    pub fn schedule_secs(ticker: &str, default_secs: i64) -> i64 {
-        if super::MAJOR_GLOBAL.contains(&ticker) {
-            super::MAJOR_SCHEDULE_SECS
-        } else {
-            default_secs
-        }
-    }
-
     /// Bootstrap chunk sizes (days)
     pub const BOOTSTRAP_DAILY_CHUNK_DAYS: i64 = 365;
     pub const BOOTSTRAP_HOURLY_CHUNK_DAYS: i64 = 30;
@@ -246,6 +273,8 @@ pub mod yahoo_worker {
     pub const DIVIDEND_RATIO_THRESHOLD: f64 = 1.03;
     pub const DIVIDEND_CHECK_BARS: i64 = 20;
     pub const DIVIDEND_MIN_DIVERGING_BARS: usize = 5;
+    /// Yahoo `close` is split-adjusted only, so every confirmed divergence is a split
+    pub const SPLIT_RATIO_THRESHOLD: f64 = 1.0;
 }
 
 /// SJC gold price worker timing and configuration constants.
@@ -277,6 +306,85 @@ pub mod sjc_worker {
     pub const BRANCH: &str = "Hồ Chí Minh";
 }
 
+/// Cross-provider reconciliation worker constants (VCI vs UDF brokers).
+pub mod reconcile_worker {
+    /// Loop interval between reconciliation rounds (30 min)
+    pub const LOOP_SECS: u64 = 1800;
+    /// Initial delay so the sync workers get the first request budget
+    pub const INITIAL_DELAY_SECS: u64 = 300;
+    /// VN tickers sampled per round
+    pub const SAMPLE_SIZE: usize = 20;
+    /// Recent daily bars compared per ticker
+    pub const DAILY_COUNTBACK: u32 = 10;
+    /// Recent hourly bars compared per ticker
+    pub const HOURLY_COUNTBACK: u32 = 30;
+    /// Requests per minute for each provider used by the reconciler
+    pub const RPM: u32 = 10;
+    /// Max relative OHLC difference before a bar is flagged (0.5%)
+    pub const PRICE_TOLERANCE_PCT: f64 = 0.5;
+    /// Max relative volume difference before a bar is flagged (10%)
+    pub const VOLUME_TOLERANCE_PCT: f64 = 10.0;
+    /// Default and max rows returned by /admin/discrepancies
+    pub const API_DEFAULT_LIMIT: i64 = 100;
+    pub const API_MAX_LIMIT: i64 = 1000;
+}
+
+/// Gap scanner / targeted backfill constants (1h and 1m bars).
+pub mod gap_worker {
+    /// Loop interval between scan rounds (6 hours)
+    pub const LOOP_SECS: u64 = 6 * 3600;
+    /// Initial delay so the sync workers get the first request budget
+    pub const INITIAL_DELAY_SECS: u64 = 600;
+    /// Sources scanned; both have a reliable exchange calendar
+    pub const SOURCES: &[&str] = &["vn", "crypto"];
+    /// Days of hourly history scanned per ticker
+    pub const HOURLY_LOOKBACK_DAYS: i64 = 30;
+    /// Days of minute history scanned per ticker
+    pub const MINUTE_LOOKBACK_DAYS: i64 = 5;
+    /// Minimum consecutive missing hourly bars reported as a gap
+    pub const MIN_HOURLY_BARS: usize = 2;
+    /// Minimum consecutive missing minute bars reported as a gap. Illiquid VN
+    /// tickers skip minutes without trades, and the ATC auction leaves ~30.
+    pub const MIN_MINUTE_BARS: usize = 60;
+    /// Bars newer than this are left to the regular sync workers
+    pub const SETTLE_MINUTES: i64 = 90;
+    /// Pending gaps backfilled per round
+    pub const FILLS_PER_ROUND: i64 = 200;
+    /// Fetch attempts before a gap is marked unfillable
+    pub const MAX_ATTEMPTS: i32 = 3;
+    /// Requests per minute for backfill fetches
+    pub const RPM: u32 = 20;
+    /// Default and max rows returned by /admin/gaps
+    pub const API_DEFAULT_LIMIT: i64 = 100;
+    pub const API_MAX_LIMIT: i64 = 1000;
+}
+
+/// Ingest validation rules (see `services::validation`).
+pub mod validation {
+    /// Bars used for the ATR in the spike rule
+    pub const ATR_PERIOD: usize = 14;
+    /// Stored bars loaded before each batch as spike-rule history
+    pub const HISTORY_BARS: i64 = 30;
+    /// Move from the previous close, in ATRs, above which a bar may be a bad tick
+    pub const SPIKE_ZSCORE: f64 = 8.0;
+    /// ...and the price ratio it must also exceed (keeps 2:1 splits out)
+    pub const SPIKE_MIN_RATIO: f64 = 3.0;
+    /// Sources whose intraday bars only exist when trades happened, so a
+    /// zero-volume bar is bogus (crypto and Yahoo emit legitimate empty bars)
+    pub const ZERO_VOLUME_SOURCES: &[&str] = &["vn"];
+
+    /// Rules switched off via `VALIDATION_DISABLED_RULES` (comma-separated
+    /// rule names, e.g. `spike,volume`). Default: all rules on.
+    pub fn disabled_rules() -> Vec<String> {
+        std::env::var("VALIDATION_DISABLED_RULES")
+            .unwrap_or_default()
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect()
+    }
+}
+
 /// Additional data sources whose tickers should appear under the yahoo/global mode.
 /// Each source maps to its ticker JSON file: {source}_tickers.json
 pub const MERGE_WITH_YAHOO: &[&str] = &["sjc"];
@@ -302,6 +410,10 @@ pub mod api {
     pub const fn sma_buffer_for(max_period: usize) -> i64 {
         max_period as i64
     }
+    /// EMA counterpart of `sma_buffer_for`: three periods, like `EMA_LOOKBACK`.
+    pub const fn ema_buffer_for(max_period: usize) -> i64 {
+        3 * max_period as i64
+    }
     /// Default value for the `ema` query parameter across all endpoints.
     /// Set to true to use EMA by default, false to use SMA.
     pub const DEFAULT_USE_EMA: bool = false;
@@ -372,6 +484,210 @@ pub mod redis_ts {
         /// TTL for snapshot hashes in seconds.
         pub const TTL_SECS: u64 = 30;
     }
+
+    /// Pub/sub channel announcing every ZSET write, payload `{"source","ticker","interval"}`.
+    /// Lets every API replica push live candles regardless of which process ran the worker.
+    pub const OHLCV_UPDATES_CHANNEL: &str = "ohlcv:updates";
+}
+
+/// Materialised aggregate intervals, stored in their own `ohlcv` partitions.
+pub mod materialize {
+    use crate::models::aggregated_interval::AggregatedInterval;
+
+    /// Intervals that have a partition (see the `add_materialized_intervals` migration).
+    pub const SUPPORTED: &[AggregatedInterval] =
+        &[AggregatedInterval::Minutes5, AggregatedInterval::Minutes15, AggregatedInterval::Week];
+
+    /// Enabled intervals. Set via `MATERIALIZED_INTERVALS` (comma-separated,
+    /// e.g. `5m,15m,1W`); unsupported values are ignored. Default: none.
+    pub fn intervals() -> Vec<AggregatedInterval> {
+        std::env::var("MATERIALIZED_INTERVALS")
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|s| AggregatedInterval::from_str(s.trim()))
+            .filter(|a| SUPPORTED.contains(a))
+            .collect()
+    }
+
+    /// Worker loop interval (catch-up for bars written outside `enhance_and_save`).
+    pub const LOOP_SECS: u64 = 3600;
+    /// Worker initial delay before the first pass.
+    pub const INITIAL_DELAY_SECS: u64 = 120;
+    /// Base-bar window per backfill query for minute-based targets (days).
+    pub const MINUTE_WINDOW_DAYS: i64 = 7;
+    /// Base-bar window per backfill query for day-based targets (weeks).
+    pub const DAILY_WINDOW_WEEKS: i64 = 520;
+}
+
+/// Exchange calendar configuration (see `models::calendar`).
+pub mod calendar {
+    use chrono::NaiveDate;
+
+    /// `VN_EXTRA_HOLIDAYS`, read once: the parsed dates and the entries
+    /// that are not `YYYY-MM-DD`.
+    fn vn_extra() -> &'static (Vec<NaiveDate>, Vec<String>) {
+        static EXTRA: std::sync::OnceLock<(Vec<NaiveDate>, Vec<String>)> = std::sync::OnceLock::new();
+        EXTRA.get_or_init(|| parse_holidays(&std::env::var("VN_EXTRA_HOLIDAYS").unwrap_or_default()))
+    }
+
+    /// Split a comma-separated `YYYY-MM-DD` list into dates and malformed
+    /// entries. Blank entries are skipped.
+    pub fn parse_holidays(raw: &str) -> (Vec<NaiveDate>, Vec<String>) {
+        let mut dates = Vec::new();
+        let mut malformed = Vec::new();
+        for entry in raw.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            match NaiveDate::parse_from_str(entry, "%Y-%m-%d") {
+                Ok(d) => dates.push(d),
+                Err(_) => malformed.push(entry.to_string()),
+            }
+        }
+        (dates, malformed)
+    }
+
+    /// Extra VN exchange closures not yet in the built-in table. Set via
+    /// `VN_EXTRA_HOLIDAYS` (comma-separated `YYYY-MM-DD`).
+    pub fn vn_extra_holidays() -> &'static [NaiveDate] {
+        &vn_extra().0
+    }
+
+    /// `VN_EXTRA_HOLIDAYS` entries that failed to parse; `serve` refuses to
+    /// start while there are any.
+    pub fn vn_extra_holidays_malformed() -> &'static [String] {
+        &vn_extra().1
+    }
+}
+
+/// Live candle streaming (`/ws`, `/tickers/stream`) configuration.
+pub mod live {
+    /// Max (mode, symbol, interval) subscriptions per WebSocket or SSE connection.
+    pub const MAX_SUBSCRIPTIONS: usize = 200;
+    /// Capacity of the in-process candle broadcast channel. Slow clients that
+    /// fall further behind skip the missed candles.
+    pub const BROADCAST_CAPACITY: usize = 4096;
+    /// Server → client ping / SSE keep-alive interval (seconds). Keeps proxies from closing idle connections.
+    pub const PING_SECS: u64 = 20;
+    /// Max candles replayed per key when an SSE client resumes with `Last-Event-ID`.
+    pub const RESUME_MAX_BARS: i64 = 500;
+}
+
+/// Price/indicator alert rules (`/alerts`) and the webhook delivery worker.
+pub mod alerts {
+    /// Max rules per API token
+    pub const MAX_RULES_PER_OWNER: i64 = 200;
+    /// How often the worker reloads enabled rules from Postgres
+    pub const RULES_REFRESH_SECS: u64 = 30;
+    /// Webhook request timeout
+    pub const WEBHOOK_TIMEOUT_SECS: u64 = 10;
+    /// Delivery attempts before a notification is marked failed
+    pub const MAX_ATTEMPTS: i32 = 6;
+    /// First retry delay; doubles after every failed attempt
+    pub const RETRY_BASE_SECS: i64 = 30;
+    /// Upper bound on the retry delay
+    pub const RETRY_MAX_SECS: i64 = 3600;
+    /// Deliveries claimed per round
+    pub const DELIVERY_BATCH: i64 = 50;
+    /// A claimed delivery is hidden from other rounds for this long
+    pub const DELIVERY_LEASE_SECS: i64 = 120;
+    /// Poll interval for due retries when no new alert fired
+    pub const RETRY_POLL_SECS: u64 = 15;
+    /// Deliveries returned by GET /alerts/{id}
+    pub const API_DELIVERY_LIMIT: i64 = 50;
+}
+
+/// Portfolio tracking (`/portfolios`).
+pub mod portfolio {
+    /// Max portfolios per API token
+    pub const MAX_PORTFOLIOS_PER_OWNER: i64 = 50;
+    /// Max ledger entries per portfolio
+    pub const MAX_TRANSACTIONS: i64 = 10_000;
+    /// Longest NAV history in one request, in days
+    pub const MAX_NAV_DAYS: i64 = 3660;
+    /// USD/VND rate (VND per USD), a Yahoo daily series
+    pub const FX_SOURCE: &str = "yahoo";
+    pub const FX_TICKER: &str = "VND=X";
+    /// Gold holdings are valued at the SJC bar price (VND per tael)
+    pub const GOLD_SOURCE: &str = "sjc";
+    /// Calendar days searched back for the last close before a valuation date
+    pub const PRICE_LOOKBACK_DAYS: i64 = 30;
+}
+
+/// Backtesting engine (`POST /analysis/backtest`, `backtest` CLI).
+pub mod backtest {
+    /// Starting cash when the request sets none
+    pub const DEFAULT_CAPITAL: f64 = 100_000_000.0;
+    /// Share of equity put into each new position (%)
+    pub const DEFAULT_POSITION_PCT: f64 = 10.0;
+    pub const DEFAULT_MAX_POSITIONS: usize = 10;
+    /// Commission per side (%), typical VN retail broker rate
+    pub const DEFAULT_FEE_PCT: f64 = 0.15;
+    /// VN personal income tax withheld on the value of every sale (%)
+    pub const VN_SELL_TAX_PCT: f64 = 0.1;
+    /// Max tickers in one run
+    pub const MAX_SYMBOLS: usize = 1000;
+    /// Longest simulated range, in calendar days (~20 years)
+    pub const MAX_RANGE_DAYS: i64 = 20 * 366;
+}
+
+/// Currency conversion (`currency=` on /tickers and /analysis/*).
+pub mod fx {
+    /// Rates are Yahoo `{CODE}=X` series: units of CODE per US dollar
+    pub const SOURCE: &str = "yahoo";
+    /// Yahoo symbol suffix → quote currency, for listings outside the US
+    pub const YAHOO_SUFFIXES: &[(&str, &str)] = &[
+        (".T", "JPY"),
+        (".HK", "HKD"),
+        (".KS", "KRW"),
+        (".SS", "CNY"),
+        (".SZ", "CNY"),
+        (".DE", "EUR"),
+        (".PA", "EUR"),
+        (".AS", "EUR"),
+    ];
+    /// Calendar days of rates loaded before a request's estimated first bar,
+    /// for holidays and trading halts the per-bar estimate leaves out
+    pub const MARGIN_DAYS: i64 = 30;
+    /// Calendar minutes assumed per minute of an intraday bar: VN trades
+    /// 4.5 hours a weekday, so a run of bars spans up to ~7.5x its length
+    pub const INTRADAY_SPAN_FACTOR: i64 = 8;
+}
+
+/// Market breadth (`/analysis/breadth`).
+pub mod breadth {
+    /// Days returned when the request sets none
+    pub const DEFAULT_DAYS: usize = 60;
+    /// Longest series in one request, in trading days
+    pub const MAX_DAYS: usize = 500;
+    /// MA periods behind `above_ma20` / `above_ma50` / `above_ma200`
+    pub const MA_PERIODS: [usize; 3] = [20, 50, 200];
+    /// Bars in the 52-week high/low window
+    pub const HIGH_LOW_WINDOW: usize = 252;
+    /// `sector` value of the whole-market rows
+    pub const MARKET: &str = "ALL";
+}
+
+/// Ticker metadata (`ticker_metadata`, `/admin/tickers`).
+pub mod metadata {
+    /// Seconds between reloads of the in-memory catalog, so edits made on
+    /// another replica show up here
+    pub const RELOAD_SECS: u64 = 300;
+    /// Group every crypto ticker is listed under
+    pub const CRYPTO_SECTOR: &str = "CRYPTO_TOP_100";
+    /// Group of Yahoo/SJC tickers without a category
+    pub const DEFAULT_CATEGORY: &str = "Other";
+    /// Shares per lot seeded for VN stocks and funds
+    pub const VN_LOT_SIZE: f64 = 100.0;
+}
+
+/// Synthetic custom indices (`/admin/indices`, custom index worker).
+pub mod custom_index {
+    /// Seconds between refresh rounds
+    pub const LOOP_SECS: u64 = 300;
+    /// Worker initial delay before the first round
+    pub const INITIAL_DELAY_SECS: u64 = 90;
+    /// Intraday intervals computed, with the days of history built on a rebuild
+    pub const INTRADAY: &[(&str, i64)] = &[("1h", 365), ("1m", 30)];
+    /// Level of the first bar when the definition sets none
+    pub const DEFAULT_BASE_VALUE: f64 = 1000.0;
 }
 
 /// S3 archive worker configuration.
@@ -0,0 +1,397 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Timelike, Utc, Weekday};
+use serde::Serialize;
+
+use super::interval::Interval;
+
+/// Exchange trading calendar: sessions in local time, lunch breaks and
+/// holidays. Used for worker pacing, `/health` and aggregation alignment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub enum Exchange {
+    Hose,
+    Hnx,
+    Upcom,
+    Nyse,
+    /// Binance spot — open around the clock.
+    Crypto,
+}
+
+/// VN exchange closures (HOSE/HNX/UPCOM share one schedule). Lunar holidays
+/// (Tet, Hung Kings) move every year, so this table is updated from the
+/// exchange announcements each December; `VN_EXTRA_HOLIDAYS` covers the gap.
+/// In a year neither lists, VN gap scanning is off (see
+/// [`Exchange::holidays_known`] and [`check_vn_holidays`]).
+const VN_HOLIDAYS: &[(i32, u32, u32)] = &[
+    // 2024
+    (2024, 1, 1),
+    (2024, 2, 8), (2024, 2, 9), (2024, 2, 12), (2024, 2, 13), (2024, 2, 14),
+    (2024, 4, 18),
+    (2024, 4, 29), (2024, 4, 30), (2024, 5, 1),
+    (2024, 9, 2), (2024, 9, 3),
+    // 2025
+    (2025, 1, 1),
+    (2025, 1, 27), (2025, 1, 28), (2025, 1, 29), (2025, 1, 30), (2025, 1, 31),
+    (2025, 4, 7),
+    (2025, 4, 30), (2025, 5, 1),
+    (2025, 9, 1), (2025, 9, 2),
+    // 2026
+    (2026, 1, 1),
+    (2026, 2, 16), (2026, 2, 17), (2026, 2, 18), (2026, 2, 19), (2026, 2, 20),
+    (2026, 4, 27),
+    (2026, 4, 30), (2026, 5, 1),
+    (2026, 9, 1), (2026, 9, 2),
+];
+
+/// Whether the built-in table or `VN_EXTRA_HOLIDAYS` lists a closure in `year`.
+fn vn_holidays_known(year: i32) -> bool {
+    VN_HOLIDAYS.iter().any(|&(y, _, _)| y == year)
+        || crate::constants::calendar::vn_extra_holidays().iter().any(|d| d.year() == year)
+}
+
+/// Startup check: an error when `VN_EXTRA_HOLIDAYS` has malformed entries.
+/// Missing holidays for `today`'s year (Tet would count as trading days) are
+/// logged as an error, missing ones for next year as a warning in December.
+pub fn check_vn_holidays(today: NaiveDate) -> Result<(), String> {
+    let malformed = crate::constants::calendar::vn_extra_holidays_malformed();
+    if !malformed.is_empty() {
+        return Err(format!("VN_EXTRA_HOLIDAYS: expected YYYY-MM-DD, got {}", malformed.join(", ")));
+    }
+    let year = today.year();
+    if !vn_holidays_known(year) {
+        tracing::error!(
+            "No VN exchange holidays known for {year}: VN gap scanning is off for {year} until they are added to VN_HOLIDAYS or VN_EXTRA_HOLIDAYS"
+        );
+    }
+    if today.month() == 12 && !vn_holidays_known(year + 1) {
+        tracing::warn!("No VN exchange holidays known for {} yet: update VN_HOLIDAYS before January", year + 1);
+    }
+    Ok(())
+}
+
+/// Indochina Time (no daylight saving).
+const VN_UTC_OFFSET_HOURS: i64 = 7;
+/// Morning and afternoon sessions, 09:00–11:30 and 13:00–15:00 ICT
+/// (the close includes ATC and put-through).
+const VN_SESSIONS: &[(u32, u32)] = &[(9 * 60, 11 * 60 + 30), (13 * 60, 15 * 60)];
+/// Regular session 09:30–16:00 ET. Early closes are treated as full days.
+const NYSE_SESSIONS: &[(u32, u32)] = &[(9 * 60 + 30, 16 * 60)];
+const CRYPTO_SESSIONS: &[(u32, u32)] = &[(0, 24 * 60)];
+
+impl Exchange {
+    pub const ALL: [Exchange; 5] = [Self::Hose, Self::Hnx, Self::Upcom, Self::Nyse, Self::Crypto];
+
+    /// Calendar for a DB source. SJC and other sources have no calendar.
+    pub fn for_source(source: &str) -> Option<Self> {
+        match source {
+            "vn" => Some(Self::Hose),
+            "yahoo" => Some(Self::Nyse),
+            "crypto" => Some(Self::Crypto),
+            _ => None,
+        }
+    }
+
+    /// Exchange for a listing code as stored in `ticker_metadata.exchange`.
+    pub fn from_code(code: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|e| e.as_str().eq_ignore_ascii_case(code.trim()))
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Hose => "HOSE",
+            Self::Hnx => "HNX",
+            Self::Upcom => "UPCOM",
+            Self::Nyse => "NYSE",
+            Self::Crypto => "CRYPTO",
+        }
+    }
+
+    /// Daily price limit as a fraction of the reference (previous close).
+    pub fn price_band(&self) -> Option<f64> {
+        match self {
+            Self::Hose => Some(0.07),
+            Self::Hnx => Some(0.10),
+            Self::Upcom => Some(0.15),
+            Self::Nyse | Self::Crypto => None,
+        }
+    }
+
+    /// Shares per round lot; None = fractional quantities allowed.
+    pub fn lot_size(&self) -> Option<f64> {
+        match self {
+            Self::Hose | Self::Hnx | Self::Upcom => Some(100.0),
+            Self::Nyse => Some(1.0),
+            Self::Crypto => None,
+        }
+    }
+
+    /// Trading days until bought shares (and sale proceeds) settle.
+    pub fn settlement_days(&self) -> usize {
+        match self {
+            Self::Hose | Self::Hnx | Self::Upcom => 2,
+            Self::Nyse => 1,
+            Self::Crypto => 0,
+        }
+    }
+
+    /// IANA timezone the sessions are expressed in.
+    pub fn timezone(&self) -> &'static str {
+        match self {
+            Self::Hose | Self::Hnx | Self::Upcom => "Asia/Ho_Chi_Minh",
+            Self::Nyse => "America/New_York",
+            Self::Crypto => "UTC",
+        }
+    }
+
+    /// Local UTC offset on `date`. New York observes US daylight saving time
+    /// (second Sunday of March to first Sunday of November).
+    pub fn utc_offset(&self, date: NaiveDate) -> Duration {
+        match self {
+            Self::Hose | Self::Hnx | Self::Upcom => Duration::hours(VN_UTC_OFFSET_HOURS),
+            Self::Crypto => Duration::zero(),
+            Self::Nyse => {
+                let year = date.year();
+                let dst_start = NaiveDate::from_weekday_of_month_opt(year, 3, Weekday::Sun, 2).unwrap();
+                let dst_end = NaiveDate::from_weekday_of_month_opt(year, 11, Weekday::Sun, 1).unwrap();
+                if date >= dst_start && date < dst_end { Duration::hours(-4) } else { Duration::hours(-5) }
+            }
+        }
+    }
+
+    /// Trading sessions as `[open, close)` minutes after local midnight.
+    pub fn sessions(&self) -> &'static [(u32, u32)] {
+        match self {
+            Self::Hose | Self::Hnx | Self::Upcom => VN_SESSIONS,
+            Self::Nyse => NYSE_SESSIONS,
+            Self::Crypto => CRYPTO_SESSIONS,
+        }
+    }
+
+    pub fn is_holiday(&self, date: NaiveDate) -> bool {
+        match self {
+            Self::Hose | Self::Hnx | Self::Upcom => {
+                VN_HOLIDAYS.iter().any(|&(y, m, d)| (date.year(), date.month(), date.day()) == (y, m, d))
+                    || crate::constants::calendar::vn_extra_holidays().contains(&date)
+            }
+            Self::Nyse => nyse_holidays(date.year()).contains(&date),
+            Self::Crypto => false,
+        }
+    }
+
+    /// Whether the holidays of `year` are known. False for VN years missing
+    /// from both the built-in table and `VN_EXTRA_HOLIDAYS`; callers that
+    /// need exact trading days (gap scanning) skip such years.
+    pub fn holidays_known(&self, year: i32) -> bool {
+        match self {
+            Self::Hose | Self::Hnx | Self::Upcom => vn_holidays_known(year),
+            Self::Nyse | Self::Crypto => true,
+        }
+    }
+
+    /// Weekday that is not a holiday (every day for crypto).
+    pub fn is_trading_day(&self, date: NaiveDate) -> bool {
+        match self {
+            Self::Crypto => true,
+            _ => date.weekday().num_days_from_monday() < 5 && !self.is_holiday(date),
+        }
+    }
+
+    /// Whether `now` falls inside a session (lunch breaks excluded).
+    pub fn is_open(&self, now: DateTime<Utc>) -> bool {
+        let local = now.naive_utc() + self.utc_offset(now.date_naive());
+        let minute = local.hour() * 60 + local.minute();
+        self.is_trading_day(local.date())
+            && self.sessions().iter().any(|&(open, close)| minute >= open && minute < close)
+    }
+
+    /// Expected bar open times with `start <= time < end`: every minute or hour
+    /// overlapping a session on a trading day. Daily bars are stamped at
+    /// midnight UTC of the local trading date, matching stored 1D rows.
+    pub fn expected_bars(&self, interval: Interval, start: DateTime<Utc>, end: DateTime<Utc>) -> Vec<DateTime<Utc>> {
+        let step = match interval {
+            Interval::Minute => 1,
+            Interval::Hourly => 60,
+            Interval::Daily => 24 * 60,
+        };
+        let local_date = |t: DateTime<Utc>| (t.naive_utc() + self.utc_offset(t.date_naive())).date();
+
+        let mut bars = Vec::new();
+        let mut date = local_date(start);
+        let last = local_date(end);
+        while date <= last {
+            if self.is_trading_day(date) {
+                let midnight = date.and_hms_opt(0, 0, 0).unwrap().and_utc();
+                if interval == Interval::Daily {
+                    bars.push(midnight);
+                } else {
+                    let offset = self.utc_offset(date);
+                    for &(open, close) in self.sessions() {
+                        let mut minute = open / step * step;
+                        while minute < close {
+                            bars.push(midnight + Duration::minutes(minute as i64) - offset);
+                            minute += step;
+                        }
+                    }
+                }
+            }
+            date += Duration::days(1);
+        }
+        bars.retain(|t| *t >= start && *t < end);
+        bars
+    }
+
+    /// Hour offset from UTC midnight for multi-hour candle buckets (4h).
+    ///
+    /// VN buckets start at the 09:00 ICT open (02:00 UTC). NYSE opens on the
+    /// half hour and shifts with DST, and crypto has no open, so both keep
+    /// midnight-UTC alignment.
+    pub fn bucket_offset_hours(&self) -> i64 {
+        match self {
+            Self::Hose | Self::Hnx | Self::Upcom => (VN_SESSIONS[0].0 / 60) as i64 - VN_UTC_OFFSET_HOURS,
+            Self::Nyse | Self::Crypto => 0,
+        }
+    }
+}
+
+/// NYSE full-day closures for `year`, from the exchange's holiday rules.
+pub fn nyse_holidays(year: i32) -> Vec<NaiveDate> {
+    let ymd = |m, d| NaiveDate::from_ymd_opt(year, m, d).unwrap();
+    let nth = |m, wd, n| NaiveDate::from_weekday_of_month_opt(year, m, wd, n).unwrap();
+
+    let mut days = Vec::with_capacity(10);
+    // New Year's Day: a Saturday holiday is not observed on the Friday before.
+    let new_year = ymd(1, 1);
+    match new_year.weekday() {
+        Weekday::Sat => {}
+        Weekday::Sun => days.push(ymd(1, 2)),
+        _ => days.push(new_year),
+    }
+    days.push(nth(1, Weekday::Mon, 3)); // Martin Luther King Jr. Day
+    days.push(nth(2, Weekday::Mon, 3)); // Washington's Birthday
+    days.push(easter(year) - Duration::days(2)); // Good Friday
+    days.push(
+        NaiveDate::from_weekday_of_month_opt(year, 5, Weekday::Mon, 5).unwrap_or_else(|| nth(5, Weekday::Mon, 4)),
+    ); // Memorial Day
+    if year >= 2022 {
+        days.push(observed(ymd(6, 19))); // Juneteenth
+    }
+    days.push(observed(ymd(7, 4))); // Independence Day
+    days.push(nth(9, Weekday::Mon, 1)); // Labor Day
+    days.push(nth(11, Weekday::Thu, 4)); // Thanksgiving
+    days.push(observed(ymd(12, 25))); // Christmas
+    days
+}
+
+/// Saturday holidays are observed on Friday, Sunday holidays on Monday.
+fn observed(date: NaiveDate) -> NaiveDate {
+    match date.weekday() {
+        Weekday::Sat => date - Duration::days(1),
+        Weekday::Sun => date + Duration::days(1),
+        _ => date,
+    }
+}
+
+/// Gregorian Easter Sunday (anonymous Gregorian algorithm).
+fn easter(year: i32) -> NaiveDate {
+    let a = year % 19;
+    let b = year / 100;
+    let c = year % 100;
+    let d = b / 4;
+    let e = b % 4;
+    let f = (b + 8) / 25;
+    let g = (b - f + 1) / 3;
+    let h = (19 * a + b - d - g + 15) % 30;
+    let i = c / 4;
+    let k = c % 4;
+    let l = (32 + 2 * e + 2 * i - h - k) % 7;
+    let m = (a + 11 * h + 22 * l) / 451;
+    let month = (h + l - 7 * m + 114) / 31;
+    let day = (h + l - 7 * m + 114) % 31 + 1;
+    NaiveDate::from_ymd_opt(year, month as u32, day as u32).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn utc(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, m, d, h, min, 0).unwrap()
+    }
+
+    #[test]
+    fn test_nyse_holidays_2026() {
+        let expected = vec![
+            date(2026, 1, 1),
+            date(2026, 1, 19),
+            date(2026, 2, 16),
+            date(2026, 4, 3),
+            date(2026, 5, 25),
+            date(2026, 6, 19),
+            date(2026, 7, 3),
+            date(2026, 9, 7),
+            date(2026, 11, 26),
+            date(2026, 12, 25),
+        ];
+        assert_eq!(nyse_holidays(2026), expected);
+        // 2022-01-01 was a Saturday: no observed closure.
+        assert!(!nyse_holidays(2022).contains(&date(2021, 12, 31)));
+        assert_eq!(nyse_holidays(2022)[0], date(2022, 1, 17));
+    }
+
+    #[test]
+    fn test_vn_sessions_lunch_and_tet() {
+        let hose = Exchange::Hose;
+        // Friday 2026-10-16: 09:30 ICT open, 12:00 ICT lunch, 14:59 ICT open, 15:00 closed.
+        assert!(hose.is_open(utc(2026, 10, 16, 2, 30)));
+        assert!(!hose.is_open(utc(2026, 10, 16, 5, 0)));
+        assert!(hose.is_open(utc(2026, 10, 16, 7, 59)));
+        assert!(!hose.is_open(utc(2026, 10, 16, 8, 0)));
+        // Tet 2026 and a Saturday.
+        assert!(!hose.is_open(utc(2026, 2, 17, 3, 0)));
+        assert!(!hose.is_open(utc(2026, 10, 17, 3, 0)));
+        assert_eq!(hose.bucket_offset_hours(), 2);
+    }
+
+    #[test]
+    fn test_vn_holidays_known_by_year() {
+        assert!(check_vn_holidays(date(2026, 10, 18)).is_ok());
+        // An unknown year is logged, not fatal
+        assert!(check_vn_holidays(date(2099, 1, 5)).is_ok());
+        assert!(Exchange::Hose.holidays_known(2026));
+        assert!(!Exchange::Upcom.holidays_known(2099));
+        assert!(Exchange::Crypto.holidays_known(2099));
+
+        let (dates, malformed) = crate::constants::calendar::parse_holidays(" 2027-02-05, 2027-2-30 ,,2027-13-01");
+        assert_eq!(dates, vec![date(2027, 2, 5)]);
+        assert_eq!(malformed, vec!["2027-2-30".to_string(), "2027-13-01".to_string()]);
+    }
+
+    #[test]
+    fn test_expected_bars_skip_lunch_and_weekend() {
+        // Friday 2026-10-16 through Sunday: five VN hourly bars, none at the weekend.
+        let bars = Exchange::Hose.expected_bars(Interval::Hourly, utc(2026, 10, 16, 0, 0), utc(2026, 10, 19, 0, 0));
+        let hours: Vec<u32> = bars.iter().map(|t| t.hour()).collect();
+        assert_eq!(hours, vec![2, 3, 4, 6, 7]);
+
+        let minutes = Exchange::Hose.expected_bars(Interval::Minute, utc(2026, 10, 16, 0, 0), utc(2026, 10, 17, 0, 0));
+        assert_eq!(minutes.len(), 150 + 120);
+        assert_eq!(minutes[0], utc(2026, 10, 16, 2, 0));
+
+        let crypto = Exchange::Crypto.expected_bars(Interval::Hourly, utc(2026, 10, 17, 0, 0), utc(2026, 10, 18, 0, 0));
+        assert_eq!(crypto.len(), 24);
+    }
+
+    #[test]
+    fn test_nyse_session_follows_dst() {
+        let nyse = Exchange::Nyse;
+        // 09:30 ET is 13:30 UTC in summer and 14:30 UTC in winter.
+        assert!(nyse.is_open(utc(2026, 7, 15, 13, 30)));
+        assert!(!nyse.is_open(utc(2026, 12, 15, 13, 30)));
+        assert!(nyse.is_open(utc(2026, 12, 15, 14, 30)));
+        assert!(!nyse.is_open(utc(2026, 11, 26, 16, 0)));
+        assert!(Exchange::Crypto.is_open(utc(2026, 1, 1, 0, 0)));
+    }
+}
@@ -0,0 +1,304 @@
+use std::collections::HashMap;
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::models::lifecycle::Segment;
+use crate::models::ohlcv::OhlcvRow;
+use crate::providers::ohlcv::OhlcvData;
+
+/// Kind of corporate action. Stock dividends and bonus shares change the share
+/// count the same way a split does, so they are stored as `Split`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ActionType {
+    Split,
+    Dividend,
+}
+
+impl ActionType {
+    /// The exact string stored in `corporate_actions.action_type`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Split => "split",
+            Self::Dividend => "dividend",
+        }
+    }
+
+    pub fn from_db(s: &str) -> Option<Self> {
+        match s {
+            "split" => Some(Self::Split),
+            "dividend" => Some(Self::Dividend),
+            _ => None,
+        }
+    }
+}
+
+/// Price series requested via `/tickers?adjust=`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Adjustment {
+    /// Prices as stored.
+    #[default]
+    Raw,
+    /// Back-adjusted for splits / stock dividends only.
+    Split,
+    /// Back-adjusted for splits and cash dividends.
+    Total,
+}
+
+impl Adjustment {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Raw => "raw",
+            Self::Split => "split",
+            Self::Total => "total",
+        }
+    }
+
+    fn includes(&self, action: ActionType) -> bool {
+        match self {
+            Self::Raw => false,
+            Self::Split => action == ActionType::Split,
+            Self::Total => true,
+        }
+    }
+}
+
+/// One recorded action. `ratio` is the price factor across the ex-date:
+/// raw close before the ex-date divided by the vendor's adjusted close (> 1).
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CorporateAction {
+    pub ex_date: NaiveDate,
+    pub action_type: ActionType,
+    pub ratio: f64,
+    /// Cash per share in raw price units, for `Dividend` only.
+    pub cash_amount: Option<f64>,
+    pub source: String,
+}
+
+/// Cumulative factor to divide a raw price on `date` by.
+pub fn adjustment_factor(date: NaiveDate, actions: &[CorporateAction], mode: Adjustment) -> f64 {
+    actions
+        .iter()
+        .filter(|a| date < a.ex_date && mode.includes(a.action_type))
+        .map(|a| a.ratio)
+        .product()
+}
+
+/// Back-adjust stored rows in place. Volumes are left untouched.
+pub fn adjust_rows(rows: &mut [OhlcvRow], actions: &[CorporateAction], mode: Adjustment) {
+    if mode == Adjustment::Raw || actions.is_empty() {
+        return;
+    }
+    for row in rows {
+        let f = adjustment_factor(row.time.date_naive(), actions, mode);
+        if f != 1.0 {
+            row.open /= f;
+            row.high /= f;
+            row.low /= f;
+            row.close /= f;
+        }
+    }
+}
+
+/// File the actions of a rename chain (segments oldest first) under its
+/// newest symbol, the key a stitched history is served under. Each symbol
+/// contributes only the actions dated inside its own segment; back-adjusting
+/// the merged history then carries them to every earlier bar, whichever
+/// symbol it traded under.
+pub fn stitch_actions(actions: &mut HashMap<String, Vec<CorporateAction>>, chain: &[Segment]) {
+    let Some(head) = chain.last() else { return };
+    let mut merged: Vec<CorporateAction> = chain
+        .iter()
+        .flat_map(|seg| {
+            actions
+                .remove(&seg.ticker)
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|a| seg.from.is_none_or(|f| a.ex_date >= f) && seg.until.is_none_or(|u| a.ex_date < u))
+        })
+        .collect();
+    if merged.is_empty() {
+        return;
+    }
+    merged.sort_by_key(|a| a.ex_date);
+    actions.insert(head.ticker.clone(), merged);
+}
+
+/// Undo a vendor's back-adjustment for every recorded action, so freshly
+/// fetched bars match the raw prices already stored.
+pub fn restore_raw(data: &mut [OhlcvData], actions: &[CorporateAction]) {
+    for d in data {
+        let f = adjustment_factor(d.time.date_naive(), actions, Adjustment::Total);
+        if f != 1.0 {
+            d.open *= f;
+            d.high *= f;
+            d.low *= f;
+            d.close *= f;
+        }
+    }
+}
+
+/// Build an action from a detected divergence between stored closes
+/// (`existing`, keyed by `%Y-%m-%d`) and freshly fetched, back-adjusted bars.
+///
+/// The ex-date is the first compared bar after the last diverging one (or
+/// `last_date`, the excluded live bar, when every compared bar diverges). The
+/// ratio is the median of the diverging ratios. Ratios at or above
+/// `split_threshold` are recorded as splits, smaller ones as cash dividends
+/// with `cash = prev_close × (1 − 1/ratio)`.
+pub fn derive_action(
+    existing: &HashMap<String, f64>,
+    compare_data: &[OhlcvData],
+    last_date: NaiveDate,
+    divergence_threshold: f64,
+    split_threshold: f64,
+    source: &str,
+) -> Option<CorporateAction> {
+    let mut bars: Vec<&OhlcvData> = compare_data.iter().collect();
+    bars.sort_by_key(|d| d.time);
+
+    let mut ratios = Vec::new();
+    let mut last_diverging: Option<(usize, f64)> = None;
+    for (i, d) in bars.iter().enumerate() {
+        let key = d.time.format("%Y-%m-%d").to_string();
+        if let Some(&existing_close) = existing.get(&key)
+            && existing_close > 0.0
+            && d.close > 0.0
+        {
+            let ratio = existing_close / d.close;
+            if ratio > divergence_threshold {
+                ratios.push(ratio);
+                last_diverging = Some((i, existing_close));
+            }
+        }
+    }
+
+    let (idx, prev_close) = last_diverging?;
+    let ex_date = bars.get(idx + 1).map(|d| d.time.date_naive()).unwrap_or(last_date);
+
+    ratios.sort_by(|a, b| a.total_cmp(b));
+    let mid = ratios.len() / 2;
+    let ratio = if ratios.len() % 2 == 0 { (ratios[mid - 1] + ratios[mid]) / 2.0 } else { ratios[mid] };
+
+    let (action_type, cash_amount) = if ratio >= split_threshold {
+        (ActionType::Split, None)
+    } else {
+        (ActionType::Dividend, Some(prev_close * (1.0 - 1.0 / ratio)))
+    };
+
+    Some(CorporateAction { ex_date, action_type, ratio, cash_amount, source: source.to_string() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn bar(day: u32, close: f64) -> OhlcvData {
+        OhlcvData {
+            time: Utc.with_ymd_and_hms(2026, 9, day, 0, 0, 0).unwrap(),
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume: 100,
+            symbol: None,
+        }
+    }
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 9, day).unwrap()
+    }
+
+    fn action(day: u32, action_type: ActionType, ratio: f64) -> CorporateAction {
+        CorporateAction { ex_date: date(day), action_type, ratio, cash_amount: None, source: "vci".into() }
+    }
+
+    #[test]
+    fn test_adjustment_factor_by_mode() {
+        let actions = vec![action(10, ActionType::Split, 2.0), action(20, ActionType::Dividend, 1.05)];
+        assert_eq!(adjustment_factor(date(5), &actions, Adjustment::Raw), 1.0);
+        assert_eq!(adjustment_factor(date(5), &actions, Adjustment::Split), 2.0);
+        assert!((adjustment_factor(date(5), &actions, Adjustment::Total) - 2.1).abs() < 1e-9);
+        assert!((adjustment_factor(date(15), &actions, Adjustment::Total) - 1.05).abs() < 1e-9);
+        assert_eq!(adjustment_factor(date(20), &actions, Adjustment::Total), 1.0);
+    }
+
+    #[test]
+    fn test_stitch_actions_keeps_each_segment_own_actions() {
+        let seg = |ticker: &str, from: Option<u32>, until: Option<u32>| Segment {
+            ticker: ticker.into(),
+            from: from.map(date),
+            until: until.map(date),
+        };
+        // OLD renamed to NEW on the 15th
+        let chain = vec![seg("OLD", None, Some(15)), seg("NEW", Some(15), None)];
+        let mut actions: HashMap<String, Vec<CorporateAction>> = [
+            ("OLD".to_string(), vec![action(10, ActionType::Split, 2.0), action(20, ActionType::Split, 9.0)]),
+            ("NEW".to_string(), vec![action(25, ActionType::Dividend, 1.05), action(12, ActionType::Split, 7.0)]),
+        ]
+        .into();
+        stitch_actions(&mut actions, &chain);
+        assert!(!actions.contains_key("OLD"));
+        let ex_dates: Vec<NaiveDate> = actions["NEW"].iter().map(|a| a.ex_date).collect();
+        assert_eq!(ex_dates, vec![date(10), date(25)]);
+
+        let mut rows: Vec<OhlcvRow> = [5, 12, 30]
+            .iter()
+            .map(|&d| OhlcvRow {
+                ticker_id: 1,
+                interval: "1D".into(),
+                time: Utc.with_ymd_and_hms(2026, 9, d, 0, 0, 0).unwrap(),
+                open: 21.0,
+                high: 21.0,
+                low: 21.0,
+                close: 21.0,
+                volume: 100,
+            })
+            .collect();
+        adjust_rows(&mut rows, &actions["NEW"], Adjustment::Total);
+        let closes: Vec<f64> = rows.iter().map(|r| r.close).collect();
+        assert_eq!(closes, vec![10.0, 20.0, 21.0]);
+    }
+
+    #[test]
+    fn test_restore_raw_undoes_vendor_adjustment() {
+        let actions = vec![action(3, ActionType::Split, 2.0)];
+        let mut data = vec![bar(1, 50.0), bar(2, 50.0), bar(3, 100.0)];
+        restore_raw(&mut data, &actions);
+        let closes: Vec<f64> = data.iter().map(|d| d.close).collect();
+        assert_eq!(closes, vec![100.0, 100.0, 100.0]);
+    }
+
+    #[test]
+    fn test_derive_action_split() {
+        // Stored raw closes 100 before the ex-date; vendor now reports 50.
+        let existing: HashMap<String, f64> = (1..=4)
+            .map(|d| (format!("2026-09-{d:02}"), if d < 4 { 100.0 } else { 51.0 }))
+            .collect();
+        let api = vec![bar(1, 50.0), bar(2, 50.0), bar(3, 50.0), bar(4, 51.0)];
+        let a = derive_action(&existing, &api, date(5), 1.03, 1.08, "vci").unwrap();
+        assert_eq!(a.ex_date, date(4));
+        assert_eq!(a.action_type, ActionType::Split);
+        assert_eq!(a.ratio, 2.0);
+        assert_eq!(a.cash_amount, None);
+    }
+
+    #[test]
+    fn test_derive_action_cash_dividend_uses_last_bar_date() {
+        let existing: HashMap<String, f64> = (1..=3).map(|d| (format!("2026-09-{d:02}"), 105.0)).collect();
+        let api = vec![bar(1, 100.0), bar(2, 100.0), bar(3, 100.0)];
+        let a = derive_action(&existing, &api, date(4), 1.03, 1.08, "vci").unwrap();
+        assert_eq!(a.ex_date, date(4));
+        assert_eq!(a.action_type, ActionType::Dividend);
+        assert!((a.cash_amount.unwrap() - 5.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_derive_action_none_without_divergence() {
+        let existing: HashMap<String, f64> = [("2026-09-01".to_string(), 100.0)].into();
+        assert!(derive_action(&existing, &[bar(1, 100.0)], date(2), 1.03, 1.08, "vci").is_none());
+    }
+}
@@ -0,0 +1,79 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Source under which synthetic index series are stored.
+pub const SOURCE: &str = "custom";
+
+/// How the members of a custom index are weighted. Weights are re-applied
+/// at every previous close, so the index is rebalanced daily.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Weighting {
+    Equal,
+    /// Market cap: outstanding shares × previous close.
+    Cap,
+    /// Fixed relative weights given with the members.
+    Manual,
+}
+
+impl Weighting {
+    /// The exact string stored in `custom_indices.weighting`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Equal => "equal",
+            Self::Cap => "cap",
+            Self::Manual => "manual",
+        }
+    }
+
+    pub fn from_db(s: &str) -> Option<Self> {
+        match s {
+            "equal" => Some(Self::Equal),
+            "cap" => Some(Self::Cap),
+            "manual" => Some(Self::Manual),
+            _ => None,
+        }
+    }
+
+    /// Unnormalised weight of a member with stored `weight` at `prev_close`.
+    pub fn raw_weight(&self, weight: Option<f64>, prev_close: f64) -> f64 {
+        match self {
+            Self::Equal => 1.0,
+            Self::Cap => weight.unwrap_or(0.0) * prev_close,
+            Self::Manual => weight.unwrap_or(0.0),
+        }
+    }
+}
+
+/// One constituent. `weight` is the outstanding share count for `Cap`, the
+/// relative weight for `Manual` and unused for `Equal`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct IndexMember {
+    pub source: String,
+    pub ticker: String,
+    pub weight: Option<f64>,
+}
+
+/// A synthetic index definition, stored as `tickers(source = "custom", name)`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CustomIndex {
+    pub id: i32,
+    pub name: String,
+    pub title: Option<String>,
+    pub weighting: Weighting,
+    pub base_value: f64,
+    /// First day of the series; None = the first day any member traded.
+    pub base_date: Option<NaiveDate>,
+    pub note: Option<String>,
+    pub members: Vec<IndexMember>,
+    pub updated_at: DateTime<Utc>,
+    /// When the worker last refreshed the series; older than `updated_at`
+    /// means the stored bars predate the current definition.
+    pub computed_at: Option<DateTime<Utc>>,
+}
+
+impl CustomIndex {
+    pub fn needs_rebuild(&self) -> bool {
+        self.computed_at.is_none_or(|c| c < self.updated_at)
+    }
+}
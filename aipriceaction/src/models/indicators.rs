@@ -77,3 +77,745 @@ pub fn calculate_ma_score(close: f64, ma: f64) -> f64 {
         ((close - ma) / ma) * 100.0
     }
 }
+
+/// MA periods behind the fixed `ma10`..`ma200` fields, used unless a request
+/// passes `ma_periods=`.
+pub const DEFAULT_MA_PERIODS: [usize; 5] = [10, 20, 50, 100, 200];
+/// Longest period accepted in `ma_periods=`.
+pub const MAX_MA_PERIOD: usize = 500;
+/// Most periods accepted in one `ma_periods=`.
+pub const MAX_MA_PERIODS: usize = 10;
+
+/// Parse `ma_periods=5,13,34,89` into sorted, deduplicated periods.
+/// Missing or blank means [`DEFAULT_MA_PERIODS`].
+pub fn parse_ma_periods(raw: Option<&str>) -> Result<Vec<usize>, String> {
+    let raw = raw.unwrap_or("").trim();
+    if raw.is_empty() {
+        return Ok(DEFAULT_MA_PERIODS.to_vec());
+    }
+    let mut periods = Vec::new();
+    for token in raw.split(',').map(str::trim).filter(|t| !t.is_empty()) {
+        match token.parse::<usize>() {
+            Ok(p) if (1..=MAX_MA_PERIOD).contains(&p) => periods.push(p),
+            _ => return Err(format!("Invalid MA period '{token}'. Must be between 1 and {MAX_MA_PERIOD}")),
+        }
+    }
+    periods.sort_unstable();
+    periods.dedup();
+    if periods.len() > MAX_MA_PERIODS {
+        return Err(format!("At most {MAX_MA_PERIODS} MA periods per request"));
+    }
+    Ok(periods)
+}
+
+/// History rows needed before the first returned bar for `periods`: the
+/// longest period for SMA, three times it for EMA (see `EMA_LOOKBACK`).
+pub fn ma_lookback(periods: &[usize], use_ema: bool) -> i64 {
+    let max_period = periods.iter().copied().max().unwrap_or(0);
+    if use_ema {
+        crate::constants::api::ema_buffer_for(max_period)
+    } else {
+        crate::constants::api::sma_buffer_for(max_period)
+    }
+}
+
+/// Whether an extra row field (`ma{p}` or an `indicators=` output) is a price,
+/// written with the row's price decimals like OHLC (CSV output).
+pub fn is_price_field(field: &str) -> bool {
+    if let Some(period) = field.strip_prefix("ma")
+        && !period.is_empty()
+        && period.bytes().all(|b| b.is_ascii_digit())
+    {
+        return true;
+    }
+    Indicator::for_field(field).is_some_and(|ind| ind.is_price())
+}
+
+/// MA series for each of `periods`, as `(period, values)`. Values follow
+/// [`calculate_sma`] / [`calculate_ema`] (0.0 where unavailable).
+pub fn calculate_mas(closes: &[f64], periods: &[usize], use_ema: bool) -> Vec<(usize, Vec<f64>)> {
+    let calc_ma = if use_ema { calculate_ema } else { calculate_sma };
+    periods.iter().map(|&p| (p, calc_ma(closes, p))).collect()
+}
+
+// ── Technical indicators ──
+//
+// Unlike the MA helpers above, these return `None` during warm-up instead of
+// 0.0, since 0 is a meaningful value for RSI, MACD, OBV and friends. All
+// inputs are chronological (oldest first) and outputs have the same length.
+
+/// Relative Strength Index with Wilder smoothing. First value at `period`.
+pub fn calculate_rsi(closes: &[f64], period: usize) -> Vec<Option<f64>> {
+    let mut out = vec![None; closes.len()];
+    if period == 0 || closes.len() <= period {
+        return out;
+    }
+
+    let rsi = |gain: f64, loss: f64| {
+        if loss == 0.0 {
+            if gain == 0.0 { 50.0 } else { 100.0 }
+        } else {
+            100.0 - 100.0 / (1.0 + gain / loss)
+        }
+    };
+
+    let (mut gain, mut loss) = (0.0, 0.0);
+    for i in 1..=period {
+        let change = closes[i] - closes[i - 1];
+        gain += change.max(0.0);
+        loss += (-change).max(0.0);
+    }
+    gain /= period as f64;
+    loss /= period as f64;
+    out[period] = Some(rsi(gain, loss));
+
+    let p = period as f64;
+    for i in (period + 1)..closes.len() {
+        let change = closes[i] - closes[i - 1];
+        gain = (gain * (p - 1.0) + change.max(0.0)) / p;
+        loss = (loss * (p - 1.0) + (-change).max(0.0)) / p;
+        out[i] = Some(rsi(gain, loss));
+    }
+    out
+}
+
+/// MACD line, signal line and histogram.
+pub struct Macd {
+    pub macd: Vec<Option<f64>>,
+    pub signal: Vec<Option<f64>>,
+    pub histogram: Vec<Option<f64>>,
+}
+
+/// MACD = EMA(fast) - EMA(slow); signal = EMA(signal) of MACD.
+/// MACD starts at `slow - 1`, signal and histogram `signal - 1` bars later.
+pub fn calculate_macd(closes: &[f64], fast: usize, slow: usize, signal: usize) -> Macd {
+    let n = closes.len();
+    let mut out = Macd { macd: vec![None; n], signal: vec![None; n], histogram: vec![None; n] };
+    if fast == 0 || signal == 0 || slow <= fast || n < slow {
+        return out;
+    }
+
+    let ema_fast = calculate_ema(closes, fast);
+    let ema_slow = calculate_ema(closes, slow);
+    let line: Vec<f64> = (slow - 1..n).map(|i| ema_fast[i] - ema_slow[i]).collect();
+    for (j, v) in line.iter().enumerate() {
+        out.macd[slow - 1 + j] = Some(*v);
+    }
+
+    if line.len() >= signal {
+        let sig = calculate_ema(&line, signal);
+        for j in (signal - 1)..line.len() {
+            let i = slow - 1 + j;
+            out.signal[i] = Some(sig[j]);
+            out.histogram[i] = Some(line[j] - sig[j]);
+        }
+    }
+    out
+}
+
+/// Upper / middle / lower band values.
+pub struct Bands {
+    pub upper: Vec<Option<f64>>,
+    pub middle: Vec<Option<f64>>,
+    pub lower: Vec<Option<f64>>,
+}
+
+/// Bollinger Bands: SMA(period) ± `width` population standard deviations.
+pub fn calculate_bollinger(closes: &[f64], period: usize, width: f64) -> Bands {
+    let n = closes.len();
+    let mut out = Bands { upper: vec![None; n], middle: vec![None; n], lower: vec![None; n] };
+    if period == 0 {
+        return out;
+    }
+    for i in (period - 1)..n {
+        let window = &closes[i + 1 - period..=i];
+        let mean = window.iter().sum::<f64>() / period as f64;
+        let var = window.iter().map(|c| (c - mean).powi(2)).sum::<f64>() / period as f64;
+        let sd = var.sqrt();
+        out.upper[i] = Some(mean + width * sd);
+        out.middle[i] = Some(mean);
+        out.lower[i] = Some(mean - width * sd);
+    }
+    out
+}
+
+/// True range per bar; the first bar has no previous close and uses high - low.
+fn true_ranges(highs: &[f64], lows: &[f64], closes: &[f64]) -> Vec<f64> {
+    (0..closes.len())
+        .map(|i| {
+            let range = highs[i] - lows[i];
+            if i == 0 {
+                range
+            } else {
+                range
+                    .max((highs[i] - closes[i - 1]).abs())
+                    .max((lows[i] - closes[i - 1]).abs())
+            }
+        })
+        .collect()
+}
+
+/// Average True Range with Wilder smoothing. First value at `period - 1`.
+pub fn calculate_atr(highs: &[f64], lows: &[f64], closes: &[f64], period: usize) -> Vec<Option<f64>> {
+    let mut out = vec![None; closes.len()];
+    if period == 0 || closes.len() < period {
+        return out;
+    }
+    let tr = true_ranges(highs, lows, closes);
+    let p = period as f64;
+    let mut atr = tr[..period].iter().sum::<f64>() / p;
+    out[period - 1] = Some(atr);
+    for i in period..tr.len() {
+        atr = (atr * (p - 1.0) + tr[i]) / p;
+        out[i] = Some(atr);
+    }
+    out
+}
+
+/// Stochastic oscillator %K and its %D signal.
+pub struct Stochastic {
+    pub k: Vec<Option<f64>>,
+    pub d: Vec<Option<f64>>,
+}
+
+/// %K = 100 × (close − lowest low) / (highest high − lowest low) over
+/// `k_period` bars; %D = SMA(`d_period`) of %K. A flat range gives 50.
+pub fn calculate_stochastic(
+    highs: &[f64],
+    lows: &[f64],
+    closes: &[f64],
+    k_period: usize,
+    d_period: usize,
+) -> Stochastic {
+    let n = closes.len();
+    let mut out = Stochastic { k: vec![None; n], d: vec![None; n] };
+    if k_period == 0 || d_period == 0 {
+        return out;
+    }
+    for (i, close) in closes.iter().enumerate().skip(k_period - 1) {
+        let (hh, ll) = high_low(highs, lows, i, k_period);
+        out.k[i] = Some(if hh > ll { 100.0 * (close - ll) / (hh - ll) } else { 50.0 });
+    }
+    for i in (k_period + d_period - 2)..n {
+        let sum: f64 = out.k[i + 1 - d_period..=i].iter().flatten().sum();
+        out.d[i] = Some(sum / d_period as f64);
+    }
+    out
+}
+
+/// ADX with its directional indicators.
+pub struct Adx {
+    pub adx: Vec<Option<f64>>,
+    pub plus_di: Vec<Option<f64>>,
+    pub minus_di: Vec<Option<f64>>,
+}
+
+/// Wilder's Average Directional Index. +DI/−DI start at `period`, ADX at
+/// `2 × period - 1` (the mean of the first `period` DX values).
+pub fn calculate_adx(highs: &[f64], lows: &[f64], closes: &[f64], period: usize) -> Adx {
+    let n = closes.len();
+    let mut out = Adx { adx: vec![None; n], plus_di: vec![None; n], minus_di: vec![None; n] };
+    if period == 0 || n <= period {
+        return out;
+    }
+
+    let tr = true_ranges(highs, lows, closes);
+    let mut plus_dm = vec![0.0; n];
+    let mut minus_dm = vec![0.0; n];
+    for i in 1..n {
+        let up = highs[i] - highs[i - 1];
+        let down = lows[i - 1] - lows[i];
+        if up > down && up > 0.0 {
+            plus_dm[i] = up;
+        }
+        if down > up && down > 0.0 {
+            minus_dm[i] = down;
+        }
+    }
+
+    let p = period as f64;
+    let mut s_tr: f64 = tr[1..=period].iter().sum();
+    let mut s_plus: f64 = plus_dm[1..=period].iter().sum();
+    let mut s_minus: f64 = minus_dm[1..=period].iter().sum();
+    let mut dx_sum = 0.0;
+    let mut adx: Option<f64> = None;
+
+    for i in period..n {
+        if i > period {
+            s_tr = s_tr - s_tr / p + tr[i];
+            s_plus = s_plus - s_plus / p + plus_dm[i];
+            s_minus = s_minus - s_minus / p + minus_dm[i];
+        }
+        let (pdi, mdi) = if s_tr > 0.0 { (100.0 * s_plus / s_tr, 100.0 * s_minus / s_tr) } else { (0.0, 0.0) };
+        out.plus_di[i] = Some(pdi);
+        out.minus_di[i] = Some(mdi);
+
+        let dx = if pdi + mdi > 0.0 { 100.0 * (pdi - mdi).abs() / (pdi + mdi) } else { 0.0 };
+        let count = i - period + 1;
+        adx = match adx {
+            Some(prev) => Some((prev * (p - 1.0) + dx) / p),
+            None if count == period => Some((dx_sum + dx) / p),
+            None => {
+                dx_sum += dx;
+                None
+            }
+        };
+        out.adx[i] = adx;
+    }
+    out
+}
+
+/// On-Balance Volume, starting from 0 at the first bar.
+pub fn calculate_obv(closes: &[f64], volumes: &[f64]) -> Vec<Option<f64>> {
+    let mut obv = 0.0;
+    (0..closes.len())
+        .map(|i| {
+            if i > 0 {
+                if closes[i] > closes[i - 1] {
+                    obv += volumes[i];
+                } else if closes[i] < closes[i - 1] {
+                    obv -= volumes[i];
+                }
+            }
+            Some(obv)
+        })
+        .collect()
+}
+
+/// Rolling VWAP over `period` bars using the typical price (H+L+C)/3.
+/// Windows with no volume fall back to the mean typical price.
+pub fn calculate_vwap(
+    highs: &[f64],
+    lows: &[f64],
+    closes: &[f64],
+    volumes: &[f64],
+    period: usize,
+) -> Vec<Option<f64>> {
+    let mut out = vec![None; closes.len()];
+    if period == 0 {
+        return out;
+    }
+    let typical: Vec<f64> = (0..closes.len()).map(|i| (highs[i] + lows[i] + closes[i]) / 3.0).collect();
+    for (i, slot) in out.iter_mut().enumerate().skip(period - 1) {
+        let range = i + 1 - period..=i;
+        let vol: f64 = volumes[range.clone()].iter().sum();
+        *slot = Some(if vol > 0.0 {
+            range.map(|j| typical[j] * volumes[j]).sum::<f64>() / vol
+        } else {
+            typical[range].iter().sum::<f64>() / period as f64
+        });
+    }
+    out
+}
+
+/// Ichimoku Kinko Hyo lines, aligned to the bar they are plotted against.
+pub struct Ichimoku {
+    pub tenkan: Vec<Option<f64>>,
+    pub kijun: Vec<Option<f64>>,
+    /// Leading span A, computed `displacement` bars earlier.
+    pub senkou_a: Vec<Option<f64>>,
+    /// Leading span B, computed `displacement` bars earlier.
+    pub senkou_b: Vec<Option<f64>>,
+    /// Close `displacement` bars later (None for the newest bars).
+    pub chikou: Vec<Option<f64>>,
+}
+
+/// Ichimoku with the usual (tenkan, kijun, senkou B) = (9, 26, 52) periods;
+/// the displacement equals the kijun period.
+pub fn calculate_ichimoku(
+    highs: &[f64],
+    lows: &[f64],
+    closes: &[f64],
+    tenkan: usize,
+    kijun: usize,
+    senkou_b: usize,
+) -> Ichimoku {
+    let n = closes.len();
+    let midpoint = |i: usize, period: usize| -> Option<f64> {
+        (period > 0 && i + 1 >= period).then(|| {
+            let (hh, ll) = high_low(highs, lows, i, period);
+            (hh + ll) / 2.0
+        })
+    };
+
+    let tenkan_line: Vec<Option<f64>> = (0..n).map(|i| midpoint(i, tenkan)).collect();
+    let kijun_line: Vec<Option<f64>> = (0..n).map(|i| midpoint(i, kijun)).collect();
+    let shift = kijun;
+
+    let senkou_a = (0..n)
+        .map(|i| {
+            let j = i.checked_sub(shift)?;
+            Some((tenkan_line[j]? + kijun_line[j]?) / 2.0)
+        })
+        .collect();
+    let senkou_b_line = (0..n).map(|i| midpoint(i.checked_sub(shift)?, senkou_b)).collect();
+    let chikou = (0..n).map(|i| closes.get(i + shift).copied()).collect();
+
+    Ichimoku { tenkan: tenkan_line, kijun: kijun_line, senkou_a, senkou_b: senkou_b_line, chikou }
+}
+
+/// Highest high and lowest low of the `period` bars ending at `i`.
+fn high_low(highs: &[f64], lows: &[f64], i: usize, period: usize) -> (f64, f64) {
+    let start = i + 1 - period;
+    let hh = highs[start..=i].iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let ll = lows[start..=i].iter().copied().fold(f64::INFINITY, f64::min);
+    (hh, ll)
+}
+
+// ── Indicator selection (`indicators=` query parameter) ──
+
+/// Longest period accepted in an `indicators=` token.
+pub const MAX_INDICATOR_PERIOD: usize = 250;
+/// Most indicators accepted in one request.
+pub const MAX_INDICATORS: usize = 16;
+
+/// One indicator requested via `indicators=`, e.g. `rsi14`, `macd`,
+/// `macd_5_35_5`, `bb20`, `atr14`, `stoch14`, `adx14`, `obv`, `vwap20`,
+/// `ichimoku`. The period suffix is optional and falls back to the usual default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Indicator {
+    Rsi(usize),
+    Macd { fast: usize, slow: usize, signal: usize },
+    Bollinger(usize),
+    Atr(usize),
+    Stochastic(usize),
+    Adx(usize),
+    Obv,
+    Vwap(usize),
+    Ichimoku,
+}
+
+impl Indicator {
+    /// Parse a comma-separated list; duplicates are dropped, order is kept.
+    pub fn parse_list(raw: &str) -> Result<Vec<Self>, String> {
+        let mut out: Vec<Self> = Vec::new();
+        for token in raw.split(',').map(str::trim).filter(|t| !t.is_empty()) {
+            let ind = Self::parse(token)?;
+            if !out.contains(&ind) {
+                out.push(ind);
+            }
+        }
+        if out.len() > MAX_INDICATORS {
+            return Err(format!("At most {MAX_INDICATORS} indicators per request"));
+        }
+        Ok(out)
+    }
+
+    pub fn parse(token: &str) -> Result<Self, String> {
+        let lower = token.to_ascii_lowercase();
+        let split = lower.find(|c: char| c.is_ascii_digit() || c == '_').unwrap_or(lower.len());
+        let (name, args) = lower.split_at(split);
+        let nums: Vec<usize> = args
+            .split('_')
+            .filter(|s| !s.is_empty())
+            .map(|s| s.parse::<usize>().map_err(|_| format!("Invalid indicator '{token}'")))
+            .collect::<Result<_, _>>()?;
+        if nums.iter().any(|&p| p == 0 || p > MAX_INDICATOR_PERIOD) {
+            return Err(format!("Indicator periods must be between 1 and {MAX_INDICATOR_PERIOD} ('{token}')"));
+        }
+
+        let period = |default: usize| match nums.as_slice() {
+            [] => Ok(default),
+            [p] => Ok(*p),
+            _ => Err(format!("Invalid indicator '{token}'")),
+        };
+        let no_args = |ind: Self| if nums.is_empty() { Ok(ind) } else { Err(format!("Invalid indicator '{token}'")) };
+
+        match name {
+            "rsi" => Ok(Self::Rsi(period(14)?)),
+            "macd" => match nums.as_slice() {
+                [] => Ok(Self::Macd { fast: 12, slow: 26, signal: 9 }),
+                [fast, slow, signal] if fast < slow => Ok(Self::Macd { fast: *fast, slow: *slow, signal: *signal }),
+                _ => Err(format!("Invalid indicator '{token}' (use macd or macd_<fast>_<slow>_<signal>)")),
+            },
+            "bb" => Ok(Self::Bollinger(period(20)?)),
+            "atr" => Ok(Self::Atr(period(14)?)),
+            "stoch" => Ok(Self::Stochastic(period(14)?)),
+            "adx" => Ok(Self::Adx(period(14)?)),
+            "obv" => no_args(Self::Obv),
+            "vwap" => Ok(Self::Vwap(period(20)?)),
+            "ichimoku" => no_args(Self::Ichimoku),
+            _ => Err(format!(
+                "Unknown indicator '{token}'. Supported: rsi, macd, bb, atr, stoch, adx, obv, vwap, ichimoku"
+            )),
+        }
+    }
+
+    /// Canonical token, also the prefix of every output field.
+    pub fn name(&self) -> String {
+        match *self {
+            Self::Rsi(p) => format!("rsi{p}"),
+            Self::Macd { fast: 12, slow: 26, signal: 9 } => "macd".to_string(),
+            Self::Macd { fast, slow, signal } => format!("macd_{fast}_{slow}_{signal}"),
+            Self::Bollinger(p) => format!("bb{p}"),
+            Self::Atr(p) => format!("atr{p}"),
+            Self::Stochastic(p) => format!("stoch{p}"),
+            Self::Adx(p) => format!("adx{p}"),
+            Self::Obv => "obv".to_string(),
+            Self::Vwap(p) => format!("vwap{p}"),
+            Self::Ichimoku => "ichimoku".to_string(),
+        }
+    }
+
+    /// Whether the outputs are in price units (as opposed to oscillators,
+    /// percentages and volume).
+    pub fn is_price(&self) -> bool {
+        matches!(self, Self::Macd { .. } | Self::Bollinger(_) | Self::Atr(_) | Self::Vwap(_) | Self::Ichimoku)
+    }
+
+    /// Indicator whose output includes `field`, e.g. `bb20_upper` → `bb20`.
+    pub fn for_field(field: &str) -> Option<Self> {
+        let field = field.to_ascii_lowercase();
+        std::iter::once(field.len())
+            .chain(field.rmatch_indices('_').map(|(i, _)| i))
+            .filter_map(|end| Self::parse(&field[..end]).ok())
+            .find(|ind| ind.compute(&PriceSeries::default()).iter().any(|(name, _)| *name == field))
+    }
+
+    /// Bars needed before the first returned bar for a settled value.
+    /// Wilder/EMA smoothing gets three periods to converge, like `EMA_LOOKBACK`.
+    pub fn lookback(&self) -> i64 {
+        let bars = match *self {
+            Self::Rsi(p) | Self::Atr(p) => 3 * p,
+            Self::Adx(p) => 4 * p,
+            Self::Macd { slow, signal, .. } => 3 * slow + signal,
+            Self::Bollinger(p) | Self::Vwap(p) => p,
+            Self::Stochastic(p) => p + STOCH_D_PERIOD,
+            // OBV is cumulative; its level depends on the window, its slope does not.
+            Self::Obv => 1,
+            Self::Ichimoku => 52 + 26,
+        };
+        bars as i64
+    }
+
+    /// Output series as `(field name, values)`, aligned with the input bars.
+    pub fn compute(&self, s: &PriceSeries) -> Vec<(String, Vec<Option<f64>>)> {
+        let name = self.name();
+        let (h, l, c, v) = (&s.high[..], &s.low[..], &s.close[..], &s.volume[..]);
+        match *self {
+            Self::Rsi(p) => vec![(name, calculate_rsi(c, p))],
+            Self::Macd { fast, slow, signal } => {
+                let m = calculate_macd(c, fast, slow, signal);
+                vec![
+                    (format!("{name}_signal"), m.signal),
+                    (format!("{name}_hist"), m.histogram),
+                    (name, m.macd),
+                ]
+            }
+            Self::Bollinger(p) => {
+                let b = calculate_bollinger(c, p, 2.0);
+                vec![
+                    (format!("{name}_upper"), b.upper),
+                    (format!("{name}_middle"), b.middle),
+                    (format!("{name}_lower"), b.lower),
+                ]
+            }
+            Self::Atr(p) => vec![(name, calculate_atr(h, l, c, p))],
+            Self::Stochastic(p) => {
+                let st = calculate_stochastic(h, l, c, p, STOCH_D_PERIOD);
+                vec![(format!("{name}_k"), st.k), (format!("{name}_d"), st.d)]
+            }
+            Self::Adx(p) => {
+                let a = calculate_adx(h, l, c, p);
+                vec![
+                    (format!("{name}_plus_di"), a.plus_di),
+                    (format!("{name}_minus_di"), a.minus_di),
+                    (name, a.adx),
+                ]
+            }
+            Self::Obv => vec![(name, calculate_obv(c, v))],
+            Self::Vwap(p) => vec![(name, calculate_vwap(h, l, c, v, p))],
+            Self::Ichimoku => {
+                let ich = calculate_ichimoku(h, l, c, 9, 26, 52);
+                vec![
+                    (format!("{name}_tenkan"), ich.tenkan),
+                    (format!("{name}_kijun"), ich.kijun),
+                    (format!("{name}_senkou_a"), ich.senkou_a),
+                    (format!("{name}_senkou_b"), ich.senkou_b),
+                    (format!("{name}_chikou"), ich.chikou),
+                ]
+            }
+        }
+    }
+}
+
+/// %D smoothing for `stochN`.
+const STOCH_D_PERIOD: usize = 3;
+
+/// Column-wise bars, oldest first, as consumed by [`Indicator::compute`].
+#[derive(Default)]
+pub struct PriceSeries {
+    pub high: Vec<f64>,
+    pub low: Vec<f64>,
+    pub close: Vec<f64>,
+    pub volume: Vec<f64>,
+}
+
+impl PriceSeries {
+    pub fn push(&mut self, high: f64, low: f64, close: f64, volume: f64) {
+        self.high.push(high);
+        self.low.push(low);
+        self.close.push(close);
+        self.volume.push(volume);
+    }
+}
+
+/// Largest [`Indicator::lookback`] of `indicators` (0 when none).
+pub fn indicators_lookback(indicators: &[Indicator]) -> i64 {
+    indicators.iter().map(Indicator::lookback).max().unwrap_or(0)
+}
+
+/// Per-bar field maps for `indicators` over `series`. Warm-up and non-finite
+/// values are left out.
+pub fn compute_indicators(
+    indicators: &[Indicator],
+    series: &PriceSeries,
+) -> Vec<std::collections::BTreeMap<String, f64>> {
+    let mut out = vec![std::collections::BTreeMap::new(); series.close.len()];
+    for indicator in indicators {
+        for (field, values) in indicator.compute(series) {
+            for (bar, value) in out.iter_mut().zip(values) {
+                if let Some(v) = value.filter(|v| v.is_finite()) {
+                    bar.insert(field.clone(), v);
+                }
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn approx(a: Option<f64>, b: f64) -> bool {
+        a.is_some_and(|a| (a - b).abs() < 1e-6)
+    }
+
+    #[test]
+    fn test_rsi_extremes_and_warmup() {
+        let rising: Vec<f64> = (1..=20).map(f64::from).collect();
+        let rsi = calculate_rsi(&rising, 14);
+        assert!(rsi[..14].iter().all(Option::is_none));
+        assert!(approx(rsi[14], 100.0));
+
+        let zigzag: Vec<f64> = (0..30).map(|i| if i % 2 == 0 { 10.0 } else { 11.0 }).collect();
+        let rsi = calculate_rsi(&zigzag, 14);
+        assert!(rsi[29].is_some_and(|v| (v - 50.0).abs() < 5.0));
+    }
+
+    #[test]
+    fn test_is_price_field() {
+        for field in ["ma13", "bb20_upper", "atr14", "macd_signal", "vwap20", "ichimoku_kijun"] {
+            assert!(is_price_field(field), "{field}");
+        }
+        for field in ["ma13_score", "rsi14", "stoch14_k", "adx14_plus_di", "obv", "ma"] {
+            assert!(!is_price_field(field), "{field}");
+        }
+    }
+
+    #[test]
+    fn test_macd_constant_series_is_zero() {
+        let closes = vec![100.0; 40];
+        let m = calculate_macd(&closes, 12, 26, 9);
+        assert!(m.macd[24].is_none());
+        assert!(approx(m.macd[25], 0.0));
+        assert!(m.signal[32].is_none());
+        assert!(approx(m.signal[33], 0.0));
+        assert!(approx(m.histogram[39], 0.0));
+    }
+
+    #[test]
+    fn test_bollinger_and_atr() {
+        let closes = [1.0, 2.0, 3.0, 4.0, 5.0];
+        let b = calculate_bollinger(&closes, 5, 2.0);
+        assert!(b.middle[3].is_none());
+        assert!(approx(b.middle[4], 3.0));
+        assert!(approx(b.upper[4], 3.0 + 2.0 * 2f64.sqrt()));
+
+        let highs = [11.0, 12.0, 13.0];
+        let lows = [9.0, 10.0, 11.0];
+        let closes = [10.0, 11.0, 12.0];
+        let atr = calculate_atr(&highs, &lows, &closes, 2);
+        assert!(atr[0].is_none());
+        assert!(approx(atr[1], 2.0));
+        assert!(approx(atr[2], 2.0));
+    }
+
+    #[test]
+    fn test_stochastic_obv_vwap() {
+        let highs = [10.0, 12.0, 14.0, 16.0];
+        let lows = [8.0, 9.0, 10.0, 11.0];
+        let closes = [9.0, 11.0, 13.0, 12.0];
+        let st = calculate_stochastic(&highs, &lows, &closes, 3, 2);
+        assert!(approx(st.k[2], 100.0 * (13.0 - 8.0) / (14.0 - 8.0)));
+        assert!(approx(st.k[3], 100.0 * (12.0 - 9.0) / (16.0 - 9.0)));
+        assert!(approx(st.d[3], (st.k[2].unwrap() + st.k[3].unwrap()) / 2.0));
+
+        let obv = calculate_obv(&closes, &[1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(obv, vec![Some(0.0), Some(2.0), Some(5.0), Some(1.0)]);
+
+        let vwap = calculate_vwap(&[3.0, 6.0], &[3.0, 6.0], &[3.0, 6.0], &[1.0, 2.0], 2);
+        assert!(approx(vwap[1], 5.0));
+    }
+
+    #[test]
+    fn test_adx_trending_series() {
+        let highs: Vec<f64> = (0..40).map(|i| 11.0 + i as f64).collect();
+        let lows: Vec<f64> = (0..40).map(|i| 9.0 + i as f64).collect();
+        let closes: Vec<f64> = (0..40).map(|i| 10.0 + i as f64).collect();
+        let a = calculate_adx(&highs, &lows, &closes, 14);
+        assert!(a.adx[26].is_none());
+        assert!(approx(a.adx[27], 100.0));
+        assert!(approx(a.minus_di[39], 0.0));
+    }
+
+    #[test]
+    fn test_ichimoku_displacement() {
+        let highs: Vec<f64> = (0..80).map(|i| 2.0 + i as f64).collect();
+        let lows: Vec<f64> = (0..80).map(|i| i as f64).collect();
+        let closes: Vec<f64> = (0..80).map(|i| 1.0 + i as f64).collect();
+        let ich = calculate_ichimoku(&highs, &lows, &closes, 9, 26, 52);
+        // tenkan at 8: (max high 10 + min low 0) / 2
+        assert!(approx(ich.tenkan[8], 5.0));
+        assert!(ich.senkou_b[76].is_none());
+        assert!(approx(ich.senkou_b[77], (53.0 + 0.0) / 2.0));
+        assert_eq!(ich.chikou[0], Some(27.0));
+        assert_eq!(ich.chikou[54], None);
+    }
+
+    #[test]
+    fn test_parse_ma_periods() {
+        assert_eq!(parse_ma_periods(None).unwrap(), DEFAULT_MA_PERIODS.to_vec());
+        assert_eq!(parse_ma_periods(Some("89, 5,13,34,5")).unwrap(), vec![5, 13, 34, 89]);
+        assert!(parse_ma_periods(Some("0")).is_err());
+        assert!(parse_ma_periods(Some("10,abc")).is_err());
+        assert_eq!(ma_lookback(&[5, 89], false), 89);
+        assert_eq!(ma_lookback(&DEFAULT_MA_PERIODS, true), crate::constants::api::EMA_LOOKBACK);
+    }
+
+    #[test]
+    fn test_parse_indicator_list() {
+        let list = Indicator::parse_list("rsi14,MACD,bb20,rsi14,stoch,vwap").unwrap();
+        assert_eq!(
+            list,
+            vec![
+                Indicator::Rsi(14),
+                Indicator::Macd { fast: 12, slow: 26, signal: 9 },
+                Indicator::Bollinger(20),
+                Indicator::Stochastic(14),
+                Indicator::Vwap(20),
+            ]
+        );
+        assert_eq!(Indicator::parse("macd_5_35_5").unwrap().name(), "macd_5_35_5");
+        assert!(Indicator::parse("rsi0").is_err());
+        assert!(Indicator::parse("obv3").is_err());
+        assert!(Indicator::parse("macd_26_12_9").is_err());
+        assert_eq!(Indicator::for_field("bb20_upper"), Some(Indicator::Bollinger(20)));
+        assert_eq!(Indicator::for_field("macd_5_35_5_hist"), Some(Indicator::Macd { fast: 5, slow: 35, signal: 5 }));
+        assert_eq!(Indicator::for_field("rsi14"), Some(Indicator::Rsi(14)));
+        assert_eq!(Indicator::for_field("rsi"), None);
+        assert_eq!(Indicator::for_field("close"), None);
+        assert!(Indicator::parse("foo").is_err());
+    }
+}
@@ -0,0 +1,194 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+/// Listing state of a ticker from an effective date on. `Renamed` and
+/// `Merged` carry a successor ticker; `Active` ends a suspension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LifecycleState {
+    Active,
+    Suspended,
+    Delisted,
+    Renamed,
+    Merged,
+}
+
+impl LifecycleState {
+    /// The exact string stored in `ticker_lifecycle.state`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Active => "active",
+            Self::Suspended => "suspended",
+            Self::Delisted => "delisted",
+            Self::Renamed => "renamed",
+            Self::Merged => "merged",
+        }
+    }
+
+    pub fn from_db(s: &str) -> Option<Self> {
+        match s {
+            "active" => Some(Self::Active),
+            "suspended" => Some(Self::Suspended),
+            "delisted" => Some(Self::Delisted),
+            "renamed" => Some(Self::Renamed),
+            "merged" => Some(Self::Merged),
+            _ => None,
+        }
+    }
+
+    /// Whether the state points at a successor ticker.
+    pub fn has_successor(&self) -> bool {
+        matches!(self, Self::Renamed | Self::Merged)
+    }
+
+    /// Whether the symbol no longer trades (a suspension can be lifted).
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, Self::Delisted | Self::Renamed | Self::Merged)
+    }
+}
+
+/// One recorded state change.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LifecycleEvent {
+    pub id: i64,
+    pub source: String,
+    pub ticker: String,
+    pub state: LifecycleState,
+    pub effective_on: NaiveDate,
+    /// New symbol (`Renamed`) or absorbing ticker (`Merged`), same source.
+    pub successor: Option<String>,
+    pub note: Option<String>,
+}
+
+/// State on `date` given one ticker's events in `effective_on` order.
+pub fn state_on(events: &[LifecycleEvent], date: NaiveDate) -> LifecycleState {
+    events
+        .iter()
+        .rev()
+        .find(|e| e.effective_on <= date)
+        .map_or(LifecycleState::Active, |e| e.state)
+}
+
+/// Part of a stitched history: bars of `ticker` from `from` (inclusive) to
+/// `until` (exclusive). None = unbounded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Segment {
+    pub ticker: String,
+    pub from: Option<NaiveDate>,
+    pub until: Option<NaiveDate>,
+}
+
+/// Segments, oldest first, of the rename chain `ticker` belongs to; the last
+/// one is the current symbol. None when the ticker was never renamed and no
+/// ticker was renamed to it. `events` holds each ticker's events of one
+/// source in `effective_on` order.
+pub fn rename_chain(events: &HashMap<String, Vec<LifecycleEvent>>, ticker: &str) -> Option<Vec<Segment>> {
+    let renamed_to = |t: &str| {
+        let e = events.get(t)?.iter().rev().find(|e| e.state == LifecycleState::Renamed)?;
+        Some((e.successor.clone()?, e.effective_on))
+    };
+    let renamed_from = |t: &str| {
+        events
+            .values()
+            .flatten()
+            .filter(|e| e.state == LifecycleState::Renamed && e.successor.as_deref() == Some(t))
+            .max_by_key(|e| e.effective_on)
+            .map(|e| (e.ticker.clone(), e.effective_on))
+    };
+
+    // Walk back to the oldest symbol, then forward to the current one.
+    let mut seen = HashSet::from([ticker.to_string()]);
+    let mut oldest = ticker.to_string();
+    while let Some((prev, _)) = renamed_from(&oldest) {
+        if !seen.insert(prev.clone()) {
+            break;
+        }
+        oldest = prev;
+    }
+
+    let mut seen = HashSet::from([oldest.clone()]);
+    let mut segments = vec![Segment { ticker: oldest, from: None, until: None }];
+    while let Some((next, on)) = renamed_to(&segments.last()?.ticker) {
+        if !seen.insert(next.clone()) {
+            break;
+        }
+        segments.last_mut()?.until = Some(on);
+        segments.push(Segment { ticker: next, from: Some(on), until: None });
+    }
+    (segments.len() > 1).then_some(segments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn d(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn event(ticker: &str, state: LifecycleState, on: &str, successor: Option<&str>) -> LifecycleEvent {
+        LifecycleEvent {
+            id: 0,
+            source: "vn".to_string(),
+            ticker: ticker.to_string(),
+            state,
+            effective_on: d(on),
+            successor: successor.map(String::from),
+            note: None,
+        }
+    }
+
+    fn by_ticker(events: Vec<LifecycleEvent>) -> HashMap<String, Vec<LifecycleEvent>> {
+        let mut map: HashMap<String, Vec<LifecycleEvent>> = HashMap::new();
+        for e in events {
+            map.entry(e.ticker.clone()).or_default().push(e);
+        }
+        map
+    }
+
+    #[test]
+    fn test_state_on() {
+        let events = vec![
+            event("AAA", LifecycleState::Suspended, "2024-03-01", None),
+            event("AAA", LifecycleState::Active, "2024-04-01", None),
+            event("AAA", LifecycleState::Delisted, "2025-01-02", None),
+        ];
+        assert_eq!(state_on(&events, d("2024-02-29")), LifecycleState::Active);
+        assert_eq!(state_on(&events, d("2024-03-01")), LifecycleState::Suspended);
+        assert_eq!(state_on(&events, d("2024-06-01")), LifecycleState::Active);
+        assert_eq!(state_on(&events, d("2025-01-02")), LifecycleState::Delisted);
+        assert_eq!(state_on(&[], d("2025-01-02")), LifecycleState::Active);
+    }
+
+    #[test]
+    fn test_rename_chain() {
+        let events = by_ticker(vec![
+            event("OLD", LifecycleState::Renamed, "2020-05-01", Some("MID")),
+            event("MID", LifecycleState::Renamed, "2023-01-03", Some("NEW")),
+            event("XYZ", LifecycleState::Merged, "2023-01-03", Some("NEW")),
+        ]);
+        let expected = vec![
+            Segment { ticker: "OLD".into(), from: None, until: Some(d("2020-05-01")) },
+            Segment { ticker: "MID".into(), from: Some(d("2020-05-01")), until: Some(d("2023-01-03")) },
+            Segment { ticker: "NEW".into(), from: Some(d("2023-01-03")), until: None },
+        ];
+        for t in ["OLD", "MID", "NEW"] {
+            assert_eq!(rename_chain(&events, t), Some(expected.clone()), "{t}");
+        }
+        // A merge is not a rename: the absorbed ticker keeps its own history
+        assert_eq!(rename_chain(&events, "XYZ"), None);
+        assert_eq!(rename_chain(&events, "FPT"), None);
+    }
+
+    #[test]
+    fn test_rename_cycle_terminates() {
+        let events = by_ticker(vec![
+            event("A", LifecycleState::Renamed, "2020-01-01", Some("B")),
+            event("B", LifecycleState::Renamed, "2021-01-01", Some("A")),
+        ]);
+        let chain = rename_chain(&events, "A").unwrap();
+        assert_eq!(chain.len(), 2);
+    }
+}
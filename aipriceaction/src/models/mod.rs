@@ -1,5 +1,10 @@
 pub mod aggregated_interval;
+pub mod calendar;
 pub mod checkpoint;
+pub mod corporate_action;
+pub mod custom_index;
 pub mod indicators;
 pub mod interval;
+pub mod lifecycle;
 pub mod ohlcv;
+pub mod portfolio;
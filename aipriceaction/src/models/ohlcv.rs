@@ -1,8 +1,11 @@
+use std::collections::BTreeMap;
 use std::fmt;
 
 use chrono::{DateTime, Utc};
 use sqlx::FromRow;
 
+use crate::models::indicators::{DEFAULT_MA_PERIODS, calculate_ma_score};
+
 #[derive(Debug, Clone, FromRow)]
 pub struct Ticker {
     pub id: i32,
@@ -84,6 +87,73 @@ pub struct OhlcvJoined {
     pub close_changed: Option<f64>,
     pub volume_changed: Option<f64>,
     pub total_money_changed: Option<f64>,
+    /// Extra indicators requested via `indicators=` (not part of the CSV format).
+    #[sqlx(skip)]
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub indicators: BTreeMap<String, f64>,
+}
+
+/// Rows with the fixed `ma10`..`ma200` columns and an `indicators` map
+/// for other periods (`OhlcvJoined`, `AggregatedOhlcv`).
+pub trait MaColumns {
+    fn close(&self) -> f64;
+
+    /// `(ma, ma_score)` fields of each of `DEFAULT_MA_PERIODS`, in order.
+    fn ma_columns(&mut self) -> [(&mut Option<f64>, &mut Option<f64>); 5];
+
+    fn indicators_mut(&mut self) -> &mut BTreeMap<String, f64>;
+
+    /// Store an MA value and its score: the fixed `ma10`..`ma200` fields for
+    /// the default periods, `ma{period}` / `ma{period}_score` in `indicators`
+    /// for any other `ma_periods=` period.
+    fn set_ma(&mut self, period: usize, value: f64) {
+        let score = calculate_ma_score(self.close(), value);
+        if let Some((_, (ma, ma_score))) = DEFAULT_MA_PERIODS.iter().zip(self.ma_columns()).find(|(p, _)| **p == period) {
+            *ma = Some(value);
+            *ma_score = Some(score);
+            return;
+        }
+        let indicators = self.indicators_mut();
+        indicators.insert(format!("ma{period}"), value);
+        indicators.insert(format!("ma{period}_score"), score);
+    }
+}
+
+impl MaColumns for OhlcvJoined {
+    fn close(&self) -> f64 {
+        self.close
+    }
+
+    fn ma_columns(&mut self) -> [(&mut Option<f64>, &mut Option<f64>); 5] {
+        [
+            (&mut self.ma10, &mut self.ma10_score),
+            (&mut self.ma20, &mut self.ma20_score),
+            (&mut self.ma50, &mut self.ma50_score),
+            (&mut self.ma100, &mut self.ma100_score),
+            (&mut self.ma200, &mut self.ma200_score),
+        ]
+    }
+
+    fn indicators_mut(&mut self) -> &mut BTreeMap<String, f64> {
+        &mut self.indicators
+    }
+}
+
+impl OhlcvJoined {
+    /// MA value and score for `period`, wherever `set_ma` stored them.
+    pub fn ma(&self, period: usize) -> (Option<f64>, Option<f64>) {
+        match period {
+            10 => (self.ma10, self.ma10_score),
+            20 => (self.ma20, self.ma20_score),
+            50 => (self.ma50, self.ma50_score),
+            100 => (self.ma100, self.ma100_score),
+            200 => (self.ma200, self.ma200_score),
+            _ => (
+                self.indicators.get(&format!("ma{period}")).copied(),
+                self.indicators.get(&format!("ma{period}_score")).copied(),
+            ),
+        }
+    }
 }
 
 impl fmt::Display for OhlcvJoined {
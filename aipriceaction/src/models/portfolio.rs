@@ -0,0 +1,117 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+/// Cash currency of a portfolio transaction or balance. USDT is treated as
+/// pegged 1:1 to USD.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Currency {
+    Vnd,
+    Usd,
+    Usdt,
+}
+
+impl Currency {
+    /// The exact string stored in `portfolios.base_currency` / `portfolio_transactions.currency`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Vnd => "VND",
+            Self::Usd => "USD",
+            Self::Usdt => "USDT",
+        }
+    }
+
+    pub fn from_db(s: &str) -> Option<Self> {
+        match s.to_ascii_uppercase().as_str() {
+            "VND" => Some(Self::Vnd),
+            "USD" => Some(Self::Usd),
+            "USDT" => Some(Self::Usdt),
+            _ => None,
+        }
+    }
+
+    /// Currency the stored prices of `source` are quoted in.
+    pub fn for_source(source: &str) -> Option<Self> {
+        match source {
+            "vn" | "sjc" => Some(Self::Vnd),
+            "yahoo" => Some(Self::Usd),
+            "crypto" => Some(Self::Usdt),
+            _ => None,
+        }
+    }
+
+    pub fn is_usd(&self) -> bool {
+        matches!(self, Self::Usd | Self::Usdt)
+    }
+}
+
+/// Kind of ledger entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TxKind {
+    /// `quantity` of (source, ticker) at `price`, plus `fee`, paid from cash.
+    Buy,
+    /// `quantity` of (source, ticker) at `price`, less `fee`, credited to cash.
+    Sell,
+    /// Cash `amount` received, optionally attributed to a ticker.
+    Dividend,
+    /// Cash `amount` paid outside a trade (custody, transfer fees).
+    Fee,
+    /// Cash `amount` added to the portfolio.
+    Deposit,
+    /// Cash `amount` taken out of the portfolio.
+    Withdrawal,
+}
+
+impl TxKind {
+    /// The exact string stored in `portfolio_transactions.kind`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Buy => "buy",
+            Self::Sell => "sell",
+            Self::Dividend => "dividend",
+            Self::Fee => "fee",
+            Self::Deposit => "deposit",
+            Self::Withdrawal => "withdrawal",
+        }
+    }
+
+    pub fn from_db(s: &str) -> Option<Self> {
+        match s {
+            "buy" => Some(Self::Buy),
+            "sell" => Some(Self::Sell),
+            "dividend" => Some(Self::Dividend),
+            "fee" => Some(Self::Fee),
+            "deposit" => Some(Self::Deposit),
+            "withdrawal" => Some(Self::Withdrawal),
+            _ => None,
+        }
+    }
+
+    /// Whether the entry moves a holding (and so needs source and ticker).
+    pub fn is_trade(&self) -> bool {
+        matches!(self, Self::Buy | Self::Sell)
+    }
+}
+
+/// One portfolio ledger entry. Stock dividends and bonus shares are recorded
+/// as a `Buy` at price 0.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Transaction {
+    #[serde(default)]
+    pub id: i64,
+    pub kind: TxKind,
+    pub trade_date: NaiveDate,
+    pub source: Option<String>,
+    pub ticker: Option<String>,
+    #[serde(default)]
+    pub quantity: f64,
+    #[serde(default)]
+    pub price: f64,
+    #[serde(default)]
+    pub amount: f64,
+    #[serde(default)]
+    pub fee: f64,
+    pub currency: Currency,
+    pub note: Option<String>,
+}
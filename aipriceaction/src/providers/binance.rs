@@ -340,18 +340,9 @@ impl BinanceProvider {
     }
 
     // -----------------------------------------------------------------------
-    // get_history — main entry point: historical + live merge
+    // get_history_since — main entry point: historical + live merge
     // -----------------------------------------------------------------------
 
-    pub async fn get_history(
-        &self,
-        symbol: &str,
-        interval: &str,
-        limit: u32,
-    ) -> Result<Vec<OhlcvData>, BinanceError> {
-        self.get_history_since(symbol, interval, limit, None).await
-    }
-
     /// Fetch OHLCV history, optionally starting from a given timestamp.
     /// When start_time is provided, only Vision daily ZIPs from that date onwards
     /// are fetched (plus live API), avoiding redundant downloads.
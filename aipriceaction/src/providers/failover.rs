@@ -0,0 +1,215 @@
+//! Primary provider with an ordered list of fallbacks.
+//!
+//! Used for VN tickers: VCI is tried first, and when it is rate limited, down
+//! (5xx, connection errors, timeouts) or has no data for a symbol the UDF brokers (vietstock, vndirect, dnse, vps) are
+//! tried in the configured order. The UDF clients already rescale prices to
+//! VCI's units; like VCI they serve back-adjusted history, which the workers
+//! restore to raw prices whichever source answered.
+
+use chrono::{DateTime, Utc};
+
+use crate::models::interval::Interval;
+use super::market_data::{HistoryRequest, MarketDataProvider, ProviderCapabilities, ProviderError, SymbolMatch};
+use super::ohlcv::OhlcvData;
+use super::udf::UdfProvider;
+use super::vci::VciProvider;
+
+pub struct FailoverProvider<P, F> {
+    primary: P,
+    fallbacks: Vec<F>,
+}
+
+impl<P: MarketDataProvider, F: MarketDataProvider> FailoverProvider<P, F> {
+    pub fn new(primary: P, fallbacks: Vec<F>) -> Self {
+        Self { primary, fallbacks }
+    }
+
+    pub fn fallback_names(&self) -> Vec<&'static str> {
+        self.fallbacks.iter().map(|f| f.name()).collect()
+    }
+}
+
+impl FailoverProvider<VciProvider, UdfProvider> {
+    /// VCI backed by the UDF brokers from `VCI_FALLBACK_SOURCES`. Unknown
+    /// broker names are logged and skipped.
+    pub fn vci_with_udf(primary: VciProvider) -> Self {
+        let fallbacks = crate::constants::vci_worker::fallback_sources()
+            .iter()
+            .filter_map(|name| match UdfProvider::new(name, crate::constants::vci_worker::FALLBACK_RPM) {
+                Ok(p) => Some(p),
+                Err(e) => {
+                    tracing::warn!(source = %name, "skipping UDF fallback: {e}");
+                    None
+                }
+            })
+            .collect();
+        Self::new(primary, fallbacks)
+    }
+}
+
+/// Errors that mean "this source can't serve the symbol right now" rather
+/// than a bad request that would fail everywhere.
+fn should_fail_over(e: &ProviderError) -> bool {
+    matches!(e, ProviderError::RateLimit | ProviderError::NoData | ProviderError::Unavailable(_))
+}
+
+impl<P: MarketDataProvider, F: MarketDataProvider> MarketDataProvider for FailoverProvider<P, F> {
+    fn name(&self) -> &'static str {
+        self.primary.name()
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        self.primary.capabilities()
+    }
+
+    fn client_count(&self) -> usize {
+        self.primary.client_count()
+    }
+
+    async fn history(
+        &self,
+        symbol: &str,
+        interval: Interval,
+        request: HistoryRequest,
+    ) -> Result<Vec<OhlcvData>, ProviderError> {
+        self.history_with_source(symbol, interval, request).await.map(|(data, _)| data)
+    }
+
+    async fn history_with_source(
+        &self,
+        symbol: &str,
+        interval: Interval,
+        request: HistoryRequest,
+    ) -> Result<(Vec<OhlcvData>, &'static str), ProviderError> {
+        let primary_err = match self.primary.history(symbol, interval, request).await {
+            Ok(data) if !data.is_empty() => return Ok((data, self.primary.name())),
+            Ok(_) => ProviderError::NoData,
+            Err(e) if should_fail_over(&e) => e,
+            Err(e) => return Err(e),
+        };
+
+        for fallback in self.fallbacks.iter().filter(|f| f.capabilities().supports(interval)) {
+            match fallback.history(symbol, interval, request).await {
+                Ok(mut data) if !data.is_empty() => {
+                    tracing::info!(
+                        symbol,
+                        primary = self.primary.name(),
+                        fallback = fallback.name(),
+                        reason = %primary_err,
+                        count = data.len(),
+                        "served by fallback provider"
+                    );
+                    for bar in &mut data {
+                        bar.symbol = Some(symbol.to_string());
+                    }
+                    return Ok((data, fallback.name()));
+                }
+                Ok(_) => tracing::debug!(symbol, fallback = fallback.name(), "fallback returned no data"),
+                Err(e) => tracing::debug!(symbol, fallback = fallback.name(), "fallback failed: {e}"),
+            }
+        }
+
+        // Surface the primary error so rate limits still trigger the batch cooldown.
+        Err(primary_err)
+    }
+
+    async fn search(&self, query: &str, limit: u32) -> Result<Vec<SymbolMatch>, ProviderError> {
+        self.primary.search(query, limit).await
+    }
+
+    async fn server_time(&self) -> Result<Option<DateTime<Utc>>, ProviderError> {
+        self.primary.server_time().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::providers::market_data::stub::{Reply, StubProvider};
+
+    fn bars() -> Vec<OhlcvData> {
+        vec![OhlcvData { time: Utc::now(), open: 1.0, high: 1.0, low: 1.0, close: 1.0, volume: 1, symbol: None }]
+    }
+
+    async fn fetch(provider: &FailoverProvider<StubProvider, StubProvider>) -> Result<&'static str, ProviderError> {
+        provider
+            .history_with_source("FPT", Interval::Daily, HistoryRequest::count_back(1))
+            .await
+            .map(|(_, served_by)| served_by)
+    }
+
+    #[tokio::test]
+    async fn test_fallbacks_tried_in_order_until_one_has_data() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let mut hourly_only = StubProvider::new("dnse", Reply::Bars(bars()), &calls);
+        hourly_only.intervals = &[Interval::Hourly];
+        let provider = FailoverProvider::new(
+            StubProvider::new("vci", Reply::RateLimit, &calls),
+            vec![
+                StubProvider::new("vietstock", Reply::NoData, &calls),
+                hourly_only,
+                StubProvider::new("vndirect", Reply::Unavailable("Server error (502)"), &calls),
+                StubProvider::new("vps", Reply::Bars(bars()), &calls),
+                StubProvider::new("unused", Reply::Bars(bars()), &calls),
+            ],
+        );
+        assert_eq!(fetch(&provider).await.unwrap(), "vps");
+        // dnse is skipped for daily bars; nothing after the first answer is called
+        assert_eq!(*calls.lock().unwrap(), vec!["vci", "vietstock", "vndirect", "vps"]);
+    }
+
+    #[tokio::test]
+    async fn test_outages_fail_over() {
+        let outages = [
+            Reply::Bars(Vec::new()),
+            Reply::Unavailable("Max attempts exceeded (5): Server error (503) - Service Unavailable"),
+            Reply::Unavailable("request timed out"),
+        ];
+        for reply in outages {
+            let calls = Arc::new(Mutex::new(Vec::new()));
+            let provider = FailoverProvider::new(
+                StubProvider::new("vci", reply, &calls),
+                vec![StubProvider::new("vndirect", Reply::Bars(bars()), &calls)],
+            );
+            assert_eq!(fetch(&provider).await.unwrap(), "vndirect");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_bad_request_does_not_fail_over() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let provider = FailoverProvider::new(
+            StubProvider::new("vci", Reply::Other("Client error (400) - Bad Request - not retryable"), &calls),
+            vec![StubProvider::new("vndirect", Reply::Bars(bars()), &calls)],
+        );
+        assert!(matches!(fetch(&provider).await, Err(ProviderError::Other(_))));
+        assert_eq!(*calls.lock().unwrap(), vec!["vci"]);
+    }
+
+    #[test]
+    fn test_vci_errors_classified_for_failover() {
+        use crate::providers::vci::VciError;
+        let fails_over = |e: VciError| should_fail_over(&e.into());
+        assert!(fails_over(VciError::Unavailable("Max attempts exceeded (5): Server error (502) - Bad Gateway".into())));
+        assert!(fails_over(VciError::Unavailable("request timed out".into())));
+        assert!(fails_over(VciError::RateLimit));
+        assert!(!fails_over(VciError::InvalidResponse("Client error (404) - Not Found - not retryable".into())));
+        assert!(!fails_over(VciError::InvalidInterval("2h".into())));
+    }
+
+    #[tokio::test]
+    async fn test_primary_error_surfaces_when_every_fallback_fails() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let provider = FailoverProvider::new(
+            StubProvider::new("vci", Reply::RateLimit, &calls),
+            vec![
+                StubProvider::new("vndirect", Reply::NoData, &calls),
+                StubProvider::new("vps", Reply::Unavailable("request timed out"), &calls),
+            ],
+        );
+        assert!(fetch(&provider).await.unwrap_err().is_rate_limited());
+        assert_eq!(*calls.lock().unwrap(), vec!["vci", "vndirect", "vps"]);
+    }
+}
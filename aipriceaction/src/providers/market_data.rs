@@ -0,0 +1,697 @@
+//! Vendor-neutral market data interface.
+//!
+//! Every upstream (VCI, Binance, Yahoo, the UDF brokers and SJC) exposes its own
+//! `get_history`-style method with its own interval strings and error enum.
+//! `MarketDataProvider` wraps them behind one async trait so workers and CLI
+//! commands can be written once and run against any source.
+
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use std::future::Future;
+
+use crate::models::interval::Interval;
+use super::binance::{BinanceError, BinanceProvider};
+use super::ohlcv::OhlcvData;
+use super::sjc::SjcProvider;
+use super::udf::{UdfError, UdfProvider};
+use super::vci::{VciError, VciProvider};
+use super::yahoo::{YahooError, YahooProvider};
+
+// ---------------------------------------------------------------------------
+// Error type
+// ---------------------------------------------------------------------------
+
+#[derive(Debug)]
+pub enum ProviderError {
+    RateLimit,
+    NoData,
+    InvalidInterval(String),
+    Unsupported(String),
+    /// Upstream down: 5xx responses, connection errors or timeouts.
+    Unavailable(String),
+    Other(String),
+}
+
+impl ProviderError {
+    /// True when the upstream throttled us (HTTP 429/403, "Too many requests").
+    pub fn is_rate_limited(&self) -> bool {
+        matches!(self, ProviderError::RateLimit)
+    }
+
+    /// Classify a free-form upstream error message.
+    fn from_message(msg: String) -> Self {
+        if msg.contains("429") || msg.contains("403") || msg.contains("Too many requests") {
+            ProviderError::RateLimit
+        } else {
+            ProviderError::Other(msg)
+        }
+    }
+}
+
+impl std::fmt::Display for ProviderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            // Keep "429" in the message: workers match on the rendered string.
+            ProviderError::RateLimit => write!(f, "Rate limit exceeded (429)"),
+            ProviderError::NoData => write!(f, "No data available"),
+            ProviderError::InvalidInterval(s) => write!(f, "Invalid interval: {s}"),
+            ProviderError::Unsupported(s) => write!(f, "Unsupported: {s}"),
+            ProviderError::Unavailable(s) => write!(f, "Unavailable: {s}"),
+            ProviderError::Other(s) => write!(f, "{s}"),
+        }
+    }
+}
+
+impl std::error::Error for ProviderError {}
+
+impl From<VciError> for ProviderError {
+    fn from(e: VciError) -> Self {
+        match e {
+            VciError::RateLimit => ProviderError::RateLimit,
+            VciError::NoData => ProviderError::NoData,
+            VciError::InvalidInterval(s) => ProviderError::InvalidInterval(s),
+            e @ (VciError::Unavailable(_) | VciError::Http(_)) => ProviderError::Unavailable(e.to_string()),
+            other => ProviderError::from_message(other.to_string()),
+        }
+    }
+}
+
+impl From<BinanceError> for ProviderError {
+    fn from(e: BinanceError) -> Self {
+        match e {
+            BinanceError::InvalidInterval(s) => ProviderError::InvalidInterval(s),
+            other => ProviderError::from_message(other.to_string()),
+        }
+    }
+}
+
+impl From<YahooError> for ProviderError {
+    fn from(e: YahooError) -> Self {
+        match e {
+            YahooError::NoData => ProviderError::NoData,
+            YahooError::InvalidInterval(s) => ProviderError::InvalidInterval(s),
+            other => ProviderError::from_message(other.to_string()),
+        }
+    }
+}
+
+impl From<UdfError> for ProviderError {
+    fn from(e: UdfError) -> Self {
+        match e {
+            UdfError::RateLimit => ProviderError::RateLimit,
+            UdfError::NoData => ProviderError::NoData,
+            UdfError::InvalidInterval(s) => ProviderError::InvalidInterval(s),
+            other => ProviderError::from_message(other.to_string()),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Request / capability types
+// ---------------------------------------------------------------------------
+
+/// Which slice of history to fetch.
+#[derive(Debug, Clone, Copy)]
+pub enum HistoryRequest {
+    /// The newest `count` bars ending at `end` (or now).
+    CountBack { count: u32, end: Option<DateTime<Utc>> },
+    /// All bars with `start <= time <= end`.
+    Range { start: DateTime<Utc>, end: DateTime<Utc> },
+}
+
+impl HistoryRequest {
+    pub fn count_back(count: u32) -> Self {
+        HistoryRequest::CountBack { count, end: None }
+    }
+}
+
+/// Static description of what a provider can serve.
+#[derive(Debug, Clone, Copy)]
+pub struct ProviderCapabilities {
+    pub intervals: &'static [Interval],
+    pub supports_search: bool,
+    pub supports_server_time: bool,
+    /// Largest `count` a single `CountBack` request returns; larger requests are clamped.
+    pub max_count_back: u32,
+}
+
+impl ProviderCapabilities {
+    pub fn supports(&self, interval: Interval) -> bool {
+        self.intervals.contains(&interval)
+    }
+}
+
+/// A symbol-search hit, normalised across vendors.
+#[derive(Debug, Clone)]
+pub struct SymbolMatch {
+    pub symbol: String,
+    pub description: Option<String>,
+    pub exchange: Option<String>,
+    pub kind: Option<String>,
+}
+
+// ---------------------------------------------------------------------------
+// Trait
+// ---------------------------------------------------------------------------
+
+pub trait MarketDataProvider: Send + Sync {
+    /// Short source name used in logs (e.g. "vci", "binance", "vndirect").
+    fn name(&self) -> &'static str;
+
+    fn capabilities(&self) -> ProviderCapabilities;
+
+    /// Independent HTTP clients (proxies/direct) the provider rotates through.
+    fn client_count(&self) -> usize {
+        1
+    }
+
+    /// Fetch OHLCV bars in chronological order.
+    fn history(
+        &self,
+        symbol: &str,
+        interval: Interval,
+        request: HistoryRequest,
+    ) -> impl Future<Output = Result<Vec<OhlcvData>, ProviderError>> + Send;
+
+    /// Like [`history`](Self::history), also returning the name of the source
+    /// that actually served the bars. Differs from `name()` only for
+    /// composite providers such as [`FailoverProvider`](super::failover::FailoverProvider).
+    fn history_with_source(
+        &self,
+        symbol: &str,
+        interval: Interval,
+        request: HistoryRequest,
+    ) -> impl Future<Output = Result<(Vec<OhlcvData>, &'static str), ProviderError>> + Send {
+        async move { Ok((self.history(symbol, interval, request).await?, self.name())) }
+    }
+
+    fn search(
+        &self,
+        query: &str,
+        limit: u32,
+    ) -> impl Future<Output = Result<Vec<SymbolMatch>, ProviderError>> + Send {
+        let _ = (query, limit);
+        async move { Err(ProviderError::Unsupported(format!("{} has no symbol search", self.name()))) }
+    }
+
+    /// Upstream server time, when the vendor exposes one.
+    fn server_time(&self) -> impl Future<Output = Result<Option<DateTime<Utc>>, ProviderError>> + Send {
+        async { Ok(None) }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+const ALL_INTERVALS: &[Interval] = &[Interval::Daily, Interval::Hourly, Interval::Minute];
+
+fn bar_duration(interval: Interval) -> Duration {
+    match interval {
+        Interval::Daily => Duration::days(1),
+        Interval::Hourly => Duration::hours(1),
+        Interval::Minute => Duration::minutes(1),
+    }
+}
+
+/// Wall-clock span covering `count` bars. `sessions_factor` pads for closed
+/// markets (weekends, nights) on exchanges that don't trade 24/7.
+fn count_back_span(interval: Interval, count: u32, sessions_factor: i32) -> Duration {
+    bar_duration(interval) * (count as i32).saturating_mul(sessions_factor)
+}
+
+/// Number of bars that fit in a range, used by countback-only vendors.
+fn range_count(interval: Interval, start: DateTime<Utc>, end: DateTime<Utc>, cap: u32) -> u32 {
+    let bars = (end - start).num_seconds() / bar_duration(interval).num_seconds() + 1;
+    bars.clamp(1, cap as i64) as u32
+}
+
+/// Trim to the request window and keep chronological order.
+fn clip(mut data: Vec<OhlcvData>, request: HistoryRequest) -> Vec<OhlcvData> {
+    data.sort_by_key(|d| d.time);
+    match request {
+        HistoryRequest::CountBack { count, end } => {
+            if let Some(end) = end {
+                data.retain(|d| d.time <= end);
+            }
+            let excess = data.len().saturating_sub(count as usize);
+            data.drain(..excess);
+        }
+        HistoryRequest::Range { start, end } => {
+            data.retain(|d| d.time >= start && d.time <= end);
+        }
+    }
+    data
+}
+
+/// VCI/UDF interval strings (upper-case hour, matching their resolution maps).
+fn vn_interval(interval: Interval) -> &'static str {
+    match interval {
+        Interval::Daily => "1D",
+        Interval::Hourly => "1H",
+        Interval::Minute => "1m",
+    }
+}
+
+// ---------------------------------------------------------------------------
+// VCI
+// ---------------------------------------------------------------------------
+
+impl MarketDataProvider for VciProvider {
+    fn name(&self) -> &'static str {
+        "vci"
+    }
+
+    fn client_count(&self) -> usize {
+        VciProvider::client_count(self)
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            intervals: ALL_INTERVALS,
+            supports_search: false,
+            supports_server_time: false,
+            max_count_back: 50_000,
+        }
+    }
+
+    async fn history(
+        &self,
+        symbol: &str,
+        interval: Interval,
+        request: HistoryRequest,
+    ) -> Result<Vec<OhlcvData>, ProviderError> {
+        let (count, end) = match request {
+            HistoryRequest::CountBack { count, end } => (count, end),
+            HistoryRequest::Range { start, end } => {
+                (range_count(interval, start, end, self.capabilities().max_count_back), Some(end))
+            }
+        };
+        let data = self
+            .get_history(symbol, vn_interval(interval), count, end.map(|t| t.timestamp()))
+            .await?;
+        Ok(clip(data, request))
+    }
+}
+
+// ---------------------------------------------------------------------------
+// UDF brokers (Vietstock, VNDirect, DNSE, VPS)
+// ---------------------------------------------------------------------------
+
+impl MarketDataProvider for UdfProvider {
+    fn name(&self) -> &'static str {
+        self.source_name()
+    }
+
+    fn client_count(&self) -> usize {
+        UdfProvider::client_count(self)
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            intervals: ALL_INTERVALS,
+            supports_search: true,
+            supports_server_time: true,
+            max_count_back: 50_000,
+        }
+    }
+
+    async fn history(
+        &self,
+        symbol: &str,
+        interval: Interval,
+        request: HistoryRequest,
+    ) -> Result<Vec<OhlcvData>, ProviderError> {
+        let (count, end) = match request {
+            HistoryRequest::CountBack { count, end } => (count, end),
+            HistoryRequest::Range { start, end } => {
+                (range_count(interval, start, end, self.capabilities().max_count_back), Some(end))
+            }
+        };
+        let data = self
+            .get_history(symbol, vn_interval(interval), count, end.map(|t| t.timestamp()))
+            .await?;
+        Ok(clip(data, request))
+    }
+
+    async fn search(&self, query: &str, limit: u32) -> Result<Vec<SymbolMatch>, ProviderError> {
+        let results = UdfProvider::search(self, query, limit)
+            .await?
+            .ok_or_else(|| ProviderError::Unsupported(format!("{} has no /search", self.source_name())))?;
+        Ok(results
+            .into_iter()
+            .filter_map(|r| {
+                Some(SymbolMatch {
+                    symbol: r.symbol?,
+                    description: r.description,
+                    exchange: r.exchange,
+                    kind: r.r#type,
+                })
+            })
+            .collect())
+    }
+
+    async fn server_time(&self) -> Result<Option<DateTime<Utc>>, ProviderError> {
+        Ok(self
+            .get_server_time()
+            .await?
+            .and_then(|ts| DateTime::<Utc>::from_timestamp(ts, 0)))
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Binance
+// ---------------------------------------------------------------------------
+
+/// Max klines per REST page.
+const BINANCE_PAGE_LIMIT: u32 = 1000;
+
+impl MarketDataProvider for BinanceProvider {
+    fn name(&self) -> &'static str {
+        "binance"
+    }
+
+    fn client_count(&self) -> usize {
+        BinanceProvider::client_count(self)
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            intervals: ALL_INTERVALS,
+            supports_search: false,
+            supports_server_time: false,
+            max_count_back: BINANCE_PAGE_LIMIT,
+        }
+    }
+
+    async fn history(
+        &self,
+        symbol: &str,
+        interval: Interval,
+        request: HistoryRequest,
+    ) -> Result<Vec<OhlcvData>, ProviderError> {
+        let iv = match interval {
+            Interval::Daily => "1d",
+            Interval::Hourly => "1h",
+            Interval::Minute => "1m",
+        };
+        let data = match request {
+            // Short tails come straight from the REST API; Vision ZIPs are only
+            // worth downloading for explicit ranges.
+            HistoryRequest::CountBack { count, end } => {
+                let count = count.min(BINANCE_PAGE_LIMIT);
+                let end = end.unwrap_or_else(Utc::now);
+                let start = end - count_back_span(interval, count, 1);
+                // One extra kline covers the bar straddling `start`; `clip`
+                // trims back to `count`. Binance rejects limits above the page size.
+                let limit = (count + 1).min(BINANCE_PAGE_LIMIT);
+                self.get_klines_after(symbol, iv, limit, start.timestamp_millis()).await?
+            }
+            HistoryRequest::Range { start, end } => {
+                let live_limit = range_count(interval, start, end, BINANCE_PAGE_LIMIT);
+                self.get_history_since(symbol, iv, live_limit, Some(start)).await?
+            }
+        };
+        Ok(clip(data, request))
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Yahoo
+// ---------------------------------------------------------------------------
+
+impl MarketDataProvider for YahooProvider {
+    fn name(&self) -> &'static str {
+        "yahoo"
+    }
+
+    fn client_count(&self) -> usize {
+        YahooProvider::client_count(self)
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            intervals: ALL_INTERVALS,
+            supports_search: true,
+            supports_server_time: false,
+            max_count_back: 50_000,
+        }
+    }
+
+    async fn history(
+        &self,
+        symbol: &str,
+        interval: Interval,
+        request: HistoryRequest,
+    ) -> Result<Vec<OhlcvData>, ProviderError> {
+        let now = Utc::now();
+        let (start, end) = match request {
+            HistoryRequest::CountBack { count, end } => {
+                // Pad for weekends and overnight closes on equity exchanges.
+                let factor = match interval {
+                    Interval::Daily => 2,
+                    Interval::Hourly | Interval::Minute => 5,
+                };
+                let end = end.unwrap_or(now);
+                (end - count_back_span(interval, count, factor), end)
+            }
+            HistoryRequest::Range { start, end } => (start, end),
+        };
+        // Tails ending now go through the `range=` endpoint, which is also the
+        // only one that works for the PVT raw path without pulling `max`.
+        let data = if now - end < bar_duration(interval) {
+            self.get_history(symbol, interval.as_str(), yahoo_range_for(now - start)).await?
+        } else {
+            self.get_history_interval(symbol, interval.as_str(), start, end).await?
+        };
+        Ok(clip(data, request))
+    }
+
+    async fn search(&self, query: &str, limit: u32) -> Result<Vec<SymbolMatch>, ProviderError> {
+        let result = self.search_ticker(query).await?;
+        Ok(result
+            .quotes
+            .into_iter()
+            .take(limit as usize)
+            .map(|q| SymbolMatch {
+                symbol: q.symbol,
+                description: Some(q.short_name).filter(|s| !s.is_empty()),
+                exchange: Some(q.exchange).filter(|s| !s.is_empty()),
+                kind: Some(q.quote_type).filter(|s| !s.is_empty()),
+            })
+            .collect())
+    }
+}
+
+/// Smallest Yahoo `range=` value covering `span`.
+fn yahoo_range_for(span: Duration) -> &'static str {
+    const RANGES: &[(i64, &str)] = &[
+        (1, "1d"),
+        (5, "5d"),
+        (30, "1mo"),
+        (90, "3mo"),
+        (180, "6mo"),
+        (365, "1y"),
+        (730, "2y"),
+        (1825, "5y"),
+        (3650, "10y"),
+    ];
+    let days = span.num_days().max(1);
+    RANGES
+        .iter()
+        .find(|(d, _)| days <= *d)
+        .map(|(_, r)| *r)
+        .unwrap_or("max")
+}
+
+// ---------------------------------------------------------------------------
+// SJC (daily gold price, one request per date)
+// ---------------------------------------------------------------------------
+
+/// SJC has no bulk history endpoint; cap per-call day walks.
+const SJC_MAX_DAYS: u32 = 60;
+
+impl SjcProvider {
+    async fn daily_bar(&self, date: NaiveDate) -> Result<OhlcvData, ProviderError> {
+        let price = self
+            .fetch_price(Some(date))
+            .await
+            .map_err(|e| ProviderError::from_message(e.to_string()))?;
+        let mid = (price.buy + price.sell) / 2.0;
+        Ok(OhlcvData {
+            time: date.and_hms_opt(0, 0, 0).unwrap().and_utc(),
+            open: mid,
+            high: price.sell,
+            low: price.buy,
+            close: mid,
+            volume: 1,
+            symbol: Some(crate::constants::sjc_worker::TICKER.to_string()),
+        })
+    }
+}
+
+impl MarketDataProvider for SjcProvider {
+    fn name(&self) -> &'static str {
+        "sjc"
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            intervals: &[Interval::Daily],
+            supports_search: false,
+            supports_server_time: false,
+            max_count_back: SJC_MAX_DAYS,
+        }
+    }
+
+    async fn history(
+        &self,
+        _symbol: &str,
+        interval: Interval,
+        request: HistoryRequest,
+    ) -> Result<Vec<OhlcvData>, ProviderError> {
+        if interval != Interval::Daily {
+            return Err(ProviderError::InvalidInterval(interval.as_str().to_string()));
+        }
+        let (last, days) = match request {
+            HistoryRequest::CountBack { count, end } => {
+                (end.unwrap_or_else(Utc::now).date_naive(), count.min(SJC_MAX_DAYS))
+            }
+            HistoryRequest::Range { start, end } => {
+                let days = (end.date_naive() - start.date_naive()).num_days() + 1;
+                (end.date_naive(), days.clamp(1, SJC_MAX_DAYS as i64) as u32)
+            }
+        };
+
+        let mut data = Vec::with_capacity(days as usize);
+        for offset in 0..days {
+            let date = last - Duration::days(offset as i64);
+            match self.daily_bar(date).await {
+                Ok(bar) => data.push(bar),
+                Err(e) if e.is_rate_limited() => return Err(e),
+                Err(e) => tracing::debug!(%date, "SJC price unavailable: {e}"),
+            }
+        }
+        if data.is_empty() {
+            return Err(ProviderError::NoData);
+        }
+        Ok(clip(data, request))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 9, 1, hour, 0, 0).unwrap()
+    }
+
+    fn bars(hours: &[u32]) -> Vec<OhlcvData> {
+        hours
+            .iter()
+            .map(|&h| OhlcvData { time: at(h), open: 1.0, high: 1.0, low: 1.0, close: 1.0, volume: 1, symbol: None })
+            .collect()
+    }
+
+    fn hours(data: &[OhlcvData]) -> Vec<u32> {
+        data.iter().map(|d| chrono::Timelike::hour(&d.time)).collect()
+    }
+
+    #[test]
+    fn test_clip_count_back_keeps_newest_in_order() {
+        let data = bars(&[5, 1, 4, 2, 3]);
+        assert_eq!(hours(&clip(data.clone(), HistoryRequest::count_back(3))), vec![3, 4, 5]);
+        let request = HistoryRequest::CountBack { count: 2, end: Some(at(3)) };
+        assert_eq!(hours(&clip(data.clone(), request)), vec![2, 3]);
+        assert_eq!(hours(&clip(data, HistoryRequest::count_back(10))), vec![1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn test_clip_range_is_inclusive() {
+        let request = HistoryRequest::Range { start: at(2), end: at(4) };
+        assert_eq!(hours(&clip(bars(&[1, 2, 3, 4, 5]), request)), vec![2, 3, 4]);
+    }
+
+    #[test]
+    fn test_range_count_includes_both_ends_and_caps() {
+        assert_eq!(range_count(Interval::Hourly, at(0), at(5), 1000), 6);
+        assert_eq!(range_count(Interval::Minute, at(0), at(5), 1000), 301);
+        assert_eq!(range_count(Interval::Minute, at(0), at(23), 1000), 1000);
+        assert_eq!(range_count(Interval::Daily, at(5), at(0), 1000), 1);
+    }
+
+    #[test]
+    fn test_yahoo_range_for_picks_smallest_covering_range() {
+        assert_eq!(yahoo_range_for(Duration::hours(3)), "1d");
+        assert_eq!(yahoo_range_for(Duration::days(5)), "5d");
+        assert_eq!(yahoo_range_for(Duration::days(6)), "1mo");
+        assert_eq!(yahoo_range_for(Duration::days(365)), "1y");
+        assert_eq!(yahoo_range_for(Duration::days(4000)), "max");
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Test stub
+// ---------------------------------------------------------------------------
+
+/// Provider with a canned reply, for testing composite providers and workers.
+#[cfg(test)]
+pub(crate) mod stub {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    pub(crate) enum Reply {
+        Bars(Vec<OhlcvData>),
+        RateLimit,
+        NoData,
+        Unavailable(&'static str),
+        Other(&'static str),
+    }
+
+    pub(crate) struct StubProvider {
+        pub name: &'static str,
+        pub intervals: &'static [Interval],
+        pub reply: Reply,
+        /// Names of the stubs called so far, shared between stubs to check order.
+        pub calls: std::sync::Arc<Mutex<Vec<&'static str>>>,
+    }
+
+    impl StubProvider {
+        pub fn new(name: &'static str, reply: Reply, calls: &std::sync::Arc<Mutex<Vec<&'static str>>>) -> Self {
+            Self { name, intervals: ALL_INTERVALS, reply, calls: calls.clone() }
+        }
+    }
+
+    impl MarketDataProvider for StubProvider {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        fn capabilities(&self) -> ProviderCapabilities {
+            ProviderCapabilities {
+                intervals: self.intervals,
+                supports_search: false,
+                supports_server_time: false,
+                max_count_back: 1000,
+            }
+        }
+
+        async fn history(
+            &self,
+            _symbol: &str,
+            _interval: Interval,
+            _request: HistoryRequest,
+        ) -> Result<Vec<OhlcvData>, ProviderError> {
+            self.calls.lock().unwrap().push(self.name);
+            match &self.reply {
+                Reply::Bars(data) => Ok(data.clone()),
+                Reply::RateLimit => Err(ProviderError::RateLimit),
+                Reply::NoData => Err(ProviderError::NoData),
+                Reply::Unavailable(msg) => Err(ProviderError::Unavailable(msg.to_string())),
+                Reply::Other(msg) => Err(ProviderError::Other(msg.to_string())),
+            }
+        }
+    }
+}
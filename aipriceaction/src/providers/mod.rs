@@ -1,4 +1,6 @@
 pub mod binance;
+pub mod failover;
+pub mod market_data;
 pub mod ohlcv;
 pub mod sjc;
 pub mod udf;
@@ -21,6 +21,8 @@ pub enum VciError {
     Serialization(serde_json::Error),
     InvalidInterval(String),
     InvalidResponse(String),
+    /// Every attempt hit a 5xx, a network error or a timeout.
+    Unavailable(String),
     RateLimit,
     NoData,
 }
@@ -44,6 +46,7 @@ impl std::fmt::Display for VciError {
             VciError::Serialization(e) => write!(f, "Serialization error: {}", e),
             VciError::InvalidInterval(s) => write!(f, "Invalid interval: {}", s),
             VciError::InvalidResponse(s) => write!(f, "Invalid response: {}", s),
+            VciError::Unavailable(s) => write!(f, "Upstream unavailable: {}", s),
             VciError::RateLimit => write!(f, "Rate limit exceeded"),
             VciError::NoData => write!(f, "No data available"),
         }
@@ -475,7 +478,7 @@ impl VciProvider {
             sleep(Duration::from_secs(60)).await;
             return Err(VciError::RateLimit);
         }
-        Err(VciError::InvalidResponse(format!(
+        Err(VciError::Unavailable(format!(
             "Max attempts exceeded ({}): {}",
             MAX_TOTAL_ATTEMPTS,
             error_msg,
@@ -502,7 +505,7 @@ impl VciProvider {
             Ok(r) => r,
             Err(_) => {
                 tracing::error!("[{}] timed out after {}s", label_prefix, REQUEST_TIMEOUT_SECS * MAX_TOTAL_ATTEMPTS as u64);
-                Err(VciError::InvalidResponse("request timed out".to_string()))
+                Err(VciError::Unavailable("request timed out".to_string()))
             }
         }
     }
@@ -666,7 +669,7 @@ impl VciProvider {
             sleep(Duration::from_secs(60)).await;
             return Err(VciError::RateLimit);
         }
-        Err(VciError::InvalidResponse(format!(
+        Err(VciError::Unavailable(format!(
             "Max attempts exceeded ({}): {}",
             MAX_TOTAL_ATTEMPTS, error_msg,
         )))
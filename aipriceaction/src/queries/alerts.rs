@@ -0,0 +1,250 @@
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use sqlx::types::Json;
+
+use crate::services::alerts::Condition;
+
+// ── Data structures ──
+
+/// Alert rule as stored in `alert_rules` and returned by /alerts.
+#[derive(Debug, Clone, serde::Serialize, sqlx::FromRow)]
+pub struct AlertRule {
+    pub id: i64,
+    pub source: String,
+    pub ticker: String,
+    pub interval: String,
+    pub condition: Json<Condition>,
+    pub webhook_url: String,
+    pub enabled: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// One webhook notification, as returned by GET /alerts/{id}.
+#[derive(Debug, Clone, serde::Serialize, sqlx::FromRow)]
+pub struct DeliveryRow {
+    pub id: i64,
+    pub bar_time: DateTime<Utc>,
+    pub payload: serde_json::Value,
+    pub status: String,
+    pub attempts: i32,
+    pub response_status: Option<i32>,
+    pub last_error: Option<String>,
+    pub next_attempt_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+/// A pending delivery claimed for sending.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct DueDelivery {
+    pub id: i64,
+    pub rule_id: i64,
+    pub attempts: i32,
+    pub payload: serde_json::Value,
+    pub webhook_url: String,
+}
+
+const RULE_COLUMNS: &str =
+    "id, source, ticker, interval, condition, webhook_url, enabled, created_at, updated_at";
+
+// ── Rules ──
+
+pub async fn create_rule(
+    pool: &PgPool,
+    owner: &str,
+    source: &str,
+    ticker: &str,
+    interval: &str,
+    condition: &Condition,
+    webhook_url: &str,
+) -> sqlx::Result<AlertRule> {
+    sqlx::query_as::<_, AlertRule>(&format!(
+        r#"INSERT INTO alert_rules (owner, source, ticker, interval, condition, webhook_url)
+           VALUES ($1, $2, $3, $4, $5, $6)
+           RETURNING {RULE_COLUMNS}"#
+    ))
+    .bind(owner)
+    .bind(source)
+    .bind(ticker)
+    .bind(interval)
+    .bind(Json(condition))
+    .bind(webhook_url)
+    .fetch_one(pool)
+    .await
+}
+
+/// Partial update; `None` fields keep their value. Returns None when the
+/// rule does not exist or belongs to another owner.
+pub async fn update_rule(
+    pool: &PgPool,
+    owner: &str,
+    id: i64,
+    condition: Option<&Condition>,
+    webhook_url: Option<&str>,
+    enabled: Option<bool>,
+) -> sqlx::Result<Option<AlertRule>> {
+    sqlx::query_as::<_, AlertRule>(&format!(
+        r#"UPDATE alert_rules
+           SET condition   = COALESCE($3, condition),
+               webhook_url = COALESCE($4, webhook_url),
+               enabled     = COALESCE($5, enabled),
+               updated_at  = NOW()
+           WHERE id = $1 AND owner = $2
+           RETURNING {RULE_COLUMNS}"#
+    ))
+    .bind(id)
+    .bind(owner)
+    .bind(condition.map(Json))
+    .bind(webhook_url)
+    .bind(enabled)
+    .fetch_optional(pool)
+    .await
+}
+
+pub async fn delete_rule(pool: &PgPool, owner: &str, id: i64) -> sqlx::Result<bool> {
+    let result = sqlx::query("DELETE FROM alert_rules WHERE id = $1 AND owner = $2")
+        .bind(id)
+        .bind(owner)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn get_rule(pool: &PgPool, owner: &str, id: i64) -> sqlx::Result<Option<AlertRule>> {
+    sqlx::query_as::<_, AlertRule>(&format!(
+        "SELECT {RULE_COLUMNS} FROM alert_rules WHERE id = $1 AND owner = $2"
+    ))
+    .bind(id)
+    .bind(owner)
+    .fetch_optional(pool)
+    .await
+}
+
+pub async fn list_rules(pool: &PgPool, owner: &str) -> sqlx::Result<Vec<AlertRule>> {
+    sqlx::query_as::<_, AlertRule>(&format!(
+        "SELECT {RULE_COLUMNS} FROM alert_rules WHERE owner = $1 ORDER BY id"
+    ))
+    .bind(owner)
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn count_rules(pool: &PgPool, owner: &str) -> sqlx::Result<i64> {
+    sqlx::query_scalar("SELECT COUNT(*) FROM alert_rules WHERE owner = $1")
+        .bind(owner)
+        .fetch_one(pool)
+        .await
+}
+
+/// Every enabled rule, for the alert worker.
+pub async fn enabled_rules(pool: &PgPool) -> sqlx::Result<Vec<AlertRule>> {
+    sqlx::query_as::<_, AlertRule>(&format!(
+        "SELECT {RULE_COLUMNS} FROM alert_rules WHERE enabled ORDER BY id"
+    ))
+    .fetch_all(pool)
+    .await
+}
+
+// ── Deliveries ──
+
+/// Queue a notification. Returns None when the rule already fired on this
+/// bar (the dedup key is `(rule_id, bar_time)`).
+pub async fn insert_delivery(
+    pool: &PgPool,
+    rule_id: i64,
+    bar_time: DateTime<Utc>,
+    payload: &serde_json::Value,
+) -> sqlx::Result<Option<i64>> {
+    sqlx::query_scalar(
+        r#"INSERT INTO alert_deliveries (rule_id, bar_time, payload)
+           VALUES ($1, $2, $3)
+           ON CONFLICT (rule_id, bar_time) DO NOTHING
+           RETURNING id"#,
+    )
+    .bind(rule_id)
+    .bind(bar_time)
+    .bind(payload)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Claim up to `limit` pending deliveries that are due, pushing their
+/// `next_attempt_at` out by `lease_secs` so concurrent rounds skip them.
+pub async fn claim_due(pool: &PgPool, limit: i64, lease_secs: i64) -> sqlx::Result<Vec<DueDelivery>> {
+    sqlx::query_as::<_, DueDelivery>(
+        r#"WITH due AS (
+               SELECT id FROM alert_deliveries
+               WHERE status = 'pending' AND next_attempt_at <= NOW()
+               ORDER BY next_attempt_at
+               LIMIT $1
+               FOR UPDATE SKIP LOCKED
+           )
+           UPDATE alert_deliveries d
+           SET next_attempt_at = NOW() + make_interval(secs => $2)
+           FROM due, alert_rules r
+           WHERE d.id = due.id AND r.id = d.rule_id
+           RETURNING d.id, d.rule_id, d.attempts, d.payload, r.webhook_url"#,
+    )
+    .bind(limit)
+    .bind(lease_secs as f64)
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn mark_delivered(pool: &PgPool, id: i64, response_status: i32) -> sqlx::Result<()> {
+    sqlx::query(
+        r#"UPDATE alert_deliveries
+           SET status = 'delivered', attempts = attempts + 1, response_status = $2,
+               last_error = NULL, delivered_at = NOW()
+           WHERE id = $1"#,
+    )
+    .bind(id)
+    .bind(response_status)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Record a failed attempt: retry after `retry_in_secs`, or mark the
+/// delivery failed when it is None.
+pub async fn record_failure(
+    pool: &PgPool,
+    id: i64,
+    response_status: Option<i32>,
+    error: &str,
+    retry_in_secs: Option<i64>,
+) -> sqlx::Result<()> {
+    sqlx::query(
+        r#"UPDATE alert_deliveries
+           SET status          = CASE WHEN $4::float8 IS NULL THEN 'failed' ELSE 'pending' END,
+               attempts        = attempts + 1,
+               response_status = $2,
+               last_error      = $3,
+               next_attempt_at = NOW() + make_interval(secs => COALESCE($4::float8, 0))
+           WHERE id = $1"#,
+    )
+    .bind(id)
+    .bind(response_status)
+    .bind(error)
+    .bind(retry_in_secs.map(|s| s as f64))
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Newest deliveries of one rule.
+pub async fn list_deliveries(pool: &PgPool, rule_id: i64, limit: i64) -> sqlx::Result<Vec<DeliveryRow>> {
+    sqlx::query_as::<_, DeliveryRow>(
+        r#"SELECT id, bar_time, payload, status, attempts, response_status, last_error,
+                  next_attempt_at, created_at, delivered_at
+           FROM alert_deliveries
+           WHERE rule_id = $1
+           ORDER BY bar_time DESC
+           LIMIT $2"#,
+    )
+    .bind(rule_id)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
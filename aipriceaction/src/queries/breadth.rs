@@ -0,0 +1,123 @@
+use chrono::NaiveDate;
+use sqlx::PgPool;
+
+use crate::constants::breadth::MARKET;
+
+// ── Data structures ──
+
+/// One day of breadth for a sector (or the whole market, `sector = "ALL"`),
+/// as stored in `market_breadth`.
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, sqlx::FromRow)]
+pub struct BreadthDay {
+    #[serde(skip_serializing)]
+    pub sector: String,
+    pub date: NaiveDate,
+    /// Stocks with a bar on `date`
+    pub total: i32,
+    pub advances: i32,
+    pub declines: i32,
+    pub unchanged: i32,
+    /// Volume of advancing / declining stocks
+    pub up_volume: i64,
+    pub down_volume: i64,
+    pub above_ma20: i32,
+    pub above_ma50: i32,
+    pub above_ma200: i32,
+    /// Stocks with enough history for the MA (the `above_ma*` denominators)
+    pub with_ma20: i32,
+    pub with_ma50: i32,
+    pub with_ma200: i32,
+    /// High above / low below the previous 52 weeks
+    pub new_highs: i32,
+    pub new_lows: i32,
+}
+
+const BREADTH_COLUMNS: &str = "sector, date, total, advances, declines, unchanged, up_volume, down_volume, \
+    above_ma20, above_ma50, above_ma200, with_ma20, with_ma50, with_ma200, new_highs, new_lows";
+
+// ── Write queries ──
+
+/// Upsert computed days; existing (source, sector, date) rows are replaced.
+pub async fn save_breadth(pool: &PgPool, source: &str, days: &[BreadthDay]) -> sqlx::Result<u64> {
+    if days.is_empty() {
+        return Ok(0);
+    }
+    let int_col = |f: fn(&BreadthDay) -> i32| days.iter().map(f).collect::<Vec<i32>>();
+
+    let result = sqlx::query(&format!(
+        r#"INSERT INTO market_breadth (source, {BREADTH_COLUMNS})
+           SELECT $1, t.*
+           FROM UNNEST($2::text[], $3::date[], $4::int[], $5::int[], $6::int[], $7::int[], $8::bigint[], $9::bigint[],
+                       $10::int[], $11::int[], $12::int[], $13::int[], $14::int[], $15::int[], $16::int[], $17::int[])
+                AS t({BREADTH_COLUMNS})
+           ON CONFLICT (source, sector, date)
+           DO UPDATE SET total       = EXCLUDED.total,
+                         advances    = EXCLUDED.advances,
+                         declines    = EXCLUDED.declines,
+                         unchanged   = EXCLUDED.unchanged,
+                         up_volume   = EXCLUDED.up_volume,
+                         down_volume = EXCLUDED.down_volume,
+                         above_ma20  = EXCLUDED.above_ma20,
+                         above_ma50  = EXCLUDED.above_ma50,
+                         above_ma200 = EXCLUDED.above_ma200,
+                         with_ma20   = EXCLUDED.with_ma20,
+                         with_ma50   = EXCLUDED.with_ma50,
+                         with_ma200  = EXCLUDED.with_ma200,
+                         new_highs   = EXCLUDED.new_highs,
+                         new_lows    = EXCLUDED.new_lows,
+                         computed_at = NOW()"#
+    ))
+    .bind(source)
+    .bind(days.iter().map(|d| d.sector.clone()).collect::<Vec<String>>())
+    .bind(days.iter().map(|d| d.date).collect::<Vec<NaiveDate>>())
+    .bind(int_col(|d| d.total))
+    .bind(int_col(|d| d.advances))
+    .bind(int_col(|d| d.declines))
+    .bind(int_col(|d| d.unchanged))
+    .bind(days.iter().map(|d| d.up_volume).collect::<Vec<i64>>())
+    .bind(days.iter().map(|d| d.down_volume).collect::<Vec<i64>>())
+    .bind(int_col(|d| d.above_ma20))
+    .bind(int_col(|d| d.above_ma50))
+    .bind(int_col(|d| d.above_ma200))
+    .bind(int_col(|d| d.with_ma20))
+    .bind(int_col(|d| d.with_ma50))
+    .bind(int_col(|d| d.with_ma200))
+    .bind(int_col(|d| d.new_highs))
+    .bind(int_col(|d| d.new_lows))
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+// ── Read queries ──
+
+/// Newest stored whole-market dates on or before `end`, newest first.
+pub async fn market_dates(pool: &PgPool, source: &str, end: NaiveDate, limit: i64) -> sqlx::Result<Vec<NaiveDate>> {
+    sqlx::query_scalar(
+        r#"SELECT date FROM market_breadth
+           WHERE source = $1 AND sector = $2 AND date <= $3
+           ORDER BY date DESC
+           LIMIT $4"#,
+    )
+    .bind(source)
+    .bind(MARKET)
+    .bind(end)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
+
+/// All stored rows (every sector) between `start` and `end`, oldest first.
+pub async fn list_breadth(pool: &PgPool, source: &str, start: NaiveDate, end: NaiveDate) -> sqlx::Result<Vec<BreadthDay>> {
+    sqlx::query_as::<_, BreadthDay>(&format!(
+        r#"SELECT {BREADTH_COLUMNS} FROM market_breadth
+           WHERE source = $1 AND date BETWEEN $2 AND $3
+           ORDER BY date, sector"#
+    ))
+    .bind(source)
+    .bind(start)
+    .bind(end)
+    .fetch_all(pool)
+    .await
+}
@@ -0,0 +1,109 @@
+use chrono::NaiveDate;
+use sqlx::PgPool;
+use std::collections::HashMap;
+
+use crate::models::corporate_action::{stitch_actions, ActionType, CorporateAction};
+
+// ── Data structures ──
+
+#[derive(sqlx::FromRow)]
+struct ActionRow {
+    ticker: String,
+    ex_date: NaiveDate,
+    action_type: String,
+    ratio: f64,
+    cash_amount: Option<f64>,
+    source: String,
+}
+
+impl ActionRow {
+    fn into_action(self) -> Option<(String, CorporateAction)> {
+        let action_type = ActionType::from_db(&self.action_type)?;
+        Some((
+            self.ticker,
+            CorporateAction {
+                ex_date: self.ex_date,
+                action_type,
+                ratio: self.ratio,
+                cash_amount: self.cash_amount,
+                source: self.source,
+            },
+        ))
+    }
+}
+
+const SELECT_ACTIONS: &str = r#"SELECT t.ticker, ca.ex_date, ca.action_type, ca.ratio, ca.cash_amount, ca.source
+           FROM corporate_actions ca
+           JOIN tickers t ON t.id = ca.ticker_id"#;
+
+// ── Write queries ──
+
+/// Insert a detected action; a re-detection on the same ex-date updates it.
+pub async fn upsert_action(pool: &PgPool, ticker_id: i32, action: &CorporateAction) -> sqlx::Result<()> {
+    sqlx::query(
+        r#"INSERT INTO corporate_actions (ticker_id, ex_date, action_type, ratio, cash_amount, source)
+           VALUES ($1, $2, $3, $4, $5, $6)
+           ON CONFLICT (ticker_id, ex_date, action_type)
+           DO UPDATE SET ratio = EXCLUDED.ratio, cash_amount = EXCLUDED.cash_amount,
+                         source = EXCLUDED.source, detected_at = NOW()"#,
+    )
+    .bind(ticker_id)
+    .bind(action.ex_date)
+    .bind(action.action_type.as_str())
+    .bind(action.ratio)
+    .bind(action.cash_amount)
+    .bind(&action.source)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+// ── Read queries ──
+
+/// All actions for one ticker, oldest ex-date first.
+pub async fn list_for_ticker(pool: &PgPool, ticker_id: i32) -> sqlx::Result<Vec<CorporateAction>> {
+    let sql = format!("{SELECT_ACTIONS} WHERE ca.ticker_id = $1 ORDER BY ca.ex_date");
+    let rows = sqlx::query_as::<_, ActionRow>(&sql).bind(ticker_id).fetch_all(pool).await?;
+    Ok(rows.into_iter().filter_map(|r| r.into_action().map(|(_, a)| a)).collect())
+}
+
+/// Actions for tickers of the given sources, grouped by ticker symbol.
+/// An empty `symbols` slice means every ticker of those sources. A symbol
+/// of a rename chain gets the actions of every chain member, each limited to
+/// its own segment, under the newest symbol (as stitched histories are).
+pub async fn list_for_tickers(
+    pool: &PgPool,
+    sources: &[&str],
+    symbols: &[String],
+) -> sqlx::Result<HashMap<String, Vec<CorporateAction>>> {
+    let chains: Vec<_> = {
+        let catalog = crate::services::metadata::catalog();
+        let catalog = &catalog;
+        sources
+            .iter()
+            .flat_map(|src| symbols.iter().filter_map(move |s| catalog.rename_chain(src, s)))
+            .collect()
+    };
+    let mut symbols = symbols.to_vec();
+    symbols.extend(chains.iter().flatten().map(|seg| seg.ticker.clone()));
+    symbols.sort();
+    symbols.dedup();
+
+    let sql = format!(
+        "{SELECT_ACTIONS} WHERE t.source = ANY($1) AND (cardinality($2::text[]) = 0 OR t.ticker = ANY($2)) ORDER BY t.ticker, ca.ex_date"
+    );
+    let rows = sqlx::query_as::<_, ActionRow>(&sql)
+        .bind(sources)
+        .bind(&symbols)
+        .fetch_all(pool)
+        .await?;
+
+    let mut map: HashMap<String, Vec<CorporateAction>> = HashMap::new();
+    for (ticker, action) in rows.into_iter().filter_map(ActionRow::into_action) {
+        map.entry(ticker).or_default().push(action);
+    }
+    for chain in &chains {
+        stitch_actions(&mut map, chain);
+    }
+    Ok(map)
+}
@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use sqlx::{PgConnection, PgExecutor, PgPool};
+
+use crate::models::custom_index::{CustomIndex, IndexMember, Weighting, SOURCE};
+
+// ── Data structures ──
+
+#[derive(sqlx::FromRow)]
+struct IndexRow {
+    id: i32,
+    name: String,
+    title: Option<String>,
+    weighting: String,
+    base_value: f64,
+    base_date: Option<NaiveDate>,
+    note: Option<String>,
+    updated_at: DateTime<Utc>,
+    computed_at: Option<DateTime<Utc>>,
+}
+
+#[derive(sqlx::FromRow)]
+struct MemberRow {
+    index_id: i32,
+    source: String,
+    ticker: String,
+    weight: Option<f64>,
+}
+
+impl IndexRow {
+    fn into_index(self, members: Vec<IndexMember>) -> Option<CustomIndex> {
+        Some(CustomIndex {
+            id: self.id,
+            name: self.name,
+            title: self.title,
+            weighting: Weighting::from_db(&self.weighting)?,
+            base_value: self.base_value,
+            base_date: self.base_date,
+            note: self.note,
+            members,
+            updated_at: self.updated_at,
+            computed_at: self.computed_at,
+        })
+    }
+}
+
+const INDEX_COLUMNS: &str = "id, name, title, weighting, base_value, base_date, note, updated_at, computed_at";
+
+// ── Write queries ──
+
+/// Create or replace the definition named `index.name` together with its
+/// members (`index.id` and timestamps are ignored). Bumps `updated_at`, so
+/// the worker rebuilds the series. Run it inside the caller's transaction.
+/// Returns the id.
+pub async fn save(conn: &mut PgConnection, index: &CustomIndex) -> sqlx::Result<i32> {
+    let id: i32 = sqlx::query_scalar(
+        r#"INSERT INTO custom_indices (name, title, weighting, base_value, base_date, note)
+           VALUES ($1, $2, $3, $4, $5, $6)
+           ON CONFLICT (name) DO UPDATE SET
+             title = EXCLUDED.title, weighting = EXCLUDED.weighting,
+             base_value = EXCLUDED.base_value, base_date = EXCLUDED.base_date,
+             note = EXCLUDED.note, updated_at = NOW()
+           RETURNING id"#,
+    )
+    .bind(&index.name)
+    .bind(&index.title)
+    .bind(index.weighting.as_str())
+    .bind(index.base_value)
+    .bind(index.base_date)
+    .bind(&index.note)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    sqlx::query("DELETE FROM custom_index_members WHERE index_id = $1")
+        .bind(id)
+        .execute(&mut *conn)
+        .await?;
+    let sources: Vec<&str> = index.members.iter().map(|m| m.source.as_str()).collect();
+    let tickers: Vec<&str> = index.members.iter().map(|m| m.ticker.as_str()).collect();
+    let weights: Vec<Option<f64>> = index.members.iter().map(|m| m.weight).collect();
+    sqlx::query(
+        r#"INSERT INTO custom_index_members (index_id, source, ticker, weight)
+           SELECT $1, * FROM UNNEST($2::text[], $3::text[], $4::float8[])"#,
+    )
+    .bind(id)
+    .bind(&sources)
+    .bind(&tickers)
+    .bind(&weights)
+    .execute(&mut *conn)
+    .await?;
+    Ok(id)
+}
+
+pub async fn delete(conn: impl PgExecutor<'_>, name: &str) -> sqlx::Result<bool> {
+    let result = sqlx::query("DELETE FROM custom_indices WHERE name = $1")
+        .bind(name)
+        .execute(conn)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Remove the stored series of `name` and its `tickers` row. Run it inside
+/// the caller's transaction.
+pub async fn drop_series(conn: &mut PgConnection, name: &str) -> sqlx::Result<()> {
+    let id: Option<i32> = sqlx::query_scalar("SELECT id FROM tickers WHERE source = $1 AND ticker = $2")
+        .bind(SOURCE)
+        .bind(name)
+        .fetch_optional(&mut *conn)
+        .await?;
+    if let Some(id) = id {
+        for table in ["ohlcv", "ohlcv_quarantine", "tickers"] {
+            let column = if table == "tickers" { "id" } else { "ticker_id" };
+            sqlx::query(&format!("DELETE FROM {table} WHERE {column} = $1"))
+                .bind(id)
+                .execute(&mut *conn)
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+/// Record a refresh that started at `started` (edits after it trigger
+/// another rebuild).
+pub async fn mark_computed(pool: &PgPool, id: i32, started: DateTime<Utc>) -> sqlx::Result<()> {
+    sqlx::query("UPDATE custom_indices SET computed_at = $2 WHERE id = $1")
+        .bind(id)
+        .bind(started)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+// ── Read queries ──
+
+/// All definitions ordered by name, with their members.
+pub async fn list(pool: &PgPool) -> sqlx::Result<Vec<CustomIndex>> {
+    let rows = sqlx::query_as::<_, IndexRow>(&format!("SELECT {INDEX_COLUMNS} FROM custom_indices ORDER BY name"))
+        .fetch_all(pool)
+        .await?;
+    let mut members = load_members(pool, None).await?;
+    Ok(rows
+        .into_iter()
+        .filter_map(|r| {
+            let m = members.remove(&r.id).unwrap_or_default();
+            r.into_index(m)
+        })
+        .collect())
+}
+
+pub async fn get(pool: &PgPool, name: &str) -> sqlx::Result<Option<CustomIndex>> {
+    let row = sqlx::query_as::<_, IndexRow>(&format!("SELECT {INDEX_COLUMNS} FROM custom_indices WHERE name = $1"))
+        .bind(name)
+        .fetch_optional(pool)
+        .await?;
+    let Some(row) = row else {
+        return Ok(None);
+    };
+    let members = load_members(pool, Some(row.id)).await?.remove(&row.id).unwrap_or_default();
+    Ok(row.into_index(members))
+}
+
+/// Members per index id, optionally of one index only.
+async fn load_members(pool: &PgPool, index_id: Option<i32>) -> sqlx::Result<HashMap<i32, Vec<IndexMember>>> {
+    let rows = sqlx::query_as::<_, MemberRow>(
+        r#"SELECT index_id, source, ticker, weight
+           FROM custom_index_members
+           WHERE ($1::int IS NULL OR index_id = $1)
+           ORDER BY index_id, source, ticker"#,
+    )
+    .bind(index_id)
+    .fetch_all(pool)
+    .await?;
+    let mut map: HashMap<i32, Vec<IndexMember>> = HashMap::new();
+    for r in rows {
+        map.entry(r.index_id).or_default().push(IndexMember {
+            source: r.source,
+            ticker: r.ticker,
+            weight: r.weight,
+        });
+    }
+    Ok(map)
+}
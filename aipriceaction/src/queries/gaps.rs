@@ -0,0 +1,141 @@
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+
+// ── Data structures ──
+
+/// A run of consecutive missing bars (`start` and `end` are both missing bars).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gap {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub missing_bars: i32,
+}
+
+/// Stored gap joined with its ticker, as returned by /admin/gaps.
+#[derive(Debug, Clone, serde::Serialize, sqlx::FromRow)]
+pub struct GapRow {
+    pub id: i64,
+    pub ticker_id: i32,
+    pub source: String,
+    pub ticker: String,
+    pub interval: String,
+    pub gap_start: DateTime<Utc>,
+    pub gap_end: DateTime<Utc>,
+    pub missing_bars: i32,
+    pub status: String,
+    pub attempts: i32,
+    pub detected_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+// ── Write queries ──
+
+/// Queue gaps for one ticker + interval. Re-detections refresh pending rows;
+/// filled and unfillable rows are left alone.
+pub async fn save_gaps(pool: &PgPool, ticker_id: i32, interval: &str, gaps: &[Gap]) -> sqlx::Result<u64> {
+    if gaps.is_empty() {
+        return Ok(0);
+    }
+
+    let starts: Vec<DateTime<Utc>> = gaps.iter().map(|g| g.start).collect();
+    let ends: Vec<DateTime<Utc>> = gaps.iter().map(|g| g.end).collect();
+    let missing: Vec<i32> = gaps.iter().map(|g| g.missing_bars).collect();
+
+    let result = sqlx::query(
+        r#"INSERT INTO ohlcv_gaps (ticker_id, interval, gap_start, gap_end, missing_bars)
+           SELECT $1, $2, t.gap_start, t.gap_end, t.missing_bars
+           FROM UNNEST($3::timestamptz[], $4::timestamptz[], $5::int[])
+                AS t(gap_start, gap_end, missing_bars)
+           ON CONFLICT (ticker_id, interval, gap_start)
+           DO UPDATE SET gap_end      = EXCLUDED.gap_end,
+                         missing_bars = EXCLUDED.missing_bars,
+                         updated_at   = NOW()
+           WHERE ohlcv_gaps.status = 'pending'"#,
+    )
+    .bind(ticker_id)
+    .bind(interval)
+    .bind(&starts)
+    .bind(&ends)
+    .bind(&missing)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Record a backfill attempt: new status, remaining missing bars, attempts + 1.
+pub async fn record_attempt(pool: &PgPool, id: i64, status: &str, missing_bars: i32) -> sqlx::Result<()> {
+    sqlx::query(
+        r#"UPDATE ohlcv_gaps
+           SET status = $2, missing_bars = $3, attempts = attempts + 1, updated_at = NOW()
+           WHERE id = $1"#,
+    )
+    .bind(id)
+    .bind(status)
+    .bind(missing_bars)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+// ── Read queries ──
+
+/// Bar times for a ticker_id + interval with `start <= time < end`, ascending.
+pub async fn bar_times(
+    pool: &PgPool,
+    ticker_id: i32,
+    interval: &str,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> sqlx::Result<Vec<DateTime<Utc>>> {
+    sqlx::query_scalar::<_, DateTime<Utc>>(
+        r#"SELECT time FROM ohlcv
+           WHERE ticker_id = $1 AND interval = $2 AND time >= $3 AND time < $4
+           ORDER BY time ASC"#,
+    )
+    .bind(ticker_id)
+    .bind(interval)
+    .bind(start)
+    .bind(end)
+    .fetch_all(pool)
+    .await
+}
+
+/// Oldest pending gaps first, for the backfill queue.
+pub async fn pending_gaps(pool: &PgPool, limit: i64) -> sqlx::Result<Vec<GapRow>> {
+    list_gaps(pool, None, None, None, Some("pending"), limit, true).await
+}
+
+/// Gaps filtered by optional source, ticker, interval and status.
+/// Newest first unless `oldest_first`.
+pub async fn list_gaps(
+    pool: &PgPool,
+    source: Option<&str>,
+    ticker: Option<&str>,
+    interval: Option<&str>,
+    status: Option<&str>,
+    limit: i64,
+    oldest_first: bool,
+) -> sqlx::Result<Vec<GapRow>> {
+    let order = if oldest_first { "ASC" } else { "DESC" };
+    let sql = format!(
+        r#"SELECT g.id, g.ticker_id, t.source, t.ticker, g.interval, g.gap_start, g.gap_end,
+                  g.missing_bars, g.status, g.attempts, g.detected_at, g.updated_at
+           FROM ohlcv_gaps g
+           JOIN tickers t ON t.id = g.ticker_id
+           WHERE ($1::text IS NULL OR t.source = $1)
+             AND ($2::text IS NULL OR t.ticker = $2)
+             AND ($3::text IS NULL OR g.interval = $3)
+             AND ($4::text IS NULL OR g.status = $4)
+           ORDER BY g.detected_at {order}, g.id {order}
+           LIMIT $5"#
+    );
+    sqlx::query_as::<_, GapRow>(&sql)
+        .bind(source)
+        .bind(ticker)
+        .bind(interval)
+        .bind(status)
+        .bind(limit)
+        .fetch_all(pool)
+        .await
+}
@@ -0,0 +1,95 @@
+use chrono::NaiveDate;
+use sqlx::PgPool;
+
+use crate::models::lifecycle::{LifecycleEvent, LifecycleState};
+
+// ── Data structures ──
+
+#[derive(sqlx::FromRow)]
+struct EventRow {
+    id: i64,
+    source: String,
+    ticker: String,
+    state: String,
+    effective_on: NaiveDate,
+    successor: Option<String>,
+    note: Option<String>,
+}
+
+impl EventRow {
+    fn into_event(self) -> Option<LifecycleEvent> {
+        Some(LifecycleEvent {
+            id: self.id,
+            source: self.source,
+            ticker: self.ticker,
+            state: LifecycleState::from_db(&self.state)?,
+            effective_on: self.effective_on,
+            successor: self.successor,
+            note: self.note,
+        })
+    }
+}
+
+const SELECT_EVENTS: &str = r#"SELECT l.id, t.source, t.ticker, l.state, l.effective_on, s.ticker AS successor, l.note
+           FROM ticker_lifecycle l
+           JOIN tickers t ON t.id = l.ticker_id
+           LEFT JOIN tickers s ON s.id = l.successor_id"#;
+
+// ── Write queries ──
+
+/// Record a state change; a second event on the same date replaces the first.
+pub async fn record(
+    pool: &PgPool,
+    ticker_id: i32,
+    state: LifecycleState,
+    effective_on: NaiveDate,
+    successor_id: Option<i32>,
+    note: Option<&str>,
+) -> sqlx::Result<i64> {
+    sqlx::query_scalar(
+        r#"INSERT INTO ticker_lifecycle (ticker_id, state, effective_on, successor_id, note)
+           VALUES ($1, $2, $3, $4, $5)
+           ON CONFLICT (ticker_id, effective_on)
+           DO UPDATE SET state = EXCLUDED.state, successor_id = EXCLUDED.successor_id,
+                         note = EXCLUDED.note, created_at = NOW()
+           RETURNING id"#,
+    )
+    .bind(ticker_id)
+    .bind(state.as_str())
+    .bind(effective_on)
+    .bind(successor_id)
+    .bind(note)
+    .fetch_one(pool)
+    .await
+}
+
+pub async fn delete(pool: &PgPool, id: i64) -> sqlx::Result<bool> {
+    let result = sqlx::query("DELETE FROM ticker_lifecycle WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+// ── Read queries ──
+
+/// Events ordered by (source, ticker, effective_on), optionally narrowed to
+/// one source and ticker.
+pub async fn list(pool: &PgPool, source: Option<&str>, ticker: Option<&str>) -> sqlx::Result<Vec<LifecycleEvent>> {
+    let sql = format!(
+        "{SELECT_EVENTS} WHERE ($1::text IS NULL OR t.source = $1) AND ($2::text IS NULL OR t.ticker = $2)
+           ORDER BY t.source, t.ticker, l.effective_on"
+    );
+    let rows = sqlx::query_as::<_, EventRow>(&sql)
+        .bind(source)
+        .bind(ticker)
+        .fetch_all(pool)
+        .await?;
+    Ok(rows.into_iter().filter_map(EventRow::into_event).collect())
+}
+
+pub async fn get(pool: &PgPool, id: i64) -> sqlx::Result<Option<LifecycleEvent>> {
+    let sql = format!("{SELECT_EVENTS} WHERE l.id = $1");
+    let row = sqlx::query_as::<_, EventRow>(&sql).bind(id).fetch_optional(pool).await?;
+    Ok(row.and_then(EventRow::into_event))
+}
@@ -0,0 +1,116 @@
+use chrono::NaiveDate;
+use sqlx::PgPool;
+
+// ── Data structures ──
+
+/// One membership period of a ticker in a sector or index, as stored in
+/// `group_membership`.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, sqlx::FromRow)]
+pub struct Membership {
+    pub id: i64,
+    pub source: String,
+    /// `sector` or `index`
+    pub kind: String,
+    pub group_name: String,
+    pub ticker: String,
+    /// Inclusive; None = since listing
+    pub effective_from: Option<NaiveDate>,
+    /// Exclusive; None = still a member
+    pub effective_to: Option<NaiveDate>,
+    pub note: Option<String>,
+}
+
+impl Membership {
+    /// Whether the period covers `day`.
+    pub fn covers(&self, day: NaiveDate) -> bool {
+        self.effective_from.is_none_or(|f| f <= day) && self.effective_to.is_none_or(|t| day < t)
+    }
+
+    /// Whether the two periods share a day.
+    pub fn overlaps(&self, other: &Membership) -> bool {
+        let starts_before_end = |from: Option<NaiveDate>, to: Option<NaiveDate>| match (from, to) {
+            (Some(f), Some(t)) => f < t,
+            _ => true,
+        };
+        starts_before_end(self.effective_from, other.effective_to) && starts_before_end(other.effective_from, self.effective_to)
+    }
+}
+
+const MEMBERSHIP_COLUMNS: &str = "id, source, kind, group_name, ticker, effective_from, effective_to, note";
+
+// ── Write queries ──
+
+/// Insert a period (`m.id` is ignored) and return the stored row.
+pub async fn insert(pool: &PgPool, m: &Membership) -> sqlx::Result<Membership> {
+    sqlx::query_as::<_, Membership>(&format!(
+        r#"INSERT INTO group_membership (source, kind, group_name, ticker, effective_from, effective_to, note)
+           VALUES ($1, $2, $3, $4, $5, $6, $7)
+           RETURNING {MEMBERSHIP_COLUMNS}"#
+    ))
+    .bind(&m.source)
+    .bind(&m.kind)
+    .bind(&m.group_name)
+    .bind(&m.ticker)
+    .bind(m.effective_from)
+    .bind(m.effective_to)
+    .bind(&m.note)
+    .fetch_one(pool)
+    .await
+}
+
+/// Replace the period `m.id`. Returns None when it does not exist.
+pub async fn update(pool: &PgPool, m: &Membership) -> sqlx::Result<Option<Membership>> {
+    sqlx::query_as::<_, Membership>(&format!(
+        r#"UPDATE group_membership
+           SET source = $2, kind = $3, group_name = $4, ticker = $5,
+               effective_from = $6, effective_to = $7, note = $8
+           WHERE id = $1
+           RETURNING {MEMBERSHIP_COLUMNS}"#
+    ))
+    .bind(m.id)
+    .bind(&m.source)
+    .bind(&m.kind)
+    .bind(&m.group_name)
+    .bind(&m.ticker)
+    .bind(m.effective_from)
+    .bind(m.effective_to)
+    .bind(&m.note)
+    .fetch_optional(pool)
+    .await
+}
+
+pub async fn delete(pool: &PgPool, id: i64) -> sqlx::Result<bool> {
+    let result = sqlx::query("DELETE FROM group_membership WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+// ── Read queries ──
+
+/// Periods ordered by (source, kind, group, ticker, effective_from),
+/// optionally narrowed by any of the filters.
+pub async fn list(
+    pool: &PgPool,
+    source: Option<&str>,
+    kind: Option<&str>,
+    group_name: Option<&str>,
+    ticker: Option<&str>,
+) -> sqlx::Result<Vec<Membership>> {
+    sqlx::query_as::<_, Membership>(&format!(
+        r#"SELECT {MEMBERSHIP_COLUMNS}
+           FROM group_membership
+           WHERE ($1::text IS NULL OR source = $1)
+             AND ($2::text IS NULL OR kind = $2)
+             AND ($3::text IS NULL OR group_name = $3)
+             AND ($4::text IS NULL OR ticker = $4)
+           ORDER BY source, kind, group_name, ticker, effective_from NULLS FIRST"#
+    ))
+    .bind(source)
+    .bind(kind)
+    .bind(group_name)
+    .bind(ticker)
+    .fetch_all(pool)
+    .await
+}
@@ -1,3 +1,14 @@
+pub mod alerts;
+pub mod breadth;
+pub mod corporate_actions;
+pub mod custom_indices;
+pub mod gaps;
+pub mod quarantine;
 pub mod import;
+pub mod lifecycle;
+pub mod membership;
 pub mod ohlcv;
+pub mod portfolios;
+pub mod reconcile;
 pub mod s3_archive;
+pub mod ticker_metadata;
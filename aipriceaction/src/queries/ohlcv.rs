@@ -1,16 +1,65 @@
 use chrono::{DateTime, Duration, Utc};
-use sqlx::PgPool;
-
-use crate::models::indicators::{calculate_ema, calculate_ma_score, calculate_sma};
+use sqlx::{PgExecutor, PgPool};
+
+use crate::models::corporate_action::{adjust_rows, Adjustment};
+use crate::models::lifecycle::Segment;
+use crate::models::indicators::{
+    DEFAULT_MA_PERIODS, Indicator, PriceSeries, calculate_mas,
+    compute_indicators, indicators_lookback, ma_lookback,
+};
 pub use crate::models::ohlcv::{OhlcvJoined, OhlcvRow, Ticker};
+use crate::models::ohlcv::MaColumns;
+use crate::services::fx::Conversion;
 
 /// Maximum SMA period — fetch this many extra rows before the requested range
 /// to ensure all moving averages are accurate.
-use crate::constants::api::{EMA_LOOKBACK, SMA_MAX_PERIOD};
+use crate::constants::api::SMA_MAX_PERIOD;
+
+/// What a batch fetch reads and which indicators it adds. Start from
+/// `Default` and override the fields a request sets:
+/// `FetchOptions { limit: Some(100), use_ema: true, ..Default::default() }`.
+#[derive(Debug, Clone, Copy)]
+pub struct FetchOptions<'a> {
+    /// Rows kept per ticker: the newest, or the oldest from `start_time`.
+    pub limit: Option<i64>,
+    pub start_time: Option<DateTime<Utc>>,
+    pub end_time: Option<DateTime<Utc>>,
+    /// Sources read alongside the requested one (e.g. sjc for mode=yahoo).
+    pub extra_sources: &'a [&'a str],
+    /// Compute MAs and scores. Change fields are always computed.
+    pub with_ma: bool,
+    pub use_ema: bool,
+    pub ma_periods: &'a [usize],
+    pub indicators: &'a [Indicator],
+    pub adjust: Adjustment,
+    /// Let pre-computed Redis snapshots answer (`snap=`). Only the Redis
+    /// read paths look at it.
+    pub snap: bool,
+    /// `currency=` conversion, applied to raw bars before indicators.
+    pub fx: Option<&'a Conversion>,
+}
+
+impl Default for FetchOptions<'_> {
+    fn default() -> Self {
+        Self {
+            limit: None,
+            start_time: None,
+            end_time: None,
+            extra_sources: &[],
+            with_ma: true,
+            use_ema: crate::constants::api::DEFAULT_USE_EMA,
+            ma_periods: &DEFAULT_MA_PERIODS,
+            indicators: &[],
+            adjust: Adjustment::Raw,
+            snap: false,
+            fx: None,
+        }
+    }
+}
 
 /// Insert ticker if not exists, return the id.
 pub async fn upsert_ticker(
-    pool: &PgPool,
+    conn: impl PgExecutor<'_>,
     source: &str,
     ticker: &str,
     name: Option<&str>,
@@ -23,7 +72,7 @@ pub async fn upsert_ticker(
         ticker,
         name
     )
-    .fetch_one(pool)
+    .fetch_one(conn)
     .await?;
     Ok(row)
 }
@@ -91,13 +140,14 @@ pub async fn resolve_ticker_sources(
     .fetch_all(pool)
     .await?;
 
-    // Priority order: vn > yahoo > sjc > crypto
+    // Priority order: vn > yahoo > sjc > crypto > custom
     let priority = |source: &str| match source {
         "vn" => 0,
         "yahoo" => 1,
         "sjc" => 2,
         "crypto" => 3,
-        _ => 4,
+        "custom" => 4,
+        _ => 5,
     };
 
     let mut map: std::collections::HashMap<String, String> = std::collections::HashMap::new();
@@ -138,6 +188,29 @@ pub async fn get_ohlcv(
     .await
 }
 
+/// Get OHLCV rows for a ticker_id + interval with `start <= time < end`,
+/// ordered by time ASC.
+pub async fn get_ohlcv_between(
+    pool: &PgPool,
+    ticker_id: i32,
+    interval: &str,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> sqlx::Result<Vec<OhlcvRow>> {
+    sqlx::query_as::<_, OhlcvRow>(
+        r#"SELECT ticker_id, interval, time, open, high, low, close, volume
+           FROM ohlcv
+           WHERE ticker_id = $1 AND interval = $2 AND time >= $3 AND time < $4
+           ORDER BY time ASC"#,
+    )
+    .bind(ticker_id)
+    .bind(interval)
+    .bind(start)
+    .bind(end)
+    .fetch_all(pool)
+    .await
+}
+
 /// Get joined OHLCV + indicators for a ticker symbol + interval.
 /// Returns rows matching the 20-column CSV format, ordered by time DESC.
 ///
@@ -167,7 +240,7 @@ pub async fn get_ohlcv_joined(
     }
 
     let ticker_str = ticker.to_string();
-    let result = enhance_rows(&ticker_str, rows, limit, None, true, crate::constants::api::DEFAULT_USE_EMA);
+    let result = enhance_rows(&ticker_str, rows, &FetchOptions { limit, ..Default::default() });
     Ok(result)
 }
 
@@ -240,27 +313,26 @@ pub async fn get_ohlcv_joined_range(
     }
 
     let ticker_str = ticker.to_string();
-    let result = enhance_rows(&ticker_str, rows, limit, start_time, true, crate::constants::api::DEFAULT_USE_EMA);
+    let result = enhance_rows(&ticker_str, rows, &FetchOptions { limit, start_time, ..Default::default() });
     Ok(result)
 }
 
 /// Enhance a set of OHLCV rows with in-memory calculated indicators.
 ///
 /// The rows must be in time DESC order (as returned from the DB).
-/// All rows are used for SMA calculation, then filtered to `start_time`
-/// if provided, and trimmed to `limit` from the newest end.
+/// All rows are used for SMA calculation, then filtered to `opts.start_time`
+/// if provided, and trimmed to `opts.limit` from the newest end.
 ///
-/// When `with_ma` is false, SMA/score indicators are skipped (all set to None),
+/// MAs are computed for `opts.ma_periods` (see `OhlcvJoined::set_ma`). When
+/// `opts.with_ma` is false, SMA/score indicators are skipped (all set to None),
 /// saving CPU time. Change indicators (close_changed, volume_changed,
-/// total_money_changed) are still computed.
-pub fn enhance_rows(
-    ticker: &str,
-    rows: Vec<OhlcvRow>,
-    limit: Option<i64>,
-    start_time: Option<DateTime<Utc>>,
-    with_ma: bool,
-    use_ema: bool,
-) -> Vec<OhlcvJoined> {
+/// total_money_changed) are still computed, as are any `opts.indicators`
+/// requested via `indicators=`. A `opts.fx` conversion is applied to the raw
+/// bars first, so every derived value comes out in the target currency.
+/// `end_time`, `extra_sources` and `adjust` are the fetch's business and
+/// ignored here.
+pub fn enhance_rows(ticker: &str, rows: Vec<OhlcvRow>, opts: &FetchOptions<'_>) -> Vec<OhlcvJoined> {
+    let FetchOptions { limit, start_time, with_ma, use_ema, ma_periods, indicators, fx, .. } = *opts;
     if rows.is_empty() {
         return Vec::new();
     }
@@ -268,38 +340,22 @@ pub fn enhance_rows(
     // Reverse to chronological order (oldest first) for SMA calculation
     let mut chrono_rows = rows;
     chrono_rows.reverse();
+    if let Some(fx) = fx {
+        fx.convert(ticker, &mut chrono_rows);
+    }
 
     // Extract closes and volumes in chronological order
     let closes: Vec<f64> = chrono_rows.iter().map(|r| r.close).collect();
     let volumes: Vec<f64> = chrono_rows.iter().map(|r| r.volume as f64).collect();
 
     // Calculate MAs on the full dataset (skip when with_ma=false)
-    let (ma10, ma20, ma50, ma100, ma200) = if with_ma {
-        let calc_ma = if use_ema { calculate_ema } else { calculate_sma };
-        (
-            calc_ma(&closes, 10),
-            calc_ma(&closes, 20),
-            calc_ma(&closes, 50),
-            calc_ma(&closes, 100),
-            calc_ma(&closes, 200),
-        )
-    } else {
-        (vec![], vec![], vec![], vec![], vec![])
-    };
+    let mas = if with_ma { calculate_mas(&closes, ma_periods, use_ema) } else { Vec::new() };
 
-    // Build joined rows with indicators
+    // Build joined rows with change indicators; MAs are filled in below
     let mut joined: Vec<OhlcvJoined> = chrono_rows
         .iter()
         .enumerate()
         .map(|(i, r)| {
-            let make_opt = |vals: &[f64], idx: usize| -> Option<f64> {
-                if idx < vals.len() && vals[idx] > 0.0 {
-                    Some(vals[idx])
-                } else {
-                    None
-                }
-            };
-
             OhlcvJoined {
                 ticker: ticker.to_string(),
                 time: r.time,
@@ -308,16 +364,16 @@ pub fn enhance_rows(
                 low: r.low,
                 close: r.close,
                 volume: r.volume,
-                ma10: make_opt(&ma10, i),
-                ma20: make_opt(&ma20, i),
-                ma50: make_opt(&ma50, i),
-                ma100: make_opt(&ma100, i),
-                ma200: make_opt(&ma200, i),
-                ma10_score: make_opt(&ma10, i).map(|v| calculate_ma_score(r.close, v)),
-                ma20_score: make_opt(&ma20, i).map(|v| calculate_ma_score(r.close, v)),
-                ma50_score: make_opt(&ma50, i).map(|v| calculate_ma_score(r.close, v)),
-                ma100_score: make_opt(&ma100, i).map(|v| calculate_ma_score(r.close, v)),
-                ma200_score: make_opt(&ma200, i).map(|v| calculate_ma_score(r.close, v)),
+                ma10: None,
+                ma20: None,
+                ma50: None,
+                ma100: None,
+                ma200: None,
+                ma10_score: None,
+                ma20_score: None,
+                ma50_score: None,
+                ma100_score: None,
+                ma200_score: None,
                 close_changed: if i > 0 && closes[i - 1] > 0.0 {
                     Some(((r.close - closes[i - 1]) / closes[i - 1]) * 100.0)
                 } else {
@@ -333,123 +389,28 @@ pub fn enhance_rows(
                 } else {
                     None
                 },
+                indicators: Default::default(),
             }
         })
         .collect();
 
-    // Reverse back to newest-first order
-    joined.reverse();
-
-    // Post-filter: remove rows before the user's original start_time
-    if let Some(st) = start_time {
-        joined.retain(|r| r.time >= st);
-        // When start_time is set, keep the N OLDEST rows (first N from start_date)
-        // instead of the N newest. Reverse to oldest-first, truncate, reverse back.
-        if let Some(l) = limit {
-            let l = l as usize;
-            if joined.len() > l {
-                joined.reverse();
-                joined.truncate(l);
-                joined.reverse();
-            }
+    if !indicators.is_empty() {
+        let mut series = PriceSeries::default();
+        for r in &chrono_rows {
+            series.push(r.high, r.low, r.close, r.volume as f64);
         }
-    } else {
-        // No start_time: keep the N newest rows (default behavior)
-        if let Some(l) = limit {
-            let l = l as usize;
-            if joined.len() > l {
-                joined.truncate(l);
-            }
+        for (row, values) in joined.iter_mut().zip(compute_indicators(indicators, &series)) {
+            row.indicators = values;
         }
     }
 
-    joined
-}
-
-/// Like [`enhance_rows`] but only computes MAs for periods <= `max_period`.
-///
-/// This is useful for callers that only need a subset of the standard 5 MAs
-/// (10, 20, 50, 100, 200), avoiding unnecessary work and a larger lookback buffer.
-///
-/// Scores for periods > `max_period` are set to `None`.
-pub fn enhance_rows_selective(
-    ticker: &str,
-    rows: Vec<OhlcvRow>,
-    limit: Option<i64>,
-    start_time: Option<DateTime<Utc>>,
-    use_ema: bool,
-    max_period: usize,
-) -> Vec<OhlcvJoined> {
-    if rows.is_empty() {
-        return Vec::new();
-    }
-
-    // Reverse to chronological order (oldest first) for MA calculation
-    let mut chrono_rows = rows;
-    chrono_rows.reverse();
-
-    // Extract closes and volumes in chronological order
-    let closes: Vec<f64> = chrono_rows.iter().map(|r| r.close).collect();
-    let volumes: Vec<f64> = chrono_rows.iter().map(|r| r.volume as f64).collect();
-
-    let calc_ma = if use_ema { calculate_ema } else { calculate_sma };
-
-    // Only compute MAs for periods <= max_period
-    let ma10 = if max_period >= 10 { calc_ma(&closes, 10) } else { vec![] };
-    let ma20 = if max_period >= 20 { calc_ma(&closes, 20) } else { vec![] };
-    let ma50 = if max_period >= 50 { calc_ma(&closes, 50) } else { vec![] };
-    let ma100 = if max_period >= 100 { calc_ma(&closes, 100) } else { vec![] };
-    let ma200 = if max_period >= 200 { calc_ma(&closes, 200) } else { vec![] };
-
-    // Build joined rows with indicators
-    let mut joined: Vec<OhlcvJoined> = chrono_rows
-        .iter()
-        .enumerate()
-        .map(|(i, r)| {
-            let make_opt = |vals: &[f64], idx: usize| -> Option<f64> {
-                if idx < vals.len() && vals[idx] > 0.0 {
-                    Some(vals[idx])
-                } else {
-                    None
-                }
-            };
-
-            OhlcvJoined {
-                ticker: ticker.to_string(),
-                time: r.time,
-                open: r.open,
-                high: r.high,
-                low: r.low,
-                close: r.close,
-                volume: r.volume,
-                ma10: make_opt(&ma10, i),
-                ma20: make_opt(&ma20, i),
-                ma50: make_opt(&ma50, i),
-                ma100: make_opt(&ma100, i),
-                ma200: make_opt(&ma200, i),
-                ma10_score: make_opt(&ma10, i).map(|v| calculate_ma_score(r.close, v)),
-                ma20_score: make_opt(&ma20, i).map(|v| calculate_ma_score(r.close, v)),
-                ma50_score: make_opt(&ma50, i).map(|v| calculate_ma_score(r.close, v)),
-                ma100_score: make_opt(&ma100, i).map(|v| calculate_ma_score(r.close, v)),
-                ma200_score: make_opt(&ma200, i).map(|v| calculate_ma_score(r.close, v)),
-                close_changed: if i > 0 && closes[i - 1] > 0.0 {
-                    Some(((r.close - closes[i - 1]) / closes[i - 1]) * 100.0)
-                } else {
-                    None
-                },
-                volume_changed: if i > 0 && volumes[i - 1] > 0.0 {
-                    Some(((volumes[i] - volumes[i - 1]) / volumes[i - 1]) * 100.0)
-                } else {
-                    None
-                },
-                total_money_changed: if i > 0 && closes[i - 1] > 0.0 {
-                    Some((r.close - closes[i - 1]) * r.volume as f64)
-                } else {
-                    None
-                },
+    for (period, values) in &mas {
+        for (row, &v) in joined.iter_mut().zip(values) {
+            if v > 0.0 {
+                row.set_ma(*period, v);
             }
-        })
-        .collect();
+        }
+    }
 
     // Reverse back to newest-first order
     joined.reverse();
@@ -458,6 +419,7 @@ pub fn enhance_rows_selective(
     if let Some(st) = start_time {
         joined.retain(|r| r.time >= st);
         // When start_time is set, keep the N OLDEST rows (first N from start_date)
+        // instead of the N newest. Reverse to oldest-first, truncate, reverse back.
         if let Some(l) = limit {
             let l = l as usize;
             if joined.len() > l {
@@ -485,22 +447,42 @@ pub fn enhance_rows_selective(
 /// When `symbols` is empty, fetches ALL tickers for the source.
 /// When `symbols` is non-empty, fetches only the specified tickers.
 ///
-/// `per_ticker_limit` caps rows per ticker (no cap if None).
+/// `opts.limit` caps rows per ticker (no cap if None); `opts.start_time`,
+/// `opts.end_time` and `opts.extra_sources` bound the query.
 /// `lookback_minutes` shifts `start_time` backwards for SMA accuracy.
+///
+/// A requested symbol that is part of a rename chain (`ticker_lifecycle`) is
+/// served as one history stitched from every symbol of the chain, each up to
+/// its rename date, and returned under the newest symbol.
 #[tracing::instrument(skip(pool))]
 async fn fetch_ohlcv_batch_raw(
     pool: &PgPool,
     source: &str,
     symbols: &[String],
-    extra_sources: &[&str],
     interval: &str,
-    per_ticker_limit: Option<i64>,
-    start_time: Option<DateTime<Utc>>,
-    end_time: Option<DateTime<Utc>>,
+    opts: &FetchOptions<'_>,
     lookback_minutes: Option<i64>,
 ) -> sqlx::Result<std::collections::HashMap<String, Vec<OhlcvRow>>> {
     use std::collections::HashMap;
 
+    let FetchOptions { limit: per_ticker_limit, start_time, end_time, extra_sources, .. } = *opts;
+
+    let chains: Vec<Vec<Segment>> = {
+        let catalog = crate::services::metadata::catalog();
+        symbols.iter().filter_map(|s| catalog.rename_chain(source, s)).collect()
+    };
+    let expanded: Vec<String>;
+    let symbols = if chains.is_empty() {
+        symbols
+    } else {
+        let mut all: Vec<String> = symbols.to_vec();
+        all.extend(chains.iter().flatten().map(|seg| seg.ticker.clone()));
+        all.sort();
+        all.dedup();
+        expanded = all;
+        &expanded[..]
+    };
+
     // Fetch ticker IDs + names
     let tickers: Vec<Ticker> = if symbols.is_empty() {
         list_tickers_with_extra(pool, source, extra_sources).await?
@@ -533,9 +515,13 @@ async fn fetch_ohlcv_batch_raw(
     }
 
     let ticker_ids: Vec<i32> = tickers.iter().map(|t| t.id).collect();
+    let windows = stitch_windows(&tickers, source, &chains);
     let ticker_names: HashMap<i32, String> = tickers
         .into_iter()
-        .map(|t| (t.id, t.ticker))
+        .map(|t| {
+            let name = windows.get(&t.id).map_or(t.ticker, |w| w.symbol.clone());
+            (t.id, name)
+        })
         .collect();
 
     // Shift start_time back for SMA lookback
@@ -560,7 +546,21 @@ async fn fetch_ohlcv_batch_raw(
         }
         if end_time.is_some() {
             lateral_conditions.push(format!("time <= ${param_idx}"));
+            param_idx += 1;
         }
+        // Stitched symbols only read their own part of the chain
+        let (from_col, until_col) = if windows.is_empty() {
+            ("", "")
+        } else {
+            lateral_conditions.push("(t.from_t IS NULL OR time >= t.from_t)".to_string());
+            lateral_conditions.push("(t.until_t IS NULL OR time < t.until_t)".to_string());
+            (", from_t", ", until_t")
+        };
+        let unnest_args = if windows.is_empty() {
+            "$1::int[]".to_string()
+        } else {
+            format!("$1::int[], ${param_idx}::timestamptz[], ${}::timestamptz[]", param_idx + 1)
+        };
         let lateral_where = lateral_conditions.join(" AND ");
 
         // When start_time is set, fetch oldest rows first (ASC) so the per-ticker
@@ -573,7 +573,7 @@ async fn fetch_ohlcv_batch_raw(
 
         let sql = format!(
             r#"SELECT o.ticker_id, o.interval, o.time, o.open, o.high, o.low, o.close, o.volume
-               FROM unnest($1::int[]) AS t(id)
+               FROM unnest({unnest_args}) AS t(id{from_col}{until_col})
                CROSS JOIN LATERAL (
                    SELECT ticker_id, interval, time, open, high, low, close, volume
                    FROM ohlcv
@@ -593,18 +593,15 @@ async fn fetch_ohlcv_batch_raw(
         if let Some(e) = end_time {
             q = q.bind(e);
         }
+        if !windows.is_empty() {
+            let bound = |f: fn(&StitchWindow) -> Option<DateTime<Utc>>| {
+                ticker_ids.iter().map(|id| windows.get(id).and_then(f)).collect::<Vec<_>>()
+            };
+            q = q.bind(bound(|w| w.from)).bind(bound(|w| w.until));
+        }
 
         let rows: Vec<OhlcvRow> = q.fetch_all(pool).await?;
-
-        let mut by_ticker: HashMap<String, Vec<OhlcvRow>> = HashMap::new();
-        for row in rows {
-            let name = ticker_names
-                .get(&row.ticker_id)
-                .cloned()
-                .unwrap_or_default();
-            by_ticker.entry(name).or_default().push(row);
-        }
-        return Ok(by_ticker);
+        return Ok(group_rows(rows, &ticker_names, &windows, start_time.is_none(), per_ticker_limit));
     }
 
     // No per-ticker limit path
@@ -644,10 +641,56 @@ async fn fetch_ohlcv_batch_raw(
     }
 
     let rows: Vec<OhlcvRow> = q.fetch_all(pool).await?;
+    Ok(group_rows(rows, &ticker_names, &windows, start_time.is_none(), None))
+}
 
-    // Group by ticker name
-    let mut by_ticker: HashMap<String, Vec<OhlcvRow>> = HashMap::new();
+/// Where one ticker's bars go in a stitched history: the newest symbol of
+/// its rename chain and the time range it covers.
+struct StitchWindow {
+    symbol: String,
+    from: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+}
+
+/// Windows of the `source` tickers that belong to one of `chains`.
+fn stitch_windows(
+    tickers: &[Ticker],
+    source: &str,
+    chains: &[Vec<Segment>],
+) -> std::collections::HashMap<i32, StitchWindow> {
+    let at_midnight = |d: chrono::NaiveDate| d.and_hms_opt(0, 0, 0).map(|t| t.and_utc());
+    let mut windows = std::collections::HashMap::new();
+    for chain in chains {
+        let Some(head) = chain.last() else { continue };
+        for seg in chain {
+            let Some(t) = tickers.iter().find(|t| t.source == source && t.ticker == seg.ticker) else { continue };
+            windows.insert(t.id, StitchWindow {
+                symbol: head.ticker.clone(),
+                from: seg.from.and_then(at_midnight),
+                until: seg.until.and_then(at_midnight),
+            });
+        }
+    }
+    windows
+}
+
+/// Group rows (ordered by ticker_id, then time) by ticker name. Rows of
+/// stitched tickers are trimmed to their window, merged under the newest
+/// symbol in time order (`desc` = newest first) and capped at `limit`.
+fn group_rows(
+    rows: Vec<OhlcvRow>,
+    ticker_names: &std::collections::HashMap<i32, String>,
+    windows: &std::collections::HashMap<i32, StitchWindow>,
+    desc: bool,
+    limit: Option<i64>,
+) -> std::collections::HashMap<String, Vec<OhlcvRow>> {
+    let mut by_ticker: std::collections::HashMap<String, Vec<OhlcvRow>> = std::collections::HashMap::new();
     for row in rows {
+        if let Some(w) = windows.get(&row.ticker_id)
+            && (w.from.is_some_and(|f| row.time < f) || w.until.is_some_and(|u| row.time >= u))
+        {
+            continue;
+        }
         let name = ticker_names
             .get(&row.ticker_id)
             .cloned()
@@ -655,55 +698,59 @@ async fn fetch_ohlcv_batch_raw(
         by_ticker.entry(name).or_default().push(row);
     }
 
-    Ok(by_ticker)
+    let stitched: std::collections::HashSet<&str> = windows.values().map(|w| w.symbol.as_str()).collect();
+    for symbol in stitched {
+        let Some(rows) = by_ticker.get_mut(symbol) else { continue };
+        if desc {
+            rows.sort_by_key(|r| std::cmp::Reverse(r.time));
+        } else {
+            rows.sort_by_key(|r| r.time);
+        }
+        if let Some(limit) = limit {
+            rows.truncate(limit.max(0) as usize);
+        }
+    }
+    by_ticker
 }
 
 /// Batch-fetch joined OHLCV + indicators for tickers of a source + interval.
 ///
-/// When `symbols` is empty, fetches ALL tickers for the source.
-/// When `symbols` is non-empty, fetches only the specified tickers.
+/// When `symbols` is empty, fetches ALL tickers for the source (plus
+/// `opts.extra_sources`). When `symbols` is non-empty, fetches only the
+/// specified tickers.
 ///
-/// For `limit`, each ticker gets at most `limit` result rows. The underlying query
-/// fetches up to `limit + SMA_MAX_PERIOD` rows per ticker for accurate SMA.
-/// When `with_ma` is false, no SMA buffer is added and MA indicators are skipped.
+/// Each ticker gets at most `opts.limit` result rows. The underlying query
+/// fetches enough extra rows per ticker for the warm-up of `opts.ma_periods`
+/// and `opts.indicators`; when `opts.with_ma` is false, no MA buffer is added
+/// and MA indicators are skipped. Prices are back-adjusted for recorded
+/// corporate actions (`adjust=split|total`) before indicators are computed.
 #[tracing::instrument(skip(pool))]
 pub async fn get_ohlcv_joined_batch(
     pool: &PgPool,
     source: &str,
     symbols: &[String],
     interval: &str,
-    limit: Option<i64>,
-    start_time: Option<DateTime<Utc>>,
-    end_time: Option<DateTime<Utc>>,
-    with_ma: bool,
-    use_ema: bool,
-) -> sqlx::Result<std::collections::HashMap<String, Vec<OhlcvJoined>>> {
-    get_ohlcv_joined_batch_with_extra(pool, source, symbols, interval, limit, start_time, end_time, &[], with_ma, use_ema).await
-}
-
-pub async fn get_ohlcv_joined_batch_with_extra(
-    pool: &PgPool,
-    source: &str,
-    symbols: &[String],
-    interval: &str,
-    limit: Option<i64>,
-    start_time: Option<DateTime<Utc>>,
-    end_time: Option<DateTime<Utc>>,
-    extra_sources: &[&str],
-    with_ma: bool,
-    use_ema: bool,
+    opts: &FetchOptions<'_>,
 ) -> sqlx::Result<std::collections::HashMap<String, Vec<OhlcvJoined>>> {
     use std::collections::HashMap;
 
-    let ma_buffer: i64 = if use_ema { EMA_LOOKBACK } else { SMA_MAX_PERIOD };
-    let per_ticker = limit.map(|l| if with_ma { (l + ma_buffer) as i64 } else { l + 1 });
-    let lookback = if with_ma { limit.map(|_| interval_duration(interval) * ma_buffer) } else { None };
+    let FetchOptions { limit, start_time, extra_sources, with_ma, use_ema, ma_periods, indicators, adjust, .. } = *opts;
+    let ma_buffer = ma_lookback(ma_periods, use_ema);
+    let buffer = if with_ma { ma_buffer } else { 1 }.max(indicators_lookback(indicators));
+    let per_ticker = limit.map(|l| l + buffer);
+    let lookback = if buffer > 1 { limit.map(|_| interval_duration(interval) * buffer) } else { None };
 
     let raw = fetch_ohlcv_batch_raw(
-        pool, source, symbols, extra_sources, interval,
-        per_ticker, start_time, end_time, lookback,
+        pool, source, symbols, interval, &FetchOptions { limit: per_ticker, ..*opts }, lookback,
     ).await?;
 
+    let actions = if adjust == Adjustment::Raw {
+        HashMap::new()
+    } else {
+        let sources: Vec<&str> = std::iter::once(source).chain(extra_sources.iter().copied()).collect();
+        super::corporate_actions::list_for_tickers(pool, &sources, symbols).await?
+    };
+
     // Enhance each group
     // When start_time is set, fetch_ohlcv_batch_raw returns rows in ASC (oldest-first)
     // order, but enhance_rows expects DESC (newest-first). Reverse before enhancing.
@@ -713,7 +760,10 @@ pub async fn get_ohlcv_joined_batch_with_extra(
         if need_reverse {
             ticker_rows.reverse();
         }
-        let joined = enhance_rows(&ticker, ticker_rows, limit, start_time, with_ma, use_ema);
+        if let Some(ticker_actions) = actions.get(&ticker) {
+            adjust_rows(&mut ticker_rows, ticker_actions, adjust);
+        }
+        let joined = enhance_rows(&ticker, ticker_rows, opts);
         result.insert(ticker, joined);
     }
 
@@ -733,23 +783,21 @@ pub async fn get_ohlcv_batch_raw(
     start_time: Option<DateTime<Utc>>,
     end_time: Option<DateTime<Utc>>,
 ) -> sqlx::Result<std::collections::HashMap<String, Vec<OhlcvRow>>> {
-    get_ohlcv_batch_raw_with_extra(pool, source, symbols, interval, per_ticker_limit, start_time, end_time, &[]).await
+    let opts = FetchOptions { limit: per_ticker_limit, start_time, end_time, ..Default::default() };
+    fetch_ohlcv_batch_raw(pool, source, symbols, interval, &opts, None).await
 }
 
+/// Like `get_ohlcv_batch_raw` but also includes tickers from
+/// `opts.extra_sources`. `opts.limit` is the per-ticker row cap; no MA
+/// lookback is added and the indicator fields are ignored.
 pub async fn get_ohlcv_batch_raw_with_extra(
     pool: &PgPool,
     source: &str,
     symbols: &[String],
     interval: &str,
-    per_ticker_limit: Option<i64>,
-    start_time: Option<DateTime<Utc>>,
-    end_time: Option<DateTime<Utc>>,
-    extra_sources: &[&str],
+    opts: &FetchOptions<'_>,
 ) -> sqlx::Result<std::collections::HashMap<String, Vec<OhlcvRow>>> {
-    fetch_ohlcv_batch_raw(
-        pool, source, symbols, extra_sources, interval,
-        per_ticker_limit, start_time, end_time, None,
-    ).await
+    fetch_ohlcv_batch_raw(pool, source, symbols, interval, opts, None).await
 }
 
 /// Get the duration of one row for the given interval.
@@ -834,12 +882,18 @@ async fn source_ticker_ids(pool: &PgPool, source: &str) -> sqlx::Result<Vec<i32>
 /// Get the latest daily record for each ticker of a given source.
 /// Uses DISTINCT ON for a single efficient query.
 ///
-/// Indicators are calculated in-memory from the last 200+ daily rows per ticker.
+/// Indicators are calculated in-memory from enough recent daily rows per ticker
+/// to cover the longest of `opts.ma_periods`, with `opts.use_ema` and
+/// `opts.fx`; the other fields are ignored.
 pub async fn get_latest_daily_per_ticker(
     pool: &PgPool,
     source: &str,
+    opts: &FetchOptions<'_>,
 ) -> sqlx::Result<Vec<OhlcvJoined>> {
-    // Fetch latest 200+1 daily rows per ticker for accurate SMA(200)
+    let FetchOptions { use_ema, ma_periods, fx, .. } = *opts;
+    let keep = ma_lookback(ma_periods, use_ema).max(SMA_MAX_PERIOD) as usize + 1;
+
+    // Fetch the latest daily rows per ticker; trimmed below to what the longest MA needs
     let rows = sqlx::query_as::<_, OhlcvRow>(
         r#"SELECT ticker_id, interval, time, open, high, low, close, volume
            FROM ohlcv
@@ -852,7 +906,7 @@ pub async fn get_latest_daily_per_ticker(
     .fetch_all(pool)
     .await?;
 
-    // Group by ticker_id, keep at most SMA_MAX_PERIOD + 1 rows per ticker (for accuracy)
+    // Group by ticker_id, keep at most `keep` rows per ticker (for accuracy)
     let mut by_ticker: std::collections::HashMap<i32, Vec<OhlcvRow>> = std::collections::HashMap::new();
     for row in rows {
         by_ticker
@@ -870,9 +924,9 @@ pub async fn get_latest_daily_per_ticker(
 
     let mut result = Vec::new();
     for (ticker_id, mut ticker_rows) in by_ticker {
-        // Already in DESC order, trim to SMA_MAX_PERIOD + 1
-        if ticker_rows.len() > SMA_MAX_PERIOD as usize + 1 {
-            ticker_rows.truncate(SMA_MAX_PERIOD as usize + 1);
+        // Already in DESC order, trim to the MA lookback + 1
+        if ticker_rows.len() > keep {
+            ticker_rows.truncate(keep);
         }
 
         if ticker_rows.is_empty() {
@@ -885,7 +939,7 @@ pub async fn get_latest_daily_per_ticker(
             .unwrap_or_default();
 
         // Enhance with all rows for accurate indicators
-        let joined = enhance_rows(&ticker_str, ticker_rows, Some(1), None, true, crate::constants::api::DEFAULT_USE_EMA);
+        let joined = enhance_rows(&ticker_str, ticker_rows, &FetchOptions { limit: Some(1), use_ema, ma_periods, fx, ..Default::default() });
         result.extend(joined);
     }
 
@@ -1044,26 +1098,34 @@ pub async fn get_earliest_time(
     .await
 }
 
-/// Get latest time for a ticker + interval. Returns None if no data exists.
-pub async fn get_last_time(
+/// Record which upstream served the latest sync for a ticker + interval.
+pub async fn record_fetch_source(
     pool: &PgPool,
     ticker_id: i32,
     interval: &str,
-) -> sqlx::Result<Option<DateTime<Utc>>> {
-    sqlx::query_scalar(
-        r#"SELECT time FROM ohlcv
-           WHERE ticker_id = $1 AND interval = $2
-           ORDER BY time DESC LIMIT 1"#,
+    provider: &str,
+    fallback: bool,
+) -> sqlx::Result<()> {
+    sqlx::query(
+        r#"INSERT INTO ohlcv_fetch_source (ticker_id, interval, provider, fallback, fetched_at)
+           VALUES ($1, $2, $3, $4, NOW())
+           ON CONFLICT (ticker_id, interval)
+           DO UPDATE SET provider = EXCLUDED.provider, fallback = EXCLUDED.fallback, fetched_at = NOW()"#,
     )
     .bind(ticker_id)
     .bind(interval)
-    .fetch_optional(pool)
-    .await
+    .bind(provider)
+    .bind(fallback)
+    .execute(pool)
+    .await?;
+    Ok(())
 }
 
 // ── Priority scheduling queries ──
 
 /// Fetch all tickers that are due for processing based on a `next_*` column.
+/// Tickers whose current `ticker_lifecycle` state is not `active` (suspended,
+/// delisted, renamed, merged) are never due.
 pub async fn get_due_tickers(
     pool: &PgPool,
     source: &str,
@@ -1079,6 +1141,11 @@ pub async fn get_due_tickers(
         r#"SELECT id, source, ticker, name, status, next_1d
            FROM tickers
            WHERE source = $1 AND status = 'ready' AND {next_col} < NOW()
+             AND COALESCE((
+                 SELECT l.state FROM ticker_lifecycle l
+                 WHERE l.ticker_id = tickers.id AND l.effective_on <= CURRENT_DATE
+                 ORDER BY l.effective_on DESC LIMIT 1
+             ), 'active') = 'active'
            ORDER BY {next_col} ASC"#
     );
 
@@ -0,0 +1,166 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use sqlx::PgPool;
+
+use crate::models::portfolio::{Currency, Transaction, TxKind};
+
+// ── Data structures ──
+
+/// Portfolio as stored in `portfolios` and returned by /portfolios.
+#[derive(Debug, Clone, serde::Serialize, sqlx::FromRow)]
+pub struct Portfolio {
+    pub id: i64,
+    pub name: String,
+    pub base_currency: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Portfolio {
+    pub fn base(&self) -> Currency {
+        Currency::from_db(&self.base_currency).unwrap_or(Currency::Vnd)
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct TransactionRow {
+    id: i64,
+    kind: String,
+    trade_date: NaiveDate,
+    source: Option<String>,
+    ticker: Option<String>,
+    quantity: f64,
+    price: f64,
+    amount: f64,
+    fee: f64,
+    currency: String,
+    note: Option<String>,
+}
+
+impl TransactionRow {
+    fn into_transaction(self) -> Option<Transaction> {
+        Some(Transaction {
+            id: self.id,
+            kind: TxKind::from_db(&self.kind)?,
+            trade_date: self.trade_date,
+            source: self.source,
+            ticker: self.ticker,
+            quantity: self.quantity,
+            price: self.price,
+            amount: self.amount,
+            fee: self.fee,
+            currency: Currency::from_db(&self.currency)?,
+            note: self.note,
+        })
+    }
+}
+
+const PORTFOLIO_COLUMNS: &str = "id, name, base_currency, created_at, updated_at";
+
+// ── Portfolios ──
+
+pub async fn create_portfolio(pool: &PgPool, owner: &str, name: &str, base: Currency) -> sqlx::Result<Portfolio> {
+    sqlx::query_as::<_, Portfolio>(&format!(
+        r#"INSERT INTO portfolios (owner, name, base_currency)
+           VALUES ($1, $2, $3)
+           RETURNING {PORTFOLIO_COLUMNS}"#
+    ))
+    .bind(owner)
+    .bind(name)
+    .bind(base.as_str())
+    .fetch_one(pool)
+    .await
+}
+
+pub async fn list_portfolios(pool: &PgPool, owner: &str) -> sqlx::Result<Vec<Portfolio>> {
+    sqlx::query_as::<_, Portfolio>(&format!(
+        "SELECT {PORTFOLIO_COLUMNS} FROM portfolios WHERE owner = $1 ORDER BY id"
+    ))
+    .bind(owner)
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn get_portfolio(pool: &PgPool, owner: &str, id: i64) -> sqlx::Result<Option<Portfolio>> {
+    sqlx::query_as::<_, Portfolio>(&format!(
+        "SELECT {PORTFOLIO_COLUMNS} FROM portfolios WHERE id = $1 AND owner = $2"
+    ))
+    .bind(id)
+    .bind(owner)
+    .fetch_optional(pool)
+    .await
+}
+
+pub async fn count_portfolios(pool: &PgPool, owner: &str) -> sqlx::Result<i64> {
+    sqlx::query_scalar("SELECT COUNT(*) FROM portfolios WHERE owner = $1")
+        .bind(owner)
+        .fetch_one(pool)
+        .await
+}
+
+/// Deletes the portfolio and its ledger.
+pub async fn delete_portfolio(pool: &PgPool, owner: &str, id: i64) -> sqlx::Result<bool> {
+    let result = sqlx::query("DELETE FROM portfolios WHERE id = $1 AND owner = $2")
+        .bind(id)
+        .bind(owner)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+// ── Transactions ──
+
+/// The whole ledger in replay order.
+pub async fn list_transactions(pool: &PgPool, portfolio_id: i64) -> sqlx::Result<Vec<Transaction>> {
+    let rows = sqlx::query_as::<_, TransactionRow>(
+        r#"SELECT id, kind, trade_date, source, ticker, quantity, price, amount, fee, currency, note
+           FROM portfolio_transactions
+           WHERE portfolio_id = $1
+           ORDER BY trade_date, id"#,
+    )
+    .bind(portfolio_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.into_iter().filter_map(TransactionRow::into_transaction).collect())
+}
+
+pub async fn insert_transaction(pool: &PgPool, portfolio_id: i64, tx: &Transaction) -> sqlx::Result<i64> {
+    let id = sqlx::query_scalar(
+        r#"INSERT INTO portfolio_transactions
+               (portfolio_id, kind, trade_date, source, ticker, quantity, price, amount, fee, currency, note)
+           VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+           RETURNING id"#,
+    )
+    .bind(portfolio_id)
+    .bind(tx.kind.as_str())
+    .bind(tx.trade_date)
+    .bind(&tx.source)
+    .bind(&tx.ticker)
+    .bind(tx.quantity)
+    .bind(tx.price)
+    .bind(tx.amount)
+    .bind(tx.fee)
+    .bind(tx.currency.as_str())
+    .bind(&tx.note)
+    .fetch_one(pool)
+    .await?;
+    touch(pool, portfolio_id).await?;
+    Ok(id)
+}
+
+pub async fn delete_transaction(pool: &PgPool, portfolio_id: i64, id: i64) -> sqlx::Result<bool> {
+    let result = sqlx::query("DELETE FROM portfolio_transactions WHERE id = $1 AND portfolio_id = $2")
+        .bind(id)
+        .bind(portfolio_id)
+        .execute(pool)
+        .await?;
+    touch(pool, portfolio_id).await?;
+    Ok(result.rows_affected() > 0)
+}
+
+async fn touch(pool: &PgPool, portfolio_id: i64) -> sqlx::Result<()> {
+    sqlx::query("UPDATE portfolios SET updated_at = NOW() WHERE id = $1")
+        .bind(portfolio_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
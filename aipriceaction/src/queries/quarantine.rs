@@ -0,0 +1,96 @@
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+
+use crate::models::ohlcv::OhlcvRow;
+
+// ── Data structures ──
+
+/// A provider bar rejected by a validation rule.
+#[derive(Clone)]
+pub struct QuarantinedRow {
+    pub row: OhlcvRow,
+    pub rule: &'static str,
+    pub reason: String,
+}
+
+// ── Write queries ──
+
+/// Upsert quarantined bars for one source. Re-fetches of the same bad bar
+/// refresh the values and bump `seen_count`.
+pub async fn save_quarantined(pool: &PgPool, source: &str, items: &[QuarantinedRow]) -> sqlx::Result<u64> {
+    if items.is_empty() {
+        return Ok(0);
+    }
+
+    let ticker_ids: Vec<i32> = items.iter().map(|q| q.row.ticker_id).collect();
+    let intervals: Vec<&str> = items.iter().map(|q| q.row.interval.as_str()).collect();
+    let times: Vec<DateTime<Utc>> = items.iter().map(|q| q.row.time).collect();
+    let opens: Vec<f64> = items.iter().map(|q| q.row.open).collect();
+    let highs: Vec<f64> = items.iter().map(|q| q.row.high).collect();
+    let lows: Vec<f64> = items.iter().map(|q| q.row.low).collect();
+    let closes: Vec<f64> = items.iter().map(|q| q.row.close).collect();
+    let volumes: Vec<i64> = items.iter().map(|q| q.row.volume).collect();
+    let rules: Vec<&str> = items.iter().map(|q| q.rule).collect();
+    let reasons: Vec<&str> = items.iter().map(|q| q.reason.as_str()).collect();
+
+    let result = sqlx::query(
+        r#"INSERT INTO ohlcv_quarantine
+               (ticker_id, interval, time, open, high, low, close, volume, source, rule, reason)
+           SELECT t.ticker_id, t.interval, t.time, t.open, t.high, t.low, t.close, t.volume, $1, t.rule, t.reason
+           FROM UNNEST($2::int[], $3::text[], $4::timestamptz[], $5::float8[], $6::float8[],
+                       $7::float8[], $8::float8[], $9::int8[], $10::text[], $11::text[])
+                AS t(ticker_id, interval, time, open, high, low, close, volume, rule, reason)
+           ON CONFLICT (ticker_id, interval, time, rule)
+           DO UPDATE SET open         = EXCLUDED.open,
+                         high         = EXCLUDED.high,
+                         low          = EXCLUDED.low,
+                         close        = EXCLUDED.close,
+                         volume       = EXCLUDED.volume,
+                         source       = EXCLUDED.source,
+                         reason       = EXCLUDED.reason,
+                         seen_count   = ohlcv_quarantine.seen_count + 1,
+                         last_seen_at = NOW()"#,
+    )
+    .bind(source)
+    .bind(&ticker_ids)
+    .bind(&intervals)
+    .bind(&times)
+    .bind(&opens)
+    .bind(&highs)
+    .bind(&lows)
+    .bind(&closes)
+    .bind(&volumes)
+    .bind(&rules)
+    .bind(&reasons)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+// ── Read queries ──
+
+/// The newest `limit` stored bars before `before`, returned oldest first.
+pub async fn recent_bars_before(
+    pool: &PgPool,
+    ticker_id: i32,
+    interval: &str,
+    before: DateTime<Utc>,
+    limit: i64,
+) -> sqlx::Result<Vec<OhlcvRow>> {
+    let mut rows = sqlx::query_as::<_, OhlcvRow>(
+        r#"SELECT ticker_id, interval, time, open, high, low, close, volume
+           FROM ohlcv
+           WHERE ticker_id = $1 AND interval = $2 AND time < $3
+           ORDER BY time DESC
+           LIMIT $4"#,
+    )
+    .bind(ticker_id)
+    .bind(interval)
+    .bind(before)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+    rows.reverse();
+    Ok(rows)
+}
@@ -0,0 +1,108 @@
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+
+// ── Data structures ──
+
+/// One OHLCV field on one bar where two providers disagree.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Discrepancy {
+    pub time: DateTime<Utc>,
+    pub field: &'static str,
+    pub reference_value: f64,
+    pub other_value: f64,
+    pub diff_pct: f64,
+}
+
+/// Stored discrepancy joined with its ticker, as returned by /admin/discrepancies.
+#[derive(Debug, Clone, serde::Serialize, sqlx::FromRow)]
+pub struct DiscrepancyRow {
+    pub id: i64,
+    pub ticker: String,
+    pub interval: String,
+    pub time: DateTime<Utc>,
+    pub field: String,
+    pub reference_source: String,
+    pub other_source: String,
+    pub reference_value: f64,
+    pub other_value: f64,
+    pub diff_pct: f64,
+    pub detected_at: DateTime<Utc>,
+}
+
+// ── Write queries ──
+
+/// Upsert discrepancies for one ticker + interval + source pair.
+pub async fn save_discrepancies(
+    pool: &PgPool,
+    ticker_id: i32,
+    interval: &str,
+    reference_source: &str,
+    other_source: &str,
+    items: &[Discrepancy],
+) -> sqlx::Result<u64> {
+    if items.is_empty() {
+        return Ok(0);
+    }
+
+    let times: Vec<DateTime<Utc>> = items.iter().map(|d| d.time).collect();
+    let fields: Vec<&str> = items.iter().map(|d| d.field).collect();
+    let reference_values: Vec<f64> = items.iter().map(|d| d.reference_value).collect();
+    let other_values: Vec<f64> = items.iter().map(|d| d.other_value).collect();
+    let diffs: Vec<f64> = items.iter().map(|d| d.diff_pct).collect();
+
+    let result = sqlx::query(
+        r#"INSERT INTO ohlcv_discrepancies
+               (ticker_id, interval, time, field, reference_source, other_source,
+                reference_value, other_value, diff_pct, detected_at)
+           SELECT $1, $2, t.time, t.field, $3, $4, t.reference_value, t.other_value, t.diff_pct, NOW()
+           FROM UNNEST($5::timestamptz[], $6::text[], $7::float8[], $8::float8[], $9::float8[])
+                AS t(time, field, reference_value, other_value, diff_pct)
+           ON CONFLICT (ticker_id, interval, time, field, reference_source, other_source)
+           DO UPDATE SET reference_value = EXCLUDED.reference_value,
+                         other_value     = EXCLUDED.other_value,
+                         diff_pct        = EXCLUDED.diff_pct,
+                         detected_at     = NOW()"#,
+    )
+    .bind(ticker_id)
+    .bind(interval)
+    .bind(reference_source)
+    .bind(other_source)
+    .bind(&times)
+    .bind(&fields)
+    .bind(&reference_values)
+    .bind(&other_values)
+    .bind(&diffs)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+// ── Read queries ──
+
+/// Most recent discrepancies, newest first. All filters are optional.
+pub async fn list_discrepancies(
+    pool: &PgPool,
+    ticker: Option<&str>,
+    interval: Option<&str>,
+    since: Option<DateTime<Utc>>,
+    limit: i64,
+) -> sqlx::Result<Vec<DiscrepancyRow>> {
+    sqlx::query_as::<_, DiscrepancyRow>(
+        r#"SELECT d.id, t.ticker, d.interval, d.time, d.field, d.reference_source, d.other_source,
+                  d.reference_value, d.other_value, d.diff_pct, d.detected_at
+           FROM ohlcv_discrepancies d
+           JOIN tickers t ON t.id = d.ticker_id
+           WHERE ($1::text IS NULL OR t.ticker = $1)
+             AND ($2::text IS NULL OR d.interval = $2)
+             AND ($3::timestamptz IS NULL OR d.detected_at >= $3)
+           ORDER BY d.detected_at DESC, d.id DESC
+           LIMIT $4"#,
+    )
+    .bind(ticker)
+    .bind(interval)
+    .bind(since)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
@@ -0,0 +1,161 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use sqlx::{PgExecutor, PgPool};
+
+// ── Data structures ──
+
+/// Reference data of one (source, ticker), as stored in `ticker_metadata`
+/// and returned by /admin/tickers.
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, sqlx::FromRow)]
+pub struct TickerMetadata {
+    pub source: String,
+    pub ticker: String,
+    pub name: Option<String>,
+    /// English name (VN listings)
+    pub en_name: Option<String>,
+    /// HOSE, HNX, UPCOM, ...
+    pub exchange: Option<String>,
+    /// Quote currency; None when the series is not a price
+    pub currency: Option<String>,
+    /// stock, fund, cw, bond, index, ...
+    pub asset_type: Option<String>,
+    /// Group the ticker is listed under; None = not in any group
+    pub sector: Option<String>,
+    pub industry: Option<String>,
+    pub listed_on: Option<NaiveDate>,
+    pub delisted_on: Option<NaiveDate>,
+    pub lot_size: Option<f64>,
+    pub tick_size: Option<f64>,
+    /// Other symbols the ticker is known by (e.g. `VFS` for Yahoo `VFS:US`)
+    pub aliases: Vec<String>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+const METADATA_COLUMNS: &str = "source, ticker, name, en_name, exchange, currency, asset_type, sector, industry, \
+    listed_on, delisted_on, lot_size, tick_size, aliases, updated_at";
+
+// ── Write queries ──
+
+/// Insert seed rows that are not in the table yet; existing rows (and any
+/// admin edits to them) are left alone. Returns the number inserted.
+pub async fn insert_missing(pool: &PgPool, rows: &[TickerMetadata]) -> sqlx::Result<u64> {
+    if rows.is_empty() {
+        return Ok(0);
+    }
+    let text_col = |f: fn(&TickerMetadata) -> Option<&String>| rows.iter().map(|r| f(r).cloned()).collect::<Vec<Option<String>>>();
+    let float_col = |f: fn(&TickerMetadata) -> Option<f64>| rows.iter().map(f).collect::<Vec<Option<f64>>>();
+    let date_col = |f: fn(&TickerMetadata) -> Option<NaiveDate>| rows.iter().map(f).collect::<Vec<Option<NaiveDate>>>();
+
+    // Postgres cannot UNNEST a ragged text[][], so aliases travel as one
+    // comma-separated string per row.
+    let result = sqlx::query(
+        r#"INSERT INTO ticker_metadata (source, ticker, name, en_name, exchange, currency, asset_type, sector, industry,
+                                        listed_on, delisted_on, lot_size, tick_size, aliases)
+           SELECT source, ticker, name, en_name, exchange, currency, asset_type, sector, industry,
+                  listed_on, delisted_on, lot_size, tick_size,
+                  COALESCE(string_to_array(NULLIF(aliases, ''), ','), '{}')
+           FROM UNNEST($1::text[], $2::text[], $3::text[], $4::text[], $5::text[], $6::text[], $7::text[], $8::text[],
+                       $9::text[], $10::date[], $11::date[], $12::float8[], $13::float8[], $14::text[])
+                AS t(source, ticker, name, en_name, exchange, currency, asset_type, sector, industry,
+                     listed_on, delisted_on, lot_size, tick_size, aliases)
+           ON CONFLICT (source, ticker) DO NOTHING"#,
+    )
+    .bind(rows.iter().map(|r| r.source.clone()).collect::<Vec<String>>())
+    .bind(rows.iter().map(|r| r.ticker.clone()).collect::<Vec<String>>())
+    .bind(text_col(|r| r.name.as_ref()))
+    .bind(text_col(|r| r.en_name.as_ref()))
+    .bind(text_col(|r| r.exchange.as_ref()))
+    .bind(text_col(|r| r.currency.as_ref()))
+    .bind(text_col(|r| r.asset_type.as_ref()))
+    .bind(text_col(|r| r.sector.as_ref()))
+    .bind(text_col(|r| r.industry.as_ref()))
+    .bind(date_col(|r| r.listed_on))
+    .bind(date_col(|r| r.delisted_on))
+    .bind(float_col(|r| r.lot_size))
+    .bind(float_col(|r| r.tick_size))
+    .bind(rows.iter().map(|r| r.aliases.join(",")).collect::<Vec<String>>())
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+/// Create or replace the row of `(m.source, m.ticker)`.
+pub async fn upsert(conn: impl PgExecutor<'_>, m: &TickerMetadata) -> sqlx::Result<TickerMetadata> {
+    sqlx::query_as::<_, TickerMetadata>(&format!(
+        r#"INSERT INTO ticker_metadata (source, ticker, name, en_name, exchange, currency, asset_type, sector, industry,
+                                        listed_on, delisted_on, lot_size, tick_size, aliases)
+           VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
+           ON CONFLICT (source, ticker)
+           DO UPDATE SET name        = EXCLUDED.name,
+                         en_name     = EXCLUDED.en_name,
+                         exchange    = EXCLUDED.exchange,
+                         currency    = EXCLUDED.currency,
+                         asset_type  = EXCLUDED.asset_type,
+                         sector      = EXCLUDED.sector,
+                         industry    = EXCLUDED.industry,
+                         listed_on   = EXCLUDED.listed_on,
+                         delisted_on = EXCLUDED.delisted_on,
+                         lot_size    = EXCLUDED.lot_size,
+                         tick_size   = EXCLUDED.tick_size,
+                         aliases     = EXCLUDED.aliases,
+                         updated_at  = NOW()
+           RETURNING {METADATA_COLUMNS}"#
+    ))
+    .bind(&m.source)
+    .bind(&m.ticker)
+    .bind(&m.name)
+    .bind(&m.en_name)
+    .bind(&m.exchange)
+    .bind(&m.currency)
+    .bind(&m.asset_type)
+    .bind(&m.sector)
+    .bind(&m.industry)
+    .bind(m.listed_on)
+    .bind(m.delisted_on)
+    .bind(m.lot_size)
+    .bind(m.tick_size)
+    .bind(&m.aliases)
+    .fetch_one(conn)
+    .await
+}
+
+pub async fn delete(conn: impl PgExecutor<'_>, source: &str, ticker: &str) -> sqlx::Result<bool> {
+    let result = sqlx::query("DELETE FROM ticker_metadata WHERE source = $1 AND ticker = $2")
+        .bind(source)
+        .bind(ticker)
+        .execute(conn)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+// ── Read queries ──
+
+/// Rows ordered by (source, ticker), optionally narrowed to one source and
+/// sector.
+pub async fn list(pool: &PgPool, source: Option<&str>, sector: Option<&str>) -> sqlx::Result<Vec<TickerMetadata>> {
+    sqlx::query_as::<_, TickerMetadata>(&format!(
+        r#"SELECT {METADATA_COLUMNS}
+           FROM ticker_metadata
+           WHERE ($1::text IS NULL OR source = $1)
+             AND ($2::text IS NULL OR sector = $2)
+           ORDER BY source, ticker"#
+    ))
+    .bind(source)
+    .bind(sector)
+    .fetch_all(pool)
+    .await
+}
+
+/// Whether the table has no rows at all (nothing seeded yet).
+pub async fn is_empty(pool: &PgPool) -> sqlx::Result<bool> {
+    sqlx::query_scalar("SELECT NOT EXISTS (SELECT 1 FROM ticker_metadata)").fetch_one(pool).await
+}
+
+pub async fn get(pool: &PgPool, source: &str, ticker: &str) -> sqlx::Result<Option<TickerMetadata>> {
+    sqlx::query_as::<_, TickerMetadata>(&format!(
+        "SELECT {METADATA_COLUMNS} FROM ticker_metadata WHERE source = $1 AND ticker = $2"
+    ))
+    .bind(source)
+    .bind(ticker)
+    .fetch_optional(pool)
+    .await
+}
@@ -1,4 +1,5 @@
 use fred::prelude::*;
+use fred::clients::SubscriberClient;
 use fred::types::ConnectHandle;
 
 pub type RedisClient = Client;
@@ -76,3 +77,49 @@ pub async fn connect() -> Option<(RedisClient, ConnectHandle)> {
 
     Some((client, handle))
 }
+
+/// Connect a dedicated pub/sub client and subscribe to `channel`.
+/// Returns None if REDIS_URL is not set.
+///
+/// The subscription is retried in the background until it succeeds, and
+/// re-issued automatically after every reconnect, so the returned client's
+/// `message_rx()` keeps delivering across Redis restarts.
+pub async fn subscribe(channel: &'static str) -> Option<(SubscriberClient, ConnectHandle)> {
+    let redis_url = std::env::var("REDIS_URL").ok().filter(|u| !u.is_empty())?;
+    let config = match Config::from_url(&redis_url) {
+        Ok(c) => c,
+        Err(e) => {
+            tracing::error!("Invalid REDIS_URL: {e}");
+            return None;
+        }
+    };
+
+    let policy = ReconnectPolicy::new_exponential(0, 100, 30_000, 2);
+    let subscriber = SubscriberClient::new(config, None, None, Some(policy));
+    let handle = subscriber.connect();
+    if tokio::time::timeout(std::time::Duration::from_secs(3), subscriber.wait_for_connect()).await.is_err() {
+        tracing::warn!("Redis subscriber initial connect timed out after 3s (fred will retry in background)");
+    }
+    // Detached: resubscribes tracked channels after every reconnect.
+    drop(subscriber.manage_subscriptions());
+
+    // Only successful subscriptions are tracked for resubscription, so keep
+    // trying until the first one lands.
+    let retry_client = subscriber.clone();
+    tokio::spawn(async move {
+        loop {
+            match retry_client.subscribe(channel).await {
+                Ok(()) => {
+                    tracing::info!(channel, "Subscribed to Redis pub/sub channel");
+                    return;
+                }
+                Err(e) => {
+                    tracing::warn!(channel, "Redis subscribe failed: {e}, retrying in 5s");
+                    tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+                }
+            }
+        }
+    });
+
+    Some((subscriber, handle))
+}
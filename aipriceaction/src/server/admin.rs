@@ -0,0 +1,910 @@
+use axum::extract::{Path, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Json, Response};
+use axum_extra::extract::Query as AxumQuery;
+use serde::Deserialize;
+use std::sync::Arc;
+
+use super::AppState;
+use super::auth::BearerAuth;
+use super::analysis::get_all_sources;
+use super::api::data_loader::load_outstanding_shares;
+use super::api::fetch::parse_date;
+use crate::constants::{gap_worker, reconcile_worker};
+use crate::constants::custom_index::DEFAULT_BASE_VALUE;
+use crate::models::custom_index::{self as custom, CustomIndex, IndexMember, Weighting};
+use crate::models::lifecycle::LifecycleState;
+use crate::queries::ticker_metadata::{self, TickerMetadata};
+use crate::queries::membership::{self, Membership};
+use crate::queries::{custom_indices, gaps, lifecycle, ohlcv, reconcile};
+use crate::services::metadata;
+
+// ── Request types ──
+
+#[derive(Debug, Deserialize)]
+pub struct DiscrepanciesQuery {
+    pub symbol: Option<String>,
+    pub interval: Option<String>,
+    /// Only rows detected on or after this date (YYYY-MM-DD).
+    pub since: Option<String>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GapsQuery {
+    pub source: Option<String>,
+    pub symbol: Option<String>,
+    pub interval: Option<String>,
+    /// pending, filled or unfillable
+    pub status: Option<String>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TickerMetadataQuery {
+    pub source: Option<String>,
+    pub sector: Option<String>,
+}
+
+/// Body of PUT /admin/tickers/{source}/{ticker}: the whole record, omitted
+/// fields are cleared.
+#[derive(Debug, Deserialize)]
+pub struct TickerMetadataBody {
+    pub name: Option<String>,
+    pub en_name: Option<String>,
+    pub exchange: Option<String>,
+    pub currency: Option<String>,
+    pub asset_type: Option<String>,
+    pub sector: Option<String>,
+    pub industry: Option<String>,
+    pub listed_on: Option<chrono::NaiveDate>,
+    pub delisted_on: Option<chrono::NaiveDate>,
+    pub lot_size: Option<f64>,
+    pub tick_size: Option<f64>,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LifecycleQuery {
+    pub source: Option<String>,
+    pub ticker: Option<String>,
+}
+
+/// Body of POST /admin/lifecycle.
+#[derive(Debug, Deserialize)]
+pub struct LifecycleBody {
+    pub source: String,
+    pub ticker: String,
+    pub state: LifecycleState,
+    pub effective_on: chrono::NaiveDate,
+    /// Required for `renamed` / `merged`.
+    pub successor: Option<String>,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MembershipQuery {
+    pub source: Option<String>,
+    /// sector or index
+    pub kind: Option<String>,
+    pub group: Option<String>,
+    pub ticker: Option<String>,
+}
+
+/// Body of POST /admin/membership and PUT /admin/membership/{id}.
+#[derive(Debug, Deserialize)]
+pub struct MembershipBody {
+    pub source: String,
+    /// sector or index
+    pub kind: String,
+    pub group: String,
+    pub ticker: String,
+    /// Inclusive; omitted = since listing.
+    pub effective_from: Option<chrono::NaiveDate>,
+    /// Exclusive; omitted = still a member.
+    pub effective_to: Option<chrono::NaiveDate>,
+    pub note: Option<String>,
+}
+
+/// Body of PUT /admin/indices/{name}.
+#[derive(Debug, Deserialize)]
+pub struct CustomIndexBody {
+    pub title: Option<String>,
+    pub weighting: Weighting,
+    /// Level of the first bar (default 1000).
+    pub base_value: Option<f64>,
+    /// First day of the series; omitted = the first day any member traded.
+    pub base_date: Option<chrono::NaiveDate>,
+    pub note: Option<String>,
+    pub members: Vec<CustomIndexMemberBody>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CustomIndexMemberBody {
+    /// Defaults to vn.
+    pub source: Option<String>,
+    pub ticker: String,
+    /// manual: relative weight (required). cap: outstanding shares, taken
+    /// from company_info.json when omitted. Ignored for equal.
+    pub weight: Option<f64>,
+}
+
+// ── Helpers ──
+
+/// `ADMIN_TOKEN` (comma-separated list) guards every /admin endpoint.
+const ADMIN_AUTH: BearerAuth = BearerAuth {
+    env_var: "ADMIN_TOKEN",
+    route: "/admin",
+    disabled: "Admin endpoints are disabled. Set ADMIN_TOKEN environment variable.",
+};
+
+fn error_response(status: StatusCode, message: &str) -> Response {
+    (status, Json(serde_json::json!({ "error": message }))).into_response()
+}
+
+/// Validate a PUT body and turn it into the row to store. Text fields are
+/// trimmed (blank = cleared) and currency/aliases upper-cased.
+fn metadata_row(source: &str, ticker: &str, body: TickerMetadataBody) -> Result<TickerMetadata, String> {
+    if !get_all_sources().contains(&source) {
+        return Err(format!("Invalid source '{source}'. Must be one of: {}", get_all_sources().join(", ")));
+    }
+    let text = |s: Option<String>| s.map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+    let currency = text(body.currency).map(|c| c.to_ascii_uppercase());
+    if currency.as_deref().is_some_and(|c| c.len() != 3 || !c.bytes().all(|b| b.is_ascii_alphabetic())) {
+        return Err("currency must be a 3-letter code (e.g. VND, USD)".to_string());
+    }
+    if [body.lot_size, body.tick_size].into_iter().flatten().any(|v| !v.is_finite() || v <= 0.0) {
+        return Err("lot_size and tick_size must be positive".to_string());
+    }
+    if let (Some(listed), Some(delisted)) = (body.listed_on, body.delisted_on)
+        && delisted < listed
+    {
+        return Err("delisted_on must not be before listed_on".to_string());
+    }
+    let mut aliases: Vec<String> = body.aliases.iter().map(|a| a.trim().to_uppercase()).filter(|a| !a.is_empty()).collect();
+    if aliases.iter().any(|a| a.contains(',') || a == ticker) {
+        return Err("aliases must not contain commas or repeat the ticker".to_string());
+    }
+    aliases.sort();
+    aliases.dedup();
+
+    Ok(TickerMetadata {
+        source: source.to_string(),
+        ticker: ticker.to_string(),
+        name: text(body.name),
+        en_name: text(body.en_name),
+        exchange: text(body.exchange).map(|e| e.to_ascii_uppercase()),
+        currency,
+        asset_type: text(body.asset_type),
+        sector: text(body.sector),
+        industry: text(body.industry),
+        listed_on: body.listed_on,
+        delisted_on: body.delisted_on,
+        lot_size: body.lot_size,
+        tick_size: body.tick_size,
+        aliases,
+        updated_at: None,
+    })
+}
+
+/// Validate a membership body and turn it into the row to store. Index names
+/// and tickers are upper-cased; sector names are kept as written since they
+/// must match the sector keys of the metadata. A ticker is in one sector at
+/// a time, so a sector period may not overlap any of `existing` (the stored
+/// sector periods of the ticker; the row being replaced is skipped).
+fn membership_row(id: i64, body: MembershipBody, existing: &[Membership]) -> Result<Membership, String> {
+    if !get_all_sources().contains(&body.source.as_str()) {
+        return Err(format!("Invalid source '{}'. Must be one of: {}", body.source, get_all_sources().join(", ")));
+    }
+    if body.kind != "sector" && body.kind != "index" {
+        return Err(format!("Invalid kind '{}'. Must be sector or index", body.kind));
+    }
+    let group_name = match body.kind.as_str() {
+        "index" => body.group.trim().to_uppercase(),
+        _ => body.group.trim().to_string(),
+    };
+    let ticker = body.ticker.trim().to_uppercase();
+    if group_name.is_empty() || ticker.is_empty() {
+        return Err("group and ticker must not be empty".to_string());
+    }
+    if let (Some(from), Some(to)) = (body.effective_from, body.effective_to)
+        && to <= from
+    {
+        return Err("effective_to must be after effective_from".to_string());
+    }
+
+    let row = Membership {
+        id,
+        source: body.source,
+        kind: body.kind,
+        group_name,
+        ticker,
+        effective_from: body.effective_from,
+        effective_to: body.effective_to,
+        note: body.note.map(|n| n.trim().to_string()).filter(|n| !n.is_empty()),
+    };
+    if row.kind == "sector"
+        && let Some(other) = existing.iter().find(|m| {
+            m.id != id && m.kind == "sector" && m.source == row.source && m.ticker == row.ticker && m.overlaps(&row)
+        })
+    {
+        let bound = |d: Option<chrono::NaiveDate>, open: &str| d.map_or(open.to_string(), |d| d.to_string());
+        return Err(format!(
+            "{} is already in sector '{}' from {} to {}",
+            row.ticker,
+            other.group_name,
+            bound(other.effective_from, "listing"),
+            bound(other.effective_to, "now"),
+        ));
+    }
+    Ok(row)
+}
+
+/// Stored sector periods of the ticker a membership body names.
+async fn sector_periods(pool: &sqlx::PgPool, body: &MembershipBody) -> sqlx::Result<Vec<Membership>> {
+    let ticker = body.ticker.trim().to_uppercase();
+    membership::list(pool, Some(&body.source), Some("sector"), None, Some(&ticker)).await
+}
+
+/// Validate a PUT body and turn it into the definition to store. Cap
+/// members without a weight get their outstanding shares from
+/// company_info.json, and must all be quoted in one currency.
+fn custom_index_def(name: &str, body: CustomIndexBody) -> Result<CustomIndex, String> {
+    if name.is_empty() || !name.bytes().all(|b| b.is_ascii_uppercase() || b.is_ascii_digit() || b"_-^.".contains(&b)) {
+        return Err("name must be letters, digits, '_', '-', '^' or '.'".to_string());
+    }
+    let catalog = metadata::catalog();
+    if let Some(source) = get_all_sources().into_iter().find(|s| catalog.get(s, name).is_some()) {
+        return Err(format!("'{name}' is already a {source} ticker"));
+    }
+    let base_value = body.base_value.unwrap_or(DEFAULT_BASE_VALUE);
+    if !base_value.is_finite() || base_value <= 0.0 {
+        return Err("base_value must be positive".to_string());
+    }
+    if body.members.is_empty() {
+        return Err("members must not be empty".to_string());
+    }
+
+    let mut members = Vec::with_capacity(body.members.len());
+    for m in body.members {
+        let source = m.source.unwrap_or_else(|| "vn".to_string());
+        if !get_all_sources().contains(&source.as_str()) {
+            return Err(format!("Invalid member source '{source}'. Must be one of: {}", get_all_sources().join(", ")));
+        }
+        let ticker = m.ticker.trim().to_uppercase();
+        if ticker.is_empty() {
+            return Err("member ticker must not be empty".to_string());
+        }
+        if m.weight.is_some_and(|w| !w.is_finite() || w <= 0.0) {
+            return Err(format!("weight of {ticker} must be positive"));
+        }
+        if members.iter().any(|e: &IndexMember| e.source == source && e.ticker == ticker) {
+            return Err(format!("{ticker} is listed twice"));
+        }
+        let weight = match body.weighting {
+            Weighting::Equal => None,
+            Weighting::Manual if m.weight.is_none() => return Err(format!("weight of {ticker} is required for manual weighting")),
+            _ => m.weight,
+        };
+        members.push(IndexMember { source, ticker, weight });
+    }
+
+    if body.weighting == Weighting::Cap {
+        let missing: Vec<&str> = members.iter().filter(|m| m.weight.is_none()).map(|m| m.ticker.as_str()).collect();
+        if !missing.is_empty() {
+            let shares = load_outstanding_shares(&missing).unwrap_or_else(|e| {
+                tracing::warn!("company_info.json not available: {e}");
+                Default::default()
+            });
+            for m in members.iter_mut().filter(|m| m.weight.is_none() && m.source == "vn") {
+                m.weight = shares.get(&m.ticker).copied();
+            }
+            if let Some(m) = members.iter().find(|m| m.weight.is_none()) {
+                return Err(format!("No outstanding shares known for {}; give its weight", m.ticker));
+            }
+        }
+        // Market caps are only comparable in one currency
+        let currencies: std::collections::BTreeSet<&str> =
+            members.iter().map(|m| catalog.currency(&m.source, &m.ticker).unwrap_or("none")).collect();
+        if currencies.len() > 1 {
+            let list: Vec<&str> = currencies.into_iter().collect();
+            return Err(format!("cap weighting needs members quoted in one currency, got {}", list.join(", ")));
+        }
+    }
+
+    let text = |s: Option<String>| s.map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+    Ok(CustomIndex {
+        id: 0,
+        name: name.to_string(),
+        title: text(body.title),
+        weighting: body.weighting,
+        base_value,
+        base_date: body.base_date,
+        note: text(body.note),
+        members,
+        updated_at: chrono::Utc::now(),
+        computed_at: None,
+    })
+}
+
+/// Pick up an admin edit in this process right away; other replicas follow
+/// on their next periodic reload.
+async fn reload_catalog(state: &AppState) {
+    if let Err(e) = metadata::reload(&state.pool).await {
+        tracing::warn!("failed to reload ticker metadata after edit: {e}");
+    }
+}
+
+// ── GET /admin/discrepancies ──
+
+pub async fn discrepancies(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    AxumQuery(params): AxumQuery<DiscrepanciesQuery>,
+) -> Response {
+    if let Err((status, message)) = ADMIN_AUTH.verify(&headers) {
+        return error_response(status, message);
+    }
+
+    if let Some(iv) = params.interval.as_deref()
+        && !matches!(iv, "1D" | "1h")
+    {
+        return error_response(StatusCode::BAD_REQUEST, "Invalid interval. Must be one of: 1D, 1h");
+    }
+
+    let since = match params.since.as_deref() {
+        Some(s) => match parse_date(s) {
+            Some(d) => Some(d),
+            None => return error_response(StatusCode::BAD_REQUEST, "Invalid since date. Use YYYY-MM-DD"),
+        },
+        None => None,
+    };
+
+    let limit = params
+        .limit
+        .unwrap_or(reconcile_worker::API_DEFAULT_LIMIT)
+        .clamp(1, reconcile_worker::API_MAX_LIMIT);
+    let symbol = params.symbol.as_deref().map(str::to_uppercase);
+
+    match reconcile::list_discrepancies(&state.pool, symbol.as_deref(), params.interval.as_deref(), since, limit).await {
+        Ok(rows) => (
+            StatusCode::OK,
+            Json(serde_json::json!({ "count": rows.len(), "discrepancies": rows })),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!("GET /admin/discrepancies failed: {e}");
+            error_response(StatusCode::INTERNAL_SERVER_ERROR, "Database error")
+        }
+    }
+}
+
+// ── GET /admin/gaps ──
+
+pub async fn gaps(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    AxumQuery(params): AxumQuery<GapsQuery>,
+) -> Response {
+    if let Err((status, message)) = ADMIN_AUTH.verify(&headers) {
+        return error_response(status, message);
+    }
+
+    if let Some(iv) = params.interval.as_deref()
+        && !matches!(iv, "1h" | "1m")
+    {
+        return error_response(StatusCode::BAD_REQUEST, "Invalid interval. Must be one of: 1h, 1m");
+    }
+    if let Some(st) = params.status.as_deref()
+        && !matches!(st, "pending" | "filled" | "unfillable")
+    {
+        return error_response(StatusCode::BAD_REQUEST, "Invalid status. Must be one of: pending, filled, unfillable");
+    }
+
+    let limit = params
+        .limit
+        .unwrap_or(gap_worker::API_DEFAULT_LIMIT)
+        .clamp(1, gap_worker::API_MAX_LIMIT);
+    let symbol = params.symbol.as_deref().map(str::to_uppercase);
+
+    match gaps::list_gaps(
+        &state.pool,
+        params.source.as_deref(),
+        symbol.as_deref(),
+        params.interval.as_deref(),
+        params.status.as_deref(),
+        limit,
+        false,
+    )
+    .await
+    {
+        Ok(rows) => {
+            let missing_bars: i64 = rows.iter().map(|r| r.missing_bars as i64).sum();
+            (
+                StatusCode::OK,
+                Json(serde_json::json!({ "count": rows.len(), "missing_bars": missing_bars, "gaps": rows })),
+            )
+                .into_response()
+        }
+        Err(e) => {
+            tracing::error!("GET /admin/gaps failed: {e}");
+            error_response(StatusCode::INTERNAL_SERVER_ERROR, "Database error")
+        }
+    }
+}
+
+// ── GET /admin/tickers ──
+
+pub async fn list_tickers(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    AxumQuery(params): AxumQuery<TickerMetadataQuery>,
+) -> Response {
+    if let Err((status, message)) = ADMIN_AUTH.verify(&headers) {
+        return error_response(status, message);
+    }
+
+    match ticker_metadata::list(&state.pool, params.source.as_deref(), params.sector.as_deref()).await {
+        Ok(rows) => (
+            StatusCode::OK,
+            Json(serde_json::json!({ "count": rows.len(), "tickers": rows })),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!("GET /admin/tickers failed: {e}");
+            error_response(StatusCode::INTERNAL_SERVER_ERROR, "Database error")
+        }
+    }
+}
+
+// ── GET /admin/tickers/{source}/{ticker} ──
+
+pub async fn get_ticker(
+    State(state): State<Arc<AppState>>,
+    Path((source, ticker)): Path<(String, String)>,
+    headers: HeaderMap,
+) -> Response {
+    if let Err((status, message)) = ADMIN_AUTH.verify(&headers) {
+        return error_response(status, message);
+    }
+
+    match ticker_metadata::get(&state.pool, &source, &ticker.to_uppercase()).await {
+        Ok(Some(row)) => (StatusCode::OK, Json(row)).into_response(),
+        Ok(None) => error_response(StatusCode::NOT_FOUND, "Ticker metadata not found"),
+        Err(e) => {
+            tracing::error!("GET /admin/tickers/{{source}}/{{ticker}} failed: {e}");
+            error_response(StatusCode::INTERNAL_SERVER_ERROR, "Database error")
+        }
+    }
+}
+
+// ── PUT /admin/tickers/{source}/{ticker} ──
+
+pub async fn put_ticker(
+    State(state): State<Arc<AppState>>,
+    Path((source, ticker)): Path<(String, String)>,
+    headers: HeaderMap,
+    axum::Json(body): axum::Json<TickerMetadataBody>,
+) -> Response {
+    if let Err((status, message)) = ADMIN_AUTH.verify(&headers) {
+        return error_response(status, message);
+    }
+
+    let row = match metadata_row(&source, &ticker.to_uppercase(), body) {
+        Ok(row) => row,
+        Err(e) => return error_response(StatusCode::BAD_REQUEST, &e),
+    };
+
+    match ticker_metadata::upsert(&state.pool, &row).await {
+        Ok(saved) => {
+            reload_catalog(&state).await;
+            (StatusCode::OK, Json(saved)).into_response()
+        }
+        Err(e) => {
+            tracing::error!("PUT /admin/tickers/{{source}}/{{ticker}} failed: {e}");
+            error_response(StatusCode::INTERNAL_SERVER_ERROR, "Database error")
+        }
+    }
+}
+
+// ── DELETE /admin/tickers/{source}/{ticker} ──
+
+pub async fn delete_ticker(
+    State(state): State<Arc<AppState>>,
+    Path((source, ticker)): Path<(String, String)>,
+    headers: HeaderMap,
+) -> Response {
+    if let Err((status, message)) = ADMIN_AUTH.verify(&headers) {
+        return error_response(status, message);
+    }
+
+    match ticker_metadata::delete(&state.pool, &source, &ticker.to_uppercase()).await {
+        Ok(true) => {
+            reload_catalog(&state).await;
+            StatusCode::NO_CONTENT.into_response()
+        }
+        Ok(false) => error_response(StatusCode::NOT_FOUND, "Ticker metadata not found"),
+        Err(e) => {
+            tracing::error!("DELETE /admin/tickers/{{source}}/{{ticker}} failed: {e}");
+            error_response(StatusCode::INTERNAL_SERVER_ERROR, "Database error")
+        }
+    }
+}
+
+// ── GET /admin/lifecycle ──
+
+pub async fn list_lifecycle(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    AxumQuery(params): AxumQuery<LifecycleQuery>,
+) -> Response {
+    if let Err((status, message)) = ADMIN_AUTH.verify(&headers) {
+        return error_response(status, message);
+    }
+
+    let ticker = params.ticker.map(|t| t.to_uppercase());
+    match lifecycle::list(&state.pool, params.source.as_deref(), ticker.as_deref()).await {
+        Ok(events) => (
+            StatusCode::OK,
+            Json(serde_json::json!({ "count": events.len(), "events": events })),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!("GET /admin/lifecycle failed: {e}");
+            error_response(StatusCode::INTERNAL_SERVER_ERROR, "Database error")
+        }
+    }
+}
+
+// ── POST /admin/lifecycle ──
+
+/// Record a state change. The successor of a rename/merge is added to
+/// `tickers` when missing, and a renamed ticker's metadata is carried over
+/// to its new symbol (with the old one as an alias) unless the new symbol
+/// already has a row.
+pub async fn record_lifecycle(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    axum::Json(body): axum::Json<LifecycleBody>,
+) -> Response {
+    if let Err((status, message)) = ADMIN_AUTH.verify(&headers) {
+        return error_response(status, message);
+    }
+
+    let ticker = body.ticker.trim().to_uppercase();
+    let successor = body.successor.map(|s| s.trim().to_uppercase()).filter(|s| !s.is_empty());
+    if !get_all_sources().contains(&body.source.as_str()) {
+        let message = format!("Invalid source '{}'. Must be one of: {}", body.source, get_all_sources().join(", "));
+        return error_response(StatusCode::BAD_REQUEST, &message);
+    }
+    if body.state.has_successor() != successor.is_some() {
+        return error_response(StatusCode::BAD_REQUEST, "successor is required for renamed/merged and not allowed otherwise");
+    }
+    if successor.as_deref() == Some(ticker.as_str()) {
+        return error_response(StatusCode::BAD_REQUEST, "successor must differ from the ticker");
+    }
+
+    let result: sqlx::Result<Option<i64>> = async {
+        let Some(ticker_id) = ohlcv::get_ticker_id(&state.pool, &body.source, &ticker).await? else {
+            return Ok(None);
+        };
+        let successor_id = match &successor {
+            Some(s) => Some(ohlcv::upsert_ticker(&state.pool, &body.source, s, None).await?),
+            None => None,
+        };
+        if body.state == LifecycleState::Renamed
+            && let Some(new) = &successor
+            && ticker_metadata::get(&state.pool, &body.source, new).await?.is_none()
+            && let Some(old) = ticker_metadata::get(&state.pool, &body.source, &ticker).await?
+        {
+            let mut aliases = old.aliases.clone();
+            aliases.push(ticker.clone());
+            aliases.retain(|a| a != new);
+            let carried = TickerMetadata { ticker: new.clone(), aliases, listed_on: Some(body.effective_on), ..old };
+            ticker_metadata::upsert(&state.pool, &carried).await?;
+        }
+        let note = body.note.as_deref().map(str::trim).filter(|n| !n.is_empty());
+        lifecycle::record(&state.pool, ticker_id, body.state, body.effective_on, successor_id, note).await.map(Some)
+    }
+    .await;
+
+    match result {
+        Ok(Some(id)) => {
+            reload_catalog(&state).await;
+            match lifecycle::get(&state.pool, id).await {
+                Ok(Some(event)) => (StatusCode::CREATED, Json(event)).into_response(),
+                Ok(None) => error_response(StatusCode::NOT_FOUND, "Lifecycle event not found"),
+                Err(e) => {
+                    tracing::error!("POST /admin/lifecycle failed: {e}");
+                    error_response(StatusCode::INTERNAL_SERVER_ERROR, "Database error")
+                }
+            }
+        }
+        Ok(None) => error_response(StatusCode::NOT_FOUND, "Unknown ticker"),
+        Err(e) => {
+            tracing::error!("POST /admin/lifecycle failed: {e}");
+            error_response(StatusCode::INTERNAL_SERVER_ERROR, "Database error")
+        }
+    }
+}
+
+// ── DELETE /admin/lifecycle/{id} ──
+
+pub async fn delete_lifecycle(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+    headers: HeaderMap,
+) -> Response {
+    if let Err((status, message)) = ADMIN_AUTH.verify(&headers) {
+        return error_response(status, message);
+    }
+
+    match lifecycle::delete(&state.pool, id).await {
+        Ok(true) => {
+            reload_catalog(&state).await;
+            StatusCode::NO_CONTENT.into_response()
+        }
+        Ok(false) => error_response(StatusCode::NOT_FOUND, "Lifecycle event not found"),
+        Err(e) => {
+            tracing::error!("DELETE /admin/lifecycle/{{id}} failed: {e}");
+            error_response(StatusCode::INTERNAL_SERVER_ERROR, "Database error")
+        }
+    }
+}
+
+// ── GET /admin/membership ──
+
+pub async fn list_membership(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    AxumQuery(params): AxumQuery<MembershipQuery>,
+) -> Response {
+    if let Err((status, message)) = ADMIN_AUTH.verify(&headers) {
+        return error_response(status, message);
+    }
+
+    let ticker = params.ticker.map(|t| t.to_uppercase());
+    let result = membership::list(
+        &state.pool,
+        params.source.as_deref(),
+        params.kind.as_deref(),
+        params.group.as_deref(),
+        ticker.as_deref(),
+    )
+    .await;
+    match result {
+        Ok(memberships) => (
+            StatusCode::OK,
+            Json(serde_json::json!({ "count": memberships.len(), "memberships": memberships })),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!("GET /admin/membership failed: {e}");
+            error_response(StatusCode::INTERNAL_SERVER_ERROR, "Database error")
+        }
+    }
+}
+
+// ── POST /admin/membership ──
+
+pub async fn create_membership(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    axum::Json(body): axum::Json<MembershipBody>,
+) -> Response {
+    if let Err((status, message)) = ADMIN_AUTH.verify(&headers) {
+        return error_response(status, message);
+    }
+
+    let existing = match sector_periods(&state.pool, &body).await {
+        Ok(periods) => periods,
+        Err(e) => {
+            tracing::error!("POST /admin/membership failed: {e}");
+            return error_response(StatusCode::INTERNAL_SERVER_ERROR, "Database error");
+        }
+    };
+    let row = match membership_row(0, body, &existing) {
+        Ok(row) => row,
+        Err(message) => return error_response(StatusCode::BAD_REQUEST, &message),
+    };
+    match membership::insert(&state.pool, &row).await {
+        Ok(stored) => {
+            reload_catalog(&state).await;
+            (StatusCode::CREATED, Json(stored)).into_response()
+        }
+        Err(e) => {
+            tracing::error!("POST /admin/membership failed: {e}");
+            error_response(StatusCode::INTERNAL_SERVER_ERROR, "Database error")
+        }
+    }
+}
+
+// ── PUT /admin/membership/{id} ──
+
+pub async fn put_membership(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+    headers: HeaderMap,
+    axum::Json(body): axum::Json<MembershipBody>,
+) -> Response {
+    if let Err((status, message)) = ADMIN_AUTH.verify(&headers) {
+        return error_response(status, message);
+    }
+
+    let existing = match sector_periods(&state.pool, &body).await {
+        Ok(periods) => periods,
+        Err(e) => {
+            tracing::error!("PUT /admin/membership/{{id}} failed: {e}");
+            return error_response(StatusCode::INTERNAL_SERVER_ERROR, "Database error");
+        }
+    };
+    let row = match membership_row(id, body, &existing) {
+        Ok(row) => row,
+        Err(message) => return error_response(StatusCode::BAD_REQUEST, &message),
+    };
+    match membership::update(&state.pool, &row).await {
+        Ok(Some(stored)) => {
+            reload_catalog(&state).await;
+            (StatusCode::OK, Json(stored)).into_response()
+        }
+        Ok(None) => error_response(StatusCode::NOT_FOUND, "Membership not found"),
+        Err(e) => {
+            tracing::error!("PUT /admin/membership/{{id}} failed: {e}");
+            error_response(StatusCode::INTERNAL_SERVER_ERROR, "Database error")
+        }
+    }
+}
+
+// ── DELETE /admin/membership/{id} ──
+
+pub async fn delete_membership(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+    headers: HeaderMap,
+) -> Response {
+    if let Err((status, message)) = ADMIN_AUTH.verify(&headers) {
+        return error_response(status, message);
+    }
+
+    match membership::delete(&state.pool, id).await {
+        Ok(true) => {
+            reload_catalog(&state).await;
+            StatusCode::NO_CONTENT.into_response()
+        }
+        Ok(false) => error_response(StatusCode::NOT_FOUND, "Membership not found"),
+        Err(e) => {
+            tracing::error!("DELETE /admin/membership/{{id}} failed: {e}");
+            error_response(StatusCode::INTERNAL_SERVER_ERROR, "Database error")
+        }
+    }
+}
+
+// ── GET /admin/indices ──
+
+pub async fn list_indices(State(state): State<Arc<AppState>>, headers: HeaderMap) -> Response {
+    if let Err((status, message)) = ADMIN_AUTH.verify(&headers) {
+        return error_response(status, message);
+    }
+
+    match custom_indices::list(&state.pool).await {
+        Ok(indices) => (
+            StatusCode::OK,
+            Json(serde_json::json!({ "count": indices.len(), "indices": indices })),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!("GET /admin/indices failed: {e}");
+            error_response(StatusCode::INTERNAL_SERVER_ERROR, "Database error")
+        }
+    }
+}
+
+// ── GET /admin/indices/{name} ──
+
+pub async fn get_index(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    headers: HeaderMap,
+) -> Response {
+    if let Err((status, message)) = ADMIN_AUTH.verify(&headers) {
+        return error_response(status, message);
+    }
+
+    match custom_indices::get(&state.pool, &name.to_uppercase()).await {
+        Ok(Some(index)) => (StatusCode::OK, Json(index)).into_response(),
+        Ok(None) => error_response(StatusCode::NOT_FOUND, "Custom index not found"),
+        Err(e) => {
+            tracing::error!("GET /admin/indices/{{name}} failed: {e}");
+            error_response(StatusCode::INTERNAL_SERVER_ERROR, "Database error")
+        }
+    }
+}
+
+// ── PUT /admin/indices/{name} ──
+
+/// Create or replace a custom index. The custom index worker rebuilds its
+/// series under `source=custom` on its next round.
+pub async fn put_index(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    headers: HeaderMap,
+    axum::Json(body): axum::Json<CustomIndexBody>,
+) -> Response {
+    if let Err((status, message)) = ADMIN_AUTH.verify(&headers) {
+        return error_response(status, message);
+    }
+
+    let def = match custom_index_def(&name.to_uppercase(), body) {
+        Ok(def) => def,
+        Err(e) => return error_response(StatusCode::BAD_REQUEST, &e),
+    };
+
+    // Definition, tickers row and metadata row change together or not at all
+    let result: sqlx::Result<Option<CustomIndex>> = async {
+        let mut tx = state.pool.begin().await?;
+        custom_indices::save(&mut tx, &def).await?;
+        ohlcv::upsert_ticker(&mut *tx, custom::SOURCE, &def.name, def.title.as_deref()).await?;
+        let meta = TickerMetadata {
+            source: custom::SOURCE.to_string(),
+            ticker: def.name.clone(),
+            name: def.title.clone(),
+            asset_type: Some("index".to_string()),
+            ..Default::default()
+        };
+        ticker_metadata::upsert(&mut *tx, &meta).await?;
+        tx.commit().await?;
+        custom_indices::get(&state.pool, &def.name).await
+    }
+    .await;
+
+    match result {
+        Ok(Some(saved)) => {
+            reload_catalog(&state).await;
+            (StatusCode::OK, Json(saved)).into_response()
+        }
+        Ok(None) => error_response(StatusCode::NOT_FOUND, "Custom index not found"),
+        Err(e) => {
+            tracing::error!("PUT /admin/indices/{{name}} failed: {e}");
+            error_response(StatusCode::INTERNAL_SERVER_ERROR, "Database error")
+        }
+    }
+}
+
+// ── DELETE /admin/indices/{name} ──
+
+/// Remove a custom index together with its stored series.
+pub async fn delete_index(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    headers: HeaderMap,
+) -> Response {
+    if let Err((status, message)) = ADMIN_AUTH.verify(&headers) {
+        return error_response(status, message);
+    }
+
+    let name = name.to_uppercase();
+    let result: sqlx::Result<bool> = async {
+        let mut tx = state.pool.begin().await?;
+        if !custom_indices::delete(&mut *tx, &name).await? {
+            return Ok(false);
+        }
+        ticker_metadata::delete(&mut *tx, custom::SOURCE, &name).await?;
+        custom_indices::drop_series(&mut tx, &name).await?;
+        tx.commit().await?;
+        Ok(true)
+    }
+    .await;
+
+    match result {
+        Ok(true) => {
+            reload_catalog(&state).await;
+            StatusCode::NO_CONTENT.into_response()
+        }
+        Ok(false) => error_response(StatusCode::NOT_FOUND, "Custom index not found"),
+        Err(e) => {
+            tracing::error!("DELETE /admin/indices/{{name}} failed: {e}");
+            error_response(StatusCode::INTERNAL_SERVER_ERROR, "Database error")
+        }
+    }
+}
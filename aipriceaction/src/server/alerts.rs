@@ -0,0 +1,181 @@
+use axum::extract::{Path, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Json, Response};
+use serde::Deserialize;
+use std::sync::Arc;
+
+use super::AppState;
+use super::auth::BearerAuth;
+use super::analysis::get_all_sources;
+use super::types::NormalizedInterval;
+use crate::constants::alerts as cfg;
+use crate::queries::alerts;
+use crate::services::alerts::{Condition, check_webhook_url};
+
+// ── Request types ──
+
+#[derive(Debug, Deserialize)]
+pub struct CreateAlertBody {
+    pub source: String,
+    pub ticker: String,
+    pub interval: String,
+    pub condition: Condition,
+    pub webhook_url: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateAlertBody {
+    pub condition: Option<Condition>,
+    pub webhook_url: Option<String>,
+    pub enabled: Option<bool>,
+}
+
+// ── Helpers ──
+
+/// `ALERTS_TOKEN` (comma-separated list). Each token owns its rules; the
+/// owner key is the token's SHA-256.
+const ALERTS_AUTH: BearerAuth = BearerAuth {
+    env_var: "ALERTS_TOKEN",
+    route: "/alerts",
+    disabled: "Alert endpoints are disabled. Set ALERTS_TOKEN environment variable.",
+};
+
+fn error_response(status: StatusCode, message: &str) -> Response {
+    (status, Json(serde_json::json!({ "error": message }))).into_response()
+}
+
+fn db_error(route: &str, e: sqlx::Error) -> Response {
+    tracing::error!("{route} failed: {e}");
+    error_response(StatusCode::INTERNAL_SERVER_ERROR, "Database error")
+}
+
+/// Check the optional condition and webhook URL of a create/update body.
+/// The URL must resolve to public addresses only.
+async fn validate_rule(condition: Option<&mut Condition>, webhook_url: Option<&str>) -> Result<(), String> {
+    if let Some(condition) = condition {
+        condition.validate().map_err(|e| format!("Invalid condition: {e}"))?;
+    }
+    if let Some(url) = webhook_url {
+        check_webhook_url(url).await?;
+    }
+    Ok(())
+}
+
+// ── GET /alerts ──
+
+pub async fn list(State(state): State<Arc<AppState>>, headers: HeaderMap) -> Response {
+    let owner = match ALERTS_AUTH.verify(&headers) {
+        Ok(o) => o,
+        Err((status, message)) => return error_response(status, message),
+    };
+    match alerts::list_rules(&state.pool, &owner).await {
+        Ok(rules) => (StatusCode::OK, Json(serde_json::json!({ "count": rules.len(), "rules": rules }))).into_response(),
+        Err(e) => db_error("GET /alerts", e),
+    }
+}
+
+// ── POST /alerts ──
+
+pub async fn create(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    axum::Json(mut body): axum::Json<CreateAlertBody>,
+) -> Response {
+    let owner = match ALERTS_AUTH.verify(&headers) {
+        Ok(o) => o,
+        Err((status, message)) => return error_response(status, message),
+    };
+
+    let source = body.source.to_lowercase();
+    if !get_all_sources().contains(&source.as_str()) {
+        return error_response(
+            StatusCode::BAD_REQUEST,
+            &format!("Invalid source. Must be one of: {}", get_all_sources().join(", ")),
+        );
+    }
+    // Rules watch the native ZSETs the workers write
+    let interval = match NormalizedInterval::parse(&body.interval) {
+        Some(NormalizedInterval::Native(iv)) => iv,
+        _ => return error_response(StatusCode::BAD_REQUEST, "Invalid interval. Must be one of: 1D, 1h, 1m"),
+    };
+    let ticker = body.ticker.trim().to_uppercase();
+    if ticker.is_empty() {
+        return error_response(StatusCode::BAD_REQUEST, "ticker must not be empty");
+    }
+    if let Err(e) = validate_rule(Some(&mut body.condition), Some(&body.webhook_url)).await {
+        return error_response(StatusCode::BAD_REQUEST, &e);
+    }
+
+    match alerts::count_rules(&state.pool, &owner).await {
+        Ok(n) if n >= cfg::MAX_RULES_PER_OWNER => {
+            return error_response(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                &format!("At most {} alert rules per token", cfg::MAX_RULES_PER_OWNER),
+            );
+        }
+        Ok(_) => {}
+        Err(e) => return db_error("POST /alerts", e),
+    }
+
+    match alerts::create_rule(&state.pool, &owner, &source, &ticker, interval, &body.condition, &body.webhook_url).await {
+        Ok(rule) => (StatusCode::CREATED, Json(rule)).into_response(),
+        Err(e) => db_error("POST /alerts", e),
+    }
+}
+
+// ── GET /alerts/{id} ──
+
+pub async fn get(State(state): State<Arc<AppState>>, Path(id): Path<i64>, headers: HeaderMap) -> Response {
+    let owner = match ALERTS_AUTH.verify(&headers) {
+        Ok(o) => o,
+        Err((status, message)) => return error_response(status, message),
+    };
+    let rule = match alerts::get_rule(&state.pool, &owner, id).await {
+        Ok(Some(rule)) => rule,
+        Ok(None) => return error_response(StatusCode::NOT_FOUND, "Alert rule not found"),
+        Err(e) => return db_error("GET /alerts/{id}", e),
+    };
+    match alerts::list_deliveries(&state.pool, id, cfg::API_DELIVERY_LIMIT).await {
+        Ok(deliveries) => {
+            (StatusCode::OK, Json(serde_json::json!({ "rule": rule, "deliveries": deliveries }))).into_response()
+        }
+        Err(e) => db_error("GET /alerts/{id}", e),
+    }
+}
+
+// ── PATCH /alerts/{id} ──
+
+pub async fn update(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+    headers: HeaderMap,
+    axum::Json(mut body): axum::Json<UpdateAlertBody>,
+) -> Response {
+    let owner = match ALERTS_AUTH.verify(&headers) {
+        Ok(o) => o,
+        Err((status, message)) => return error_response(status, message),
+    };
+    if let Err(e) = validate_rule(body.condition.as_mut(), body.webhook_url.as_deref()).await {
+        return error_response(StatusCode::BAD_REQUEST, &e);
+    }
+
+    match alerts::update_rule(&state.pool, &owner, id, body.condition.as_ref(), body.webhook_url.as_deref(), body.enabled).await {
+        Ok(Some(rule)) => (StatusCode::OK, Json(rule)).into_response(),
+        Ok(None) => error_response(StatusCode::NOT_FOUND, "Alert rule not found"),
+        Err(e) => db_error("PATCH /alerts/{id}", e),
+    }
+}
+
+// ── DELETE /alerts/{id} ──
+
+pub async fn delete(State(state): State<Arc<AppState>>, Path(id): Path<i64>, headers: HeaderMap) -> Response {
+    let owner = match ALERTS_AUTH.verify(&headers) {
+        Ok(o) => o,
+        Err((status, message)) => return error_response(status, message),
+    };
+    match alerts::delete_rule(&state.pool, &owner, id).await {
+        Ok(true) => StatusCode::NO_CONTENT.into_response(),
+        Ok(false) => error_response(StatusCode::NOT_FOUND, "Alert rule not found"),
+        Err(e) => db_error("DELETE /alerts/{id}", e),
+    }
+}
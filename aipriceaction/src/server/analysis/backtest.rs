@@ -0,0 +1,45 @@
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Json},
+};
+use std::sync::Arc;
+
+use crate::server::AppState;
+use crate::services::backtest::{BacktestRequest, Plan};
+
+use super::{universe_history, AnalysisResponse};
+
+/// Simulate a strategy over stored daily bars, see `services::backtest`.
+#[tracing::instrument(skip(state, req))]
+pub async fn backtest_handler(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<BacktestRequest>,
+) -> impl IntoResponse {
+    let error = |status: StatusCode, error: String| (status, Json(serde_json::json!({ "error": error }))).into_response();
+
+    let plan = match Plan::new(&req, universe_history) {
+        Ok(plan) => plan,
+        Err(e) => return error(StatusCode::BAD_REQUEST, e),
+    };
+
+    let data = match plan.load(&state.pool).await {
+        Ok(data) => data,
+        Err(e) => {
+            tracing::error!("Backtest data load failed: {}", e);
+            return error(StatusCode::INTERNAL_SERVER_ERROR, "Database error".to_string());
+        }
+    };
+    let total_analyzed = data.values().filter(|bars| !bars.is_empty()).count();
+    let report = plan.run(&data);
+
+    (
+        StatusCode::OK,
+        Json(AnalysisResponse {
+            analysis_date: report.end_date.clone(),
+            analysis_type: "backtest".to_string(),
+            total_analyzed,
+            data: report,
+        }),
+    ).into_response()
+}
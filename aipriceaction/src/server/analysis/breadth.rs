@@ -0,0 +1,229 @@
+use std::collections::{BTreeMap, HashMap};
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Json},
+};
+use chrono::{Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::constants::breadth::{DEFAULT_DAYS, MARKET, MAX_DAYS};
+use crate::models::calendar::Exchange;
+use crate::queries::breadth::{self as breadth_q, BreadthDay};
+use crate::server::types::Mode;
+use crate::server::AppState;
+use crate::services::breadth;
+
+use super::{is_index_ticker, universe, AnalysisResponse};
+
+// ---------------------------------------------------------------------------
+// Query params
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Deserialize)]
+pub struct BreadthQuery {
+    /// vn (default), yahoo or crypto. Breadth is per market, so not `all`.
+    #[serde(default)]
+    pub mode: Mode,
+    /// Only this sector (the whole-market series is always returned).
+    pub sector: Option<String>,
+    /// Trading days of history.
+    #[serde(default = "default_days")]
+    pub days: usize,
+    /// Last day (YYYY-MM-DD, default: today).
+    pub date: Option<String>,
+    /// true = recompute the whole window instead of reusing stored days.
+    #[serde(default)]
+    pub refresh: bool,
+}
+
+fn default_days() -> usize {
+    DEFAULT_DAYS
+}
+
+// ---------------------------------------------------------------------------
+// Response types
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Serialize)]
+pub struct BreadthResponse {
+    pub source: String,
+    pub days: usize,
+    /// Whole market, oldest first.
+    pub market: Vec<BreadthPoint>,
+    pub sectors: BTreeMap<String, Vec<BreadthPoint>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BreadthPoint {
+    #[serde(flatten)]
+    pub day: BreadthDay,
+    pub pct_above_ma20: Option<f64>,
+    pub pct_above_ma50: Option<f64>,
+    pub pct_above_ma200: Option<f64>,
+    /// Cumulative advances − declines since the first returned day.
+    pub ad_line: i64,
+}
+
+fn pct(count: i32, of: i32) -> Option<f64> {
+    (of > 0).then(|| count as f64 * 100.0 / of as f64)
+}
+
+fn to_points(days: Vec<BreadthDay>) -> Vec<BreadthPoint> {
+    let mut ad_line = 0i64;
+    days.into_iter()
+        .map(|day| {
+            ad_line += (day.advances - day.declines) as i64;
+            BreadthPoint {
+                pct_above_ma20: pct(day.above_ma20, day.with_ma20),
+                pct_above_ma50: pct(day.above_ma50, day.with_ma50),
+                pct_above_ma200: pct(day.above_ma200, day.with_ma200),
+                ad_line,
+                day,
+            }
+        })
+        .collect()
+}
+
+// ---------------------------------------------------------------------------
+// Handler
+// ---------------------------------------------------------------------------
+
+/// Stored days are reused once the exchange's local day has ended; newer
+/// days are computed from daily bars and stored for the next request.
+#[tracing::instrument(skip(state))]
+pub async fn breadth_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<BreadthQuery>,
+) -> impl IntoResponse {
+    let bad_request = |error: String| {
+        (StatusCode::BAD_REQUEST, Json(serde_json::json!({ "error": error }))).into_response()
+    };
+    let db_error = || {
+        (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({ "error": "Database error" }))).into_response()
+    };
+    if params.mode == Mode::All {
+        return bad_request("Breadth is computed per market: use mode=vn, yahoo or crypto".to_string());
+    }
+    if params.days == 0 || params.days > MAX_DAYS {
+        return bad_request(format!("days must be between 1 and {MAX_DAYS}"));
+    }
+    let source = params.mode.source_label();
+
+    let now = Utc::now();
+    let local_today = Exchange::for_source(source)
+        .map(|e| (now.naive_utc() + e.utc_offset(now.date_naive())).date())
+        .unwrap_or_else(|| now.date_naive());
+    let end = match &params.date {
+        Some(date_str) => match NaiveDate::parse_from_str(date_str, "%Y-%m-%d") {
+            Ok(d) => d.min(local_today),
+            Err(_) => return bad_request(format!("Invalid date format '{date_str}'. Use YYYY-MM-DD")),
+        },
+        None => local_today,
+    };
+
+    // Sector membership as of the last day of the window
+    let mut groups = universe(source, end, None);
+    for tickers in groups.values_mut() {
+        tickers.retain(|t| !is_index_ticker(t));
+    }
+    groups.retain(|_, tickers| !tickers.is_empty());
+    if let Some(sector) = &params.sector
+        && !groups.contains_key(sector)
+    {
+        return bad_request(format!("Unknown sector '{sector}'"));
+    }
+
+    // Stored days that can no longer change
+    let stored = match breadth_q::market_dates(&state.pool, source, end, params.days as i64).await {
+        Ok(d) => d,
+        Err(e) => {
+            tracing::error!("Failed to read stored breadth for '{}': {}", source, e);
+            return db_error();
+        }
+    };
+    let final_days: Vec<NaiveDate> = stored.iter().copied().filter(|d| *d < local_today).collect();
+    let newest_final = final_days.first().copied();
+
+    // Window to compute: everything, or only the days after the newest final one
+    let incremental = newest_final.filter(|_| !params.refresh && final_days.len() >= params.days);
+    let needs_compute = incremental.is_none_or(|d| d < end);
+    let mut computed: Vec<BreadthDay> = Vec::new();
+    if needs_compute {
+        let bars = match incremental {
+            Some(d) => (end - d).num_days(),
+            None => params.days as i64,
+        } + breadth::WARMUP_BARS;
+        let symbols: Vec<String> = {
+            let mut all: Vec<String> = groups.values().flatten().cloned().collect();
+            all.sort();
+            all.dedup();
+            all
+        };
+        let end_time = end.and_hms_opt(23, 59, 59).map(|dt| dt.and_utc());
+        let rows = match breadth::load(&state.pool, source, &symbols, bars, end_time).await {
+            Ok(r) => r,
+            Err(e) => {
+                tracing::error!("Failed to fetch daily data for breadth '{}': {}", source, e);
+                return db_error();
+            }
+        };
+        let since = match incremental {
+            Some(d) => Some(d + Duration::days(1)),
+            None => breadth::trading_dates(&rows).into_iter().rev().nth(params.days - 1),
+        };
+        if let Some(since) = since.or_else(|| breadth::trading_dates(&rows).first().copied()) {
+            computed = breadth::compute(&groups, &rows, since);
+            if let Err(e) = breadth_q::save_breadth(&state.pool, source, &computed).await {
+                tracing::warn!("Failed to store breadth for '{}': {}", source, e);
+            }
+        }
+    }
+
+    // Stored rows overlaid with this run's, limited to the newest `days` days
+    let start = stored.iter().chain(computed.iter().map(|d| &d.date)).min().copied().unwrap_or(end);
+    let mut rows: BTreeMap<(NaiveDate, String), BreadthDay> = match breadth_q::list_breadth(&state.pool, source, start, end).await {
+        Ok(days) => days.into_iter().map(|d| ((d.date, d.sector.clone()), d)).collect(),
+        Err(e) => {
+            tracing::error!("Failed to read stored breadth for '{}': {}", source, e);
+            return db_error();
+        }
+    };
+    rows.extend(computed.into_iter().map(|d| ((d.date, d.sector.clone()), d)));
+    let mut market_dates: Vec<NaiveDate> = rows.keys().filter(|(_, s)| s == MARKET).map(|(d, _)| *d).collect();
+    let keep_from = market_dates.len().saturating_sub(params.days);
+    market_dates.drain(..keep_from);
+    let first = market_dates.first().copied().unwrap_or(end);
+
+    let mut market = Vec::new();
+    let mut sectors: HashMap<String, Vec<BreadthDay>> = HashMap::new();
+    for ((date, sector), day) in rows {
+        if date < first {
+            continue;
+        }
+        if sector == MARKET {
+            market.push(day);
+        } else if params.sector.as_ref().is_none_or(|s| *s == sector) {
+            sectors.entry(sector).or_default().push(day);
+        }
+    }
+
+    let total_analyzed = market.last().map_or(0, |d| d.total as usize);
+    (
+        StatusCode::OK,
+        Json(AnalysisResponse {
+            analysis_date: market.last().map_or_else(|| end.to_string(), |d| d.date.to_string()),
+            analysis_type: "breadth".to_string(),
+            total_analyzed,
+            data: BreadthResponse {
+                source: source.to_string(),
+                days: market.len(),
+                market: to_points(market),
+                sectors: sectors.into_iter().map(|(s, days)| (s, to_points(days))).collect(),
+            },
+        }),
+    )
+        .into_response()
+}
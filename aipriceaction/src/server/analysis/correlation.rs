@@ -0,0 +1,544 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Json},
+};
+use chrono::{Duration, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::models::ohlcv::OhlcvRow;
+use crate::queries::ohlcv;
+use crate::server::types::Mode;
+use crate::server::AppState;
+use crate::services::{fx, metadata};
+
+use super::{get_all_sources, get_ticker_sector, try_redis_batch, universe_day, AnalysisResponse};
+
+// ---------------------------------------------------------------------------
+// Query params
+// ---------------------------------------------------------------------------
+
+/// Max symbols in one matrix.
+const MAX_SYMBOLS: usize = 100;
+const MAX_LOOKBACK: usize = 1000;
+/// Pairs with fewer common returns get no correlation.
+const MIN_OBSERVATIONS: usize = 20;
+
+#[derive(Debug, Deserialize)]
+pub struct CorrelationQuery {
+    /// Comma-separated tickers, e.g. `VCB,BID,SJC-GOLD,BTCUSDT`.
+    pub symbols: Option<String>,
+    /// Sector group to add to `symbols`.
+    pub sector: Option<String>,
+    #[serde(default)]
+    pub mode: Mode,
+    /// Daily returns per pair (newest common dates).
+    #[serde(default = "default_lookback")]
+    pub lookback: usize,
+    /// Default: VNINDEX (vn, all), ^GSPC (yahoo), BTCUSDT (crypto).
+    pub benchmark: Option<String>,
+    /// Returns per rolling beta point.
+    #[serde(default = "default_beta_window")]
+    pub beta_window: usize,
+    /// Last day (YYYY-MM-DD, default: latest).
+    pub date: Option<String>,
+    /// USD or VND: compute returns in one currency, so cross-market pairs
+    /// include the FX move.
+    pub currency: Option<String>,
+}
+
+fn default_lookback() -> usize {
+    120
+}
+fn default_beta_window() -> usize {
+    60
+}
+
+fn default_benchmark(mode: Mode) -> &'static str {
+    match mode {
+        Mode::Yahoo => "^GSPC",
+        Mode::Crypto => "BTCUSDT",
+        Mode::Vn | Mode::All => "VNINDEX",
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Response types
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Serialize)]
+pub struct CorrelationResponse {
+    pub benchmark: Instrument,
+    pub lookback: usize,
+    pub beta_window: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub currency: Option<String>,
+    pub symbols: Vec<SymbolStats>,
+    /// `pearson[i][j]`, in `symbols` order; null below the minimum overlap.
+    pub pearson: Vec<Vec<Option<f64>>>,
+    pub spearman: Vec<Vec<Option<f64>>>,
+    /// Symbols in dendrogram leaf order (similar series next to each other).
+    pub cluster_order: Vec<String>,
+    /// Average-linkage merges on `sqrt((1 - pearson) / 2)`. Leaves are
+    /// `0..n` in `symbols` order, the k-th merge creates cluster `n + k`.
+    pub clusters: Vec<Merge>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Instrument {
+    pub symbol: String,
+    pub source: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SymbolStats {
+    pub symbol: String,
+    pub source: String,
+    pub sector: Option<String>,
+    /// Returns in common with the benchmark.
+    pub observations: usize,
+    pub beta: Option<f64>,
+    pub correlation_to_benchmark: Option<f64>,
+    pub rolling_beta: Vec<BetaPoint>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BetaPoint {
+    pub date: String,
+    pub beta: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Merge {
+    pub left: usize,
+    pub right: usize,
+    pub distance: f64,
+    pub size: usize,
+}
+
+// ---------------------------------------------------------------------------
+// Math helpers
+// ---------------------------------------------------------------------------
+
+/// Daily closes keyed by calendar date: markets stamp their daily bars at
+/// different times of day, so cross-source pairs align on the date.
+fn closes_by_date(rows: &[OhlcvRow]) -> BTreeMap<NaiveDate, f64> {
+    rows.iter().filter(|r| r.close > 0.0).map(|r| (r.time.date_naive(), r.close)).collect()
+}
+
+/// Returns of `a` and `b` between consecutive common dates, newest `lookback`
+/// kept — the return counterpart of `rrg::align_closes_by_date`.
+fn aligned_returns(
+    a: &BTreeMap<NaiveDate, f64>,
+    b: &BTreeMap<NaiveDate, f64>,
+    lookback: usize,
+) -> (Vec<NaiveDate>, Vec<f64>, Vec<f64>) {
+    let common: Vec<(NaiveDate, f64, f64)> =
+        a.iter().filter_map(|(d, ca)| b.get(d).map(|cb| (*d, *ca, *cb))).collect();
+    let skip = common.len().saturating_sub(lookback + 1);
+    let common = &common[skip..];
+    let mut dates = Vec::new();
+    let mut ra = Vec::new();
+    let mut rb = Vec::new();
+    for w in common.windows(2) {
+        dates.push(w[1].0);
+        ra.push(w[1].1 / w[0].1 - 1.0);
+        rb.push(w[1].2 / w[0].2 - 1.0);
+    }
+    (dates, ra, rb)
+}
+
+fn mean(x: &[f64]) -> f64 {
+    x.iter().sum::<f64>() / x.len() as f64
+}
+
+/// Sample covariance and the two variances.
+fn moments(x: &[f64], y: &[f64]) -> Option<(f64, f64, f64)> {
+    if x.len() != y.len() || x.len() < 2 {
+        return None;
+    }
+    let (mx, my) = (mean(x), mean(y));
+    let (mut cov, mut vx, mut vy) = (0.0, 0.0, 0.0);
+    for (a, b) in x.iter().zip(y) {
+        cov += (a - mx) * (b - my);
+        vx += (a - mx).powi(2);
+        vy += (b - my).powi(2);
+    }
+    let n = (x.len() - 1) as f64;
+    Some((cov / n, vx / n, vy / n))
+}
+
+fn pearson(x: &[f64], y: &[f64]) -> Option<f64> {
+    let (cov, vx, vy) = moments(x, y)?;
+    (vx > 0.0 && vy > 0.0).then(|| (cov / (vx * vy).sqrt()).clamp(-1.0, 1.0))
+}
+
+/// 1-based ranks, ties sharing their average rank.
+fn ranks(x: &[f64]) -> Vec<f64> {
+    let mut order: Vec<usize> = (0..x.len()).collect();
+    order.sort_by(|&a, &b| x[a].total_cmp(&x[b]));
+    let mut out = vec![0.0; x.len()];
+    let mut i = 0;
+    while i < order.len() {
+        let mut j = i;
+        while j + 1 < order.len() && x[order[j + 1]] == x[order[i]] {
+            j += 1;
+        }
+        let rank = (i + j) as f64 / 2.0 + 1.0;
+        for &k in &order[i..=j] {
+            out[k] = rank;
+        }
+        i = j + 1;
+    }
+    out
+}
+
+fn spearman(x: &[f64], y: &[f64]) -> Option<f64> {
+    pearson(&ranks(x), &ranks(y))
+}
+
+/// Slope of `security` returns on `benchmark` returns.
+fn beta(security: &[f64], benchmark: &[f64]) -> Option<f64> {
+    let (cov, _, vb) = moments(security, benchmark)?;
+    (vb > 0.0).then(|| cov / vb)
+}
+
+/// Beta over each trailing `window` of returns.
+fn rolling_beta(dates: &[NaiveDate], security: &[f64], benchmark: &[f64], window: usize) -> Vec<BetaPoint> {
+    if window < 2 || security.len() < window {
+        return Vec::new();
+    }
+    (window..=security.len())
+        .filter_map(|end| {
+            beta(&security[end - window..end], &benchmark[end - window..end])
+                .map(|b| BetaPoint { date: dates[end - 1].to_string(), beta: b })
+        })
+        .collect()
+}
+
+/// Average-linkage agglomerative clustering over a symmetric distance
+/// matrix. Returns the dendrogram leaf order and the merges.
+fn cluster(dist: &[Vec<f64>]) -> (Vec<usize>, Vec<Merge>) {
+    let n = dist.len();
+    // Active clusters: (id, leaves)
+    let mut active: Vec<(usize, Vec<usize>)> = (0..n).map(|i| (i, vec![i])).collect();
+    let mut merges = Vec::new();
+    while active.len() > 1 {
+        let mut best = (0, 1, f64::INFINITY);
+        for a in 0..active.len() {
+            for b in a + 1..active.len() {
+                let (la, lb) = (&active[a].1, &active[b].1);
+                let total: f64 = la.iter().flat_map(|&i| lb.iter().map(move |&j| dist[i][j])).sum();
+                let d = total / (la.len() * lb.len()) as f64;
+                if d < best.2 {
+                    best = (a, b, d);
+                }
+            }
+        }
+        let (a, b, distance) = best;
+        let (right_id, right) = active.remove(b);
+        let (left_id, mut leaves) = active.remove(a);
+        leaves.extend(right);
+        merges.push(Merge { left: left_id, right: right_id, distance, size: leaves.len() });
+        active.push((n + merges.len() - 1, leaves));
+    }
+    (active.pop().map(|(_, leaves)| leaves).unwrap_or_default(), merges)
+}
+
+// ---------------------------------------------------------------------------
+// Handler
+// ---------------------------------------------------------------------------
+
+#[tracing::instrument(skip(state))]
+pub async fn correlation_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<CorrelationQuery>,
+) -> impl IntoResponse {
+    let bad_request = |error: String| {
+        (StatusCode::BAD_REQUEST, Json(serde_json::json!({ "error": error }))).into_response()
+    };
+    if params.lookback < MIN_OBSERVATIONS || params.lookback > MAX_LOOKBACK {
+        return bad_request(format!("lookback must be between {MIN_OBSERVATIONS} and {MAX_LOOKBACK}"));
+    }
+    if params.beta_window < 2 || params.beta_window > params.lookback {
+        return bad_request("beta_window must be between 2 and lookback".to_string());
+    }
+    let currency = match fx::parse_target(params.currency.as_deref()) {
+        Ok(c) => c,
+        Err(e) => return bad_request(e),
+    };
+    let end_time = match &params.date {
+        Some(date_str) => match NaiveDate::parse_from_str(date_str, "%Y-%m-%d") {
+            Ok(d) => d.and_hms_opt(23, 59, 59).map(|dt| dt.and_utc()),
+            Err(_) => return bad_request(format!("Invalid date format '{date_str}'. Use YYYY-MM-DD")),
+        },
+        None => None,
+    };
+
+    let day = universe_day(params.date.as_deref());
+    let sources: Vec<&'static str> =
+        if params.mode == Mode::All { get_all_sources() } else { vec![params.mode.source_label()] };
+    let groups: HashMap<&str, BTreeMap<String, Vec<String>>> =
+        sources.iter().map(|&s| (s, super::universe(s, day, None))).collect();
+
+    // Universe: sector members (source known) plus explicit symbols
+    let mut universe: Vec<(String, Option<&'static str>)> = Vec::new();
+    if let Some(sector) = &params.sector {
+        let Some((source, members)) =
+            sources.iter().find_map(|&s| groups[s].get(sector.as_str()).map(|members| (s, members)))
+        else {
+            return bad_request(format!("Unknown sector '{sector}'"));
+        };
+        universe.extend(members.iter().map(|t| (t.clone(), Some(source))));
+    }
+    for symbol in params.symbols.as_deref().unwrap_or("").split(',') {
+        let symbol = symbol.trim().to_uppercase();
+        if !symbol.is_empty() && !universe.iter().any(|(t, _)| *t == symbol) {
+            universe.push((symbol, None));
+        }
+    }
+    if universe.len() < 2 {
+        return bad_request("Need at least 2 symbols (symbols= and/or sector=)".to_string());
+    }
+    if universe.len() > MAX_SYMBOLS {
+        return bad_request(format!("At most {MAX_SYMBOLS} symbols"));
+    }
+    let benchmark = params
+        .benchmark
+        .as_deref()
+        .map(|b| b.trim().to_uppercase())
+        .unwrap_or_else(|| default_benchmark(params.mode).to_string());
+
+    // Source of every symbol: the mode's source, or in mode=all the first
+    // source (in get_all_sources order) that has the ticker
+    let mut source_of: HashMap<String, &'static str> = HashMap::new();
+    if params.mode == Mode::All {
+        let bare: Vec<String> = universe
+            .iter()
+            .filter(|(_, s)| s.is_none())
+            .map(|(t, _)| t.clone())
+            .chain(std::iter::once(benchmark.clone()))
+            .collect();
+        let resolved = crate::server::api::fetch::resolve_source_map(&state.redis_client, &state.pool, Some(&bare)).await;
+        for &source in sources.iter().rev() {
+            for ticker in resolved.get(source).into_iter().flatten() {
+                source_of.insert(ticker.clone(), source);
+            }
+        }
+    }
+    for (ticker, source) in &universe {
+        let source = source.or_else(|| source_of.get(ticker).copied()).unwrap_or(sources[0]);
+        source_of.insert(ticker.clone(), source);
+    }
+    let benchmark_source = source_of.get(&benchmark).copied().unwrap_or(sources[0]);
+
+    // Fetch: crypto trades 7 days a week, so pull extra bars for cross-market pairs
+    let per_ticker = (params.lookback as i64 + 1) * 3 / 2 + 5;
+    let mut by_source: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    for (ticker, source) in &source_of {
+        by_source.entry(source).or_default().push(ticker.clone());
+    }
+    by_source.entry(benchmark_source).or_default().push(benchmark.clone());
+    let mut closes: HashMap<(String, &str), BTreeMap<NaiveDate, f64>> = HashMap::new();
+    for (source, mut tickers) in by_source {
+        tickers.sort();
+        tickers.dedup();
+        let cached = if end_time.is_none() {
+            try_redis_batch(&state.redis_client, source, &tickers, "1D", per_ticker, "correlation")
+                .await
+                .filter(|m| tickers.iter().all(|t| m.get(t).is_some_and(|rows| rows.len() as i64 >= per_ticker)))
+        } else {
+            None
+        };
+        let rows = match cached {
+            Some(rows) => rows,
+            None => match ohlcv::get_ohlcv_batch_raw(&state.pool, source, &tickers, "1D", Some(per_ticker), None, end_time).await {
+                Ok(rows) => rows,
+                Err(e) => {
+                    tracing::error!("Failed to fetch daily data for source '{}': {}", source, e);
+                    return (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(serde_json::json!({ "error": "Database error" })),
+                    )
+                        .into_response();
+                }
+            },
+        };
+        for (ticker, rows) in rows {
+            closes.insert((ticker, source), closes_by_date(&rows));
+        }
+    }
+
+    if let Some(target) = currency {
+        let dates = closes.values().flat_map(|s| s.keys().copied());
+        if let (Some(first), Some(last)) = (dates.clone().min(), dates.max()) {
+            let day_start = |d: NaiveDate| d.and_hms_opt(0, 0, 0).map(|dt| dt.and_utc()).unwrap_or_default();
+            let catalog = metadata::catalog();
+            let codes: BTreeSet<&str> = closes
+                .keys()
+                .filter_map(|(ticker, source)| catalog.currency(source, ticker))
+                .chain(std::iter::once(target.as_str()))
+                .collect();
+            let rates = match fx::FxRates::load(&state.pool, &codes, day_start(first), day_start(last + Duration::days(1)), false).await {
+                Ok(r) => r,
+                Err(e) => {
+                    tracing::error!("Failed to load FX rates: {}", e);
+                    return (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(serde_json::json!({ "error": "Failed to load FX rates" })),
+                    )
+                        .into_response();
+                }
+            };
+            for ((ticker, source), series) in closes.iter_mut() {
+                let Some(from) = catalog.currency(source, ticker) else { continue };
+                for (date, close) in series.iter_mut() {
+                    if let Some(f) = rates.factor(from, target.as_str(), day_start(*date), false) {
+                        *close *= f;
+                    }
+                }
+            }
+        }
+    }
+
+    let empty = BTreeMap::new();
+    let series = |ticker: &str| closes.get(&(ticker.to_string(), source_of[ticker])).unwrap_or(&empty);
+    let bench_series = closes.get(&(benchmark.clone(), benchmark_source)).unwrap_or(&empty);
+
+    let symbols: Vec<&str> = universe.iter().map(|(t, _)| t.as_str()).collect();
+    let n = symbols.len();
+    let mut pearson_m = vec![vec![None; n]; n];
+    let mut spearman_m = vec![vec![None; n]; n];
+    for i in 0..n {
+        pearson_m[i][i] = Some(1.0);
+        spearman_m[i][i] = Some(1.0);
+        for j in i + 1..n {
+            let (_, a, b) = aligned_returns(series(symbols[i]), series(symbols[j]), params.lookback);
+            if a.len() >= MIN_OBSERVATIONS {
+                let (p, s) = (pearson(&a, &b), spearman(&a, &b));
+                (pearson_m[i][j], pearson_m[j][i]) = (p, p);
+                (spearman_m[i][j], spearman_m[j][i]) = (s, s);
+            }
+        }
+    }
+
+    let stats: Vec<SymbolStats> = symbols
+        .iter()
+        .map(|&ticker| {
+            let source = source_of[ticker];
+            let (dates, sec, bench) = aligned_returns(series(ticker), bench_series, params.lookback);
+            let enough = sec.len() >= MIN_OBSERVATIONS;
+            SymbolStats {
+                symbol: ticker.to_string(),
+                source: source.to_string(),
+                sector: groups.get(source).and_then(|g| get_ticker_sector(ticker, g)),
+                observations: sec.len(),
+                beta: if enough { beta(&sec, &bench) } else { None },
+                correlation_to_benchmark: if enough { pearson(&sec, &bench) } else { None },
+                rolling_beta: rolling_beta(&dates, &sec, &bench, params.beta_window),
+            }
+        })
+        .collect();
+    let total_analyzed = stats.iter().filter(|s| !series(&s.symbol).is_empty()).count();
+
+    // Unknown pairs count as uncorrelated
+    let dist: Vec<Vec<f64>> = pearson_m
+        .iter()
+        .map(|row| row.iter().map(|p| ((1.0 - p.unwrap_or(0.0)) / 2.0).sqrt()).collect())
+        .collect();
+    let (order, clusters) = cluster(&dist);
+
+    let analysis_date = params.date.clone().unwrap_or_else(|| "latest".to_string());
+    (
+        StatusCode::OK,
+        Json(AnalysisResponse {
+            analysis_date,
+            analysis_type: "correlation".to_string(),
+            total_analyzed,
+            data: CorrelationResponse {
+                benchmark: Instrument { symbol: benchmark.clone(), source: benchmark_source.to_string() },
+                lookback: params.lookback,
+                beta_window: params.beta_window,
+                currency: currency.map(|c| c.as_str().to_string()),
+                cluster_order: order.iter().map(|&i| symbols[i].to_string()).collect(),
+                symbols: stats,
+                pearson: pearson_m,
+                spearman: spearman_m,
+                clusters,
+            },
+        }),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn series(start: NaiveDate, closes: &[f64], step_days: i64) -> BTreeMap<NaiveDate, f64> {
+        closes
+            .iter()
+            .enumerate()
+            .map(|(i, &c)| (start + chrono::Duration::days(i as i64 * step_days), c))
+            .collect()
+    }
+
+    #[test]
+    fn test_aligned_returns_use_common_dates_only() {
+        let d0 = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        // Daily vs every other day: common dates are d0, d2, d4
+        let a = series(d0, &[100.0, 200.0, 110.0, 300.0, 121.0], 1);
+        let b = series(d0, &[10.0, 20.0, 40.0], 2);
+        let (dates, ra, rb) = aligned_returns(&a, &b, 10);
+        assert_eq!(dates, vec![d0 + chrono::Duration::days(2), d0 + chrono::Duration::days(4)]);
+        assert!((ra[0] - 0.1).abs() < 1e-12 && (ra[1] - 0.1).abs() < 1e-12);
+        assert_eq!(rb, vec![1.0, 1.0]);
+        // Lookback keeps the newest returns
+        let (dates, _, _) = aligned_returns(&a, &b, 1);
+        assert_eq!(dates, vec![d0 + chrono::Duration::days(4)]);
+    }
+
+    #[test]
+    fn test_pearson_spearman_beta() {
+        let x = [0.01, -0.02, 0.03, 0.0, 0.015];
+        let y: Vec<f64> = x.iter().map(|v| 2.0 * v + 0.001).collect();
+        assert!((pearson(&x, &y).unwrap() - 1.0).abs() < 1e-12);
+        assert!((beta(&y, &x).unwrap() - 2.0).abs() < 1e-12);
+        let inverse: Vec<f64> = x.iter().map(|v| -v * v * v).collect();
+        assert!((spearman(&x, &inverse).unwrap() + 1.0).abs() < 1e-12);
+        assert_eq!(ranks(&[3.0, 1.0, 3.0, 2.0]), vec![3.5, 1.0, 3.5, 2.0]);
+        assert_eq!(pearson(&[1.0, 1.0], &[1.0, 2.0]), None);
+    }
+
+    #[test]
+    fn test_rolling_beta_windows() {
+        let d0 = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        let dates: Vec<NaiveDate> = (0..5).map(|i| d0 + chrono::Duration::days(i)).collect();
+        let bench = [0.01, -0.01, 0.02, -0.02, 0.01];
+        let sec: Vec<f64> = bench.iter().map(|b| 1.5 * b).collect();
+        let points = rolling_beta(&dates, &sec, &bench, 3);
+        assert_eq!(points.len(), 3);
+        assert_eq!(points[0].date, "2025-01-03");
+        assert!(points.iter().all(|p| (p.beta - 1.5).abs() < 1e-12));
+    }
+
+    #[test]
+    fn test_cluster_groups_close_pairs() {
+        // 0-2 and 1-3 are near each other
+        let dist = vec![
+            vec![0.0, 0.9, 0.1, 0.8],
+            vec![0.9, 0.0, 0.85, 0.2],
+            vec![0.1, 0.85, 0.0, 0.9],
+            vec![0.8, 0.2, 0.9, 0.0],
+        ];
+        let (order, merges) = cluster(&dist);
+        assert_eq!(order, vec![0, 2, 1, 3]);
+        assert_eq!(merges[0], Merge { left: 0, right: 2, distance: 0.1, size: 2 });
+        assert_eq!(merges[1], Merge { left: 1, right: 3, distance: 0.2, size: 2 });
+        assert_eq!((merges[2].left, merges[2].right, merges[2].size), (4, 5, 4));
+        assert!((merges[2].distance - 0.8625).abs() < 1e-12);
+    }
+}
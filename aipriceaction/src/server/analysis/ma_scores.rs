@@ -7,12 +7,13 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
 
-use crate::queries::ohlcv;
+use crate::models::indicators::{DEFAULT_MA_PERIODS, MAX_MA_PERIOD, ma_lookback};
+use crate::queries::ohlcv::{self, FetchOptions};
 use crate::server::types::Mode;
 use crate::server::AppState;
-use crate::constants::api::{EMA_LOOKBACK, SMA_MAX_PERIOD};
+use crate::services::fx;
 
-use super::{get_all_sources, get_tickers_in_sector, is_index_ticker, load_crypto_groups, load_ticker_groups, load_yahoo_groups, AnalysisResponse};
+use super::{get_all_sources, get_tickers_in_sector, is_index_ticker, universe, universe_day, validate_index, AnalysisResponse};
 
 #[derive(Debug, Deserialize)]
 pub struct MaScoresBySectorQuery {
@@ -32,6 +33,10 @@ pub struct MaScoresBySectorQuery {
     /// true = use Redis snapshot cache (default).
     #[serde(default = "default_true")]
     pub snap: bool,
+    /// USD or VND: convert `close` and `ma_value`.
+    pub currency: Option<String>,
+    /// Only the constituents of this index (e.g. VN30) on `date`.
+    pub index: Option<String>,
 }
 
 fn default_ma_period() -> u32 { 20 }
@@ -43,6 +48,8 @@ pub struct MaScoresBySectorResponse {
     pub sectors: Vec<SectorMaAnalysis>,
     pub ma_period: u32,
     pub threshold: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub currency: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -72,26 +79,34 @@ pub async fn ma_scores_by_sector_handler(
     State(state): State<Arc<AppState>>,
     Query(params): Query<MaScoresBySectorQuery>,
 ) -> impl IntoResponse {
-    if ![10, 20, 50, 100, 200].contains(&params.ma_period) {
+    let period = params.ma_period as usize;
+    if !(1..=MAX_MA_PERIOD).contains(&period) {
         return (
             StatusCode::BAD_REQUEST,
             Json(serde_json::json!({
-                "error": "Invalid MA period. Must be one of: 10, 20, 50, 100, 200"
+                "error": format!("Invalid MA period. Must be between 1 and {MAX_MA_PERIOD}")
             })),
         ).into_response();
     }
-
-    let ticker_groups = match load_ticker_groups() {
-        Ok(groups) => groups,
+    // Default periods share the snapshot cache with /tickers; others are computed alone.
+    let ma_periods = if DEFAULT_MA_PERIODS.contains(&period) { DEFAULT_MA_PERIODS.to_vec() } else { vec![period] };
+    let redis_limit = 1 + ma_lookback(&ma_periods, params.ema);
+    let currency = match fx::parse_target(params.currency.as_deref()) {
+        Ok(c) => c,
         Err(e) => {
-            tracing::error!("Failed to load ticker groups: {}", e);
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(serde_json::json!({ "error": "Failed to load sector information" })),
-            ).into_response();
+            return (StatusCode::BAD_REQUEST, Json(serde_json::json!({ "error": e }))).into_response();
         }
     };
 
+    if let Err(e) = validate_index(params.index.as_deref()) {
+        return (StatusCode::BAD_REQUEST, Json(serde_json::json!({ "error": e }))).into_response();
+    }
+
+    // Sector membership as of the analysis date
+    let day = universe_day(params.date.as_deref());
+    let index = params.index.as_deref();
+    let ticker_groups: HashMap<String, Vec<String>> = universe("vn", day, index).into_iter().collect();
+
     let is_all = params.mode == Mode::All;
 
     // Build symbol lists per source
@@ -100,8 +115,8 @@ pub async fn ma_scores_by_sector_handler(
         sources.iter().map(|&src| {
             let symbols = match src {
                 "vn" => ticker_groups.values().flat_map(|v| v.iter().cloned()).collect(),
-                "crypto" => load_crypto_groups().map(|g| g.into_values().flatten().collect()).unwrap_or_default(),
-                "yahoo" => load_yahoo_groups().map(|g| g.into_values().flatten().collect()).unwrap_or_default(),
+                "crypto" => universe("crypto", day, index).into_values().flatten().collect(),
+                "yahoo" => universe("yahoo", day, index).into_values().flatten().collect(),
                 _ => Vec::new(),
             };
             (src, symbols)
@@ -110,25 +125,38 @@ pub async fn ma_scores_by_sector_handler(
         let source = params.mode.source_label();
         let symbols: Vec<String> = match source {
             "vn" => ticker_groups.values().flat_map(|v| v.iter().cloned()).collect(),
-            "crypto" => load_crypto_groups().map(|g| g.into_values().flatten().collect()).unwrap_or_default(),
-            "yahoo" => load_yahoo_groups().map(|g| g.into_values().flatten().collect()).unwrap_or_default(),
+            "crypto" => universe("crypto", day, index).into_values().flatten().collect(),
+            "yahoo" => universe("yahoo", day, index).into_values().flatten().collect(),
             _ => Vec::new(),
         };
         vec![(source, symbols)]
     };
 
+    // currency=: bars are converted before MAs and changes are computed
+    let conversions = match super::load_conversions(&state.pool, &source_symbols, currency, None, redis_limit).await {
+        Ok(c) => c,
+        Err(e) => {
+            tracing::error!("Failed to load FX rates: {}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": "Failed to load FX rates" })),
+            ).into_response();
+        }
+    };
+    let fetch_opts = FetchOptions { use_ema: params.ema, ma_periods: &ma_periods, snap: params.snap, ..Default::default() };
     // Fetch latest daily data with Redis-first, PG fallback per source
     let rows: Vec<(crate::models::ohlcv::OhlcvJoined, &str)> = if is_all {
         let sources = get_all_sources();
-        let redis_limit = 1 + if params.ema { EMA_LOOKBACK } else { SMA_MAX_PERIOD };
         let syms: Vec<Vec<String>> = sources.iter()
             .map(|src| source_symbols.iter().find(|(s,_)| *s == *src).map(|(_,v)| v.clone()).unwrap_or_default())
             .collect();
+        let opts: Vec<FetchOptions> =
+            sources.iter().map(|src| FetchOptions { fx: conversions.get(*src), ..fetch_opts }).collect();
         let (r1, r2, r3, r4) = tokio::join!(
-            super::fetch_source_enhanced(&state.redis_client, sources[0], &syms[0], "1D", redis_limit, "ma_scores", params.ema, !params.snap),
-            super::fetch_source_enhanced(&state.redis_client, sources[1], &syms[1], "1D", redis_limit, "ma_scores", params.ema, !params.snap),
-            super::fetch_source_enhanced(&state.redis_client, sources[2], &syms[2], "1D", redis_limit, "ma_scores", params.ema, !params.snap),
-            super::fetch_source_enhanced(&state.redis_client, sources[3], &syms[3], "1D", redis_limit, "ma_scores", params.ema, !params.snap),
+            super::fetch_source_enhanced(&state.redis_client, sources[0], &syms[0], "1D", redis_limit, "ma_scores", &opts[0]),
+            super::fetch_source_enhanced(&state.redis_client, sources[1], &syms[1], "1D", redis_limit, "ma_scores", &opts[1]),
+            super::fetch_source_enhanced(&state.redis_client, sources[2], &syms[2], "1D", redis_limit, "ma_scores", &opts[2]),
+            super::fetch_source_enhanced(&state.redis_client, sources[3], &syms[3], "1D", redis_limit, "ma_scores", &opts[3]),
         );
         let mut merged = Vec::new();
         for (map, src) in [(r1, sources[0]), (r2, sources[1]), (r3, sources[2]), (r4, sources[3])] {
@@ -140,7 +168,8 @@ pub async fn ma_scores_by_sector_handler(
     } else {
         let source = params.mode.source_label();
         let symbols: Vec<String> = source_symbols.iter().find(|(s,_)| *s == source).map(|(_,v)| v.clone()).unwrap_or_default();
-        let map = super::fetch_source_enhanced(&state.redis_client, source, &symbols, "1D", 1 + if params.ema { EMA_LOOKBACK } else { SMA_MAX_PERIOD }, "ma_scores/single", params.ema, !params.snap).await;
+        let opts = FetchOptions { fx: conversions.get(source), ..fetch_opts };
+        let map = super::fetch_source_enhanced(&state.redis_client, source, &symbols, "1D", redis_limit, "ma_scores/single", &opts).await;
         let mut merged: Vec<(crate::models::ohlcv::OhlcvJoined, &str)> = Vec::new();
         for (_ticker, bars) in map {
             merged.extend(bars.into_iter().map(|row| (row, "")));
@@ -148,7 +177,7 @@ pub async fn ma_scores_by_sector_handler(
         if !merged.is_empty() {
             merged
         } else {
-            match ohlcv::get_latest_daily_per_ticker(&state.pool, source).await {
+            match ohlcv::get_latest_daily_per_ticker(&state.pool, source, &opts).await {
                 Ok(r) => r.into_iter().map(|row| (row, "")).collect(),
                 Err(e) => {
                     tracing::error!("Failed to fetch daily data: {}", e);
@@ -188,15 +217,11 @@ pub async fn ma_scores_by_sector_handler(
 
     // For mode=all, add yahoo/global and crypto sector groups
     if is_all {
-        if let Ok(yahoo_groups) = load_yahoo_groups() {
-            for (sector_name, tickers) in yahoo_groups {
-                all_sector_tickers.push((sector_name, tickers, false, Some("yahoo")));
-            }
+        for (sector_name, tickers) in universe("yahoo", day, index) {
+            all_sector_tickers.push((sector_name, tickers, false, Some("yahoo")));
         }
-        if let Ok(crypto_groups) = load_crypto_groups() {
-            for (sector_name, tickers) in crypto_groups {
-                all_sector_tickers.push((sector_name, tickers, false, Some("crypto")));
-            }
+        for (sector_name, tickers) in universe("crypto", day, index) {
+            all_sector_tickers.push((sector_name, tickers, false, Some("crypto")));
         }
     }
 
@@ -233,14 +258,7 @@ pub async fn ma_scores_by_sector_handler(
                 }
             };
 
-            let (ma_value, ma_score) = match params.ma_period {
-                10 => (current.ma10, current.ma10_score),
-                20 => (current.ma20, current.ma20_score),
-                50 => (current.ma50, current.ma50_score),
-                100 => (current.ma100, current.ma100_score),
-                200 => (current.ma200, current.ma200_score),
-                _ => (None, None),
-            };
+            let (ma_value, ma_score) = current.ma(period);
 
             if let (Some(ma_val), Some(ma_scr)) = (ma_value, ma_score) {
                 let above_threshold = ma_scr >= params.min_score;
@@ -314,6 +332,7 @@ pub async fn ma_scores_by_sector_handler(
                 sectors: sector_analyses,
                 ma_period: params.ma_period,
                 threshold: params.min_score,
+                currency: currency.map(|c| c.as_str().to_string()),
             },
         }),
     ).into_response()
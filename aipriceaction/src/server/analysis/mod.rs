@@ -2,17 +2,30 @@ pub mod performers;
 pub mod ma_scores;
 pub mod volume_profile;
 pub mod rrg;
+pub mod screen;
+pub mod backtest;
+pub mod correlation;
+pub mod breadth;
 
+use chrono::NaiveDate;
 use serde::Serialize;
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 use crate::models::ohlcv::OhlcvRow;
+use crate::queries::ohlcv::FetchOptions;
+use crate::models::portfolio::Currency;
+use crate::services::fx::Conversion;
+use crate::services::metadata;
 use crate::redis::RedisClient;
 
 pub use performers::top_performers_handler;
 pub use ma_scores::ma_scores_by_sector_handler;
 pub use volume_profile::volume_profile_handler;
 pub use rrg::rrg_handler;
+pub use screen::screen_handler;
+pub use backtest::backtest_handler;
+pub use correlation::correlation_handler;
+pub use breadth::breadth_handler;
 
 /// Try reading pre-computed OhlcvJoined from snapshot cache.
 /// Returns `Some` if snapshots exist for enough tickers (>=90% hit rate).
@@ -23,10 +36,11 @@ pub async fn try_snap_joined(
     interval: &str,
     limit: i64,
     ma_type: &str,
+    ma_periods: &[usize],
 ) -> Option<std::collections::HashMap<String, Vec<crate::models::ohlcv::OhlcvJoined>>> {
     let client = redis_client.as_ref()?;
     let result = crate::workers::redis_worker::batch_read_joined_snapshots(
-        client, source, symbols, interval, limit, ma_type,
+        client, source, symbols, interval, limit, ma_type, ma_periods,
     ).await?;
     if result.len() >= symbols.len() * 9 / 10 {
         Some(result)
@@ -36,8 +50,10 @@ pub async fn try_snap_joined(
 }
 
 /// Fetch enhanced data for a single source with snapshot optimization.
-/// Tries snapshot first, falls through to try_redis_batch + enhance_rows.
-/// On miss, writes joined snapshots for future reads (fire-and-forget).
+/// MAs are computed for `opts.ma_periods`; the snapshot field includes the set.
+/// Tries snapshot first (when `opts.snap`), falls through to try_redis_batch +
+/// enhance_rows. On miss, writes joined snapshots for future reads
+/// (fire-and-forget). Keeps the newest bar per ticker.
 pub async fn fetch_source_enhanced(
     redis_client: &Option<RedisClient>,
     source: &str,
@@ -45,14 +61,33 @@ pub async fn fetch_source_enhanced(
     interval: &str,
     redis_limit: i64,
     ctx: &str,
-    use_ema: bool,
-    skip_snap: bool,
+    opts: &FetchOptions<'_>,
 ) -> std::collections::HashMap<String, Vec<crate::models::ohlcv::OhlcvJoined>> {
+    let opts = FetchOptions { limit: Some(1), indicators: &[], ..*opts };
+    fetch_source_window(redis_client, source, symbols, interval, redis_limit, ctx, &opts).await
+}
+
+/// Like `fetch_source_enhanced`, but keeps the newest `opts.limit` bars per
+/// ticker (newest first) and adds extra `opts.indicators`. Snapshots only hold
+/// raw-currency MA fields, so they are bypassed when `indicators` is non-empty
+/// or a `currency=` conversion is set.
+pub async fn fetch_source_window(
+    redis_client: &Option<RedisClient>,
+    source: &str,
+    symbols: &[String],
+    interval: &str,
+    redis_limit: i64,
+    ctx: &str,
+    opts: &FetchOptions<'_>,
+) -> std::collections::HashMap<String, Vec<crate::models::ohlcv::OhlcvJoined>> {
+    let FetchOptions { use_ema, ma_periods, indicators, snap, fx, .. } = *opts;
+    let keep = opts.limit.unwrap_or(1);
     let ma_type = if use_ema { "ema" } else { "sma" };
+    let use_snap = snap && indicators.is_empty() && fx.is_none();
 
     // Try snapshot cache
-    if !skip_snap {
-        if let Some(snap_map) = try_snap_joined(redis_client, source, symbols, interval, 1, ma_type).await {
+    if use_snap {
+        if let Some(snap_map) = try_snap_joined(redis_client, source, symbols, interval, keep, ma_type, ma_periods).await {
             return snap_map;
         }
     }
@@ -61,7 +96,7 @@ pub async fn fetch_source_enhanced(
     let mut result = std::collections::HashMap::new();
     if let Some(map) = try_redis_batch(redis_client, source, symbols, interval, redis_limit, ctx).await {
         for (ticker, orows) in map {
-            let enhanced = crate::queries::ohlcv::enhance_rows(&ticker, orows, Some(1), None, true, use_ema);
+            let enhanced = crate::queries::ohlcv::enhance_rows(&ticker, orows, &FetchOptions { limit: Some(keep), ..*opts });
             if !enhanced.is_empty() {
                 result.insert(ticker, enhanced);
             }
@@ -69,16 +104,17 @@ pub async fn fetch_source_enhanced(
     }
 
     // Write joined snapshots for future reads (fire-and-forget)
-    if !result.is_empty() {
+    if use_snap && !result.is_empty() {
         if let Some(redis) = redis_client {
             let ma_owned = ma_type.to_string();
+            let periods_owned = ma_periods.to_vec();
             let src_owned = source.to_string();
             let iv_owned = interval.to_string();
             let result_clone = result.clone();
             let redis_clone = redis.clone();
             tokio::spawn(async move {
                 crate::workers::redis_worker::batch_write_joined_snapshots(
-                    &redis_clone, &src_owned, &[], &iv_owned, 1, &ma_owned, &result_clone,
+                    &redis_clone, &src_owned, &iv_owned, keep, &ma_owned, &periods_owned, &result_clone,
                 ).await;
             });
         }
@@ -104,21 +140,112 @@ pub async fn try_redis_batch(
     Some(result.into_iter().map(|(k, v)| (k, v.rows)).collect())
 }
 
+/// `currency=` conversions of daily bars for each source of
+/// `source_symbols`, sharing one rate load. `bars` daily bars up to
+/// `end` (or now) are read, and anything newer up to now. Empty without a
+/// `target`.
+pub async fn load_conversions(
+    pool: &sqlx::PgPool,
+    source_symbols: &[(&str, Vec<String>)],
+    target: Option<Currency>,
+    end: Option<chrono::DateTime<chrono::Utc>>,
+    bars: i64,
+) -> sqlx::Result<HashMap<String, Conversion>> {
+    let Some(target) = target else {
+        return Ok(HashMap::new());
+    };
+    let pairs: Vec<(&str, &str)> =
+        source_symbols.iter().flat_map(|(src, syms)| syms.iter().map(move |s| (s.as_str(), *src))).collect();
+    let now = chrono::Utc::now();
+    let start = crate::services::fx::window_start(None, end.unwrap_or(now), bars, 24 * 60);
+    let all = Conversion::load(pool, pairs, target, start, now, false).await?;
+    Ok(source_symbols.iter().map(|(src, syms)| (src.to_string(), all.for_source(src, syms))).collect())
+}
+
 /// All data sources used by mode=all
 pub fn get_all_sources() -> Vec<&'static str> {
     vec!["vn", "yahoo", "sjc", "crypto"]
 }
 
 /// Load yahoo/global groups including MERGE_WITH_YAHOO sources (e.g. SJC).
-pub fn load_yahoo_groups() -> Result<BTreeMap<String, Vec<String>>, Box<dyn std::error::Error + Send + Sync>> {
+pub fn load_yahoo_groups() -> BTreeMap<String, Vec<String>> {
     crate::server::api::data_loader::load_yahoo_groups()
 }
 
-/// Load crypto groups from binance_tickers.json. All crypto go under "CRYPTO".
-pub fn load_crypto_groups() -> Result<BTreeMap<String, Vec<String>>, Box<dyn std::error::Error + Send + Sync>> {
+/// Load crypto groups. All crypto go under "CRYPTO_TOP_100".
+pub fn load_crypto_groups() -> BTreeMap<String, Vec<String>> {
     crate::server::api::data_loader::load_crypto_groups()
 }
 
+/// Sector groups of `source`: `ticker_groups` for vn, the crypto and yahoo
+/// groups otherwise.
+pub fn source_groups(source: &str, ticker_groups: &HashMap<String, Vec<String>>) -> BTreeMap<String, Vec<String>> {
+    match source {
+        "vn" => ticker_groups.iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
+        "crypto" => load_crypto_groups(),
+        "yahoo" => load_yahoo_groups(),
+        _ => BTreeMap::new(),
+    }
+}
+
+/// Day whose sector/index membership an analysis uses: its `date`
+/// (YYYY-MM-DD), or today.
+pub fn universe_day(date: Option<&str>) -> NaiveDate {
+    date.and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+        .unwrap_or_else(|| chrono::Utc::now().date_naive())
+}
+
+/// Sector groups of `source` as they were on `day` (yahoo includes the
+/// MERGE_WITH_YAHOO sources), narrowed to the constituents of `index` on
+/// that day when given.
+pub fn universe(source: &str, day: NaiveDate, index: Option<&str>) -> BTreeMap<String, Vec<String>> {
+    let catalog = metadata::catalog();
+    let mut groups = catalog.groups_on(source, day);
+    if source == "yahoo" {
+        for extra in crate::constants::MERGE_WITH_YAHOO {
+            for (category, symbols) in catalog.groups_on(extra, day) {
+                groups.entry(category).or_default().extend(symbols);
+            }
+        }
+    }
+    if let Some(index) = index {
+        let members: HashSet<String> = catalog.index_members(source, index, day).into_iter().collect();
+        for tickers in groups.values_mut() {
+            tickers.retain(|t| members.contains(t));
+        }
+        groups.retain(|_, tickers| !tickers.is_empty());
+    }
+    groups
+}
+
+/// Sector groups of `source` (see [`universe`]) on `start` and on every
+/// later day up to `end` where they change, oldest first.
+pub fn universe_history(source: &str, start: NaiveDate, end: NaiveDate) -> Vec<(NaiveDate, BTreeMap<String, Vec<String>>)> {
+    let catalog = metadata::catalog();
+    let mut days = catalog.group_change_days(source, start, end);
+    if source == "yahoo" {
+        for extra in crate::constants::MERGE_WITH_YAHOO {
+            days.extend(catalog.group_change_days(extra, start, end));
+        }
+    }
+    let mut history = vec![(start, universe(source, start, None))];
+    for day in days {
+        let groups = universe(source, day, None);
+        if history.last().is_none_or(|(_, last)| *last != groups) {
+            history.push((day, groups));
+        }
+    }
+    history
+}
+
+/// Reject an `index=` that has no recorded constituents.
+pub fn validate_index(index: Option<&str>) -> Result<(), String> {
+    match index {
+        Some(index) if !metadata::catalog().has_index(index) => Err(format!("Unknown index '{index}'")),
+        _ => Ok(()),
+    }
+}
+
 /// Common analysis response structure
 #[derive(Debug, Serialize)]
 pub struct AnalysisResponse<T> {
@@ -150,18 +277,9 @@ pub fn parse_analysis_date(
     }
 }
 
-/// Load ticker groups from JSON file
-pub fn load_ticker_groups() -> Result<HashMap<String, Vec<String>>, Box<dyn std::error::Error + Send + Sync>> {
-    // Try current directory first, then parent
-    let path = if std::path::Path::new("ticker_group.json").exists() {
-        std::path::PathBuf::from("ticker_group.json")
-    } else {
-        std::path::Path::new("..").join("ticker_group.json")
-    };
-
-    let content = std::fs::read_to_string(&path)?;
-    let groups: HashMap<String, Vec<String>> = serde_json::from_str(&content)?;
-    Ok(groups)
+/// Load VN sector groups from the ticker metadata.
+pub fn load_ticker_groups() -> HashMap<String, Vec<String>> {
+    metadata::catalog().groups("vn").into_iter().collect()
 }
 
 /// Get tickers for a specific sector
@@ -4,14 +4,19 @@ use axum::{
     response::{IntoResponse, Json},
 };
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::sync::Arc;
 
-use crate::queries::ohlcv;
+use crate::models::indicators::{ma_lookback, parse_ma_periods};
+use crate::queries::ohlcv::{self, FetchOptions};
 use crate::server::types::{is_vn_ticker, Mode};
 use crate::server::AppState;
-use crate::constants::api::{EMA_LOOKBACK, SMA_MAX_PERIOD};
+use crate::services::fx;
 
-use super::{get_all_sources, get_ticker_sector, is_index_ticker, load_crypto_groups, load_yahoo_groups, parse_analysis_date, validate_limit, AnalysisResponse};
+use super::{
+    get_all_sources, get_ticker_sector, is_index_ticker, parse_analysis_date, universe, universe_day, validate_index,
+    validate_limit, AnalysisResponse,
+};
 
 #[derive(Debug, Deserialize)]
 pub struct TopPerformersQuery {
@@ -30,6 +35,13 @@ pub struct TopPerformersQuery {
     /// true = use Redis snapshot cache (default).
     #[serde(default = "default_true")]
     pub snap: bool,
+    /// MA periods, comma-separated (e.g. `5,13,34,89`); defaults to `10,20,50,100,200`.
+    pub ma_periods: Option<String>,
+    /// USD or VND: convert prices, MAs and `total_money_changed` (mixes
+    /// markets on one scale under mode=all).
+    pub currency: Option<String>,
+    /// Only the constituents of this index (e.g. VN30) on `date`.
+    pub index: Option<String>,
 }
 
 fn default_sort_by() -> String { "close_changed".to_string() }
@@ -41,6 +53,8 @@ pub struct TopPerformersResponse {
     pub performers: Vec<PerformerInfo>,
     pub worst_performers: Vec<PerformerInfo>,
     pub hourly: Option<Vec<HourlyPerformers>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub currency: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -71,6 +85,9 @@ pub struct PerformerInfo {
     pub total_money_changed: Option<f64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub source: Option<String>,
+    /// `ma{p}` / `ma{p}_score` for `ma_periods=` outside the defaults.
+    #[serde(flatten, skip_serializing_if = "BTreeMap::is_empty")]
+    pub indicators: BTreeMap<String, f64>,
 }
 
 fn sort_optional_f64_desc(a: &Option<f64>, b: &Option<f64>) -> std::cmp::Ordering {
@@ -99,6 +116,10 @@ fn sort_performers(
             "ma100_score" => sort_optional_f64_desc(&a.ma100_score, &b.ma100_score),
             "ma200_score" => sort_optional_f64_desc(&a.ma200_score, &b.ma200_score),
             "total_money_changed" => sort_optional_f64_desc(&a.total_money_changed, &b.total_money_changed),
+            // Custom `ma{p}_score` from ma_periods=
+            other if a.indicators.contains_key(other) || b.indicators.contains_key(other) => {
+                sort_optional_f64_desc(&a.indicators.get(other).copied(), &b.indicators.get(other).copied())
+            }
             _ => sort_optional_f64_desc(&a.close_changed, &b.close_changed),
         };
         if ascending { order.reverse() } else { order }
@@ -121,16 +142,28 @@ pub async fn top_performers_handler(
     State(state): State<Arc<AppState>>,
     Query(params): Query<TopPerformersQuery>,
 ) -> impl IntoResponse {
-    let ticker_groups = match super::load_ticker_groups() {
-        Ok(groups) => groups,
+    if let Err(e) = validate_index(params.index.as_deref()) {
+        return (StatusCode::BAD_REQUEST, Json(serde_json::json!({ "error": e }))).into_response();
+    }
+
+    // Sector membership as of the analysis date
+    let day = universe_day(params.date.as_deref());
+    let index = params.index.as_deref();
+    let ticker_groups = universe("vn", day, index);
+
+    let ma_periods = match parse_ma_periods(params.ma_periods.as_deref()) {
+        Ok(periods) => periods,
         Err(e) => {
-            tracing::error!("Failed to load ticker groups: {}", e);
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(serde_json::json!({ "error": "Failed to load sector information" })),
-            ).into_response();
+            return (StatusCode::BAD_REQUEST, Json(serde_json::json!({ "error": e }))).into_response();
         }
     };
+    let currency = match fx::parse_target(params.currency.as_deref()) {
+        Ok(c) => c,
+        Err(e) => {
+            return (StatusCode::BAD_REQUEST, Json(serde_json::json!({ "error": e }))).into_response();
+        }
+    };
+    let redis_limit = 1 + ma_lookback(&ma_periods, params.ema);
 
     let is_all = params.mode == Mode::All;
     let analysis_date = parse_analysis_date(params.date.as_deref());
@@ -141,8 +174,8 @@ pub async fn top_performers_handler(
         sources.iter().map(|&src| {
             let symbols = match src {
                 "vn" => ticker_groups.values().flat_map(|v| v.iter().cloned()).collect(),
-                "crypto" => load_crypto_groups().map(|g| g.into_values().flatten().collect()).unwrap_or_default(),
-                "yahoo" => load_yahoo_groups().map(|g| g.into_values().flatten().collect()).unwrap_or_default(),
+                "crypto" => universe("crypto", day, index).into_values().flatten().collect(),
+                "yahoo" => universe("yahoo", day, index).into_values().flatten().collect(),
                 _ => Vec::new(),
             };
             (src, symbols)
@@ -151,25 +184,38 @@ pub async fn top_performers_handler(
         let source = params.mode.source_label();
         let symbols: Vec<String> = match source {
             "vn" => ticker_groups.values().flat_map(|v| v.iter().cloned()).collect(),
-            "crypto" => load_crypto_groups().map(|g| g.into_values().flatten().collect()).unwrap_or_default(),
-            "yahoo" => load_yahoo_groups().map(|g| g.into_values().flatten().collect()).unwrap_or_default(),
+            "crypto" => universe("crypto", day, index).into_values().flatten().collect(),
+            "yahoo" => universe("yahoo", day, index).into_values().flatten().collect(),
             _ => Vec::new(),
         };
         vec![(source, symbols)]
     };
 
+    // currency=: bars are converted before MAs and changes are computed
+    let conversions = match super::load_conversions(&state.pool, &source_symbols, currency, None, redis_limit).await {
+        Ok(c) => c,
+        Err(e) => {
+            tracing::error!("Failed to load FX rates: {}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": "Failed to load FX rates" })),
+            ).into_response();
+        }
+    };
+    let fetch_opts = FetchOptions { use_ema: params.ema, ma_periods: &ma_periods, snap: params.snap, ..Default::default() };
     // Fetch latest daily data with snapshot optimization
     let rows: Vec<(crate::models::ohlcv::OhlcvJoined, &str)> = if is_all {
         let sources = get_all_sources();
-        let redis_limit = 1 + if params.ema { EMA_LOOKBACK } else { SMA_MAX_PERIOD };
         let syms: Vec<Vec<String>> = sources.iter()
             .map(|src| source_symbols.iter().find(|(s,_)| *s == *src).map(|(_,v)| v.clone()).unwrap_or_default())
             .collect();
+        let opts: Vec<FetchOptions> =
+            sources.iter().map(|src| FetchOptions { fx: conversions.get(*src), ..fetch_opts }).collect();
         let (r1, r2, r3, r4) = tokio::join!(
-            super::fetch_source_enhanced(&state.redis_client, sources[0], &syms[0], "1D", redis_limit, "performers", params.ema, !params.snap),
-            super::fetch_source_enhanced(&state.redis_client, sources[1], &syms[1], "1D", redis_limit, "performers", params.ema, !params.snap),
-            super::fetch_source_enhanced(&state.redis_client, sources[2], &syms[2], "1D", redis_limit, "performers", params.ema, !params.snap),
-            super::fetch_source_enhanced(&state.redis_client, sources[3], &syms[3], "1D", redis_limit, "performers", params.ema, !params.snap),
+            super::fetch_source_enhanced(&state.redis_client, sources[0], &syms[0], "1D", redis_limit, "performers", &opts[0]),
+            super::fetch_source_enhanced(&state.redis_client, sources[1], &syms[1], "1D", redis_limit, "performers", &opts[1]),
+            super::fetch_source_enhanced(&state.redis_client, sources[2], &syms[2], "1D", redis_limit, "performers", &opts[2]),
+            super::fetch_source_enhanced(&state.redis_client, sources[3], &syms[3], "1D", redis_limit, "performers", &opts[3]),
         );
         let mut merged: Vec<(crate::models::ohlcv::OhlcvJoined, &str)> = Vec::new();
         for (map, src) in [(r1, sources[0]), (r2, sources[1]), (r3, sources[2]), (r4, sources[3])] {
@@ -181,7 +227,8 @@ pub async fn top_performers_handler(
     } else {
         let source = params.mode.source_label();
         let symbols: Vec<String> = source_symbols.iter().find(|(s,_)| *s == source).map(|(_,v)| v.clone()).unwrap_or_default();
-        let map = super::fetch_source_enhanced(&state.redis_client, source, &symbols, "1D", 1 + if params.ema { EMA_LOOKBACK } else { SMA_MAX_PERIOD }, "performers/single", params.ema, !params.snap).await;
+        let opts = FetchOptions { fx: conversions.get(source), ..fetch_opts };
+        let map = super::fetch_source_enhanced(&state.redis_client, source, &symbols, "1D", redis_limit, "performers/single", &opts).await;
         let mut merged: Vec<(crate::models::ohlcv::OhlcvJoined, &str)> = Vec::new();
         for (_ticker, bars) in map {
             merged.extend(bars.into_iter().map(|row| (row, "")));
@@ -189,7 +236,7 @@ pub async fn top_performers_handler(
         if !merged.is_empty() {
             merged
         } else {
-            match ohlcv::get_latest_daily_per_ticker(&state.pool, source).await {
+            match ohlcv::get_latest_daily_per_ticker(&state.pool, source, &opts).await {
                 Ok(r) => r.into_iter().map(|row| (row, "")).collect(),
                 Err(e) => {
                     tracing::error!("Failed to fetch daily data: {}", e);
@@ -238,6 +285,7 @@ pub async fn top_performers_handler(
             sector,
             total_money_changed: row.total_money_changed,
             source: if is_all { Some(row_source.to_string()) } else { None },
+            indicators: row.indicators.clone(),
         });
     }
 
@@ -264,6 +312,7 @@ pub async fn top_performers_handler(
                 performers: top_performers,
                 worst_performers,
                 hourly: hourly_data,
+                currency: currency.map(|c| c.as_str().to_string()),
             },
         }),
     ).into_response()
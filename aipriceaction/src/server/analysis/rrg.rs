@@ -9,16 +9,19 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
-use crate::models::indicators::calculate_wma;
+use crate::models::custom_index;
+use crate::models::indicators::{calculate_wma, ma_lookback, parse_ma_periods};
 use crate::models::ohlcv::{OhlcvJoined, OhlcvRow};
-use crate::queries::ohlcv;
+use crate::models::portfolio::Currency;
+use crate::queries::ohlcv::{self, FetchOptions};
 use crate::server::types::Mode;
+use crate::services::{fx, metadata};
 use crate::server::AppState;
 use crate::constants::api::{EMA_LOOKBACK, SMA_MAX_PERIOD};
 
 use super::{
-    get_all_sources, get_ticker_sector, is_index_ticker, load_crypto_groups, load_ticker_groups,
-    load_yahoo_groups, try_redis_batch, AnalysisResponse,
+    get_all_sources, get_ticker_sector, is_index_ticker, try_redis_batch, universe, universe_day, validate_index,
+    AnalysisResponse,
 };
 
 // ---------------------------------------------------------------------------
@@ -53,6 +56,13 @@ pub struct RrgQuery {
     /// true = use Redis snapshot cache (default).
     #[serde(default = "default_true")]
     pub snap: bool,
+    /// Only the constituents of this index (e.g. VN30) on `date`.
+    pub index: Option<String>,
+    /// USD or VND: convert closes before scores and RS are computed.
+    pub currency: Option<String>,
+    /// Mascore: the two MA periods whose scores give rs_ratio (the shorter)
+    /// and rs_momentum (the longer). Default `20,100`.
+    pub ma_periods: Option<String>,
 }
 
 fn default_period() -> usize {
@@ -76,18 +86,16 @@ fn parse_rrg_date(date_str: &str) -> Option<DateTime<Utc>> {
 /// Build a source → sector-groups lookup so each source gets its own sector mapping.
 fn build_source_sector_groups(
     vn_groups: &HashMap<String, Vec<String>>,
+    day: chrono::NaiveDate,
+    index: Option<&str>,
 ) -> HashMap<&'static str, BTreeMap<String, Vec<String>>> {
     let mut map = HashMap::new();
     // VN: convert HashMap → BTreeMap
     map.insert("vn", vn_groups.iter().map(|(k, v)| (k.clone(), v.clone())).collect());
     // Crypto
-    if let Ok(groups) = load_crypto_groups() {
-        map.insert("crypto", groups);
-    }
+    map.insert("crypto", universe("crypto", day, index));
     // Yahoo/global
-    if let Ok(groups) = load_yahoo_groups() {
-        map.insert("yahoo", groups);
-    }
+    map.insert("yahoo", universe("yahoo", day, index));
     map
 }
 
@@ -103,6 +111,8 @@ pub struct RrgResponse {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub period: Option<usize>,
     pub tickers: Vec<RrgTickerSnapshot>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub currency: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -161,7 +171,7 @@ fn normalize_rolling_zscore(values: &[f64], window: usize) -> Vec<f64> {
 }
 
 /// JdK RS-Ratio algorithm.
-fn compute_jdk(
+pub(crate) fn compute_jdk(
     security: &[f64],
     benchmark: &[f64],
     period: usize,
@@ -213,13 +223,13 @@ fn compute_jdk(
 // Data alignment
 // ---------------------------------------------------------------------------
 
-struct AlignedData {
-    dates: Vec<DateTime<Utc>>,
-    sec_closes: Vec<f64>,
-    bench_closes: Vec<f64>,
+pub(crate) struct AlignedData {
+    pub dates: Vec<DateTime<Utc>>,
+    pub sec_closes: Vec<f64>,
+    pub bench_closes: Vec<f64>,
 }
 
-fn align_closes_by_date(
+pub(crate) fn align_closes_by_date(
     security_rows: &[OhlcvRow],
     benchmark_rows: &[OhlcvRow],
 ) -> Option<AlignedData> {
@@ -270,19 +280,22 @@ pub async fn rrg_handler(
     State(state): State<Arc<AppState>>,
     Query(params): Query<RrgQuery>,
 ) -> impl IntoResponse {
-    // Load sector info (shared by both algorithms)
-    let ticker_groups = match load_ticker_groups() {
-        Ok(g) => g,
+    if let Err(e) = validate_index(params.index.as_deref()) {
+        return (StatusCode::BAD_REQUEST, Json(serde_json::json!({ "error": e }))).into_response();
+    }
+    let currency = match fx::parse_target(params.currency.as_deref()) {
+        Ok(c) => c,
         Err(e) => {
-            tracing::error!("Failed to load ticker groups: {}", e);
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(serde_json::json!({ "error": "Failed to load sector information" })),
-            )
-                .into_response();
+            return (StatusCode::BAD_REQUEST, Json(serde_json::json!({ "error": e }))).into_response();
         }
     };
 
+    // Load sector info as of the analysis date (shared by both algorithms)
+    let ticker_groups: HashMap<String, Vec<String>> =
+        universe("vn", universe_day(params.date.as_deref()), params.index.as_deref())
+            .into_iter()
+            .collect();
+
     let is_all = params.mode == Mode::All;
 
     // Validate date format early if provided
@@ -307,10 +320,10 @@ pub async fn rrg_handler(
 
     match params.algorithm {
         RrgAlgorithm::Mascore => {
-            handle_mascore(state, &params, &ticker_groups, is_all, end_time, &analysis_date).await
+            handle_mascore(state, &params, &ticker_groups, is_all, end_time, &analysis_date, currency).await
         }
         RrgAlgorithm::Jdk => {
-            handle_jdk(state, params, &ticker_groups, is_all, end_time, &analysis_date).await
+            handle_jdk(state, params, &ticker_groups, is_all, end_time, &analysis_date, currency).await
         }
     }
 }
@@ -319,8 +332,8 @@ pub async fn rrg_handler(
 // Mascore handler
 // ---------------------------------------------------------------------------
 
-/// Mascore algorithm only needs MA20 and MA100, so max_period=100.
-const MASCORE_MAX_MA_PERIOD: usize = 100;
+/// MA periods of the mascore axes when `ma_periods=` is not given.
+const MASCORE_MA_PERIODS: [usize; 2] = [20, 100];
 
 async fn handle_mascore(
     state: Arc<AppState>,
@@ -329,9 +342,28 @@ async fn handle_mascore(
     is_all: bool,
     end_time: Option<DateTime<Utc>>,
     analysis_date: &str,
+    currency: Option<Currency>,
 ) -> axum::response::Response {
+    let ma_periods = match params.ma_periods.as_deref().filter(|s| !s.trim().is_empty()) {
+        None => MASCORE_MA_PERIODS.to_vec(),
+        Some(raw) => match parse_ma_periods(Some(raw)) {
+            Ok(p) if p.len() == 2 => p,
+            Ok(_) => {
+                let e = "Mascore takes two MA periods (rs_ratio, rs_momentum), e.g. ma_periods=20,100";
+                return (StatusCode::BAD_REQUEST, Json(serde_json::json!({ "error": e }))).into_response();
+            }
+            Err(e) => return (StatusCode::BAD_REQUEST, Json(serde_json::json!({ "error": e }))).into_response(),
+        },
+    };
+    let (x_period, y_period) = (ma_periods[0], ma_periods[1]);
+    let buffer = ma_lookback(&ma_periods, params.ema);
+
     // Build per-source sector groups for correct sector assignment
-    let source_groups = build_source_sector_groups(ticker_groups);
+    let source_groups = build_source_sector_groups(
+        ticker_groups,
+        universe_day(params.date.as_deref()),
+        params.index.as_deref(),
+    );
 
     // Collect ticker symbols per source (reuse handle_jdk pattern)
     let mut source_symbols: Vec<(&str, Vec<String>)> = Vec::new();
@@ -344,22 +376,20 @@ async fn handle_mascore(
             source_symbols.push(("vn", symbols));
         }
         Mode::Crypto => {
-            if let Ok(groups) = load_crypto_groups() {
-                let symbols = groups
-                    .values()
-                    .flat_map(|v| v.iter().cloned())
-                    .collect::<Vec<_>>();
-                source_symbols.push(("crypto", symbols));
-            }
+            let symbols = source_groups["crypto"]
+                .values()
+                .flatten()
+                .cloned()
+                .collect::<Vec<_>>();
+            source_symbols.push(("crypto", symbols));
         }
         Mode::Yahoo => {
-            if let Ok(groups) = load_yahoo_groups() {
-                let symbols = groups
-                    .values()
-                    .flat_map(|v| v.iter().cloned())
-                    .collect::<Vec<_>>();
-                source_symbols.push(("yahoo", symbols));
-            }
+            let symbols = source_groups["yahoo"]
+                .values()
+                .flatten()
+                .cloned()
+                .collect::<Vec<_>>();
+            source_symbols.push(("yahoo", symbols));
         }
         Mode::All => {
             let sources = get_all_sources();
@@ -369,12 +399,16 @@ async fn handle_mascore(
                         .values()
                         .flat_map(|v| v.iter().cloned())
                         .collect(),
-                    "crypto" => load_crypto_groups()
-                        .map(|g| g.into_values().flatten().collect())
-                        .unwrap_or_default(),
-                    "yahoo" => load_yahoo_groups()
-                        .map(|g| g.into_values().flatten().collect())
-                        .unwrap_or_default(),
+                    "crypto" => source_groups["crypto"]
+                        .values()
+                        .flatten()
+                        .cloned()
+                        .collect(),
+                    "yahoo" => source_groups["yahoo"]
+                        .values()
+                        .flatten()
+                        .cloned()
+                        .collect(),
                     _ => Vec::new(),
                 };
                 source_symbols.push((src, symbols));
@@ -382,6 +416,21 @@ async fn handle_mascore(
         }
     }
 
+    // currency=: bars are converted before MAs and scores are computed
+    let bars = params.trails.clamp(1, 120) as i64 + buffer;
+    let conversions = match super::load_conversions(&state.pool, &source_symbols, currency, end_time, bars).await {
+        Ok(c) => c,
+        Err(e) => {
+            tracing::error!("Failed to load FX rates: {}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": "Failed to load FX rates" })),
+            )
+                .into_response();
+        }
+    };
+    let fetch_opts = FetchOptions { use_ema: params.ema, ma_periods: &ma_periods, snap: params.snap, ..Default::default() };
+    let opts_for = |src: &str| FetchOptions { fx: conversions.get(src), ..fetch_opts };
     // When trails=0 and no date filter, use the efficient get_latest_daily_per_ticker (DISTINCT ON)
     // When trails>0 or date is specified, use get_ohlcv_joined_batch to get historical rows
     if params.trails == 0 && end_time.is_none() {
@@ -392,11 +441,12 @@ async fn handle_mascore(
             let syms: Vec<Vec<String>> = sources.iter()
                 .map(|src| source_symbols.iter().find(|(s,_)| *s == *src).map(|(_,v)| v.clone()).unwrap_or_default())
                 .collect();
+            let opts: Vec<FetchOptions> = sources.iter().map(|src| opts_for(src)).collect();
             let (r1, r2, r3, r4) = tokio::join!(
-                super::fetch_source_enhanced(&state.redis_client, sources[0], &syms[0], "1D", 1 + crate::constants::api::sma_buffer_for(MASCORE_MAX_MA_PERIOD), "rrg", params.ema, !params.snap),
-                super::fetch_source_enhanced(&state.redis_client, sources[1], &syms[1], "1D", 1 + crate::constants::api::sma_buffer_for(MASCORE_MAX_MA_PERIOD), "rrg", params.ema, !params.snap),
-                super::fetch_source_enhanced(&state.redis_client, sources[2], &syms[2], "1D", 1 + crate::constants::api::sma_buffer_for(MASCORE_MAX_MA_PERIOD), "rrg", params.ema, !params.snap),
-                super::fetch_source_enhanced(&state.redis_client, sources[3], &syms[3], "1D", 1 + crate::constants::api::sma_buffer_for(MASCORE_MAX_MA_PERIOD), "rrg", params.ema, !params.snap),
+                super::fetch_source_enhanced(&state.redis_client, sources[0], &syms[0], "1D", 1 + buffer, "rrg", &opts[0]),
+                super::fetch_source_enhanced(&state.redis_client, sources[1], &syms[1], "1D", 1 + buffer, "rrg", &opts[1]),
+                super::fetch_source_enhanced(&state.redis_client, sources[2], &syms[2], "1D", 1 + buffer, "rrg", &opts[2]),
+                super::fetch_source_enhanced(&state.redis_client, sources[3], &syms[3], "1D", 1 + buffer, "rrg", &opts[3]),
             );
             let mut merged: Vec<(OhlcvJoined, &str)> = Vec::new();
             for (map, src) in [(r1, sources[0]), (r2, sources[1]), (r3, sources[2]), (r4, sources[3])] {
@@ -406,7 +456,7 @@ async fn handle_mascore(
                     }
                 } else {
                     // Redis/snapshots failed for this source — fall back to PG
-                    match ohlcv::get_latest_daily_per_ticker(&state.pool, src).await {
+                    match ohlcv::get_latest_daily_per_ticker(&state.pool, src, &opts_for(src)).await {
                         Ok(v) => merged.extend(v.into_iter().map(|row| (row, src))),
                         Err(e) => tracing::warn!("Failed to fetch daily data for source '{}': {}", src, e),
                     }
@@ -416,7 +466,8 @@ async fn handle_mascore(
         } else {
             let source = params.mode.source_label();
             let symbols: Vec<String> = source_symbols.iter().find(|(s,_)| *s == source).map(|(_,v)| v.clone()).unwrap_or_default();
-            let map = super::fetch_source_enhanced(&state.redis_client, source, &symbols, "1D", 1 + crate::constants::api::sma_buffer_for(MASCORE_MAX_MA_PERIOD), "rrg/single", params.ema, !params.snap).await;
+            let opts = opts_for(source);
+            let map = super::fetch_source_enhanced(&state.redis_client, source, &symbols, "1D", 1 + buffer, "rrg/single", &opts).await;
             let mut merged: Vec<(OhlcvJoined, &str)> = Vec::new();
             for (_ticker, bars) in map {
                 merged.extend(bars.into_iter().map(|row| (row, source)));
@@ -424,7 +475,7 @@ async fn handle_mascore(
             if !merged.is_empty() {
                 merged
             } else {
-                match ohlcv::get_latest_daily_per_ticker(&state.pool, source).await {
+                match ohlcv::get_latest_daily_per_ticker(&state.pool, source, &opts).await {
                     Ok(r) => r.into_iter().map(|row| (row, source)).collect(),
                     Err(e) => {
                         tracing::error!("Failed to fetch daily data: {}", e);
@@ -446,11 +497,11 @@ async fn handle_mascore(
             if row.volume < params.min_volume {
                 continue;
             }
-            let x = match row.ma20_score {
+            let x = match row.ma(x_period).1 {
                 Some(v) => v,
                 None => continue,
             };
-            let y = match row.ma100_score {
+            let y = match row.ma(y_period).1 {
                 Some(v) => v,
                 None => continue,
             };
@@ -481,6 +532,7 @@ async fn handle_mascore(
                     algorithm: "mascore".to_string(),
                     period: None,
                     tickers: snapshots,
+                    currency: currency.map(|c| c.as_str().to_string()),
                 },
             }),
         )
@@ -499,7 +551,7 @@ async fn handle_mascore(
 
     // Trails path: fetch historical rows per source
     let mut all_joined: Vec<(HashMap<String, Vec<OhlcvJoined>>, &str)> = Vec::new();
-    let redis_limit = effective_limit.unwrap_or(1) + crate::constants::api::sma_buffer_for(MASCORE_MAX_MA_PERIOD);
+    let redis_limit = effective_limit.unwrap_or(1) + buffer;
 
     if is_all {
         let sources = get_all_sources();
@@ -523,7 +575,7 @@ async fn handle_mascore(
                             Some(end) => orows.into_iter().filter(|r| r.time <= end).collect(),
                             None => orows,
                         };
-                        let enhanced = ohlcv::enhance_rows_selective(&ticker, filtered, effective_limit, None, params.ema, MASCORE_MAX_MA_PERIOD);
+                        let enhanced = ohlcv::enhance_rows(&ticker, filtered, &FetchOptions { limit: el, ..opts_for(src) });
                         (ticker, enhanced)
                     })
                     .filter(|(_, v)| !v.is_empty())
@@ -534,7 +586,7 @@ async fn handle_mascore(
                 }
             }
             // Redis failed or returned empty for this source — fall back to PG
-            match ohlcv::get_ohlcv_joined_batch(&state.pool, src, &[], "1D", el, None, et, true, params.ema).await {
+            match ohlcv::get_ohlcv_joined_batch(&state.pool, src, &[], "1D", &FetchOptions { limit: el, end_time: et, ..opts_for(src) }).await {
                 Ok(map) => all_joined.push((map, src)),
                 Err(e) => tracing::warn!("Failed to fetch daily data for source '{}': {}", src, e),
             }
@@ -549,7 +601,7 @@ async fn handle_mascore(
                         Some(end) => orows.into_iter().filter(|r| r.time <= end).collect(),
                         None => orows,
                     };
-                    let enhanced = ohlcv::enhance_rows_selective(&ticker, filtered, effective_limit, None, params.ema, MASCORE_MAX_MA_PERIOD);
+                    let enhanced = ohlcv::enhance_rows(&ticker, filtered, &FetchOptions { limit: effective_limit, ..opts_for(source) });
                     (ticker, enhanced)
                 })
                 .filter(|(_, v)| !v.is_empty())
@@ -558,7 +610,7 @@ async fn handle_mascore(
                 all_joined.push((joined, source));
             } else {
                 // Redis returned empty — fall back to PG
-                match ohlcv::get_ohlcv_joined_batch(&state.pool, source, &[], "1D", effective_limit, None, end_time, true, params.ema).await {
+                match ohlcv::get_ohlcv_joined_batch(&state.pool, source, &[], "1D", &FetchOptions { limit: effective_limit, end_time, ..opts_for(source) }).await {
                     Ok(map) => all_joined.push((map, source)),
                     Err(e) => {
                         tracing::error!("Failed to fetch daily data: {}", e);
@@ -572,7 +624,7 @@ async fn handle_mascore(
             }
         } else {
             // Redis unavailable — fall back to PG
-            match ohlcv::get_ohlcv_joined_batch(&state.pool, source, &[], "1D", effective_limit, None, end_time, true, params.ema).await {
+            match ohlcv::get_ohlcv_joined_batch(&state.pool, source, &[], "1D", &FetchOptions { limit: effective_limit, end_time, ..opts_for(source) }).await {
                 Ok(map) => all_joined.push((map, source)),
                 Err(e) => {
                     tracing::error!("Failed to fetch daily data: {}", e);
@@ -599,8 +651,8 @@ async fn handle_mascore(
 
             // Find the last known scores (newest bar) to backfill earlier
             // rows that don't have enough history for MA computation.
-            let fallback_x = chrono_rows.iter().rev().find_map(|r| r.ma20_score);
-            let fallback_y = chrono_rows.iter().rev().find_map(|r| r.ma100_score);
+            let fallback_x = chrono_rows.iter().rev().find_map(|r| r.ma(x_period).1);
+            let fallback_y = chrono_rows.iter().rev().find_map(|r| r.ma(y_period).1);
 
             let (fx, fy) = match (fallback_x, fallback_y) {
                 (Some(x), Some(y)) => (x, y),
@@ -611,8 +663,8 @@ async fn handle_mascore(
                 .iter()
                 .map(|r| (
                     r.time,
-                    r.ma20_score.unwrap_or(fx),
-                    r.ma100_score.unwrap_or(fy),
+                    r.ma(x_period).1.unwrap_or(fx),
+                    r.ma(y_period).1.unwrap_or(fy),
                     r.volume,
                     r.close,
                 ))
@@ -680,6 +732,7 @@ async fn handle_mascore(
                 algorithm: "mascore".to_string(),
                 period: None,
                 tickers: snapshots,
+                currency: currency.map(|c| c.as_str().to_string()),
             },
         }),
     )
@@ -697,8 +750,13 @@ async fn handle_jdk(
     is_all: bool,
     end_time: Option<DateTime<Utc>>,
     analysis_date: &str,
+    currency: Option<Currency>,
 ) -> axum::response::Response {
-    let source_groups = build_source_sector_groups(ticker_groups);
+    let source_groups = build_source_sector_groups(
+        ticker_groups,
+        universe_day(params.date.as_deref()),
+        params.index.as_deref(),
+    );
 
     let period = params.period.clamp(4, 50);
     let trail_length = params.trails.clamp(1, 120);
@@ -721,22 +779,20 @@ async fn handle_jdk(
             source_symbols.push(("vn", symbols));
         }
         Mode::Crypto => {
-            if let Ok(groups) = load_crypto_groups() {
-                let symbols = groups
-                    .values()
-                    .flat_map(|v| v.iter().cloned())
-                    .collect::<Vec<_>>();
-                source_symbols.push(("crypto", symbols));
-            }
+            let symbols = source_groups["crypto"]
+                .values()
+                .flatten()
+                .cloned()
+                .collect::<Vec<_>>();
+            source_symbols.push(("crypto", symbols));
         }
         Mode::Yahoo => {
-            if let Ok(groups) = load_yahoo_groups() {
-                let symbols = groups
-                    .values()
-                    .flat_map(|v| v.iter().cloned())
-                    .collect::<Vec<_>>();
-                source_symbols.push(("yahoo", symbols));
-            }
+            let symbols = source_groups["yahoo"]
+                .values()
+                .flatten()
+                .cloned()
+                .collect::<Vec<_>>();
+            source_symbols.push(("yahoo", symbols));
         }
         Mode::All => {
             let sources = get_all_sources();
@@ -746,12 +802,16 @@ async fn handle_jdk(
                         .values()
                         .flat_map(|v| v.iter().cloned())
                         .collect(),
-                    "crypto" => load_crypto_groups()
-                        .map(|g| g.into_values().flatten().collect())
-                        .unwrap_or_default(),
-                    "yahoo" => load_yahoo_groups()
-                        .map(|g| g.into_values().flatten().collect())
-                        .unwrap_or_default(),
+                    "crypto" => source_groups["crypto"]
+                        .values()
+                        .flatten()
+                        .cloned()
+                        .collect(),
+                    "yahoo" => source_groups["yahoo"]
+                        .values()
+                        .flatten()
+                        .cloned()
+                        .collect(),
                     _ => Vec::new(),
                 };
                 source_symbols.push((src, symbols));
@@ -759,6 +819,29 @@ async fn handle_jdk(
         }
     }
 
+    // A custom index benchmark is stored under its own source
+    if metadata::catalog().get(custom_index::SOURCE, &benchmark_upper).is_some() {
+        source_symbols.push((custom_index::SOURCE, vec![benchmark_upper.clone()]));
+    }
+
+    // currency=: convert security and benchmark closes before RS is taken
+    let conversion_symbols: Vec<(&str, Vec<String>)> = source_symbols
+        .iter()
+        .map(|(src, syms)| (*src, syms.iter().cloned().chain(std::iter::once(benchmark_upper.clone())).collect()))
+        .collect();
+    let jdk_limit = 250 + if params.ema { EMA_LOOKBACK } else { SMA_MAX_PERIOD };
+    let conversions = match super::load_conversions(&state.pool, &conversion_symbols, currency, end_time, jdk_limit).await {
+        Ok(c) => c,
+        Err(e) => {
+            tracing::error!("Failed to load FX rates: {}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": "Failed to load FX rates" })),
+            )
+                .into_response();
+        }
+    };
+
     // For each source, fetch batch raw OHLCV for all its symbols + benchmark
     let min_bars = 3 * period + 1;
     let mut results: HashMap<String, Vec<OhlcvRow>> = HashMap::new();
@@ -773,15 +856,17 @@ async fn handle_jdk(
         fetch_symbols.dedup();
 
         // Try Redis first
-        let jdk_limit = 250 + if params.ema { EMA_LOOKBACK } else { SMA_MAX_PERIOD };
         if let Some(map) = try_redis_batch(
             &state.redis_client, source, &fetch_symbols, "1D", jdk_limit, "rrg/jdk",
         ).await {
             for (sym, rows) in map {
-                let filtered = match end_time {
+                let mut filtered = match end_time {
                     Some(end) => rows.into_iter().filter(|r| r.time <= end).collect(),
                     None => rows,
                 };
+                if let Some(fx) = conversions.get(*source) {
+                    fx.convert(&sym, &mut filtered);
+                }
                 results.insert(format!("{source}:{sym}"), filtered);
             }
         } else {
@@ -798,7 +883,10 @@ async fn handle_jdk(
             .await
             {
                 Ok(map) => {
-                    for (sym, rows) in map {
+                    for (sym, mut rows) in map {
+                        if let Some(fx) = conversions.get(*source) {
+                            fx.convert(&sym, &mut rows);
+                        }
                         results.insert(format!("{source}:{sym}"), rows);
                     }
                 }
@@ -948,6 +1036,7 @@ async fn handle_jdk(
                 algorithm: "jdk".to_string(),
                 period: Some(period),
                 tickers: snapshots,
+                currency: currency.map(|c| c.as_str().to_string()),
             },
         }),
     )
@@ -0,0 +1,182 @@
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Json},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+use crate::models::indicators::{DEFAULT_MA_PERIODS, MAX_INDICATORS, indicators_lookback, ma_lookback};
+use crate::queries::ohlcv::{self, FetchOptions};
+use crate::server::types::Mode;
+use crate::server::AppState;
+use crate::services::screener::{Expression, Hit, Requirements, Row};
+
+use super::{get_all_sources, get_ticker_sector, is_index_ticker, load_ticker_groups, source_groups, validate_limit, AnalysisResponse};
+
+#[derive(Debug, Deserialize)]
+pub struct ScreenRequest {
+    /// Filter expression, see `services::screener` for the grammar.
+    pub filter: String,
+    #[serde(default)]
+    pub mode: Mode,
+    /// Numeric expression to rank matches by.
+    #[serde(default = "default_sort_by")]
+    pub sort_by: String,
+    #[serde(default = "default_direction")]
+    pub direction: String,
+    pub limit: Option<usize>,
+    /// true = use EMA instead of SMA for MA indicators.
+    #[serde(default)]
+    pub ema: bool,
+    /// true = use Redis snapshot cache (default).
+    #[serde(default = "default_true")]
+    pub snap: bool,
+}
+
+fn default_sort_by() -> String { "close_changed".to_string() }
+fn default_direction() -> String { "desc".to_string() }
+fn default_true() -> bool { true }
+
+#[derive(Debug, Serialize)]
+pub struct ScreenResponse {
+    pub filter: String,
+    pub sort_by: String,
+    pub total_matches: usize,
+    pub matches: Vec<ScreenMatch>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ScreenMatch {
+    pub rank: usize,
+    pub symbol: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    pub sector: Option<String>,
+    pub time: String,
+    pub close: f64,
+    pub sort_value: Option<f64>,
+    /// Comparisons that passed, with the values on each side.
+    pub conditions: Vec<Hit>,
+}
+
+#[tracing::instrument(skip(state))]
+pub async fn screen_handler(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<ScreenRequest>,
+) -> impl IntoResponse {
+    let bad_request = |error: String| {
+        (StatusCode::BAD_REQUEST, Json(serde_json::json!({ "error": error }))).into_response()
+    };
+    let filter = match Expression::parse_filter(&req.filter) {
+        Ok(e) => e,
+        Err(e) => return bad_request(format!("Invalid filter: {e}")),
+    };
+    let sort_by = match Expression::parse_value(&req.sort_by) {
+        Ok(e) => e,
+        Err(e) => return bad_request(format!("Invalid sort_by: {e}")),
+    };
+
+    // Size the fetch from what the expressions reference
+    let mut needs = Requirements::default();
+    needs.add(&filter);
+    needs.add(&sort_by);
+    if needs.indicators.len() > MAX_INDICATORS {
+        return bad_request(format!("At most {MAX_INDICATORS} indicators per screen"));
+    }
+    let mut ma_periods: Vec<usize> = DEFAULT_MA_PERIODS.iter().copied().chain(needs.ma_periods.iter().copied()).collect();
+    ma_periods.sort_unstable();
+    ma_periods.dedup();
+    let keep = needs.bars as i64;
+    let redis_limit = keep + ma_lookback(&ma_periods, req.ema).max(indicators_lookback(&needs.indicators));
+
+    let ticker_groups = load_ticker_groups();
+
+    let is_all = req.mode == Mode::All;
+    let sources = if is_all { get_all_sources() } else { vec![req.mode.source_label()] };
+
+    // Fetch every source of the universe in parallel: snapshot/Redis first, PG fallback
+    let per_source = futures::future::join_all(sources.iter().map(|&source| {
+        let groups = source_groups(source, &ticker_groups);
+        let (state, ma_periods, indicators) = (&state, &ma_periods, &needs.indicators);
+        let (use_ema, snap) = (req.ema, req.snap);
+        async move {
+            let symbols: Vec<String> = groups.values().flatten().cloned().collect::<BTreeSet<_>>().into_iter().collect();
+            let opts = FetchOptions { limit: Some(keep), use_ema, ma_periods, indicators, snap, ..Default::default() };
+            let mut map = super::fetch_source_window(&state.redis_client, source, &symbols, "1D", redis_limit, "screen", &opts).await;
+            if map.is_empty() && !symbols.is_empty() {
+                match ohlcv::get_ohlcv_joined_batch(&state.pool, source, &symbols, "1D", &opts).await {
+                    Ok(m) => map = m,
+                    Err(e) => tracing::warn!("Failed to fetch daily data for source '{}': {}", source, e),
+                }
+            }
+            (source, groups, map)
+        }
+    }))
+    .await;
+
+    let mut total_analyzed = 0;
+    let mut matches = Vec::new();
+    for (source, groups, map) in per_source {
+        for (ticker, mut bars) in map {
+            if bars.is_empty() || is_index_ticker(&ticker) {
+                continue;
+            }
+            bars.sort_by_key(|b| std::cmp::Reverse(b.time));
+            total_analyzed += 1;
+
+            let sector = get_ticker_sector(&ticker, &groups);
+            let row = Row { bars: &bars, sector: sector.as_deref(), source };
+            let Some(conditions) = filter.matches(&row) else { continue };
+            let sort_value = sort_by.value(&row);
+
+            matches.push(ScreenMatch {
+                rank: 0,
+                symbol: ticker,
+                source: if is_all { Some(source.to_string()) } else { None },
+                sector,
+                time: bars[0].time.format("%Y-%m-%d").to_string(),
+                close: bars[0].close,
+                sort_value,
+                conditions,
+            });
+        }
+    }
+
+    // Rank by sort_value; tickers without one go last either way
+    let ascending = req.direction == "asc";
+    matches.sort_by(|a, b| {
+        use std::cmp::Ordering;
+        let order = match (a.sort_value, b.sort_value) {
+            (Some(x), Some(y)) => {
+                let o = y.partial_cmp(&x).unwrap_or(Ordering::Equal);
+                if ascending { o.reverse() } else { o }
+            }
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        };
+        order.then_with(|| a.symbol.cmp(&b.symbol))
+    });
+    let total_matches = matches.len();
+    matches.truncate(validate_limit(req.limit));
+    for (i, m) in matches.iter_mut().enumerate() {
+        m.rank = i + 1;
+    }
+
+    (
+        StatusCode::OK,
+        Json(AnalysisResponse {
+            analysis_date: "latest".to_string(),
+            analysis_type: "screen".to_string(),
+            total_analyzed,
+            data: ScreenResponse {
+                filter: filter.to_string(),
+                sort_by: sort_by.to_string(),
+                total_matches,
+                matches,
+            },
+        }),
+    ).into_response()
+}
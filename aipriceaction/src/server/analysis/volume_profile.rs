@@ -7,9 +7,12 @@ use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use chrono::NaiveDate;
 
-use crate::models::ohlcv::OhlcvJoined;
+use crate::models::custom_index;
+use crate::models::ohlcv::OhlcvRow;
 use crate::queries::ohlcv;
 use crate::server::types::Mode;
+use crate::services::fx::{self, Conversion};
+use crate::services::metadata;
 use crate::server::AppState;
 use crate::workers::redis_worker;
 
@@ -25,6 +28,8 @@ pub struct VolumeProfileQuery {
     pub mode: String,
     pub bins: Option<usize>,
     pub value_area_pct: Option<f64>,
+    /// USD or VND: convert the minute bars before they are binned.
+    pub currency: Option<String>,
 }
 
 fn default_mode() -> String { "vn".to_string() }
@@ -39,6 +44,8 @@ pub struct VolumeProfileResponse {
     pub value_area: ValueArea,
     pub profile: Vec<PriceLevelVolume>,
     pub statistics: VolumeStatistics,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub currency: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -120,13 +127,22 @@ pub async fn volume_profile_handler(
         return (StatusCode::BAD_REQUEST, Json(serde_json::json!({ "error": "end_date must be >= start_date" }))).into_response();
     }
 
+    let currency = match fx::parse_target(params.currency.as_deref()) {
+        Ok(c) => c,
+        Err(e) => {
+            return (StatusCode::BAD_REQUEST, Json(serde_json::json!({ "error": e }))).into_response();
+        }
+    };
+
     let mode = match params.mode.to_lowercase().as_str() {
         "crypto" => Mode::Crypto,
         "yahoo" => Mode::Yahoo,
         _ => Mode::Vn,
     };
 
-    let source = mode.source_label();
+    // Custom indices live under their own source whatever the mode
+    let is_custom = metadata::catalog().get(custom_index::SOURCE, &params.symbol).is_some();
+    let source = if is_custom { custom_index::SOURCE } else { mode.source_label() };
     let num_bins = params.bins.unwrap_or(50).clamp(2, 200);
     let value_area_pct = params.value_area_pct.unwrap_or(70.0).clamp(60.0, 90.0);
 
@@ -135,55 +151,32 @@ pub async fn volume_profile_handler(
 
     // Try Redis first, fall back to PG
     let limit = redis_worker::max_size("1m") as i64;
-    let rows: Vec<OhlcvJoined> = if let Some(map) = try_redis_batch(
+    let redis_rows: Vec<OhlcvRow> = match try_redis_batch(
         &state.redis_client, source, &[params.symbol.clone()], "1m", limit, "volume_profile",
     ).await {
-        let redis_rows: Vec<OhlcvJoined> = map.get(&params.symbol)
-            .map(|r| r.iter().map(|row| OhlcvJoined {
-                ticker: params.symbol.clone(),
-                time: row.time,
-                open: row.open,
-                high: row.high,
-                low: row.low,
-                close: row.close,
-                volume: row.volume,
-                ma10: None, ma20: None, ma50: None, ma100: None, ma200: None,
-                ma10_score: None, ma20_score: None, ma50_score: None, ma100_score: None, ma200_score: None,
-                close_changed: None, volume_changed: None, total_money_changed: None,
-            }).collect::<Vec<_>>())
-            .unwrap_or_default();
-        // If Redis has data but none falls in the requested range, try PG
-        if !redis_rows.is_empty() {
-            let in_range: Vec<_> = redis_rows.iter()
-                .filter(|d| {
-                    let date = d.time.date_naive();
-                    date >= start_naive && date <= end_naive
-                })
-                .collect();
-            if in_range.is_empty() {
-                Vec::new() // Redis data doesn't cover this date range, fall to PG
-            } else {
-                redis_rows
-            }
-        } else {
-            Vec::new()
-        }
-    } else {
-        Vec::new() // Redis unavailable, fall through to PG
+        Some(mut map) => map.remove(&params.symbol).unwrap_or_default(),
+        None => Vec::new(), // Redis unavailable, fall through to PG
     };
-
-    let rows = if rows.is_empty() {
-        // Fetch minute data from DB
-        match ohlcv::get_ohlcv_joined_range(
+    // If Redis has data but none falls in the requested range, try PG
+    let covered = redis_rows.iter().any(|d| {
+        let date = d.time.date_naive();
+        date >= start_naive && date <= end_naive
+    });
+
+    let mut rows = if covered {
+        redis_rows
+    } else {
+        // Fetch minute data from DB, no limit — all minute data for the date range
+        match ohlcv::get_ohlcv_batch_raw(
             &state.pool,
             source,
-            &params.symbol,
+            std::slice::from_ref(&params.symbol),
             "1m",
-            None, // no limit — get all minute data for the date range
+            None,
             Some(start_datetime),
             Some(end_datetime),
         ).await {
-            Ok(r) => r,
+            Ok(mut map) => map.remove(&params.symbol).unwrap_or_default(),
             Err(e) => {
                 return (
                     StatusCode::INTERNAL_SERVER_ERROR,
@@ -191,10 +184,27 @@ pub async fn volume_profile_handler(
                 ).into_response();
             }
         }
-    } else {
-        rows
     };
 
+    // currency=: convert the bars themselves, so bins and ticks follow the target
+    let conversion = match currency {
+        Some(target) => match Conversion::load(&state.pool, [(params.symbol.as_str(), source)], target, start_datetime, end_datetime, true).await {
+            Ok(c) => Some(c),
+            Err(e) => {
+                tracing::error!("Failed to load FX rates: {}", e);
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(serde_json::json!({ "error": "Failed to load FX rates" })),
+                ).into_response();
+            }
+        },
+        None => None,
+    };
+    if let Some(fx) = &conversion {
+        fx.convert(&params.symbol, &mut rows);
+    }
+    let price_currency = conversion.as_ref().and_then(|fx| fx.label(&params.symbol));
+
     if rows.is_empty() {
         let date_desc = if start_date_str == end_date_str {
             start_date_str
@@ -232,6 +242,12 @@ pub async fn volume_profile_handler(
     let avg_price = total_price / filtered.len() as f64;
 
     let tick_size = match mode {
+        // Converted prices take the ticks of the currency they end up in
+        _ if conversion.is_some() => match price_currency {
+            Some("VND") => get_tick_size_vn(avg_price, &params.symbol),
+            _ => get_tick_size_crypto(avg_price),
+        },
+        _ if is_custom => get_tick_size_crypto(avg_price),
         Mode::Vn | Mode::All => get_tick_size_vn(avg_price, &params.symbol),
         Mode::Crypto | Mode::Yahoo => get_tick_size_crypto(avg_price),
     };
@@ -308,6 +324,7 @@ pub async fn volume_profile_handler(
         value_area,
         profile: calculate_poc_and_percentages(profile, total_volume),
         statistics,
+        currency: price_currency.map(str::to_string),
     };
 
     let analysis_date = if start_date_str == end_date_str {
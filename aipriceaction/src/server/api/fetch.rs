@@ -2,8 +2,16 @@ use chrono::NaiveDate;
 use sqlx::PgPool;
 use std::collections::{BTreeMap, HashMap};
 
+use crate::models::calendar::Exchange;
+use crate::models::corporate_action::Adjustment;
+use crate::models::custom_index;
+use crate::models::indicators::{indicators_lookback, ma_lookback};
+use crate::queries::ohlcv::FetchOptions;
 use crate::server::redis_reader;
 use crate::server::types::{Mode, NormalizedInterval, StockDataResponse, TickersQuery};
+use crate::models::portfolio::Currency;
+use crate::services::fx::{self, Conversion};
+use crate::services::metadata;
 use crate::services::ohlcv;
 
 /// PG fallback for list_tickers_with_extra when Redis doesn't have data.
@@ -143,6 +151,7 @@ pub(crate) fn build_cache_key(
     interval: &NormalizedInterval,
     symbols: &[String],
     effective_limit: Option<i64>,
+    ma_periods: &[usize],
 ) -> String {
     let source = params.mode.source_label();
     let interval_str = match interval {
@@ -164,7 +173,95 @@ pub(crate) fn build_cache_key(
     let start = params.start_date.as_deref().unwrap_or("");
     let end = params.end_date.as_deref().unwrap_or("");
 
-    format!("{source}|{interval_str}|{sorted_symbols}|{limit}|{start}|{end}|ma={}|ema={}", params.ma, params.ema)
+    let indicators = params.indicators.as_deref().unwrap_or("").to_ascii_lowercase();
+    // Parsed periods, so `20,10` and `10,20,10` share an entry with `10,20`
+    let ma_periods = ma_periods.iter().map(usize::to_string).collect::<Vec<_>>().join(",");
+
+    let currency = params.currency.as_deref().unwrap_or("").to_ascii_uppercase();
+
+    format!(
+        "{source}|{interval_str}|{sorted_symbols}|{limit}|{start}|{end}|ma={}|ema={}|map={ma_periods}|adj={}|ind={indicators}|cur={currency}",
+        params.ma, params.ema, params.adjust.as_str()
+    )
+}
+
+/// Source of each symbol of a single-mode response: `source`, or the extra
+/// source whose metadata lists it (e.g. SJC-GOLD under mode=yahoo).
+pub(crate) fn symbol_sources<'a>(
+    source: &str,
+    extra_sources: &[&str],
+    symbols: impl IntoIterator<Item = &'a String>,
+) -> HashMap<String, String> {
+    let catalog = metadata::catalog();
+    symbols
+        .into_iter()
+        .map(|symbol| {
+            let src = extra_sources.iter().find(|&&src| catalog.get(src, symbol).is_some()).copied().unwrap_or(source);
+            (symbol.clone(), src.to_string())
+        })
+        .collect()
+}
+
+/// Prepare the `currency=` conversion of `symbols` (with their sources) for
+/// the bars `opts` reads at `interval`: its date range or limit, plus the
+/// MA/indicator warm-up, up to `opts.end_time` or now.
+pub(crate) async fn load_conversion<'a>(
+    pool: &PgPool,
+    symbols: impl IntoIterator<Item = (&'a str, &'a str)>,
+    target: Currency,
+    interval: &NormalizedInterval,
+    opts: &FetchOptions<'_>,
+) -> sqlx::Result<Conversion> {
+    use crate::models::interval::Interval;
+    let minutes = |base: Interval| match base {
+        Interval::Minute => 1,
+        Interval::Hourly => 60,
+        Interval::Daily => 24 * 60,
+    };
+    let (base, interval_minutes) = match interval {
+        NormalizedInterval::Native(db_interval) => {
+            let base = match *db_interval {
+                "1m" => Interval::Minute,
+                "1h" => Interval::Hourly,
+                _ => Interval::Daily,
+            };
+            (base, minutes(base))
+        }
+        NormalizedInterval::Aggregated(agg) => (agg.base_interval(), minutes(agg.base_interval()) * agg.base_bars_per_candle()),
+    };
+    let warm_up = if opts.with_ma { ma_lookback(opts.ma_periods, opts.use_ema) } else { 0 }.max(indicators_lookback(opts.indicators));
+    let bars = warm_up + if opts.start_time.is_none() { opts.limit.unwrap_or(1) } else { 0 };
+    let end = opts.end_time.unwrap_or_else(chrono::Utc::now);
+    let start = fx::window_start(opts.start_time, end, bars, interval_minutes);
+    Conversion::load(pool, symbols, target, start, end, base != Interval::Daily).await
+}
+
+/// Record on each row the currency `fx` left its prices in.
+pub(crate) fn label_currency(data: &mut BTreeMap<String, Vec<StockDataResponse>>, fx: &Conversion) {
+    for (symbol, rows) in data.iter_mut() {
+        let label = fx.label(symbol).map(str::to_string);
+        for row in rows.iter_mut() {
+            row.currency = label.clone();
+        }
+    }
+}
+
+/// `extra_sources` plus `custom` when a requested symbol is a custom index,
+/// so synthetic indices resolve under every mode.
+pub(crate) fn with_custom_indices(extra_sources: &[&'static str], symbols: Option<&[String]>) -> Vec<&'static str> {
+    let catalog = metadata::catalog();
+    let mut sources = extra_sources.to_vec();
+    if symbols.unwrap_or_default().iter().any(|s| catalog.get(custom_index::SOURCE, &s.to_uppercase()).is_some()) {
+        sources.push(custom_index::SOURCE);
+    }
+    sources
+}
+
+/// Whether any symbol is part of a rename chain, whose history PG stitches
+/// together under the newest symbol.
+fn any_renamed(source: &str, symbols: &[String]) -> bool {
+    let catalog = metadata::catalog();
+    symbols.iter().any(|s| catalog.rename_chain(source, s).is_some())
 }
 
 /// Parse a date string as start-of-day UTC.
@@ -189,48 +286,50 @@ pub(crate) fn parse_date_end(s: &str) -> Option<chrono::DateTime<chrono::Utc>> {
 /// When called from `handle_mode_all`, pass `use_redis=true` to enable the
 /// Redis-first path (no `params.redis` check). The single-mode `tickers` handler
 /// passes `use_redis=params.redis` to respect the user's redis flag.
-#[tracing::instrument(skip(pool, redis_client, symbols, extra_sources))]
+#[tracing::instrument(skip(pool, redis_client, symbols, opts))]
 pub(crate) async fn fetch_native_tickers(
     pool: &PgPool,
     redis_client: &Option<crate::redis::RedisClient>,
     source: &str,
     symbols: Vec<String>,
     interval: &str,
-    start_time: Option<chrono::DateTime<chrono::Utc>>,
-    end_time: Option<chrono::DateTime<chrono::Utc>>,
-    limit: Option<i64>,
-    extra_sources: &[&str],
+    opts: &FetchOptions<'_>,
     use_redis: bool,
-    with_ma: bool,
-    use_ema: bool,
-    skip_snap: bool,
 ) -> (BTreeMap<String, Vec<StockDataResponse>>, &'static str, Option<redis_reader::RedisReadResult>) {
+    let FetchOptions { limit, start_time, end_time, extra_sources, with_ma, use_ema, ma_periods, indicators, adjust, snap, fx } = *opts;
     let is_daily = interval == "1D";
 
     // Redis shortcut: native interval, Redis client available
     // When a date range is given, check if Redis has data covering start_time
+    // Redis holds stored (raw) prices of one symbol only, so adjusted and
+    // stitched (renamed) requests go to PG.
     let redis_allowed = use_redis
+        && adjust == Adjustment::Raw
         && !symbols.is_empty()
+        && !any_renamed(source, &symbols)
         && redis_client.is_some();
 
     // --- Snapshot fast path ---
     // Try reading pre-computed responses from Redis HASH snapshots.
-    // Only eligible when: no date range (or end_date >= today), MA enabled, limit is set, and not a recursive call.
+    // Only eligible when: no date range (or end_date >= today), MA enabled, limit is set,
+    // no extra indicators or currency conversion requested, and not a recursive call.
     let today = chrono::Utc::now().date_naive();
     let end_is_today = end_time.map_or(true, |t| t.date_naive() >= today);
-    let snap_eligible = !skip_snap
+    let snap_eligible = snap
         && redis_allowed
         && limit.is_some()
         && start_time.is_none()
         && end_is_today
-        && with_ma;
+        && with_ma
+        && indicators.is_empty()
+        && fx.is_none();
     let ma_type: &str = if use_ema { "ema" } else { "sma" };
     let limit_val = limit.unwrap_or(1);
 
     if snap_eligible {
         if let Some(redis) = &*redis_client {
         if let Some(raw_values) = crate::workers::redis_worker::batch_read_snapshots(
-            redis, source, &symbols, interval, limit_val, ma_type,
+            redis, source, &symbols, interval, limit_val, ma_type, ma_periods,
         ).await {
             // Separate hits from misses
             let mut result: BTreeMap<String, Vec<StockDataResponse>> = BTreeMap::new();
@@ -248,25 +347,24 @@ pub(crate) async fn fetch_native_tickers(
                 missed_symbols.push(symbols[i].clone());
             }
 
-            // Compute missing tickers via existing path (skip_snap=true to avoid recursion)
+            // Compute missing tickers via existing path (snap=false to avoid recursion)
             if !missed_symbols.is_empty() {
+                let missed_opts = FetchOptions { start_time: None, end_time: None, snap: false, ..*opts };
                 let (missed_result, _tag, _meta) = Box::pin(fetch_native_tickers(
-                    pool, redis_client, source, missed_symbols.clone(), interval,
-                    None, None, limit, extra_sources, use_redis, with_ma, use_ema, true,
+                    pool, redis_client, source, missed_symbols, interval, &missed_opts, use_redis,
                 )).await;
 
                 // Write back snapshots for the newly computed tickers
                 if !missed_result.is_empty() {
                     let redis = redis.clone();
                     let ma_type_owned = ma_type.to_string();
+                    let ma_periods_owned = ma_periods.to_vec();
                     let source_owned = source.to_string();
                     let interval_owned = interval.to_string();
                     let missed_clone = missed_result.clone();
-                    let missed_syms = missed_symbols;
                     tokio::spawn(async move {
                         crate::workers::redis_worker::batch_write_snapshots(
-                            &redis, &source_owned, &missed_syms,
-                            &interval_owned, limit_val, &ma_type_owned, &missed_clone, None,
+                            &redis, &source_owned, &interval_owned, limit_val, &ma_type_owned, &ma_periods_owned, &missed_clone,
                         ).await;
                     });
                 }
@@ -300,10 +398,11 @@ pub(crate) async fn fetch_native_tickers(
             // When a start date is given, fetch all ZSET rows so the range
             // can be in the middle of history (not just at the tail).
             // For end-only, use ZREVRANGEBYSCORE to start from end_time,
-            // so a small limit + SMA_MAX_PERIOD suffices.
+            // so a small limit + the MA lookback suffices.
             let need_full_scan = start_time.is_some();
             let max_score = if need_full_scan { None } else { end_time.map(|t| t.timestamp_millis()) };
-            let buffer = if with_ma { if use_ema { crate::constants::api::EMA_LOOKBACK } else { crate::constants::api::SMA_MAX_PERIOD } } else { 1 };
+            let buffer = if with_ma { ma_lookback(ma_periods, use_ema) } else { 1 };
+            let buffer = buffer.max(indicators_lookback(indicators));
             let total_limit = if need_full_scan {
                 (crate::workers::redis_worker::max_size(interval) as i64) + buffer
             } else {
@@ -358,7 +457,7 @@ pub(crate) async fn fetch_native_tickers(
                         limit
                     };
                     let enhanced = crate::queries::ohlcv::enhance_rows(
-                        &ticker, redis_result.rows, enhance_limit, start_time, with_ma, use_ema,
+                        &ticker, redis_result.rows, &FetchOptions { limit: enhance_limit, ..*opts },
                     );
                     let mut enhanced = enhanced;
                     // Apply end_time filter when date range was provided
@@ -393,15 +492,14 @@ pub(crate) async fn fetch_native_tickers(
                         if let Some(redis) = redis_client.as_ref() {
                         let redis = redis.clone();
                         let ma_type_owned = ma_type.to_string();
+                        let ma_periods_owned = ma_periods.to_vec();
                         let source_owned = source.to_string();
                         let interval_owned = interval.to_string();
                         let result_clone = result.clone();
-                        let syms_owned: Vec<String> = symbols.clone();
                         let snap_limit = limit_val;
                         tokio::spawn(async move {
                             crate::workers::redis_worker::batch_write_snapshots(
-                                &redis, &source_owned, &syms_owned,
-                                &interval_owned, snap_limit, &ma_type_owned, &result_clone, None,
+                                &redis, &source_owned, &interval_owned, snap_limit, &ma_type_owned, &ma_periods_owned, &result_clone,
                             ).await;
                         });
                         } // end if let Some(redis)
@@ -413,24 +511,12 @@ pub(crate) async fn fetch_native_tickers(
         }
     }
 
-    // Fall through to PostgreSQL
-    let batch_map = if extra_sources.is_empty() {
-        tokio::time::timeout(
-            std::time::Duration::from_secs(5),
-            ohlcv::get_ohlcv_joined_batch(
-                pool, source, &symbols, interval, limit, start_time, end_time, with_ma, use_ema,
-            ),
-        )
-        .await
-    } else {
-        tokio::time::timeout(
-            std::time::Duration::from_secs(5),
-            ohlcv::get_ohlcv_joined_batch_with_extra(
-                pool, source, &symbols, interval, limit, start_time, end_time, extra_sources, with_ma, use_ema,
-            ),
-        )
-        .await
-    };
+    // Fall through to PostgreSQL (Adjustment::Raw returns stored prices)
+    let batch_map = tokio::time::timeout(
+        std::time::Duration::from_secs(5),
+        ohlcv::get_ohlcv_joined_batch(pool, source, &symbols, interval, opts),
+    )
+    .await;
 
     let batch_map = match batch_map {
         Ok(Ok(m)) => m,
@@ -465,46 +551,49 @@ pub(crate) async fn fetch_native_tickers(
 
 /// Aggregated interval: fetch source data, aggregate, enhance, trim.
 /// Returns (data, source_tag, redis_meta).
-#[tracing::instrument(skip(pool, redis_client, symbols, extra_sources))]
+#[tracing::instrument(skip(pool, redis_client, symbols, opts))]
 pub(crate) async fn fetch_aggregated_tickers(
     pool: &PgPool,
     redis_client: &Option<crate::redis::RedisClient>,
     source: &str,
     symbols: Vec<String>,
     agg: crate::models::aggregated_interval::AggregatedInterval,
-    start_time: Option<chrono::DateTime<chrono::Utc>>,
-    end_time: Option<chrono::DateTime<chrono::Utc>>,
-    limit: i64,
-    extra_sources: &[&str],
+    opts: &FetchOptions<'_>,
     use_redis: bool,
-    with_ma: bool,
-    use_ema: bool,
 ) -> (BTreeMap<String, Vec<StockDataResponse>>, &'static str, Option<redis_reader::RedisReadResult>) {
     use crate::services::aggregator::{AggregatedOhlcv, Aggregator};
 
+    let FetchOptions { start_time, end_time, extra_sources, with_ma, ma_periods, indicators, adjust, .. } = *opts;
+    let limit = opts.limit.unwrap_or(crate::constants::api::DEFAULT_LIMIT);
+
     let base_interval = agg.base_interval().as_str();
 
-    // Fetch source data with lookback buffer for MA200 (skip when with_ma=false).
+    // Fetch source data with lookback buffer for the longest MA (skip when with_ma=false).
     // When ma=false, still need enough base bars to form the requested aggregated candles
     // plus one extra bucket for the partial leading candle.
     let agg_buffer = if with_ma {
-        crate::constants::api::AGGREGATED_LOOKBACK
+        let max_period = ma_periods.iter().copied().max().unwrap_or(0) as i64;
+        crate::constants::api::AGGREGATED_LOOKBACK.max(max_period * agg.base_bars_per_candle())
     } else {
         agg.base_bars_per_candle() // e.g. 15 for 15m-from-1m, 4 for 4h-from-1h, 7 for 1W-from-1D
     };
+    // Extra indicators need their warm-up in aggregated candles.
+    let agg_buffer = agg_buffer.max(indicators_lookback(indicators) * agg.base_bars_per_candle());
     let lookback = limit * agg.base_bars_per_candle() + agg_buffer;
 
     let is_daily = base_interval == "1D";
 
-    // Hourly offset: VN stocks align to market open (09:00 ICT = 02:00 UTC),
-    // crypto aligns to midnight UTC.
-    let hourly_offset: i64 = if source == "vn" { 2 } else { 0 };
+    // Hourly offset from the exchange calendar: VN stocks align to market open
+    // (09:00 ICT = 02:00 UTC), everything else to midnight UTC.
+    let hourly_offset: i64 = Exchange::for_source(source).map_or(0, |e| e.bucket_offset_hours());
 
     // Redis shortcut: aggregated interval, no extra sources
     // When a date range is given, check if Redis has data covering start_time
     let redis_allowed = use_redis
+        && adjust == Adjustment::Raw
         && !symbols.is_empty()
         && extra_sources.is_empty()
+        && !any_renamed(source, &symbols)
         && redis_client.is_some();
 
     if redis_allowed {
@@ -554,19 +643,23 @@ pub(crate) async fn fetch_aggregated_tickers(
                         first_meta = Some(meta);
                     }
 
+                    let mut rows = redis_result.rows;
+                    if let Some(fx) = opts.fx {
+                        fx.convert(&ticker, &mut rows);
+                    }
                     let aggregated = match agg.base_interval() {
                         crate::models::interval::Interval::Daily => {
-                            Aggregator::aggregate_daily_data(&ticker, redis_result.rows, agg)
+                            Aggregator::aggregate_daily_data(&ticker, rows, agg)
                         }
                         crate::models::interval::Interval::Hourly => {
-                            Aggregator::aggregate_hourly_data(&ticker, redis_result.rows, agg, hourly_offset)
+                            Aggregator::aggregate_hourly_data(&ticker, rows, agg, hourly_offset)
                         }
-                        _ => Aggregator::aggregate_minute_data(&ticker, redis_result.rows, agg),
+                        _ => Aggregator::aggregate_minute_data(&ticker, rows, agg),
                     };
                     per_ticker.insert(ticker, aggregated);
                 }
 
-                let enhanced = Aggregator::enhance_aggregated_data(per_ticker, with_ma, use_ema);
+                let enhanced = Aggregator::enhance_aggregated_data(per_ticker, opts);
                 let mut result = BTreeMap::new();
 
                 for (ticker, data) in &enhanced {
@@ -606,28 +699,29 @@ pub(crate) async fn fetch_aggregated_tickers(
         }
     }
 
+    // Materialised candles (MATERIALIZED_INTERVALS): read stored aggregates
+    // instead of over-fetching base bars. Latest-N requests only; any miss
+    // falls through to on-the-fly aggregation.
+    if start_time.is_none()
+        && adjust == Adjustment::Raw
+        && !symbols.is_empty()
+        && crate::constants::materialize::intervals().contains(&agg)
+        && let Some(result) = fetch_materialized(pool, source, &symbols, agg, limit, opts, is_daily).await
+    {
+        tracing::info!(path = "materialized", tickers = result.len());
+        return (result, "materialized", None);
+    }
+
     // Fall through to PostgreSQL
-    let raw_result = if extra_sources.is_empty() {
-        tokio::time::timeout(
-            std::time::Duration::from_secs(5),
-            ohlcv::get_ohlcv_batch_raw(
-                pool, source, &symbols, base_interval,
-                Some(lookback), start_time, end_time,
-            ),
-        )
-        .await
-    } else {
-        tokio::time::timeout(
-            std::time::Duration::from_secs(5),
-            ohlcv::get_ohlcv_batch_raw_with_extra(
-                pool, source, &symbols, base_interval,
-                Some(lookback), start_time, end_time, extra_sources,
-            ),
-        )
-        .await
-    };
+    let raw_result = tokio::time::timeout(
+        std::time::Duration::from_secs(5),
+        ohlcv::get_ohlcv_batch_raw_with_extra(
+            pool, source, &symbols, base_interval, &FetchOptions { limit: Some(lookback), ..*opts },
+        ),
+    )
+    .await;
 
-    let raw_map = match raw_result {
+    let mut raw_map = match raw_result {
         Ok(Ok(m)) => m,
         Ok(Err(e)) => {
             tracing::warn!("Failed to batch-fetch for aggregation ({base_interval}): {e}");
@@ -639,9 +733,18 @@ pub(crate) async fn fetch_aggregated_tickers(
         }
     };
 
+    if let Err(e) = ohlcv::adjust_raw_batch(pool, source, extra_sources, &symbols, &mut raw_map, adjust).await {
+        tracing::warn!("Failed to load corporate actions for adjust={}: {e}", adjust.as_str());
+        return (BTreeMap::new(), "postgres", None);
+    }
+
     let mut per_ticker: HashMap<String, Vec<AggregatedOhlcv>> = HashMap::new();
 
-    for (ticker, rows) in raw_map {
+    for (ticker, mut rows) in raw_map {
+        // Convert the base bars so aggregates and indicators are in the target currency
+        if let Some(fx) = opts.fx {
+            fx.convert(&ticker, &mut rows);
+        }
         let aggregated = match agg.base_interval() {
             crate::models::interval::Interval::Daily => {
                 Aggregator::aggregate_daily_data(&ticker, rows, agg)
@@ -655,7 +758,7 @@ pub(crate) async fn fetch_aggregated_tickers(
     }
 
     // Enhance with indicators
-    let enhanced = Aggregator::enhance_aggregated_data(per_ticker, with_ma, use_ema);
+    let enhanced = Aggregator::enhance_aggregated_data(per_ticker, opts);
 
     // Trim to requested limit and map to response
     let mut result: BTreeMap<String, Vec<StockDataResponse>> = BTreeMap::new();
@@ -684,3 +787,101 @@ pub(crate) async fn fetch_aggregated_tickers(
     result.retain(|_, v| !v.is_empty());
     (result, "postgres", None)
 }
+
+/// Read `limit` materialised `agg` candles per symbol up to `opts.end_time`
+/// (plus the MA lookback) and enhance them. Returns `None` unless every symbol
+/// has a full window, so tickers still being backfilled are served by the
+/// aggregation path.
+async fn fetch_materialized(
+    pool: &PgPool,
+    source: &str,
+    symbols: &[String],
+    agg: crate::models::aggregated_interval::AggregatedInterval,
+    limit: i64,
+    opts: &FetchOptions<'_>,
+    is_daily: bool,
+) -> Option<BTreeMap<String, Vec<StockDataResponse>>> {
+    use crate::services::aggregator::{AggregatedOhlcv, Aggregator};
+
+    let buffer = if opts.with_ma { ma_lookback(opts.ma_periods, opts.use_ema) } else { 1 };
+    let needed = limit + buffer.max(indicators_lookback(opts.indicators));
+
+    let raw_map = match tokio::time::timeout(
+        std::time::Duration::from_secs(5),
+        ohlcv::get_ohlcv_batch_raw_with_extra(
+            pool, source, symbols, agg.to_str(), &FetchOptions { limit: Some(needed), start_time: None, ..*opts },
+        ),
+    )
+    .await
+    {
+        Ok(Ok(m)) => m,
+        Ok(Err(e)) => {
+            tracing::warn!("Failed to fetch materialised {}: {e}", agg.to_str());
+            return None;
+        }
+        Err(_) => {
+            tracing::warn!("Timeout fetching materialised {}", agg.to_str());
+            return None;
+        }
+    };
+
+    let complete = symbols
+        .iter()
+        .all(|s| raw_map.get(s).is_some_and(|rows| rows.len() as i64 >= needed));
+    if !complete {
+        return None;
+    }
+
+    let per_ticker: HashMap<String, Vec<AggregatedOhlcv>> = raw_map
+        .into_iter()
+        .map(|(ticker, mut rows)| {
+            // DB returns newest first; indicators need oldest first
+            rows.reverse();
+            if let Some(fx) = opts.fx {
+                fx.convert(&ticker, &mut rows);
+            }
+            let candles = rows
+                .into_iter()
+                .map(|r| AggregatedOhlcv {
+                    ticker: ticker.clone(),
+                    time: r.time,
+                    open: r.open,
+                    high: r.high,
+                    low: r.low,
+                    close: r.close,
+                    volume: r.volume,
+                    ma10: None,
+                    ma20: None,
+                    ma50: None,
+                    ma100: None,
+                    ma200: None,
+                    ma10_score: None,
+                    ma20_score: None,
+                    ma50_score: None,
+                    ma100_score: None,
+                    ma200_score: None,
+                    close_changed: None,
+                    volume_changed: None,
+                    total_money_changed: None,
+                    indicators: BTreeMap::new(),
+                })
+                .collect();
+            (ticker, candles)
+        })
+        .collect();
+
+    let enhanced = Aggregator::enhance_aggregated_data(per_ticker, opts);
+
+    let mut result = BTreeMap::new();
+    for (ticker, data) in enhanced {
+        let start = data.len().saturating_sub(limit as usize);
+        let trimmed: Vec<StockDataResponse> = data[start..]
+            .iter()
+            .map(|d| super::response::map_aggregated_to_response(d, is_daily, Mode::All))
+            .collect();
+        if !trimmed.is_empty() {
+            result.insert(ticker, trimmed);
+        }
+    }
+    Some(result)
+}
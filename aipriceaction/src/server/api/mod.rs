@@ -1,15 +1,19 @@
 pub(super) mod data_loader;
-mod fetch;
-mod response;
+pub(crate) mod fetch;
+pub(crate) mod response;
 
 use axum::extract::State;
 use axum::http::{HeaderName, HeaderValue, StatusCode};
 use axum::response::{IntoResponse, Json, Response};
 use axum_extra::extract::Query;
-use chrono::{Datelike, Timelike};
 use std::collections::BTreeMap;
 use std::sync::Arc;
 
+use crate::models::calendar::Exchange;
+use crate::models::indicators::{Indicator, parse_ma_periods};
+use crate::queries::ohlcv::FetchOptions;
+use crate::models::portfolio::Currency;
+use crate::services::fx;
 use crate::server::types::{
     GroupQuery, Mode, NormalizedInterval, RefreshQuery, StockDataResponse,
     TickersQuery,
@@ -25,13 +29,23 @@ pub async fn health(State(state): State<Arc<AppState>>) -> Response {
 
     let uptime_secs = state.started_at.elapsed().as_secs();
 
-    // Trading hours: 9:00-15:00 ICT (UTC+7) = 02:00-08:00 UTC, Mon-Fri
+    // VN session state (HOSE calendar), plus every exchange we track
     let now = chrono::Utc::now();
-    let weekday = now.weekday();
-    let hour_utc = now.hour();
-    let is_trading_hours = weekday.num_days_from_monday() < 5
-        && hour_utc >= 2
-        && hour_utc < 8;
+    let is_trading_hours = Exchange::Hose.is_open(now);
+    let exchanges: serde_json::Map<String, serde_json::Value> = Exchange::ALL
+        .iter()
+        .map(|e| {
+            let local_date = (now.naive_utc() + e.utc_offset(now.date_naive())).date();
+            (
+                e.as_str().to_string(),
+                serde_json::json!({
+                    "is_open": e.is_open(now),
+                    "is_trading_day": e.is_trading_day(local_date),
+                    "timezone": e.timezone(),
+                }),
+            )
+        })
+        .collect();
 
     (
         StatusCode::OK,
@@ -45,7 +59,9 @@ pub async fn health(State(state): State<Arc<AppState>>) -> Response {
             "hourly_last_sync": snap.hourly_last_sync,
             "minute_last_sync": snap.minute_last_sync,
             "is_trading_hours": is_trading_hours,
-            "trading_hours_timezone": "Asia/Ho_Chi_Minh",
+            "trading_hours_timezone": Exchange::Hose.timezone(),
+            "exchanges": exchanges,
+            "validation": crate::services::validation::stats(),
             "uptime_secs": uptime_secs,
             "current_system_time": chrono::Utc::now().to_rfc3339(),
             "crypto_last_sync": 0,
@@ -95,9 +111,34 @@ pub async fn tickers(
         }
     };
 
+    // Extra indicators (indicators=rsi14,macd,bb20)
+    let indicators = match Indicator::parse_list(params.indicators.as_deref().unwrap_or("")) {
+        Ok(list) => list,
+        Err(e) => {
+            return (StatusCode::BAD_REQUEST, Json(serde_json::json!({ "error": e }))).into_response();
+        }
+    };
+
+    // MA periods (ma_periods=5,13,34,89)
+    let ma_periods = match parse_ma_periods(params.ma_periods.as_deref()) {
+        Ok(periods) => periods,
+        Err(e) => {
+            return (StatusCode::BAD_REQUEST, Json(serde_json::json!({ "error": e }))).into_response();
+        }
+    };
+
+    // Price currency (currency=USD|VND); converted rows skip legacy VND scaling
+    let currency = match fx::parse_target(params.currency.as_deref()) {
+        Ok(c) => c,
+        Err(e) => {
+            return (StatusCode::BAD_REQUEST, Json(serde_json::json!({ "error": e }))).into_response();
+        }
+    };
+    let legacy = params.legacy && !currency.is_some_and(|c| c.is_usd());
+
     // mode=all: query across all sources
     if params.mode == Mode::All {
-        return handle_mode_all(&state, params, interval).await;
+        return handle_mode_all(&state, params, interval, indicators, ma_periods, currency).await;
     }
 
     let extra_sources = if params.mode == Mode::Yahoo {
@@ -105,6 +146,7 @@ pub async fn tickers(
     } else {
         &[][..]
     };
+    let extra_sources = &fetch::with_custom_indices(extra_sources, params.symbol.as_deref())[..];
 
     // Early return for empty or blank explicit symbol list
     if let Some(ref syms) = params.symbol {
@@ -132,14 +174,14 @@ pub async fn tickers(
 
     // Build cache key with symbols available so far
     let cache_key_symbols = params.symbol.as_deref().unwrap_or(&[]);
-    let cache_key = fetch::build_cache_key(&params, &interval, cache_key_symbols, Some(effective_limit));
+    let cache_key = fetch::build_cache_key(&params, &interval, cache_key_symbols, Some(effective_limit), &ma_periods);
 
     // Try cache BEFORE any DB call
     if params.cache {
         let mut guard = state.tickers_cache.write().await;
         if let Some(cached) = guard.get(&cache_key) {
             tracing::info!(step = "cache_hit", elapsed_ms = t0.elapsed().as_millis() as u64);
-            let mut resp = response::build_response(cached, params.legacy, params.mode, is_csv);
+            let mut resp = response::build_response(cached, legacy, params.mode, is_csv);
             resp.headers_mut().insert(
                 HeaderName::from_static("x-data-source"),
                 HeaderValue::from_static("in-memory"),
@@ -185,25 +227,52 @@ pub async fn tickers(
     let start_time = params.start_date.as_deref().and_then(fetch::parse_date);
     let end_time = params.end_date.as_deref().and_then(fetch::parse_date_end);
 
-    let (result, source_tag, redis_meta) = match interval {
+    let opts = FetchOptions {
+        limit: Some(effective_limit),
+        start_time,
+        end_time,
+        extra_sources,
+        with_ma: params.ma,
+        use_ema: params.ema,
+        ma_periods: &ma_periods,
+        indicators: &indicators,
+        adjust: params.adjust,
+        snap: params.snap,
+        fx: None,
+    };
+
+    // currency=: bars are converted before indicators are computed
+    let conversion = match currency {
+        Some(target) => {
+            let sources = fetch::symbol_sources(source, extra_sources, &symbols);
+            let pairs: Vec<(&str, &str)> = sources.iter().map(|(symbol, src)| (symbol.as_str(), src.as_str())).collect();
+            match fetch::load_conversion(&state.pool, pairs, target, &interval, &opts).await {
+                Ok(c) => Some(c),
+                Err(e) => {
+                    tracing::error!("Failed to load FX rates: {e}");
+                    return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({ "error": "Failed to load FX rates" }))).into_response();
+                }
+            }
+        }
+        None => None,
+    };
+
+    let opts = FetchOptions { fx: conversion.as_ref(), ..opts };
+    let (mut result, source_tag, redis_meta) = match interval {
         NormalizedInterval::Native(db_interval) => {
-            fetch::fetch_native_tickers(
-                &state.pool, &state.redis_client, source, symbols,
-                db_interval, start_time, end_time,
-                Some(effective_limit), extra_sources, params.redis, params.ma, params.ema, !params.snap,
-            ).await
+            fetch::fetch_native_tickers(&state.pool, &state.redis_client, source, symbols, db_interval, &opts, params.redis).await
         }
         NormalizedInterval::Aggregated(agg) => {
-            fetch::fetch_aggregated_tickers(
-                &state.pool, &state.redis_client, source, symbols,
-                agg, start_time, end_time,
-                effective_limit, extra_sources, params.redis, params.ma, params.ema,
-            ).await
+            fetch::fetch_aggregated_tickers(&state.pool, &state.redis_client, source, symbols, agg, &opts, params.redis).await
         }
     };
 
     tracing::info!(step = "fetch_done", path = source_tag, tickers = result.len(), elapsed_ms = t0.elapsed().as_millis() as u64);
 
+    if let Some(fx) = &conversion {
+        fetch::label_currency(&mut result, fx);
+    }
+
     // Store in cache
     if params.cache {
         let mut guard = state.tickers_cache.write().await;
@@ -211,7 +280,7 @@ pub async fn tickers(
         tracing::info!(step = "cache_store", elapsed_ms = t0.elapsed().as_millis() as u64);
     }
 
-    let mut response = response::build_response(result, params.legacy, params.mode, is_csv);
+    let mut response = response::build_response(result, legacy, params.mode, is_csv);
     tracing::info!(step = "build_response", is_csv, elapsed_ms = t0.elapsed().as_millis() as u64);
     response.headers_mut().insert(
         HeaderName::from_static("x-data-source"),
@@ -239,8 +308,12 @@ async fn handle_mode_all(
     state: &Arc<AppState>,
     params: TickersQuery,
     interval: NormalizedInterval,
+    indicators: Vec<Indicator>,
+    ma_periods: Vec<usize>,
+    currency: Option<Currency>,
 ) -> Response {
     let t0 = std::time::Instant::now();
+    let legacy = params.legacy && !currency.is_some_and(|c| c.is_usd());
 
     // Early return for empty or blank explicit symbol list
     if let Some(ref syms) = params.symbol {
@@ -269,14 +342,14 @@ async fn handle_mode_all(
 
     // Build cache key before any DB call
     let cache_key_symbols = params.symbol.as_deref().unwrap_or(&[]);
-    let cache_key = fetch::build_cache_key(&params, &interval, cache_key_symbols, Some(effective_limit));
+    let cache_key = fetch::build_cache_key(&params, &interval, cache_key_symbols, Some(effective_limit), &ma_periods);
 
     // Check cache BEFORE any DB call
     if params.cache {
         let mut guard = state.tickers_cache.write().await;
         if let Some(cached) = guard.get(&cache_key) {
             tracing::info!(step = "cache_hit", elapsed_ms = t0.elapsed().as_millis() as u64);
-            return response::build_response(cached, params.legacy, params.mode, is_csv);
+            return response::build_response(cached, legacy, params.mode, is_csv);
         }
         drop(guard);
     }
@@ -290,39 +363,79 @@ async fn handle_mode_all(
     let sources: Vec<&str> = source_map.keys().map(|s| s.as_str()).collect();
     tracing::info!(step = "resolve_sources", sources = ?sources, tickers = source_map.values().map(|v| v.len()).sum::<usize>(), elapsed_ms = t0.elapsed().as_millis() as u64);
 
+    let start_time = params.start_date.as_deref().and_then(fetch::parse_date);
+    let end_time = params.end_date.as_deref().and_then(fetch::parse_date_end);
+
+    // currency=: rates are loaded once, then split per source
+    let conversion = match currency {
+        Some(target) => {
+            let pairs: Vec<(&str, &str)> =
+                source_map.iter().flat_map(|(src, syms)| syms.iter().map(move |s| (s.as_str(), src.as_str()))).collect();
+            let window = FetchOptions {
+                limit: Some(effective_limit), start_time, end_time, with_ma: params.ma, use_ema: params.ema,
+                ma_periods: &ma_periods, indicators: &indicators,
+                ..Default::default()
+            };
+            match fetch::load_conversion(&state.pool, pairs, target, &interval, &window).await {
+                Ok(c) => Some(c),
+                Err(e) => {
+                    tracing::error!("Failed to load FX rates: {e}");
+                    return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({ "error": "Failed to load FX rates" }))).into_response();
+                }
+            }
+        }
+        None => None,
+    };
+
     // Fetch per source in parallel using shared fetch functions
     let mut handles = Vec::new();
     let with_ma = params.ma;
     let use_ema = params.ema;
+    let adjust = params.adjust;
     for (source, syms) in &source_map {
         let pool = state.pool.clone();
         let redis_client = state.redis_client.clone();
         let syms = syms.clone();
         let source = source.clone();
         let limit = effective_limit;
-        let start_time = params.start_date.as_deref().and_then(fetch::parse_date);
-        let end_time = params.end_date.as_deref().and_then(fetch::parse_date_end);
+        let indicators = indicators.clone();
+        let ma_periods = ma_periods.clone();
+        let conversion = conversion.as_ref().map(|c| c.for_source(&source, &syms));
 
         match &interval {
             NormalizedInterval::Native(db_interval) => {
                 let db_interval = db_interval.to_string();
                 handles.push(tokio::spawn(async move {
-                    let (data, tag, _meta) = fetch::fetch_native_tickers(
-                        &pool, &redis_client, &source, syms,
-                        &db_interval, start_time, end_time,
-                        Some(limit), &[], true, with_ma, use_ema, true,
+                    let opts = FetchOptions {
+                        limit: Some(limit), start_time, end_time, with_ma, use_ema,
+                        ma_periods: &ma_periods, indicators: &indicators, adjust,
+                        fx: conversion.as_ref(),
+                        ..Default::default()
+                    };
+                    let (mut data, tag, _meta) = fetch::fetch_native_tickers(
+                        &pool, &redis_client, &source, syms, &db_interval, &opts, true,
                     ).await;
+                    if let Some(fx) = &conversion {
+                        fetch::label_currency(&mut data, fx);
+                    }
                     (source, data, tag)
                 }));
             }
             NormalizedInterval::Aggregated(agg) => {
                 let agg = *agg;
                 handles.push(tokio::spawn(async move {
-                    let (data, tag, _meta) = fetch::fetch_aggregated_tickers(
-                        &pool, &redis_client, &source, syms,
-                        agg, start_time, end_time,
-                        limit, &[], true, with_ma, use_ema,
+                    let opts = FetchOptions {
+                        limit: Some(limit), start_time, end_time, with_ma, use_ema,
+                        ma_periods: &ma_periods, indicators: &indicators, adjust,
+                        fx: conversion.as_ref(),
+                        ..Default::default()
+                    };
+                    let (mut data, tag, _meta) = fetch::fetch_aggregated_tickers(
+                        &pool, &redis_client, &source, syms, agg, &opts, true,
                     ).await;
+                    if let Some(fx) = &conversion {
+                        fetch::label_currency(&mut data, fx);
+                    }
                     (source, data, tag)
                 }));
             }
@@ -359,7 +472,7 @@ async fn handle_mode_all(
     let any_redis = source_tags.contains("redis");
     let source_tag = if merged.is_empty() { "empty" } else if all_redis { "redis" } else if any_redis { "mixed" } else { "postgres" };
 
-    let mut response = response::build_response(merged, params.legacy, params.mode, is_csv);
+    let mut response = response::build_response(merged, legacy, params.mode, is_csv);
     tracing::info!(step = "build_response", is_csv, elapsed_ms = t0.elapsed().as_millis() as u64);
     response.headers_mut().insert(
         HeaderName::from_static("x-data-source"),
@@ -462,48 +575,26 @@ pub async fn tickers_refresh(
 
 #[tracing::instrument]
 pub async fn tickers_group(Query(params): Query<GroupQuery>) -> Response {
-    let result: Result<BTreeMap<String, Vec<String>>, Box<dyn std::error::Error + Send + Sync>> = match params.mode {
+    let groups = match params.mode {
         Mode::Vn => data_loader::load_vn_groups(),
         Mode::Crypto => data_loader::load_crypto_groups(),
         Mode::Yahoo => data_loader::load_yahoo_groups(),
         Mode::All => data_loader::load_all_groups(),
     };
-
-    match result {
-        Ok(groups) => (StatusCode::OK, Json(groups)).into_response(),
-        Err(e) => {
-            tracing::error!("tickers_group error: {e}");
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(serde_json::json!({ "error": "Failed to load ticker groups" })),
-            )
-                .into_response()
-        }
-    }
+    (StatusCode::OK, Json(groups)).into_response()
 }
 
 // ── /tickers/name ──
 
 #[tracing::instrument]
 pub async fn tickers_name(Query(params): Query<GroupQuery>) -> Response {
-    let result: Result<BTreeMap<String, String>, Box<dyn std::error::Error + Send + Sync>> = match params.mode {
+    let names = match params.mode {
         Mode::Vn => data_loader::load_vn_names(),
         Mode::Crypto => data_loader::load_crypto_names(),
         Mode::Yahoo => data_loader::load_yahoo_names(),
         Mode::All => data_loader::load_all_names(),
     };
-
-    match result {
-        Ok(names) => (StatusCode::OK, Json(names)).into_response(),
-        Err(e) => {
-            tracing::error!("tickers_name error: {e}");
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(serde_json::json!({ "error": "Failed to load ticker names" })),
-            )
-                .into_response()
-        }
-    }
+    (StatusCode::OK, Json(names)).into_response()
 }
 
 // ── /tickers/info ──
@@ -3,6 +3,7 @@ use axum::response::{IntoResponse, Json, Response};
 use std::collections::BTreeMap;
 use std::fmt::Write;
 
+use crate::models::indicators::is_price_field;
 use crate::server::types::{Mode, StockDataResponse, is_vn_ticker};
 
 /// Map an OhlcvJoined row to a StockDataResponse.
@@ -38,6 +39,8 @@ pub(crate) fn map_ohlcv_to_response(
         close_changed: row.close_changed,
         volume_changed: row.volume_changed,
         total_money_changed: row.total_money_changed,
+        currency: None,
+        indicators: row.indicators,
     }
 }
 
@@ -74,6 +77,8 @@ pub(crate) fn map_aggregated_to_response(
         close_changed: row.close_changed,
         volume_changed: row.volume_changed,
         total_money_changed: row.total_money_changed,
+        currency: None,
+        indicators: row.indicators.clone(),
     }
 }
 
@@ -162,7 +167,19 @@ fn csv_response(data: &BTreeMap<String, Vec<StockDataResponse>>) -> Response {
     let row_count: usize = data.values().map(|v| v.len()).sum();
     let mut buf = String::with_capacity(200 + row_count * 180);
 
-    buf.push_str("symbol,time,open,high,low,close,volume,ma10,ma20,ma50,ma100,ma200,ma10_score,ma20_score,ma50_score,ma100_score,ma200_score,close_changed,volume_changed,total_money_changed\n");
+    // Extra `indicators=` fields become trailing columns, sorted by name.
+    let indicator_columns: std::collections::BTreeSet<&str> = data
+        .values()
+        .flatten()
+        .flat_map(|r| r.indicators.keys().map(String::as_str))
+        .collect();
+
+    buf.push_str("symbol,time,open,high,low,close,volume,ma10,ma20,ma50,ma100,ma200,ma10_score,ma20_score,ma50_score,ma100_score,ma200_score,close_changed,volume_changed,total_money_changed");
+    for col in &indicator_columns {
+        buf.push(',');
+        buf.push_str(col);
+    }
+    buf.push('\n');
 
     for (symbol, rows) in data {
         for r in rows {
@@ -206,6 +223,15 @@ fn csv_response(data: &BTreeMap<String, Vec<StockDataResponse>>) -> Response {
             write_opt_pct(&mut buf, r.volume_changed);
             buf.push(',');
             write_opt_pct(&mut buf, r.total_money_changed);
+            for col in &indicator_columns {
+                buf.push(',');
+                let v = r.indicators.get(*col).copied();
+                if is_price_field(col) {
+                    write_opt_price(&mut buf, v, d);
+                } else {
+                    write_opt_pct(&mut buf, v);
+                }
+            }
             buf.push('\n');
         }
     }
@@ -0,0 +1,40 @@
+use axum::http::{HeaderMap, StatusCode};
+use sha2::{Digest, Sha256};
+
+/// Bearer-token guard of one endpoint family. The accepted tokens are the
+/// comma-separated list in `env_var`; the endpoints are disabled while it is
+/// unset or empty.
+pub struct BearerAuth {
+    pub env_var: &'static str,
+    /// Route prefix used in logs, e.g. "/admin".
+    pub route: &'static str,
+    /// Message returned while the endpoints are disabled.
+    pub disabled: &'static str,
+}
+
+impl BearerAuth {
+    /// Check the `Authorization: Bearer` header. Returns the owner key of the
+    /// matched token (its SHA-256, hex), for endpoints that scope data per token.
+    pub fn verify(&self, headers: &HeaderMap) -> Result<String, (StatusCode, &'static str)> {
+        let raw = std::env::var(self.env_var).unwrap_or_default();
+        let valid_tokens: Vec<&str> = raw.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()).collect();
+
+        if valid_tokens.is_empty() {
+            tracing::warn!("{} not set — {} endpoints disabled", self.env_var, self.route);
+            return Err((StatusCode::FORBIDDEN, self.disabled));
+        }
+
+        let provided = headers
+            .get("authorization")
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("");
+
+        match valid_tokens.iter().find(|token| provided == format!("Bearer {token}")) {
+            Some(token) => Ok(hex::encode(Sha256::digest(token.as_bytes()))),
+            None => {
+                tracing::warn!("{} auth failed", self.route);
+                Err((StatusCode::UNAUTHORIZED, "Invalid or missing authorization token."))
+            }
+        }
+    }
+}
@@ -1,10 +1,16 @@
+mod admin;
+mod alerts;
 mod api;
+mod auth;
 mod cache;
+mod portfolios;
+mod sse;
 mod sync;
 pub mod types;
 pub mod analysis;
 
 pub mod redis_reader;
+pub mod ws;
 
 use sqlx::PgPool;
 use std::sync::Arc;
@@ -55,6 +61,8 @@ pub struct AppState {
     pub redis_client: Option<crate::redis::RedisClient>,
     /// Holds the Redis connection handle to keep it alive for automatic reconnection.
     pub _redis_handle: Option<ConnectHandle>,
+    /// Live candle fan-out for `/ws`. None when Redis is not configured.
+    pub live: Option<Arc<ws::LiveHub>>,
 }
 
 /// Middleware to add security headers to all responses
@@ -109,7 +117,7 @@ async fn add_cache_headers(request: Request, next: Next) -> Response {
 }
 
 #[allow(deprecated)]
-pub fn create_app(pool: PgPool, redis_client: Option<crate::redis::RedisClient>, redis_handle: Option<ConnectHandle>) -> (axum::Router, Arc<tokio::sync::RwLock<HealthSnapshot>>) {
+pub fn create_app(pool: PgPool, redis_client: Option<crate::redis::RedisClient>, redis_handle: Option<ConnectHandle>, live: Option<Arc<ws::LiveHub>>) -> (axum::Router, Arc<tokio::sync::RwLock<HealthSnapshot>>) {
     let tickers_cache = cache::TickersCache::new(
         crate::constants::api::CACHE_MAX_ENTRIES,
         Duration::from_secs(crate::constants::api::CACHE_TTL_SECS),
@@ -130,6 +138,7 @@ pub fn create_app(pool: PgPool, redis_client: Option<crate::redis::RedisClient>,
         health_snapshot: health_snapshot.clone(),
         redis_client,
         _redis_handle: redis_handle,
+        live,
     });
 
     // Main routes with 1MB body limit
@@ -141,9 +150,22 @@ pub fn create_app(pool: PgPool, redis_client: Option<crate::redis::RedisClient>,
         .route("/tickers/name", axum::routing::get(api::tickers_name))
         .route("/tickers/info", axum::routing::get(api::tickers_info))
         .route("/tickers/refresh", axum::routing::post(api::tickers_refresh))
+        .route("/tickers/stream", axum::routing::get(sse::tickers_stream))
+        .route("/ws", axum::routing::get(ws::ws_handler))
         .route("/sync/{key}", axum::routing::get(sync::sync_get))
         .route("/sync/{key}", axum::routing::post(sync::sync_post))
+        .route("/alerts", axum::routing::get(alerts::list).post(alerts::create))
+        .route("/alerts/{id}", axum::routing::get(alerts::get).patch(alerts::update).delete(alerts::delete))
+        .route("/portfolios", axum::routing::get(portfolios::list).post(portfolios::create))
+        .route("/portfolios/{id}", axum::routing::get(portfolios::get).delete(portfolios::delete))
+        .route("/portfolios/{id}/nav", axum::routing::get(portfolios::nav))
+        .route(
+            "/portfolios/{id}/transactions",
+            axum::routing::get(portfolios::list_transactions).post(portfolios::add_transaction),
+        )
+        .route("/portfolios/{id}/transactions/{tx_id}", axum::routing::delete(portfolios::delete_transaction))
         .nest("/analysis", analysis_routes())
+        .nest("/admin", admin_routes())
         .fallback(api::not_found_handler)
         .layer(RequestBodyLimitLayer::new(5 * 1024 * 1024));
 
@@ -250,7 +272,7 @@ fn build_cors_layer() -> CorsLayer {
     tracing::info!("CORS: allowed origins = {:?}", origins_str);
     CorsLayer::new()
         .allow_origin(origins)
-        .allow_methods([Method::GET, Method::POST, Method::DELETE, Method::OPTIONS])
+        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::PATCH, Method::DELETE, Method::OPTIONS])
         .allow_headers([
             HeaderName::from_static("content-type"),
             HeaderName::from_static("authorization"),
@@ -264,4 +286,28 @@ fn analysis_routes() -> axum::Router<Arc<AppState>> {
         .route("/ma-scores-by-sector", axum::routing::get(analysis::ma_scores_by_sector_handler))
         .route("/volume-profile", axum::routing::get(analysis::volume_profile_handler))
         .route("/rrg", axum::routing::get(analysis::rrg_handler))
+        .route("/correlation", axum::routing::get(analysis::correlation_handler))
+        .route("/breadth", axum::routing::get(analysis::breadth_handler))
+        .route("/screen", axum::routing::post(analysis::screen_handler))
+        .route("/backtest", axum::routing::post(analysis::backtest_handler))
+}
+
+fn admin_routes() -> axum::Router<Arc<AppState>> {
+    axum::Router::new()
+        .route("/discrepancies", axum::routing::get(admin::discrepancies))
+        .route("/gaps", axum::routing::get(admin::gaps))
+        .route("/tickers", axum::routing::get(admin::list_tickers))
+        .route(
+            "/tickers/{source}/{ticker}",
+            axum::routing::get(admin::get_ticker).put(admin::put_ticker).delete(admin::delete_ticker),
+        )
+        .route("/lifecycle", axum::routing::get(admin::list_lifecycle).post(admin::record_lifecycle))
+        .route("/lifecycle/{id}", axum::routing::delete(admin::delete_lifecycle))
+        .route("/membership", axum::routing::get(admin::list_membership).post(admin::create_membership))
+        .route("/membership/{id}", axum::routing::put(admin::put_membership).delete(admin::delete_membership))
+        .route("/indices", axum::routing::get(admin::list_indices))
+        .route(
+            "/indices/{name}",
+            axum::routing::get(admin::get_index).put(admin::put_index).delete(admin::delete_index),
+        )
 }
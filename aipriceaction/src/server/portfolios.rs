@@ -0,0 +1,337 @@
+use axum::extract::{Path, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Json, Response};
+use axum_extra::extract::Query as AxumQuery;
+use chrono::NaiveDate;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use super::AppState;
+use super::auth::BearerAuth;
+use super::analysis::{get_ticker_sector, load_ticker_groups, source_groups};
+use crate::constants::portfolio as cfg;
+use crate::models::portfolio::{Currency, Transaction};
+use crate::queries::portfolios::{self, Portfolio};
+use crate::services::portfolio::{Ledger, PriceBook, nav_history, validate_transaction};
+
+// ── Request types ──
+
+#[derive(Debug, Deserialize)]
+pub struct CreatePortfolioBody {
+    pub name: String,
+    /// VND (default) or USD
+    pub base_currency: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ValuationQuery {
+    /// Valuation date (YYYY-MM-DD, default: today)
+    pub date: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NavQuery {
+    /// First day (YYYY-MM-DD, default: first transaction)
+    pub start_date: Option<String>,
+    /// Last day (YYYY-MM-DD, default: today)
+    pub end_date: Option<String>,
+}
+
+// ── Helpers ──
+
+/// `PORTFOLIO_TOKEN` (comma-separated list). Each token owns its
+/// portfolios; the owner key is the token's SHA-256.
+const PORTFOLIO_AUTH: BearerAuth = BearerAuth {
+    env_var: "PORTFOLIO_TOKEN",
+    route: "/portfolios",
+    disabled: "Portfolio endpoints are disabled. Set PORTFOLIO_TOKEN environment variable.",
+};
+
+fn error_response(status: StatusCode, message: &str) -> Response {
+    (status, Json(serde_json::json!({ "error": message }))).into_response()
+}
+
+fn db_error(route: &str, e: sqlx::Error) -> Response {
+    tracing::error!("{route} failed: {e}");
+    error_response(StatusCode::INTERNAL_SERVER_ERROR, "Database error")
+}
+
+fn parse_day(value: Option<&str>, name: &str) -> Result<Option<NaiveDate>, String> {
+    value
+        .map(|s| NaiveDate::parse_from_str(s, "%Y-%m-%d").map_err(|_| format!("Invalid {name}, expected YYYY-MM-DD")))
+        .transpose()
+}
+
+/// Authenticate and load the caller's portfolio `id` with its ledger.
+async fn load_owned(
+    state: &AppState,
+    headers: &HeaderMap,
+    id: i64,
+    route: &str,
+) -> Result<(Portfolio, Vec<Transaction>), (StatusCode, &'static str)> {
+    let owner = PORTFOLIO_AUTH.verify(headers)?;
+    let db_fail = |e: sqlx::Error| {
+        tracing::error!("{route} failed: {e}");
+        (StatusCode::INTERNAL_SERVER_ERROR, "Database error")
+    };
+    let portfolio = portfolios::get_portfolio(&state.pool, &owner, id)
+        .await
+        .map_err(db_fail)?
+        .ok_or((StatusCode::NOT_FOUND, "Portfolio not found"))?;
+    let transactions = portfolios::list_transactions(&state.pool, id).await.map_err(db_fail)?;
+    Ok((portfolio, transactions))
+}
+
+/// Allocation bucket of every instrument in the ledger: its sector group,
+/// `GOLD` for SJC bars.
+fn instrument_sectors(transactions: &[Transaction]) -> HashMap<(String, String), String> {
+    let ticker_groups = load_ticker_groups();
+    let mut groups_by_source: BTreeMap<&str, BTreeMap<String, Vec<String>>> = BTreeMap::new();
+    let mut sectors = HashMap::new();
+    for tx in transactions {
+        let (Some(source), Some(ticker)) = (tx.source.as_deref(), tx.ticker.as_deref()) else { continue };
+        let sector = if source == cfg::GOLD_SOURCE {
+            Some("GOLD".to_string())
+        } else {
+            let groups = groups_by_source.entry(source).or_insert_with(|| source_groups(source, &ticker_groups));
+            get_ticker_sector(ticker, &*groups)
+        };
+        if let Some(sector) = sector {
+            sectors.insert((source.to_string(), ticker.to_string()), sector);
+        }
+    }
+    sectors
+}
+
+// ── GET /portfolios ──
+
+pub async fn list(State(state): State<Arc<AppState>>, headers: HeaderMap) -> Response {
+    let owner = match PORTFOLIO_AUTH.verify(&headers) {
+        Ok(o) => o,
+        Err((status, message)) => return error_response(status, message),
+    };
+    match portfolios::list_portfolios(&state.pool, &owner).await {
+        Ok(list) => (StatusCode::OK, Json(serde_json::json!({ "count": list.len(), "portfolios": list }))).into_response(),
+        Err(e) => db_error("GET /portfolios", e),
+    }
+}
+
+// ── POST /portfolios ──
+
+pub async fn create(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    axum::Json(body): axum::Json<CreatePortfolioBody>,
+) -> Response {
+    let owner = match PORTFOLIO_AUTH.verify(&headers) {
+        Ok(o) => o,
+        Err((status, message)) => return error_response(status, message),
+    };
+    let name = body.name.trim();
+    if name.is_empty() || name.len() > 100 {
+        return error_response(StatusCode::BAD_REQUEST, "name must be 1-100 characters");
+    }
+    let base = match body.base_currency.as_deref().map(Currency::from_db) {
+        None => Currency::Vnd,
+        Some(Some(c @ (Currency::Vnd | Currency::Usd))) => c,
+        Some(_) => return error_response(StatusCode::BAD_REQUEST, "base_currency must be VND or USD"),
+    };
+
+    match portfolios::count_portfolios(&state.pool, &owner).await {
+        Ok(n) if n >= cfg::MAX_PORTFOLIOS_PER_OWNER => {
+            return error_response(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                &format!("At most {} portfolios per token", cfg::MAX_PORTFOLIOS_PER_OWNER),
+            );
+        }
+        Ok(_) => {}
+        Err(e) => return db_error("POST /portfolios", e),
+    }
+
+    match portfolios::create_portfolio(&state.pool, &owner, name, base).await {
+        Ok(p) => (StatusCode::CREATED, Json(p)).into_response(),
+        Err(e) if e.as_database_error().is_some_and(|d| d.is_unique_violation()) => {
+            error_response(StatusCode::CONFLICT, "A portfolio with this name already exists")
+        }
+        Err(e) => db_error("POST /portfolios", e),
+    }
+}
+
+// ── GET /portfolios/{id} ──
+
+/// Holdings, cash balances, P&L and sector allocation as of `date`.
+pub async fn get(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+    headers: HeaderMap,
+    AxumQuery(params): AxumQuery<ValuationQuery>,
+) -> Response {
+    let date = match parse_day(params.date.as_deref(), "date") {
+        Ok(d) => d.unwrap_or_else(|| chrono::Utc::now().date_naive()),
+        Err(e) => return error_response(StatusCode::BAD_REQUEST, &e),
+    };
+    let (portfolio, transactions) = match load_owned(&state, &headers, id, "GET /portfolios/{id}").await {
+        Ok(loaded) => loaded,
+        Err((status, message)) => return error_response(status, message),
+    };
+
+    let start = transactions.first().map_or(date, |tx| tx.trade_date.min(date));
+    let prices = match PriceBook::load(&state.pool, &transactions, portfolio.base(), start, date).await {
+        Ok(p) => p,
+        Err(e) => return db_error("GET /portfolios/{id}", e),
+    };
+    let ledger = match Ledger::replay(portfolio.base(), &transactions, date, &prices) {
+        Ok(l) => l,
+        Err(e) => return error_response(StatusCode::UNPROCESSABLE_ENTITY, &e),
+    };
+    let valuation = ledger.value(date, &prices, &instrument_sectors(&transactions));
+    (StatusCode::OK, Json(serde_json::json!({ "portfolio": portfolio, "valuation": valuation }))).into_response()
+}
+
+// ── DELETE /portfolios/{id} ──
+
+pub async fn delete(State(state): State<Arc<AppState>>, Path(id): Path<i64>, headers: HeaderMap) -> Response {
+    let owner = match PORTFOLIO_AUTH.verify(&headers) {
+        Ok(o) => o,
+        Err((status, message)) => return error_response(status, message),
+    };
+    match portfolios::delete_portfolio(&state.pool, &owner, id).await {
+        Ok(true) => StatusCode::NO_CONTENT.into_response(),
+        Ok(false) => error_response(StatusCode::NOT_FOUND, "Portfolio not found"),
+        Err(e) => db_error("DELETE /portfolios/{id}", e),
+    }
+}
+
+// ── GET /portfolios/{id}/nav ──
+
+pub async fn nav(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+    headers: HeaderMap,
+    AxumQuery(params): AxumQuery<NavQuery>,
+) -> Response {
+    let (start, end) = match (
+        parse_day(params.start_date.as_deref(), "start_date"),
+        parse_day(params.end_date.as_deref(), "end_date"),
+    ) {
+        (Ok(s), Ok(e)) => (s, e.unwrap_or_else(|| chrono::Utc::now().date_naive())),
+        (Err(e), _) | (_, Err(e)) => return error_response(StatusCode::BAD_REQUEST, &e),
+    };
+    let (portfolio, transactions) = match load_owned(&state, &headers, id, "GET /portfolios/{id}/nav").await {
+        Ok(loaded) => loaded,
+        Err((status, message)) => return error_response(status, message),
+    };
+    let Some(start) = start.or_else(|| transactions.first().map(|tx| tx.trade_date)) else {
+        return (StatusCode::OK, Json(serde_json::json!({ "portfolio": portfolio, "nav": [] }))).into_response();
+    };
+    if end < start {
+        return error_response(StatusCode::BAD_REQUEST, "end_date must not be before start_date");
+    }
+    if (end - start).num_days() >= cfg::MAX_NAV_DAYS {
+        return error_response(
+            StatusCode::BAD_REQUEST,
+            &format!("NAV history is limited to {} days", cfg::MAX_NAV_DAYS),
+        );
+    }
+
+    let first_trade = transactions.first().map_or(start, |tx| tx.trade_date.min(start));
+    let prices = match PriceBook::load(&state.pool, &transactions, portfolio.base(), first_trade, end).await {
+        Ok(p) => p,
+        Err(e) => return db_error("GET /portfolios/{id}/nav", e),
+    };
+    match nav_history(portfolio.base(), &transactions, start, end, &prices) {
+        Ok(points) => (StatusCode::OK, Json(serde_json::json!({ "portfolio": portfolio, "nav": points }))).into_response(),
+        Err(e) => error_response(StatusCode::UNPROCESSABLE_ENTITY, &e),
+    }
+}
+
+// ── GET /portfolios/{id}/transactions ──
+
+pub async fn list_transactions(State(state): State<Arc<AppState>>, Path(id): Path<i64>, headers: HeaderMap) -> Response {
+    match load_owned(&state, &headers, id, "GET /portfolios/{id}/transactions").await {
+        Ok((_, transactions)) => (
+            StatusCode::OK,
+            Json(serde_json::json!({ "count": transactions.len(), "transactions": transactions })),
+        )
+            .into_response(),
+        Err((status, message)) => error_response(status, message),
+    }
+}
+
+// ── POST /portfolios/{id}/transactions ──
+
+pub async fn add_transaction(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+    headers: HeaderMap,
+    axum::Json(mut tx): axum::Json<Transaction>,
+) -> Response {
+    let route = "POST /portfolios/{id}/transactions";
+    let (_, mut transactions) = match load_owned(&state, &headers, id, route).await {
+        Ok(loaded) => loaded,
+        Err((status, message)) => return error_response(status, message),
+    };
+    if let Err(e) = validate_transaction(&mut tx) {
+        return error_response(StatusCode::BAD_REQUEST, &e);
+    }
+    if transactions.len() as i64 >= cfg::MAX_TRANSACTIONS {
+        return error_response(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            &format!("At most {} transactions per portfolio", cfg::MAX_TRANSACTIONS),
+        );
+    }
+
+    // Replay with the new entry in place (same day: after existing ones) to
+    // catch overselling; quantities depend on splits but not on prices
+    tx.id = i64::MAX;
+    let at = transactions.partition_point(|t| t.trade_date <= tx.trade_date);
+    transactions.insert(at, tx.clone());
+    let splits = match PriceBook::splits_only(&state.pool, &transactions).await {
+        Ok(book) => book,
+        Err(e) => return db_error(route, e),
+    };
+    if let Err(e) = Ledger::replay(Currency::Vnd, &transactions, NaiveDate::MAX, &splits) {
+        return error_response(StatusCode::UNPROCESSABLE_ENTITY, &e);
+    }
+
+    match portfolios::insert_transaction(&state.pool, id, &tx).await {
+        Ok(new_id) => {
+            tx.id = new_id;
+            (StatusCode::CREATED, Json(tx)).into_response()
+        }
+        Err(e) => db_error(route, e),
+    }
+}
+
+// ── DELETE /portfolios/{id}/transactions/{tx_id} ──
+
+pub async fn delete_transaction(
+    State(state): State<Arc<AppState>>,
+    Path((id, tx_id)): Path<(i64, i64)>,
+    headers: HeaderMap,
+) -> Response {
+    let route = "DELETE /portfolios/{id}/transactions/{tx_id}";
+    let (_, mut transactions) = match load_owned(&state, &headers, id, route).await {
+        Ok(loaded) => loaded,
+        Err((status, message)) => return error_response(status, message),
+    };
+    let Some(at) = transactions.iter().position(|t| t.id == tx_id) else {
+        return error_response(StatusCode::NOT_FOUND, "Transaction not found");
+    };
+    // Removing a buy must not leave a later sell uncovered
+    transactions.remove(at);
+    let splits = match PriceBook::splits_only(&state.pool, &transactions).await {
+        Ok(book) => book,
+        Err(e) => return db_error(route, e),
+    };
+    if let Err(e) = Ledger::replay(Currency::Vnd, &transactions, NaiveDate::MAX, &splits) {
+        return error_response(StatusCode::UNPROCESSABLE_ENTITY, &e);
+    }
+
+    match portfolios::delete_transaction(&state.pool, id, tx_id).await {
+        Ok(true) => StatusCode::NO_CONTENT.into_response(),
+        Ok(false) => error_response(StatusCode::NOT_FOUND, "Transaction not found"),
+        Err(e) => db_error(route, e),
+    }
+}
@@ -0,0 +1,182 @@
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use axum_extra::extract::Query;
+use serde_json::json;
+use tokio::sync::{broadcast, mpsc};
+
+use crate::constants::live as cfg;
+use crate::server::AppState;
+use crate::server::types::{NormalizedInterval, StreamQuery};
+use crate::server::ws::{self, CandleKey, LiveCandle};
+
+/// GET /tickers/stream — Server-Sent Events fallback for `/ws`.
+///
+/// Emits one `candle` event (data: `StockDataResponse`) per ZSET write for the
+/// requested symbols. The event id is a resume cursor: the ZSET score (bar time
+/// in epoch ms) of the last candle sent for each subscribed key, comma-separated
+/// in key order (see [`format_cursors`]). A client reconnecting with
+/// `Last-Event-ID` first receives, per key, every stored candle with score >=
+/// that key's cursor, so the bar it last saw is refreshed too.
+pub async fn tickers_stream(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(params): Query<StreamQuery>,
+) -> Response {
+    let Some(hub) = state.live.clone() else {
+        return error(StatusCode::SERVICE_UNAVAILABLE, "Live streaming requires Redis (REDIS_URL not set)".to_string());
+    };
+
+    let symbols: Vec<String> = params.symbol.into_iter().filter(|s| !s.is_empty()).collect();
+    if symbols.is_empty() {
+        return error(StatusCode::BAD_REQUEST, "symbol is required".to_string());
+    }
+    if symbols.len() > cfg::MAX_SUBSCRIPTIONS {
+        return error(StatusCode::BAD_REQUEST, format!("Too many symbols (max {})", cfg::MAX_SUBSCRIPTIONS));
+    }
+
+    let raw_interval = params.interval.as_deref().unwrap_or("1D");
+    let interval = match NormalizedInterval::parse(raw_interval) {
+        Some(NormalizedInterval::Native(i)) => i,
+        _ => {
+            return error(
+                StatusCode::BAD_REQUEST,
+                format!("Invalid interval '{raw_interval}'. Live streaming supports 1D, 1H, 1m"),
+            );
+        }
+    };
+
+    let keys: Vec<CandleKey> = symbols.iter().flat_map(|s| ws::keys_for(params.mode, s, interval)).collect();
+    let cursors = headers
+        .get("last-event-id")
+        .and_then(|v| v.to_str().ok())
+        .map(|v| parse_cursors(v, keys.len()));
+
+    // A task per connection feeds the response stream; it ends (and releases
+    // its interest) once the client disconnects.
+    let (events_tx, events_rx) = mpsc::channel::<Event>(64);
+    tokio::spawn(async move {
+        // Register before replaying so writes during the replay are not lost.
+        let mut rx = hub.receiver();
+        hub.watch(&keys);
+        stream_candles(&hub, &keys, cursors, &mut rx, &events_tx).await;
+        hub.unwatch(&keys);
+    });
+
+    let stream = futures::stream::unfold(events_rx, |mut rx| async move {
+        rx.recv().await.map(|event| (Ok::<_, Infallible>(event), rx))
+    });
+    Sse::new(stream)
+        .keep_alive(KeepAlive::new().interval(Duration::from_secs(cfg::PING_SECS)))
+        .into_response()
+}
+
+/// Replay missed candles (or the current one) per key, then forward live
+/// candles until the client goes away. `resume` holds the per-key cursors of
+/// a reconnect.
+async fn stream_candles(
+    hub: &ws::LiveHub,
+    keys: &[CandleKey],
+    resume: Option<Vec<Option<i64>>>,
+    rx: &mut broadcast::Receiver<Arc<LiveCandle>>,
+    events: &mpsc::Sender<Event>,
+) {
+    let mut cursors = resume.clone().unwrap_or_else(|| vec![None; keys.len()]);
+    for (i, key) in keys.iter().enumerate() {
+        let initial = match resume.as_ref().and_then(|r| r[i]) {
+            Some(since) => hub.load_since(key, since).await,
+            None => hub.load_candle(key).await.into_iter().collect(),
+        };
+        for candle in &initial {
+            advance(&mut cursors[i], candle.score);
+            if events.send(candle_event(candle, &cursors)).await.is_err() {
+                return;
+            }
+        }
+    }
+
+    loop {
+        tokio::select! {
+            candle = rx.recv() => match candle {
+                Ok(candle) => {
+                    let Some(i) = keys.iter().position(|k| *k == candle.key) else { continue };
+                    advance(&mut cursors[i], candle.score);
+                    if events.send(candle_event(&candle, &cursors)).await.is_err() {
+                        return;
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(n)) => {
+                    tracing::debug!(skipped = n, "sse client lagged, dropping candles");
+                }
+                Err(broadcast::error::RecvError::Closed) => return,
+            },
+            _ = events.closed() => return,
+        }
+    }
+}
+
+/// Move a key's cursor forward to `score` (a refreshed older bar keeps it).
+fn advance(cursor: &mut Option<i64>, score: i64) {
+    *cursor = Some(cursor.map_or(score, |c| c.max(score)));
+}
+
+/// Event id for `cursors`: one score per key, blank for keys with nothing
+/// sent yet (e.g. `1760572800000,,1760576400000`).
+fn format_cursors(cursors: &[Option<i64>]) -> String {
+    cursors.iter().map(|c| c.map(|s| s.to_string()).unwrap_or_default()).collect::<Vec<_>>().join(",")
+}
+
+/// Per-key cursors of a `Last-Event-ID` for `keys` keys. A single score
+/// applies to every key; unparsable or missing positions resume from the
+/// current candle.
+fn parse_cursors(raw: &str, keys: usize) -> Vec<Option<i64>> {
+    let parts: Vec<Option<i64>> = raw.split(',').map(|p| p.trim().parse().ok()).collect();
+    if let [single] = parts[..] {
+        return vec![single; keys];
+    }
+    (0..keys).map(|i| parts.get(i).copied().flatten()).collect()
+}
+
+fn candle_event(candle: &LiveCandle, cursors: &[Option<i64>]) -> Event {
+    Event::default()
+        .event("candle")
+        .id(format_cursors(cursors))
+        .json_data(&candle.data)
+        .unwrap_or_else(|_| Event::default().comment("serialization failed"))
+}
+
+fn error(status: StatusCode, message: String) -> Response {
+    (status, Json(json!({ "error": message }))).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursors_round_trip_per_key() {
+        let mut cursors = vec![None; 3];
+        advance(&mut cursors[0], 200);
+        advance(&mut cursors[2], 500);
+        // A refreshed older bar does not move the cursor back
+        advance(&mut cursors[0], 100);
+        let id = format_cursors(&cursors);
+        assert_eq!(id, "200,,500");
+        assert_eq!(parse_cursors(&id, 3), vec![Some(200), None, Some(500)]);
+    }
+
+    #[test]
+    fn test_parse_cursors_single_and_malformed() {
+        // A plain score applies to every key
+        assert_eq!(parse_cursors("300", 2), vec![Some(300), Some(300)]);
+        // Fewer positions than keys, or junk, resume from the current candle
+        assert_eq!(parse_cursors("1,x", 3), vec![Some(1), None, None]);
+        assert_eq!(parse_cursors("abc", 2), vec![None, None]);
+    }
+}
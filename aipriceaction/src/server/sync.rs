@@ -8,6 +8,7 @@ use std::sync::Arc;
 use uuid::Uuid;
 
 use super::AppState;
+use super::auth::BearerAuth;
 
 // ── Request / Response types ──
 
@@ -41,52 +42,19 @@ struct SyncRow {
 
 // ── Helpers ──
 
+/// `SYNC_TOKEN` (comma-separated list) guards the /sync endpoints.
+const SYNC_AUTH: BearerAuth = BearerAuth {
+    env_var: "SYNC_TOKEN",
+    route: "/sync",
+    disabled: "Sync endpoint is disabled. Set SYNC_TOKEN environment variable.",
+};
+
 fn hash_secret(plaintext: &str) -> String {
     let mut hasher = Sha256::new();
     hasher.update(plaintext.as_bytes());
     hex::encode(hasher.finalize())
 }
 
-fn verify_sync_token(headers: &HeaderMap) -> Result<(), Response> {
-    let raw = match std::env::var("SYNC_TOKEN") {
-        Ok(v) => v,
-        Err(_) => {
-            tracing::warn!("SYNC_TOKEN not set — /sync endpoint disabled");
-            return Err(error_response(
-                StatusCode::FORBIDDEN,
-                "Sync endpoint is disabled. Set SYNC_TOKEN environment variable.",
-            ));
-        }
-    };
-
-    let valid_tokens: Vec<&str> = raw.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()).collect();
-
-    if valid_tokens.is_empty() {
-        tracing::warn!("SYNC_TOKEN is empty — /sync endpoint disabled");
-        return Err(error_response(
-            StatusCode::FORBIDDEN,
-            "Sync endpoint is disabled. Set SYNC_TOKEN environment variable.",
-        ));
-    }
-
-    let provided = headers
-        .get("authorization")
-        .and_then(|v| v.to_str().ok())
-        .unwrap_or("");
-
-    let matched = valid_tokens.iter().any(|token| provided == format!("Bearer {token}"));
-
-    if !matched {
-        tracing::warn!("/sync auth failed");
-        return Err(error_response(
-            StatusCode::UNAUTHORIZED,
-            "Invalid or missing authorization token.",
-        ));
-    }
-
-    Ok(())
-}
-
 fn error_response(status: StatusCode, message: &str) -> Response {
     (
         status,
@@ -116,8 +84,8 @@ pub async fn sync_post(
     headers: HeaderMap,
     axum::Json(body): axum::Json<SyncPostBody>,
 ) -> Response {
-    if let Err(e) = verify_sync_token(&headers) {
-        return e;
+    if let Err((status, message)) = SYNC_AUTH.verify(&headers) {
+        return error_response(status, message);
     }
 
     let uuid = match Uuid::parse_str(&key) {
@@ -177,8 +145,8 @@ pub async fn sync_get(
     headers: HeaderMap,
     AxumQuery(query): AxumQuery<SyncGetQuery>,
 ) -> Response {
-    if let Err(e) = verify_sync_token(&headers) {
-        return e;
+    if let Err((status, message)) = SYNC_AUTH.verify(&headers) {
+        return error_response(status, message);
     }
 
     let uuid = match Uuid::parse_str(&key) {
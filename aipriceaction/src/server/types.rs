@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 use crate::models::aggregated_interval::AggregatedInterval;
+use crate::models::corporate_action::Adjustment;
 
 /// Data source mode matching the parent project.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
@@ -63,6 +65,19 @@ pub struct TickersQuery {
     /// true = use pre-computed snapshot cache (default), false = skip snapshots.
     #[serde(default = "default_true")]
     pub snap: bool,
+    /// raw (default) = stored prices; split / total = back-adjusted for recorded
+    /// corporate actions. Adjusted requests bypass Redis and read from PG.
+    #[serde(default)]
+    pub adjust: Adjustment,
+    /// Extra technical indicators, comma-separated (e.g. `rsi14,macd,bb20`).
+    /// See `models::indicators::Indicator` for the supported tokens.
+    pub indicators: Option<String>,
+    /// MA periods, comma-separated (e.g. `5,13,34,89`). Defaults to
+    /// `10,20,50,100,200`; other periods are returned as `ma{p}` / `ma{p}_score`.
+    pub ma_periods: Option<String>,
+    /// USD or VND: convert prices, MAs, price indicators and
+    /// `total_money_changed` bar by bar with the FX close of the bar's time.
+    pub currency: Option<String>,
 }
 
 fn default_format() -> String {
@@ -81,6 +96,17 @@ fn default_true() -> bool {
     true
 }
 
+/// Query parameters for GET /tickers/stream
+#[derive(Debug, Deserialize)]
+pub struct StreamQuery {
+    #[serde(default)]
+    pub symbol: Vec<String>,
+    /// Native intervals only (1D, 1H, 1m). Defaults to 1D.
+    pub interval: Option<String>,
+    #[serde(default)]
+    pub mode: Mode,
+}
+
 /// Query parameters for GET /tickers/group
 #[derive(Debug, Deserialize)]
 pub struct GroupQuery {
@@ -170,9 +196,18 @@ pub struct StockDataResponse {
     pub volume_changed: Option<f64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub total_money_changed: Option<f64>,
+    /// Currency of the price fields; only set for `currency=` requests, and
+    /// left out for series that are not prices (indices, yields, FX rates).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub currency: Option<String>,
+    /// Fields from `indicators=` (e.g. `rsi14`, `bb20_upper`), flattened into the row.
+    #[serde(flatten, default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub indicators: BTreeMap<String, f64>,
 }
 
-/// Whether a ticker is an index (no legacy price scaling).
+/// Whether a ticker is an index, provider or custom (no legacy price scaling).
 pub fn is_index_ticker(ticker: &str) -> bool {
-    crate::constants::vci_worker::INDEX_TICKERS.contains(&ticker.to_uppercase().as_str())
+    let ticker = ticker.to_uppercase();
+    crate::constants::vci_worker::INDEX_TICKERS.contains(&ticker.as_str())
+        || crate::services::metadata::catalog().get(crate::models::custom_index::SOURCE, &ticker).is_some()
 }
@@ -0,0 +1,408 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use axum::extract::State;
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use fred::clients::SubscriberClient;
+use fred::prelude::*;
+use fred::types::ConnectHandle;
+use serde::Deserialize;
+use serde_json::json;
+use tokio::sync::broadcast;
+
+use crate::constants::{api, live as cfg, redis_ts};
+use crate::redis::RedisClient;
+use crate::server::AppState;
+use crate::server::types::{Mode, NormalizedInterval, StockDataResponse};
+
+// ---------------------------------------------------------------------------
+// Hub
+// ---------------------------------------------------------------------------
+
+/// One ZSET: `ohlcv:{source}:{ticker}:{interval}`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+pub(crate) struct CandleKey {
+    pub source: String,
+    pub ticker: String,
+    pub interval: String,
+}
+
+/// Sources whose ZSETs feed a `mode`, matching `/tickers` mode semantics.
+pub(crate) fn sources_for(mode: Mode) -> Vec<&'static str> {
+    match mode {
+        Mode::Vn => vec!["vn"],
+        Mode::Crypto => vec!["crypto"],
+        Mode::Yahoo => [&["yahoo"][..], crate::constants::MERGE_WITH_YAHOO].concat(),
+        Mode::All => [&["vn", "crypto", "yahoo"][..], crate::constants::MERGE_WITH_YAHOO].concat(),
+    }
+}
+
+/// Every ZSET that can hold `symbol` at `interval` under `mode`.
+pub(crate) fn keys_for(mode: Mode, symbol: &str, interval: &str) -> Vec<CandleKey> {
+    sources_for(mode)
+        .into_iter()
+        .map(|source| CandleKey {
+            source: source.to_string(),
+            ticker: symbol.to_string(),
+            interval: interval.to_string(),
+        })
+        .collect()
+}
+
+/// One candle from a ZSET, with MA fields computed like `/tickers`.
+#[derive(Debug)]
+pub struct LiveCandle {
+    pub(crate) key: CandleKey,
+    /// ZSET score (bar open time in epoch ms).
+    pub(crate) score: i64,
+    pub(crate) data: StockDataResponse,
+}
+
+/// Per-process fan-out point for live candles.
+///
+/// Workers publish `{source, ticker, interval}` on `OHLCV_UPDATES_CHANNEL`
+/// after each ZSET write (from any process). The hub listens on that channel,
+/// and for keys that at least one local socket subscribed to, reads the tail
+/// of the ZSET, runs `enhance_rows`, and broadcasts the last candle.
+pub struct LiveHub {
+    redis_client: RedisClient,
+    /// Number of local subscriptions per key — keys nobody watches are skipped.
+    interest: Mutex<HashMap<CandleKey, usize>>,
+    tx: broadcast::Sender<Arc<LiveCandle>>,
+    /// Keeps the pub/sub connection alive for automatic reconnection.
+    _subscriber: (SubscriberClient, ConnectHandle),
+}
+
+impl LiveHub {
+    /// Start the hub. Returns None when Redis is not configured.
+    pub async fn start(redis_client: Option<RedisClient>) -> Option<Arc<Self>> {
+        let redis_client = redis_client?;
+        let subscriber = crate::redis::subscribe(redis_ts::OHLCV_UPDATES_CHANNEL).await?;
+        let mut messages = subscriber.0.message_rx();
+
+        let (tx, _) = broadcast::channel(cfg::BROADCAST_CAPACITY);
+        let hub = Arc::new(Self {
+            redis_client,
+            interest: Mutex::new(HashMap::new()),
+            tx,
+            _subscriber: subscriber,
+        });
+
+        let worker = hub.clone();
+        tokio::spawn(async move {
+            loop {
+                let message = match messages.recv().await {
+                    Ok(m) => m,
+                    Err(broadcast::error::RecvError::Lagged(n)) => {
+                        tracing::warn!(skipped = n, "Live hub lagged behind Redis pub/sub");
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                let Some(key) = message
+                    .value
+                    .as_string()
+                    .and_then(|s| serde_json::from_str::<CandleKey>(&s).ok())
+                else {
+                    continue;
+                };
+                if !worker.is_watched(&key) {
+                    continue;
+                }
+                if let Some(candle) = worker.load_candle(&key).await {
+                    // Err only means no socket is listening right now.
+                    let _ = worker.tx.send(Arc::new(candle));
+                }
+            }
+            tracing::warn!("Live hub pub/sub stream closed");
+        });
+
+        tracing::info!("Live candle hub started");
+        Some(hub)
+    }
+
+    fn is_watched(&self, key: &CandleKey) -> bool {
+        self.interest.lock().unwrap().get(key).is_some_and(|n| *n > 0)
+    }
+
+    /// Receive every candle the hub broadcasts; filter by key on the receiving side.
+    pub(crate) fn receiver(&self) -> broadcast::Receiver<Arc<LiveCandle>> {
+        self.tx.subscribe()
+    }
+
+    /// Register interest in `keys`. Every call must be paired with [`Self::unwatch`].
+    pub(crate) fn watch(&self, keys: &[CandleKey]) {
+        let mut interest = self.interest.lock().unwrap();
+        for key in keys {
+            *interest.entry(key.clone()).or_default() += 1;
+        }
+    }
+
+    pub(crate) fn unwatch(&self, keys: &[CandleKey]) {
+        let mut interest = self.interest.lock().unwrap();
+        for key in keys {
+            if let Some(n) = interest.get_mut(key) {
+                *n -= 1;
+                if *n == 0 {
+                    interest.remove(key);
+                }
+            }
+        }
+    }
+
+    /// The newest candle in the ZSET, enhanced.
+    pub(crate) async fn load_candle(&self, key: &CandleKey) -> Option<LiveCandle> {
+        self.load_recent(key, 1, None).await.pop()
+    }
+
+    /// Candles with score >= `since_ms` (at most `RESUME_MAX_BARS`, newest
+    /// kept), oldest first. The bar at `since_ms` is included because a
+    /// forming bar keeps its score while its values change.
+    pub(crate) async fn load_since(&self, key: &CandleKey, since_ms: i64) -> Vec<LiveCandle> {
+        let since = chrono::DateTime::from_timestamp_millis(since_ms);
+        self.load_recent(key, cfg::RESUME_MAX_BARS, since).await
+    }
+
+    /// Read `count` bars plus enough history for the longest MA, enhance, and
+    /// return up to `count` of the newest, oldest first.
+    async fn load_recent(&self, key: &CandleKey, count: i64, since: Option<chrono::DateTime<chrono::Utc>>) -> Vec<LiveCandle> {
+        let lookback = if api::DEFAULT_USE_EMA { api::EMA_LOOKBACK } else { api::SMA_MAX_PERIOD };
+        let tickers = [key.ticker.clone()];
+        let Some(mut result) = crate::server::redis_reader::batch_read_ohlcv_from_redis(
+            &Some(self.redis_client.clone()),
+            &key.source,
+            &tickers,
+            &key.interval,
+            lookback + count,
+            "live",
+            None,
+        )
+        .await
+        else {
+            return Vec::new();
+        };
+        let Some(read) = result.remove(&key.ticker) else {
+            return Vec::new();
+        };
+
+        // enhance_rows keeps the oldest rows when given a start time; filter
+        // and truncate here instead so the newest bars survive.
+        let mut rows = crate::queries::ohlcv::enhance_rows(&key.ticker, read.rows, &Default::default());
+        if let Some(since) = since {
+            rows.retain(|r| r.time >= since);
+        }
+        rows.truncate(count as usize);
+        rows.reverse();
+
+        let is_daily = key.interval == "1D";
+        rows.into_iter()
+            .map(|row| LiveCandle {
+                key: key.clone(),
+                score: row.time.timestamp_millis(),
+                data: crate::server::api::response::map_ohlcv_to_response(row, is_daily, Mode::All),
+            })
+            .collect()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Socket protocol
+// ---------------------------------------------------------------------------
+
+/// Client → server message.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "action", rename_all = "lowercase")]
+enum ClientMessage {
+    Subscribe(SubscriptionRequest),
+    Unsubscribe(SubscriptionRequest),
+}
+
+#[derive(Debug, Deserialize)]
+struct SubscriptionRequest {
+    #[serde(default)]
+    mode: Mode,
+    symbol: String,
+    interval: String,
+}
+
+/// One accepted `(mode, symbol, interval)` tuple on a socket.
+#[derive(Debug, Clone)]
+struct Subscription {
+    mode: Mode,
+    symbol: String,
+    /// Interval as the client sent it, echoed back in candle messages.
+    interval_label: String,
+    interval: &'static str,
+}
+
+impl Subscription {
+    fn keys(&self) -> Vec<CandleKey> {
+        keys_for(self.mode, &self.symbol, self.interval)
+    }
+
+    /// Same tuple, regardless of how the interval was spelled (`1D` vs `daily`).
+    fn same_target(&self, other: &Subscription) -> bool {
+        self.mode == other.mode && self.symbol == other.symbol && self.interval == other.interval
+    }
+
+    fn matches(&self, key: &CandleKey) -> bool {
+        key.ticker == self.symbol && key.interval == self.interval && sources_for(self.mode).contains(&key.source.as_str())
+    }
+
+    fn candle_message(&self, data: &StockDataResponse) -> String {
+        json!({
+            "type": "candle",
+            "mode": self.mode.source_label(),
+            "symbol": self.symbol,
+            "interval": self.interval_label,
+            "data": data,
+        })
+        .to_string()
+    }
+}
+
+fn parse_subscription(req: SubscriptionRequest) -> Result<Subscription, String> {
+    if req.symbol.is_empty() {
+        return Err("symbol is required".to_string());
+    }
+    match NormalizedInterval::parse(&req.interval) {
+        Some(NormalizedInterval::Native(interval)) => Ok(Subscription {
+            mode: req.mode,
+            symbol: req.symbol,
+            interval_label: req.interval,
+            interval,
+        }),
+        _ => Err(format!("Invalid interval '{}'. Live streaming supports 1D, 1H, 1m", req.interval)),
+    }
+}
+
+fn error_message(message: &str) -> String {
+    json!({ "type": "error", "message": message }).to_string()
+}
+
+/// GET /ws — upgrade to a WebSocket streaming live candles.
+///
+/// Messages are JSON. Subscribe with
+/// `{"action":"subscribe","mode":"vn","symbol":"VCB","interval":"1D"}`; the
+/// server answers `{"type":"subscribed",...}`, sends the current candle, then
+/// pushes `{"type":"candle",...,"data":{...}}` whenever the ZSET is written.
+pub async fn ws_handler(State(state): State<Arc<AppState>>, ws: WebSocketUpgrade) -> Response {
+    let Some(hub) = state.live.clone() else {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(json!({ "error": "Live streaming requires Redis (REDIS_URL not set)" })),
+        )
+            .into_response();
+    };
+    ws.on_upgrade(move |socket| handle_socket(socket, hub))
+}
+
+async fn handle_socket(mut socket: WebSocket, hub: Arc<LiveHub>) {
+    let mut rx = hub.receiver();
+    let mut subscriptions: Vec<Subscription> = Vec::new();
+    let mut ping = tokio::time::interval(std::time::Duration::from_secs(cfg::PING_SECS));
+    ping.tick().await;
+
+    loop {
+        let outgoing: Vec<String> = tokio::select! {
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Text(text))) => handle_client_message(&hub, &mut subscriptions, &text).await,
+                Some(Ok(Message::Close(_))) | None | Some(Err(_)) => break,
+                Some(Ok(_)) => continue,
+            },
+            candle = rx.recv() => match candle {
+                Ok(candle) => subscriptions
+                    .iter()
+                    .filter(|s| s.matches(&candle.key))
+                    .map(|s| s.candle_message(&candle.data))
+                    .collect(),
+                Err(broadcast::error::RecvError::Lagged(n)) => {
+                    tracing::debug!(skipped = n, "ws client lagged, dropping candles");
+                    continue;
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+            _ = ping.tick() => {
+                if socket.send(Message::Ping(Default::default())).await.is_err() {
+                    break;
+                }
+                continue;
+            }
+        };
+
+        let mut closed = false;
+        for text in outgoing {
+            if socket.send(Message::Text(text.into())).await.is_err() {
+                closed = true;
+                break;
+            }
+        }
+        if closed {
+            break;
+        }
+    }
+
+    let keys: Vec<CandleKey> = subscriptions.iter().flat_map(|s| s.keys()).collect();
+    hub.unwatch(&keys);
+}
+
+/// Apply one client message and return the replies to send.
+async fn handle_client_message(hub: &LiveHub, subscriptions: &mut Vec<Subscription>, text: &str) -> Vec<String> {
+    let message = match serde_json::from_str::<ClientMessage>(text) {
+        Ok(m) => m,
+        Err(e) => return vec![error_message(&format!("Invalid message: {e}"))],
+    };
+
+    match message {
+        ClientMessage::Subscribe(req) => {
+            let sub = match parse_subscription(req) {
+                Ok(s) => s,
+                Err(e) => return vec![error_message(&e)],
+            };
+            if subscriptions.iter().any(|s| s.same_target(&sub)) {
+                return Vec::new();
+            }
+            if subscriptions.len() >= cfg::MAX_SUBSCRIPTIONS {
+                return vec![error_message(&format!("Too many subscriptions (max {})", cfg::MAX_SUBSCRIPTIONS))];
+            }
+
+            let keys = sub.keys();
+            hub.watch(&keys);
+            let mut replies = vec![json!({
+                "type": "subscribed",
+                "mode": sub.mode.source_label(),
+                "symbol": sub.symbol,
+                "interval": sub.interval_label,
+            })
+            .to_string()];
+            for key in &keys {
+                if let Some(candle) = hub.load_candle(key).await {
+                    replies.push(sub.candle_message(&candle.data));
+                }
+            }
+            subscriptions.push(sub);
+            replies
+        }
+        ClientMessage::Unsubscribe(req) => {
+            let sub = match parse_subscription(req) {
+                Ok(s) => s,
+                Err(e) => return vec![error_message(&e)],
+            };
+            let Some(pos) = subscriptions.iter().position(|s| s.same_target(&sub)) else {
+                return Vec::new();
+            };
+            let sub = subscriptions.remove(pos);
+            hub.unwatch(&sub.keys());
+            vec![json!({
+                "type": "unsubscribed",
+                "mode": sub.mode.source_label(),
+                "symbol": sub.symbol,
+                "interval": sub.interval_label,
+            })
+            .to_string()]
+        }
+    }
+}
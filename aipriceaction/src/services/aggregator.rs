@@ -1,8 +1,9 @@
 use crate::models::aggregated_interval::AggregatedInterval;
-use crate::models::indicators::{calculate_ema, calculate_ma_score, calculate_sma};
-use crate::models::ohlcv::OhlcvRow;
+use crate::models::indicators::{PriceSeries, calculate_mas, compute_indicators};
+use crate::models::ohlcv::{MaColumns, OhlcvRow};
+use crate::queries::ohlcv::FetchOptions;
 use chrono::{DateTime, Datelike, Duration, TimeZone, Timelike, Utc};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use tracing::debug;
 
 /// Service for aggregating OHLCV data into different timeframes.
@@ -31,6 +32,27 @@ pub struct AggregatedOhlcv {
     pub close_changed: Option<f64>,
     pub volume_changed: Option<f64>,
     pub total_money_changed: Option<f64>,
+    pub indicators: BTreeMap<String, f64>,
+}
+
+impl MaColumns for AggregatedOhlcv {
+    fn close(&self) -> f64 {
+        self.close
+    }
+
+    fn ma_columns(&mut self) -> [(&mut Option<f64>, &mut Option<f64>); 5] {
+        [
+            (&mut self.ma10, &mut self.ma10_score),
+            (&mut self.ma20, &mut self.ma20_score),
+            (&mut self.ma50, &mut self.ma50_score),
+            (&mut self.ma100, &mut self.ma100_score),
+            (&mut self.ma200, &mut self.ma200_score),
+        ]
+    }
+
+    fn indicators_mut(&mut self) -> &mut BTreeMap<String, f64> {
+        &mut self.indicators
+    }
 }
 
 impl Aggregator {
@@ -137,13 +159,15 @@ impl Aggregator {
 
     /// Enhance aggregated data with technical indicators.
     ///
-    /// When `with_ma` is false, SMA/score indicators are skipped (all remain None),
-    /// saving CPU time. Change indicators are still computed.
+    /// MAs are computed for `opts.ma_periods`. When `opts.with_ma` is false,
+    /// SMA/score indicators are skipped (all remain None), saving CPU time.
+    /// Change indicators are still computed, as are any `opts.indicators`
+    /// requested via `indicators=`.
     pub fn enhance_aggregated_data(
         mut data: HashMap<String, Vec<AggregatedOhlcv>>,
-        with_ma: bool,
-        use_ema: bool,
+        opts: &FetchOptions<'_>,
     ) -> HashMap<String, Vec<AggregatedOhlcv>> {
+        let FetchOptions { with_ma, use_ema, ma_periods, indicators, .. } = *opts;
         for stock_data in data.values_mut() {
             if stock_data.is_empty() {
                 continue;
@@ -151,38 +175,6 @@ impl Aggregator {
 
             let closes: Vec<f64> = stock_data.iter().map(|d| d.close).collect();
 
-            if with_ma {
-                let calc_ma = if use_ema { calculate_ema } else { calculate_sma };
-                let ma10_values = calc_ma(&closes, 10);
-                let ma20_values = calc_ma(&closes, 20);
-                let ma50_values = calc_ma(&closes, 50);
-                let ma100_values = calc_ma(&closes, 100);
-                let ma200_values = calc_ma(&closes, 200);
-
-                for (i, stock) in stock_data.iter_mut().enumerate() {
-                    if ma10_values[i] > 0.0 {
-                        stock.ma10 = Some(ma10_values[i]);
-                        stock.ma10_score = Some(calculate_ma_score(stock.close, ma10_values[i]));
-                    }
-                    if ma20_values[i] > 0.0 {
-                        stock.ma20 = Some(ma20_values[i]);
-                        stock.ma20_score = Some(calculate_ma_score(stock.close, ma20_values[i]));
-                    }
-                    if ma50_values[i] > 0.0 {
-                        stock.ma50 = Some(ma50_values[i]);
-                        stock.ma50_score = Some(calculate_ma_score(stock.close, ma50_values[i]));
-                    }
-                    if ma100_values[i] > 0.0 {
-                        stock.ma100 = Some(ma100_values[i]);
-                        stock.ma100_score = Some(calculate_ma_score(stock.close, ma100_values[i]));
-                    }
-                    if ma200_values[i] > 0.0 {
-                        stock.ma200 = Some(ma200_values[i]);
-                        stock.ma200_score = Some(calculate_ma_score(stock.close, ma200_values[i]));
-                    }
-                }
-            }
-
             // Calculate change indicators
             for i in 1..stock_data.len() {
                 let prev_close = stock_data[i - 1].close;
@@ -201,11 +193,45 @@ impl Aggregator {
                 let price_change = curr.close - prev_close;
                 curr.total_money_changed = Some(price_change * curr.volume as f64);
             }
+
+            if !indicators.is_empty() {
+                let mut series = PriceSeries::default();
+                for d in stock_data.iter() {
+                    series.push(d.high, d.low, d.close, d.volume as f64);
+                }
+                for (stock, values) in stock_data.iter_mut().zip(compute_indicators(indicators, &series)) {
+                    stock.indicators = values;
+                }
+            }
+
+            if with_ma {
+                for (period, values) in calculate_mas(&closes, ma_periods, use_ema) {
+                    for (stock, &v) in stock_data.iter_mut().zip(&values) {
+                        if v > 0.0 {
+                            stock.set_ma(period, v);
+                        }
+                    }
+                }
+            }
         }
 
         data
     }
 
+    /// Start of the bucket containing `time`, using the same alignment as the
+    /// `aggregate_*` functions.
+    pub fn bucket_start(interval: AggregatedInterval, time: DateTime<Utc>, offset_hours: i64) -> DateTime<Utc> {
+        match interval {
+            AggregatedInterval::Minutes5 | AggregatedInterval::Minutes15 | AggregatedInterval::Minutes30 => {
+                Self::bucket_minute(time, interval.bucket_minutes().unwrap_or(1))
+            }
+            AggregatedInterval::Hours4 => Self::bucket_hour(time, 4, offset_hours),
+            AggregatedInterval::Week => Self::bucket_week(time),
+            AggregatedInterval::Week2 => Self::bucket_2week(time),
+            AggregatedInterval::Month => Self::bucket_month(time),
+        }
+    }
+
     // ── Private helpers ──
 
     fn group_by_minute_bucket(
@@ -349,6 +375,7 @@ impl Aggregator {
             close_changed: None,
             volume_changed: None,
             total_money_changed: None,
+            indicators: BTreeMap::new(),
         }
     }
 }
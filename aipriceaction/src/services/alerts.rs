@@ -0,0 +1,472 @@
+//! Alert rule conditions (`/alerts`) and their evaluation against the newest
+//! bars of one ZSET, e.g.
+//! `{"type": "price_cross", "level": 90.5, "direction": "above"}` or
+//! `{"type": "ma_cross", "fast": 50, "slow": 200}`.
+//!
+//! Crossing conditions compare the newest bar with the one before it, so a
+//! rule fires on the bar where the cross happens; `close_changed` fires on
+//! every bar beyond its threshold. `workers::alert_worker` stores one delivery
+//! per (rule, bar), so a forming bar that is rewritten many times fires once.
+//!
+//! Webhooks may only target public addresses: [`check_webhook_url`] runs when
+//! a rule is saved and again before every delivery, and the worker's client
+//! resolves through [`PublicResolver`] so a name cannot be re-pointed at an
+//! internal address in between.
+
+use std::net::{IpAddr, SocketAddr};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::models::indicators::{MAX_MA_PERIOD, ma_lookback};
+use crate::models::ohlcv::{OhlcvJoined, OhlcvRow};
+use crate::queries::ohlcv::{FetchOptions, enhance_rows};
+use crate::server::analysis::rrg::{align_closes_by_date, compute_jdk};
+
+/// Accepted RRG smoothing periods, matching `/analysis/rrg`.
+const RRG_PERIODS: std::ops::RangeInclusive<usize> = 4..=50;
+
+/// Which way a crossing must go.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    Above,
+    Below,
+    #[default]
+    Any,
+}
+
+/// RRG quadrant of an (RS-Ratio, RS-Momentum) point, both centred on 100.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Quadrant {
+    Leading,
+    Weakening,
+    Lagging,
+    Improving,
+}
+
+impl Quadrant {
+    pub fn of(rs_ratio: f64, rs_momentum: f64) -> Self {
+        match (rs_ratio >= 100.0, rs_momentum >= 100.0) {
+            (true, true) => Self::Leading,
+            (true, false) => Self::Weakening,
+            (false, false) => Self::Lagging,
+            (false, true) => Self::Improving,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Leading => "leading",
+            Self::Weakening => "weakening",
+            Self::Lagging => "lagging",
+            Self::Improving => "improving",
+        }
+    }
+}
+
+/// What a rule watches for.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Condition {
+    /// Close crosses `level`.
+    PriceCross {
+        level: f64,
+        #[serde(default)]
+        direction: Direction,
+    },
+    /// The `fast` MA crosses the `slow` MA (`above` = golden cross).
+    MaCross {
+        fast: usize,
+        slow: usize,
+        #[serde(default)]
+        direction: Direction,
+        /// true = EMA instead of SMA.
+        #[serde(default)]
+        ema: bool,
+    },
+    /// `close_changed` (%) at or above `above` and/or at or below `below`.
+    CloseChanged {
+        above: Option<f64>,
+        below: Option<f64>,
+    },
+    /// The JdK RRG quadrant against `benchmark` changes; with `to`, only
+    /// moves into one of those quadrants fire.
+    RrgQuadrant {
+        #[serde(default = "default_benchmark")]
+        benchmark: String,
+        #[serde(default = "default_rrg_period")]
+        period: usize,
+        #[serde(default)]
+        to: Vec<Quadrant>,
+    },
+}
+
+fn default_benchmark() -> String { "VNINDEX".to_string() }
+fn default_rrg_period() -> usize { 10 }
+
+/// A condition that held on the newest bar.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Trigger {
+    /// Bar the rule fired on; deliveries are deduplicated on it.
+    pub time: DateTime<Utc>,
+    pub close: f64,
+    pub message: String,
+    /// Values the condition was decided on.
+    pub values: serde_json::Value,
+}
+
+impl Condition {
+    /// Check parameters and normalise names (benchmark upper-cased).
+    pub fn validate(&mut self) -> Result<(), String> {
+        match self {
+            Self::PriceCross { level, .. } => {
+                if !level.is_finite() || *level <= 0.0 {
+                    return Err("level must be a positive number".to_string());
+                }
+            }
+            Self::MaCross { fast, slow, .. } => {
+                for p in [*fast, *slow] {
+                    if p == 0 || p > MAX_MA_PERIOD {
+                        return Err(format!("MA periods must be between 1 and {MAX_MA_PERIOD}"));
+                    }
+                }
+                if fast == slow {
+                    return Err("fast and slow MA periods must differ".to_string());
+                }
+            }
+            Self::CloseChanged { above, below } => {
+                if above.is_none() && below.is_none() {
+                    return Err("close_changed needs `above` and/or `below`".to_string());
+                }
+                if above.iter().chain(below.iter()).any(|v| !v.is_finite()) {
+                    return Err("close_changed thresholds must be numbers".to_string());
+                }
+            }
+            Self::RrgQuadrant { benchmark, period, .. } => {
+                *benchmark = benchmark.trim().to_uppercase();
+                if benchmark.is_empty() {
+                    return Err("benchmark must not be empty".to_string());
+                }
+                if !RRG_PERIODS.contains(period) {
+                    return Err(format!(
+                        "RRG period must be between {} and {}",
+                        RRG_PERIODS.start(),
+                        RRG_PERIODS.end()
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Bars to read (newest first) for [`Self::evaluate`].
+    pub fn bars_needed(&self) -> i64 {
+        match self {
+            Self::PriceCross { .. } | Self::CloseChanged { .. } => 2,
+            Self::MaCross { fast, slow, ema, .. } => 2 + ma_lookback(&[*fast, *slow], *ema),
+            Self::RrgQuadrant { period, .. } => 3 * *period as i64 + 2,
+        }
+    }
+
+    /// Ticker whose bars are also needed, from the same source.
+    pub fn benchmark(&self) -> Option<&str> {
+        match self {
+            Self::RrgQuadrant { benchmark, .. } => Some(benchmark),
+            _ => None,
+        }
+    }
+
+    /// Evaluate on `rows` (newest first, at least [`Self::bars_needed`] for
+    /// MA periods to be warm) and, for RRG, the benchmark's rows.
+    pub fn evaluate(&self, ticker: &str, rows: &[OhlcvRow], benchmark: &[OhlcvRow]) -> Option<Trigger> {
+        let newest = rows.first()?;
+        match self {
+            Self::PriceCross { level, direction } => {
+                let prev = rows.get(1)?;
+                let crossed = crossing(prev.close - level, newest.close - level, *direction)?;
+                Some(Trigger {
+                    time: newest.time,
+                    close: newest.close,
+                    message: format!("{ticker} close crossed {} {level} ({})", label(crossed), newest.close),
+                    values: serde_json::json!({ "previous_close": prev.close, "level": level }),
+                })
+            }
+            Self::MaCross { fast, slow, direction, ema } => {
+                let mut periods = vec![*fast, *slow];
+                periods.sort_unstable();
+                let joined = enhance_rows(ticker, rows.to_vec(), &FetchOptions { limit: Some(2), use_ema: *ema, ma_periods: &periods, ..Default::default() });
+                let spread = |row: &OhlcvJoined| Some(row.ma(*fast).0? - row.ma(*slow).0?);
+                let (current, previous) = (joined.first()?, joined.get(1)?);
+                let crossed = crossing(spread(previous)?, spread(current)?, *direction)?;
+                let kind = if *ema { "EMA" } else { "MA" };
+                Some(Trigger {
+                    time: newest.time,
+                    close: newest.close,
+                    message: format!("{ticker} {kind}{fast} crossed {} {kind}{slow}", label(crossed)),
+                    values: serde_json::json!({
+                        format!("ma{fast}"): current.ma(*fast).0,
+                        format!("ma{slow}"): current.ma(*slow).0,
+                    }),
+                })
+            }
+            Self::CloseChanged { above, below } => {
+                let joined = enhance_rows(ticker, rows.iter().take(2).cloned().collect(), &FetchOptions { limit: Some(1), with_ma: false, ..Default::default() });
+                let changed = joined.first()?.close_changed?;
+                let message = if above.is_some_and(|a| changed >= a) {
+                    format!("{ticker} close changed {changed:+.2}% (>= {}%)", above.unwrap_or_default())
+                } else if below.is_some_and(|b| changed <= b) {
+                    format!("{ticker} close changed {changed:+.2}% (<= {}%)", below.unwrap_or_default())
+                } else {
+                    return None;
+                };
+                Some(Trigger {
+                    time: newest.time,
+                    close: newest.close,
+                    message,
+                    values: serde_json::json!({ "close_changed": changed }),
+                })
+            }
+            Self::RrgQuadrant { benchmark: bench, period, to } => {
+                let aligned = align_closes_by_date(rows, benchmark)?;
+                let (x, y) = compute_jdk(&aligned.sec_closes, &aligned.bench_closes, *period)?;
+                let n = x.len();
+                if n < 2 {
+                    return None;
+                }
+                let from = Quadrant::of(x[n - 2], y[n - 2]);
+                let into = Quadrant::of(x[n - 1], y[n - 1]);
+                if from == into || (!to.is_empty() && !to.contains(&into)) {
+                    return None;
+                }
+                Some(Trigger {
+                    time: *aligned.dates.last()?,
+                    close: *aligned.sec_closes.last()?,
+                    message: format!("{ticker} moved from {} to {} against {bench}", from.as_str(), into.as_str()),
+                    values: serde_json::json!({
+                        "from": from,
+                        "to": into,
+                        "rs_ratio": x[n - 1],
+                        "rs_momentum": y[n - 1],
+                    }),
+                })
+            }
+        }
+    }
+}
+
+/// Direction of a sign change from `prev` to `current` (distances from the
+/// level), if it matches `wanted`. Touching the level counts as reaching it.
+fn crossing(prev: f64, current: f64, wanted: Direction) -> Option<Direction> {
+    let crossed = if prev < 0.0 && current >= 0.0 {
+        Direction::Above
+    } else if prev > 0.0 && current <= 0.0 {
+        Direction::Below
+    } else {
+        return None;
+    };
+    (wanted == Direction::Any || wanted == crossed).then_some(crossed)
+}
+
+fn label(direction: Direction) -> &'static str {
+    match direction {
+        Direction::Above => "above",
+        Direction::Below => "below",
+        Direction::Any => "",
+    }
+}
+
+/// True for addresses on the public internet: not loopback, private
+/// (RFC 1918, unique local), link-local (incl. cloud metadata at
+/// 169.254.169.254), shared (100.64/10), multicast, broadcast or unspecified.
+pub fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => {
+            let [a, b, ..] = v4.octets();
+            !(v4.is_loopback()
+                || v4.is_private()
+                || v4.is_link_local()
+                || v4.is_unspecified()
+                || v4.is_broadcast()
+                || v4.is_multicast()
+                || v4.is_documentation()
+                || a == 0
+                || (a == 100 && (64..128).contains(&b)))
+        }
+        IpAddr::V6(v6) => match v6.to_ipv4_mapped() {
+            Some(v4) => is_public_ip(IpAddr::V4(v4)),
+            None => !(v6.is_loopback()
+                || v6.is_unspecified()
+                || v6.is_multicast()
+                || v6.is_unique_local()
+                || v6.is_unicast_link_local()),
+        },
+    }
+}
+
+/// Resolve `host:port` and require every address to be public.
+async fn resolve_public(host: &str, port: u16) -> Result<Vec<SocketAddr>, String> {
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host, port))
+        .await
+        .map_err(|e| format!("cannot resolve {host}: {e}"))?
+        .collect();
+    if addrs.is_empty() {
+        return Err(format!("{host} has no addresses"));
+    }
+    match addrs.iter().find(|a| !is_public_ip(a.ip())) {
+        Some(a) => Err(format!("{host} resolves to non-public address {}", a.ip())),
+        None => Ok(addrs),
+    }
+}
+
+/// Check that `url` is an http(s) URL whose host is a public address.
+pub async fn check_webhook_url(url: &str) -> Result<(), String> {
+    let parsed = match reqwest::Url::parse(url) {
+        Ok(u) if matches!(u.scheme(), "http" | "https") => u,
+        _ => return Err("webhook_url must be an http(s) URL".to_string()),
+    };
+    let Some(host) = parsed.host_str() else {
+        return Err("webhook_url must be an http(s) URL".to_string());
+    };
+    let host = host.trim_start_matches('[').trim_end_matches(']').trim_end_matches('.').to_ascii_lowercase();
+    if let Ok(ip) = host.parse::<IpAddr>() {
+        return if is_public_ip(ip) { Ok(()) } else { Err("webhook_url must not target a private address".to_string()) };
+    }
+    if host == "localhost" || host.ends_with(".localhost") {
+        return Err("webhook_url must not target localhost".to_string());
+    }
+    let port = parsed.port_or_known_default().unwrap_or(80);
+    resolve_public(&host, port).await.map(|_| ()).map_err(|e| format!("webhook_url rejected: {e}"))
+}
+
+/// DNS resolver for the webhook client that refuses names resolving to
+/// non-public addresses, so the check holds at connect time.
+pub struct PublicResolver;
+
+impl reqwest::dns::Resolve for PublicResolver {
+    fn resolve(&self, name: reqwest::dns::Name) -> reqwest::dns::Resolving {
+        let host = name.as_str().to_string();
+        Box::pin(async move {
+            let addrs = resolve_public(&host, 0).await?;
+            Ok(Box::new(addrs.into_iter()) as reqwest::dns::Addrs)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+
+    /// Daily bars from closes given oldest first; returned newest first.
+    fn bars(closes: &[f64]) -> Vec<OhlcvRow> {
+        let start = Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap();
+        let mut rows: Vec<OhlcvRow> = closes
+            .iter()
+            .enumerate()
+            .map(|(i, &c)| OhlcvRow {
+                ticker_id: 1,
+                interval: "1D".into(),
+                time: start + Duration::days(i as i64),
+                open: c,
+                high: c,
+                low: c,
+                close: c,
+                volume: 1000,
+            })
+            .collect();
+        rows.reverse();
+        rows
+    }
+
+    #[test]
+    fn test_price_cross() {
+        let up = Condition::PriceCross { level: 100.0, direction: Direction::Above };
+        let t = up.evaluate("VCB", &bars(&[98.0, 101.0]), &[]).unwrap();
+        assert_eq!(t.close, 101.0);
+        assert_eq!(t.message, "VCB close crossed above 100 (101)");
+        // Already above, or crossing the other way
+        assert!(up.evaluate("VCB", &bars(&[100.5, 101.0]), &[]).is_none());
+        assert!(up.evaluate("VCB", &bars(&[101.0, 99.0]), &[]).is_none());
+
+        let any = Condition::PriceCross { level: 100.0, direction: Direction::Any };
+        assert!(any.evaluate("VCB", &bars(&[101.0, 99.0]), &[]).is_some());
+        assert!(any.evaluate("VCB", &bars(&[101.0]), &[]).is_none());
+    }
+
+    #[test]
+    fn test_ma_cross() {
+        // Falling then a sharp rally: MA2 crosses above MA4 on the last bar only
+        let closes = [10.0, 9.0, 8.0, 7.0, 6.0, 12.0];
+        let golden = Condition::MaCross { fast: 2, slow: 4, direction: Direction::Above, ema: false };
+        let rows = bars(&closes);
+        assert!(golden.bars_needed() as usize >= 6);
+        let t = golden.evaluate("FPT", &rows, &[]).unwrap();
+        assert_eq!(t.time, rows[0].time);
+        assert_eq!(t.values["ma2"], 9.0);
+        assert_eq!(t.values["ma4"], 8.25);
+        // One bar earlier the cross had not happened yet
+        assert!(golden.evaluate("FPT", &rows[1..], &[]).is_none());
+        let death = Condition::MaCross { fast: 2, slow: 4, direction: Direction::Below, ema: false };
+        assert!(death.evaluate("FPT", &rows, &[]).is_none());
+    }
+
+    #[test]
+    fn test_close_changed_and_validate() {
+        let cond = Condition::CloseChanged { above: Some(5.0), below: Some(-5.0) };
+        assert!(cond.evaluate("HPG", &bars(&[100.0, 106.0]), &[]).is_some());
+        assert!(cond.evaluate("HPG", &bars(&[100.0, 94.0]), &[]).is_some());
+        assert!(cond.evaluate("HPG", &bars(&[100.0, 103.0]), &[]).is_none());
+
+        assert!(Condition::CloseChanged { above: None, below: None }.validate().is_err());
+        assert!(Condition::MaCross { fast: 20, slow: 20, direction: Direction::Any, ema: false }.validate().is_err());
+        let mut rrg: Condition = serde_json::from_str(r#"{"type":"rrg_quadrant","benchmark":"vnindex"}"#).unwrap();
+        rrg.validate().unwrap();
+        assert_eq!(rrg.benchmark(), Some("VNINDEX"));
+        assert_eq!(rrg.bars_needed(), 32);
+    }
+
+    #[test]
+    fn test_rrg_quadrant_change() {
+        // Underperform the flat benchmark for a while, then rally hard
+        let mut closes: Vec<f64> = (0..40).map(|i| 100.0 - i as f64 * 0.5).collect();
+        let bench = bars(&vec![100.0; 60]);
+        let cond = Condition::RrgQuadrant { benchmark: "VNINDEX".into(), period: 4, to: vec![] };
+        let mut fired = Vec::new();
+        for i in 0..20 {
+            closes.push(closes[closes.len() - 1] + 3.0 + i as f64);
+            if let Some(t) = cond.evaluate("SSI", &bars(&closes), &bench) {
+                fired.push(t.values["to"].clone());
+            }
+        }
+        assert!(!fired.is_empty());
+        assert!(fired.contains(&serde_json::json!("improving")));
+    }
+
+    #[test]
+    fn test_is_public_ip() {
+        for ip in ["127.0.0.1", "10.1.2.3", "172.16.0.1", "192.168.1.1", "169.254.169.254", "100.64.0.1", "0.0.0.0", "::1", "fd00::1", "fe80::1", "::ffff:127.0.0.1"] {
+            assert!(!is_public_ip(ip.parse().unwrap()), "{ip}");
+        }
+        for ip in ["8.8.8.8", "1.1.1.1", "2606:4700:4700::1111"] {
+            assert!(is_public_ip(ip.parse().unwrap()), "{ip}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_check_webhook_url_rejects_internal_targets() {
+        for url in [
+            "ftp://example.com/hook",
+            "http://127.0.0.1:8080/hook",
+            "http://169.254.169.254/latest/meta-data",
+            "http://[::1]/hook",
+            "http://localhost/hook",
+            "http://api.localhost./hook",
+            "http://10.0.0.5/hook",
+        ] {
+            assert!(check_webhook_url(url).await.is_err(), "{url}");
+        }
+        assert!(check_webhook_url("https://8.8.8.8/hook").await.is_ok());
+    }
+}
@@ -0,0 +1,1121 @@
+//! Backtesting engine for `POST /analysis/backtest` and the `backtest` CLI.
+//!
+//! A strategy is a pair of screener expressions (see `services::screener`):
+//! `entry` picks tickers to buy, `exit` closes held positions. `ma_cross` and
+//! `rsi_reversion` are shorthands that expand to such a pair.
+//!
+//! Execution model, on daily bars of one source:
+//! - signals are evaluated on each bar's close and filled at the next bar's
+//!   open; long only, one position per ticker, at most `max_positions`
+//! - entries are ranked by `rank_by` (highest first) when there are more
+//!   candidates than free slots; each buys `position_pct` of the previous
+//!   day's equity, rounded down to the lot size and capped by settled cash
+//! - fills are clamped to the daily price band of the ticker's exchange
+//!   (HOSE 7%, HNX 10%, UPCOM 15%) around the previous close; a bar locked at
+//!   the ceiling (buy) or floor (sell) does not fill
+//! - bought shares can be sold and sale proceeds spent `settlement_days`
+//!   trading days after the fill (T+2 on VN); blocked sells retry daily
+//! - fees apply to both sides, the sell tax to the value of each sale
+//! - group members are taken as of each signal day (`group_membership`), so
+//!   tickers enter and leave the universe when they did historically;
+//!   explicit `symbols` are always in it
+//!
+//! Defaults follow the source's exchange (`models::calendar::Exchange`), and
+//! every market rule can be overridden per request.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use chrono::{NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+
+use crate::constants::backtest as cfg;
+use crate::models::calendar::Exchange;
+use crate::models::corporate_action::Adjustment;
+use crate::models::indicators::{Indicator, MAX_INDICATORS, indicators_lookback, ma_lookback};
+use crate::models::ohlcv::OhlcvJoined;
+use crate::queries::ohlcv::{self, FetchOptions};
+use crate::server::analysis::{get_ticker_sector, is_index_ticker};
+use crate::server::types::Mode;
+use crate::services::metadata;
+use crate::services::screener::{Expression, Requirements, Row};
+
+/// A bar whose low is within this fraction of the ceiling (or high within
+/// it of the floor) is treated as locked: band prices are rounded to ticks.
+const LOCK_TOLERANCE: f64 = 0.005;
+
+// ── Request ──
+
+/// Entry/exit rules of a backtest.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Strategy {
+    /// Screener expressions, e.g. `close > ma50 and rsi14 < 40`.
+    Rules { entry: String, exit: String },
+    /// Buy when the fast MA crosses above the slow one, sell when it is back below.
+    MaCross { fast: usize, slow: usize },
+    /// Buy oversold, sell overbought.
+    RsiReversion {
+        #[serde(default = "default_rsi_period")]
+        period: usize,
+        #[serde(default = "default_oversold")]
+        oversold: f64,
+        #[serde(default = "default_overbought")]
+        overbought: f64,
+    },
+}
+
+fn default_rsi_period() -> usize { 14 }
+fn default_oversold() -> f64 { 30.0 }
+fn default_overbought() -> f64 { 70.0 }
+
+impl Strategy {
+    /// The `(entry, exit)` expressions this strategy stands for.
+    pub fn rules(&self) -> Result<(String, String), String> {
+        match *self {
+            Self::Rules { ref entry, ref exit } => Ok((entry.clone(), exit.clone())),
+            Self::MaCross { fast, slow } => {
+                if fast == 0 || fast >= slow {
+                    return Err("ma_cross needs 0 < fast < slow".to_string());
+                }
+                Ok((
+                    format!("ma{fast} > ma{slow} and prev(ma{fast}, 1) <= prev(ma{slow}, 1)"),
+                    format!("ma{fast} < ma{slow}"),
+                ))
+            }
+            Self::RsiReversion { period, oversold, overbought } => {
+                if !(0.0..=100.0).contains(&oversold) || !(0.0..=100.0).contains(&overbought) || oversold >= overbought {
+                    return Err("rsi_reversion needs 0 <= oversold < overbought <= 100".to_string());
+                }
+                Ok((format!("rsi{period} < {oversold}"), format!("rsi{period} > {overbought}")))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BacktestRequest {
+    pub strategy: Strategy,
+    #[serde(default)]
+    pub mode: Mode,
+    /// Sector groups making up the universe, with their members of each day.
+    #[serde(default)]
+    pub groups: Vec<String>,
+    /// Extra tickers. With neither `groups` nor `symbols`, every group but INDEX.
+    #[serde(default)]
+    pub symbols: Vec<String>,
+    /// First trading day, YYYY-MM-DD.
+    pub start_date: String,
+    /// Last trading day, YYYY-MM-DD (default: today).
+    pub end_date: Option<String>,
+    /// Numeric expression ranking entry candidates, highest first.
+    #[serde(default = "default_rank_by")]
+    pub rank_by: String,
+    /// true = use EMA instead of SMA for MA fields.
+    #[serde(default)]
+    pub ema: bool,
+    /// Price series to simulate on (default: split-adjusted).
+    #[serde(default = "default_adjust")]
+    pub adjust: Adjustment,
+    #[serde(default)]
+    pub sizing: SizingOverrides,
+    #[serde(default)]
+    pub market: MarketOverrides,
+    /// Close a position when its close falls this far below the entry (%).
+    pub stop_loss_pct: Option<f64>,
+    /// Close a position when its close rises this far above the entry (%).
+    pub take_profit_pct: Option<f64>,
+}
+
+fn default_rank_by() -> String { "close * volume".to_string() }
+fn default_adjust() -> Adjustment { Adjustment::Split }
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SizingOverrides {
+    pub initial_capital: Option<f64>,
+    pub position_pct: Option<f64>,
+    pub max_positions: Option<usize>,
+}
+
+/// Market rule overrides; unset fields follow the source's exchange.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct MarketOverrides {
+    pub settlement_days: Option<usize>,
+    /// Daily price band (%); 0 disables it.
+    pub price_band_pct: Option<f64>,
+    /// Round lot; 0 allows fractional quantities.
+    pub lot_size: Option<f64>,
+    pub fee_pct: Option<f64>,
+    pub sell_tax_pct: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Sizing {
+    pub initial_capital: f64,
+    pub position_pct: f64,
+    pub max_positions: usize,
+}
+
+/// Market rules in effect for a run.
+#[derive(Debug, Clone, Serialize)]
+pub struct Market {
+    pub settlement_days: usize,
+    /// Band of the source's exchange. Tickers listed elsewhere (HNX, UPCOM)
+    /// use their own exchange's band unless the band is overridden.
+    pub price_band_pct: Option<f64>,
+    pub lot_size: Option<f64>,
+    pub fee_pct: f64,
+    pub sell_tax_pct: f64,
+    #[serde(skip)]
+    band_overridden: bool,
+}
+
+impl Market {
+    /// Exchange defaults for `source`, with `overrides` applied.
+    pub fn resolve(source: &str, overrides: &MarketOverrides) -> Result<Self, String> {
+        let exchange = Exchange::for_source(source);
+        let market = Self {
+            settlement_days: overrides
+                .settlement_days
+                .unwrap_or_else(|| exchange.map_or(0, |e| e.settlement_days())),
+            price_band_pct: match overrides.price_band_pct {
+                Some(p) => (p > 0.0).then_some(p),
+                None => exchange.and_then(|e| e.price_band()).map(|b| b * 100.0),
+            },
+            lot_size: match overrides.lot_size {
+                Some(l) => (l > 0.0).then_some(l),
+                None => exchange.and_then(|e| e.lot_size()),
+            },
+            fee_pct: overrides.fee_pct.unwrap_or(cfg::DEFAULT_FEE_PCT),
+            sell_tax_pct: overrides
+                .sell_tax_pct
+                .unwrap_or(if source == "vn" { cfg::VN_SELL_TAX_PCT } else { 0.0 }),
+            band_overridden: overrides.price_band_pct.is_some(),
+        };
+        let pct_ok = |v: f64| v.is_finite() && (0.0..100.0).contains(&v);
+        if market.settlement_days > 10 {
+            return Err("settlement_days must be at most 10".to_string());
+        }
+        if !market.price_band_pct.is_none_or(pct_ok) || !pct_ok(market.fee_pct) || !pct_ok(market.sell_tax_pct) {
+            return Err("price_band_pct, fee_pct and sell_tax_pct must be between 0 and 100".to_string());
+        }
+        if market.lot_size.is_some_and(|l| !l.is_finite()) {
+            return Err("lot_size must be finite".to_string());
+        }
+        Ok(market)
+    }
+
+    /// Daily price band (%) of a ticker listed on `exchange`.
+    pub fn price_band_for(&self, exchange: Option<Exchange>) -> Option<f64> {
+        match exchange {
+            Some(e) if !self.band_overridden => e.price_band().map(|b| b * 100.0),
+            _ => self.price_band_pct,
+        }
+    }
+}
+
+// ── Report ──
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BacktestReport {
+    pub source: String,
+    pub entry: String,
+    pub exit: String,
+    pub rank_by: String,
+    pub start_date: String,
+    pub end_date: String,
+    pub universe: usize,
+    pub sizing: Sizing,
+    pub market: Market,
+    pub summary: Summary,
+    pub equity_curve: Vec<EquityPoint>,
+    pub trades: Vec<Trade>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Summary {
+    pub initial_capital: f64,
+    pub final_equity: f64,
+    pub total_return_pct: f64,
+    pub annualized_return_pct: Option<f64>,
+    pub max_drawdown_pct: f64,
+    /// Annualized, from daily equity returns with a zero risk-free rate.
+    pub sharpe: Option<f64>,
+    /// Closed trades.
+    pub trades: usize,
+    pub open_positions: usize,
+    pub win_rate_pct: Option<f64>,
+    pub avg_trade_return_pct: Option<f64>,
+    pub fees_paid: f64,
+    pub taxes_paid: f64,
+    /// Orders that could not be filled, by reason.
+    pub blocked: Blocked,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Blocked {
+    /// Bar locked at the ceiling (buys) or floor (sells).
+    pub price_band: usize,
+    /// Sell signal before the shares settled; retried the next day.
+    pub settlement: usize,
+    /// Position budget smaller than one lot.
+    pub lot_size: usize,
+    /// Not enough settled cash for one lot.
+    pub cash: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EquityPoint {
+    pub date: String,
+    pub equity: f64,
+    pub cash: f64,
+    pub positions: usize,
+    pub drawdown_pct: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Trade {
+    pub symbol: String,
+    pub sector: Option<String>,
+    pub entry_date: String,
+    pub entry_price: f64,
+    pub quantity: f64,
+    /// None while the position is still open at the end of the run.
+    pub exit_date: Option<String>,
+    /// Fill price, or the last close for open positions.
+    pub exit_price: f64,
+    /// After fees and taxes; open positions are marked at the last close.
+    pub pnl: f64,
+    pub return_pct: f64,
+    /// `exit`, `stop_loss`, `take_profit` or `open`.
+    pub exit_reason: String,
+    pub bars_held: usize,
+}
+
+// ── Plan ──
+
+/// Sector groups of a source on `start` and each later day they change, as
+/// `(day, groups)` oldest first (see `server::analysis::universe_history`).
+pub type GroupHistory = Vec<(NaiveDate, BTreeMap<String, Vec<String>>)>;
+
+/// Sector groups and the tickers entries may be picked from, from `from`
+/// until the next change.
+#[derive(Debug)]
+struct Universe {
+    from: NaiveDate,
+    groups: BTreeMap<String, Vec<String>>,
+    symbols: BTreeSet<String>,
+}
+
+/// A validated backtest, ready to load data for and run.
+#[derive(Debug)]
+pub struct Plan {
+    source: &'static str,
+    /// Every ticker in the universe at some point of the range.
+    symbols: Vec<String>,
+    /// Listing exchange per symbol, where `ticker_metadata` knows it.
+    exchanges: HashMap<String, Exchange>,
+    /// Oldest first, the first from `start`.
+    universe: Vec<Universe>,
+    entry: Expression,
+    exit: Expression,
+    rank_by: Expression,
+    start: NaiveDate,
+    end: NaiveDate,
+    ema: bool,
+    adjust: Adjustment,
+    ma_periods: Vec<usize>,
+    indicators: Vec<Indicator>,
+    /// Bars loaded before `start` so MAs and indicators are settled.
+    warmup: i64,
+    sizing: Sizing,
+    market: Market,
+    stop_loss_pct: Option<f64>,
+    take_profit_pct: Option<f64>,
+}
+
+impl Plan {
+    /// Validate `req` against the sector groups of its source, which
+    /// `group_history` gives for `(source, start, end)`.
+    pub fn new(
+        req: &BacktestRequest,
+        group_history: impl FnOnce(&str, NaiveDate, NaiveDate) -> GroupHistory,
+    ) -> Result<Self, String> {
+        if req.mode == Mode::All {
+            return Err("mode=all is not supported; backtest one source at a time".to_string());
+        }
+        let source = req.mode.source_label();
+
+        let (entry, exit) = req.strategy.rules()?;
+        let entry = Expression::parse_filter(&entry).map_err(|e| format!("Invalid entry: {e}"))?;
+        let exit = Expression::parse_filter(&exit).map_err(|e| format!("Invalid exit: {e}"))?;
+        let rank_by = Expression::parse_value(&req.rank_by).map_err(|e| format!("Invalid rank_by: {e}"))?;
+
+        let parse = |s: &str, name: &str| {
+            NaiveDate::parse_from_str(s, "%Y-%m-%d").map_err(|_| format!("Invalid {name}, expected YYYY-MM-DD"))
+        };
+        let start = parse(&req.start_date, "start_date")?;
+        let end = match &req.end_date {
+            Some(s) => parse(s, "end_date")?,
+            None => Utc::now().date_naive(),
+        };
+        if end < start {
+            return Err("end_date must not be before start_date".to_string());
+        }
+        if (end - start).num_days() > cfg::MAX_RANGE_DAYS {
+            return Err(format!("Date range must be at most {} days", cfg::MAX_RANGE_DAYS));
+        }
+
+        // Universe: the named groups as of each change plus explicit symbols
+        let mut history = group_history(source, start, end);
+        if history.is_empty() {
+            history.push((start, BTreeMap::new()));
+        }
+        if let Some(name) = req.groups.iter().find(|name| !history.iter().any(|(_, groups)| groups.contains_key(*name))) {
+            return Err(format!("Unknown group '{name}'"));
+        }
+        let explicit: BTreeSet<String> =
+            req.symbols.iter().map(|s| s.trim().to_uppercase()).filter(|s| !s.is_empty()).collect();
+        let universe: Vec<Universe> = history
+            .into_iter()
+            .map(|(from, groups)| {
+                let mut symbols = explicit.clone();
+                let named = |name: &String| {
+                    if req.groups.is_empty() && req.symbols.is_empty() { name != "INDEX" } else { req.groups.contains(name) }
+                };
+                symbols.extend(groups.iter().filter(|(name, _)| named(name)).flat_map(|(_, tickers)| tickers.iter().cloned()));
+                symbols.retain(|t| !is_index_ticker(t));
+                Universe { from, groups, symbols }
+            })
+            .collect();
+        let all: BTreeSet<String> = universe.iter().flat_map(|u| u.symbols.iter().cloned()).collect();
+        if all.is_empty() {
+            return Err("Universe is empty".to_string());
+        }
+        if all.len() > cfg::MAX_SYMBOLS {
+            return Err(format!("Universe has {} tickers, at most {} allowed", all.len(), cfg::MAX_SYMBOLS));
+        }
+
+        let mut needs = Requirements::default();
+        needs.add(&entry);
+        needs.add(&exit);
+        needs.add(&rank_by);
+        if needs.indicators.len() > MAX_INDICATORS {
+            return Err(format!("At most {MAX_INDICATORS} indicators per backtest"));
+        }
+        let ma_periods: Vec<usize> = needs.ma_periods.iter().copied().collect();
+        let warmup = needs.bars as i64 + ma_lookback(&ma_periods, req.ema).max(indicators_lookback(&needs.indicators));
+
+        let sizing = Sizing {
+            initial_capital: req.sizing.initial_capital.unwrap_or(cfg::DEFAULT_CAPITAL),
+            position_pct: req.sizing.position_pct.unwrap_or(cfg::DEFAULT_POSITION_PCT),
+            max_positions: req.sizing.max_positions.unwrap_or(cfg::DEFAULT_MAX_POSITIONS),
+        };
+        if !sizing.initial_capital.is_finite() || sizing.initial_capital <= 0.0 {
+            return Err("initial_capital must be positive".to_string());
+        }
+        if !(sizing.position_pct > 0.0 && sizing.position_pct <= 100.0) {
+            return Err("position_pct must be in (0, 100]".to_string());
+        }
+        if sizing.max_positions == 0 {
+            return Err("max_positions must be at least 1".to_string());
+        }
+        for (name, value) in [("stop_loss_pct", req.stop_loss_pct), ("take_profit_pct", req.take_profit_pct)] {
+            if value.is_some_and(|v| !(v > 0.0 && v.is_finite())) {
+                return Err(format!("{name} must be positive"));
+            }
+        }
+        if req.stop_loss_pct.is_some_and(|v| v >= 100.0) {
+            return Err("stop_loss_pct must be below 100".to_string());
+        }
+
+        let catalog = metadata::catalog();
+        let exchanges = all
+            .iter()
+            .filter_map(|t| {
+                let code = catalog.get(source, t)?.exchange.as_deref()?;
+                Some((t.clone(), Exchange::from_code(code)?))
+            })
+            .collect();
+
+        Ok(Self {
+            source,
+            symbols: all.into_iter().collect(),
+            exchanges,
+            universe,
+            entry,
+            exit,
+            rank_by,
+            start,
+            end,
+            ema: req.ema,
+            adjust: req.adjust,
+            ma_periods,
+            indicators: needs.indicators,
+            warmup,
+            sizing,
+            market: Market::resolve(source, &req.market)?,
+            stop_loss_pct: req.stop_loss_pct,
+            take_profit_pct: req.take_profit_pct,
+        })
+    }
+
+    pub fn source(&self) -> &'static str {
+        self.source
+    }
+
+    pub fn symbols(&self) -> &[String] {
+        &self.symbols
+    }
+
+    /// The universe in force on `date`.
+    fn universe_on(&self, date: NaiveDate) -> &Universe {
+        let i = self.universe.partition_point(|u| u.from <= date);
+        &self.universe[i.saturating_sub(1)]
+    }
+
+    /// Daily bars (newest first) of the universe from `start` minus the
+    /// warm-up through `end`, adjusted and with the fields the rules use.
+    pub async fn load(&self, pool: &PgPool) -> sqlx::Result<HashMap<String, Vec<OhlcvJoined>>> {
+        // Warm-up is in bars; weekends and holidays need ~1.5 calendar days each
+        let fetch_start = self.start - chrono::Duration::days(self.warmup * 3 / 2 + 10);
+        let start_time = fetch_start.and_hms_opt(0, 0, 0).map(|t| t.and_utc());
+        let end_time = self.end.and_hms_opt(23, 59, 59).map(|t| t.and_utc());
+        let opts = FetchOptions {
+            start_time,
+            end_time,
+            with_ma: !self.ma_periods.is_empty(),
+            use_ema: self.ema,
+            ma_periods: &self.ma_periods,
+            indicators: &self.indicators,
+            adjust: self.adjust,
+            ..Default::default()
+        };
+        ohlcv::get_ohlcv_joined_batch(pool, self.source, &self.symbols, "1D", &opts).await
+    }
+
+    /// Simulate the strategy over `data` (bars newest first, per ticker).
+    pub fn run(&self, data: &HashMap<String, Vec<OhlcvJoined>>) -> BacktestReport {
+        Simulation::new(self, data).run()
+    }
+}
+
+// ── Simulation ──
+
+#[derive(Clone, Copy, PartialEq)]
+enum Side {
+    Buy,
+    Sell,
+}
+
+/// Open price clamped into the band around `prev_close`, or None when the bar
+/// is locked at the limit against `side`.
+fn fill_price(bar: &OhlcvJoined, prev_close: Option<f64>, band_pct: Option<f64>, side: Side) -> Option<f64> {
+    let (Some(band), Some(prev)) = (band_pct, prev_close.filter(|p| *p > 0.0)) else {
+        return Some(bar.open);
+    };
+    let floor = prev * (1.0 - band / 100.0);
+    let ceiling = prev * (1.0 + band / 100.0);
+    let locked = match side {
+        Side::Buy => bar.low >= ceiling * (1.0 - LOCK_TOLERANCE),
+        Side::Sell => bar.high <= floor * (1.0 + LOCK_TOLERANCE),
+    };
+    (!locked).then(|| bar.open.clamp(floor, ceiling))
+}
+
+struct Position {
+    symbol: String,
+    entry_day: usize,
+    entry_date: NaiveDate,
+    entry_price: f64,
+    quantity: f64,
+    /// Price plus buy fee.
+    cost: f64,
+    last_close: f64,
+}
+
+struct Simulation<'a> {
+    plan: &'a Plan,
+    data: &'a HashMap<String, Vec<OhlcvJoined>>,
+    /// Trading days in range, oldest first.
+    days: Vec<NaiveDate>,
+    /// Per ticker: date -> index into its newest-first bars.
+    index: HashMap<&'a str, HashMap<NaiveDate, usize>>,
+    cash: f64,
+    /// Sale proceeds not settled yet: (day index they settle on, amount).
+    unsettled: Vec<(usize, f64)>,
+    positions: Vec<Position>,
+    pending_buys: Vec<String>,
+    pending_sells: Vec<(String, &'static str)>,
+    trades: Vec<Trade>,
+    curve: Vec<EquityPoint>,
+    blocked: Blocked,
+    fees_paid: f64,
+    taxes_paid: f64,
+}
+
+impl<'a> Simulation<'a> {
+    fn new(plan: &'a Plan, data: &'a HashMap<String, Vec<OhlcvJoined>>) -> Self {
+        let mut days = BTreeSet::new();
+        let mut index = HashMap::new();
+        for (ticker, bars) in data {
+            let dates: HashMap<NaiveDate, usize> = bars.iter().enumerate().map(|(i, b)| (b.time.date_naive(), i)).collect();
+            days.extend(dates.keys().copied().filter(|d| (plan.start..=plan.end).contains(d)));
+            index.insert(ticker.as_str(), dates);
+        }
+        Self {
+            plan,
+            data,
+            days: days.into_iter().collect(),
+            index,
+            cash: plan.sizing.initial_capital,
+            unsettled: Vec::new(),
+            positions: Vec::new(),
+            pending_buys: Vec::new(),
+            pending_sells: Vec::new(),
+            trades: Vec::new(),
+            curve: Vec::new(),
+            blocked: Blocked::default(),
+            fees_paid: 0.0,
+            taxes_paid: 0.0,
+        }
+    }
+
+    /// `ticker`'s bars from `date` backwards, if it traded that day.
+    fn bars_at(&self, ticker: &str, date: NaiveDate) -> Option<&'a [OhlcvJoined]> {
+        let i = *self.index.get(ticker)?.get(&date)?;
+        Some(&self.data[ticker][i..])
+    }
+
+    fn sector(&self, ticker: &str, date: NaiveDate) -> Option<String> {
+        get_ticker_sector(ticker, &self.plan.universe_on(date).groups)
+    }
+
+    fn run(mut self) -> BacktestReport {
+        let mut peak = self.plan.sizing.initial_capital;
+        let mut equity = peak;
+        for day in 0..self.days.len() {
+            let date = self.days[day];
+            let settled: f64 = self.unsettled.iter().filter(|(d, _)| *d <= day).map(|(_, a)| a).sum();
+            self.unsettled.retain(|(d, _)| *d > day);
+            self.cash += settled;
+
+            self.fill_sells(day, date);
+            self.fill_buys(day, date, equity);
+
+            for pos in &mut self.positions {
+                if let Some(i) = self.index.get(pos.symbol.as_str()).and_then(|m| m.get(&date)) {
+                    pos.last_close = self.data[&pos.symbol][*i].close;
+                }
+            }
+            equity = self.cash
+                + self.unsettled.iter().map(|(_, a)| a).sum::<f64>()
+                + self.positions.iter().map(|p| p.quantity * p.last_close).sum::<f64>();
+            peak = peak.max(equity);
+            self.curve.push(EquityPoint {
+                date: date.to_string(),
+                equity,
+                cash: self.cash,
+                positions: self.positions.len(),
+                drawdown_pct: if peak > 0.0 { (peak - equity) / peak * 100.0 } else { 0.0 },
+            });
+
+            self.signal(date);
+        }
+        self.report()
+    }
+
+    fn fill_sells(&mut self, day: usize, date: NaiveDate) {
+        let plan = self.plan;
+        let market = &plan.market;
+        let mut retry = Vec::new();
+        for (symbol, reason) in std::mem::take(&mut self.pending_sells) {
+            let Some(p) = self.positions.iter().position(|p| p.symbol == symbol) else { continue };
+            let Some(bars) = self.bars_at(&symbol, date) else {
+                // Not traded today (suspended, missing bar)
+                retry.push((symbol, reason));
+                continue;
+            };
+            if day < self.positions[p].entry_day + market.settlement_days {
+                self.blocked.settlement += 1;
+                retry.push((symbol, reason));
+                continue;
+            }
+            let band = market.price_band_for(plan.exchanges.get(&symbol).copied());
+            let Some(price) = fill_price(&bars[0], bars.get(1).map(|b| b.close), band, Side::Sell) else {
+                self.blocked.price_band += 1;
+                retry.push((symbol, reason));
+                continue;
+            };
+
+            let pos = self.positions.remove(p);
+            let gross = pos.quantity * price;
+            let fee = gross * market.fee_pct / 100.0;
+            let tax = gross * market.sell_tax_pct / 100.0;
+            let proceeds = gross - fee - tax;
+            self.fees_paid += fee;
+            self.taxes_paid += tax;
+            if market.settlement_days == 0 {
+                self.cash += proceeds;
+            } else {
+                self.unsettled.push((day + market.settlement_days, proceeds));
+            }
+            let pnl = proceeds - pos.cost;
+            self.trades.push(Trade {
+                sector: self.sector(&pos.symbol, date),
+                symbol: pos.symbol,
+                entry_date: pos.entry_date.to_string(),
+                entry_price: pos.entry_price,
+                quantity: pos.quantity,
+                exit_date: Some(date.to_string()),
+                exit_price: price,
+                pnl,
+                return_pct: pnl / pos.cost * 100.0,
+                exit_reason: reason.to_string(),
+                bars_held: day - pos.entry_day,
+            });
+        }
+        self.pending_sells = retry;
+    }
+
+    fn fill_buys(&mut self, day: usize, date: NaiveDate, equity: f64) {
+        let plan = self.plan;
+        let market = &plan.market;
+        for symbol in std::mem::take(&mut self.pending_buys) {
+            if self.positions.len() >= plan.sizing.max_positions {
+                break;
+            }
+            let Some(bars) = self.bars_at(&symbol, date) else { continue };
+            let band = market.price_band_for(plan.exchanges.get(&symbol).copied());
+            let Some(price) = fill_price(&bars[0], bars.get(1).map(|b| b.close), band, Side::Buy) else {
+                self.blocked.price_band += 1;
+                continue;
+            };
+            if price <= 0.0 {
+                continue;
+            }
+
+            let unit_cost = price * (1.0 + market.fee_pct / 100.0);
+            let target = equity * plan.sizing.position_pct / 100.0;
+            let raw = target.min(self.cash) / unit_cost;
+            let quantity = match market.lot_size {
+                Some(lot) => (raw / lot).floor() * lot,
+                None => raw,
+            };
+            if quantity <= 0.0 {
+                let one_lot = unit_cost * market.lot_size.unwrap_or(0.0);
+                if target >= one_lot {
+                    self.blocked.cash += 1;
+                } else {
+                    self.blocked.lot_size += 1;
+                }
+                continue;
+            }
+
+            let fee = quantity * price * market.fee_pct / 100.0;
+            let cost = quantity * price + fee;
+            self.cash -= cost;
+            self.fees_paid += fee;
+            self.positions.push(Position {
+                symbol,
+                entry_day: day,
+                entry_date: date,
+                entry_price: price,
+                quantity,
+                cost,
+                last_close: bars[0].close,
+            });
+        }
+    }
+
+    /// Queue exits for held positions and entries for the free slots.
+    fn signal(&mut self, date: NaiveDate) {
+        let plan = self.plan;
+        for pos in &self.positions {
+            if self.pending_sells.iter().any(|(s, _)| *s == pos.symbol) {
+                continue;
+            }
+            let Some(bars) = self.bars_at(&pos.symbol, date) else { continue };
+            let close = bars[0].close;
+            let reason = if plan.stop_loss_pct.is_some_and(|sl| close <= pos.entry_price * (1.0 - sl / 100.0)) {
+                Some("stop_loss")
+            } else if plan.take_profit_pct.is_some_and(|tp| close >= pos.entry_price * (1.0 + tp / 100.0)) {
+                Some("take_profit")
+            } else {
+                let sector = self.sector(&pos.symbol, date);
+                let row = Row { bars, sector: sector.as_deref(), source: plan.source };
+                plan.exit.matches(&row).map(|_| "exit")
+            };
+            if let Some(reason) = reason {
+                self.pending_sells.push((pos.symbol.clone(), reason));
+            }
+        }
+
+        let free = plan.sizing.max_positions.saturating_sub(self.positions.len());
+        if free == 0 {
+            self.pending_buys.clear();
+            return;
+        }
+        let mut candidates: Vec<(Option<f64>, &str)> = Vec::new();
+        for symbol in &plan.universe_on(date).symbols {
+            if self.positions.iter().any(|p| p.symbol == *symbol) {
+                continue;
+            }
+            let Some(bars) = self.bars_at(symbol, date) else { continue };
+            let sector = self.sector(symbol, date);
+            let row = Row { bars, sector: sector.as_deref(), source: plan.source };
+            if plan.entry.matches(&row).is_some() {
+                candidates.push((plan.rank_by.value(&row), symbol));
+            }
+        }
+        candidates.sort_by(|a, b| {
+            use std::cmp::Ordering;
+            let order = match (a.0, b.0) {
+                (Some(x), Some(y)) => y.partial_cmp(&x).unwrap_or(Ordering::Equal),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            };
+            order.then_with(|| a.1.cmp(b.1))
+        });
+        self.pending_buys = candidates.into_iter().take(free).map(|(_, s)| s.to_string()).collect();
+    }
+
+    fn report(mut self) -> BacktestReport {
+        let plan = self.plan;
+        let last_day = self.days.len().saturating_sub(1);
+        let last_date = self.days.last().copied().unwrap_or(plan.end);
+        let closed = self.trades.len();
+        let open_positions = self.positions.len();
+        for pos in std::mem::take(&mut self.positions) {
+            let pnl = pos.quantity * pos.last_close - pos.cost;
+            self.trades.push(Trade {
+                sector: self.sector(&pos.symbol, last_date),
+                symbol: pos.symbol,
+                entry_date: pos.entry_date.to_string(),
+                entry_price: pos.entry_price,
+                quantity: pos.quantity,
+                exit_date: None,
+                exit_price: pos.last_close,
+                pnl,
+                return_pct: pnl / pos.cost * 100.0,
+                exit_reason: "open".to_string(),
+                bars_held: last_day - pos.entry_day,
+            });
+        }
+
+        let initial = plan.sizing.initial_capital;
+        let final_equity = self.curve.last().map_or(initial, |p| p.equity);
+        let calendar_days = match (self.days.first(), self.days.last()) {
+            (Some(first), Some(last)) => (*last - *first).num_days(),
+            _ => 0,
+        };
+        let annualized_return_pct = (calendar_days > 0 && final_equity > 0.0)
+            .then(|| ((final_equity / initial).powf(365.0 / calendar_days as f64) - 1.0) * 100.0);
+
+        let closed_trades = &self.trades[..closed];
+        let wins = closed_trades.iter().filter(|t| t.pnl > 0.0).count();
+        let periods_per_year = if plan.source == "crypto" { 365.0 } else { 252.0 };
+        let summary = Summary {
+            initial_capital: initial,
+            final_equity,
+            total_return_pct: (final_equity / initial - 1.0) * 100.0,
+            annualized_return_pct,
+            max_drawdown_pct: self.curve.iter().map(|p| p.drawdown_pct).fold(0.0, f64::max),
+            sharpe: sharpe(&self.curve, periods_per_year),
+            trades: closed,
+            open_positions,
+            win_rate_pct: (closed > 0).then(|| wins as f64 / closed as f64 * 100.0),
+            avg_trade_return_pct: (closed > 0)
+                .then(|| closed_trades.iter().map(|t| t.return_pct).sum::<f64>() / closed as f64),
+            fees_paid: self.fees_paid,
+            taxes_paid: self.taxes_paid,
+            blocked: self.blocked,
+        };
+
+        BacktestReport {
+            source: plan.source.to_string(),
+            entry: plan.entry.to_string(),
+            exit: plan.exit.to_string(),
+            rank_by: plan.rank_by.to_string(),
+            start_date: plan.start.to_string(),
+            end_date: plan.end.to_string(),
+            universe: plan.symbols.len(),
+            sizing: plan.sizing.clone(),
+            market: plan.market.clone(),
+            summary,
+            equity_curve: self.curve,
+            trades: self.trades,
+        }
+    }
+}
+
+/// Annualized Sharpe ratio of the daily equity returns.
+fn sharpe(curve: &[EquityPoint], periods_per_year: f64) -> Option<f64> {
+    let returns: Vec<f64> = curve
+        .windows(2)
+        .filter(|w| w[0].equity > 0.0)
+        .map(|w| w[1].equity / w[0].equity - 1.0)
+        .collect();
+    if returns.len() < 2 {
+        return None;
+    }
+    let n = returns.len() as f64;
+    let mean = returns.iter().sum::<f64>() / n;
+    let var = returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (n - 1.0);
+    let sd = var.sqrt();
+    (sd > 0.0).then(|| mean / sd * periods_per_year.sqrt())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bar(ticker: &str, date: NaiveDate, (open, high, low, close): (f64, f64, f64, f64)) -> OhlcvJoined {
+        OhlcvJoined {
+            ticker: ticker.to_string(),
+            time: date.and_hms_opt(0, 0, 0).unwrap().and_utc(),
+            open,
+            high,
+            low,
+            close,
+            volume: 1000,
+            ma10: None,
+            ma20: None,
+            ma50: None,
+            ma100: None,
+            ma200: None,
+            ma10_score: None,
+            ma20_score: None,
+            ma50_score: None,
+            ma100_score: None,
+            ma200_score: None,
+            close_changed: None,
+            volume_changed: None,
+            total_money_changed: None,
+            indicators: Default::default(),
+        }
+    }
+
+    /// Daily bars from `(open, high, low, close)` starting at `start`, newest first.
+    fn bars(ticker: &str, start: NaiveDate, ohlc: &[(f64, f64, f64, f64)]) -> Vec<OhlcvJoined> {
+        let mut out: Vec<OhlcvJoined> = ohlc
+            .iter()
+            .enumerate()
+            .map(|(i, &prices)| bar(ticker, start + chrono::Duration::days(i as i64), prices))
+            .collect();
+        out.reverse();
+        out
+    }
+
+    fn flat(price: f64) -> (f64, f64, f64, f64) {
+        (price, price, price, price)
+    }
+
+    fn plan(entry: &str, exit: &str, market: MarketOverrides, sizing: SizingOverrides) -> Plan {
+        let req = BacktestRequest {
+            strategy: Strategy::Rules { entry: entry.to_string(), exit: exit.to_string() },
+            mode: Mode::Vn,
+            groups: Vec::new(),
+            symbols: vec!["AAA".to_string(), "BBB".to_string()],
+            start_date: "2025-01-01".to_string(),
+            end_date: Some("2025-12-31".to_string()),
+            rank_by: default_rank_by(),
+            ema: false,
+            adjust: Adjustment::Raw,
+            sizing,
+            market,
+            stop_loss_pct: None,
+            take_profit_pct: None,
+        };
+        Plan::new(&req, |_, _, _| Vec::new()).unwrap()
+    }
+
+    fn day(n: i64) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 1, 1).unwrap() + chrono::Duration::days(n)
+    }
+
+    #[test]
+    fn test_strategy_shorthands_expand_to_rules() {
+        let (entry, exit) = Strategy::MaCross { fast: 20, slow: 50 }.rules().unwrap();
+        assert_eq!(entry, "ma20 > ma50 and prev(ma20, 1) <= prev(ma50, 1)");
+        assert_eq!(exit, "ma20 < ma50");
+        assert!(Strategy::MaCross { fast: 50, slow: 20 }.rules().is_err());
+        let rsi = Strategy::RsiReversion { period: 14, oversold: 30.0, overbought: 70.0 };
+        assert_eq!(rsi.rules().unwrap(), ("rsi14 < 30".to_string(), "rsi14 > 70".to_string()));
+    }
+
+    #[test]
+    fn test_vn_market_defaults() {
+        let market = Market::resolve("vn", &MarketOverrides::default()).unwrap();
+        assert_eq!(market.settlement_days, 2);
+        assert!((market.price_band_pct.unwrap() - 7.0).abs() < 1e-9);
+        assert_eq!(market.lot_size, Some(100.0));
+        assert_eq!(market.sell_tax_pct, cfg::VN_SELL_TAX_PCT);
+        let crypto = Market::resolve("crypto", &MarketOverrides { lot_size: Some(0.0), ..Default::default() }).unwrap();
+        assert_eq!((crypto.settlement_days, crypto.price_band_pct, crypto.lot_size), (0, None, None));
+    }
+
+    #[test]
+    fn test_fill_price_clamps_and_detects_locks() {
+        let bar = |open: f64, high: f64, low: f64| bar("AAA", day(0), (open, high, low, open));
+        // Open outside the band is clamped
+        assert_eq!(fill_price(&bar(120.0, 120.0, 100.0), Some(100.0), Some(7.0), Side::Sell), Some(107.0));
+        // Locked at the ceiling: no sellers, buy does not fill
+        assert_eq!(fill_price(&bar(107.0, 107.0, 107.0), Some(100.0), Some(7.0), Side::Buy), None);
+        assert_eq!(fill_price(&bar(107.0, 107.0, 107.0), Some(100.0), Some(7.0), Side::Sell), Some(107.0));
+        // Locked at the floor: sell does not fill
+        assert_eq!(fill_price(&bar(93.0, 93.0, 93.0), Some(100.0), Some(7.0), Side::Sell), None);
+        // No band
+        assert_eq!(fill_price(&bar(120.0, 120.0, 120.0), Some(100.0), None, Side::Buy), Some(120.0));
+    }
+
+    #[test]
+    fn test_t2_settlement_blocks_early_sell_and_lots_round_down() {
+        // Entry on day 0's close, fill day 1; exit fires every day from day 1,
+        // but the shares only become sellable on day 3 (T+2).
+        let plan = plan(
+            "close > 0 and open == 10",
+            "close > 0",
+            MarketOverrides { price_band_pct: Some(0.0), fee_pct: Some(0.0), sell_tax_pct: Some(0.0), ..Default::default() },
+            SizingOverrides { initial_capital: Some(10_000.0), position_pct: Some(15.0), max_positions: Some(1) },
+        );
+        let data = HashMap::from([(
+            "AAA".to_string(),
+            bars("AAA", day(0), &[flat(10.0), flat(11.0), flat(11.0), flat(12.0), flat(12.0)]),
+        )]);
+        let report = plan.run(&data);
+
+        let trade = &report.trades[0];
+        // 1500 budget / 11 = 136 shares -> one lot of 100
+        assert_eq!(trade.quantity, 100.0);
+        assert_eq!(trade.entry_date, day(1).to_string());
+        assert_eq!(trade.exit_date.as_deref(), Some(day(3).to_string().as_str()));
+        assert_eq!(trade.bars_held, 2);
+        assert_eq!(trade.pnl, 100.0);
+        assert_eq!(report.summary.blocked.settlement, 1);
+        // Proceeds are unsettled on day 3 but still count towards equity
+        assert_eq!(report.equity_curve[3].cash, 10_000.0 - 1100.0);
+        assert_eq!(report.summary.final_equity, 10_100.0);
+        assert_eq!(report.summary.win_rate_pct, Some(100.0));
+    }
+
+    #[test]
+    fn test_ceiling_lock_blocks_entry_and_small_budget_blocks_lot() {
+        let market = MarketOverrides { fee_pct: Some(0.0), ..Default::default() };
+        let plan_locked = plan("close > 0", "close < 0", market.clone(), SizingOverrides { max_positions: Some(1), ..Default::default() });
+        // Day 1 opens and stays at the +7% ceiling
+        let data = HashMap::from([("AAA".to_string(), bars("AAA", day(0), &[flat(100.0), flat(107.0)]))]);
+        let report = plan_locked.run(&data);
+        assert!(report.trades.is_empty());
+        assert_eq!(report.summary.blocked.price_band, 1);
+
+        let plan_small = plan(
+            "close > 0",
+            "close < 0",
+            market,
+            SizingOverrides { initial_capital: Some(5_000.0), max_positions: Some(1), ..Default::default() },
+        );
+        let data = HashMap::from([("AAA".to_string(), bars("AAA", day(0), &[flat(100.0), flat(100.0)]))]);
+        let report = plan_small.run(&data);
+        assert!(report.trades.is_empty());
+        assert_eq!(report.summary.blocked.lot_size, 1);
+    }
+
+    #[test]
+    fn test_price_band_follows_listing_exchange() {
+        let market = MarketOverrides { fee_pct: Some(0.0), ..Default::default() };
+        let sizing = SizingOverrides { max_positions: Some(1), ..Default::default() };
+        // Day 1 gaps up 12% and stays there: inside UPCOM's 15% band, at the
+        // ceiling of HNX's 10% and beyond HOSE's 7%
+        let data = HashMap::from([("AAA".to_string(), bars("AAA", day(0), &[flat(100.0), flat(112.0)]))]);
+
+        let mut upcom = plan("close > 0", "close < 0", market.clone(), sizing.clone());
+        upcom.exchanges.insert("AAA".to_string(), Exchange::Upcom);
+        let report = upcom.run(&data);
+        assert_eq!(report.trades[0].entry_price, 112.0);
+
+        let mut hnx = plan("close > 0", "close < 0", market.clone(), sizing.clone());
+        hnx.exchanges.insert("AAA".to_string(), Exchange::Hnx);
+        let data_hnx = HashMap::from([("AAA".to_string(), bars("AAA", day(0), &[flat(100.0), (109.0, 109.0, 105.0, 109.0)]))]);
+        assert_eq!(hnx.run(&data_hnx).trades[0].entry_price, 109.0);
+        // HOSE would have clamped the same open to 107
+        let hose = plan("close > 0", "close < 0", market, sizing);
+        assert_eq!(hose.run(&data_hnx).trades[0].entry_price, 107.0);
+
+        // An explicit band applies to every exchange
+        let overridden = Market::resolve("vn", &MarketOverrides { price_band_pct: Some(7.0), ..Default::default() }).unwrap();
+        assert_eq!(overridden.price_band_for(Some(Exchange::Upcom)), Some(7.0));
+        assert_eq!(Exchange::from_code("hnx"), Some(Exchange::Hnx));
+    }
+
+    #[test]
+    fn test_universe_follows_group_membership() {
+        let req = BacktestRequest {
+            strategy: Strategy::Rules { entry: "close > 0".to_string(), exit: "close < 0".to_string() },
+            mode: Mode::Vn,
+            groups: vec!["BANK".to_string()],
+            symbols: Vec::new(),
+            start_date: "2025-01-01".to_string(),
+            end_date: Some("2025-12-31".to_string()),
+            rank_by: default_rank_by(),
+            ema: false,
+            adjust: Adjustment::Raw,
+            sizing: SizingOverrides::default(),
+            market: MarketOverrides { fee_pct: Some(0.0), ..Default::default() },
+            stop_loss_pct: None,
+            take_profit_pct: None,
+        };
+        // BBB joins BANK on day 3; AAA leaves it then
+        let history = |_: &str, _: NaiveDate, _: NaiveDate| {
+            vec![
+                (day(0), BTreeMap::from([("BANK".to_string(), vec!["AAA".to_string()])])),
+                (day(3), BTreeMap::from([("BANK".to_string(), vec!["BBB".to_string()])])),
+            ]
+        };
+        let plan = Plan::new(&req, history).unwrap();
+        assert_eq!(plan.symbols(), ["AAA", "BBB"]);
+
+        let data = HashMap::from([("BBB".to_string(), bars("BBB", day(0), &[flat(100.0); 6]))]);
+        let report = plan.run(&data);
+        // First signal on day 3, filled at day 4's open
+        assert_eq!(report.trades.len(), 1);
+        assert_eq!(report.trades[0].entry_date, day(4).to_string());
+        assert_eq!(report.trades[0].sector.as_deref(), Some("BANK"));
+
+        let unknown = BacktestRequest { groups: vec!["STEEL".to_string()], ..req };
+        assert_eq!(Plan::new(&unknown, history).unwrap_err(), "Unknown group 'STEEL'");
+    }
+
+    #[test]
+    fn test_ranking_stop_loss_and_metrics() {
+        let mut plan = plan(
+            "close > 0",
+            "close < 0",
+            MarketOverrides { fee_pct: Some(0.0), sell_tax_pct: Some(0.0), ..Default::default() },
+            SizingOverrides { initial_capital: Some(100_000.0), position_pct: Some(50.0), max_positions: Some(1) },
+        );
+        plan.stop_loss_pct = Some(5.0);
+        // BBB trades more value, so it wins the single slot; it then drops 6%
+        let mut bbb = bars("BBB", day(0), &[flat(100.0), flat(100.0), flat(94.0), flat(94.0), flat(94.0), flat(94.0)]);
+        for b in &mut bbb {
+            b.volume = 5000;
+        }
+        let data = HashMap::from([
+            ("AAA".to_string(), bars("AAA", day(0), &[flat(100.0); 6])),
+            ("BBB".to_string(), bbb),
+        ]);
+        let report = plan.run(&data);
+
+        let first = &report.trades[0];
+        assert_eq!((first.symbol.as_str(), first.exit_reason.as_str()), ("BBB", "stop_loss"));
+        assert_eq!(first.exit_date.as_deref(), Some(day(3).to_string().as_str()));
+        assert_eq!(first.pnl, -3000.0);
+        assert_eq!(report.summary.trades, 1);
+        assert_eq!(report.summary.win_rate_pct, Some(0.0));
+        assert!((report.summary.max_drawdown_pct - 3.0).abs() < 1e-9);
+        // The slot frees up and is refilled once the exit has filled
+        assert_eq!(report.trades.last().unwrap().exit_reason, "open");
+        assert_eq!(report.summary.open_positions, 1);
+    }
+}
@@ -0,0 +1,219 @@
+//! Market breadth for `/analysis/breadth`.
+//!
+//! Each stock with a daily bar on a date counts as advancing, declining or
+//! unchanged against its previous bar, adds its volume to the up or down
+//! side, is checked against its 20/50/200-day SMA and flags a new 52-week
+//! high (low) when the bar's high (low) exceeds the previous 252 bars. Prices
+//! are split-adjusted so corporate actions do not show up as new lows.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use chrono::{DateTime, NaiveDate, Utc};
+use sqlx::PgPool;
+
+use crate::constants::breadth::{HIGH_LOW_WINDOW, MA_PERIODS, MARKET};
+use crate::models::corporate_action::Adjustment;
+use crate::models::ohlcv::OhlcvRow;
+use crate::queries::breadth::BreadthDay;
+use crate::services::ohlcv;
+
+/// Bars needed before the first computed date.
+pub const WARMUP_BARS: i64 = HIGH_LOW_WINDOW as i64 + 1;
+
+/// One stock on one date.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct StockDay {
+    /// Sign of the close-to-close change; `None` on the first bar.
+    change: Option<std::cmp::Ordering>,
+    volume: i64,
+    /// `Some(close > sma)` per `MA_PERIODS` entry once there is enough history.
+    above_ma: [Option<bool>; 3],
+    new_high: bool,
+    new_low: bool,
+}
+
+fn stock_days(rows: &[&OhlcvRow], since: NaiveDate) -> Vec<(NaiveDate, StockDay)> {
+    let closes: Vec<f64> = rows.iter().map(|r| r.close).collect();
+    let mut out = Vec::new();
+    for (i, row) in rows.iter().enumerate() {
+        let date = row.time.date_naive();
+        if date < since {
+            continue;
+        }
+        let mut day = StockDay {
+            change: i.checked_sub(1).and_then(|p| row.close.partial_cmp(&closes[p])),
+            volume: row.volume,
+            ..Default::default()
+        };
+        for (slot, &period) in day.above_ma.iter_mut().zip(MA_PERIODS.iter()) {
+            if i + 1 >= period {
+                let sma = closes[i + 1 - period..=i].iter().sum::<f64>() / period as f64;
+                *slot = Some(row.close > sma);
+            }
+        }
+        if i >= HIGH_LOW_WINDOW {
+            let prior = &rows[i - HIGH_LOW_WINDOW..i];
+            day.new_high = prior.iter().all(|r| row.high > r.high);
+            day.new_low = prior.iter().all(|r| row.low < r.low);
+        }
+        out.push((date, day));
+    }
+    out
+}
+
+impl BreadthDay {
+    fn add(&mut self, day: &StockDay) {
+        self.total += 1;
+        match day.change {
+            Some(std::cmp::Ordering::Greater) => {
+                self.advances += 1;
+                self.up_volume += day.volume;
+            }
+            Some(std::cmp::Ordering::Less) => {
+                self.declines += 1;
+                self.down_volume += day.volume;
+            }
+            Some(std::cmp::Ordering::Equal) => self.unchanged += 1,
+            None => {}
+        }
+        let counters = [
+            (&mut self.above_ma20, &mut self.with_ma20),
+            (&mut self.above_ma50, &mut self.with_ma50),
+            (&mut self.above_ma200, &mut self.with_ma200),
+        ];
+        for ((above, with), flag) in counters.into_iter().zip(day.above_ma) {
+            if let Some(is_above) = flag {
+                *with += 1;
+                *above += is_above as i32;
+            }
+        }
+        self.new_highs += day.new_high as i32;
+        self.new_lows += day.new_low as i32;
+    }
+}
+
+/// Dates with at least one bar, oldest first.
+pub fn trading_dates(rows: &HashMap<String, Vec<OhlcvRow>>) -> BTreeSet<NaiveDate> {
+    rows.values().flatten().map(|r| r.time.date_naive()).collect()
+}
+
+/// Breadth per sector of `groups` and for the whole market (the union of all
+/// groups, `sector = "ALL"`) on every date from `since` on. Rows may come in
+/// any order.
+pub fn compute(
+    groups: &BTreeMap<String, Vec<String>>,
+    rows: &HashMap<String, Vec<OhlcvRow>>,
+    since: NaiveDate,
+) -> Vec<BreadthDay> {
+    let per_stock: HashMap<&str, HashMap<NaiveDate, StockDay>> = rows
+        .iter()
+        .map(|(ticker, ticker_rows)| {
+            let mut sorted: Vec<&OhlcvRow> = ticker_rows.iter().filter(|r| r.close > 0.0).collect();
+            sorted.sort_by_key(|r| r.time);
+            (ticker.as_str(), stock_days(&sorted, since).into_iter().collect())
+        })
+        .collect();
+
+    let market: BTreeSet<&str> = groups.values().flatten().map(String::as_str).collect();
+    let members = groups
+        .iter()
+        .map(|(sector, tickers)| (sector.as_str(), tickers.iter().map(String::as_str).collect::<BTreeSet<_>>()))
+        .chain(std::iter::once((MARKET, market)));
+
+    let mut out = Vec::new();
+    for (sector, tickers) in members {
+        let mut by_date: BTreeMap<NaiveDate, BreadthDay> = BTreeMap::new();
+        for ticker in tickers {
+            for (date, day) in per_stock.get(ticker).into_iter().flatten() {
+                by_date
+                    .entry(*date)
+                    .or_insert_with(|| BreadthDay { sector: sector.to_string(), date: *date, ..Default::default() })
+                    .add(day);
+            }
+        }
+        out.extend(by_date.into_values());
+    }
+    out
+}
+
+/// Latest `bars` split-adjusted daily bars per ticker up to `end_time`.
+pub async fn load(
+    pool: &PgPool,
+    source: &str,
+    symbols: &[String],
+    bars: i64,
+    end_time: Option<DateTime<Utc>>,
+) -> sqlx::Result<HashMap<String, Vec<OhlcvRow>>> {
+    let mut rows = ohlcv::get_ohlcv_batch_raw(pool, source, symbols, "1D", Some(bars), None, end_time).await?;
+    ohlcv::adjust_raw_batch(pool, source, &[], symbols, &mut rows, Adjustment::Split).await?;
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn bars(closes: &[f64]) -> Vec<OhlcvRow> {
+        closes
+            .iter()
+            .enumerate()
+            .map(|(i, &c)| OhlcvRow {
+                ticker_id: 1,
+                interval: "1D".to_string(),
+                time: Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap() + chrono::Duration::days(i as i64),
+                open: c,
+                high: c,
+                low: c,
+                close: c,
+                volume: 100,
+            })
+            .collect()
+    }
+
+    fn last_date(n: usize) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 1, 1).unwrap() + chrono::Duration::days(n as i64 - 1)
+    }
+
+    #[test]
+    fn test_advance_decline_and_volume() {
+        let mut rows = HashMap::new();
+        rows.insert("AAA".to_string(), bars(&[10.0, 11.0, 12.0]));
+        rows.insert("BBB".to_string(), bars(&[10.0, 9.0, 8.0]));
+        rows.insert("CCC".to_string(), bars(&[10.0, 10.0, 10.0]));
+        let groups = BTreeMap::from([
+            ("UP".to_string(), vec!["AAA".to_string()]),
+            ("REST".to_string(), vec!["BBB".to_string(), "CCC".to_string()]),
+        ]);
+        let days = compute(&groups, &rows, last_date(3));
+        let market = days.iter().find(|d| d.sector == MARKET).unwrap();
+        assert_eq!((market.total, market.advances, market.declines, market.unchanged), (3, 1, 1, 1));
+        assert_eq!((market.up_volume, market.down_volume), (100, 100));
+        let rest = days.iter().find(|d| d.sector == "REST").unwrap();
+        assert_eq!((rest.total, rest.advances, rest.declines), (2, 0, 1));
+        // Only dates from `since` on
+        assert_eq!(days.len(), 3);
+        // Not enough history for any MA or the 52-week window
+        assert_eq!((market.with_ma20, market.new_highs), (0, 0));
+    }
+
+    #[test]
+    fn test_ma_and_52_week_extremes() {
+        let n = HIGH_LOW_WINDOW + 1;
+        let rising: Vec<f64> = (0..n).map(|i| 100.0 + i as f64).collect();
+        let falling: Vec<f64> = (0..n).map(|i| 500.0 - i as f64).collect();
+        let rows = HashMap::from([("UP".to_string(), bars(&rising)), ("DOWN".to_string(), bars(&falling))]);
+        let groups = BTreeMap::from([("G".to_string(), vec!["UP".to_string(), "DOWN".to_string()])]);
+        let days = compute(&groups, &rows, last_date(n));
+        let market = days.iter().find(|d| d.sector == MARKET).unwrap();
+        assert_eq!((market.with_ma20, market.with_ma50, market.with_ma200), (2, 2, 2));
+        assert_eq!((market.above_ma20, market.above_ma50, market.above_ma200), (1, 1, 1));
+        assert_eq!((market.new_highs, market.new_lows), (1, 1));
+    }
+
+    #[test]
+    fn test_trading_dates_union() {
+        let rows = HashMap::from([("A".to_string(), bars(&[1.0, 2.0])), ("B".to_string(), bars(&[1.0, 2.0, 3.0]))]);
+        assert_eq!(trading_dates(&rows).len(), 3);
+    }
+}
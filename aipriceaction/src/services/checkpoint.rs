@@ -189,6 +189,9 @@ pub async fn import_checkpoint(
                 }
             }
 
+            // Bars rejected by a validation rule go to ohlcv_quarantine instead.
+            let rows = crate::services::validation::validate_rows(pool, &source_cp.source, &ticker_cp.ticker, rows).await;
+
             // Upsert in 10k batches
             for chunk in rows.chunks(10_000) {
                 bulk_upsert_ohlcv(pool, chunk)
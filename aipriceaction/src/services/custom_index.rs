@@ -0,0 +1,269 @@
+//! Synthetic custom index series (`source = "custom"`).
+//!
+//! Levels are chain-linked: each day the index moves by the weighted mean
+//! return of its members against their previous daily close, with weights
+//! taken at that close (equal, shares × close, or manual). A member joins
+//! the day after its first bar and counts as unchanged on days it has no
+//! bar. Intraday bars apply the same weights to each member's latest price
+//! of the (UTC) day, anchored at the index's previous daily close, so the
+//! last intraday close of a day matches the daily close. Volume is the sum
+//! of the members' volume.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use chrono::{DateTime, NaiveDate, Utc};
+
+use crate::models::custom_index::Weighting;
+use crate::models::ohlcv::OhlcvRow;
+use crate::providers::ohlcv::OhlcvData;
+
+/// One member's stored bars, oldest first.
+pub struct MemberBars<'a> {
+    /// `IndexMember::weight`
+    pub weight: Option<f64>,
+    pub daily: &'a [OhlcvRow],
+    pub intraday: &'a [OhlcvRow],
+}
+
+/// A member's move against its previous close within one index bar.
+struct Leg {
+    weight: f64,
+    prev_close: f64,
+    open: f64,
+    high: f64,
+    low: f64,
+    close: f64,
+    volume: i64,
+}
+
+impl Leg {
+    /// `bar`, or flat at `price` when the member has no bar at this time.
+    fn new(weighting: Weighting, weight: Option<f64>, prev_close: f64, bar: Option<&OhlcvRow>, price: f64) -> Self {
+        let weight = weighting.raw_weight(weight, prev_close);
+        match bar {
+            Some(b) => Leg { weight, prev_close, open: b.open, high: b.high, low: b.low, close: b.close, volume: b.volume },
+            None => Leg { weight, prev_close, open: price, high: price, low: price, close: price, volume: 0 },
+        }
+    }
+}
+
+/// Index bar at `time` from `anchor` (index level at the members' previous
+/// close). None when no member carries weight.
+fn combine(time: DateTime<Utc>, anchor: f64, legs: &[Leg]) -> Option<OhlcvData> {
+    let total: f64 = legs.iter().map(|l| l.weight).sum();
+    if total <= 0.0 || !total.is_finite() {
+        return None;
+    }
+    let level = |price: fn(&Leg) -> f64| {
+        anchor * legs.iter().map(|l| l.weight * price(l) / l.prev_close).sum::<f64>() / total
+    };
+    let (open, close) = (level(|l| l.open), level(|l| l.close));
+    Some(OhlcvData {
+        time,
+        open,
+        high: level(|l| l.high).max(open).max(close),
+        low: level(|l| l.low).min(open).min(close),
+        close,
+        volume: legs.iter().map(|l| l.volume.max(0) as u64).sum(),
+        symbol: None,
+    })
+}
+
+fn by_day(rows: &[OhlcvRow]) -> BTreeMap<NaiveDate, &OhlcvRow> {
+    rows.iter().filter(|r| r.close > 0.0).map(|r| (r.time.date_naive(), r)).collect()
+}
+
+/// Daily bars from the first trading day on or after `base_date` (None =
+/// the first day any member traded), which is set to `base_value`.
+pub fn daily_series(
+    weighting: Weighting,
+    members: &[MemberBars],
+    base_value: f64,
+    base_date: Option<NaiveDate>,
+) -> Vec<OhlcvData> {
+    let days_by_member: Vec<BTreeMap<NaiveDate, &OhlcvRow>> = members.iter().map(|m| by_day(m.daily)).collect();
+    let days: BTreeSet<NaiveDate> = days_by_member
+        .iter()
+        .flat_map(|m| m.keys().copied())
+        .filter(|d| base_date.is_none_or(|b| *d >= b))
+        .collect();
+    let Some(&first) = days.first() else {
+        return Vec::new();
+    };
+
+    // Closes before the base day, for members not trading on it
+    let mut prev: Vec<Option<f64>> =
+        days_by_member.iter().map(|m| m.range(..first).next_back().map(|(_, r)| r.close)).collect();
+    let mut level: Option<f64> = None;
+    let mut out = Vec::with_capacity(days.len());
+    for day in days {
+        let time = day.and_time(chrono::NaiveTime::MIN).and_utc();
+        let today: Vec<Option<&OhlcvRow>> = days_by_member.iter().map(|m| m.get(&day).copied()).collect();
+        let bar = match level {
+            None => Some(OhlcvData {
+                time,
+                open: base_value,
+                high: base_value,
+                low: base_value,
+                close: base_value,
+                volume: today.iter().flatten().map(|r| r.volume.max(0) as u64).sum(),
+                symbol: None,
+            }),
+            Some(anchor) => {
+                let legs: Vec<Leg> = members
+                    .iter()
+                    .zip(&prev)
+                    .zip(&today)
+                    .filter_map(|((m, pc), bar)| Some(Leg::new(weighting, m.weight, (*pc)?, *bar, (*pc)?)))
+                    .collect();
+                combine(time, anchor, &legs)
+            }
+        };
+        if let Some(bar) = bar {
+            level = Some(bar.close);
+            out.push(bar);
+        }
+        for (pc, bar) in prev.iter_mut().zip(&today) {
+            if let Some(bar) = bar {
+                *pc = Some(bar.close);
+            }
+        }
+    }
+    out
+}
+
+/// Intraday bars at the members' intraday timestamps from `since` on.
+/// `daily` is the index's daily series; days without a previous index close
+/// are skipped.
+pub fn intraday_series(
+    weighting: Weighting,
+    members: &[MemberBars],
+    daily: &[OhlcvData],
+    since: DateTime<Utc>,
+) -> Vec<OhlcvData> {
+    let days_by_member: Vec<BTreeMap<NaiveDate, &OhlcvRow>> = members.iter().map(|m| by_day(m.daily)).collect();
+    let index_close: BTreeMap<NaiveDate, f64> = daily.iter().map(|b| (b.time.date_naive(), b.close)).collect();
+    let mut times: BTreeMap<DateTime<Utc>, Vec<(usize, &OhlcvRow)>> = BTreeMap::new();
+    for (i, m) in members.iter().enumerate() {
+        for r in m.intraday.iter().filter(|r| r.time >= since) {
+            times.entry(r.time).or_default().push((i, r));
+        }
+    }
+
+    let mut out = Vec::new();
+    let mut current: Option<NaiveDate> = None;
+    let mut anchor: Option<f64> = None;
+    let mut prev: Vec<Option<f64>> = Vec::new();
+    let mut last: Vec<Option<f64>> = Vec::new();
+    for (time, bars) in times {
+        let day = time.date_naive();
+        if current != Some(day) {
+            current = Some(day);
+            anchor = index_close.range(..day).next_back().map(|(_, c)| *c);
+            prev = days_by_member.iter().map(|m| m.range(..day).next_back().map(|(_, r)| r.close)).collect();
+            last = vec![None; members.len()];
+        }
+        let Some(anchor) = anchor else {
+            continue;
+        };
+        let legs: Vec<Leg> = members
+            .iter()
+            .enumerate()
+            .filter_map(|(i, m)| {
+                let pc = prev[i]?;
+                let bar = bars.iter().find(|(j, _)| *j == i).map(|(_, r)| *r);
+                Some(Leg::new(weighting, m.weight, pc, bar, last[i].unwrap_or(pc)))
+            })
+            .collect();
+        for (i, r) in &bars {
+            last[*i] = Some(r.close);
+        }
+        if let Some(bar) = combine(time, anchor, &legs) {
+            out.push(bar);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn bar(day: u32, hour: u32, interval: &str, ohlc: [f64; 4], volume: i64) -> OhlcvRow {
+        OhlcvRow {
+            ticker_id: 1,
+            interval: interval.to_string(),
+            time: Utc.with_ymd_and_hms(2024, 1, day, hour, 0, 0).unwrap(),
+            open: ohlc[0],
+            high: ohlc[1],
+            low: ohlc[2],
+            close: ohlc[3],
+            volume,
+        }
+    }
+
+    fn daily(closes: &[(u32, f64)]) -> Vec<OhlcvRow> {
+        closes.iter().map(|&(d, c)| bar(d, 0, "1D", [c, c, c, c], 10)).collect()
+    }
+
+    fn member<'a>(weight: Option<f64>, daily: &'a [OhlcvRow], intraday: &'a [OhlcvRow]) -> MemberBars<'a> {
+        MemberBars { weight, daily, intraday }
+    }
+
+    fn closes(bars: &[OhlcvData]) -> Vec<f64> {
+        bars.iter().map(|b| (b.close * 1e6).round() / 1e6).collect()
+    }
+
+    #[test]
+    fn test_equal_weight_chain_links_daily_returns() {
+        let a = daily(&[(1, 10.0), (2, 11.0), (3, 11.0)]);
+        let b = daily(&[(1, 20.0), (2, 20.0), (3, 18.0)]);
+        let members = [member(None, &a, &[]), member(None, &b, &[])];
+        let series = daily_series(Weighting::Equal, &members, 1000.0, None);
+        // +10% / 0% → +5%, then 0% / -10% → -5%
+        assert_eq!(closes(&series), vec![1000.0, 1050.0, 997.5]);
+        assert_eq!(series[1].volume, 20);
+    }
+
+    #[test]
+    fn test_cap_and_manual_weights() {
+        let a = daily(&[(1, 10.0), (2, 11.0)]);
+        let b = daily(&[(1, 30.0), (2, 30.0)]);
+        // Caps 100×10 and 100×30: A is a quarter of the index
+        let cap = [member(Some(100.0), &a, &[]), member(Some(100.0), &b, &[])];
+        assert_eq!(closes(&daily_series(Weighting::Cap, &cap, 100.0, None)), vec![100.0, 102.5]);
+        let manual = [member(Some(3.0), &a, &[]), member(Some(1.0), &b, &[])];
+        assert_eq!(closes(&daily_series(Weighting::Manual, &manual, 100.0, None)), vec![100.0, 107.5]);
+    }
+
+    #[test]
+    fn test_late_member_and_base_date() {
+        let a = daily(&[(1, 10.0), (2, 10.0), (3, 10.0), (4, 10.0)]);
+        // B lists on day 3 and doubles on day 4
+        let b = daily(&[(3, 5.0), (4, 10.0)]);
+        let members = [member(None, &a, &[]), member(None, &b, &[])];
+        let series = daily_series(Weighting::Equal, &members, 1000.0, None);
+        assert_eq!(closes(&series), vec![1000.0, 1000.0, 1000.0, 1500.0]);
+
+        let base = NaiveDate::from_ymd_opt(2024, 1, 4);
+        let rebased = daily_series(Weighting::Equal, &members, 1000.0, base);
+        assert_eq!(closes(&rebased), vec![1000.0]);
+    }
+
+    #[test]
+    fn test_intraday_ends_at_daily_close() {
+        let a = daily(&[(1, 10.0), (2, 12.0)]);
+        let b = daily(&[(1, 10.0), (2, 9.0)]);
+        let a_hours = [bar(2, 2, "1h", [10.0, 11.0, 10.0, 11.0], 5), bar(2, 3, "1h", [11.0, 12.0, 11.0, 12.0], 5)];
+        // B trades only in the second hour
+        let b_hours = [bar(2, 3, "1h", [10.0, 10.0, 9.0, 9.0], 7)];
+        let members = [member(None, &a, &a_hours), member(None, &b, &b_hours)];
+        let days = daily_series(Weighting::Equal, &members, 1000.0, None);
+        let hours = intraday_series(Weighting::Equal, &members, &days, Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap());
+        assert_eq!(closes(&hours), vec![1050.0, 1050.0]);
+        assert_eq!(hours.last().unwrap().close, days.last().unwrap().close);
+        assert_eq!(hours[1].volume, 12);
+        assert!(hours.iter().all(|h| h.low <= h.open.min(h.close) && h.high >= h.open.max(h.close)));
+    }
+}
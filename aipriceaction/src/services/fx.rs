@@ -0,0 +1,343 @@
+//! Currency conversion for `currency=USD|VND` on /tickers and /analysis/*.
+//!
+//! Stored prices are in the currency of their market: VND for vn and sjc,
+//! USDT (taken as USD) for crypto, USD for Yahoo unless the symbol carries a
+//! non-US exchange suffix. Rates are the Yahoo `{CODE}=X` closes synced into
+//! `ohlcv` like any other ticker (units of CODE per USD); each bar uses the
+//! last rate at or before its timestamp, hourly rates for intraday bars and
+//! daily ones (matched by date) otherwise. Bars older than the first stored
+//! rate stay unconverted. Index levels, yields and exchange rates have no
+//! currency and are never converted.
+//!
+//! Only the rates of a request's window are read (see [`window_start`]), plus
+//! the last one before it for carry-forward.
+//!
+//! Bars are converted raw, before indicators are computed (see
+//! [`Conversion`]), so changes, MAs, scores and money flow all come out in the
+//! target currency.
+
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use sqlx::PgPool;
+
+use crate::constants::fx::{INTRADAY_SPAN_FACTOR, MARGIN_DAYS, SOURCE, YAHOO_SUFFIXES};
+use crate::models::ohlcv::OhlcvRow;
+use crate::models::portfolio::Currency;
+use crate::queries::ohlcv;
+use crate::services::metadata;
+
+/// Quote currency of a stored series, `None` when its values are not prices.
+pub fn quote_currency(source: &str, ticker: &str) -> Option<&'static str> {
+    match source {
+        "vn" => (!crate::constants::vci_worker::INDEX_TICKERS.contains(&ticker)).then_some("VND"),
+        "sjc" => Some("VND"),
+        "crypto" => ticker.ends_with("USDT").then_some("USD"),
+        "yahoo" => {
+            if ticker.starts_with('^') || ticker.ends_with("=X") || ticker == "DX-Y.NYB" {
+                None
+            } else {
+                Some(YAHOO_SUFFIXES.iter().find(|(suffix, _)| ticker.ends_with(suffix)).map_or("USD", |(_, code)| code))
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Parse the `currency=` parameter (USD or VND, any case).
+pub fn parse_target(raw: Option<&str>) -> Result<Option<Currency>, String> {
+    match raw.map(str::trim).filter(|s| !s.is_empty()) {
+        None => Ok(None),
+        Some(s) => match Currency::from_db(s) {
+            Some(c @ (Currency::Usd | Currency::Vnd)) => Ok(Some(c)),
+            _ => Err(format!("Invalid currency '{s}'. Must be USD or VND")),
+        },
+    }
+}
+
+/// Yahoo symbol of the USD rate of `code`.
+fn rate_ticker(code: &str) -> String {
+    format!("{code}=X")
+}
+
+/// Value at or before `key`; `None` before the series starts.
+fn as_of<K: Ord>(series: &[(K, f64)], key: &K) -> Option<f64> {
+    let i = series.partition_point(|(k, _)| k <= key);
+    i.checked_sub(1).map(|i| series[i].1)
+}
+
+/// Earliest bar time a request may convert: `start` (or `end` when only a
+/// limit is given) moved back by `bars` bars of `interval_minutes`, the
+/// limit and/or indicator warm-up. The calendar time per bar is
+/// overestimated (sessions, weekends) and `MARGIN_DAYS` is added on top.
+pub fn window_start(start: Option<DateTime<Utc>>, end: DateTime<Utc>, bars: i64, interval_minutes: i64) -> DateTime<Utc> {
+    let per_bar = if interval_minutes >= 24 * 60 { interval_minutes * 3 / 2 } else { interval_minutes * INTRADAY_SPAN_FACTOR };
+    let span = bars.max(0).saturating_mul(per_bar).saturating_add(MARGIN_DAYS * 24 * 60);
+    Duration::try_minutes(span)
+        .and_then(|span| start.unwrap_or(end).checked_sub_signed(span))
+        .unwrap_or(DateTime::UNIX_EPOCH)
+}
+
+/// Rows per rate ticker, oldest first: those in `start..=end` after the last
+/// one before `start`.
+async fn load_series(
+    pool: &PgPool,
+    tickers: &[String],
+    interval: &str,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> sqlx::Result<HashMap<String, Vec<OhlcvRow>>> {
+    let mut series = ohlcv::get_ohlcv_batch_raw(pool, SOURCE, tickers, interval, Some(1), None, Some(start)).await?;
+    for (ticker, rows) in ohlcv::get_ohlcv_batch_raw(pool, SOURCE, tickers, interval, None, Some(start), Some(end)).await? {
+        series.entry(ticker).or_default().extend(rows);
+    }
+    Ok(series)
+}
+
+/// USD rates per currency code, oldest first.
+#[derive(Debug, Default)]
+pub struct FxRates {
+    daily: HashMap<String, Vec<(NaiveDate, f64)>>,
+    intraday: HashMap<String, Vec<(DateTime<Utc>, f64)>>,
+}
+
+impl FxRates {
+    pub fn insert_daily(&mut self, code: &str, mut points: Vec<(NaiveDate, f64)>) {
+        points.retain(|(_, v)| *v > 0.0);
+        points.sort_by_key(|(d, _)| *d);
+        points.dedup_by_key(|(d, _)| *d);
+        self.daily.insert(code.to_string(), points);
+    }
+
+    pub fn insert_intraday(&mut self, code: &str, mut points: Vec<(DateTime<Utc>, f64)>) {
+        points.retain(|(_, v)| *v > 0.0);
+        points.sort_by_key(|(t, _)| *t);
+        points.dedup_by_key(|(t, _)| *t);
+        self.intraday.insert(code.to_string(), points);
+    }
+
+    /// Units of `code` per USD at `time`.
+    pub fn per_usd(&self, code: &str, time: DateTime<Utc>, intraday: bool) -> Option<f64> {
+        if code == "USD" {
+            return Some(1.0);
+        }
+        if intraday
+            && let Some(rate) = self.intraday.get(code).filter(|s| s.first().is_some_and(|(t, _)| *t <= time)).and_then(|s| as_of(s, &time))
+        {
+            return Some(rate);
+        }
+        self.daily.get(code).and_then(|s| as_of(s, &time.date_naive()))
+    }
+
+    /// Multiplier taking `from` amounts into `to` at `time`.
+    pub fn factor(&self, from: &str, to: &str, time: DateTime<Utc>, intraday: bool) -> Option<f64> {
+        if from == to {
+            return Some(1.0);
+        }
+        Some(self.per_usd(to, time, intraday)? / self.per_usd(from, time, intraday)?)
+    }
+
+    /// Load the rates of `codes` in `start..=end` and the last one before
+    /// `start` (daily, plus hourly when `intraday`).
+    pub async fn load(
+        pool: &PgPool,
+        codes: &BTreeSet<&str>,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        intraday: bool,
+    ) -> sqlx::Result<Self> {
+        let tickers: Vec<String> = codes.iter().filter(|c| **c != "USD").map(|c| rate_ticker(c)).collect();
+        let mut rates = Self::default();
+        if tickers.is_empty() {
+            return Ok(rates);
+        }
+        let daily = load_series(pool, &tickers, "1D", start, end).await?;
+        let hourly = if intraday {
+            load_series(pool, &tickers, "1h", start, end).await?
+        } else {
+            HashMap::new()
+        };
+        for code in codes.iter().filter(|c| **c != "USD") {
+            let ticker = rate_ticker(code);
+            if let Some(rows) = daily.get(&ticker) {
+                rates.insert_daily(code, rows.iter().map(|r| (r.time.date_naive(), r.close)).collect());
+            }
+            if let Some(rows) = hourly.get(&ticker) {
+                rates.insert_intraday(code, rows.iter().map(|r| (r.time, r.close)).collect());
+            }
+        }
+        Ok(rates)
+    }
+}
+
+/// Quote currency per symbol of `(symbol, source)` pairs, skipping series
+/// without one.
+fn quote_currencies<'a>(symbols: impl IntoIterator<Item = (&'a str, &'a str)>) -> HashMap<String, String> {
+    let catalog = metadata::catalog();
+    symbols
+        .into_iter()
+        .filter_map(|(symbol, source)| Some((symbol.to_string(), catalog.currency(source, symbol)?.to_string())))
+        .collect()
+}
+
+/// Whether bars of `interval` take hourly rates.
+fn is_intraday(interval: &str) -> bool {
+    interval.ends_with('m') || interval.ends_with('h') || interval.ends_with('H')
+}
+
+/// A `currency=` conversion prepared for one request: the quote currency of
+/// each requested symbol and the rates taking it to `target`.
+#[derive(Debug)]
+pub struct Conversion {
+    pub target: Currency,
+    from: HashMap<String, String>,
+    rates: Arc<FxRates>,
+}
+
+impl Conversion {
+    /// Resolve the quote currency of each `(symbol, source)` and load the
+    /// rates for bars from `start` (MA warm-up included, see
+    /// [`window_start`]) up to `end`, hourly ones too when `intraday`.
+    pub async fn load<'a>(
+        pool: &PgPool,
+        symbols: impl IntoIterator<Item = (&'a str, &'a str)>,
+        target: Currency,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        intraday: bool,
+    ) -> sqlx::Result<Self> {
+        let from = quote_currencies(symbols);
+        let codes: BTreeSet<&str> = from.values().map(String::as_str).chain(std::iter::once(target.as_str())).collect();
+        let rates = FxRates::load(pool, &codes, start, end + Duration::days(1), intraday).await?;
+        Ok(Self { target, from, rates: Arc::new(rates) })
+    }
+
+    /// The same rates for the symbols of one source. mode=all fetches per
+    /// source and a symbol may be listed under several; `self` must have
+    /// been loaded with every `(symbol, source)` pair.
+    pub fn for_source<'a>(&self, source: &str, symbols: impl IntoIterator<Item = &'a String>) -> Self {
+        let from = quote_currencies(symbols.into_iter().map(|s| (s.as_str(), source)));
+        Self { target: self.target, from, rates: Arc::clone(&self.rates) }
+    }
+
+    fn factor(&self, symbol: &str, time: DateTime<Utc>, intraday: bool) -> Option<f64> {
+        self.rates.factor(self.from.get(symbol)?, self.target.as_str(), time, intraday)
+    }
+
+    /// Convert `symbol`'s stored bars in place, each at the rate of its own
+    /// time. Volumes are share counts and stay as they are.
+    pub fn convert(&self, symbol: &str, rows: &mut [OhlcvRow]) {
+        for row in rows {
+            if let Some(f) = self.factor(symbol, row.time, is_intraday(&row.interval)) {
+                for v in [&mut row.open, &mut row.high, &mut row.low, &mut row.close] {
+                    *v *= f;
+                }
+            }
+        }
+    }
+
+    /// Currency `symbol`'s prices end up in: the target when a rate was
+    /// found, the quote currency otherwise, `None` for series without one.
+    pub fn label(&self, symbol: &str) -> Option<&str> {
+        let from = self.from.get(symbol)?;
+        let converted = self.rates.factor(from, self.target.as_str(), Utc::now(), false).is_some();
+        Some(if converted { self.target.as_str() } else { from })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn d(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 3, day).unwrap()
+    }
+
+    #[test]
+    fn test_quote_currency() {
+        assert_eq!(quote_currency("vn", "VCB"), Some("VND"));
+        assert_eq!(quote_currency("vn", "VNINDEX"), None);
+        assert_eq!(quote_currency("sjc", "SJC-GOLD"), Some("VND"));
+        assert_eq!(quote_currency("crypto", "BTCUSDT"), Some("USD"));
+        assert_eq!(quote_currency("yahoo", "NVDA"), Some("USD"));
+        assert_eq!(quote_currency("yahoo", "7203.T"), Some("JPY"));
+        assert_eq!(quote_currency("yahoo", "^GSPC"), None);
+        assert_eq!(quote_currency("yahoo", "VND=X"), None);
+    }
+
+    #[test]
+    fn test_parse_target() {
+        assert_eq!(parse_target(None), Ok(None));
+        assert_eq!(parse_target(Some("usd")), Ok(Some(Currency::Usd)));
+        assert_eq!(parse_target(Some("VND")), Ok(Some(Currency::Vnd)));
+        assert!(parse_target(Some("USDT")).is_err());
+        assert!(parse_target(Some("EUR")).is_err());
+    }
+
+    #[test]
+    fn test_factor_uses_rate_as_of_bar() {
+        let mut rates = FxRates::default();
+        rates.insert_daily("VND", vec![(d(3), 25_000.0), (d(4), 25_500.0)]);
+        rates.insert_intraday("VND", vec![(Utc.with_ymd_and_hms(2025, 3, 4, 10, 0, 0).unwrap(), 25_400.0)]);
+        let weekend = Utc.with_ymd_and_hms(2025, 3, 2, 0, 0, 0).unwrap();
+        let tuesday = Utc.with_ymd_and_hms(2025, 3, 4, 0, 0, 0).unwrap();
+        // Before the history: no rate, the bar stays unconverted
+        assert_eq!(rates.factor("VND", "USD", weekend, false), None);
+        assert_eq!(rates.factor("USD", "VND", tuesday, false), Some(25_500.0));
+        // Intraday bar after the hourly rate
+        assert_eq!(rates.factor("USD", "VND", tuesday + Duration::hours(12), true), Some(25_400.0));
+        // Intraday bar before the hourly history falls back to daily
+        assert_eq!(rates.factor("USD", "VND", tuesday, true), Some(25_500.0));
+        assert_eq!(rates.factor("VND", "VND", tuesday, false), Some(1.0));
+        assert_eq!(rates.factor("JPY", "USD", tuesday, false), None);
+    }
+
+    #[test]
+    fn test_window_start_covers_limit_and_warm_up() {
+        let end = Utc.with_ymd_and_hms(2025, 3, 31, 0, 0, 0).unwrap();
+        let margin = Duration::days(MARGIN_DAYS);
+        // 100 daily bars: 150 calendar days back from the end
+        assert_eq!(window_start(None, end, 100, 24 * 60), end - Duration::days(150) - margin);
+        // A requested start only moves back by the warm-up
+        let start = Utc.with_ymd_and_hms(2025, 3, 1, 0, 0, 0).unwrap();
+        assert_eq!(window_start(Some(start), end, 20, 24 * 60), start - Duration::days(30) - margin);
+        // 10 hourly bars span 80 calendar hours
+        assert_eq!(window_start(None, end, 10, 60), end - Duration::hours(80) - margin);
+        assert_eq!(window_start(None, end, i64::MAX, 24 * 60), DateTime::UNIX_EPOCH);
+    }
+
+    #[test]
+    fn test_conversion_precedes_indicators() {
+        let mut rates = FxRates::default();
+        rates.insert_daily("VND", vec![(d(3), 25_000.0), (d(4), 20_000.0)]);
+        let fx = Conversion {
+            target: Currency::Usd,
+            from: HashMap::from([("VCB".to_string(), "VND".to_string())]),
+            rates: Arc::new(rates),
+        };
+        let bar = |day: u32, close: f64| OhlcvRow {
+            ticker_id: 1,
+            interval: "1D".to_string(),
+            time: Utc.with_ymd_and_hms(2025, 3, day, 0, 0, 0).unwrap(),
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume: 100,
+        };
+        // Flat in VND, newest first as read from the DB
+        let rows = vec![bar(4, 50_000.0), bar(3, 50_000.0)];
+        let opts = ohlcv::FetchOptions { ma_periods: &[2], fx: Some(&fx), ..Default::default() };
+        let joined = ohlcv::enhance_rows("VCB", rows, &opts);
+        // Newest first again
+        let last = &joined[0];
+        assert_eq!(last.close, 2.5);
+        // The USD return reflects the VND appreciation, the MA averages USD closes
+        assert_eq!(last.close_changed, Some(25.0));
+        assert_eq!(last.ma(2).0, Some(2.25));
+        assert_eq!(fx.label("VCB"), Some("USD"));
+        assert_eq!(fx.label("VNINDEX"), None);
+    }
+}
@@ -142,6 +142,9 @@ async fn import_single_csv(
         })
         .collect();
 
+    // Bars rejected by a validation rule go to ohlcv_quarantine instead.
+    let ohlcv_rows = crate::services::validation::validate_rows(pool, source, &parsed.ticker, ohlcv_rows).await;
+
     // Batch upsert
     let total_rows = ohlcv_rows.len();
     let mut batches = 0;
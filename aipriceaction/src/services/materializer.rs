@@ -0,0 +1,167 @@
+use chrono::{DateTime, Duration, Utc};
+use sqlx::PgPool;
+
+use crate::constants::materialize as cfg;
+use crate::models::aggregated_interval::AggregatedInterval;
+use crate::models::interval::Interval;
+use crate::models::ohlcv::OhlcvRow;
+use crate::queries::{import, ohlcv};
+use crate::services::aggregator::Aggregator;
+
+// ── Incremental refresh ──
+
+/// Enabled materialised intervals computed from `base_interval` ("1m", "1D", ...).
+pub fn targets_for(base_interval: &str) -> Vec<AggregatedInterval> {
+    cfg::intervals()
+        .into_iter()
+        .filter(|t| t.base_interval().as_str() == base_interval)
+        .collect()
+}
+
+/// Recompute every enabled target derived from `base_interval`, starting at
+/// the bucket containing `since`. Called after base bars are upserted.
+pub async fn refresh_from(
+    pool: &PgPool,
+    ticker_id: i32,
+    ticker: &str,
+    base_interval: &str,
+    since: DateTime<Utc>,
+) {
+    // Base bars are never in the future; one day covers the forming candle.
+    let end = Utc::now() + Duration::days(1);
+    for target in targets_for(base_interval) {
+        if let Err(e) = refresh_range(pool, ticker_id, ticker, target, since, end).await {
+            tracing::warn!(ticker, interval = target.to_str(), "materialize refresh failed: {e}");
+        }
+    }
+}
+
+/// Recompute `target` candles from base bars with `start <= time < end`.
+///
+/// `start` is aligned down to its bucket so the first candle is built from
+/// all of its base bars. Reads in windows of `window(target)`, which are whole
+/// multiples of the bucket size. Returns the number of candles written.
+pub async fn refresh_range(
+    pool: &PgPool,
+    ticker_id: i32,
+    ticker: &str,
+    target: AggregatedInterval,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> sqlx::Result<usize> {
+    let base = target.base_interval().as_str();
+    let step = window(target);
+    let mut from = Aggregator::bucket_start(target, start, 0);
+    let mut written = 0;
+
+    while from < end {
+        let to = (from + step).min(end);
+        let rows = ohlcv::get_ohlcv_between(pool, ticker_id, base, from, to).await?;
+        let candles = aggregate(ticker_id, ticker, target, rows);
+        import::bulk_upsert_ohlcv(pool, &candles).await?;
+        written += candles.len();
+        from = to;
+    }
+    Ok(written)
+}
+
+/// Fill `target` newest-first from `until` (a bucket start) back to the
+/// bucket containing `earliest`, so recent candles become readable first.
+pub async fn backfill(
+    pool: &PgPool,
+    ticker_id: i32,
+    ticker: &str,
+    target: AggregatedInterval,
+    earliest: DateTime<Utc>,
+    until: DateTime<Utc>,
+) -> sqlx::Result<usize> {
+    let floor = Aggregator::bucket_start(target, earliest, 0);
+    let step = window(target);
+    let mut to = until;
+    let mut written = 0;
+
+    while to > floor {
+        let from = (to - step).max(floor);
+        written += refresh_range(pool, ticker_id, ticker, target, from, to).await?;
+        to = from;
+    }
+    Ok(written)
+}
+
+// ── Helpers ──
+
+/// Base-bar span read per query.
+fn window(target: AggregatedInterval) -> Duration {
+    match target.base_interval() {
+        Interval::Daily => Duration::weeks(cfg::DAILY_WINDOW_WEEKS),
+        _ => Duration::days(cfg::MINUTE_WINDOW_DAYS),
+    }
+}
+
+/// Aggregate ascending base rows into `target` rows ready for upsert.
+fn aggregate(ticker_id: i32, ticker: &str, target: AggregatedInterval, rows: Vec<OhlcvRow>) -> Vec<OhlcvRow> {
+    let aggregated = match target.base_interval() {
+        Interval::Daily => Aggregator::aggregate_daily_data(ticker, rows, target),
+        Interval::Hourly => Aggregator::aggregate_hourly_data(ticker, rows, target, 0),
+        Interval::Minute => Aggregator::aggregate_minute_data(ticker, rows, target),
+    };
+    aggregated
+        .into_iter()
+        .map(|a| OhlcvRow {
+            ticker_id,
+            interval: target.to_str().to_string(),
+            time: a.time,
+            open: a.open,
+            high: a.high,
+            low: a.low,
+            close: a.close,
+            volume: a.volume,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn minute(min: u32, close: f64) -> OhlcvRow {
+        OhlcvRow {
+            ticker_id: 7,
+            interval: "1m".into(),
+            time: Utc.with_ymd_and_hms(2026, 10, 16, 2, min, 0).unwrap(),
+            open: close,
+            high: close + 1.0,
+            low: close - 1.0,
+            close,
+            volume: 10,
+        }
+    }
+
+    #[test]
+    fn test_aggregate_tags_target_interval() {
+        let rows = (0..7).map(|m| minute(m, 100.0 + m as f64)).collect();
+        let out = aggregate(7, "BTCUSDT", AggregatedInterval::Minutes5, rows);
+        assert_eq!(out.len(), 2);
+        assert!(out.iter().all(|r| r.interval == "5m" && r.ticker_id == 7));
+        assert_eq!(out[0].open, 100.0);
+        assert_eq!(out[0].close, 104.0);
+        assert_eq!(out[0].high, 105.0);
+        assert_eq!(out[0].volume, 50);
+        assert_eq!(out[1].time, Utc.with_ymd_and_hms(2026, 10, 16, 2, 5, 0).unwrap());
+    }
+
+    #[test]
+    fn test_bucket_start_aligns_to_target() {
+        let t = Utc.with_ymd_and_hms(2026, 10, 16, 2, 17, 30).unwrap();
+        assert_eq!(
+            Aggregator::bucket_start(AggregatedInterval::Minutes15, t, 0),
+            Utc.with_ymd_and_hms(2026, 10, 16, 2, 15, 0).unwrap()
+        );
+        // 2026-10-16 is a Friday; weeks start on Monday.
+        assert_eq!(
+            Aggregator::bucket_start(AggregatedInterval::Week, t, 0),
+            Utc.with_ymd_and_hms(2026, 10, 12, 0, 0, 0).unwrap()
+        );
+    }
+}
@@ -0,0 +1,492 @@
+//! Ticker metadata: names, exchanges, quote currencies, sectors and trading
+//! units for every source, stored in `ticker_metadata`.
+//!
+//! The side files (`vn.csv`, `ticker_group.json`, `binance_tickers.json`,
+//! `global_tickers.json`, `sjc_tickers.json`) are only seeds: [`seed`] fills
+//! an empty table from them once, after which rows edited or deleted through
+//! /admin/tickers stay that way. The table is held in memory as
+//! a [`Catalog`] that the group, name and currency lookups read, together
+//! with the listing events of `ticker_lifecycle` and the sector/index
+//! history of `group_membership`; it is reloaded after every admin edit and
+//! every `RELOAD_SECS` (see `workers::metadata`). Processes that never load it from the database (CLI
+//! commands without a pool) build it from the side files instead.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+
+use chrono::{NaiveDate, Utc};
+use sqlx::PgPool;
+
+use crate::constants::metadata::{CRYPTO_SECTOR, DEFAULT_CATEGORY, VN_LOT_SIZE};
+use crate::constants::vci_worker::INDEX_TICKERS;
+use crate::models::lifecycle::{self, LifecycleEvent, LifecycleState, Segment};
+use crate::queries::lifecycle as lifecycle_queries;
+use crate::queries::membership::{self, Membership};
+use crate::queries::ticker_metadata::{self, TickerMetadata};
+use crate::services::fx;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+static CATALOG: RwLock<Option<Arc<Catalog>>> = RwLock::new(None);
+
+/// Resolve a data file by searching CWD then parent directory.
+pub fn resolve_data_file(name: &str) -> Result<PathBuf, BoxError> {
+    let cwd = std::path::Path::new(name);
+    if cwd.exists() {
+        return Ok(cwd.to_path_buf());
+    }
+    let parent = std::path::Path::new("..").join(name);
+    if parent.exists() {
+        return Ok(parent);
+    }
+    Err(format!("Data file not found: {name} (searched . and ../)").into())
+}
+
+// ---------------------------------------------------------------------------
+// Catalog
+// ---------------------------------------------------------------------------
+
+/// In-memory copy of `ticker_metadata`, keyed by (source, ticker), of
+/// `ticker_lifecycle`, keyed by source then ticker, and of
+/// `group_membership`, keyed by source.
+#[derive(Debug, Default)]
+pub struct Catalog {
+    rows: BTreeMap<(String, String), TickerMetadata>,
+    events: HashMap<String, HashMap<String, Vec<LifecycleEvent>>>,
+    membership: HashMap<String, Vec<Membership>>,
+}
+
+impl Catalog {
+    pub fn new(rows: Vec<TickerMetadata>) -> Self {
+        Self {
+            rows: rows.into_iter().map(|m| ((m.source.clone(), m.ticker.clone()), m)).collect(),
+            ..Default::default()
+        }
+    }
+
+    /// Attach sector/index membership periods.
+    pub fn with_membership(mut self, periods: Vec<Membership>) -> Self {
+        for m in periods {
+            self.membership.entry(m.source.clone()).or_default().push(m);
+        }
+        self
+    }
+
+    /// Attach lifecycle events, given in `effective_on` order per ticker.
+    pub fn with_events(mut self, events: Vec<LifecycleEvent>) -> Self {
+        for e in events {
+            self.events.entry(e.source.clone()).or_default().entry(e.ticker.clone()).or_default().push(e);
+        }
+        self
+    }
+
+    pub fn get(&self, source: &str, ticker: &str) -> Option<&TickerMetadata> {
+        self.rows.get(&(source.to_string(), ticker.to_string()))
+    }
+
+    /// Rows of `source`, ordered by ticker.
+    pub fn source_rows<'a>(&'a self, source: &'a str) -> impl Iterator<Item = &'a TickerMetadata> + 'a {
+        self.rows
+            .range((source.to_string(), String::new())..)
+            .take_while(move |((s, _), _)| s == source)
+            .map(|(_, m)| m)
+    }
+
+    /// Sector → tickers of `source` today (see [`Catalog::groups_on`]).
+    pub fn groups(&self, source: &str) -> BTreeMap<String, Vec<String>> {
+        self.groups_on(source, Utc::now().date_naive())
+    }
+
+    /// Sector → tickers of `source` as of `day`. Tickers with sector history
+    /// in `group_membership` are listed under the sectors whose period
+    /// covers `day`, every other ticker under its current sector. Tickers
+    /// that do not trade on `day` (not yet listed, delisted, renamed,
+    /// merged) are left out.
+    pub fn groups_on(&self, source: &str, day: NaiveDate) -> BTreeMap<String, Vec<String>> {
+        let history: Vec<&Membership> = self.periods(source, "sector").collect();
+        let with_history: HashSet<&str> = history.iter().map(|m| m.ticker.as_str()).collect();
+
+        let mut groups: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for m in self.source_rows(source) {
+            if let Some(sector) = &m.sector
+                && !with_history.contains(m.ticker.as_str())
+                && self.trades_on(source, &m.ticker, day)
+            {
+                groups.entry(sector.clone()).or_default().push(m.ticker.clone());
+            }
+        }
+        for m in history {
+            if m.covers(day) && self.trades_on(source, &m.ticker, day) {
+                groups.entry(m.group_name.clone()).or_default().push(m.ticker.clone());
+            }
+        }
+        for tickers in groups.values_mut() {
+            tickers.sort();
+            tickers.dedup();
+        }
+        groups
+    }
+
+    /// Days in `(start, end]` on which [`Catalog::groups_on`] of `source` may
+    /// differ from the day before: sector period bounds, listing and
+    /// delisting dates and lifecycle events.
+    pub fn group_change_days(&self, source: &str, start: NaiveDate, end: NaiveDate) -> BTreeSet<NaiveDate> {
+        let periods = self.periods(source, "sector").flat_map(|m| [m.effective_from, m.effective_to]).flatten();
+        let listings = self.source_rows(source).flat_map(|m| [m.listed_on, m.delisted_on]).flatten();
+        let events = self.events.get(source).into_iter().flat_map(|m| m.values().flatten()).map(|e| e.effective_on);
+        periods.chain(listings).chain(events).filter(|d| start < *d && *d <= end).collect()
+    }
+
+    /// Constituents of `index` (e.g. VN30) of `source` on `day`.
+    pub fn index_members(&self, source: &str, index: &str, day: NaiveDate) -> Vec<String> {
+        let mut members: Vec<String> = self
+            .periods(source, "index")
+            .filter(|m| m.group_name.eq_ignore_ascii_case(index) && m.covers(day) && self.trades_on(source, &m.ticker, day))
+            .map(|m| m.ticker.clone())
+            .collect();
+        members.sort();
+        members.dedup();
+        members
+    }
+
+    /// Whether any source has recorded constituents of `index`.
+    pub fn has_index(&self, index: &str) -> bool {
+        self.membership.values().flatten().any(|m| m.kind == "index" && m.group_name.eq_ignore_ascii_case(index))
+    }
+
+    fn periods<'a>(&'a self, source: &str, kind: &'a str) -> impl Iterator<Item = &'a Membership> + 'a {
+        self.membership.get(source).into_iter().flatten().filter(move |m| m.kind == kind)
+    }
+
+    /// Whether the ticker is listed and not delisted, renamed or merged on `day`.
+    fn trades_on(&self, source: &str, ticker: &str, day: NaiveDate) -> bool {
+        let listed = self
+            .get(source, ticker)
+            .is_none_or(|m| m.listed_on.is_none_or(|d| d <= day) && m.delisted_on.is_none_or(|d| day < d));
+        listed && !self.state(source, ticker, day).is_terminal()
+    }
+
+    /// Ticker → name of the grouped tickers of `source`.
+    pub fn names(&self, source: &str) -> BTreeMap<String, String> {
+        let today = Utc::now().date_naive();
+        self.source_rows(source)
+            .filter(|m| m.sector.is_some() && !self.state(source, &m.ticker, today).is_terminal())
+            .filter_map(|m| Some((m.ticker.clone(), m.name.clone()?)))
+            .collect()
+    }
+
+    /// Quote currency of a stored series: the row's `currency` when the
+    /// ticker is known, the source/suffix rule of `fx::quote_currency`
+    /// otherwise. None when the values are not prices.
+    pub fn currency<'a>(&'a self, source: &str, ticker: &str) -> Option<&'a str> {
+        match self.get(source, ticker) {
+            Some(m) => m.currency.as_deref(),
+            None => fx::quote_currency(source, ticker),
+        }
+    }
+
+    /// Lifecycle events of one ticker, oldest first.
+    pub fn events(&self, source: &str, ticker: &str) -> &[LifecycleEvent] {
+        self.events.get(source).and_then(|m| m.get(ticker)).map_or(&[], Vec::as_slice)
+    }
+
+    /// Listing state of a ticker on `date`.
+    pub fn state(&self, source: &str, ticker: &str, date: NaiveDate) -> LifecycleState {
+        lifecycle::state_on(self.events(source, ticker), date)
+    }
+
+    /// Stitched-history segments of the rename chain `ticker` is part of
+    /// (see `lifecycle::rename_chain`).
+    pub fn rename_chain(&self, source: &str, ticker: &str) -> Option<Vec<Segment>> {
+        lifecycle::rename_chain(self.events.get(source)?, ticker)
+    }
+}
+
+/// The loaded catalog; built from the side files on first use when nothing
+/// has been loaded from the database.
+pub fn catalog() -> Arc<Catalog> {
+    if let Some(c) = CATALOG.read().unwrap_or_else(|e| e.into_inner()).as_ref() {
+        return c.clone();
+    }
+    let mut guard = CATALOG.write().unwrap_or_else(|e| e.into_inner());
+    guard.get_or_insert_with(|| Arc::new(Catalog::new(seed_rows()))).clone()
+}
+
+/// Replace the in-memory catalog with the current table. Returns its size.
+pub async fn reload(pool: &PgPool) -> sqlx::Result<usize> {
+    let catalog = Catalog::new(ticker_metadata::list(pool, None, None).await?)
+        .with_events(lifecycle_queries::list(pool, None, None).await?)
+        .with_membership(membership::list(pool, None, None, None, None).await?);
+    let len = catalog.rows.len();
+    *CATALOG.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(catalog));
+    Ok(len)
+}
+
+/// Fill `ticker_metadata` from the side files when it is empty. A table
+/// with rows is left alone, so deleted tickers do not come back.
+pub async fn seed(pool: &PgPool) -> sqlx::Result<u64> {
+    if !ticker_metadata::is_empty(pool).await? {
+        return Ok(0);
+    }
+    ticker_metadata::insert_missing(pool, &seed_rows()).await
+}
+
+// ---------------------------------------------------------------------------
+// Seeding from the side files
+// ---------------------------------------------------------------------------
+
+/// Rows described by the side files. Missing or unreadable files are skipped.
+pub fn seed_rows() -> Vec<TickerMetadata> {
+    let read = |name: &str| match resolve_data_file(name).and_then(|p| Ok(std::fs::read_to_string(p)?)) {
+        Ok(content) => Some(content),
+        Err(e) => {
+            tracing::warn!("ticker metadata: skipping {name}: {e}");
+            None
+        }
+    };
+
+    let mut rows = Vec::new();
+    let groups: BTreeMap<String, Vec<String>> = read("ticker_group.json")
+        .and_then(|c| serde_json::from_str(&c).map_err(|e| tracing::warn!("ticker metadata: ticker_group.json: {e}")).ok())
+        .unwrap_or_default();
+    match vn_rows(read("vn.csv").as_deref().unwrap_or(""), &groups) {
+        Ok(vn) => rows.extend(vn),
+        Err(e) => tracing::warn!("ticker metadata: vn.csv: {e}"),
+    }
+    for (source, file) in [("crypto", "binance_tickers.json"), ("yahoo", "global_tickers.json"), ("sjc", "sjc_tickers.json")] {
+        let Some(content) = read(file) else { continue };
+        match json_rows(source, &content) {
+            Ok(r) => rows.extend(r),
+            Err(e) => tracing::warn!("ticker metadata: {file}: {e}"),
+        }
+    }
+    rows
+}
+
+/// VN rows from `vn.csv` (symbol, organ_name, en_organ_name, exchange, type)
+/// with sectors from `ticker_group.json`. Grouped tickers and indices that
+/// the CSV lacks still get a row.
+fn vn_rows(csv: &str, groups: &BTreeMap<String, Vec<String>>) -> Result<Vec<TickerMetadata>, BoxError> {
+    let sector_of: HashMap<&str, &str> =
+        groups.iter().flat_map(|(sector, tickers)| tickers.iter().map(move |t| (t.as_str(), sector.as_str()))).collect();
+    let text = |s: Option<&str>| s.map(str::trim).filter(|s| !s.is_empty()).map(String::from);
+    let row = |ticker: &str| TickerMetadata {
+        source: "vn".to_string(),
+        ticker: ticker.to_string(),
+        currency: fx::quote_currency("vn", ticker).map(String::from),
+        sector: sector_of.get(ticker).map(|s| s.to_string()),
+        ..Default::default()
+    };
+
+    let mut rows: BTreeMap<String, TickerMetadata> = BTreeMap::new();
+    let mut rdr = csv::Reader::from_reader(csv.as_bytes());
+    for record in rdr.records() {
+        let record = record?;
+        let Some(ticker) = text(record.get(0)).map(|t| t.to_uppercase()) else { continue };
+        let asset_type = text(record.get(4));
+        let exchange = text(record.get(3));
+        let lot_size = (matches!(asset_type.as_deref(), Some("stock" | "fund"))
+            && matches!(exchange.as_deref(), Some("HOSE" | "HNX" | "UPCOM")))
+        .then_some(VN_LOT_SIZE);
+        let m = TickerMetadata {
+            name: text(record.get(1)),
+            en_name: text(record.get(2)),
+            exchange,
+            asset_type,
+            lot_size,
+            ..row(&ticker)
+        };
+        rows.insert(ticker, m);
+    }
+    for ticker in sector_of.keys() {
+        rows.entry(ticker.to_string()).or_insert_with(|| row(ticker));
+    }
+    for ticker in INDEX_TICKERS {
+        rows.entry(ticker.to_string()).or_insert_with(|| TickerMetadata { asset_type: Some("index".to_string()), ..row(ticker) });
+    }
+    Ok(rows.into_values().collect())
+}
+
+/// Rows from a `{ fetched_at?, data: [{symbol, name, category?}] }` ticker
+/// file. Crypto tickers all go under `CRYPTO_SECTOR`, those marked
+/// `"status": "delisted"` are delisted as of `fetched_at` and `copy_from`
+/// (the symbol before a rename) becomes an alias; Yahoo `X:US` symbols get
+/// `X` as an alias.
+fn json_rows(source: &str, content: &str) -> Result<Vec<TickerMetadata>, BoxError> {
+    let raw: serde_json::Value = serde_json::from_str(content)?;
+    let Some(items) = raw["data"].as_array() else {
+        return Ok(Vec::new());
+    };
+    let fetched_on = raw["fetched_at"]
+        .as_str()
+        .and_then(|s| chrono::DateTime::parse_from_rfc3339(s).ok())
+        .map(|t| t.date_naive())
+        .unwrap_or_else(|| Utc::now().date_naive());
+    Ok(items
+        .iter()
+        .filter_map(|item| {
+            let ticker = item["symbol"].as_str().filter(|s| !s.is_empty())?;
+            let sector = match source {
+                "crypto" => CRYPTO_SECTOR,
+                _ => item["category"].as_str().unwrap_or(DEFAULT_CATEGORY),
+            };
+            Some(TickerMetadata {
+                source: source.to_string(),
+                ticker: ticker.to_string(),
+                name: item["name"].as_str().map(String::from),
+                exchange: (source == "crypto").then(|| "BINANCE".to_string()),
+                currency: fx::quote_currency(source, ticker).map(String::from),
+                asset_type: (source == "crypto").then(|| "crypto".to_string()),
+                sector: Some(sector.to_string()),
+                delisted_on: (item["status"].as_str() == Some("delisted")).then_some(fetched_on),
+                aliases: ticker
+                    .strip_suffix(":US")
+                    .or(item["copy_from"].as_str())
+                    .map(|s| vec![s.to_string()])
+                    .unwrap_or_default(),
+                ..Default::default()
+            })
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn groups() -> BTreeMap<String, Vec<String>> {
+        BTreeMap::from([("NGAN_HANG".to_string(), vec!["VCB".to_string(), "TCB".to_string()])])
+    }
+
+    #[test]
+    fn test_vn_rows() {
+        let csv = "symbol,organ_name,en_organ_name,exchange,type\n\
+                   TCB,Kỹ thương,Techcombank,HOSE,stock\n\
+                   CVNM2401,Chứng quyền,,HOSE,cw\n";
+        let rows = vn_rows(csv, &groups()).unwrap();
+        let find = |t: &str| rows.iter().find(|m| m.ticker == t).unwrap();
+
+        let tcb = find("TCB");
+        assert_eq!(tcb.sector.as_deref(), Some("NGAN_HANG"));
+        assert_eq!(tcb.en_name.as_deref(), Some("Techcombank"));
+        assert_eq!(tcb.currency.as_deref(), Some("VND"));
+        assert_eq!(tcb.lot_size, Some(VN_LOT_SIZE));
+        // Listed but ungrouped, no lots for warrants
+        let cw = find("CVNM2401");
+        assert_eq!((cw.sector.as_deref(), cw.lot_size, cw.en_name.as_deref()), (None, None, None));
+        // Grouped but missing from the CSV
+        assert_eq!(find("VCB").sector.as_deref(), Some("NGAN_HANG"));
+        // Indices have no currency
+        let index = find("VNINDEX");
+        assert_eq!((index.asset_type.as_deref(), index.currency.as_deref()), (Some("index"), None));
+    }
+
+    #[test]
+    fn test_json_rows() {
+        let yahoo = r#"{"data": [{"symbol": "VFS:US", "name": "VinFast", "category": "Stock"}, {"symbol": "^GSPC"}]}"#;
+        let rows = json_rows("yahoo", yahoo).unwrap();
+        assert_eq!(rows[0].aliases, vec!["VFS".to_string()]);
+        assert_eq!(rows[0].currency.as_deref(), Some("USD"));
+        assert_eq!(rows[1].sector.as_deref(), Some(DEFAULT_CATEGORY));
+        assert_eq!(rows[1].currency, None);
+
+        let crypto = json_rows(
+            "crypto",
+            r#"{"fetched_at": "2025-11-17T02:28:50.587Z", "data": [
+                {"symbol": "BTCUSDT", "name": "Bitcoin"},
+                {"symbol": "TONUSDT", "status": "delisted"},
+                {"symbol": "GRAMUSDT", "copy_from": "TONUSDT"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(crypto[0].sector.as_deref(), Some(CRYPTO_SECTOR));
+        assert_eq!((crypto[0].delisted_on, crypto[0].aliases.len()), (None, 0));
+        assert_eq!(crypto[1].delisted_on, NaiveDate::from_ymd_opt(2025, 11, 17));
+        assert_eq!(crypto[2].aliases, vec!["TONUSDT".to_string()]);
+    }
+
+    #[test]
+    fn test_catalog_lookups() {
+        let mut rows = vn_rows("", &groups()).unwrap();
+        rows.push(TickerMetadata {
+            source: "yahoo".to_string(),
+            ticker: "7203.T".to_string(),
+            currency: Some("USD".to_string()),
+            ..Default::default()
+        });
+        let catalog = Catalog::new(rows);
+
+        assert_eq!(catalog.groups("vn")["NGAN_HANG"], vec!["TCB".to_string(), "VCB".to_string()]);
+        assert!(catalog.groups("yahoo").is_empty());
+        assert!(catalog.names("vn").is_empty());
+        // Row overrides the suffix rule; unknown tickers fall back to it
+        assert_eq!(catalog.currency("yahoo", "7203.T"), Some("USD"));
+        assert_eq!(catalog.currency("yahoo", "6758.T"), Some("JPY"));
+        assert_eq!(catalog.currency("vn", "VNINDEX"), None);
+    }
+
+    #[test]
+    fn test_catalog_lifecycle() {
+        let event = |ticker: &str, state, successor: Option<&str>| LifecycleEvent {
+            id: 0,
+            source: "vn".to_string(),
+            ticker: ticker.to_string(),
+            state,
+            effective_on: NaiveDate::from_ymd_opt(2024, 1, 2).unwrap(),
+            successor: successor.map(String::from),
+            note: None,
+        };
+        let catalog = Catalog::new(vn_rows("", &groups()).unwrap()).with_events(vec![
+            event("TCB", LifecycleState::Delisted, None),
+            event("OLD", LifecycleState::Renamed, Some("VCB")),
+        ]);
+
+        // Delisted tickers drop out of their group
+        assert_eq!(catalog.groups("vn")["NGAN_HANG"], vec!["VCB".to_string()]);
+        assert_eq!(catalog.state("vn", "VCB", Utc::now().date_naive()), LifecycleState::Active);
+        let chain = catalog.rename_chain("vn", "OLD").unwrap();
+        assert_eq!(chain.last().unwrap().ticker, "VCB");
+        assert!(catalog.rename_chain("yahoo", "OLD").is_none());
+    }
+
+    #[test]
+    fn test_catalog_groups_on() {
+        let day = |s: &str| NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap();
+        let period = |kind: &str, group: &str, ticker: &str, from: Option<&str>, to: Option<&str>| Membership {
+            id: 0,
+            source: "vn".to_string(),
+            kind: kind.to_string(),
+            group_name: group.to_string(),
+            ticker: ticker.to_string(),
+            effective_from: from.map(day),
+            effective_to: to.map(day),
+            note: None,
+        };
+        // Periods meeting at a bound do not overlap; the end is exclusive
+        let until_2021 = period("sector", "BAT_DONG_SAN", "TCB", None, Some("2021-01-01"));
+        assert!(!until_2021.overlaps(&period("sector", "NGAN_HANG", "TCB", Some("2021-01-01"), None)));
+        assert!(until_2021.overlaps(&period("sector", "NGAN_HANG", "TCB", Some("2020-12-31"), None)));
+        assert!(until_2021.overlaps(&period("sector", "NGAN_HANG", "TCB", None, None)));
+
+        let catalog = Catalog::new(vn_rows("", &groups()).unwrap()).with_membership(vec![
+            // TCB was a real-estate name until 2021, then moved to banks
+            period("sector", "BAT_DONG_SAN", "TCB", None, Some("2021-01-01")),
+            period("sector", "NGAN_HANG", "TCB", Some("2021-01-01"), None),
+            period("index", "VN30", "VCB", Some("2020-01-01"), None),
+        ]);
+
+        let old = catalog.groups_on("vn", day("2020-06-01"));
+        assert_eq!(old["NGAN_HANG"], vec!["VCB".to_string()]);
+        assert_eq!(old["BAT_DONG_SAN"], vec!["TCB".to_string()]);
+        let now = catalog.groups_on("vn", day("2024-06-01"));
+        assert_eq!(now["NGAN_HANG"], vec!["TCB".to_string(), "VCB".to_string()]);
+        assert!(!now.contains_key("BAT_DONG_SAN"));
+
+        let changes: Vec<NaiveDate> = catalog.group_change_days("vn", day("2020-01-01"), day("2024-06-01")).into_iter().collect();
+        assert_eq!(changes, vec![day("2021-01-01")]);
+        assert!(catalog.group_change_days("vn", day("2021-01-01"), day("2024-06-01")).is_empty());
+
+        assert_eq!(catalog.index_members("vn", "VN30", day("2024-06-01")), vec!["VCB".to_string()]);
+        assert!(catalog.index_members("vn", "VN30", day("2019-06-01")).is_empty());
+    }
+}
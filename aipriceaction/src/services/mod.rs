@@ -1,4 +1,14 @@
 pub mod aggregator;
+pub mod alerts;
+pub mod backtest;
+pub mod breadth;
 pub mod checkpoint;
+pub mod custom_index;
+pub mod fx;
 pub mod import;
+pub mod metadata;
+pub mod materializer;
 pub mod ohlcv;
+pub mod portfolio;
+pub mod screener;
+pub mod validation;
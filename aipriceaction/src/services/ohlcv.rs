@@ -1,8 +1,10 @@
 use chrono::{Duration, Utc};
 use sqlx::PgPool;
 
+use crate::models::corporate_action::{self, Adjustment};
 use crate::models::ohlcv::{OhlcvJoined, OhlcvRow, Ticker};
-use crate::queries::ohlcv;
+use crate::queries::ohlcv::FetchOptions;
+use crate::queries::{corporate_actions, ohlcv};
 
 /// Ensure ticker exists, return its id.
 pub async fn ensure_ticker(pool: &PgPool, source: &str, ticker: &str) -> sqlx::Result<i32> {
@@ -158,38 +160,43 @@ pub async fn count_ohlcv(
     ohlcv::count_ohlcv(pool, source, ticker, interval).await
 }
 
-/// Batch-fetch joined OHLCV + indicators for tickers of a source + interval.
+/// Batch-fetch joined OHLCV + indicators for tickers of a source + interval,
+/// also including tickers from `opts.extra_sources`.
 ///
-/// When `symbols` is empty, fetches ALL tickers for the source.
-/// When `symbols` is non-empty, fetches only the specified tickers.
+/// When `symbols` is empty, fetches ALL tickers for the source. Prices are
+/// back-adjusted for recorded corporate actions unless `Adjustment::Raw`, MAs
+/// are computed for `opts.ma_periods`, and any extra `opts.indicators` are added.
 pub async fn get_ohlcv_joined_batch(
     pool: &PgPool,
     source: &str,
     symbols: &[String],
     interval: &str,
-    limit: Option<i64>,
-    start_time: Option<chrono::DateTime<chrono::Utc>>,
-    end_time: Option<chrono::DateTime<chrono::Utc>>,
-    with_ma: bool,
-    use_ema: bool,
+    opts: &FetchOptions<'_>,
 ) -> sqlx::Result<std::collections::HashMap<String, Vec<OhlcvJoined>>> {
-    get_ohlcv_joined_batch_with_extra(pool, source, symbols, interval, limit, start_time, end_time, &[], with_ma, use_ema).await
+    ohlcv::get_ohlcv_joined_batch(pool, source, symbols, interval, opts).await
 }
 
-/// Like `get_ohlcv_joined_batch` but also includes tickers from extra_sources.
-pub async fn get_ohlcv_joined_batch_with_extra(
+/// Back-adjust raw rows in place for recorded corporate actions.
+/// No-op (and no query) for `Adjustment::Raw`.
+pub async fn adjust_raw_batch(
     pool: &PgPool,
     source: &str,
-    symbols: &[String],
-    interval: &str,
-    limit: Option<i64>,
-    start_time: Option<chrono::DateTime<chrono::Utc>>,
-    end_time: Option<chrono::DateTime<chrono::Utc>>,
     extra_sources: &[&str],
-    with_ma: bool,
-    use_ema: bool,
-) -> sqlx::Result<std::collections::HashMap<String, Vec<OhlcvJoined>>> {
-    ohlcv::get_ohlcv_joined_batch_with_extra(pool, source, symbols, interval, limit, start_time, end_time, extra_sources, with_ma, use_ema).await
+    symbols: &[String],
+    rows: &mut std::collections::HashMap<String, Vec<OhlcvRow>>,
+    adjust: Adjustment,
+) -> sqlx::Result<()> {
+    if adjust == Adjustment::Raw {
+        return Ok(());
+    }
+    let sources: Vec<&str> = std::iter::once(source).chain(extra_sources.iter().copied()).collect();
+    let actions = corporate_actions::list_for_tickers(pool, &sources, symbols).await?;
+    for (ticker, ticker_rows) in rows.iter_mut() {
+        if let Some(ticker_actions) = actions.get(ticker) {
+            corporate_action::adjust_rows(ticker_rows, ticker_actions, adjust);
+        }
+    }
+    Ok(())
 }
 
 /// Batch-fetch raw OHLCV rows (no indicators) for tickers of a source + interval.
@@ -204,19 +211,17 @@ pub async fn get_ohlcv_batch_raw(
     start_time: Option<chrono::DateTime<chrono::Utc>>,
     end_time: Option<chrono::DateTime<chrono::Utc>>,
 ) -> sqlx::Result<std::collections::HashMap<String, Vec<OhlcvRow>>> {
-    get_ohlcv_batch_raw_with_extra(pool, source, symbols, interval, per_ticker_limit, start_time, end_time, &[]).await
+    ohlcv::get_ohlcv_batch_raw(pool, source, symbols, interval, per_ticker_limit, start_time, end_time).await
 }
 
-/// Like `get_ohlcv_batch_raw` but also includes tickers from extra_sources.
+/// Like `get_ohlcv_batch_raw` but also includes tickers from
+/// `opts.extra_sources`, with `opts.limit` as the per-ticker row cap.
 pub async fn get_ohlcv_batch_raw_with_extra(
     pool: &PgPool,
     source: &str,
     symbols: &[String],
     interval: &str,
-    per_ticker_limit: Option<i64>,
-    start_time: Option<chrono::DateTime<chrono::Utc>>,
-    end_time: Option<chrono::DateTime<chrono::Utc>>,
-    extra_sources: &[&str],
+    opts: &FetchOptions<'_>,
 ) -> sqlx::Result<std::collections::HashMap<String, Vec<OhlcvRow>>> {
-    ohlcv::get_ohlcv_batch_raw_with_extra(pool, source, symbols, interval, per_ticker_limit, start_time, end_time, extra_sources).await
+    ohlcv::get_ohlcv_batch_raw_with_extra(pool, source, symbols, interval, opts).await
 }
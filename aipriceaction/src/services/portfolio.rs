@@ -0,0 +1,673 @@
+//! Portfolio ledger replay and valuation for `/portfolios`.
+//!
+//! Holdings follow the average-cost method: a buy adds `quantity * price + fee`
+//! to the cost basis, a sell realises `quantity * (price - avg_cost) - fee`
+//! and leaves the average unchanged. Cash is kept per currency, so buying a
+//! VN stock draws on the VND balance and a crypto pair on the USDT one.
+//! Recorded splits and stock dividends (`corporate_actions`, type `split`)
+//! multiply the quantity held before the ex-date; the cost basis stays, so
+//! the average cost falls by the same ratio and stored raw closes keep
+//! valuing the position correctly.
+//!
+//! Holdings are valued at the last stored daily close on or before the
+//! valuation date (`SJC-GOLD` for gold), in the source's quote currency, and
+//! converted to the portfolio's base currency with the `VND=X` close of the
+//! same day. Realised P&L, dividends, fees and contributions are converted
+//! at the rate of their trade date.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use chrono::NaiveDate;
+use serde::Serialize;
+use sqlx::PgPool;
+
+use crate::constants::portfolio as cfg;
+use crate::models::corporate_action::ActionType;
+use crate::models::portfolio::{Currency, Transaction, TxKind};
+use crate::queries::{corporate_actions, ohlcv};
+
+/// Quantities below this are treated as a closed position.
+const QTY_EPSILON: f64 = 1e-9;
+
+// ── Prices ──
+
+/// Daily closes and share splits (ex-date, ratio) per (source, ticker),
+/// oldest first.
+#[derive(Debug, Default)]
+pub struct PriceBook {
+    series: HashMap<(String, String), Vec<(NaiveDate, f64)>>,
+    splits: HashMap<(String, String), Vec<(NaiveDate, f64)>>,
+}
+
+/// Traded instruments of `transactions`, grouped by source.
+fn instruments(transactions: &[Transaction]) -> BTreeMap<&str, BTreeSet<String>> {
+    let mut by_source: BTreeMap<&str, BTreeSet<String>> = BTreeMap::new();
+    for tx in transactions {
+        if let (Some(source), Some(ticker)) = (tx.source.as_deref(), tx.ticker.as_deref())
+            && tx.kind.is_trade()
+        {
+            by_source.entry(source).or_default().insert(ticker.to_string());
+        }
+    }
+    by_source
+}
+
+impl PriceBook {
+    pub fn insert(&mut self, source: &str, ticker: &str, mut closes: Vec<(NaiveDate, f64)>) {
+        closes.sort_by_key(|(d, _)| *d);
+        self.series.insert((source.to_string(), ticker.to_string()), closes);
+    }
+
+    pub fn insert_splits(&mut self, source: &str, ticker: &str, mut splits: Vec<(NaiveDate, f64)>) {
+        splits.sort_by_key(|(d, _)| *d);
+        self.splits.insert((source.to_string(), ticker.to_string()), splits);
+    }
+
+    /// Product of the split ratios of (source, ticker) with `after < ex-date <= through`.
+    fn split_factor(&self, key: &(String, String), after: NaiveDate, through: NaiveDate) -> f64 {
+        self.splits
+            .get(key)
+            .map_or(1.0, |s| s.iter().filter(|(d, _)| *d > after && *d <= through).map(|(_, r)| r).product())
+    }
+
+    /// Last close on or before `date`, with its date.
+    pub fn close_on(&self, source: &str, ticker: &str, date: NaiveDate) -> Option<(NaiveDate, f64)> {
+        let closes = self.series.get(&(source.to_string(), ticker.to_string()))?;
+        let i = closes.partition_point(|(d, _)| *d <= date);
+        i.checked_sub(1).map(|i| closes[i])
+    }
+
+    /// Factor converting `from` amounts into `to` on `date`.
+    pub fn fx(&self, from: Currency, to: Currency, date: NaiveDate) -> Option<f64> {
+        if from == to || (from.is_usd() && to.is_usd()) {
+            return Some(1.0);
+        }
+        let (_, vnd_per_usd) = self.close_on(cfg::FX_SOURCE, cfg::FX_TICKER, date)?;
+        if vnd_per_usd <= 0.0 {
+            return None;
+        }
+        Some(if from.is_usd() { vnd_per_usd } else { 1.0 / vnd_per_usd })
+    }
+
+    /// Load closes for every instrument in `transactions` (plus USD/VND when
+    /// more than one currency is involved) from `start` minus the lookback
+    /// through `end`.
+    pub async fn load(
+        pool: &PgPool,
+        transactions: &[Transaction],
+        base: Currency,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> sqlx::Result<Self> {
+        let mut book = Self::splits_only(pool, transactions).await?;
+        let mut by_source = instruments(transactions);
+        let mut currencies = BTreeSet::from([base]);
+        currencies.extend(transactions.iter().map(|tx| tx.currency));
+        if currencies.iter().any(|c| c.is_usd()) && currencies.contains(&Currency::Vnd) {
+            by_source.entry(cfg::FX_SOURCE).or_default().insert(cfg::FX_TICKER.to_string());
+        }
+
+        let start_time = (start - chrono::Duration::days(cfg::PRICE_LOOKBACK_DAYS))
+            .and_hms_opt(0, 0, 0)
+            .map(|t| t.and_utc());
+        let end_time = end.and_hms_opt(23, 59, 59).map(|t| t.and_utc());
+        for (source, tickers) in by_source {
+            let tickers: Vec<String> = tickers.into_iter().collect();
+            let rows = ohlcv::get_ohlcv_batch_raw(pool, source, &tickers, "1D", None, start_time, end_time).await?;
+            for (ticker, rows) in rows {
+                book.insert(source, &ticker, rows.iter().map(|r| (r.time.date_naive(), r.close)).collect());
+            }
+        }
+        Ok(book)
+    }
+
+    /// Just the recorded splits of the traded instruments: enough to replay
+    /// quantities (overselling checks) without prices.
+    pub async fn splits_only(pool: &PgPool, transactions: &[Transaction]) -> sqlx::Result<Self> {
+        let mut book = Self::default();
+        for (source, tickers) in instruments(transactions) {
+            let tickers: Vec<String> = tickers.into_iter().collect();
+            for (ticker, actions) in corporate_actions::list_for_tickers(pool, &[source], &tickers).await? {
+                let splits: Vec<(NaiveDate, f64)> = actions
+                    .iter()
+                    .filter(|a| a.action_type == ActionType::Split && a.ratio > 0.0)
+                    .map(|a| (a.ex_date, a.ratio))
+                    .collect();
+                if !splits.is_empty() {
+                    book.insert_splits(source, &ticker, splits);
+                }
+            }
+        }
+        Ok(book)
+    }
+}
+
+// ── Ledger ──
+
+/// Open position in one instrument, in its quote currency.
+#[derive(Debug, Clone, Default)]
+pub struct Position {
+    pub quantity: f64,
+    /// Cost of the shares still held, fees included.
+    pub cost_basis: f64,
+    pub realised_pnl: f64,
+    pub dividends: f64,
+}
+
+/// Portfolio state after replaying transactions in order. Totals are in the
+/// base currency.
+#[derive(Debug)]
+pub struct Ledger {
+    pub base: Currency,
+    pub positions: BTreeMap<(String, String), (Currency, Position)>,
+    pub cash: BTreeMap<Currency, f64>,
+    pub net_contributions: f64,
+    pub realised_pnl: f64,
+    pub dividends: f64,
+    pub fees: f64,
+    /// Dates a conversion rate was missing on; those amounts count as 0.
+    pub missing_fx: BTreeSet<NaiveDate>,
+    /// Splits with ex-dates up to here are applied to the positions.
+    splits_through: NaiveDate,
+}
+
+impl Ledger {
+    pub fn new(base: Currency) -> Self {
+        Self {
+            base,
+            positions: BTreeMap::new(),
+            cash: BTreeMap::new(),
+            net_contributions: 0.0,
+            realised_pnl: 0.0,
+            dividends: 0.0,
+            fees: 0.0,
+            missing_fx: BTreeSet::new(),
+            splits_through: NaiveDate::MIN,
+        }
+    }
+
+    /// Replay `transactions` (sorted by date) up to and including `until`.
+    pub fn replay(base: Currency, transactions: &[Transaction], until: NaiveDate, prices: &PriceBook) -> Result<Self, String> {
+        let mut ledger = Self::new(base);
+        for tx in transactions.iter().take_while(|tx| tx.trade_date <= until) {
+            ledger.apply(tx, prices)?;
+        }
+        ledger.apply_splits(until, prices);
+        Ok(ledger)
+    }
+
+    /// Apply the splits with ex-dates after the last applied one through
+    /// `date` to the open positions. A trade on the ex-date already uses the
+    /// post-split quantity and price.
+    pub fn apply_splits(&mut self, date: NaiveDate, prices: &PriceBook) {
+        if date <= self.splits_through {
+            return;
+        }
+        for (key, (_, pos)) in &mut self.positions {
+            let factor = prices.split_factor(key, self.splits_through, date);
+            if factor != 1.0 {
+                pos.quantity *= factor;
+            }
+        }
+        self.splits_through = date;
+    }
+
+    fn convert(&mut self, amount: f64, currency: Currency, date: NaiveDate, prices: &PriceBook) -> f64 {
+        match prices.fx(currency, self.base, date) {
+            Some(rate) => amount * rate,
+            None => {
+                self.missing_fx.insert(date);
+                0.0
+            }
+        }
+    }
+
+    /// Apply one entry (after the splits up to its date). Fails when a sell
+    /// exceeds the quantity held.
+    pub fn apply(&mut self, tx: &Transaction, prices: &PriceBook) -> Result<(), String> {
+        let (ccy, date) = (tx.currency, tx.trade_date);
+        self.apply_splits(date, prices);
+        let key = || (tx.source.clone().unwrap_or_default(), tx.ticker.clone().unwrap_or_default());
+        let fee_base = self.convert(tx.fee, ccy, date, prices);
+        self.fees += fee_base;
+        match tx.kind {
+            TxKind::Buy => {
+                let cost = tx.quantity * tx.price + tx.fee;
+                *self.cash.entry(ccy).or_default() -= cost;
+                let (_, pos) = self.positions.entry(key()).or_insert_with(|| (ccy, Position::default()));
+                pos.quantity += tx.quantity;
+                pos.cost_basis += cost;
+            }
+            TxKind::Sell => {
+                let held = self.positions.get(&key()).map_or(0.0, |(_, p)| p.quantity);
+                if tx.quantity > held + QTY_EPSILON {
+                    return Err(format!(
+                        "{} {}: selling {} on {} but only {} held",
+                        key().0, key().1, tx.quantity, date, held
+                    ));
+                }
+                let proceeds = tx.quantity * tx.price - tx.fee;
+                *self.cash.entry(ccy).or_default() += proceeds;
+                let Some((_, pos)) = self.positions.get_mut(&key()) else { return Ok(()) };
+                let released = if pos.quantity > 0.0 { pos.cost_basis * tx.quantity / pos.quantity } else { 0.0 };
+                let realised = proceeds - released;
+                pos.quantity -= tx.quantity;
+                pos.cost_basis -= released;
+                pos.realised_pnl += realised;
+                if pos.quantity <= QTY_EPSILON {
+                    pos.quantity = 0.0;
+                    pos.cost_basis = 0.0;
+                }
+                self.realised_pnl += self.convert(realised, ccy, date, prices);
+            }
+            TxKind::Dividend => {
+                *self.cash.entry(ccy).or_default() += tx.amount - tx.fee;
+                if tx.ticker.is_some()
+                    && let Some((_, pos)) = self.positions.get_mut(&key())
+                {
+                    pos.dividends += tx.amount;
+                }
+                self.dividends += self.convert(tx.amount, ccy, date, prices);
+            }
+            TxKind::Fee => {
+                *self.cash.entry(ccy).or_default() -= tx.amount + tx.fee;
+                self.fees += self.convert(tx.amount, ccy, date, prices);
+            }
+            TxKind::Deposit => {
+                *self.cash.entry(ccy).or_default() += tx.amount - tx.fee;
+                self.net_contributions += self.convert(tx.amount, ccy, date, prices);
+            }
+            TxKind::Withdrawal => {
+                *self.cash.entry(ccy).or_default() -= tx.amount + tx.fee;
+                self.net_contributions -= self.convert(tx.amount, ccy, date, prices);
+            }
+        }
+        Ok(())
+    }
+
+    /// Mark the ledger to market on `date`, which splits must already be
+    /// applied through (see [`Ledger::apply_splits`]). `sectors` maps (source, ticker)
+    /// to the allocation bucket; unmapped holdings go to `OTHER`.
+    pub fn value(&self, date: NaiveDate, prices: &PriceBook, sectors: &HashMap<(String, String), String>) -> Valuation {
+        let mut missing_prices = Vec::new();
+        let mut missing_fx = self.missing_fx.clone();
+        let mut fx = |from: Currency| match prices.fx(from, self.base, date) {
+            Some(rate) => rate,
+            None => {
+                missing_fx.insert(date);
+                0.0
+            }
+        };
+
+        let mut holdings = Vec::new();
+        for ((source, ticker), (currency, pos)) in &self.positions {
+            if pos.quantity <= 0.0 {
+                continue;
+            }
+            let close = prices.close_on(source, ticker, date);
+            if close.is_none() {
+                missing_prices.push(format!("{source}:{ticker}"));
+            }
+            let last_price = close.map(|(_, c)| c);
+            let market_value = last_price.map(|p| p * pos.quantity);
+            let unrealised_pnl = market_value.map(|v| v - pos.cost_basis);
+            holdings.push(Holding {
+                source: source.clone(),
+                ticker: ticker.clone(),
+                sector: sectors.get(&(source.clone(), ticker.clone())).cloned().unwrap_or_else(|| "OTHER".to_string()),
+                currency: *currency,
+                quantity: pos.quantity,
+                avg_cost: pos.cost_basis / pos.quantity,
+                cost_basis: pos.cost_basis,
+                last_price,
+                price_date: close.map(|(d, _)| d.to_string()),
+                market_value,
+                unrealised_pnl,
+                unrealised_pnl_pct: unrealised_pnl
+                    .filter(|_| pos.cost_basis > 0.0)
+                    .map(|p| p / pos.cost_basis * 100.0),
+                realised_pnl: pos.realised_pnl,
+                dividends: pos.dividends,
+                market_value_base: market_value.unwrap_or(0.0) * fx(*currency),
+                weight_pct: 0.0,
+            });
+        }
+
+        let cash: Vec<CashBalance> = self
+            .cash
+            .iter()
+            .map(|(currency, amount)| CashBalance { currency: *currency, amount: *amount, value: amount * fx(*currency) })
+            .collect();
+        let cash_value: f64 = cash.iter().map(|c| c.value).sum();
+        let market_value: f64 = holdings.iter().map(|h| h.market_value_base).sum();
+        let unrealised_pnl: f64 = holdings
+            .iter()
+            .map(|h| h.unrealised_pnl.unwrap_or(0.0) * fx(h.currency))
+            .sum();
+        let nav = cash_value + market_value;
+
+        let mut by_sector: BTreeMap<String, f64> = BTreeMap::new();
+        for h in &holdings {
+            *by_sector.entry(h.sector.clone()).or_default() += h.market_value_base;
+        }
+        if cash_value != 0.0 {
+            *by_sector.entry("CASH".to_string()).or_default() += cash_value;
+        }
+        let weight = |v: f64| if nav != 0.0 { v / nav * 100.0 } else { 0.0 };
+        for h in &mut holdings {
+            h.weight_pct = weight(h.market_value_base);
+        }
+        holdings.sort_by(|a, b| b.market_value_base.total_cmp(&a.market_value_base));
+        let mut allocation: Vec<Allocation> = by_sector
+            .into_iter()
+            .map(|(sector, value)| Allocation { weight_pct: weight(value), sector, value })
+            .collect();
+        allocation.sort_by(|a, b| b.value.total_cmp(&a.value));
+
+        let total_pnl = nav - self.net_contributions;
+        Valuation {
+            date: date.to_string(),
+            base_currency: self.base,
+            nav,
+            cash_value,
+            market_value,
+            net_contributions: self.net_contributions,
+            total_pnl,
+            total_pnl_pct: (self.net_contributions > 0.0).then(|| total_pnl / self.net_contributions * 100.0),
+            realised_pnl: self.realised_pnl,
+            unrealised_pnl,
+            dividends: self.dividends,
+            fees: self.fees,
+            cash,
+            holdings,
+            allocation,
+            missing_prices,
+            missing_fx: missing_fx.into_iter().map(|d| d.to_string()).collect(),
+        }
+    }
+}
+
+// ── Reports ──
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Valuation {
+    pub date: String,
+    pub base_currency: Currency,
+    pub nav: f64,
+    pub cash_value: f64,
+    pub market_value: f64,
+    /// Deposits minus withdrawals.
+    pub net_contributions: f64,
+    /// `nav - net_contributions`.
+    pub total_pnl: f64,
+    pub total_pnl_pct: Option<f64>,
+    pub realised_pnl: f64,
+    pub unrealised_pnl: f64,
+    pub dividends: f64,
+    pub fees: f64,
+    pub cash: Vec<CashBalance>,
+    pub holdings: Vec<Holding>,
+    pub allocation: Vec<Allocation>,
+    /// Holdings without a stored close on or before `date`, valued at 0.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub missing_prices: Vec<String>,
+    /// Dates without a USD/VND rate; amounts on them count as 0.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub missing_fx: Vec<String>,
+}
+
+/// One open position; money fields are in its quote `currency` except
+/// `market_value_base`.
+#[derive(Debug, Clone, Serialize)]
+pub struct Holding {
+    pub source: String,
+    pub ticker: String,
+    pub sector: String,
+    pub currency: Currency,
+    pub quantity: f64,
+    pub avg_cost: f64,
+    pub cost_basis: f64,
+    pub last_price: Option<f64>,
+    pub price_date: Option<String>,
+    pub market_value: Option<f64>,
+    pub unrealised_pnl: Option<f64>,
+    pub unrealised_pnl_pct: Option<f64>,
+    pub realised_pnl: f64,
+    pub dividends: f64,
+    pub market_value_base: f64,
+    pub weight_pct: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CashBalance {
+    pub currency: Currency,
+    pub amount: f64,
+    /// In the base currency.
+    pub value: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Allocation {
+    pub sector: String,
+    pub value: f64,
+    pub weight_pct: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NavPoint {
+    pub date: String,
+    pub nav: f64,
+    pub cash_value: f64,
+    pub market_value: f64,
+    pub net_contributions: f64,
+    pub total_pnl: f64,
+}
+
+/// Daily NAV from `start` through `end` (calendar days; closes carry over
+/// weekends and holidays).
+pub fn nav_history(
+    base: Currency,
+    transactions: &[Transaction],
+    start: NaiveDate,
+    end: NaiveDate,
+    prices: &PriceBook,
+) -> Result<Vec<NavPoint>, String> {
+    let no_sectors = HashMap::new();
+    let mut ledger = Ledger::new(base);
+    let mut pending = transactions.iter().peekable();
+    let mut points = Vec::new();
+    for date in start.iter_days().take_while(|d| *d <= end) {
+        while let Some(tx) = pending.next_if(|tx| tx.trade_date <= date) {
+            ledger.apply(tx, prices)?;
+        }
+        ledger.apply_splits(date, prices);
+        let v = ledger.value(date, prices, &no_sectors);
+        points.push(NavPoint {
+            date: v.date,
+            nav: v.nav,
+            cash_value: v.cash_value,
+            market_value: v.market_value,
+            net_contributions: v.net_contributions,
+            total_pnl: v.total_pnl,
+        });
+    }
+    Ok(points)
+}
+
+/// Check a new or edited entry on its own; ledger-wide checks (overselling)
+/// happen on replay.
+pub fn validate_transaction(tx: &mut Transaction) -> Result<(), String> {
+    let finite_non_negative = |v: f64| v.is_finite() && v >= 0.0;
+    if ![tx.quantity, tx.price, tx.amount, tx.fee].into_iter().all(finite_non_negative) {
+        return Err("quantity, price, amount and fee must be non-negative numbers".to_string());
+    }
+    tx.source = tx.source.take().map(|s| s.trim().to_lowercase()).filter(|s| !s.is_empty());
+    tx.ticker = tx.ticker.take().map(|t| t.trim().to_uppercase()).filter(|t| !t.is_empty());
+
+    if tx.kind.is_trade() {
+        let (Some(source), Some(_)) = (tx.source.as_deref(), tx.ticker.as_deref()) else {
+            return Err(format!("{} needs source and ticker", tx.kind.as_str()));
+        };
+        let Some(quote) = Currency::for_source(source) else {
+            return Err(format!("Unsupported source '{source}'. Must be one of: vn, yahoo, crypto, sjc"));
+        };
+        if tx.currency != quote {
+            return Err(format!("{source} trades settle in {}", quote.as_str()));
+        }
+        if tx.quantity <= 0.0 {
+            return Err("quantity must be positive".to_string());
+        }
+    } else if tx.amount <= 0.0 {
+        return Err(format!("{} needs a positive amount", tx.kind.as_str()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn d(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 3, day).unwrap()
+    }
+
+    fn tx(kind: TxKind, day: u32, instrument: Option<(&str, &str)>, quantity: f64, price: f64, amount: f64, currency: Currency) -> Transaction {
+        Transaction {
+            id: 0,
+            kind,
+            trade_date: d(day),
+            source: instrument.map(|(s, _)| s.to_string()),
+            ticker: instrument.map(|(_, t)| t.to_string()),
+            quantity,
+            price,
+            amount,
+            fee: 0.0,
+            currency,
+            note: None,
+        }
+    }
+
+    fn book() -> PriceBook {
+        let mut book = PriceBook::default();
+        book.insert("vn", "VCB", vec![(d(3), 90_000.0), (d(4), 100_000.0), (d(7), 110_000.0)]);
+        book.insert("sjc", "SJC-GOLD", vec![(d(3), 90_000_000.0)]);
+        book.insert("crypto", "BTCUSDT", vec![(d(3), 80_000.0), (d(7), 90_000.0)]);
+        book.insert(cfg::FX_SOURCE, cfg::FX_TICKER, vec![(d(1), 25_000.0), (d(6), 26_000.0)]);
+        book
+    }
+
+    #[test]
+    fn test_average_cost_realised_and_unrealised_pnl() {
+        let prices = book();
+        let txs = vec![
+            tx(TxKind::Deposit, 1, None, 0.0, 0.0, 100_000_000.0, Currency::Vnd),
+            tx(TxKind::Buy, 3, Some(("vn", "VCB")), 200.0, 90_000.0, 0.0, Currency::Vnd),
+            tx(TxKind::Buy, 4, Some(("vn", "VCB")), 200.0, 100_000.0, 0.0, Currency::Vnd),
+            tx(TxKind::Sell, 7, Some(("vn", "VCB")), 100.0, 110_000.0, 0.0, Currency::Vnd),
+            tx(TxKind::Dividend, 7, Some(("vn", "VCB")), 0.0, 0.0, 300_000.0, Currency::Vnd),
+        ];
+        let ledger = Ledger::replay(Currency::Vnd, &txs, d(7), &prices).unwrap();
+        let (_, vcb) = &ledger.positions[&("vn".to_string(), "VCB".to_string())];
+        // avg cost 95,000; selling 100 at 110,000 realises 1.5M
+        assert_eq!(vcb.quantity, 300.0);
+        assert_eq!(vcb.cost_basis, 28_500_000.0);
+        assert_eq!(ledger.realised_pnl, 1_500_000.0);
+
+        let sectors = HashMap::from([(("vn".to_string(), "VCB".to_string()), "NGAN_HANG".to_string())]);
+        let v = ledger.value(d(7), &prices, &sectors);
+        assert_eq!(v.market_value, 33_000_000.0);
+        assert_eq!(v.unrealised_pnl, 4_500_000.0);
+        // 100M - 38M + 11M + 0.3M cash
+        assert_eq!(v.cash_value, 73_300_000.0);
+        assert_eq!(v.nav, 106_300_000.0);
+        assert_eq!(v.total_pnl, 6_300_000.0);
+        assert_eq!(v.allocation[0].sector, "CASH");
+        assert_eq!(v.allocation[1].sector, "NGAN_HANG");
+    }
+
+    #[test]
+    fn test_multi_currency_valuation_uses_fx_of_the_day() {
+        let prices = book();
+        let txs = vec![
+            tx(TxKind::Deposit, 1, None, 0.0, 0.0, 10_000.0, Currency::Usdt),
+            tx(TxKind::Buy, 3, Some(("crypto", "BTCUSDT")), 0.1, 80_000.0, 0.0, Currency::Usdt),
+            tx(TxKind::Deposit, 3, None, 0.0, 0.0, 100_000_000.0, Currency::Vnd),
+            tx(TxKind::Buy, 3, Some(("sjc", "SJC-GOLD")), 1.0, 90_000_000.0, 0.0, Currency::Vnd),
+        ];
+        let ledger = Ledger::replay(Currency::Vnd, &txs, d(7), &prices).unwrap();
+        // USDT deposit converted at 25,000 on day 1
+        assert_eq!(ledger.net_contributions, 250_000_000.0 + 100_000_000.0);
+        let v = ledger.value(d(7), &prices, &HashMap::new());
+        // BTC 0.1 * 90,000 USDT at 26,000 + gold 90M
+        assert_eq!(v.market_value, 234_000_000.0 + 90_000_000.0);
+        assert_eq!(v.cash_value, 2_000.0 * 26_000.0 + 10_000_000.0);
+        assert!(v.missing_fx.is_empty() && v.missing_prices.is_empty());
+
+        // Same ledger in USD
+        let usd = Ledger::replay(Currency::Usd, &txs, d(7), &prices).unwrap().value(d(7), &prices, &HashMap::new());
+        assert!((usd.nav - v.nav / 26_000.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_overselling_is_rejected_and_nav_history_carries_closes() {
+        let prices = book();
+        let mut txs = vec![
+            tx(TxKind::Deposit, 1, None, 0.0, 0.0, 50_000_000.0, Currency::Vnd),
+            tx(TxKind::Buy, 3, Some(("vn", "VCB")), 100.0, 90_000.0, 0.0, Currency::Vnd),
+        ];
+        let nav = nav_history(Currency::Vnd, &txs, d(2), d(5), &prices).unwrap();
+        assert_eq!(nav.len(), 4);
+        assert_eq!(nav[0].nav, 50_000_000.0);
+        // Day 5 has no bar: day 4's close carries over
+        assert_eq!(nav[3].market_value, 10_000_000.0);
+        assert_eq!(nav[3].total_pnl, 1_000_000.0);
+
+        txs.push(tx(TxKind::Sell, 4, Some(("vn", "VCB")), 200.0, 100_000.0, 0.0, Currency::Vnd));
+        assert!(Ledger::replay(Currency::Vnd, &txs, d(7), &prices).is_err());
+    }
+
+    #[test]
+    fn test_split_during_holding_scales_quantity_not_value() {
+        let mut prices = PriceBook::default();
+        // 2-for-1 on the 5th: the raw close halves
+        prices.insert("vn", "FPT", vec![(d(3), 100_000.0), (d(4), 100_000.0), (d(5), 50_000.0), (d(6), 52_000.0)]);
+        prices.insert_splits("vn", "FPT", vec![(d(5), 2.0)]);
+        let txs = vec![
+            tx(TxKind::Deposit, 1, None, 0.0, 0.0, 20_000_000.0, Currency::Vnd),
+            tx(TxKind::Buy, 3, Some(("vn", "FPT")), 100.0, 100_000.0, 0.0, Currency::Vnd),
+            tx(TxKind::Buy, 5, Some(("vn", "FPT")), 100.0, 50_000.0, 0.0, Currency::Vnd),
+            tx(TxKind::Sell, 6, Some(("vn", "FPT")), 250.0, 52_000.0, 0.0, Currency::Vnd),
+        ];
+
+        let nav = nav_history(Currency::Vnd, &txs[..2], d(3), d(5), &prices).unwrap();
+        let navs: Vec<f64> = nav.iter().map(|p| p.nav).collect();
+        assert_eq!(navs, vec![20_000_000.0; 3], "no loss on the ex-date");
+
+        let ledger = Ledger::replay(Currency::Vnd, &txs[..3], d(5), &prices).unwrap();
+        let (_, fpt) = &ledger.positions[&("vn".to_string(), "FPT".to_string())];
+        assert_eq!(fpt.quantity, 300.0);
+        assert_eq!(fpt.cost_basis, 15_000_000.0);
+        let v = ledger.value(d(5), &prices, &HashMap::new());
+        assert_eq!(v.holdings[0].avg_cost, 50_000.0);
+        assert_eq!(v.unrealised_pnl, 0.0);
+
+        // Selling more than bought pre-split is covered by the split shares
+        let ledger = Ledger::replay(Currency::Vnd, &txs, d(6), &prices).unwrap();
+        assert_eq!(ledger.realised_pnl, 250.0 * 2_000.0);
+        assert!(Ledger::replay(Currency::Vnd, &txs, d(6), &PriceBook::default()).is_err());
+    }
+
+    #[test]
+    fn test_validate_transaction() {
+        let mut buy = tx(TxKind::Buy, 3, Some((" VN ", "vcb")), 100.0, 90_000.0, 0.0, Currency::Vnd);
+        assert!(validate_transaction(&mut buy).is_ok());
+        assert_eq!((buy.source.as_deref(), buy.ticker.as_deref()), (Some("vn"), Some("VCB")));
+
+        let mut wrong_ccy = tx(TxKind::Buy, 3, Some(("crypto", "BTCUSDT")), 1.0, 1.0, 0.0, Currency::Vnd);
+        assert!(validate_transaction(&mut wrong_ccy).is_err());
+        let mut no_ticker = tx(TxKind::Sell, 3, None, 1.0, 1.0, 0.0, Currency::Vnd);
+        assert!(validate_transaction(&mut no_ticker).is_err());
+        let mut empty_deposit = tx(TxKind::Deposit, 3, None, 0.0, 0.0, 0.0, Currency::Usd);
+        assert!(validate_transaction(&mut empty_deposit).is_err());
+    }
+}
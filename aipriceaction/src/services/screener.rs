@@ -0,0 +1,959 @@
+//! Filter expressions for `POST /analysis/screen`, e.g.
+//! `close > ma50 and ma20_score > 2 and volume > 2 * avg(volume, 20) and sector in ["NGAN_HANG"]`.
+//!
+//! ```text
+//! expr    := and ("or" and)*
+//! and     := not ("and" not)*
+//! not     := "not" not | cmp
+//! cmp     := sum (("<" | "<=" | ">" | ">=" | "==" | "!=") sum | ["not"] "in" list)?
+//! sum     := product (("+" | "-") product)*
+//! product := unary (("*" | "/") unary)*
+//! unary   := "-" unary | primary
+//! primary := number | string | list | field | call | "(" expr ")"
+//! call    := ("avg" | "sum" | "min" | "max" | "prev") "(" field "," n ")" | "abs" "(" expr ")"
+//! ```
+//!
+//! Fields are the `OhlcvJoined` columns, `symbol` / `sector` / `source`, any
+//! `ma{p}` / `ma{p}_score`, and any `indicators=` output field (`rsi14`,
+//! `bb20_upper`, ...). Bars are newest first: `avg(volume, 20)` covers the
+//! newest 20 bars and `prev(close, 1)` is the previous bar's close. A condition
+//! over a missing value (MA still warming up, no sector) does not match.
+
+use std::collections::BTreeSet;
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::models::indicators::{Indicator, MAX_MA_PERIOD};
+use crate::models::ohlcv::OhlcvJoined;
+
+/// Longest accepted expression, in bytes.
+pub const MAX_EXPR_LEN: usize = 1000;
+/// Largest `n` in `avg(field, n)` and friends.
+pub const MAX_WINDOW: usize = 250;
+/// Deepest nesting of parentheses, lists and unary operators.
+const MAX_DEPTH: usize = 32;
+
+const NUMERIC_FIELDS: &[&str] = &[
+    "open", "high", "low", "close", "volume", "close_changed", "volume_changed", "total_money_changed",
+];
+const TEXT_FIELDS: &[&str] = &["symbol", "sector", "source"];
+
+/// Evaluated value of a (sub)expression.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum Value {
+    Bool(bool),
+    Num(f64),
+    Str(String),
+    List(Vec<Value>),
+}
+
+impl Value {
+    fn same(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Num(a), Value::Num(b)) => a == b,
+            (Value::Str(a), Value::Str(b)) => a.eq_ignore_ascii_case(b),
+            _ => false,
+        }
+    }
+}
+
+/// A comparison that passed, with the operand values that made it pass.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Hit {
+    pub condition: String,
+    pub left: Value,
+    pub right: Value,
+}
+
+/// One ticker as seen by an expression: its bars (newest first) plus the
+/// text fields that are not part of `OhlcvJoined`.
+pub struct Row<'a> {
+    pub bars: &'a [OhlcvJoined],
+    pub sector: Option<&'a str>,
+    pub source: &'a str,
+}
+
+/// What the data fetch must provide for a set of expressions.
+#[derive(Debug, Default)]
+pub struct Requirements {
+    /// Periods referenced through `ma{p}` / `ma{p}_score`.
+    pub ma_periods: BTreeSet<usize>,
+    /// Indicators whose output fields are referenced.
+    pub indicators: Vec<Indicator>,
+    /// Newest bars needed per ticker (at least 1).
+    pub bars: usize,
+}
+
+impl Requirements {
+    pub fn add(&mut self, expr: &Expression) {
+        self.bars = self.bars.max(1);
+        expr.expr.visit(&mut |e| match e {
+            Expr::Field(name) | Expr::Window(_, name, _) => {
+                if let Some((period, _)) = ma_field(name) {
+                    self.ma_periods.insert(period);
+                } else if let Some(ind) = Indicator::for_field(name)
+                    && !self.indicators.contains(&ind)
+                {
+                    self.indicators.push(ind);
+                }
+                if let Expr::Window(window, _, n) = e {
+                    let bars = if *window == Window::Prev { n + 1 } else { *n };
+                    self.bars = self.bars.max(bars);
+                }
+            }
+            _ => {}
+        });
+    }
+}
+
+/// A parsed, type-checked expression.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expression {
+    expr: Expr,
+}
+
+impl Expression {
+    /// Parse a filter: the expression must be a condition.
+    pub fn parse_filter(src: &str) -> Result<Self, String> {
+        Self::parse(src, Kind::Bool)
+    }
+
+    /// Parse a ranking expression: the expression must be numeric.
+    pub fn parse_value(src: &str) -> Result<Self, String> {
+        Self::parse(src, Kind::Num)
+    }
+
+    fn parse(src: &str, want: Kind) -> Result<Self, String> {
+        if src.len() > MAX_EXPR_LEN {
+            return Err(format!("Expression longer than {MAX_EXPR_LEN} characters"));
+        }
+        let mut parser = Parser { tokens: tokenize(src)?, pos: 0, depth: 0 };
+        if parser.tokens.is_empty() {
+            return Err("Empty expression".to_string());
+        }
+        let expr = parser.or()?;
+        if let Some(tok) = parser.tokens.get(parser.pos) {
+            return Err(format!("Unexpected {tok} after '{expr}'"));
+        }
+        let kind = expr.kind()?;
+        if kind != want {
+            return Err(format!("'{expr}' is a {kind}, expected a {want}"));
+        }
+        Ok(Self { expr })
+    }
+
+    /// Evaluate a filter. Returns the passing comparisons when it matches.
+    pub fn matches(&self, row: &Row) -> Option<Vec<Hit>> {
+        let mut hits = Vec::new();
+        (self.expr.eval(row, &mut hits)? == Value::Bool(true)).then_some(hits)
+    }
+
+    /// Evaluate a ranking expression.
+    pub fn value(&self, row: &Row) -> Option<f64> {
+        match self.expr.eval(row, &mut Vec::new())? {
+            Value::Num(v) => Some(v),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.expr.fmt(f)
+    }
+}
+
+// ── Syntax tree ──
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Bool,
+    Num,
+    Str,
+    List,
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Kind::Bool => "condition",
+            Kind::Num => "number",
+            Kind::Str => "string",
+            Kind::List => "list",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Window {
+    Avg,
+    Sum,
+    Min,
+    Max,
+    Prev,
+}
+
+impl Window {
+    fn name(self) -> &'static str {
+        match self {
+            Window::Avg => "avg",
+            Window::Sum => "sum",
+            Window::Min => "min",
+            Window::Max => "max",
+            Window::Prev => "prev",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CmpOp {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+}
+
+impl CmpOp {
+    fn from_op(op: &str) -> Option<Self> {
+        Some(match op {
+            "<" => CmpOp::Lt,
+            "<=" => CmpOp::Le,
+            ">" => CmpOp::Gt,
+            ">=" => CmpOp::Ge,
+            "==" => CmpOp::Eq,
+            "!=" => CmpOp::Ne,
+            _ => return None,
+        })
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            CmpOp::Lt => "<",
+            CmpOp::Le => "<=",
+            CmpOp::Gt => ">",
+            CmpOp::Ge => ">=",
+            CmpOp::Eq => "==",
+            CmpOp::Ne => "!=",
+        }
+    }
+
+    fn test(self, ord: std::cmp::Ordering) -> bool {
+        use std::cmp::Ordering::*;
+        match self {
+            CmpOp::Lt => ord == Less,
+            CmpOp::Le => ord != Greater,
+            CmpOp::Gt => ord == Greater,
+            CmpOp::Ge => ord != Less,
+            CmpOp::Eq => ord == Equal,
+            CmpOp::Ne => ord != Equal,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Num(f64),
+    Str(String),
+    List(Vec<Expr>),
+    Field(String),
+    Window(Window, String, usize),
+    Abs(Box<Expr>),
+    Neg(Box<Expr>),
+    Arith(char, Box<Expr>, Box<Expr>),
+    Cmp(CmpOp, Box<Expr>, Box<Expr>),
+    In(Box<Expr>, Box<Expr>, bool),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+}
+
+impl Expr {
+    /// Binding strength, used to parenthesise when printing.
+    fn precedence(&self) -> u8 {
+        match self {
+            Expr::Or(..) => 1,
+            Expr::And(..) => 2,
+            Expr::Not(_) => 3,
+            Expr::Cmp(..) | Expr::In(..) => 4,
+            Expr::Arith('+' | '-', ..) => 5,
+            Expr::Arith(..) => 6,
+            Expr::Neg(_) => 7,
+            _ => 8,
+        }
+    }
+
+    fn visit(&self, f: &mut impl FnMut(&Expr)) {
+        f(self);
+        match self {
+            Expr::List(items) => items.iter().for_each(|e| e.visit(f)),
+            Expr::Abs(e) | Expr::Neg(e) | Expr::Not(e) => e.visit(f),
+            Expr::Arith(_, l, r) | Expr::Cmp(_, l, r) | Expr::In(l, r, _) | Expr::And(l, r) | Expr::Or(l, r) => {
+                l.visit(f);
+                r.visit(f);
+            }
+            _ => {}
+        }
+    }
+
+    fn kind(&self) -> Result<Kind, String> {
+        let num = |e: &Expr| match e.kind()? {
+            Kind::Num => Ok(()),
+            k => Err(format!("'{e}' is a {k}, expected a number")),
+        };
+        let cond = |e: &Expr| match e.kind()? {
+            Kind::Bool => Ok(()),
+            k => Err(format!("'{e}' is a {k}, expected a condition")),
+        };
+        match self {
+            Expr::Num(_) | Expr::Window(..) => Ok(Kind::Num),
+            Expr::Str(_) => Ok(Kind::Str),
+            Expr::Field(name) => Ok(if TEXT_FIELDS.contains(&name.as_str()) { Kind::Str } else { Kind::Num }),
+            Expr::List(items) => {
+                for item in items {
+                    if !matches!(item, Expr::Num(_) | Expr::Str(_)) {
+                        return Err(format!("List items must be numbers or strings, got '{item}'"));
+                    }
+                }
+                Ok(Kind::List)
+            }
+            Expr::Abs(e) | Expr::Neg(e) => num(e).map(|_| Kind::Num),
+            Expr::Arith(_, l, r) => num(l).and(num(r)).map(|_| Kind::Num),
+            Expr::Cmp(op, l, r) => match (l.kind()?, r.kind()?) {
+                (Kind::Num, Kind::Num) => Ok(Kind::Bool),
+                (Kind::Str, Kind::Str) if matches!(op, CmpOp::Eq | CmpOp::Ne) => Ok(Kind::Bool),
+                (lk, rk) => Err(format!("Cannot compare a {lk} with a {rk} in '{self}'")),
+            },
+            Expr::In(l, r, _) => match (l.kind()?, r.kind()?) {
+                (Kind::Num | Kind::Str, Kind::List) => Ok(Kind::Bool),
+                _ => Err(format!("'in' needs a number or string on the left and a [...] list on the right in '{self}'")),
+            },
+            Expr::And(l, r) | Expr::Or(l, r) => cond(l).and(cond(r)).map(|_| Kind::Bool),
+            Expr::Not(e) => cond(e).map(|_| Kind::Bool),
+        }
+    }
+
+    fn eval(&self, row: &Row, hits: &mut Vec<Hit>) -> Option<Value> {
+        let num = |e: &Expr, hits: &mut Vec<Hit>| match e.eval(row, hits)? {
+            Value::Num(v) => Some(v),
+            _ => None,
+        };
+        match self {
+            Expr::Num(v) => Some(Value::Num(*v)),
+            Expr::Str(s) => Some(Value::Str(s.clone())),
+            Expr::List(items) => items.iter().map(|e| e.eval(row, hits)).collect::<Option<_>>().map(Value::List),
+            Expr::Field(name) => match name.as_str() {
+                "symbol" => row.bars.first().map(|b| Value::Str(b.ticker.clone())),
+                "sector" => row.sector.map(|s| Value::Str(s.to_string())),
+                "source" => Some(Value::Str(row.source.to_string())),
+                _ => field_value(row.bars.first()?, name).map(Value::Num),
+            },
+            Expr::Window(window, name, n) => {
+                if *window == Window::Prev {
+                    return field_value(row.bars.get(*n)?, name).map(Value::Num);
+                }
+                if row.bars.len() < *n {
+                    return None;
+                }
+                let values: Vec<f64> = row.bars[..*n].iter().map(|b| field_value(b, name)).collect::<Option<_>>()?;
+                let v = match window {
+                    Window::Avg => values.iter().sum::<f64>() / *n as f64,
+                    Window::Sum => values.iter().sum(),
+                    Window::Min => values.iter().copied().fold(f64::INFINITY, f64::min),
+                    Window::Max => values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+                    Window::Prev => unreachable!(),
+                };
+                Some(Value::Num(v))
+            }
+            Expr::Abs(e) => Some(Value::Num(num(e, hits)?.abs())),
+            Expr::Neg(e) => Some(Value::Num(-num(e, hits)?)),
+            Expr::Arith(op, l, r) => {
+                let (a, b) = (num(l, hits)?, num(r, hits)?);
+                let v = match op {
+                    '+' => a + b,
+                    '-' => a - b,
+                    '*' => a * b,
+                    _ => a / b,
+                };
+                v.is_finite().then_some(Value::Num(v))
+            }
+            Expr::Cmp(op, l, r) => {
+                let (a, b) = (l.eval(row, hits)?, r.eval(row, hits)?);
+                let pass = match (&a, &b) {
+                    (Value::Num(x), Value::Num(y)) => op.test(x.partial_cmp(y)?),
+                    (Value::Str(_), Value::Str(_)) => a.same(&b) == (*op == CmpOp::Eq),
+                    _ => return None,
+                };
+                if pass {
+                    hits.push(Hit { condition: self.to_string(), left: a, right: b });
+                }
+                Some(Value::Bool(pass))
+            }
+            Expr::In(l, r, negated) => {
+                let (a, b) = (l.eval(row, hits)?, r.eval(row, hits)?);
+                let Value::List(items) = &b else { return None };
+                let pass = items.iter().any(|item| a.same(item)) != *negated;
+                if pass {
+                    hits.push(Hit { condition: self.to_string(), left: a, right: b });
+                }
+                Some(Value::Bool(pass))
+            }
+            Expr::And(l, r) => {
+                let mark = hits.len();
+                let a = l.eval(row, hits);
+                let b = if a == Some(Value::Bool(false)) { a.clone() } else { r.eval(row, hits) };
+                let out = match (a, b) {
+                    (Some(Value::Bool(true)), Some(Value::Bool(true))) => Some(Value::Bool(true)),
+                    (Some(Value::Bool(false)), _) | (_, Some(Value::Bool(false))) => Some(Value::Bool(false)),
+                    _ => None,
+                };
+                if out != Some(Value::Bool(true)) {
+                    hits.truncate(mark);
+                }
+                out
+            }
+            Expr::Or(l, r) => {
+                let mark = hits.len();
+                let a = l.eval(row, hits);
+                if a == Some(Value::Bool(true)) {
+                    return a;
+                }
+                hits.truncate(mark);
+                let b = r.eval(row, hits);
+                match (a, b) {
+                    (_, Some(Value::Bool(true))) => Some(Value::Bool(true)),
+                    (Some(Value::Bool(false)), Some(Value::Bool(false))) => {
+                        hits.truncate(mark);
+                        Some(Value::Bool(false))
+                    }
+                    _ => {
+                        hits.truncate(mark);
+                        None
+                    }
+                }
+            }
+            Expr::Not(e) => {
+                let mark = hits.len();
+                let v = e.eval(row, hits);
+                hits.truncate(mark);
+                match v? {
+                    Value::Bool(b) => Some(Value::Bool(!b)),
+                    _ => None,
+                }
+            }
+        }
+    }
+
+    fn fmt_child(&self, f: &mut fmt::Formatter<'_>, min: u8) -> fmt::Result {
+        if self.precedence() < min { write!(f, "({self})") } else { write!(f, "{self}") }
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let p = self.precedence();
+        match self {
+            Expr::Num(v) => write!(f, "{v}"),
+            Expr::Str(s) => write!(f, "{s:?}"),
+            Expr::List(items) => {
+                f.write_str("[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{item}")?;
+                }
+                f.write_str("]")
+            }
+            Expr::Field(name) => f.write_str(name),
+            Expr::Window(window, name, n) => write!(f, "{}({name}, {n})", window.name()),
+            Expr::Abs(e) => write!(f, "abs({e})"),
+            Expr::Neg(e) => {
+                f.write_str("-")?;
+                e.fmt_child(f, p)
+            }
+            Expr::Not(e) => {
+                f.write_str("not ")?;
+                e.fmt_child(f, p)
+            }
+            Expr::Arith(op, l, r) => {
+                l.fmt_child(f, p)?;
+                write!(f, " {op} ")?;
+                r.fmt_child(f, p + 1)
+            }
+            Expr::Cmp(op, l, r) => {
+                l.fmt_child(f, p + 1)?;
+                write!(f, " {} ", op.as_str())?;
+                r.fmt_child(f, p + 1)
+            }
+            Expr::In(l, r, negated) => {
+                l.fmt_child(f, p + 1)?;
+                f.write_str(if *negated { " not in " } else { " in " })?;
+                r.fmt_child(f, p + 1)
+            }
+            Expr::And(l, r) | Expr::Or(l, r) => {
+                l.fmt_child(f, p)?;
+                f.write_str(if matches!(self, Expr::And(..)) { " and " } else { " or " })?;
+                r.fmt_child(f, p + 1)
+            }
+        }
+    }
+}
+
+// ── Fields ──
+
+/// `ma{p}` → `(p, false)`, `ma{p}_score` → `(p, true)`.
+fn ma_field(name: &str) -> Option<(usize, bool)> {
+    let rest = name.strip_prefix("ma")?;
+    let (digits, score) = match rest.strip_suffix("_score") {
+        Some(d) => (d, true),
+        None => (rest, false),
+    };
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    digits.parse().ok().map(|p| (p, score))
+}
+
+fn check_field(name: &str) -> Result<(), String> {
+    if NUMERIC_FIELDS.contains(&name) || TEXT_FIELDS.contains(&name) || Indicator::for_field(name).is_some() {
+        return Ok(());
+    }
+    match ma_field(name) {
+        Some((p, _)) if (1..=MAX_MA_PERIOD).contains(&p) => Ok(()),
+        Some(_) => Err(format!("MA period in '{name}' must be between 1 and {MAX_MA_PERIOD}")),
+        None => Err(format!(
+            "Unknown field '{name}'. Use OHLCV columns, symbol, sector, source, ma<N>, ma<N>_score or an indicator field (e.g. rsi14)"
+        )),
+    }
+}
+
+fn field_value(bar: &OhlcvJoined, name: &str) -> Option<f64> {
+    match name {
+        "open" => Some(bar.open),
+        "high" => Some(bar.high),
+        "low" => Some(bar.low),
+        "close" => Some(bar.close),
+        "volume" => Some(bar.volume as f64),
+        "close_changed" => bar.close_changed,
+        "volume_changed" => bar.volume_changed,
+        "total_money_changed" => bar.total_money_changed,
+        _ => match ma_field(name) {
+            Some((period, score)) => {
+                let (ma, ma_score) = bar.ma(period);
+                if score { ma_score } else { ma }
+            }
+            None => bar.indicators.get(name).copied(),
+        },
+    }
+}
+
+// ── Tokenizer ──
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Num(f64),
+    Str(String),
+    Ident(String),
+    Op(&'static str),
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    Comma,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Num(v) => write!(f, "'{v}'"),
+            Token::Str(s) => write!(f, "{s:?}"),
+            Token::Ident(s) => write!(f, "'{s}'"),
+            Token::Op(op) => write!(f, "'{op}'"),
+            Token::LParen => f.write_str("'('"),
+            Token::RParen => f.write_str("')'"),
+            Token::LBracket => f.write_str("'['"),
+            Token::RBracket => f.write_str("']'"),
+            Token::Comma => f.write_str("','"),
+        }
+    }
+}
+
+fn tokenize(src: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = src.chars().collect();
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        i += 1;
+        let tok = match c {
+            c if c.is_whitespace() => continue,
+            '(' => Token::LParen,
+            ')' => Token::RParen,
+            '[' => Token::LBracket,
+            ']' => Token::RBracket,
+            ',' => Token::Comma,
+            '+' => Token::Op("+"),
+            '-' => Token::Op("-"),
+            '*' => Token::Op("*"),
+            '/' => Token::Op("/"),
+            '<' | '>' | '=' | '!' => {
+                let eq = chars.get(i) == Some(&'=');
+                if eq {
+                    i += 1;
+                }
+                Token::Op(match (c, eq) {
+                    ('<', false) => "<",
+                    ('<', true) => "<=",
+                    ('>', false) => ">",
+                    ('>', true) => ">=",
+                    ('=', _) => "==",
+                    ('!', true) => "!=",
+                    _ => return Err(format!("Unexpected '!' at position {start}, use 'not' or '!='")),
+                })
+            }
+            '"' | '\'' => {
+                let end = chars[i..].iter().position(|&ch| ch == c).ok_or("Unterminated string")?;
+                let s: String = chars[i..i + end].iter().collect();
+                i += end + 1;
+                Token::Str(s)
+            }
+            c if c.is_ascii_digit() || c == '.' => {
+                while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                    i += 1;
+                }
+                let s: String = chars[start..i].iter().collect();
+                Token::Num(s.parse().map_err(|_| format!("Invalid number '{s}'"))?)
+            }
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                Token::Ident(chars[start..i].iter().collect::<String>().to_ascii_lowercase())
+            }
+            _ => return Err(format!("Unexpected character '{c}' at position {start}")),
+        };
+        out.push(tok);
+    }
+    Ok(out)
+}
+
+// ── Parser ──
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Result<Token, String> {
+        let tok = self.tokens.get(self.pos).cloned().ok_or("Unexpected end of expression")?;
+        self.pos += 1;
+        Ok(tok)
+    }
+
+    fn is_keyword(&self, offset: usize, kw: &str) -> bool {
+        matches!(self.tokens.get(self.pos + offset), Some(Token::Ident(s)) if s == kw)
+    }
+
+    fn eat_keyword(&mut self, kw: &str) -> bool {
+        let found = self.is_keyword(0, kw);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn eat_op(&mut self, ops: &[&'static str]) -> Option<&'static str> {
+        match self.peek() {
+            Some(Token::Op(op)) if ops.contains(op) => {
+                let op = *op;
+                self.pos += 1;
+                Some(op)
+            }
+            _ => None,
+        }
+    }
+
+    fn expect(&mut self, want: Token) -> Result<(), String> {
+        match self.next()? {
+            tok if tok == want => Ok(()),
+            tok => Err(format!("Expected {want}, found {tok}")),
+        }
+    }
+
+    /// Guard against deeply nested input before recursing.
+    fn nested<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T, String>) -> Result<T, String> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(format!("Expression nested deeper than {MAX_DEPTH} levels"));
+        }
+        let out = f(self);
+        self.depth -= 1;
+        out
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.and()?;
+        while self.eat_keyword("or") {
+            lhs = Expr::Or(Box::new(lhs), Box::new(self.and()?));
+        }
+        Ok(lhs)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.not()?;
+        while self.eat_keyword("and") {
+            lhs = Expr::And(Box::new(lhs), Box::new(self.not()?));
+        }
+        Ok(lhs)
+    }
+
+    fn not(&mut self) -> Result<Expr, String> {
+        if self.eat_keyword("not") {
+            return self.nested(|p| Ok(Expr::Not(Box::new(p.not()?))));
+        }
+        self.cmp()
+    }
+
+    fn cmp(&mut self) -> Result<Expr, String> {
+        let lhs = self.sum()?;
+        if let Some(op) = self.eat_op(&["<", "<=", ">", ">=", "==", "!="]) {
+            let op = CmpOp::from_op(op).expect("comparison operator");
+            return Ok(Expr::Cmp(op, Box::new(lhs), Box::new(self.sum()?)));
+        }
+        let negated = self.is_keyword(0, "not") && self.is_keyword(1, "in");
+        if negated {
+            self.pos += 2;
+        } else if !self.eat_keyword("in") {
+            return Ok(lhs);
+        }
+        Ok(Expr::In(Box::new(lhs), Box::new(self.sum()?), negated))
+    }
+
+    fn sum(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.product()?;
+        while let Some(op) = self.eat_op(&["+", "-"]) {
+            lhs = Expr::Arith(op.chars().next().unwrap_or('+'), Box::new(lhs), Box::new(self.product()?));
+        }
+        Ok(lhs)
+    }
+
+    fn product(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.unary()?;
+        while let Some(op) = self.eat_op(&["*", "/"]) {
+            lhs = Expr::Arith(op.chars().next().unwrap_or('*'), Box::new(lhs), Box::new(self.unary()?));
+        }
+        Ok(lhs)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.eat_op(&["-"]).is_some() {
+            return self.nested(|p| Ok(Expr::Neg(Box::new(p.unary()?))));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        match self.next()? {
+            Token::Num(v) => Ok(Expr::Num(v)),
+            Token::Str(s) => Ok(Expr::Str(s)),
+            Token::LParen => self.nested(|p| {
+                let e = p.or()?;
+                p.expect(Token::RParen)?;
+                Ok(e)
+            }),
+            Token::LBracket => self.nested(|p| {
+                let mut items = Vec::new();
+                if p.peek() == Some(&Token::RBracket) {
+                    p.pos += 1;
+                    return Ok(Expr::List(items));
+                }
+                loop {
+                    items.push(p.sum()?);
+                    match p.next()? {
+                        Token::Comma => continue,
+                        Token::RBracket => return Ok(Expr::List(items)),
+                        tok => return Err(format!("Expected ',' or ']', found {tok}")),
+                    }
+                }
+            }),
+            Token::Ident(name) if self.peek() == Some(&Token::LParen) => {
+                self.pos += 1;
+                self.call(&name)
+            }
+            Token::Ident(name) if matches!(name.as_str(), "and" | "or" | "not" | "in") => {
+                Err(format!("Unexpected keyword '{name}'"))
+            }
+            Token::Ident(name) => {
+                check_field(&name)?;
+                Ok(Expr::Field(name))
+            }
+            tok => Err(format!("Unexpected {tok}")),
+        }
+    }
+
+    fn call(&mut self, name: &str) -> Result<Expr, String> {
+        let window = match name {
+            "abs" => {
+                return self.nested(|p| {
+                    let e = p.or()?;
+                    p.expect(Token::RParen)?;
+                    Ok(Expr::Abs(Box::new(e)))
+                });
+            }
+            "avg" => Window::Avg,
+            "sum" => Window::Sum,
+            "min" => Window::Min,
+            "max" => Window::Max,
+            "prev" => Window::Prev,
+            _ => return Err(format!("Unknown function '{name}'. Supported: avg, sum, min, max, prev, abs")),
+        };
+        let field = match self.next()? {
+            Token::Ident(field) if !TEXT_FIELDS.contains(&field.as_str()) => {
+                check_field(&field)?;
+                field
+            }
+            tok => return Err(format!("{name}() expects a numeric field, found {tok}")),
+        };
+        self.expect(Token::Comma)?;
+        let n = match self.next()? {
+            Token::Num(v) if v.fract() == 0.0 && (1.0..=MAX_WINDOW as f64).contains(&v) => v as usize,
+            tok => return Err(format!("{name}() expects a bar count between 1 and {MAX_WINDOW}, found {tok}")),
+        };
+        self.expect(Token::RParen)?;
+        Ok(Expr::Window(window, field, n))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ohlcv::MaColumns;
+    use chrono::{Duration, TimeZone, Utc};
+
+    /// `n` daily bars, newest first; volume 1000 except the newest bar (5000).
+    fn bars(n: usize) -> Vec<OhlcvJoined> {
+        let t0 = Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap();
+        (0..n)
+            .map(|i| {
+                let close = 100.0 - i as f64;
+                let mut bar = OhlcvJoined {
+                    ticker: "VCB".to_string(),
+                    time: t0 - Duration::days(i as i64),
+                    open: close,
+                    high: close + 1.0,
+                    low: close - 1.0,
+                    close,
+                    volume: if i == 0 { 5000 } else { 1000 },
+                    ma10: None,
+                    ma20: None,
+                    ma50: None,
+                    ma100: None,
+                    ma200: None,
+                    ma10_score: None,
+                    ma20_score: None,
+                    ma50_score: None,
+                    ma100_score: None,
+                    ma200_score: None,
+                    close_changed: Some(1.0),
+                    volume_changed: None,
+                    total_money_changed: None,
+                    indicators: Default::default(),
+                };
+                bar.set_ma(20, close - 3.0);
+                bar.set_ma(50, close - 5.0);
+                bar
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_and_display() {
+        let e = Expression::parse_filter(
+            "close > ma50 AND ma20_score > 2 and volume > 2 * avg(volume, 20) and sector in ['NGAN_HANG']",
+        )
+        .unwrap();
+        assert_eq!(
+            e.to_string(),
+            r#"close > ma50 and ma20_score > 2 and volume > 2 * avg(volume, 20) and sector in ["NGAN_HANG"]"#
+        );
+        let e = Expression::parse_filter("(close + 1) * 2 > 3 or not (rsi14 < 30 and close > 1)").unwrap();
+        assert_eq!(e.to_string(), "(close + 1) * 2 > 3 or not (rsi14 < 30 and close > 1)");
+        assert_eq!(Expression::parse_value("-prev(close, 1) + abs(close_changed)").unwrap().to_string(), "-prev(close, 1) + abs(close_changed)");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for bad in [
+            "",
+            "close >",
+            "foo > 1",
+            "close > \"x\"",
+            "sector > 1",
+            "close + 1",
+            "avg(volume, 0) > 1",
+            "avg(sector, 5) > 1",
+            "median(close, 5) > 1",
+            "sector in \"NGAN_HANG\"",
+            "close > 1 )",
+            "symbol == 'VCB",
+            "ma0 > 1",
+        ] {
+            assert!(Expression::parse_filter(bad).is_err(), "{bad}");
+        }
+        assert!(Expression::parse_value("close > 1").is_err());
+        let deep = format!("{}close > 1{}", "(".repeat(40), ")".repeat(40));
+        assert!(Expression::parse_filter(&deep).is_err());
+    }
+
+    #[test]
+    fn test_requirements() {
+        let mut req = Requirements::default();
+        req.add(&Expression::parse_filter("ma13_score > 0 and bb20_upper > close and prev(close, 5) < close").unwrap());
+        req.add(&Expression::parse_value("avg(volume, 20) + rsi14").unwrap());
+        assert_eq!(req.ma_periods.into_iter().collect::<Vec<_>>(), vec![13]);
+        assert_eq!(req.indicators, vec![Indicator::Bollinger(20), Indicator::Rsi(14)]);
+        assert_eq!(req.bars, 20);
+    }
+
+    #[test]
+    fn test_matches_reports_passing_conditions() {
+        let bars = bars(25);
+        let row = Row { bars: &bars, sector: Some("NGAN_HANG"), source: "vn" };
+
+        let e = Expression::parse_filter(
+            r#"close > ma50 and ma20_score > 2 and volume > 2 * avg(volume, 20) and sector in ["ngan_hang"]"#,
+        )
+        .unwrap();
+        let hits = e.matches(&row).unwrap();
+        assert_eq!(hits.len(), 4);
+        assert_eq!(hits[2].condition, "volume > 2 * avg(volume, 20)");
+        assert_eq!(hits[2].left, Value::Num(5000.0));
+        assert_eq!(hits[2].right, Value::Num(2.0 * 1200.0));
+
+        // Only the passing branch of an `or` is reported
+        let e = Expression::parse_filter("close < 1 or symbol == 'VCB'").unwrap();
+        let hits = e.matches(&row).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].condition, r#"symbol == "VCB""#);
+
+        // Missing values never match, not even negated
+        assert!(Expression::parse_filter("ma200 > 0").unwrap().matches(&row).is_none());
+        assert!(Expression::parse_filter("not ma200 > 0").unwrap().matches(&row).is_none());
+        assert!(Expression::parse_filter("avg(close, 30) > 0").unwrap().matches(&row).is_none());
+        let no_sector = Row { bars: &bars, sector: None, source: "vn" };
+        assert!(Expression::parse_filter("sector not in ['BDS']").unwrap().matches(&no_sector).is_none());
+
+        assert_eq!(Expression::parse_value("close - prev(close, 2)").unwrap().value(&row), Some(2.0));
+    }
+}
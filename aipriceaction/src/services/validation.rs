@@ -0,0 +1,406 @@
+//! Ingest validation between the providers and `queries::import`.
+//!
+//! Every batch saved through `vci_shared::enhance_and_save`, the SJC CSV
+//! import and live price, legacy CSV imports and checkpoint restores runs
+//! through a [`Validator`]: an ordered list of [`ValidationRule`]s. The first rule that
+//! rejects a bar sends it to `ohlcv_quarantine` instead of `ohlcv`. Per-rule
+//! counters since process start are reported by `/health`.
+
+use chrono::{DateTime, Datelike, Duration, Timelike, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+use std::collections::BTreeMap;
+use std::sync::{Mutex, OnceLock};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::constants::validation as cfg;
+use crate::models::calendar::Exchange;
+use crate::models::ohlcv::OhlcvRow;
+use crate::queries::quarantine::{self, QuarantinedRow};
+
+// ── Rules ──
+
+/// What a rule sees besides the bar itself.
+pub struct RuleContext<'a> {
+    pub source: &'a str,
+    pub interval: &'a str,
+    /// Accepted bars before this one (stored history, then earlier bars of
+    /// the batch), oldest first.
+    pub history: &'a [OhlcvRow],
+    pub now: DateTime<Utc>,
+}
+
+pub trait ValidationRule: Send + Sync {
+    /// Stable name used in `ohlcv_quarantine.rule`, counters and
+    /// `VALIDATION_DISABLED_RULES`.
+    fn name(&self) -> &'static str;
+
+    /// `Some(reason)` when the bar must not be stored.
+    fn check(&self, row: &OhlcvRow, ctx: &RuleContext) -> Option<String>;
+}
+
+/// Prices finite and positive; high/low bracket open and close.
+pub struct OhlcConsistency;
+
+impl ValidationRule for OhlcConsistency {
+    fn name(&self) -> &'static str {
+        "ohlc"
+    }
+
+    fn check(&self, row: &OhlcvRow, _ctx: &RuleContext) -> Option<String> {
+        let prices = [row.open, row.high, row.low, row.close];
+        if prices.iter().any(|p| !p.is_finite() || *p <= 0.0) {
+            return Some(format!("non-positive price o={} h={} l={} c={}", row.open, row.high, row.low, row.close));
+        }
+        if row.high < row.low {
+            return Some(format!("high {} < low {}", row.high, row.low));
+        }
+        if row.high < row.open.max(row.close) || row.low > row.open.min(row.close) {
+            return Some(format!(
+                "open/close outside high/low o={} h={} l={} c={}",
+                row.open, row.high, row.low, row.close
+            ));
+        }
+        None
+    }
+}
+
+/// No negative volume; no zero-volume intraday bars from sources that only
+/// emit bars when trades happen (`ZERO_VOLUME_SOURCES`).
+pub struct VolumeSanity;
+
+impl ValidationRule for VolumeSanity {
+    fn name(&self) -> &'static str {
+        "volume"
+    }
+
+    fn check(&self, row: &OhlcvRow, ctx: &RuleContext) -> Option<String> {
+        if row.volume < 0 {
+            return Some(format!("negative volume {}", row.volume));
+        }
+        if row.volume == 0 && ctx.interval != "1D" && cfg::ZERO_VOLUME_SOURCES.contains(&ctx.source) {
+            return Some("zero volume in a trading-session bar".to_string());
+        }
+        None
+    }
+}
+
+/// Bad ticks: the bar's extreme is more than `SPIKE_ZSCORE` ATRs and
+/// `SPIKE_MIN_RATIO`× away from the previous close. Skipped until
+/// `ATR_PERIOD + 1` bars of history exist.
+pub struct SpikeFilter;
+
+impl ValidationRule for SpikeFilter {
+    fn name(&self) -> &'static str {
+        "spike"
+    }
+
+    fn check(&self, row: &OhlcvRow, ctx: &RuleContext) -> Option<String> {
+        let prev_close = ctx.history.last()?.close;
+        let atr = average_true_range(ctx.history, cfg::ATR_PERIOD)?;
+        if prev_close <= 0.0 || row.low <= 0.0 {
+            return None;
+        }
+
+        let up = row.high - prev_close;
+        let down = prev_close - row.low;
+        let ratio = (row.high / prev_close).max(prev_close / row.low);
+        let z = if atr > 0.0 { up.max(down) / atr } else { f64::INFINITY };
+
+        (z >= cfg::SPIKE_ZSCORE && ratio >= cfg::SPIKE_MIN_RATIO).then(|| {
+            format!("{ratio:.2}x move from previous close {prev_close} ({z:.1} ATR)")
+        })
+    }
+}
+
+/// Bar time not in the future, and for VN intraday bars inside a session
+/// slot on a weekday (a bar at the closing minute is allowed for the index
+/// close prints). Holidays are deliberately not checked, so a stale holiday
+/// table can never drop real bars.
+pub struct TimestampAlignment;
+
+impl ValidationRule for TimestampAlignment {
+    fn name(&self) -> &'static str {
+        "timestamp"
+    }
+
+    fn check(&self, row: &OhlcvRow, ctx: &RuleContext) -> Option<String> {
+        let bar_minutes: u32 = match ctx.interval {
+            "1m" => 1,
+            "1h" => 60,
+            _ => 24 * 60,
+        };
+        if row.time > ctx.now + Duration::minutes(bar_minutes as i64) {
+            return Some(format!("bar time {} is in the future", row.time.to_rfc3339()));
+        }
+        if bar_minutes >= 24 * 60 || ctx.source != "vn" {
+            return None;
+        }
+
+        let exchange = Exchange::Hose;
+        let local = row.time.naive_utc() + exchange.utc_offset(row.time.date_naive());
+        let start = local.hour() * 60 + local.minute();
+        let in_slot = local.weekday().num_days_from_monday() < 5
+            && exchange
+                .sessions()
+                .iter()
+                .any(|&(open, close)| start <= close && start + bar_minutes > open);
+        (!in_slot).then(|| format!("bar at {} local is outside the trading sessions", local.format("%a %H:%M")))
+    }
+}
+
+/// Mean true range over the last `period` bars of `history` (needs one more
+/// bar for the first previous close).
+fn average_true_range(history: &[OhlcvRow], period: usize) -> Option<f64> {
+    if period == 0 || history.len() < period + 1 {
+        return None;
+    }
+    let window = &history[history.len() - period - 1..];
+    let sum: f64 = window
+        .windows(2)
+        .map(|w| {
+            let (prev, bar) = (&w[0], &w[1]);
+            (bar.high - bar.low)
+                .max((bar.high - prev.close).abs())
+                .max((bar.low - prev.close).abs())
+        })
+        .sum();
+    Some(sum / period as f64)
+}
+
+// ── Validator ──
+
+pub struct Validator {
+    rules: Vec<Box<dyn ValidationRule>>,
+}
+
+impl Validator {
+    pub fn new(rules: Vec<Box<dyn ValidationRule>>) -> Self {
+        Self { rules }
+    }
+
+    /// Built-in rules minus any named in `VALIDATION_DISABLED_RULES`.
+    pub fn from_env() -> Self {
+        let disabled = cfg::disabled_rules();
+        let rules: Vec<Box<dyn ValidationRule>> = vec![
+            Box::new(OhlcConsistency),
+            Box::new(VolumeSanity),
+            Box::new(TimestampAlignment),
+            Box::new(SpikeFilter),
+        ];
+        Self::new(rules.into_iter().filter(|r| !disabled.iter().any(|d| d == r.name())).collect())
+    }
+
+    /// Split ascending `rows` into accepted and quarantined bars. `history`
+    /// is the stored series just before the batch, oldest first.
+    pub fn validate(
+        &self,
+        source: &str,
+        interval: &str,
+        mut history: Vec<OhlcvRow>,
+        rows: Vec<OhlcvRow>,
+        now: DateTime<Utc>,
+    ) -> (Vec<OhlcvRow>, Vec<QuarantinedRow>) {
+        let mut accepted = Vec::with_capacity(rows.len());
+        let mut quarantined = Vec::new();
+
+        for row in rows {
+            let ctx = RuleContext { source, interval, history: &history, now };
+            let failed = self
+                .rules
+                .iter()
+                .find_map(|rule| rule.check(&row, &ctx).map(|reason| (rule.name(), reason)));
+            match failed {
+                Some((rule, reason)) => quarantined.push(QuarantinedRow { row, rule, reason }),
+                None => {
+                    history.push(row.clone());
+                    accepted.push(row);
+                }
+            }
+        }
+        (accepted, quarantined)
+    }
+}
+
+/// Validator of the pipeline, built from the environment on first use.
+fn validator() -> &'static Validator {
+    static VALIDATOR: OnceLock<Validator> = OnceLock::new();
+    VALIDATOR.get_or_init(Validator::from_env)
+}
+
+// ── Counters ──
+
+static ROWS_CHECKED: AtomicU64 = AtomicU64::new(0);
+static ROWS_QUARANTINED: Mutex<BTreeMap<&'static str, u64>> = Mutex::new(BTreeMap::new());
+
+/// Counters since process start, reported by `/health`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ValidationStats {
+    pub rows_checked: u64,
+    pub rows_quarantined: u64,
+    pub by_rule: BTreeMap<&'static str, u64>,
+}
+
+pub fn stats() -> ValidationStats {
+    let by_rule = ROWS_QUARANTINED.lock().map(|m| m.clone()).unwrap_or_default();
+    ValidationStats {
+        rows_checked: ROWS_CHECKED.load(Ordering::Relaxed),
+        rows_quarantined: by_rule.values().sum(),
+        by_rule,
+    }
+}
+
+fn record(checked: usize, quarantined: &[QuarantinedRow]) {
+    ROWS_CHECKED.fetch_add(checked as u64, Ordering::Relaxed);
+    if quarantined.is_empty() {
+        return;
+    }
+    if let Ok(mut counts) = ROWS_QUARANTINED.lock() {
+        for q in quarantined {
+            *counts.entry(q.rule).or_default() += 1;
+        }
+    }
+}
+
+// ── Pipeline entry point ──
+
+/// Validate one ticker's ascending, deduplicated batch, quarantine the
+/// rejects and return the bars that may be upserted.
+pub async fn validate_batch(
+    pool: &PgPool,
+    source: &str,
+    ticker: &str,
+    interval: &str,
+    rows: Vec<OhlcvRow>,
+) -> Vec<OhlcvRow> {
+    let Some(first) = rows.first() else {
+        return rows;
+    };
+    let ticker_id = first.ticker_id;
+
+    let history = match quarantine::recent_bars_before(pool, ticker_id, interval, first.time, cfg::HISTORY_BARS).await {
+        Ok(h) => h,
+        Err(e) => {
+            // Without history only the spike rule is skipped.
+            tracing::warn!(ticker, interval, "validation: failed to load history: {e}");
+            Vec::new()
+        }
+    };
+
+    let checked = rows.len();
+    let (accepted, quarantined) = validator().validate(source, interval, history, rows, Utc::now());
+    record(checked, &quarantined);
+
+    if !quarantined.is_empty() {
+        for q in &quarantined {
+            tracing::warn!(
+                ticker, interval, rule = q.rule, time = %q.row.time.to_rfc3339(),
+                "validation: quarantined bar: {}", q.reason
+            );
+        }
+        if let Err(e) = quarantine::save_quarantined(pool, source, &quarantined).await {
+            tracing::error!(ticker, interval, "validation: failed to save quarantined bars: {e}");
+        }
+    }
+    accepted
+}
+
+/// [`validate_batch`] for one ticker's rows of any intervals and order (bulk
+/// imports). Rows sharing a time keep the last. Returns the accepted bars,
+/// ascending per interval.
+pub async fn validate_rows(pool: &PgPool, source: &str, ticker: &str, rows: Vec<OhlcvRow>) -> Vec<OhlcvRow> {
+    let mut by_interval: BTreeMap<String, BTreeMap<DateTime<Utc>, OhlcvRow>> = BTreeMap::new();
+    for row in rows {
+        by_interval.entry(row.interval.clone()).or_default().insert(row.time, row);
+    }
+    let mut accepted = Vec::new();
+    for (interval, batch) in by_interval {
+        accepted.extend(validate_batch(pool, source, ticker, &interval, batch.into_values().collect()).await);
+    }
+    accepted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn bar(hour: u32, o: f64, h: f64, l: f64, c: f64, volume: i64) -> OhlcvRow {
+        OhlcvRow {
+            ticker_id: 1,
+            interval: "1h".into(),
+            // Friday 2026-10-16, UTC hours 2..=7 cover the VN sessions.
+            time: Utc.with_ymd_and_hms(2026, 10, 16, hour, 0, 0).unwrap(),
+            open: o,
+            high: h,
+            low: l,
+            close: c,
+            volume,
+        }
+    }
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 10, 16, 9, 0, 0).unwrap()
+    }
+
+    fn history(n: usize) -> Vec<OhlcvRow> {
+        (0..n).map(|_| bar(2, 100.0, 101.0, 99.0, 100.0, 1000)).collect()
+    }
+
+    fn validator() -> Validator {
+        Validator::new(vec![
+            Box::new(OhlcConsistency),
+            Box::new(VolumeSanity),
+            Box::new(TimestampAlignment),
+            Box::new(SpikeFilter),
+        ])
+    }
+
+    fn rules_failed(row: OhlcvRow, source: &str, history: Vec<OhlcvRow>) -> Vec<&'static str> {
+        let (_, quarantined) = validator().validate(source, "1h", history, vec![row], now());
+        quarantined.iter().map(|q| q.rule).collect()
+    }
+
+    #[test]
+    fn test_ohlc_consistency() {
+        assert_eq!(rules_failed(bar(2, 100.0, 99.0, 101.0, 100.0, 10), "vn", vec![]), vec!["ohlc"]);
+        assert_eq!(rules_failed(bar(2, -1.0, 101.0, 99.0, 100.0, 10), "vn", vec![]), vec!["ohlc"]);
+        assert_eq!(rules_failed(bar(2, 102.0, 101.0, 99.0, 100.0, 10), "vn", vec![]), vec!["ohlc"]);
+        assert!(rules_failed(bar(2, 100.0, 101.0, 99.0, 100.0, 10), "vn", vec![]).is_empty());
+    }
+
+    #[test]
+    fn test_volume_sanity_only_zero_for_vn() {
+        assert_eq!(rules_failed(bar(2, 100.0, 101.0, 99.0, 100.0, 0), "vn", vec![]), vec!["volume"]);
+        assert!(rules_failed(bar(2, 100.0, 101.0, 99.0, 100.0, 0), "crypto", vec![]).is_empty());
+        assert_eq!(rules_failed(bar(2, 100.0, 101.0, 99.0, 100.0, -5), "crypto", vec![]), vec!["volume"]);
+    }
+
+    #[test]
+    fn test_timestamp_lunch_and_future() {
+        // 05:00 UTC = 12:00 ICT (lunch break)
+        assert_eq!(rules_failed(bar(5, 100.0, 101.0, 99.0, 100.0, 10), "vn", vec![]), vec!["timestamp"]);
+        assert!(rules_failed(bar(5, 100.0, 101.0, 99.0, 100.0, 10), "crypto", vec![]).is_empty());
+        let mut future = bar(2, 100.0, 101.0, 99.0, 100.0, 10);
+        future.time = now() + Duration::hours(3);
+        assert_eq!(rules_failed(future, "crypto", vec![]), vec!["timestamp"]);
+    }
+
+    #[test]
+    fn test_spike_needs_history_and_ratio() {
+        let tick = bar(3, 100.0, 1000.0, 99.0, 100.0, 10);
+        assert!(rules_failed(tick.clone(), "vn", history(5)).is_empty());
+        assert_eq!(rules_failed(tick, "vn", history(20)), vec!["spike"]);
+        // A 2:1 split is a big ATR move but below SPIKE_MIN_RATIO.
+        assert!(rules_failed(bar(3, 50.0, 51.0, 49.0, 50.0, 10), "vn", history(20)).is_empty());
+    }
+
+    #[test]
+    fn test_quarantined_bar_not_added_to_history() {
+        let rows = vec![bar(3, 100.0, 1000.0, 99.0, 100.0, 10), bar(4, 100.0, 101.0, 99.0, 100.0, 10)];
+        let (accepted, quarantined) = validator().validate("vn", "1h", history(20), rows, now());
+        assert_eq!(quarantined.len(), 1);
+        assert_eq!(accepted.len(), 1);
+        assert_eq!(accepted[0].time.hour(), 4);
+    }
+}
@@ -0,0 +1,327 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Instant;
+
+use fred::prelude::*;
+use sqlx::PgPool;
+use tokio::sync::{Notify, broadcast};
+use tokio::time::{Duration, sleep};
+
+use crate::constants::{alerts as cfg, redis_ts};
+use crate::queries::alerts::{self, AlertRule, DueDelivery};
+use crate::redis::RedisClient;
+use crate::server::ws::CandleKey;
+use crate::services::alerts::{PublicResolver, check_webhook_url};
+
+/// Alert evaluation and webhook delivery worker.
+///
+/// Listens on `OHLCV_UPDATES_CHANNEL` like the live hub. For each written ZSET
+/// that enabled rules watch, reads the tail, evaluates the rules and queues a
+/// delivery per (rule, bar). A second task POSTs queued deliveries to their
+/// webhooks, retrying failures with exponential backoff up to `MAX_ATTEMPTS`.
+pub async fn run(pool: PgPool, redis_client: Option<RedisClient>) {
+    let Some(client) = redis_client else {
+        tracing::warn!("Alert worker: Redis not configured, not started");
+        return;
+    };
+    let Some(subscriber) = crate::redis::subscribe(redis_ts::OHLCV_UPDATES_CHANNEL).await else {
+        tracing::warn!("Alert worker: failed to subscribe to {}", redis_ts::OHLCV_UPDATES_CHANNEL);
+        return;
+    };
+    let mut messages = subscriber.0.message_rx();
+
+    // No redirects and public-only DNS: a webhook must not reach internal services
+    let http = match reqwest::Client::builder()
+        .timeout(Duration::from_secs(cfg::WEBHOOK_TIMEOUT_SECS))
+        .redirect(reqwest::redirect::Policy::none())
+        .dns_resolver(Arc::new(PublicResolver))
+        .build()
+    {
+        Ok(c) => c,
+        Err(e) => {
+            tracing::error!("Alert worker: failed to build HTTP client: {e}");
+            return;
+        }
+    };
+
+    let wake = Arc::new(Notify::new());
+    {
+        let (pool, wake) = (pool.clone(), wake.clone());
+        tokio::spawn(async move { delivery_loop(pool, http, wake).await });
+    }
+
+    tracing::info!("Alert worker started");
+    let mut rules = RuleCache::default();
+    loop {
+        let message = match messages.recv().await {
+            Ok(m) => m,
+            Err(broadcast::error::RecvError::Lagged(n)) => {
+                tracing::warn!(skipped = n, "Alert worker lagged behind Redis pub/sub");
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        };
+        let Some(key) = message
+            .value
+            .as_string()
+            .and_then(|s| serde_json::from_str::<CandleKey>(&s).ok())
+        else {
+            continue;
+        };
+
+        rules.refresh(&pool).await;
+        let Some(watching) = rules.by_key.get(&key) else { continue };
+        if evaluate_key(&pool, &client, &key, watching).await > 0 {
+            wake.notify_one();
+        }
+    }
+    // Keep the connection handle alive for the whole loop
+    drop(subscriber);
+    tracing::warn!("Alert worker pub/sub stream closed");
+}
+
+// ---------------------------------------------------------------------------
+// Evaluation
+// ---------------------------------------------------------------------------
+
+/// Enabled rules grouped by the ZSET they watch, reloaded every `RULES_REFRESH_SECS`.
+#[derive(Default)]
+struct RuleCache {
+    by_key: HashMap<CandleKey, Vec<AlertRule>>,
+    loaded_at: Option<Instant>,
+}
+
+impl RuleCache {
+    async fn refresh(&mut self, pool: &PgPool) {
+        if self.loaded_at.is_some_and(|t| t.elapsed().as_secs() < cfg::RULES_REFRESH_SECS) {
+            return;
+        }
+        match alerts::enabled_rules(pool).await {
+            Ok(rules) => {
+                self.by_key.clear();
+                for rule in rules {
+                    let key = CandleKey {
+                        source: rule.source.clone(),
+                        ticker: rule.ticker.clone(),
+                        interval: rule.interval.clone(),
+                    };
+                    self.by_key.entry(key).or_default().push(rule);
+                }
+                tracing::debug!(keys = self.by_key.len(), "Alert rules reloaded");
+            }
+            Err(e) => tracing::warn!("Alert worker: failed to load rules: {e}"),
+        }
+        // On error keep the previous rules and retry after the same interval
+        self.loaded_at = Some(Instant::now());
+    }
+}
+
+/// Evaluate `rules` on the tail of `key`'s ZSET and queue new deliveries.
+/// Returns how many were queued.
+async fn evaluate_key(pool: &PgPool, client: &RedisClient, key: &CandleKey, rules: &[AlertRule]) -> usize {
+    let limit = rules.iter().map(|r| r.condition.bars_needed()).max().unwrap_or(2);
+    let mut tickers = vec![key.ticker.clone()];
+    for rule in rules {
+        if let Some(b) = rule.condition.benchmark()
+            && !tickers.iter().any(|t| t == b)
+        {
+            tickers.push(b.to_string());
+        }
+    }
+
+    let Some(mut read) = crate::server::redis_reader::batch_read_ohlcv_from_redis(
+        &Some(client.clone()),
+        &key.source,
+        &tickers,
+        &key.interval,
+        limit,
+        "alerts",
+        None,
+    )
+    .await
+    else {
+        return 0;
+    };
+    let Some(rows) = read.remove(&key.ticker).map(|r| r.rows) else {
+        return 0;
+    };
+
+    let mut queued = 0;
+    for rule in rules {
+        let benchmark = rule
+            .condition
+            .benchmark()
+            .and_then(|b| read.get(b))
+            .map(|r| r.rows.as_slice())
+            .unwrap_or_default();
+        let Some(trigger) = rule.condition.evaluate(&key.ticker, &rows, benchmark) else {
+            continue;
+        };
+        let payload = serde_json::json!({
+            "rule_id": rule.id,
+            "source": rule.source,
+            "ticker": rule.ticker,
+            "interval": rule.interval,
+            "condition": rule.condition,
+            "time": trigger.time.to_rfc3339(),
+            "close": trigger.close,
+            "message": trigger.message,
+            "values": trigger.values,
+        });
+        match alerts::insert_delivery(pool, rule.id, trigger.time, &payload).await {
+            Ok(Some(_)) => {
+                tracing::info!(rule_id = rule.id, ticker = %rule.ticker, "Alert fired: {}", trigger.message);
+                queued += 1;
+            }
+            Ok(None) => {} // already fired on this bar
+            Err(e) => tracing::warn!(rule_id = rule.id, "Alert worker: failed to queue delivery: {e}"),
+        }
+    }
+    queued
+}
+
+// ---------------------------------------------------------------------------
+// Delivery
+// ---------------------------------------------------------------------------
+
+/// Send due deliveries whenever new ones are queued, and every
+/// `RETRY_POLL_SECS` for scheduled retries.
+async fn delivery_loop(pool: PgPool, http: reqwest::Client, wake: Arc<Notify>) {
+    loop {
+        let claimed = match alerts::claim_due(&pool, cfg::DELIVERY_BATCH, cfg::DELIVERY_LEASE_SECS).await {
+            Ok(batch) => batch,
+            Err(e) => {
+                tracing::warn!("Alert worker: failed to claim deliveries: {e}");
+                Vec::new()
+            }
+        };
+        let full = claimed.len() as i64 == cfg::DELIVERY_BATCH;
+        for delivery in &claimed {
+            send_and_record(&pool, &http, delivery).await;
+        }
+        if full {
+            continue;
+        }
+        tokio::select! {
+            _ = wake.notified() => {}
+            _ = sleep(Duration::from_secs(cfg::RETRY_POLL_SECS)) => {}
+        }
+    }
+}
+
+async fn send_and_record(pool: &PgPool, http: &reqwest::Client, delivery: &DueDelivery) {
+    let attempt = delivery.attempts + 1;
+    // Re-checked per delivery: the target may have been re-pointed since the rule was saved
+    if let Err(error) = check_webhook_url(&delivery.webhook_url).await {
+        tracing::warn!(delivery_id = delivery.id, rule_id = delivery.rule_id, "Webhook target refused: {error}");
+        if let Err(e) = alerts::record_failure(pool, delivery.id, None, &error, None).await {
+            tracing::warn!(delivery_id = delivery.id, "Alert worker: failed to record delivery: {e}");
+        }
+        return;
+    }
+    let result = match deliver(http, &delivery.webhook_url, delivery.id, attempt, &delivery.payload).await {
+        Ok(status) => alerts::mark_delivered(pool, delivery.id, status as i32).await,
+        Err((status, error)) => {
+            let retry_in = (attempt < cfg::MAX_ATTEMPTS).then(|| retry_delay_secs(attempt));
+            tracing::warn!(
+                delivery_id = delivery.id,
+                rule_id = delivery.rule_id,
+                attempt,
+                retry_in,
+                "Webhook delivery failed: {error}"
+            );
+            alerts::record_failure(pool, delivery.id, status.map(i32::from), &error, retry_in).await
+        }
+    };
+    if let Err(e) = result {
+        tracing::warn!(delivery_id = delivery.id, "Alert worker: failed to record delivery: {e}");
+    }
+}
+
+/// Delay before the retry that follows failed attempt number `attempt` (1-based).
+pub fn retry_delay_secs(attempt: i32) -> i64 {
+    let shift = (attempt - 1).clamp(0, 20) as u32;
+    (cfg::RETRY_BASE_SECS << shift).min(cfg::RETRY_MAX_SECS)
+}
+
+/// POST one notification. `X-Alert-Delivery-Id` is stable across retries so
+/// receivers can drop duplicates. Any 2xx is success; returns the HTTP status,
+/// or the status (if any) and an error message.
+pub async fn deliver(
+    http: &reqwest::Client,
+    url: &str,
+    delivery_id: i64,
+    attempt: i32,
+    payload: &serde_json::Value,
+) -> Result<u16, (Option<u16>, String)> {
+    let response = http
+        .post(url)
+        .header("X-Alert-Delivery-Id", delivery_id.to_string())
+        .header("X-Alert-Attempt", attempt.to_string())
+        .json(payload)
+        .send()
+        .await
+        .map_err(|e| (None, e.to_string()))?;
+    let status = response.status();
+    if status.is_success() {
+        Ok(status.as_u16())
+    } else {
+        Err((Some(status.as_u16()), format!("HTTP {status}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::{HeaderMap, StatusCode};
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_retry_delay_backs_off_exponentially() {
+        assert_eq!(retry_delay_secs(1), cfg::RETRY_BASE_SECS);
+        assert_eq!(retry_delay_secs(2), cfg::RETRY_BASE_SECS * 2);
+        assert_eq!(retry_delay_secs(3), cfg::RETRY_BASE_SECS * 4);
+        assert_eq!(retry_delay_secs(40), cfg::RETRY_MAX_SECS);
+    }
+
+    #[tokio::test]
+    async fn test_deliver_to_local_receiver() {
+        // Receiver fails the first request, then accepts
+        type Received = Arc<Mutex<Vec<(String, serde_json::Value)>>>;
+        let received: Received = Arc::default();
+        let app = axum::Router::new()
+            .route(
+                "/hook",
+                axum::routing::post(
+                    |axum::extract::State(received): axum::extract::State<Received>,
+                     headers: HeaderMap,
+                     axum::Json(body): axum::Json<serde_json::Value>| async move {
+                        let mut received = received.lock().unwrap();
+                        let id = headers["x-alert-delivery-id"].to_str().unwrap().to_string();
+                        received.push((id, body));
+                        if received.len() == 1 { StatusCode::SERVICE_UNAVAILABLE } else { StatusCode::OK }
+                    },
+                ),
+            )
+            .with_state(received.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let http = reqwest::Client::new();
+        let url = format!("http://{addr}/hook");
+        let payload = serde_json::json!({ "rule_id": 7, "ticker": "VCB", "message": "VCB close crossed above 100 (101)" });
+
+        assert_eq!(deliver(&http, &url, 42, 1, &payload).await, Err((Some(503), "HTTP 503 Service Unavailable".to_string())));
+        assert_eq!(deliver(&http, &url, 42, 2, &payload).await, Ok(200));
+        {
+            let received = received.lock().unwrap();
+            assert_eq!(received.len(), 2);
+            assert!(received.iter().all(|(id, body)| id == "42" && body == &payload));
+        }
+
+        // Unknown path on the receiver
+        let closed = deliver(&http, &format!("http://{addr}/missing"), 1, 1, &payload).await;
+        assert_eq!(closed.unwrap_err().0, Some(404));
+    }
+}
@@ -79,13 +79,10 @@ pub async fn run(pool: PgPool, redis_client: Option<crate::redis::RedisClient>)
             // new ticker is seeded with the old ticker's history. The source is
             // first refreshed (delta since its last row) so stale data doesn't
             // leak into the new ticker.
-            let copy_source = binance_shared::load_binance_tickers_with_meta()
-                .ok()
-                .and_then(|v| {
-                    v.into_iter()
-                        .find(|(s, _, _)| s == ticker)
-                        .and_then(|(_, _, cf)| cf)
-                });
+            let copy_source = binance_shared::crypto_tickers()
+                .into_iter()
+                .find(|t| t.symbol == *ticker)
+                .and_then(|t| t.copy_from);
 
             if let Some(src_ticker) = copy_source {
                 match ohlcv::get_ticker_id(&pool, "crypto", &src_ticker).await {
@@ -1,72 +1,61 @@
 use chrono::{DateTime, Utc};
-use serde::Deserialize;
 use sqlx::PgPool;
 
 use crate::queries::ohlcv;
+use crate::services::metadata;
 use crate::workers::vci_shared;
 
 // ---------------------------------------------------------------------------
-// binance_tickers.json schema
+// Crypto tickers from ticker_metadata
 // ---------------------------------------------------------------------------
 
-#[derive(Deserialize)]
-struct BinanceTickerEntry {
-    symbol: String,
-    #[serde(default)]
-    status: Option<String>,
-    #[serde(default)]
-    copy_from: Option<String>,
+/// One crypto row of `ticker_metadata`, as the Binance workers need it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CryptoTicker {
+    /// Paired symbol, e.g. "BTCUSDT"
+    pub symbol: String,
+    /// Past its `delisted_on`, or renamed/merged/delisted in `ticker_lifecycle`
+    pub delisted: bool,
+    /// Symbol the ticker had before a rename (its alias that is itself a
+    /// crypto ticker); its history seeds this one.
+    pub copy_from: Option<String>,
 }
 
-#[derive(Deserialize)]
-struct BinanceTickerFile {
-    data: Vec<BinanceTickerEntry>,
+/// Crypto tickers of the loaded catalog, ordered by symbol.
+pub fn crypto_tickers() -> Vec<CryptoTicker> {
+    let catalog = metadata::catalog();
+    let today = Utc::now().date_naive();
+    catalog
+        .source_rows("crypto")
+        .map(|m| CryptoTicker {
+            symbol: m.ticker.clone(),
+            delisted: m.delisted_on.is_some_and(|d| d <= today) || catalog.state("crypto", &m.ticker, today).is_terminal(),
+            copy_from: m.aliases.iter().find(|a| catalog.get("crypto", a).is_some()).cloned(),
+        })
+        .collect()
 }
 
-/// Load tickers from binance_tickers.json.
-///
-/// Returns paired symbols (e.g. "BTCUSDT", "ETHUSDT").
-pub fn load_binance_tickers() -> Result<Vec<String>, Box<dyn std::error::Error>> {
-    Ok(load_binance_tickers_with_meta()?.into_iter().map(|(s, _, _)| s).collect())
-}
-
-/// Load tickers with full metadata from binance_tickers.json.
-///
-/// Returns `(symbol, status, copy_from)` tuples. Used by the bootstrap worker
-/// to look up `copy_from` for newly-seeded tickers.
-pub fn load_binance_tickers_with_meta()
--> Result<Vec<(String, Option<String>, Option<String>)>, Box<dyn std::error::Error>> {
-    let content = std::fs::read_to_string("binance_tickers.json")?;
-    let file: BinanceTickerFile = serde_json::from_str(&content)?;
-    Ok(file.data.into_iter().map(|e| (e.symbol, e.status, e.copy_from)).collect())
-}
-
-/// Sync tickers from binance_tickers.json into the database.
+/// Sync the crypto tickers of `ticker_metadata` into the `tickers` table.
 ///
 /// Three sequential passes:
-///   1. Upsert active tickers (status is None or non-"delisted"); new rows get
-///      `status='full-download-requested'` so the bootstrap worker picks them up.
-///   2. Apply explicit status overrides (e.g. `"status": "delisted"`).
-///   3. Orphan-delist safety net: any DB crypto ticker not present in the JSON
+///   1. Upsert active tickers; new rows get `status='full-download-requested'`
+///      so the bootstrap worker picks them up.
+///   2. Mark delisted tickers `delisted`.
+///   3. Orphan-delist safety net: any DB crypto ticker without a metadata row
 ///      is marked `delisted`.
 ///
 /// Returns the number of active tickers processed (Pass 1).
 pub async fn sync_crypto_tickers(pool: &PgPool) -> usize {
     tracing::info!("sync_crypto_tickers: starting");
-    let entries = match load_binance_tickers_with_meta() {
-        Ok(e) => e,
-        Err(e) => {
-            tracing::warn!("failed to load binance_tickers.json: {e}");
-            return 0;
-        }
-    };
+    let entries = crypto_tickers();
+    if entries.is_empty() {
+        tracing::warn!("sync_crypto_tickers: no crypto tickers in ticker_metadata");
+        return 0;
+    }
 
-    // PASS 1: upsert active tickers (skip entries with explicit "delisted" status)
+    // PASS 1: upsert active tickers
     let mut processed = 0usize;
-    for (ticker, status, _copy_from) in &entries {
-        if status.as_deref() == Some("delisted") {
-            continue;
-        }
+    for CryptoTicker { symbol: ticker, .. } in entries.iter().filter(|t| !t.delisted) {
         if let Err(e) = ohlcv::upsert_ticker(pool, "crypto", ticker, None).await {
             tracing::warn!(ticker, "failed to upsert crypto ticker: {e}");
             continue;
@@ -90,31 +79,28 @@ pub async fn sync_crypto_tickers(pool: &PgPool) -> usize {
         processed += 1;
     }
 
-    // PASS 2: apply explicit status overrides (e.g. "delisted")
-    for (ticker, status, _copy_from) in &entries {
-        if let Some(st) = status {
-            match sqlx::query!(
-                "UPDATE tickers SET status = $1 WHERE source = 'crypto' AND ticker = $2",
-                st,
-                ticker
-            )
-            .execute(pool)
-            .await
-            {
-                Ok(rows) => {
-                    if rows.rows_affected() > 0 {
-                        tracing::info!(ticker, status = %st, "sync_crypto_tickers: applied explicit status");
-                    }
-                }
-                Err(e) => {
-                    tracing::warn!(ticker, "failed to apply status {st}: {e}");
+    // PASS 2: mark delisted tickers
+    for CryptoTicker { symbol: ticker, .. } in entries.iter().filter(|t| t.delisted) {
+        match sqlx::query(
+            "UPDATE tickers SET status = 'delisted' WHERE source = 'crypto' AND ticker = $1 AND status IS DISTINCT FROM 'delisted'",
+        )
+        .bind(ticker)
+        .execute(pool)
+        .await
+        {
+            Ok(rows) => {
+                if rows.rows_affected() > 0 {
+                    tracing::info!(ticker, "sync_crypto_tickers: marked delisted");
                 }
             }
+            Err(e) => {
+                tracing::warn!(ticker, "failed to mark delisted: {e}");
+            }
         }
     }
 
     // PASS 3: orphan-delist safety net
-    let all_syms: Vec<String> = entries.iter().map(|(s, _, _)| s.clone()).collect();
+    let all_syms: Vec<String> = entries.iter().map(|t| t.symbol.clone()).collect();
     let orphan_result = sqlx::query!(
         "UPDATE tickers SET status = 'delisted'
          WHERE source = 'crypto'
@@ -126,13 +112,13 @@ pub async fn sync_crypto_tickers(pool: &PgPool) -> usize {
     .await;
     if let Ok(rows) = orphan_result {
         if rows.rows_affected() > 0 {
-            tracing::warn!(orphans = rows.rows_affected(), "sync_crypto_tickers: delisted orphan tickers not in JSON");
+            tracing::warn!(orphans = rows.rows_affected(), "sync_crypto_tickers: delisted orphan tickers not in ticker_metadata");
         }
     }
 
-    let explicit_count = entries.iter().filter(|(_, s, _)| s.is_some()).count();
+    let delisted_count = entries.iter().filter(|t| t.delisted).count();
     tracing::info!(
-        "sync_crypto_tickers: done — {processed} active, {explicit_count} explicit-status, orphan-net applied"
+        "sync_crypto_tickers: done — {processed} active, {delisted_count} delisted, orphan-net applied"
     );
     processed
 }
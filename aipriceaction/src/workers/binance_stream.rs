@@ -0,0 +1,341 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use futures::{SinkExt, StreamExt};
+use serde::Deserialize;
+use sqlx::PgPool;
+use tokio::sync::mpsc;
+use tokio::time::{sleep, Duration, Instant};
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::constants::binance_worker as cfg;
+use crate::models::interval::Interval;
+use crate::models::lifecycle::LifecycleState;
+use crate::providers::binance::BinanceProvider;
+use crate::providers::market_data::{HistoryRequest, MarketDataProvider};
+use crate::providers::ohlcv::OhlcvData;
+use crate::queries::ohlcv;
+use crate::services::metadata;
+use crate::workers::{binance_shared, vci_shared};
+
+const SOURCE: &str = "crypto";
+const INTERVAL: &str = "1m";
+
+/// Binance minute bars over the combined kline stream instead of REST polling.
+///
+/// Each session loads the ready crypto tickers in `ticker_metadata`,
+/// opens one combined-stream connection per `STREAMS_PER_CONNECTION` symbols,
+/// and upserts closed and in-progress candles every `STREAM_FLUSH_SECS` through
+/// `enhance_and_save` (PostgreSQL + Redis ZSET). Right after connecting, a REST
+/// backfill fills whatever was missed while disconnected. Any dropped
+/// connection ends the session; the next one starts after a backoff.
+pub async fn run(pool: PgPool, redis_client: Option<crate::redis::RedisClient>) {
+    // rustls is built with both ring and aws-lc-rs (via other deps), so it
+    // cannot pick a provider on its own for the wss:// connector.
+    let _ = rustls::crypto::ring::default_provider().install_default();
+
+    let provider = match BinanceProvider::new(cfg::STREAM_BACKFILL_RPM) {
+        Ok(p) => std::sync::Arc::new(p),
+        Err(e) => {
+            tracing::error!("Binance stream: failed to create REST provider: {e}");
+            return;
+        }
+    };
+
+    let (tx, rx) = mpsc::channel::<Kline>(10_000);
+    {
+        let pool = pool.clone();
+        let redis_client = redis_client.clone();
+        tokio::spawn(async move { flush_loop(pool, redis_client, rx).await });
+    }
+
+    let base_url = cfg::stream_url();
+    let mut backoff = cfg::STREAM_RECONNECT_MIN_SECS;
+    tracing::info!(url = %base_url, "Binance minute stream worker started");
+
+    loop {
+        let tickers = match stream_tickers(&pool).await {
+            Ok(t) if !t.is_empty() => t,
+            Ok(_) => {
+                tracing::info!("Binance stream: no ready tickers yet, retrying in 60s");
+                sleep(Duration::from_secs(60)).await;
+                continue;
+            }
+            Err(e) => {
+                tracing::warn!("Binance stream: failed to load tickers: {e}");
+                sleep(Duration::from_secs(backoff)).await;
+                backoff = (backoff * 2).min(cfg::STREAM_RECONNECT_MAX_SECS);
+                continue;
+            }
+        };
+
+        let started = Instant::now();
+        let mut readers = tokio::task::JoinSet::new();
+        for chunk in tickers.chunks(cfg::STREAMS_PER_CONNECTION) {
+            let url = combined_stream_url(&base_url, chunk.iter().map(|(t, _)| t.as_str()));
+            let tx = tx.clone();
+            readers.spawn(async move { read_stream(&url, &tx).await });
+        }
+        tracing::info!(tickers = tickers.len(), connections = readers.len(), "Binance stream: session started");
+
+        // Streaming is live from here on; fetch what was missed before it.
+        let backfill = {
+            let pool = pool.clone();
+            let redis_client = redis_client.clone();
+            let provider = provider.clone();
+            let tickers = tickers.clone();
+            tokio::spawn(async move { backfill(&pool, &redis_client, &provider, &tickers).await })
+        };
+
+        let session_end = sleep(Duration::from_secs(cfg::STREAM_MAX_SESSION_SECS));
+        tokio::pin!(session_end);
+        tokio::select! {
+            Some(result) = readers.join_next() => match result {
+                Ok(Ok(())) => tracing::warn!("Binance stream: server closed a connection"),
+                Ok(Err(e)) => tracing::warn!("Binance stream: connection error: {e}"),
+                Err(e) => tracing::warn!("Binance stream: reader task failed: {e}"),
+            },
+            _ = &mut session_end => tracing::info!("Binance stream: session limit reached, reconnecting"),
+        }
+        readers.abort_all();
+        backfill.abort();
+
+        // A session that stayed up for a while resets the backoff.
+        if started.elapsed() > Duration::from_secs(cfg::STREAM_RECONNECT_MAX_SECS) {
+            backoff = cfg::STREAM_RECONNECT_MIN_SECS;
+        }
+        sleep(Duration::from_secs(backoff)).await;
+        backoff = (backoff * 2).min(cfg::STREAM_RECONNECT_MAX_SECS);
+    }
+}
+
+/// Ready crypto tickers that are in `ticker_metadata` (and not delisted)
+/// and currently active in `ticker_lifecycle`.
+async fn stream_tickers(pool: &PgPool) -> Result<Vec<(String, i32)>, String> {
+    let listed: std::collections::HashSet<String> = binance_shared::crypto_tickers()
+        .into_iter()
+        .filter(|t| !t.delisted)
+        .map(|t| t.symbol)
+        .collect();
+
+    let catalog = metadata::catalog();
+    let today = Utc::now().date_naive();
+    Ok(ohlcv::list_tickers(pool, SOURCE)
+        .await
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|t| t.status.as_deref() == Some("ready") && listed.contains(&t.ticker))
+        .filter(|t| catalog.state(SOURCE, &t.ticker, today) == LifecycleState::Active)
+        .map(|t| (t.ticker, t.id))
+        .collect())
+}
+
+/// Fetch bars from each ticker's last stored minute up to now over REST.
+async fn backfill(
+    pool: &PgPool,
+    redis_client: &Option<crate::redis::RedisClient>,
+    provider: &BinanceProvider,
+    tickers: &[(String, i32)],
+) {
+    let mut filled = 0usize;
+    for (ticker, ticker_id) in tickers {
+        let Some(start) = vci_shared::get_last_time(pool, *ticker_id, INTERVAL).await else {
+            continue;
+        };
+        let request = HistoryRequest::Range { start, end: Utc::now() };
+        match provider.history(ticker, Interval::Minute, request).await {
+            Ok(data) => {
+                if vci_shared::enhance_and_save(pool, *ticker_id, &data, INTERVAL, SOURCE, ticker, redis_client).await {
+                    filled += data.len();
+                }
+            }
+            Err(e) => tracing::warn!(ticker, "Binance stream: backfill fetch failed: {e}"),
+        }
+    }
+    tracing::info!(tickers = tickers.len(), bars = filled, "Binance stream: REST backfill complete");
+}
+
+// ---------------------------------------------------------------------------
+// Buffered writes
+// ---------------------------------------------------------------------------
+
+/// Keep only the latest update per (symbol, bar) and upsert every `STREAM_FLUSH_SECS`.
+async fn flush_loop(pool: PgPool, redis_client: Option<crate::redis::RedisClient>, mut rx: mpsc::Receiver<Kline>) {
+    let mut ids: HashMap<String, i32> = HashMap::new();
+    let mut pending: HashMap<(String, DateTime<Utc>), OhlcvData> = HashMap::new();
+    let mut tick = tokio::time::interval(Duration::from_secs(cfg::STREAM_FLUSH_SECS));
+
+    loop {
+        tokio::select! {
+            kline = rx.recv() => match kline {
+                Some(k) => {
+                    pending.insert((k.symbol, k.bar.time), k.bar);
+                }
+                None => return,
+            },
+            _ = tick.tick() => {
+                if pending.is_empty() {
+                    continue;
+                }
+                let mut by_ticker: HashMap<String, Vec<OhlcvData>> = HashMap::new();
+                for ((symbol, _), bar) in pending.drain() {
+                    by_ticker.entry(symbol).or_default().push(bar);
+                }
+                for (ticker, bars) in by_ticker {
+                    let ticker_id = match ids.get(&ticker) {
+                        Some(id) => *id,
+                        None => match binance_shared::ensure_crypto_ticker(&pool, SOURCE, &ticker).await {
+                            Ok(id) => *ids.entry(ticker.clone()).or_insert(id),
+                            Err(e) => {
+                                tracing::warn!(ticker, "Binance stream: failed to resolve ticker: {e}");
+                                continue;
+                            }
+                        },
+                    };
+                    vci_shared::enhance_and_save(&pool, ticker_id, &bars, INTERVAL, SOURCE, &ticker, &redis_client).await;
+                }
+            }
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Combined kline stream
+// ---------------------------------------------------------------------------
+
+/// One kline update (closed or still forming).
+#[derive(Debug, Clone)]
+struct Kline {
+    symbol: String,
+    bar: OhlcvData,
+    closed: bool,
+}
+
+#[derive(Deserialize)]
+struct CombinedMessage {
+    data: KlineEvent,
+}
+
+#[derive(Deserialize)]
+struct KlineEvent {
+    k: KlinePayload,
+}
+
+#[derive(Deserialize)]
+struct KlinePayload {
+    /// Bar open time (ms)
+    t: i64,
+    s: String,
+    o: String,
+    h: String,
+    l: String,
+    c: String,
+    v: String,
+    /// Whether this bar is closed
+    x: bool,
+}
+
+/// `{base}/stream?streams=btcusdt@kline_1m/ethusdt@kline_1m`
+fn combined_stream_url<'a>(base: &str, symbols: impl Iterator<Item = &'a str>) -> String {
+    let streams: Vec<String> = symbols.map(|s| format!("{}@kline_{INTERVAL}", s.to_lowercase())).collect();
+    format!("{}/stream?streams={}", base.trim_end_matches('/'), streams.join("/"))
+}
+
+fn parse_kline(text: &str) -> Option<Kline> {
+    let k = serde_json::from_str::<CombinedMessage>(text).ok()?.data.k;
+    let num = |s: &str| s.parse::<f64>().ok();
+    Some(Kline {
+        bar: OhlcvData {
+            time: DateTime::<Utc>::from_timestamp_millis(k.t)?,
+            open: num(&k.o)?,
+            high: num(&k.h)?,
+            low: num(&k.l)?,
+            close: num(&k.c)?,
+            volume: num(&k.v)? as u64,
+            symbol: Some(k.s.clone()),
+        },
+        symbol: k.s,
+        closed: k.x,
+    })
+}
+
+/// Connect to `url` and forward every kline until the connection ends.
+/// Returns Ok on a clean close, Err on transport errors.
+async fn read_stream(url: &str, tx: &mpsc::Sender<Kline>) -> Result<(), tokio_tungstenite::tungstenite::Error> {
+    let (mut socket, _) = tokio_tungstenite::connect_async(url).await?;
+    let mut closed_bars = 0u64;
+
+    while let Some(message) = socket.next().await {
+        match message? {
+            Message::Text(text) => {
+                let Some(kline) = parse_kline(&text) else {
+                    tracing::debug!("Binance stream: ignoring non-kline message");
+                    continue;
+                };
+                if kline.closed {
+                    closed_bars += 1;
+                }
+                if tx.send(kline).await.is_err() {
+                    return Ok(());
+                }
+            }
+            Message::Ping(payload) => socket.send(Message::Pong(payload)).await?,
+            Message::Close(_) => break,
+            _ => {}
+        }
+    }
+    tracing::debug!(closed_bars, "Binance stream: connection ended");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    const KLINE: &str = r#"{"stream":"btcusdt@kline_1m","data":{"e":"kline","E":1760000030000,"s":"BTCUSDT","k":{"t":1760000000000,"T":1760000059999,"s":"BTCUSDT","i":"1m","o":"100.5","c":"101.0","h":"102.0","l":"99.5","v":"12.7","x":false}}}"#;
+
+    #[test]
+    fn test_combined_stream_url() {
+        let url = combined_stream_url("wss://example.test:9443/", ["BTCUSDT", "ETHUSDT"].into_iter());
+        assert_eq!(url, "wss://example.test:9443/stream?streams=btcusdt@kline_1m/ethusdt@kline_1m");
+    }
+
+    #[test]
+    fn test_parse_kline() {
+        let k = parse_kline(KLINE).unwrap();
+        assert_eq!(k.symbol, "BTCUSDT");
+        assert!(!k.closed);
+        assert_eq!(k.bar.time, Utc.timestamp_millis_opt(1_760_000_000_000).unwrap());
+        assert_eq!((k.bar.open, k.bar.high, k.bar.low, k.bar.close), (100.5, 102.0, 99.5, 101.0));
+        assert_eq!(k.bar.volume, 12);
+        assert!(parse_kline(r#"{"result":null,"id":1}"#).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_read_stream_from_mock_server() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (tcp, _) = listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(tcp).await.unwrap();
+            ws.send(Message::Text(KLINE.into())).await.unwrap();
+            ws.send(Message::Text(r#"{"unrelated":true}"#.into())).await.unwrap();
+            ws.send(Message::Text(KLINE.replace(r#""x":false"#, r#""x":true"#).into())).await.unwrap();
+            ws.close(None).await.unwrap();
+        });
+
+        let (tx, mut rx) = mpsc::channel(8);
+        read_stream(&format!("ws://{addr}/stream?streams=btcusdt@kline_1m"), &tx).await.unwrap();
+        drop(tx);
+
+        let mut received = Vec::new();
+        while let Some(k) = rx.recv().await {
+            received.push(k);
+        }
+        assert_eq!(received.len(), 2);
+        assert!(!received[0].closed);
+        assert!(received[1].closed);
+        assert_eq!(received[1].bar.close, 101.0);
+    }
+}
@@ -0,0 +1,130 @@
+use chrono::{DateTime, Duration as ChronoDuration, NaiveTime, Utc};
+use sqlx::PgPool;
+use tokio::time::{sleep, Duration};
+
+use crate::constants::custom_index as cfg;
+use crate::models::custom_index::{CustomIndex, SOURCE};
+use crate::models::ohlcv::OhlcvRow;
+use crate::queries::{custom_indices, ohlcv};
+use crate::services::custom_index::{daily_series, intraday_series, MemberBars};
+use crate::workers::vci_shared::enhance_and_save;
+
+/// Custom index worker.
+///
+/// Every `LOOP_SECS`, recomputes each definition in `custom_indices` from its
+/// members' stored bars and writes the daily and `INTRADAY` series under
+/// `tickers(source = "custom", name)`. New or edited definitions replace the
+/// whole series; otherwise bars from the latest stored one on are rewritten.
+pub async fn run(pool: PgPool, redis_client: Option<crate::redis::RedisClient>) {
+    tracing::info!("Custom index worker started, waiting {}s before first round", cfg::INITIAL_DELAY_SECS);
+    sleep(Duration::from_secs(cfg::INITIAL_DELAY_SECS)).await;
+
+    loop {
+        match custom_indices::list(&pool).await {
+            Ok(indices) => {
+                let started = std::time::Instant::now();
+                let mut written = 0usize;
+                for index in &indices {
+                    match refresh(&pool, &redis_client, index).await {
+                        Ok(n) => written += n,
+                        Err(e) => tracing::warn!(index = index.name, "Custom index worker: {e}"),
+                    }
+                }
+                tracing::info!(
+                    indices = indices.len(),
+                    written,
+                    elapsed_secs = started.elapsed().as_secs(),
+                    "Custom index round complete"
+                );
+            }
+            Err(e) => tracing::warn!("Custom index worker: failed to list indices: {e}"),
+        }
+        sleep(Duration::from_secs(cfg::LOOP_SECS)).await;
+    }
+}
+
+/// Bring one index's series up to date. Returns bars written.
+async fn refresh(
+    pool: &PgPool,
+    redis_client: &Option<crate::redis::RedisClient>,
+    index: &CustomIndex,
+) -> sqlx::Result<usize> {
+    let started = Utc::now();
+    let rebuild = index.needs_rebuild();
+    let ticker_id = ohlcv::upsert_ticker(pool, SOURCE, &index.name, index.title.as_deref()).await?;
+    if rebuild {
+        ohlcv::delete_ohlcv_for_ticker(pool, ticker_id).await?;
+    }
+
+    let mut member_ids = Vec::with_capacity(index.members.len());
+    for m in &index.members {
+        let id = ohlcv::get_ticker_id(pool, &m.source, &m.ticker).await?;
+        if id.is_none() {
+            tracing::warn!(index = index.name, member = m.ticker, source = m.source, "Custom index member has no data");
+        }
+        member_ids.push(id);
+    }
+    let until = started + ChronoDuration::days(1);
+
+    // Daily: the whole history every round (it is chain-linked from the base day)
+    let daily_rows = load_bars(pool, &member_ids, "1D", DateTime::UNIX_EPOCH, until).await?;
+    let members: Vec<MemberBars> = index
+        .members
+        .iter()
+        .zip(&daily_rows)
+        .map(|(m, daily)| MemberBars { weight: m.weight, daily, intraday: &[] })
+        .collect();
+    let daily = daily_series(index.weighting, &members, index.base_value, index.base_date);
+
+    let mut written = 0usize;
+    let mut ok = true;
+    let latest = if rebuild { None } else { ohlcv::get_latest_time(pool, ticker_id, "1D").await? };
+    let fresh: Vec<_> = daily.iter().filter(|b| latest.is_none_or(|t| b.time >= t)).cloned().collect();
+    ok &= enhance_and_save(pool, ticker_id, &fresh, "1D", SOURCE, &index.name, redis_client).await;
+    written += fresh.len();
+
+    for &(interval, days) in cfg::INTRADAY {
+        // Redo the day of the latest stored bar: earlier bars of it may have
+        // been computed before every member had reported.
+        let latest = if rebuild { None } else { ohlcv::get_latest_time(pool, ticker_id, interval).await? };
+        let window_start = started - ChronoDuration::days(days);
+        let since = latest
+            .map(|t| t.date_naive().and_time(NaiveTime::MIN).and_utc())
+            .map_or(window_start, |t| t.max(window_start));
+        let intraday_rows = load_bars(pool, &member_ids, interval, since, until).await?;
+        let members: Vec<MemberBars> = index
+            .members
+            .iter()
+            .zip(daily_rows.iter().zip(&intraday_rows))
+            .map(|(m, (daily, intraday))| MemberBars { weight: m.weight, daily, intraday })
+            .collect();
+        let bars = intraday_series(index.weighting, &members, &daily, since);
+        ok &= enhance_and_save(pool, ticker_id, &bars, interval, SOURCE, &index.name, redis_client).await;
+        written += bars.len();
+    }
+
+    // A failed write keeps the index marked for another full rebuild
+    if ok {
+        custom_indices::mark_computed(pool, index.id, started).await?;
+    }
+    Ok(written)
+}
+
+/// `interval` bars with `start <= time < end` per member (empty when the
+/// member has no ticker row).
+async fn load_bars(
+    pool: &PgPool,
+    member_ids: &[Option<i32>],
+    interval: &str,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> sqlx::Result<Vec<Vec<OhlcvRow>>> {
+    let mut out = Vec::with_capacity(member_ids.len());
+    for id in member_ids {
+        out.push(match id {
+            Some(id) => ohlcv::get_ohlcv_between(pool, *id, interval, start, end).await?,
+            None => Vec::new(),
+        });
+    }
+    Ok(out)
+}
@@ -0,0 +1,336 @@
+use chrono::{DateTime, Datelike, Duration as ChronoDuration, Utc};
+use sqlx::PgPool;
+use std::collections::HashSet;
+use tokio::time::{sleep, Duration};
+
+use crate::constants::gap_worker as cfg;
+use crate::models::calendar::Exchange;
+use crate::models::interval::Interval;
+use crate::models::lifecycle::{self, LifecycleEvent, LifecycleState};
+use crate::providers::binance::BinanceProvider;
+use crate::providers::failover::FailoverProvider;
+use crate::providers::market_data::{HistoryRequest, MarketDataProvider};
+use crate::providers::vci::VciProvider;
+use crate::queries::gaps::{self, Gap, GapRow};
+use crate::queries::ohlcv;
+use crate::services::metadata;
+use crate::workers::{interval_sync, vci_shared};
+
+/// Intervals scanned for holes. Daily gaps are left to the sync workers' gap countback.
+pub const INTERVALS: [Interval; 2] = [Interval::Hourly, Interval::Minute];
+
+/// Gap scanner and targeted backfill worker.
+///
+/// Every `LOOP_SECS`, scans each active ticker of `SOURCES` for runs of
+/// missing 1h/1m bars between its oldest and newest stored bar (expected bars
+/// come from the exchange calendar, minus days the ticker was suspended and
+/// VN years without a holiday list),
+/// queues them in `ohlcv_gaps`, then re-fetches up to `FILLS_PER_ROUND`
+/// pending gaps with a range request.
+pub async fn run(pool: PgPool, redis_client: Option<crate::redis::RedisClient>) {
+    let vn = match VciProvider::new(cfg::RPM) {
+        Ok(p) => FailoverProvider::vci_with_udf(p),
+        Err(e) => {
+            tracing::error!("Gap worker: failed to create VCI provider: {e}");
+            return;
+        }
+    };
+    let crypto = match BinanceProvider::new(cfg::RPM) {
+        Ok(p) => p,
+        Err(e) => {
+            tracing::error!("Gap worker: failed to create Binance provider: {e}");
+            return;
+        }
+    };
+
+    tracing::info!("Gap worker started, waiting {}s before first round", cfg::INITIAL_DELAY_SECS);
+    sleep(Duration::from_secs(cfg::INITIAL_DELAY_SECS)).await;
+
+    loop {
+        for source in cfg::SOURCES {
+            match scan_source(&pool, source, None, &INTERVALS, true).await {
+                Ok(found) => tracing::info!(source, gaps = found.len(), "Gap scan complete"),
+                Err(e) => tracing::warn!(source, "Gap worker: scan failed: {e}"),
+            }
+        }
+
+        match fill_pending(&pool, &redis_client, &vn, &crypto, cfg::FILLS_PER_ROUND).await {
+            Ok((filled, attempted)) => tracing::info!(filled, attempted, "Gap backfill round complete"),
+            Err(e) => tracing::warn!("Gap worker: failed to load pending gaps: {e}"),
+        }
+        sleep(Duration::from_secs(cfg::LOOP_SECS)).await;
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Scanning
+// ---------------------------------------------------------------------------
+
+/// Scan every active ticker of `source` (or just `ticker`) and return
+/// `(ticker, interval, gap)` triples. Tickers delisted, renamed, merged or
+/// suspended today are skipped. Gaps are queued when `save` is set.
+pub async fn scan_source(
+    pool: &PgPool,
+    source: &str,
+    ticker: Option<&str>,
+    intervals: &[Interval],
+    save: bool,
+) -> sqlx::Result<Vec<(String, Interval, Gap)>> {
+    let Some(exchange) = Exchange::for_source(source) else {
+        return Ok(Vec::new());
+    };
+    let now = Utc::now();
+    let tickers = ohlcv::list_tickers(pool, source).await?;
+    let catalog = metadata::catalog();
+
+    let mut found = Vec::new();
+    for t in tickers.iter().filter(|t| ticker.is_none_or(|name| t.ticker == name)) {
+        if t.status.as_deref() == Some("delisted") || catalog.state(source, &t.ticker, now.date_naive()) != LifecycleState::Active {
+            continue;
+        }
+        let events = catalog.events(source, &t.ticker);
+        for &interval in intervals {
+            let ticker_gaps = match scan_ticker(pool, exchange, t.id, interval, now, events).await {
+                Ok(g) => g,
+                Err(e) => {
+                    tracing::warn!(ticker = t.ticker, interval = interval.as_str(), "gap scan failed: {e}");
+                    continue;
+                }
+            };
+            if save && let Err(e) = gaps::save_gaps(pool, t.id, interval.as_str(), &ticker_gaps).await {
+                tracing::warn!(ticker = t.ticker, "failed to queue gaps: {e}");
+            }
+            found.extend(ticker_gaps.into_iter().map(|g| (t.ticker.clone(), interval, g)));
+        }
+    }
+    Ok(found)
+}
+
+/// Holes in one ticker's series within the lookback window. Only ranges
+/// bounded by stored bars count: nothing before the first bar (listing date,
+/// bootstrap depth) and nothing after the last one (the sync workers' job).
+/// `events` are the ticker's lifecycle events; suspended days are no holes.
+async fn scan_ticker(
+    pool: &PgPool,
+    exchange: Exchange,
+    ticker_id: i32,
+    interval: Interval,
+    now: DateTime<Utc>,
+    events: &[LifecycleEvent],
+) -> sqlx::Result<Vec<Gap>> {
+    let iv = interval.as_str();
+    let (Some(earliest), Some(latest)) = (
+        ohlcv::get_earliest_time(pool, ticker_id, iv).await?,
+        ohlcv::get_latest_time(pool, ticker_id, iv).await?,
+    ) else {
+        return Ok(Vec::new());
+    };
+
+    let start = earliest.max(now - ChronoDuration::days(lookback_days(interval)));
+    let end = latest.min(now - ChronoDuration::minutes(cfg::SETTLE_MINUTES));
+    if start >= end {
+        return Ok(Vec::new());
+    }
+
+    let mut expected = exchange.expected_bars(interval, start, end);
+    // Without the year's holiday list every closure would look like a gap
+    expected.retain(|t| exchange.holidays_known(t.year()));
+    let mut actual = gaps::bar_times(pool, ticker_id, iv, start, end).await?;
+    actual.extend(inactive_bars(&expected, events));
+    Ok(find_gaps(&expected, &actual, min_bars(interval)))
+}
+
+/// Expected bars on days the ticker was not active (suspended trading).
+/// Counting them as present ends a run of missing bars at a suspension.
+fn inactive_bars(expected: &[DateTime<Utc>], events: &[LifecycleEvent]) -> Vec<DateTime<Utc>> {
+    if events.is_empty() {
+        return Vec::new();
+    }
+    expected
+        .iter()
+        .filter(|t| lifecycle::state_on(events, t.date_naive()) != LifecycleState::Active)
+        .copied()
+        .collect()
+}
+
+/// Runs of at least `min_bars` consecutive expected bars absent from `actual`.
+pub fn find_gaps(expected: &[DateTime<Utc>], actual: &[DateTime<Utc>], min_bars: usize) -> Vec<Gap> {
+    let present: HashSet<&DateTime<Utc>> = actual.iter().collect();
+    let mut out = Vec::new();
+    let mut run: Vec<DateTime<Utc>> = Vec::new();
+
+    let mut flush = |run: &mut Vec<DateTime<Utc>>| {
+        if run.len() >= min_bars.max(1) {
+            out.push(Gap { start: run[0], end: run[run.len() - 1], missing_bars: run.len() as i32 });
+        }
+        run.clear();
+    };
+
+    for t in expected {
+        if present.contains(t) {
+            flush(&mut run);
+        } else {
+            run.push(*t);
+        }
+    }
+    flush(&mut run);
+    out
+}
+
+fn lookback_days(interval: Interval) -> i64 {
+    match interval {
+        Interval::Minute => cfg::MINUTE_LOOKBACK_DAYS,
+        _ => cfg::HOURLY_LOOKBACK_DAYS,
+    }
+}
+
+fn min_bars(interval: Interval) -> usize {
+    match interval {
+        Interval::Minute => cfg::MIN_MINUTE_BARS,
+        _ => cfg::MIN_HOURLY_BARS,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Backfill
+// ---------------------------------------------------------------------------
+
+/// Re-fetch up to `limit` pending gaps, oldest first. Returns (filled, attempted).
+pub async fn fill_pending<V: MarketDataProvider, C: MarketDataProvider>(
+    pool: &PgPool,
+    redis_client: &Option<crate::redis::RedisClient>,
+    vn: &V,
+    crypto: &C,
+    limit: i64,
+) -> sqlx::Result<(usize, usize)> {
+    let pending = gaps::pending_gaps(pool, limit).await?;
+    let mut filled = 0;
+    for gap in &pending {
+        let done = match gap.source.as_str() {
+            "vn" => fill_gap(pool, redis_client, vn, gap, true).await,
+            "crypto" => fill_gap(pool, redis_client, crypto, gap, false).await,
+            _ => false,
+        };
+        if done {
+            filled += 1;
+        }
+    }
+    Ok((filled, pending.len()))
+}
+
+/// Fetch the gap's range, save it, and record the outcome. Returns true when
+/// no expected bar in the range is missing any more.
+async fn fill_gap<P: MarketDataProvider>(
+    pool: &PgPool,
+    redis_client: &Option<crate::redis::RedisClient>,
+    provider: &P,
+    gap: &GapRow,
+    back_adjusts: bool,
+) -> bool {
+    let Ok(interval) = Interval::from_arg(&gap.interval) else {
+        return false;
+    };
+    let request = HistoryRequest::Range { start: gap.gap_start, end: gap.gap_end };
+
+    match provider.history_with_source(&gap.ticker, interval, request).await {
+        Ok((mut data, served_by)) if !data.is_empty() => {
+            // Same raw-price restore as the sync workers, fallback bars included.
+            if back_adjusts
+                && let Err(e) = interval_sync::restore_raw_prices(pool, gap.ticker_id, &mut data).await
+            {
+                tracing::warn!(ticker = gap.ticker, served_by, "gap fill: failed to load corporate actions: {e}");
+                return false;
+            }
+            if !vci_shared::enhance_and_save(pool, gap.ticker_id, &data, &gap.interval, &gap.source, &gap.ticker, redis_client).await {
+                return false;
+            }
+        }
+        Ok(_) => tracing::debug!(ticker = gap.ticker, interval = gap.interval, "gap fill: no bars upstream"),
+        Err(e) => {
+            tracing::warn!(ticker = gap.ticker, interval = gap.interval, "gap fill: fetch failed: {e}");
+            if e.is_rate_limited() {
+                return false;
+            }
+        }
+    }
+
+    // Re-check the whole range; whatever is still missing is what upstream lacks.
+    let remaining = match Exchange::for_source(&gap.source) {
+        Some(exchange) => {
+            let end = gap.gap_end + ChronoDuration::seconds(1);
+            let expected = exchange.expected_bars(interval, gap.gap_start, end);
+            match gaps::bar_times(pool, gap.ticker_id, &gap.interval, gap.gap_start, end).await {
+                Ok(actual) => find_gaps(&expected, &actual, 1).iter().map(|g| g.missing_bars).sum(),
+                Err(e) => {
+                    tracing::warn!(ticker = gap.ticker, "gap fill: re-check failed: {e}");
+                    return false;
+                }
+            }
+        }
+        None => 0,
+    };
+
+    let status = if remaining == 0 {
+        "filled"
+    } else if gap.attempts + 1 >= cfg::MAX_ATTEMPTS {
+        "unfillable"
+    } else {
+        "pending"
+    };
+    if let Err(e) = gaps::record_attempt(pool, gap.id, status, remaining).await {
+        tracing::warn!(ticker = gap.ticker, "gap fill: failed to update gap: {e}");
+    }
+    tracing::info!(ticker = gap.ticker, interval = gap.interval, remaining, status, "gap fill attempt");
+    remaining == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(h: u32, m: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 10, 16, h, m, 0).unwrap()
+    }
+
+    #[test]
+    fn test_find_gaps_reports_runs_at_least_min() {
+        let expected: Vec<_> = (0..10).map(|m| at(2, m)).collect();
+        // Missing 02:01 (run of 1) and 02:04..02:07 (run of 4).
+        let actual = vec![at(2, 0), at(2, 2), at(2, 3), at(2, 8), at(2, 9)];
+        let gaps = find_gaps(&expected, &actual, 2);
+        assert_eq!(gaps, vec![Gap { start: at(2, 4), end: at(2, 7), missing_bars: 4 }]);
+        assert_eq!(find_gaps(&expected, &actual, 1).len(), 2);
+    }
+
+    #[test]
+    fn test_find_gaps_trailing_run_and_extra_bars() {
+        let expected = vec![at(2, 0), at(3, 0), at(4, 0), at(6, 0)];
+        // 05:00 is not an expected bar (lunch) and must not break detection.
+        let actual = vec![at(2, 0), at(5, 0)];
+        let gaps = find_gaps(&expected, &actual, 2);
+        assert_eq!(gaps, vec![Gap { start: at(3, 0), end: at(6, 0), missing_bars: 3 }]);
+    }
+
+    #[test]
+    fn test_suspended_days_are_not_gaps() {
+        let event = |day: u32, state: LifecycleState| LifecycleEvent {
+            id: 0,
+            source: "vn".into(),
+            ticker: "AAA".into(),
+            state,
+            effective_on: chrono::NaiveDate::from_ymd_opt(2026, 10, day).unwrap(),
+            successor: None,
+            note: None,
+        };
+        // Suspended on the 14th and 15th, trading again from the 16th
+        let events = [event(14, LifecycleState::Suspended), event(16, LifecycleState::Active)];
+        let day = |d: u32, h: u32| Utc.with_ymd_and_hms(2026, 10, d, h, 0, 0).unwrap();
+        let expected: Vec<_> = [13, 14, 15, 16].iter().flat_map(|&d| [day(d, 2), day(d, 3)]).collect();
+        let mut actual = vec![day(13, 2), day(16, 3)];
+        actual.extend(inactive_bars(&expected, &events));
+        // 13th 03:00 and 16th 02:00 are single missing bars split by the suspension
+        assert!(find_gaps(&expected, &actual, 2).is_empty());
+        assert_eq!(find_gaps(&expected, &actual, 1).len(), 2);
+    }
+}
@@ -36,11 +36,10 @@ struct HealthRow {
 }
 
 async fn refresh(pool: &PgPool) -> Result<HealthSnapshot, sqlx::Error> {
-    use chrono::{Datelike, Timelike};
+    use chrono::Datelike;
 
     let now = chrono::Utc::now();
-    let is_trading = now.weekday().num_days_from_monday() < 5
-        && now.hour() >= 2 && now.hour() < 8;
+    let is_trading = crate::models::calendar::Exchange::Hose.is_open(now);
 
     let year = now.year();
     let hourly_table = format!("ohlcv_hourly_{year}");
@@ -0,0 +1,621 @@
+//! Generic incremental OHLCV sync worker.
+//!
+//! One loop per (source × interval), driven by a [`SyncJob`] built from the
+//! per-source constants. Each iteration loads due tickers (`next_*` column in
+//! the past), fetches the tail through [`MarketDataProvider`], upserts + writes
+//! Redis via `enhance_and_save`, and schedules the next run.
+
+use chrono::Utc;
+use sqlx::PgPool;
+use std::sync::Arc;
+use tokio::time::{sleep, Duration};
+
+use crate::constants::{
+    binance_worker, vci_worker, yahoo_worker, MAJOR_CRYPTO, MAJOR_GLOBAL, MAJOR_SCHEDULE_SECS,
+    MAJOR_VN,
+};
+use crate::constants::vci_worker::priority;
+use crate::models::calendar::Exchange;
+use crate::models::corporate_action;
+use crate::models::interval::Interval;
+use crate::providers::binance::BinanceProvider;
+use crate::providers::failover::FailoverProvider;
+use crate::providers::market_data::{HistoryRequest, MarketDataProvider};
+use crate::providers::ohlcv::OhlcvData;
+use crate::providers::vci::VciProvider;
+use crate::providers::yahoo::YahooProvider;
+use crate::queries::{corporate_actions, ohlcv};
+use crate::workers::{binance_shared, vci_shared, yahoo_shared};
+
+// ---------------------------------------------------------------------------
+// Config
+// ---------------------------------------------------------------------------
+
+/// Which market a job syncs. Carries the per-source hooks (ticker discovery,
+/// dividend detection, symbol mapping, trading hours).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncSource {
+    Vn,
+    Crypto,
+    Yahoo,
+}
+
+impl SyncSource {
+    /// Value of `tickers.source`.
+    pub fn db_source(&self) -> &'static str {
+        match self {
+            SyncSource::Vn => "vn",
+            SyncSource::Crypto => "crypto",
+            SyncSource::Yahoo => "yahoo",
+        }
+    }
+
+    async fn ensure_ticker(&self, pool: &PgPool, ticker: &str) -> sqlx::Result<i32> {
+        let source = self.db_source();
+        match self {
+            SyncSource::Vn => vci_shared::ensure_vn_ticker(pool, source, ticker).await,
+            SyncSource::Crypto => binance_shared::ensure_crypto_ticker(pool, source, ticker).await,
+            SyncSource::Yahoo => yahoo_shared::ensure_yahoo_ticker(pool, source, ticker).await,
+        }
+    }
+
+    /// Upsert tickers listed in the source's JSON file. Returns the number synced.
+    async fn discover_tickers(&self, pool: &PgPool) -> usize {
+        match self {
+            SyncSource::Vn => vci_shared::sync_vn_tickers(pool).await,
+            SyncSource::Crypto => binance_shared::sync_crypto_tickers(pool).await,
+            SyncSource::Yahoo => yahoo_shared::sync_yahoo_tickers(pool).await,
+        }
+    }
+
+    /// Flag a split/dividend so the bootstrap or dividend worker re-downloads history.
+    async fn detect_dividend(&self, pool: &PgPool, ticker_id: i32, ticker: &str, data: &[OhlcvData]) -> bool {
+        match self {
+            SyncSource::Vn => vci_shared::detect_dividend(pool, ticker_id, ticker, data).await,
+            SyncSource::Yahoo => yahoo_shared::detect_dividend(pool, ticker_id, ticker, data).await,
+            SyncSource::Crypto => false,
+        }
+    }
+
+    /// Symbol to send upstream for a DB ticker.
+    fn vendor_symbol<'a>(&self, ticker: &'a str) -> &'a str {
+        match self {
+            SyncSource::Yahoo => yahoo_shared::yahoo_symbol(ticker),
+            _ => ticker,
+        }
+    }
+
+    /// Exchange calendar used to decide whether the market is in session.
+    fn exchange(&self) -> Exchange {
+        match self {
+            SyncSource::Vn => Exchange::Hose,
+            SyncSource::Crypto => Exchange::Crypto,
+            SyncSource::Yahoo => Exchange::Nyse,
+        }
+    }
+
+    /// Whether the market is in session. Yahoo jobs use the same loop timing
+    /// either way, so the NYSE calendar only affects logging there.
+    fn is_trading(&self) -> bool {
+        self.exchange().is_open(Utc::now())
+    }
+
+    /// Whether the vendor back-adjusts history for splits/dividends (see `corporate_actions`).
+    fn back_adjusts(&self) -> bool {
+        matches!(self, SyncSource::Vn | SyncSource::Yahoo)
+    }
+
+    /// Clients usable for history requests (Binance reserves one for Vision).
+    fn api_clients(&self, client_count: usize) -> usize {
+        match self {
+            SyncSource::Crypto => client_count.saturating_sub(1),
+            _ => client_count,
+        }
+    }
+}
+
+/// Undo the vendor's back-adjustment for every recorded corporate action so
+/// bars line up with the raw prices already stored. Applies whichever
+/// provider served the bars: the UDF fallbacks back-adjust history too, and
+/// saving their bars unrestored would overwrite the raw rows.
+pub(crate) async fn restore_raw_prices(pool: &PgPool, ticker_id: i32, data: &mut [OhlcvData]) -> sqlx::Result<()> {
+    let actions = corporate_actions::list_for_ticker(pool, ticker_id).await?;
+    corporate_action::restore_raw(data, &actions);
+    Ok(())
+}
+
+/// How much history to request per sync.
+#[derive(Debug, Clone, Copy)]
+pub enum FetchPolicy {
+    /// Fixed countback, bumped to `gap` when the newest stored bar is older than
+    /// `gap_threshold_days` (or missing).
+    CountBack { recent: u32, gap: u32, gap_threshold_days: i64 },
+    /// Everything since the newest stored bar; `fallback` bars when there is none.
+    SinceLast { fallback: u32 },
+    /// A fixed window ending now.
+    Window { days: i64 },
+}
+
+/// How to pick the next `next_*` timestamp after a successful sync.
+#[derive(Debug, Clone, Copy)]
+pub enum SchedulePolicy {
+    /// Money-flow tiers (see `vci_worker::priority`), stretched off-hours.
+    MoneyFlow { tier_secs: [i64; 4] },
+    /// Same delay for every ticker.
+    Fixed { secs: i64 },
+}
+
+pub struct SyncJob {
+    /// Log prefix, e.g. "VCI daily".
+    pub label: &'static str,
+    pub source: SyncSource,
+    pub interval: Interval,
+    pub fetch: FetchPolicy,
+    pub schedule: SchedulePolicy,
+    /// Tickers pinned to `MAJOR_SCHEDULE_SECS` regardless of `schedule`.
+    pub majors: &'static [&'static str],
+    pub initial_delay_secs: u64,
+    pub loop_trade_secs: u64,
+    pub loop_off_secs: u64,
+    pub rate_limit_cooldown_secs: u64,
+    pub concurrency: fn(usize) -> usize,
+    /// Upsert tickers from the source's JSON list every iteration.
+    pub discover_tickers: bool,
+    /// Send tickers with < 3 stored bars back to the bootstrap worker.
+    pub require_history: bool,
+    pub detect_dividends: bool,
+}
+
+impl SyncJob {
+    pub fn vci(interval: Interval) -> Self {
+        let (label, fetch, tier_secs, initial_delay_secs, loop_trade_secs, loop_off_secs) = match interval {
+            Interval::Daily => (
+                "VCI daily",
+                FetchPolicy::CountBack {
+                    recent: vci_worker::DAILY_COUNTBACK,
+                    gap: vci_worker::DAILY_COUNTBACK,
+                    gap_threshold_days: i64::MAX,
+                },
+                priority::DAILY_SECS,
+                0,
+                vci_worker::DAILY_LOOP_TRADE_SECS,
+                vci_worker::DAILY_LOOP_OFF_SECS,
+            ),
+            Interval::Hourly => (
+                "VCI hourly",
+                FetchPolicy::CountBack {
+                    recent: vci_worker::HOURLY_COUNTBACK_RECENT,
+                    gap: vci_worker::HOURLY_COUNTBACK_GAP,
+                    gap_threshold_days: vci_worker::HOURLY_GAP_THRESHOLD_DAYS,
+                },
+                priority::HOURLY_SECS,
+                vci_worker::HOURLY_INITIAL_DELAY_SECS,
+                vci_worker::HOURLY_LOOP_TRADE_SECS,
+                vci_worker::HOURLY_LOOP_OFF_SECS,
+            ),
+            Interval::Minute => (
+                "VCI minute",
+                FetchPolicy::CountBack {
+                    recent: vci_worker::MINUTE_COUNTBACK_RECENT,
+                    gap: vci_worker::MINUTE_COUNTBACK_GAP,
+                    gap_threshold_days: vci_worker::MINUTE_GAP_THRESHOLD_DAYS,
+                },
+                priority::MINUTE_SECS,
+                vci_worker::MINUTE_INITIAL_DELAY_SECS,
+                vci_worker::MINUTE_LOOP_TRADE_SECS,
+                vci_worker::MINUTE_LOOP_OFF_SECS,
+            ),
+        };
+        let daily = interval == Interval::Daily;
+        Self {
+            label,
+            source: SyncSource::Vn,
+            interval,
+            fetch,
+            schedule: SchedulePolicy::MoneyFlow { tier_secs },
+            majors: MAJOR_VN,
+            initial_delay_secs,
+            loop_trade_secs,
+            loop_off_secs,
+            rate_limit_cooldown_secs: vci_worker::RATE_LIMIT_COOLDOWN_SECS,
+            concurrency: vci_worker::concurrent_batches,
+            discover_tickers: daily,
+            require_history: daily,
+            detect_dividends: daily,
+        }
+    }
+
+    pub fn binance(interval: Interval) -> Self {
+        let (label, limit, secs, initial_delay_secs, loop_secs) = match interval {
+            Interval::Daily => (
+                "Binance daily",
+                binance_worker::DAILY_LIMIT,
+                binance_worker::SCHEDULE_DAILY_SECS,
+                0,
+                binance_worker::DAILY_LOOP_SECS,
+            ),
+            Interval::Hourly => (
+                "Binance hourly",
+                binance_worker::HOURLY_LIMIT,
+                binance_worker::SCHEDULE_HOURLY_SECS,
+                binance_worker::HOURLY_INITIAL_DELAY_SECS,
+                binance_worker::HOURLY_LOOP_SECS,
+            ),
+            Interval::Minute => (
+                "Binance minute",
+                binance_worker::MINUTE_LIMIT,
+                binance_worker::SCHEDULE_MINUTE_SECS,
+                binance_worker::MINUTE_INITIAL_DELAY_SECS,
+                binance_worker::MINUTE_LOOP_SECS,
+            ),
+        };
+        let daily = interval == Interval::Daily;
+        Self {
+            label,
+            source: SyncSource::Crypto,
+            interval,
+            fetch: FetchPolicy::SinceLast { fallback: limit },
+            schedule: SchedulePolicy::Fixed { secs },
+            majors: MAJOR_CRYPTO,
+            initial_delay_secs,
+            loop_trade_secs: loop_secs,
+            loop_off_secs: loop_secs,
+            rate_limit_cooldown_secs: binance_worker::RATE_LIMIT_COOLDOWN_SECS,
+            concurrency: binance_worker::concurrent_batches,
+            discover_tickers: daily,
+            require_history: daily,
+            detect_dividends: false,
+        }
+    }
+
+    pub fn yahoo(interval: Interval) -> Self {
+        let (label, days, secs, initial_delay_secs, loop_secs) = match interval {
+            Interval::Daily => (
+                "Yahoo daily",
+                yahoo_worker::DAILY_WINDOW_DAYS,
+                yahoo_worker::SCHEDULE_DAILY_SECS,
+                0,
+                yahoo_worker::DAILY_LOOP_SECS,
+            ),
+            Interval::Hourly => (
+                "Yahoo hourly",
+                yahoo_worker::HOURLY_WINDOW_DAYS,
+                yahoo_worker::SCHEDULE_HOURLY_SECS,
+                yahoo_worker::HOURLY_INITIAL_DELAY_SECS,
+                yahoo_worker::HOURLY_LOOP_SECS,
+            ),
+            Interval::Minute => (
+                "Yahoo minute",
+                yahoo_worker::MINUTE_WINDOW_DAYS,
+                yahoo_worker::SCHEDULE_MINUTE_SECS,
+                yahoo_worker::MINUTE_INITIAL_DELAY_SECS,
+                yahoo_worker::MINUTE_LOOP_SECS,
+            ),
+        };
+        let daily = interval == Interval::Daily;
+        Self {
+            label,
+            source: SyncSource::Yahoo,
+            interval,
+            fetch: FetchPolicy::Window { days },
+            schedule: SchedulePolicy::Fixed { secs },
+            majors: MAJOR_GLOBAL,
+            initial_delay_secs,
+            loop_trade_secs: loop_secs,
+            loop_off_secs: loop_secs,
+            rate_limit_cooldown_secs: yahoo_worker::RATE_LIMIT_COOLDOWN_SECS,
+            concurrency: yahoo_worker::concurrent_batches,
+            discover_tickers: daily,
+            require_history: daily,
+            detect_dividends: daily,
+        }
+    }
+
+    fn next_col(&self) -> &'static str {
+        match self.interval {
+            Interval::Daily => "next_1d",
+            Interval::Hourly => "next_1h",
+            Interval::Minute => "next_1m",
+        }
+    }
+
+    /// "daily" / "hourly" / "minute" for per-ticker log lines.
+    fn interval_word(&self) -> &'static str {
+        match self.interval {
+            Interval::Daily => "daily",
+            Interval::Hourly => "hourly",
+            Interval::Minute => "minute",
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Entry points (one per source, spawned once per interval)
+// ---------------------------------------------------------------------------
+
+pub async fn run_vci(pool: PgPool, redis_client: Option<crate::redis::RedisClient>, interval: Interval) {
+    let job = SyncJob::vci(interval);
+    let rpm = match interval {
+        Interval::Daily => 60,
+        Interval::Hourly => 30,
+        Interval::Minute => 20,
+    };
+    wait_initial_delay(&job).await;
+    match VciProvider::new(rpm) {
+        Ok(p) => {
+            let provider = FailoverProvider::vci_with_udf(p);
+            tracing::info!("{} worker: UDF fallbacks {:?}", job.label, provider.fallback_names());
+            run(pool, redis_client, Arc::new(provider), job).await
+        }
+        Err(e) => tracing::error!("{} worker: failed to create provider: {e}", job.label),
+    }
+}
+
+pub async fn run_binance(pool: PgPool, redis_client: Option<crate::redis::RedisClient>, interval: Interval) {
+    let job = SyncJob::binance(interval);
+    wait_initial_delay(&job).await;
+    match BinanceProvider::new(120) {
+        Ok(p) => run(pool, redis_client, Arc::new(p), job).await,
+        Err(e) => tracing::error!("{} worker: failed to create provider: {e}", job.label),
+    }
+}
+
+pub async fn run_yahoo(pool: PgPool, redis_client: Option<crate::redis::RedisClient>, interval: Interval) {
+    let job = SyncJob::yahoo(interval);
+    wait_initial_delay(&job).await;
+    match YahooProvider::with_options(60, true, true) {
+        Ok(p) => run(pool, redis_client, Arc::new(p), job).await,
+        Err(e) => tracing::error!("{} worker: failed to create provider: {e}", job.label),
+    }
+}
+
+async fn wait_initial_delay(job: &SyncJob) {
+    if job.initial_delay_secs > 0 {
+        tracing::info!("{} worker: waiting {} seconds before first sync...", job.label, job.initial_delay_secs);
+        sleep(Duration::from_secs(job.initial_delay_secs)).await;
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Loop
+// ---------------------------------------------------------------------------
+
+pub async fn run<P: MarketDataProvider + 'static>(
+    pool: PgPool,
+    redis_client: Option<crate::redis::RedisClient>,
+    provider: Arc<P>,
+    job: SyncJob,
+) {
+    let job = Arc::new(job);
+    let source = job.source.db_source();
+    let next_col = job.next_col();
+    let api_clients = job.source.api_clients(provider.client_count());
+    let concurrency = (job.concurrency)(api_clients);
+
+    tracing::info!(
+        "{} worker started (provider={}, api_clients={}, concurrency={})",
+        job.label,
+        provider.name(),
+        api_clients,
+        concurrency,
+    );
+
+    loop {
+        let trading = job.source.is_trading();
+        let loop_secs = if trading { job.loop_trade_secs } else { job.loop_off_secs };
+
+        if job.discover_tickers {
+            let added = job.source.discover_tickers(&pool).await;
+            if added > 0 {
+                tracing::info!("{} worker: synced {added} tickers from ticker list", job.label);
+            }
+        }
+
+        let mut tickers = match ohlcv::get_due_tickers(&pool, source, next_col).await {
+            Ok(t) => t,
+            Err(e) => {
+                tracing::warn!("{} worker: failed to load due tickers: {e}", job.label);
+                sleep(Duration::from_secs(loop_secs)).await;
+                continue;
+            }
+        };
+        use rand::seq::SliceRandom;
+        tickers.shuffle(&mut rand::thread_rng());
+        tickers.truncate((tickers.len() as f64 * crate::constants::due_ticker_fraction()) as usize);
+
+        if !tickers.is_empty() {
+            tracing::info!("{} worker: syncing {} due tickers (trading={})", job.label, tickers.len(), trading);
+
+            let schedule = match job.schedule {
+                SchedulePolicy::MoneyFlow { tier_secs } => {
+                    let mult = if trading { 1 } else { vci_worker::OFF_HOURS_MULTIPLIER };
+                    SchedulePolicy::MoneyFlow {
+                        tier_secs: tier_secs.map(|s| (s * mult).min(vci_worker::MAX_SCHEDULE_SECS)),
+                    }
+                }
+                fixed => fixed,
+            };
+
+            for chunk in tickers.chunks(concurrency) {
+                let mut handles = tokio::task::JoinSet::new();
+                for ticker_entry in chunk {
+                    let pool = pool.clone();
+                    let provider = provider.clone();
+                    let redis_client = redis_client.clone();
+                    let job = job.clone();
+                    let ticker = ticker_entry.ticker.clone();
+                    handles.spawn(async move {
+                        sync_ticker(&pool, &*provider, &redis_client, &job, schedule, &ticker).await
+                    });
+                }
+
+                let mut rate_limited = 0usize;
+                while let Some(result) = handles.join_next().await {
+                    if result.unwrap_or(false) {
+                        rate_limited += 1;
+                    }
+                }
+
+                if rate_limited > 0 {
+                    tracing::warn!(
+                        rate_limited,
+                        total = chunk.len(),
+                        "rate limited in batch, cooling down {}s",
+                        job.rate_limit_cooldown_secs
+                    );
+                    sleep(Duration::from_secs(job.rate_limit_cooldown_secs)).await;
+                }
+            }
+        } else {
+            tracing::debug!("{} worker: no due tickers", job.label);
+        }
+
+        sleep(Duration::from_secs(loop_secs)).await;
+    }
+}
+
+/// Sync one ticker. Returns true when the fetch was rate limited.
+async fn sync_ticker<P: MarketDataProvider>(
+    pool: &PgPool,
+    provider: &P,
+    redis_client: &Option<crate::redis::RedisClient>,
+    job: &SyncJob,
+    schedule: SchedulePolicy,
+    ticker: &str,
+) -> bool {
+    let source = job.source.db_source();
+    let interval = job.interval.as_str();
+    let word = job.interval_word();
+
+    let ticker_id = match job.source.ensure_ticker(pool, ticker).await {
+        Ok(id) => id,
+        Err(e) => {
+            tracing::warn!(ticker, "failed to upsert ticker: {e}");
+            return false;
+        }
+    };
+
+    // Check existing count before fetching — the fetch itself adds records so
+    // checking after would always pass.
+    if job.require_history
+        && let Ok(count) = ohlcv::count_ohlcv(pool, source, Some(ticker), Some(interval)).await
+        && count < 3
+    {
+        tracing::warn!(ticker, count, ticker_id, "{word}: records < 3, requesting full download");
+        let _ = ohlcv::update_ticker_status(pool, ticker_id, "full-download-requested").await;
+        return false;
+    }
+
+    let request = match job.fetch {
+        FetchPolicy::CountBack { recent, gap, gap_threshold_days } => {
+            let count = match vci_shared::get_last_time(pool, ticker_id, interval).await {
+                Some(t) if (Utc::now() - t).num_days() < gap_threshold_days => recent,
+                _ => gap,
+            };
+            HistoryRequest::count_back(count)
+        }
+        FetchPolicy::SinceLast { fallback } => match vci_shared::get_last_time(pool, ticker_id, interval).await {
+            Some(start) => HistoryRequest::Range { start, end: Utc::now() },
+            None => HistoryRequest::count_back(fallback),
+        },
+        FetchPolicy::Window { days } => {
+            let end = Utc::now();
+            HistoryRequest::Range { start: end - chrono::Duration::days(days), end }
+        }
+    };
+
+    let (mut data, served_by) = match provider.history_with_source(job.source.vendor_symbol(ticker), job.interval, request).await {
+        Ok(fetched) => fetched,
+        Err(e) => {
+            tracing::warn!(ticker, provider = provider.name(), "{word} fetch failed: {e}");
+            return e.is_rate_limited();
+        }
+    };
+    let fallback = served_by != provider.name();
+
+    if job.source.back_adjusts()
+        && let Err(e) = restore_raw_prices(pool, ticker_id, &mut data).await
+    {
+        tracing::warn!(ticker, "{word}: failed to load corporate actions, skipping sync: {e}");
+        return false;
+    }
+
+    // Fallback brokers may adjust history differently from the primary, so
+    // comparing their bars against stored ones would flag false dividends.
+    if job.detect_dividends && !fallback && job.source.detect_dividend(pool, ticker_id, ticker, &data).await {
+        tracing::warn!("[DIVIDEND] ticker={}, source={}, {} sync SKIPPED — corporate action recorded (or full re-download requested)", ticker, source, word);
+        return false;
+    }
+
+    if !vci_shared::enhance_and_save(pool, ticker_id, &data, interval, source, ticker, redis_client).await {
+        tracing::warn!(ticker, count = data.len(), "{word} sync upsert failed, skipping schedule");
+        return false;
+    }
+
+    if let Err(e) = ohlcv::record_fetch_source(pool, ticker_id, interval, served_by, fallback).await {
+        tracing::warn!(ticker, served_by, "failed to record fetch source: {e}");
+    }
+
+    let next_col = job.next_col();
+    let major = job.majors.contains(&ticker);
+    let scheduled = match schedule {
+        _ if major => binance_shared::schedule_fixed_interval(pool, ticker_id, next_col, MAJOR_SCHEDULE_SECS).await,
+        SchedulePolicy::Fixed { secs } => binance_shared::schedule_fixed_interval(pool, ticker_id, next_col, secs).await,
+        SchedulePolicy::MoneyFlow { tier_secs } => {
+            ohlcv::schedule_next_run(pool, ticker_id, next_col, &priority::THRESHOLDS, &tier_secs).await
+        }
+    };
+    match scheduled {
+        Ok(next_run) => tracing::info!(ticker, count = data.len(), major, served_by, next = %next_run, "{word} sync OK"),
+        Err(e) => tracing::warn!(ticker, count = data.len(), "{word} sync OK but scheduling failed: {e}"),
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::corporate_action::{ActionType, CorporateAction};
+    use crate::providers::market_data::stub::{Reply, StubProvider};
+    use chrono::{NaiveDate, TimeZone};
+    use std::sync::Mutex;
+
+    fn bar(day: u32, close: f64) -> OhlcvData {
+        OhlcvData {
+            time: Utc.with_ymd_and_hms(2026, 9, day, 0, 0, 0).unwrap(),
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume: 100,
+            symbol: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fallback_bars_are_restored_to_raw() {
+        // 2:1 split on the 10th: the broker serves pre-split bars halved
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let provider = FailoverProvider::new(
+            StubProvider::new("vci", Reply::RateLimit, &calls),
+            vec![StubProvider::new("vndirect", Reply::Bars(vec![bar(9, 50.0), bar(10, 51.0)]), &calls)],
+        );
+        let (mut data, served_by) = provider
+            .history_with_source("FPT", Interval::Daily, HistoryRequest::count_back(2))
+            .await
+            .unwrap();
+        assert_eq!(served_by, "vndirect");
+        assert_ne!(served_by, provider.name());
+
+        let actions = [CorporateAction {
+            ex_date: NaiveDate::from_ymd_opt(2026, 9, 10).unwrap(),
+            action_type: ActionType::Split,
+            ratio: 2.0,
+            cash_amount: None,
+            source: "vci".into(),
+        }];
+        assert!(SyncSource::Vn.back_adjusts());
+        corporate_action::restore_raw(&mut data, &actions);
+        assert_eq!(data.iter().map(|d| d.close).collect::<Vec<_>>(), vec![100.0, 51.0]);
+    }
+}
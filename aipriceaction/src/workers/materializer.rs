@@ -0,0 +1,89 @@
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use sqlx::PgPool;
+use tokio::time::{sleep, Duration};
+
+use crate::constants::materialize as cfg;
+use crate::models::aggregated_interval::AggregatedInterval;
+use crate::queries::ohlcv;
+use crate::services::aggregator::Aggregator;
+use crate::services::materializer;
+
+/// Materialised interval worker.
+///
+/// `enhance_and_save` refreshes materialised candles as base bars are written;
+/// this worker covers everything else. Every `LOOP_SECS`, for each ticker and
+/// each interval in `MATERIALIZED_INTERVALS`, it rolls the materialised series
+/// forward from its latest candle (bars written by bootstrap/import paths) and
+/// backfills it newest-first down to the earliest base bar.
+pub async fn run(pool: PgPool, _redis_client: Option<crate::redis::RedisClient>) {
+    let targets = cfg::intervals();
+    tracing::info!(
+        intervals = ?targets.iter().map(|t| t.to_str()).collect::<Vec<_>>(),
+        "Materialize worker started, waiting {}s before first round",
+        cfg::INITIAL_DELAY_SECS
+    );
+    sleep(Duration::from_secs(cfg::INITIAL_DELAY_SECS)).await;
+
+    loop {
+        let tickers = match ohlcv::list_all_tickers(&pool).await {
+            Ok(t) => t,
+            Err(e) => {
+                tracing::warn!("Materialize worker: failed to list tickers: {e}");
+                sleep(Duration::from_secs(cfg::LOOP_SECS)).await;
+                continue;
+            }
+        };
+
+        let started = std::time::Instant::now();
+        let mut written = 0usize;
+        for ticker in &tickers {
+            for &target in &targets {
+                match materialize_ticker(&pool, ticker.id, &ticker.ticker, target).await {
+                    Ok(n) => written += n,
+                    Err(e) => tracing::warn!(
+                        ticker = ticker.ticker,
+                        interval = target.to_str(),
+                        "Materialize worker: {e}"
+                    ),
+                }
+            }
+        }
+
+        tracing::info!(
+            tickers = tickers.len(),
+            written,
+            elapsed_secs = started.elapsed().as_secs(),
+            "Materialize round complete"
+        );
+        sleep(Duration::from_secs(cfg::LOOP_SECS)).await;
+    }
+}
+
+/// Bring one ticker's `target` series up to date. Returns candles written.
+async fn materialize_ticker(
+    pool: &PgPool,
+    ticker_id: i32,
+    ticker: &str,
+    target: AggregatedInterval,
+) -> sqlx::Result<usize> {
+    let base = target.base_interval().as_str();
+    let Some(base_earliest) = ohlcv::get_earliest_time(pool, ticker_id, base).await? else {
+        return Ok(0);
+    };
+    let now = Utc::now();
+
+    // Forward: re-aggregate from the newest materialised candle (it may have
+    // been partial) or, on first run, from the current bucket.
+    let latest = ohlcv::get_latest_time(pool, ticker_id, target.to_str()).await?;
+    let from = latest.unwrap_or(now);
+    let mut written =
+        materializer::refresh_range(pool, ticker_id, ticker, target, from, now + ChronoDuration::days(1)).await?;
+
+    // Backward: continue history below the oldest materialised candle.
+    let earliest = ohlcv::get_earliest_time(pool, ticker_id, target.to_str()).await?;
+    let until: DateTime<Utc> = earliest.unwrap_or_else(|| Aggregator::bucket_start(target, from, 0));
+    if until > Aggregator::bucket_start(target, base_earliest, 0) {
+        written += materializer::backfill(pool, ticker_id, ticker, target, base_earliest, until).await?;
+    }
+    Ok(written)
+}
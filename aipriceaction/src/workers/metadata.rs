@@ -0,0 +1,16 @@
+use sqlx::PgPool;
+use std::time::Duration;
+
+use crate::constants::metadata::RELOAD_SECS;
+use crate::services::metadata;
+
+/// Reload the ticker metadata catalog periodically, so edits made through
+/// another replica's /admin/tickers reach this process too.
+pub async fn run(pool: PgPool) {
+    loop {
+        tokio::time::sleep(Duration::from_secs(RELOAD_SECS)).await;
+        if let Err(e) = metadata::reload(&pool).await {
+            tracing::warn!("metadata worker: failed to reload ticker metadata: {e}");
+        }
+    }
+}
@@ -1,21 +1,20 @@
-pub mod vci_daily;
-pub mod vci_hourly;
-pub mod vci_minute;
 pub mod vci_dividend;
 pub mod vci_shared;
-pub mod binance_daily;
-pub mod binance_hourly;
-pub mod binance_minute;
 pub mod binance_bootstrap;
 pub mod binance_shared;
+pub mod binance_stream;
 pub mod yahoo_bootstrap;
-pub mod yahoo_daily;
-pub mod yahoo_hourly;
-pub mod yahoo_minute;
 pub mod yahoo_shared;
 pub mod sjc_bootstrap;
 pub mod sjc_daily;
 pub mod sjc_shared;
+pub mod alert_worker;
+pub mod custom_index;
+pub mod gap_scanner;
 pub mod health;
+pub mod interval_sync;
+pub mod materializer;
+pub mod metadata;
+pub mod reconciler;
 pub mod redis_worker;
 pub mod s3_archive;
@@ -0,0 +1,235 @@
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use tokio::time::{sleep, Duration};
+
+use crate::constants::{reconcile_worker as cfg, vci_worker};
+use crate::models::interval::Interval;
+use crate::models::lifecycle::LifecycleState;
+use crate::providers::market_data::{HistoryRequest, MarketDataProvider};
+use crate::providers::ohlcv::OhlcvData;
+use crate::providers::udf::UdfProvider;
+use crate::providers::vci::VciProvider;
+use crate::queries::ohlcv;
+use crate::queries::reconcile::{self, Discrepancy};
+use crate::services::metadata;
+
+/// Cross-provider reconciliation worker for VN prices.
+///
+/// Every `LOOP_SECS`, samples `SAMPLE_SIZE` ready VN tickers, fetches their
+/// recent 1D and 1h bars from VCI and each UDF broker in `VCI_FALLBACK_SOURCES`,
+/// and stores every completed bar whose OHLC or volume differs from the
+/// reference source (VCI when it answers, otherwise the first broker that did)
+/// beyond tolerance in `ohlcv_discrepancies`. Read-only with respect to `ohlcv`.
+pub async fn run(pool: PgPool, _redis_client: Option<crate::redis::RedisClient>) {
+    let vci = match VciProvider::new(cfg::RPM) {
+        Ok(p) => p,
+        Err(e) => {
+            tracing::error!("Reconcile worker: failed to create VCI provider: {e}");
+            return;
+        }
+    };
+    let brokers: Vec<UdfProvider> = vci_worker::fallback_sources()
+        .iter()
+        .filter_map(|name| UdfProvider::new(name, cfg::RPM).ok())
+        .collect();
+
+    if brokers.is_empty() {
+        tracing::warn!("Reconcile worker: no UDF brokers configured (VCI_FALLBACK_SOURCES), nothing to compare against");
+        return;
+    }
+
+    tracing::info!(
+        brokers = ?brokers.iter().map(|b| b.name()).collect::<Vec<_>>(),
+        "Reconcile worker started, waiting {}s before first round",
+        cfg::INITIAL_DELAY_SECS
+    );
+    sleep(Duration::from_secs(cfg::INITIAL_DELAY_SECS)).await;
+
+    loop {
+        let tickers = match ohlcv::list_tickers(&pool, "vn").await {
+            Ok(t) => t,
+            Err(e) => {
+                tracing::warn!("Reconcile worker: failed to list tickers: {e}");
+                sleep(Duration::from_secs(cfg::LOOP_SECS)).await;
+                continue;
+            }
+        };
+
+        let catalog = metadata::catalog();
+        let today = Utc::now().date_naive();
+        let mut sample: Vec<_> = tickers
+            .into_iter()
+            .filter(|t| t.status.as_deref() == Some("ready"))
+            .filter(|t| catalog.state("vn", &t.ticker, today) == LifecycleState::Active)
+            .collect();
+        {
+            use rand::seq::SliceRandom;
+            sample.shuffle(&mut rand::thread_rng());
+        }
+        sample.truncate(cfg::SAMPLE_SIZE);
+
+        let mut flagged = 0u64;
+        for ticker in &sample {
+            for interval in [Interval::Daily, Interval::Hourly] {
+                flagged += reconcile_ticker(&pool, &vci, &brokers, ticker.id, &ticker.ticker, interval).await;
+            }
+        }
+
+        tracing::info!(tickers = sample.len(), flagged, "Reconcile round complete");
+        sleep(Duration::from_secs(cfg::LOOP_SECS)).await;
+    }
+}
+
+/// Compare one ticker + interval across all providers. Returns the number of
+/// discrepancy rows written.
+async fn reconcile_ticker(
+    pool: &PgPool,
+    vci: &VciProvider,
+    brokers: &[UdfProvider],
+    ticker_id: i32,
+    ticker: &str,
+    interval: Interval,
+) -> u64 {
+    let count = match interval {
+        Interval::Hourly => cfg::HOURLY_COUNTBACK,
+        _ => cfg::DAILY_COUNTBACK,
+    };
+    let request = HistoryRequest::count_back(count);
+    let now = Utc::now();
+
+    let mut fetched: Vec<(&'static str, Vec<OhlcvData>)> = Vec::with_capacity(brokers.len() + 1);
+    match vci.history(ticker, interval, request).await {
+        Ok(data) => fetched.push((vci.name(), completed_bars(data, interval, now))),
+        Err(e) => tracing::debug!(ticker, "reconcile: vci fetch failed: {e}"),
+    }
+    for broker in brokers {
+        match broker.history(ticker, interval, request).await {
+            Ok(data) => fetched.push((broker.name(), completed_bars(data, interval, now))),
+            Err(e) => tracing::debug!(ticker, broker = broker.name(), "reconcile: fetch failed: {e}"),
+        }
+    }
+    fetched.retain(|(_, data)| !data.is_empty());
+
+    let Some(((reference_source, reference), others)) = fetched.split_first() else {
+        return 0;
+    };
+    if others.is_empty() {
+        tracing::debug!(ticker, interval = interval.as_str(), "reconcile: fewer than two sources answered");
+        return 0;
+    }
+
+    let mut written = 0;
+    for (other_source, other) in others {
+        let items = compare_bars(reference, other, cfg::PRICE_TOLERANCE_PCT, cfg::VOLUME_TOLERANCE_PCT);
+        if items.is_empty() {
+            continue;
+        }
+        tracing::warn!(
+            ticker,
+            interval = interval.as_str(),
+            reference = reference_source,
+            other = other_source,
+            count = items.len(),
+            "reconcile: providers disagree"
+        );
+        match reconcile::save_discrepancies(pool, ticker_id, interval.as_str(), reference_source, other_source, &items).await {
+            Ok(n) => written += n,
+            Err(e) => tracing::warn!(ticker, "reconcile: failed to save discrepancies: {e}"),
+        }
+    }
+    written
+}
+
+/// Drop the still-forming bar — live bars legitimately differ between feeds.
+fn completed_bars(mut data: Vec<OhlcvData>, interval: Interval, now: DateTime<Utc>) -> Vec<OhlcvData> {
+    let bar = match interval {
+        Interval::Daily => chrono::Duration::days(1),
+        Interval::Hourly => chrono::Duration::hours(1),
+        Interval::Minute => chrono::Duration::minutes(1),
+    };
+    data.retain(|d| d.time + bar <= now);
+    data
+}
+
+/// Relative difference in percent, against the larger magnitude.
+fn diff_pct(a: f64, b: f64) -> f64 {
+    let scale = a.abs().max(b.abs());
+    if scale == 0.0 { 0.0 } else { (a - b).abs() / scale * 100.0 }
+}
+
+/// Flag OHLC fields beyond `price_tol_pct` and volume beyond `volume_tol_pct`
+/// on bars present in both series (matched by timestamp).
+fn compare_bars(reference: &[OhlcvData], other: &[OhlcvData], price_tol_pct: f64, volume_tol_pct: f64) -> Vec<Discrepancy> {
+    let by_time: HashMap<DateTime<Utc>, &OhlcvData> = other.iter().map(|d| (d.time, d)).collect();
+    let mut out = Vec::new();
+
+    for r in reference {
+        let Some(o) = by_time.get(&r.time) else { continue };
+        let fields = [
+            ("open", r.open, o.open, price_tol_pct),
+            ("high", r.high, o.high, price_tol_pct),
+            ("low", r.low, o.low, price_tol_pct),
+            ("close", r.close, o.close, price_tol_pct),
+            ("volume", r.volume as f64, o.volume as f64, volume_tol_pct),
+        ];
+        for (field, reference_value, other_value, tolerance) in fields {
+            let diff = diff_pct(reference_value, other_value);
+            if diff > tolerance {
+                out.push(Discrepancy { time: r.time, field, reference_value, other_value, diff_pct: diff });
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn bar(day: u32, close: f64, volume: u64) -> OhlcvData {
+        OhlcvData {
+            time: Utc.with_ymd_and_hms(2026, 10, day, 0, 0, 0).unwrap(),
+            open: 100.0,
+            high: 110.0,
+            low: 90.0,
+            close,
+            volume,
+            symbol: None,
+        }
+    }
+
+    #[test]
+    fn test_compare_bars_within_tolerance() {
+        let a = vec![bar(1, 100.0, 1000), bar(2, 101.0, 2000)];
+        let b = vec![bar(1, 100.2, 1050), bar(2, 101.0, 2000)];
+        assert!(compare_bars(&a, &b, 0.5, 10.0).is_empty());
+    }
+
+    #[test]
+    fn test_compare_bars_flags_price_and_volume() {
+        let a = vec![bar(1, 100.0, 1000)];
+        let b = vec![bar(1, 102.0, 5000)];
+        let out = compare_bars(&a, &b, 0.5, 10.0);
+        let fields: Vec<_> = out.iter().map(|d| d.field).collect();
+        assert_eq!(fields, vec!["close", "volume"]);
+        assert!((out[0].diff_pct - 2.0 / 102.0 * 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_compare_bars_ignores_unmatched_times() {
+        let a = vec![bar(1, 100.0, 1000)];
+        let b = vec![bar(2, 200.0, 9000)];
+        assert!(compare_bars(&a, &b, 0.5, 10.0).is_empty());
+    }
+
+    #[test]
+    fn test_completed_bars_drops_forming_bar() {
+        let data = vec![bar(1, 100.0, 1), bar(2, 100.0, 1)];
+        let now = Utc.with_ymd_and_hms(2026, 10, 2, 12, 0, 0).unwrap();
+        let kept = completed_bars(data, Interval::Daily, now);
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].time, Utc.with_ymd_and_hms(2026, 10, 1, 0, 0, 0).unwrap());
+    }
+}
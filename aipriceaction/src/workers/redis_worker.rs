@@ -3,6 +3,7 @@ use sqlx::PgPool;
 
 use crate::constants::redis_ts as c;
 
+use crate::models::indicators::DEFAULT_MA_PERIODS;
 use crate::models::ohlcv::OhlcvRow;
 use crate::redis::RedisClient;
 
@@ -78,7 +79,7 @@ pub fn parse_member(member: &str, interval: &str) -> Option<(OhlcvRow, Option<i6
 
 // ---------------------------------------------------------------------------
 // Snapshot cache: pre-computed limit=N responses stored in Redis HASHes.
-// Key: `snap:{source}:{ticker}:{interval}`, Field: `{limit}:{ma_type}`
+// Key: `snap:{source}:{ticker}:{interval}`, Field: `{limit}:{ma_type}[:{ma_periods}]`
 // ---------------------------------------------------------------------------
 
 /// Build a Redis HASH key for a snapshot cache entry.
@@ -86,10 +87,15 @@ pub fn snap_key(source: &str, ticker: &str, interval: &str) -> String {
     format!("{}:{source}:{ticker}:{interval}", c::snapshot::KEY_PREFIX)
 }
 
-/// Build a HASH field name for a specific limit and MA type.
-/// Example: `snap_field(1, "sma")` returns `"1:sma"`.
-pub fn snap_field(limit: i64, ma_type: &str) -> String {
-    format!("{limit}:{ma_type}")
+/// Build a HASH field name for a specific limit, MA type and MA period set.
+/// Example: `snap_field(1, "sma", &DEFAULT_MA_PERIODS)` returns `"1:sma"`;
+/// custom `ma_periods=` sets are appended, e.g. `"1:sma:5,13,34"`.
+pub fn snap_field(limit: i64, ma_type: &str, ma_periods: &[usize]) -> String {
+    if ma_periods == DEFAULT_MA_PERIODS {
+        return format!("{limit}:{ma_type}");
+    }
+    let periods: Vec<String> = ma_periods.iter().map(|p| p.to_string()).collect();
+    format!("{limit}:{ma_type}:{}", periods.join(","))
 }
 
 /// Batch-read snapshot fields for multiple tickers via pipelined HMGET.
@@ -102,12 +108,13 @@ pub async fn batch_read_snapshots(
     interval: &str,
     limit: i64,
     ma_type: &str,
+    ma_periods: &[usize],
 ) -> Option<Vec<Option<String>>> {
     if tickers.is_empty() {
         return Some(Vec::new());
     }
 
-    let field = snap_field(limit, ma_type);
+    let field = snap_field(limit, ma_type, ma_periods);
     let pipe = client.pipeline();
 
     for ticker in tickers {
@@ -152,54 +159,43 @@ pub async fn batch_read_snapshots(
     Some(out)
 }
 
-/// Batch-write snapshot fields for multiple tickers via pipelined HSET + EXPIRE.
-/// Writes both the `StockDataResponse` field and the `OhlcvJoined` field (if provided).
+/// Batch-write snapshot fields for every ticker of `responses` via pipelined HSET + EXPIRE.
+/// Writes the `StockDataResponse` field; analysis endpoints write their
+/// `OhlcvJoined` field with [`batch_write_joined_snapshots`].
 /// Fire-and-forget — errors are logged but not propagated.
 pub async fn batch_write_snapshots(
     client: &RedisClient,
     source: &str,
-    tickers: &[String],
     interval: &str,
     limit: i64,
     ma_type: &str,
+    ma_periods: &[usize],
     responses: &std::collections::BTreeMap<String, Vec<crate::server::types::StockDataResponse>>,
-    joined: Option<&std::collections::BTreeMap<String, Vec<crate::models::ohlcv::OhlcvJoined>>>,
 ) {
     if responses.is_empty() {
         return;
     }
 
-    let field = snap_field(limit, ma_type);
-    let joined_field = format!("{field}:joined");
+    let field = snap_field(limit, ma_type, ma_periods);
     let ttl = c::snapshot::TTL_SECS as i64;
     let pipe = client.pipeline();
 
     for (ticker, bars) in responses {
-        if tickers.iter().any(|t| t == ticker) {
-            match serde_json::to_string(bars) {
-                Ok(json) => {
-                    let key = snap_key(source, ticker, interval);
-                    let mut values = std::collections::HashMap::new();
-                    values.insert(field.clone(), json);
-                    // Also write the OhlcvJoined field for analysis endpoints
-                    if let Some(joined_map) = joined {
-                        if let Some(joined_bars) = joined_map.get(ticker) {
-                            if let Ok(joined_json) = serde_json::to_string(joined_bars) {
-                                values.insert(joined_field.clone(), joined_json);
-                            }
-                        }
-                    }
-                    if let Err(e) = pipe.hset::<(), _, _>(&key, values).await {
-                        tracing::warn!(%key, "pipeline hset enqueue error: {e}");
-                    }
-                    if let Err(e) = pipe.expire::<(), _>(&key, ttl, None).await {
-                        tracing::warn!(%key, "pipeline expire enqueue error: {e}");
-                    }
+        match serde_json::to_string(bars) {
+            Ok(json) => {
+                let key = snap_key(source, ticker, interval);
+                let mut values = std::collections::HashMap::new();
+                values.insert(field.clone(), json);
+                if let Err(e) = pipe.hset::<(), _, _>(&key, values).await {
+                    tracing::warn!(%key, "pipeline hset enqueue error: {e}");
                 }
-                Err(e) => {
-                    tracing::warn!(ticker, "snapshot serialize error: {e}");
+                if let Err(e) = pipe.expire::<(), _>(&key, ttl, None).await {
+                    tracing::warn!(%key, "pipeline expire enqueue error: {e}");
                 }
             }
+            Err(e) => {
+                tracing::warn!(ticker, "snapshot serialize error: {e}");
+            }
         }
     }
 
@@ -221,12 +217,13 @@ pub async fn batch_read_joined_snapshots(
     interval: &str,
     limit: i64,
     ma_type: &str,
+    ma_periods: &[usize],
 ) -> Option<std::collections::HashMap<String, Vec<crate::models::ohlcv::OhlcvJoined>>> {
     if tickers.is_empty() {
         return Some(std::collections::HashMap::new());
     }
 
-    let field = format!("{}:joined", snap_field(limit, ma_type));
+    let field = format!("{}:joined", snap_field(limit, ma_type, ma_periods));
     let pipe = client.pipeline();
 
     for ticker in tickers {
@@ -279,17 +276,17 @@ pub async fn batch_read_joined_snapshots(
 pub async fn batch_write_joined_snapshots(
     client: &RedisClient,
     source: &str,
-    _tickers: &[String],
     interval: &str,
     limit: i64,
     ma_type: &str,
+    ma_periods: &[usize],
     joined: &std::collections::HashMap<String, Vec<crate::models::ohlcv::OhlcvJoined>>,
 ) {
     if joined.is_empty() {
         return;
     }
 
-    let field = format!("{}:joined", snap_field(limit, ma_type));
+    let field = format!("{}:joined", snap_field(limit, ma_type, ma_periods));
     let ttl = c::snapshot::TTL_SECS as i64;
     let pipe = client.pipeline();
 
@@ -503,6 +500,24 @@ pub async fn write_ohlcv_to_redis(
             tracing::warn!(key, elapsed_ms = start.elapsed().as_millis(), "zremrangebyrank timed out");
         }
     }
+
+    publish_update(client, source, ticker, interval).await;
+}
+
+/// Announce a ZSET write on `OHLCV_UPDATES_CHANNEL` so `/ws` subscribers on
+/// every API replica receive the new last candle.
+async fn publish_update(client: &RedisClient, source: &str, ticker: &str, interval: &str) {
+    let payload = serde_json::json!({ "source": source, "ticker": ticker, "interval": interval }).to_string();
+    match tokio::time::timeout(
+        std::time::Duration::from_secs(c::op_timeout_secs()),
+        client.publish::<i64, _, _>(c::OHLCV_UPDATES_CHANNEL, payload),
+    )
+    .await
+    {
+        Ok(Ok(_)) => {}
+        Ok(Err(e)) => tracing::debug!(source, ticker, interval, "publish update failed: {e}"),
+        Err(_) => tracing::debug!(source, ticker, interval, "publish update timed out"),
+    }
 }
 
 /// Backfill worker: populates Redis ZSETs from PostgreSQL.
@@ -814,7 +829,8 @@ mod tests {
     fn test_snap_key_and_field() {
         assert_eq!(snap_key("vn", "VCB", "1D"), "snap:vn:VCB:1D");
         assert_eq!(snap_key("crypto", "BTCUSDT", "1h"), "snap:crypto:BTCUSDT:1h");
-        assert_eq!(snap_field(1, "sma"), "1:sma");
-        assert_eq!(snap_field(40, "ema"), "40:ema");
+        assert_eq!(snap_field(1, "sma", &DEFAULT_MA_PERIODS), "1:sma");
+        assert_eq!(snap_field(40, "ema", &DEFAULT_MA_PERIODS), "40:ema");
+        assert_eq!(snap_field(1, "sma", &[5, 13, 34]), "1:sma:5,13,34");
     }
 }
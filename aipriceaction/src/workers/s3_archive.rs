@@ -2,7 +2,7 @@ use chrono::{Datelike, Duration, NaiveDate, Utc};
 use futures::StreamExt;
 use http::HeaderMap;
 use sqlx::PgPool;
-use std::collections::{BTreeMap, HashMap, HashSet};
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use std::time::Duration as StdDuration;
 
@@ -18,6 +18,7 @@ use crate::constants::s3_archive::{
 use crate::constants::vci_worker::INDEX_TICKERS;
 use crate::providers::vci::VciProvider;
 use crate::queries::ohlcv::Ticker;
+use crate::services::metadata::{self, Catalog};
 use crate::queries::s3_archive::{
     day_range, get_data_ranges, get_ohlcv_day_fingerprint, get_ohlcv_for_day,
     get_ohlcv_for_year, get_ohlcv_year_fingerprint, year_range, ArchiveTicker,
@@ -29,151 +30,23 @@ enum ScanResult {
     YearlyScan { uploaded: u64, skipped: u64 },
 }
 
-// ── Enrichment data loaded once at startup ──
-
-struct EnrichmentData {
-    vn_names: HashMap<String, String>,          // ticker -> organ_name (from vn.csv)
-    vn_exchange: HashMap<String, String>,       // ticker -> HOSE/HNX/UPCOM
-    vn_type: HashMap<String, String>,           // ticker -> stock/index
-    crypto_names: HashMap<String, String>,      // symbol -> name
-    yahoo_names: HashMap<String, String>,       // symbol -> name
-    yahoo_categories: HashMap<String, String>,  // symbol -> category
-    sjc_names: HashMap<String, String>,         // symbol -> name
-    sjc_categories: HashMap<String, String>,    // symbol -> category
-    ticker_groups: BTreeMap<String, Vec<String>>, // group_name -> [tickers]
-    ticker_to_group: HashMap<String, String>,   // ticker -> group_name (reverse lookup)
-}
-
-impl EnrichmentData {
-    fn load() -> Self {
-        let vn_names = load_vn_csv_field(1);
-        let vn_exchange = load_vn_csv_field(3);
-        let vn_type = load_vn_csv_field(4);
-        let (crypto_names, _) = load_ticker_json("binance_tickers.json", "CRYPTO_TOP_100");
-        let (yahoo_names, yahoo_categories) = load_ticker_json("global_tickers.json", "");
-        let (sjc_names, sjc_categories) = load_ticker_json("sjc_tickers.json", "");
-
-        let ticker_groups = load_ticker_groups();
-        let mut ticker_to_group = HashMap::new();
-        for (group, tickers) in &ticker_groups {
-            for t in tickers {
-                ticker_to_group.insert(t.clone(), group.clone());
-            }
-        }
-
-        Self {
-            vn_names,
-            vn_exchange,
-            vn_type,
-            crypto_names,
-            yahoo_names,
-            yahoo_categories,
-            sjc_names,
-            sjc_categories,
-            ticker_groups,
-            ticker_to_group,
-        }
-    }
-
-    fn enrich(&self, base: &mut ArchiveTicker) {
-        match base.source.as_str() {
-            "vn" => {
-                if base.name.is_none() {
-                    base.name = self.vn_names.get(&base.ticker).cloned();
-                }
-                base.exchange = self.vn_exchange.get(&base.ticker).cloned();
-                base.ticker_type = self.vn_type.get(&base.ticker).cloned();
-                base.group = self.ticker_to_group.get(&base.ticker).cloned();
-            }
-            "crypto" => {
-                if base.name.is_none() {
-                    base.name = self.crypto_names.get(&base.ticker).cloned();
-                }
-                base.group = Some("CRYPTO_TOP_100".into());
-            }
-            "yahoo" => {
-                if base.name.is_none() {
-                    base.name = self.yahoo_names.get(&base.ticker).cloned();
-                }
-                base.category = self.yahoo_categories.get(&base.ticker).cloned();
-                base.group = self.ticker_to_group.get(&base.ticker)
-                    .cloned()
-                    .or_else(|| self.yahoo_categories.get(&base.ticker).cloned());
-            }
-            "sjc" => {
-                if base.name.is_none() {
-                    base.name = self.sjc_names.get(&base.ticker).cloned();
-                }
-                base.category = self.sjc_categories.get(&base.ticker).cloned();
-                base.group = self.sjc_categories.get(&base.ticker).cloned();
-            }
-            _ => {}
-        }
-    }
-}
-
+// ── Enrichment from the ticker metadata ──
 
-/// Load a specific field index from vn.csv.
-fn load_vn_csv_field(field_index: usize) -> HashMap<String, String> {
-    let mut map = HashMap::new();
-    let Ok(content) = std::fs::read_to_string("vn.csv") else {
-        tracing::warn!("s3_archive: vn.csv not found, skipping VN enrichment");
-        return map;
+/// Fill name, exchange, type and group of a DB ticker from the metadata.
+/// Yahoo and SJC groups are also reported as `category`.
+fn enrich(base: &mut ArchiveTicker, catalog: &Catalog) {
+    let Some(meta) = catalog.get(&base.source, &base.ticker) else {
+        return;
     };
-    let mut rdr = csv::ReaderBuilder::new().from_reader(content.as_bytes());
-    for result in rdr.records() {
-        if let Ok(record) = result {
-            let symbol = record.get(0).unwrap_or("").trim().to_uppercase();
-            if symbol.is_empty() {
-                continue;
-            }
-            if let Some(val) = record.get(field_index) {
-                let val = val.trim();
-                if !val.is_empty() {
-                    map.insert(symbol, val.to_string());
-                }
-            }
-        }
+    if base.name.is_none() {
+        base.name = meta.name.clone();
     }
-    map
-}
-
-/// Load ticker names from a JSON file with a `data[]` array of `{symbol, name, category?}`.
-fn load_ticker_json(path: &str, _default_group: &str) -> (HashMap<String, String>, HashMap<String, String>) {
-    let mut names = HashMap::new();
-    let mut categories = HashMap::new();
-    let Ok(content) = std::fs::read_to_string(path) else {
-        tracing::warn!("s3_archive: {path} not found, skipping");
-        return (names, categories);
-    };
-    let Ok(raw) = serde_json::from_str::<serde_json::Value>(&content) else {
-        tracing::warn!("s3_archive: failed to parse {path}");
-        return (names, categories);
-    };
-    if let Some(items) = raw["data"].as_array() {
-        for item in items {
-            let symbol = item["symbol"].as_str().unwrap_or("").to_string();
-            if symbol.is_empty() {
-                continue;
-            }
-            if let Some(name) = item["name"].as_str() {
-                names.insert(symbol.clone(), name.to_string());
-            }
-            if let Some(cat) = item["category"].as_str() {
-                categories.insert(symbol.clone(), cat.to_string());
-            }
-        }
+    base.exchange = meta.exchange.clone();
+    base.ticker_type = meta.asset_type.clone();
+    if matches!(base.source.as_str(), "yahoo" | "sjc") {
+        base.category = meta.sector.clone();
     }
-    (names, categories)
-}
-
-/// Load ticker_group.json as a BTreeMap<String, Vec<String>>.
-fn load_ticker_groups() -> BTreeMap<String, Vec<String>> {
-    let Ok(content) = std::fs::read_to_string("ticker_group.json") else {
-        tracing::warn!("s3_archive: ticker_group.json not found, skipping group enrichment");
-        return BTreeMap::new();
-    };
-    serde_json::from_str(&content).unwrap_or_default()
+    base.group = meta.sector.clone();
 }
 
 // ── S3 helpers ──
@@ -1140,7 +1013,6 @@ pub async fn run(pool: PgPool, _redis: Option<crate::redis::RedisClient>) {
         }
     };
 
-    let enrichment = EnrichmentData::load();
     let interval_secs = std::env::var("S3_ARCHIVE_INTERVAL_SECS")
         .ok()
         .and_then(|v| v.parse::<u64>().ok())
@@ -1180,7 +1052,7 @@ pub async fn run(pool: PgPool, _redis: Option<crate::redis::RedisClient>) {
     // ── Startup scan: full historical check ──
     let mut last_startup_scan = std::time::Instant::now();
     tracing::info!("s3_archive: starting full historical scan...");
-    if let Err(e) = startup_scan(&pool, &bucket, &enrichment).await {
+    if let Err(e) = startup_scan(&pool, &bucket).await {
         tracing::error!("s3_archive: startup scan failed: {e}");
     }
 
@@ -1189,14 +1061,14 @@ pub async fn run(pool: PgPool, _redis: Option<crate::redis::RedisClient>) {
         // Re-run startup scan periodically to catch new tickers + full history
         if last_startup_scan.elapsed() >= StdDuration::from_secs(STARTUP_SCAN_INTERVAL_SECS) {
             tracing::info!("s3_archive: re-running full historical scan (catch new tickers)...");
-            if let Err(e) = startup_scan(&pool, &bucket, &enrichment).await {
+            if let Err(e) = startup_scan(&pool, &bucket).await {
                 tracing::error!("s3_archive: startup scan failed: {e}");
             }
             last_startup_scan = std::time::Instant::now();
         }
 
         tracing::info!("s3_archive: incremental cycle starting...");
-        if let Err(e) = incremental_cycle(&pool, &bucket, &enrichment).await {
+        if let Err(e) = incremental_cycle(&pool, &bucket).await {
             tracing::error!("s3_archive: incremental cycle failed: {e}");
         }
 
@@ -1216,11 +1088,11 @@ pub async fn run(pool: PgPool, _redis: Option<crate::redis::RedisClient>) {
 async fn upload_tickers_json(
     pool: &PgPool,
     bucket: &Bucket,
-    enrichment: &EnrichmentData,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     let mut tickers = crate::queries::s3_archive::get_all_tickers_base(pool).await?;
+    let catalog = metadata::catalog();
     for t in &mut tickers {
-        enrichment.enrich(t);
+        enrich(t, &catalog);
     }
 
     let json_bytes = serde_json::to_vec_pretty(&tickers)?;
@@ -1256,10 +1128,9 @@ async fn upload_tickers_json(
 async fn startup_scan(
     pool: &PgPool,
     bucket: &Bucket,
-    enrichment: &EnrichmentData,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     // Upload tickers.json first
-    upload_tickers_json(pool, bucket, enrichment).await?;
+    upload_tickers_json(pool, bucket).await?;
 
     // Get all tickers for source mapping
     let all_tickers = sqlx::query_as::<_, Ticker>(
@@ -1518,10 +1389,9 @@ async fn startup_scan(
 async fn incremental_cycle(
     pool: &PgPool,
     bucket: &Bucket,
-    enrichment: &EnrichmentData,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     // Upload tickers.json
-    upload_tickers_json(pool, bucket, enrichment).await?;
+    upload_tickers_json(pool, bucket).await?;
 
     // Get all tickers
     let all_tickers = sqlx::query_as::<_, Ticker>(
@@ -1897,8 +1767,7 @@ pub async fn test_s3(ticker: String, interval: String, days: u32, create_bucket:
 
     // 6. Upload tickers.json
     tracing::info!("{}", "─".repeat(60));
-    let enrichment = EnrichmentData::load();
-    match upload_tickers_json(&pool, &bucket, &enrichment).await {
+    match upload_tickers_json(&pool, &bucket).await {
         Ok(()) => tracing::info!("tickers.json: uploaded"),
         Err(e) => tracing::error!("tickers.json: failed — {e}"),
     }
@@ -136,6 +136,10 @@ pub async fn import_csv_to_ohlcv(
         prev_close = Some(close);
     }
 
+    // Bars rejected by a validation rule go to ohlcv_quarantine instead.
+    let candles =
+        crate::services::validation::validate_rows(pool, sjc_worker::SOURCE, sjc_worker::TICKER, candles).await;
+
     // Batch upsert
     let batch_size = sjc_worker::IMPORT_BATCH_SIZE;
     let mut total_imported = 0usize;
@@ -190,6 +194,11 @@ pub async fn upsert_live_price(pool: &PgPool, ticker_id: i32, buy: f64, sell: f6
         volume: 1,
     };
 
+    let accepted =
+        crate::services::validation::validate_batch(pool, sjc_worker::SOURCE, sjc_worker::TICKER, "1D", vec![row]).await;
+    let Some(row) = accepted.into_iter().next() else {
+        return Ok(());
+    };
     import::bulk_upsert_ohlcv_preserve_open(pool, &[row.clone()]).await?;
 
     // Fire-and-forget Redis ZSET write + snapshot invalidation
@@ -5,7 +5,7 @@ use tokio::time::{sleep, Duration};
 use crate::constants::vci_worker;
 use crate::providers::vci::VciProvider;
 use crate::queries::ohlcv;
-use crate::workers::vci_shared;
+use crate::workers::{interval_sync, vci_shared};
 
 pub async fn run(pool: PgPool, redis_client: Option<crate::redis::RedisClient>) {
     tracing::info!("VCI dividend worker started");
@@ -116,11 +116,17 @@ pub async fn run(pool: PgPool, redis_client: Option<crate::redis::RedisClient>)
                     tracing::info!(ticker, interval, count_back = chunk_size, to_ts, %to_date, total = total_saved, "dividend fetch");
 
                     match provider.get_history(ticker, api_interval, chunk_size, Some(to_ts)).await {
-                        Ok(data) => {
+                        Ok(mut data) => {
                             if data.is_empty() {
                                 tracing::info!(ticker, interval, "empty response, stopping");
                                 break;
                             }
+                            // VCI serves back-adjusted history; store raw prices like the syncs do
+                            if let Err(e) = interval_sync::restore_raw_prices(&pool, ticker_id, &mut data).await {
+                                tracing::warn!(ticker, interval, %to_date, "failed to load corporate actions, retrying in 60s: {e}");
+                                sleep(Duration::from_secs(60)).await;
+                                continue;
+                            }
                             let oldest = data.first().map(|r| r.time.format("%Y-%m-%d %H:%M").to_string()).unwrap_or_default();
                             let newest = data.last().map(|r| r.time.format("%Y-%m-%d %H:%M").to_string()).unwrap_or_default();
                             let fetched = data.len();
@@ -1,19 +1,20 @@
 use std::collections::{HashMap, HashSet};
 
-use chrono::{Datelike, DateTime, Timelike, Utc};
+use chrono::{DateTime, Timelike, Utc};
 use sqlx::PgPool;
 
 use crate::constants::vci_worker;
+use crate::models::calendar::Exchange;
+use crate::models::corporate_action;
 use crate::models::ohlcv::OhlcvRow;
 use crate::providers::ohlcv::OhlcvData;
 use crate::queries;
 
-/// Load the VN ticker list from ticker_group.json.
+/// Load the VN ticker list from the ticker metadata.
 ///
 /// Flattens all sector groups, prepends INDEX_TICKERS, deduplicates.
-pub fn load_vn_tickers() -> Result<Vec<String>, Box<dyn std::error::Error>> {
-    let content = std::fs::read_to_string("ticker_group.json")?;
-    let groups: HashMap<String, Vec<String>> = serde_json::from_str(&content)?;
+pub fn load_vn_tickers() -> Vec<String> {
+    let groups = crate::services::metadata::catalog().groups("vn");
 
     let mut seen = HashSet::new();
     let mut tickers = Vec::new();
@@ -26,7 +27,7 @@ pub fn load_vn_tickers() -> Result<Vec<String>, Box<dyn std::error::Error>> {
     }
 
     // Flatten all groups
-    for (_sector, symbols) in &groups {
+    for symbols in groups.values() {
         for sym in symbols {
             if seen.insert(sym.clone()) {
                 tickers.push(sym.clone());
@@ -34,26 +35,20 @@ pub fn load_vn_tickers() -> Result<Vec<String>, Box<dyn std::error::Error>> {
         }
     }
 
-    Ok(tickers)
+    tickers
 }
 
-/// Sync tickers from ticker_group.json into the database.
+/// Sync grouped VN tickers from the ticker metadata into the database.
 ///
-/// Any ticker present in the JSON file but missing from the DB is upserted
-/// with status 'ready'.  Returns the number of newly added tickers.
-pub async fn sync_tickers_from_json(pool: &PgPool) -> usize {
-    let json_tickers = match load_vn_tickers() {
-        Ok(t) => t,
-        Err(e) => {
-            tracing::warn!("failed to load ticker_group.json: {e}");
-            return 0;
-        }
-    };
+/// Any grouped ticker missing from the DB is upserted with status 'ready'.
+/// Returns the number of newly added tickers.
+pub async fn sync_vn_tickers(pool: &PgPool) -> usize {
+    let tickers = load_vn_tickers();
 
     let mut added = 0usize;
-    for ticker in &json_tickers {
+    for ticker in &tickers {
         if let Err(e) = queries::ohlcv::upsert_ticker(pool, "vn", ticker, None).await {
-            tracing::warn!(ticker, "failed to upsert ticker from json: {e}");
+            tracing::warn!(ticker, "failed to upsert ticker from metadata: {e}");
             continue;
         }
         // If the ticker was freshly inserted it will have no status (NULL) or a
@@ -146,10 +141,32 @@ pub async fn enhance_and_save(
         );
     }
 
+    // Bars rejected by a validation rule go to ohlcv_quarantine instead.
+    let deduped = crate::services::validation::validate_batch(pool, source, ticker, interval, deduped).await;
+    if deduped.is_empty() {
+        return true;
+    }
+
     if let Err(e) = queries::import::bulk_upsert_ohlcv(pool, &deduped).await {
         tracing::error!(ticker_id, interval, "bulk_upsert_ohlcv failed: {e}");
         return false;
-    } else if redis_client.is_some() {
+    }
+
+    // Keep materialised aggregates (MATERIALIZED_INTERVALS) in step with the
+    // base bars just written.
+    if let Some(first) = deduped.first()
+        && !crate::services::materializer::targets_for(interval).is_empty()
+    {
+        let pool = pool.clone();
+        let tk = ticker.to_string();
+        let iv = interval.to_string();
+        let since = first.time;
+        tokio::spawn(async move {
+            crate::services::materializer::refresh_from(&pool, ticker_id, &tk, &iv, since).await;
+        });
+    }
+
+    if redis_client.is_some() {
         // Fire-and-forget Redis TS write.
         // Errors are logged internally by write_ohlcv_to_redis.
         let redis = redis_client.clone();
@@ -195,7 +212,9 @@ fn normalize_time(time: DateTime<Utc>, interval: &str) -> DateTime<Utc> {
 
 /// Detect dividend by comparing new API data with existing DB data.
 ///
-/// Returns true if a dividend was detected (and status updated).
+/// A confirmed divergence is recorded in `corporate_actions`; only if that
+/// fails is the ticker flagged `dividend-detected` for a full re-download.
+/// Returns true if a dividend was detected (the caller skips this sync).
 pub async fn detect_dividend(
     pool: &PgPool,
     ticker_id: i32,
@@ -271,6 +290,22 @@ pub async fn detect_dividend(
             ticker, worst_date, worst_existing_close, worst_api_close, max_ratio, price_drop_pct,
             divergence_count, vci_worker::DIVIDEND_MIN_DIVERGING_BARS, vci_worker::DIVIDEND_RATIO_THRESHOLD, compare_data.len(), existing.len()
         );
+        let last_date = new_data[new_data.len() - 1].time.date_naive();
+        if let Some(action) = corporate_action::derive_action(
+            &existing_map, compare_data, last_date,
+            vci_worker::DIVIDEND_RATIO_THRESHOLD, vci_worker::SPLIT_RATIO_THRESHOLD, "vci",
+        ) {
+            match queries::corporate_actions::upsert_action(pool, ticker_id, &action).await {
+                Ok(()) => {
+                    tracing::warn!(
+                        "[DIVIDEND] ticker={}, action=recorded {} ex_date={} ratio={:.4} cash={:?} → raw history kept, adjustments applied at query time",
+                        ticker, action.action_type.as_str(), action.ex_date, action.ratio, action.cash_amount
+                    );
+                    return true;
+                }
+                Err(e) => tracing::error!("[DIVIDEND] ticker={}, FAILED to record corporate action, falling back to full re-download: {}", ticker, e),
+            }
+        }
         tracing::warn!(
             "[DIVIDEND] ticker={}, action=set status 'dividend-detected' → dividend worker will delete ALL data and re-download full history (1D from 2015, 1h/1m from 2023)",
             ticker
@@ -286,12 +321,8 @@ pub async fn detect_dividend(
     false
 }
 
-/// Check if current time is within VN trading hours (9:00-15:00 ICT = 2:00-8:00 UTC).
+/// Check if the VN market is in session (HOSE calendar: lunch break and
+/// holidays excluded).
 pub fn is_trading_hours() -> bool {
-    let now = chrono::Utc::now();
-    // VN trading hours: 9:00-15:00 ICT = 2:00-8:00 UTC
-    let hour = now.hour();
-    // Only on weekdays (Mon=1 .. Fri=5)
-    let weekday = now.weekday().num_days_from_monday(); // 0=Mon, 6=Sun
-    weekday < 5 && hour >= 2 && hour < 8
+    Exchange::Hose.is_open(chrono::Utc::now())
 }
@@ -5,7 +5,7 @@ use tokio::time::{sleep, Duration};
 use crate::constants::yahoo_worker;
 use crate::providers::yahoo::YahooProvider;
 use crate::queries::ohlcv;
-use crate::workers::yahoo_shared;
+use crate::workers::{interval_sync, yahoo_shared};
 
 /// Full-download worker for Yahoo Finance tickers.
 ///
@@ -144,12 +144,18 @@ pub async fn run(pool: PgPool, redis_client: Option<crate::redis::RedisClient>)
                     );
 
                     match provider.get_history_interval(yahoo_shared::yahoo_symbol(ticker), yahoo_interval, chunk_start, fetch_end).await {
-                        Ok(data) => {
+                        Ok(mut data) => {
                             if data.is_empty() {
                                 tracing::info!(ticker, interval = db_interval, "empty chunk response, skipping forward");
                                 chunk_start = chunk_end;
                                 continue;
                             }
+                            // Yahoo serves back-adjusted history; store raw prices like the syncs do
+                            if let Err(e) = interval_sync::restore_raw_prices(&pool, ticker_id, &mut data).await {
+                                tracing::warn!(ticker, interval = db_interval, "failed to load corporate actions, retrying in 60s: {e}");
+                                sleep(Duration::from_secs(60)).await;
+                                continue;
+                            }
 
                             let oldest = data.first().map(|r| r.time.format("%Y-%m-%d %H:%M").to_string()).unwrap_or_default();
                             let newest = data.last().map(|r| r.time.format("%Y-%m-%d %H:%M").to_string()).unwrap_or_default();
@@ -1,10 +1,10 @@
-use serde::Deserialize;
 use sqlx::PgPool;
 use std::collections::HashMap;
 
 use crate::constants::yahoo_worker;
 use crate::providers::ohlcv::OhlcvData;
-use crate::queries::ohlcv;
+use crate::models::corporate_action;
+use crate::queries::{corporate_actions, ohlcv};
 use crate::workers::vci_shared;
 
 /// Strip `:US` suffix for Yahoo API calls.
@@ -15,40 +15,18 @@ pub fn yahoo_symbol(db_ticker: &str) -> &str {
     db_ticker.strip_suffix(":US").unwrap_or(db_ticker)
 }
 
-// ---------------------------------------------------------------------------
-// yahoo_tickers.json schema
-// ---------------------------------------------------------------------------
-
-#[derive(Deserialize)]
-struct YahooTickerEntry {
-    symbol: String,
-}
-
-#[derive(Deserialize)]
-struct YahooTickerFile {
-    data: Vec<YahooTickerEntry>,
-}
-
-/// Load tickers from yahoo_tickers.json.
-pub fn load_yahoo_tickers() -> Result<Vec<String>, Box<dyn std::error::Error>> {
-    let content = std::fs::read_to_string("global_tickers.json")?;
-    let file: YahooTickerFile = serde_json::from_str(&content)?;
-    Ok(file.data.into_iter().map(|e| e.symbol).collect())
+/// Load the grouped Yahoo tickers from the ticker metadata.
+pub fn load_yahoo_tickers() -> Vec<String> {
+    crate::services::metadata::catalog().groups("yahoo").into_values().flatten().collect()
 }
 
-/// Sync tickers from yahoo_tickers.json into the database.
+/// Sync grouped Yahoo tickers from the ticker metadata into the database.
 ///
 /// New tickers are upserted with source='yahoo' and get status='full-download-requested'
 /// so the bootstrap worker picks them up.
 pub async fn sync_yahoo_tickers(pool: &PgPool) -> usize {
     tracing::info!("sync_yahoo_tickers: starting");
-    let tickers = match load_yahoo_tickers() {
-        Ok(t) => t,
-        Err(e) => {
-            tracing::warn!("failed to load global_tickers.json: {e}");
-            return 0;
-        }
-    };
+    let tickers = load_yahoo_tickers();
 
     let mut added = 0usize;
     for ticker in &tickers {
@@ -88,10 +66,10 @@ pub async fn ensure_yahoo_ticker(pool: &PgPool, source: &str, ticker: &str) -> s
 
 /// Re-export commonly used functions for convenience.
 pub use vci_shared::enhance_and_save;
-pub use crate::workers::binance_shared::schedule_fixed_interval;
 
 /// Detect stock splits / data corruption by comparing newly fetched daily bars
-/// against existing DB data. If prices diverge beyond a threshold, sets
+/// against existing DB data. If prices diverge beyond a threshold, records the
+/// split in `corporate_actions`; only if that fails does it set
 /// `dividend-detected` status so the bootstrap worker re-downloads full history.
 ///
 /// Follows the same logic as `vci_shared::detect_dividend` but uses Yahoo-specific
@@ -165,6 +143,22 @@ pub async fn detect_dividend(
             ticker, worst_date, worst_existing_close, worst_api_close, max_ratio, price_drop_pct,
             divergence_count, yahoo_worker::DIVIDEND_MIN_DIVERGING_BARS, yahoo_worker::DIVIDEND_RATIO_THRESHOLD, compare_data.len(), existing.len()
         );
+        let last_date = new_data[new_data.len() - 1].time.date_naive();
+        if let Some(action) = corporate_action::derive_action(
+            &existing_map, compare_data, last_date,
+            yahoo_worker::DIVIDEND_RATIO_THRESHOLD, yahoo_worker::SPLIT_RATIO_THRESHOLD, "yahoo",
+        ) {
+            match corporate_actions::upsert_action(pool, ticker_id, &action).await {
+                Ok(()) => {
+                    tracing::warn!(
+                        "[YAHOO-DIVIDEND] ticker={}, action=recorded {} ex_date={} ratio={:.4} → raw history kept, adjustments applied at query time",
+                        ticker, action.action_type.as_str(), action.ex_date, action.ratio
+                    );
+                    return true;
+                }
+                Err(e) => tracing::error!("[YAHOO-DIVIDEND] ticker={}, FAILED to record corporate action, falling back to full re-download: {}", ticker, e),
+            }
+        }
         tracing::warn!(
             "[YAHOO-DIVIDEND] ticker={}, action=set status 'dividend-detected' → bootstrap worker will re-download full history",
             ticker